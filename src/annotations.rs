@@ -0,0 +1,167 @@
+//! Virtual text on the lines of an editor tab: notes that lint, blame,
+//! search or a language server attach after a line's text or in the gutter
+//! without touching the buffer. Each subsystem owns the notes under its
+//! source name and replaces all of them whenever they change; notes don't
+//! follow edits, so a source refreshes them after the lines move.
+//! `EditorWidget` draws them in the `[annotations]` colors.
+
+use crate::config::{self, IconSet};
+use ratatui::style::{Color, Modifier, Style};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    EndOfLine,
+    Gutter, // Shown in the source's gutter column; see `gutter::Column`
+}
+
+/// How loud a note is; the loudest gutter note of a line wins
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[allow(dead_code)] // Not every level has a source yet
+pub enum Severity {
+    Hint,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn style(self) -> Style {
+        let colors = &config::get().annotations;
+        let (name, fallback) = match self {
+            Severity::Hint => (&colors.hint, Color::Rgb(95, 135, 175)),
+            Severity::Info => (&colors.info, Color::Rgb(128, 128, 128)),
+            Severity::Warning => (&colors.warning, Color::Rgb(215, 175, 95)),
+            Severity::Error => (&colors.error, Color::Rgb(224, 108, 117)),
+        };
+        Style::default().fg(name.parse().unwrap_or(fallback))
+    }
+
+    /// What the diagnostics column of the gutter shows for it
+    pub fn icon(self) -> char {
+        let ascii = crate::file_icons::icon_set() == IconSet::Ascii;
+        match (self, ascii) {
+            (Severity::Hint, false) => '·',
+            (Severity::Info, false) => '●',
+            (Severity::Warning, false) => '▲',
+            (Severity::Error, false) => '✘',
+            (Severity::Hint, true) => '.',
+            (Severity::Info, true) => 'i',
+            (Severity::Warning, true) => '!',
+            (Severity::Error, true) => 'x',
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub line: usize,
+    pub text: String,
+    pub placement: Placement,
+    pub severity: Severity,
+}
+
+/// A tab's notes, by the source that attached them
+#[derive(Debug, Clone, Default)]
+pub struct Annotations {
+    sources: BTreeMap<&'static str, Vec<Annotation>>,
+}
+
+impl Annotations {
+    /// Replace every note of a source; an empty list removes the source
+    pub fn set(&mut self, source: &'static str, annotations: Vec<Annotation>) {
+        if annotations.is_empty() {
+            self.sources.remove(source);
+        } else {
+            self.sources.insert(source, annotations);
+        }
+    }
+
+    /// Notes on a line in one placement, in source order
+    pub fn on_line(&self, line: usize, placement: Placement) -> impl Iterator<Item = &Annotation> {
+        self.sources
+            .values()
+            .flatten()
+            .filter(move |note| note.line == line && note.placement == placement)
+    }
+
+    /// Gutter notes of the sources `shown` accepts
+    fn gutter_notes(&self, shown: impl Fn(&str) -> bool) -> impl Iterator<Item = &Annotation> {
+        self.sources
+            .iter()
+            .filter(move |(source, _)| shown(source))
+            .flat_map(|(_, notes)| notes)
+            .filter(|note| note.placement == Placement::Gutter)
+    }
+
+    /// Whether any of the sources `shown` accepts has a gutter note
+    pub fn has_gutter(&self, shown: impl Fn(&str) -> bool) -> bool {
+        self.gutter_notes(shown).next().is_some()
+    }
+
+    /// The loudest gutter note on a line of the sources `shown` accepts
+    pub fn gutter_note(&self, line: usize, shown: impl Fn(&str) -> bool) -> Option<&Annotation> {
+        self.gutter_notes(shown)
+            .filter(|note| note.line == line)
+            .min_by_key(|note| Reverse(note.severity))
+    }
+
+    /// The end-of-line notes of a line as spans, set off from the text
+    pub fn after_line(&self, line: usize) -> Vec<(String, Style)> {
+        self.on_line(line, Placement::EndOfLine)
+            .map(|note| {
+                let style = note.severity.style().add_modifier(Modifier::ITALIC);
+                (format!("  {}", note.text), style)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(line: usize, text: &str, placement: Placement, severity: Severity) -> Annotation {
+        Annotation {
+            line,
+            text: text.to_string(),
+            placement,
+            severity,
+        }
+    }
+
+    #[test]
+    fn sources_replace_their_own_notes() {
+        let mut notes = Annotations::default();
+        notes.set(
+            "lint",
+            vec![note(2, "!", Placement::Gutter, Severity::Warning)],
+        );
+        notes.set(
+            "blame",
+            vec![
+                note(2, "b", Placement::Gutter, Severity::Info),
+                note(2, "ana, 2 days ago", Placement::EndOfLine, Severity::Hint),
+            ],
+        );
+        let sign = |notes: &Annotations| {
+            let note = notes.gutter_note(2, |_| true)?;
+            Some(note.text.clone())
+        };
+        assert_eq!(sign(&notes).as_deref(), Some("!"));
+        assert_eq!(
+            notes.gutter_note(2, |source| source == "blame").map(|note| note.severity),
+            Some(Severity::Info)
+        );
+        assert_eq!(notes.after_line(2).len(), 1);
+        assert_eq!(notes.after_line(2)[0].0, "  ana, 2 days ago");
+
+        notes.set("lint", Vec::new());
+        assert_eq!(sign(&notes).as_deref(), Some("b"));
+        assert!(!notes.has_gutter(|source| source == "lint"));
+        notes.set("blame", Vec::new());
+        assert!(!notes.has_gutter(|_| true));
+        assert_eq!(sign(&notes), None);
+    }
+}
@@ -1,5 +1,6 @@
 // Removed unused imports KeyEvent, MouseEvent, and Frame
 use std::path::PathBuf;
+use crossterm::cursor::SetCursorStyle;
 use std::time::{Duration, Instant};
 
 pub fn is_word_separator(ch: char) -> bool {
@@ -23,22 +24,33 @@ pub fn is_word_separator(ch: char) -> bool {
     )
 }
 
+use crate::config::{Bell, CursorShape};
+use crate::handlers::mouse::DragAutoscroll;
+use crate::instance;
+use crate::jobs::{JobId, JobKind, JobManager};
+use crate::paste::PasteOperation;
+use crate::pipe::Pipe;
+use crate::scratch::Autosave;
+use crate::scroll::ScrollAcceleration;
+use crate::share;
+use crate::sidebar::Sidebar;
 use crate::keyboard::EditorCommand;
 use crate::menu::MenuSystem;
+use crate::messages::{MessageLog, Severity};
 use crate::tab::{Tab, TabManager};
+use crate::tree_state::TreeState;
 use crate::tree_view::TreeView;
-use crate::ui::UI;
+use crate::ui::{Modal, UI};
+use crate::view::ViewOptions;
 
 pub struct App {
     pub tab_manager: TabManager,
     pub running: bool,
     pub ui: UI,
-    pub warning_message: Option<String>,
-    pub pending_close: bool,
-    pub pending_quit: bool,
-    pub warning_selected_button: usize, // 0 = No, 1 = Yes
-    pub warning_is_info: bool,          // true = OK button only, false = Yes/No buttons
+    pub modal: Option<Modal>, // Blocking dialog drawn above everything else
+    pub quit_save_queue: Vec<usize>, // Tabs still to be saved before quitting
     pub mouse_selecting: bool,
+    pub drag_autoscroll: Option<DragAutoscroll>,
     pub last_click_time: Option<Instant>,
     pub last_click_pos: Option<(u16, u16)>,
     pub terminal_size: (u16, u16), // (width, height)
@@ -46,19 +58,33 @@ pub struct App {
     pub scrollbar_dragging: bool,
     pub file_picker_scrollbar_dragging: bool,
     pub tree_view: Option<TreeView>,
-    pub sidebar_width: u16,
+    pub sidebar: Sidebar,
     pub sidebar_resizing: bool,
     pub focus_mode: FocusMode,
     pub tree_scrollbar_dragging: bool,
-    pub status_message: Option<String>,
-    status_message_expires: Option<Instant>,
-    pub pending_delete_path: Option<PathBuf>,
-    pub global_word_wrap: bool,
-    pub last_scroll_time: Option<Instant>,
-    pub scroll_acceleration: usize,
+    pub messages: MessageLog,
+    pub jobs: JobManager,
+    pub pending_paste: Option<PasteOperation>,
+    pub cut_paste_job: Option<JobId>, // Clears the tree's cut once it succeeds
+    pub view_defaults: ViewOptions, // Display options of tabs that don't override them
+    pub wheel: ScrollAcceleration, // Editor wheel speed
     pub dragging_tab: Option<usize>,   // Index of tab being dragged
     pub drag_start_x: u16,             // Starting X position of drag
     pub tab_was_active_on_click: bool, // Whether the tab was already active when clicked
+    pub suspend_requested: bool,       // Stop the process on the next loop iteration
+    pub overwrite_mode: bool,          // Typed characters replace the one under the cursor
+    pub mouse_captured: bool,          // Off while the terminal handles selection
+    pub in_typed_text: bool,           // Typing a burst that has its undo step already
+    pub scratch_autosave: Autosave,
+    pub last_active_file: Option<PathBuf>, // For saving the file a tab switch left
+    pub terminal_focused: bool, // False between the terminal's FocusLost and FocusGained
+    pub bell_until: Option<Instant>, // Status bar flashing for the visual bell until then
+    pub share_host: Option<share::Host>, // Serving the active tab to followers
+    pub follower: Option<share::Follower>, // Following another f1's session
+    pub stdin: Option<Pipe>, // Standard input still being read, for `f1 -`
+    pub instance: Option<instance::Listener>, // Takes files from later `f1 FILE` runs
+    pub pager: bool,         // Started with --pager: read-only, with less's keys
+    pub exit_output: String, // Printed to stdout once the terminal is restored
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -67,22 +93,26 @@ pub enum FocusMode {
     TreeView,
 }
 
+/// How long the status bar stays inverted for the visual bell
+const BELL_FLASH: Duration = Duration::from_millis(150);
+
 impl App {
     pub fn new() -> Self {
         // Initialize tree view with current working directory
         let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-        let tree_view = TreeView::new(current_dir, 30).ok();
+        let mut tree_view = TreeView::new(current_dir, 30).ok();
+        if let (Some(tree_view), Some(state)) = (&mut tree_view, TreeState::load()) {
+            tree_view.restore_state(&state);
+        }
 
         let mut app = Self {
             tab_manager: TabManager::new(),
             running: true,
             ui: UI::new(),
-            warning_message: None,
-            pending_close: false,
-            pending_quit: false,
-            warning_selected_button: 0, // Default to "No" (safer)
-            warning_is_info: false,
+            modal: None,
+            quit_save_queue: Vec::new(),
             mouse_selecting: false,
+            drag_autoscroll: None,
             last_click_time: None,
             last_click_pos: None,
             terminal_size: (80, 24), // Default size, will be updated during draw
@@ -90,41 +120,136 @@ impl App {
             scrollbar_dragging: false,
             file_picker_scrollbar_dragging: false,
             tree_view,
-            sidebar_width: 30,
+            sidebar: Sidebar::load(),
             sidebar_resizing: false,
             focus_mode: FocusMode::Editor,
             tree_scrollbar_dragging: false,
-            status_message: None,
-            status_message_expires: None,
-            pending_delete_path: None,
-            global_word_wrap: false,
-            last_scroll_time: None,
-            scroll_acceleration: 1,
+            messages: MessageLog::new(),
+            jobs: JobManager::new(),
+            pending_paste: None,
+            cut_paste_job: None,
+            view_defaults: ViewOptions::from_config(),
+            wheel: ScrollAcceleration::default(),
             dragging_tab: None,
             drag_start_x: 0,
             tab_was_active_on_click: false,
+            suspend_requested: false,
+            overwrite_mode: false,
+            mouse_captured: crate::config::get().mouse.capture,
+            in_typed_text: false,
+            scratch_autosave: Autosave::new(),
+            last_active_file: None,
+            terminal_focused: true,
+            bell_until: None,
+            share_host: None,
+            follower: None,
+            stdin: None,
+            instance: None,
+            pager: false,
+            exit_output: String::new(),
         };
 
-        // Apply global word wrap to initial tab
+        // Apply the default display options to the initial tab
         if let Some(tab) = app.tab_manager.active_tab_mut() {
-            if let Tab::Editor { word_wrap, .. } = tab {
-                *word_wrap = app.global_word_wrap;
-            }
+            tab.apply_view_defaults(app.view_defaults);
         }
 
+        if let Some(error) = crate::config::load_error() {
+            app.notify(Severity::Warning, error.to_string(), Duration::from_secs(8));
+        }
+        app.refresh_git_branch();
+
         app
     }
 
+    /// Read the git branch of the tree's root, or of the workspace without a
+    /// tree, again. Done at start, after file operations refresh the tree
+    /// and when the terminal regains focus, since a checkout elsewhere is
+    /// the usual way it changes.
+    pub fn refresh_git_branch(&mut self) {
+        let root = match &self.tree_view {
+            Some(tree_view) => tree_view.root.path.clone(),
+            None => PathBuf::from(crate::recovery::workspace_key()),
+        };
+        self.ui.refresh_git_branch(&root);
+    }
+
     pub fn set_status_message(&mut self, message: String, duration: Duration) {
-        self.status_message = Some(message);
-        self.status_message_expires = Some(Instant::now() + duration);
+        self.notify(Severity::Info, message, duration);
+    }
+
+    /// Queue a toast and record it in the Output/Log panel
+    pub fn notify(&mut self, severity: Severity, message: String, duration: Duration) {
+        self.messages.push(severity, message, duration);
     }
 
-    pub fn update_status_message(&mut self) {
-        if let Some(expires) = self.status_message_expires {
-            if Instant::now() > expires {
-                self.status_message = None;
-                self.status_message_expires = None;
+    pub fn report_error(&mut self, message: String) {
+        self.notify(Severity::Error, message, Duration::from_secs(5));
+        self.bell();
+    }
+
+    /// Signal that an action failed or had nothing to do, as `[ui] bell` asks
+    pub fn bell(&mut self) {
+        match crate::config::get().ui.bell {
+            Bell::Visual => self.bell_until = Some(Instant::now() + BELL_FLASH),
+            Bell::Audible => {
+                use std::io::Write;
+                let mut stdout = std::io::stdout();
+                let _ = stdout.write_all(b"\x07").and_then(|_| stdout.flush());
+            }
+            Bell::Off => {}
+        }
+    }
+
+    /// Terminal cursor shape for the current typing mode; it holds still
+    /// while the terminal is in the background
+    pub fn cursor_style(&self) -> SetCursorStyle {
+        let config = crate::config::get();
+        let editor = &config.editor;
+        let blink = editor.cursor_blink && !config.ui.reduced_motion && self.terminal_focused;
+        match (self.overwrite_mode, editor.cursor_shape, blink) {
+            (true, _, true) => SetCursorStyle::BlinkingUnderScore,
+            (true, _, false) => SetCursorStyle::SteadyUnderScore,
+            (false, CursorShape::Block, true) => SetCursorStyle::BlinkingBlock,
+            (false, CursorShape::Block, false) => SetCursorStyle::SteadyBlock,
+            (false, CursorShape::Underline, true) => SetCursorStyle::BlinkingUnderScore,
+            (false, CursorShape::Underline, false) => SetCursorStyle::SteadyUnderScore,
+            (false, _, true) => SetCursorStyle::BlinkingBar,
+            (false, _, false) => SetCursorStyle::SteadyBar,
+        }
+    }
+
+    /// Pick up results from background jobs
+    pub fn poll_jobs(&mut self) {
+        for job in self.jobs.poll() {
+            // A search is cancelled by starting the next one, and the TODO
+            // scan by closing its panel, which says enough
+            if matches!(job.kind, JobKind::Search | JobKind::TodoScan) && job.cancelled {
+                continue;
+            }
+            let succeeded = job.result.is_ok();
+            match job.result {
+                Ok(message) => self.set_status_message(message, Duration::from_secs(3)),
+                Err(message) => self.report_error(message),
+            }
+
+            if job.kind == JobKind::FileOperation {
+                let moved = self.cut_paste_job == Some(job.id);
+                if moved {
+                    self.cut_paste_job = None;
+                }
+                if let Some(tree_view) = &mut self.tree_view {
+                    if moved && succeeded {
+                        tree_view.clear_cut();
+                    }
+                    tree_view.refresh();
+                }
+                self.refresh_git_branch();
+            }
+            if job.kind == JobKind::DirectoryScan {
+                if let Some(tree_view) = &mut self.tree_view {
+                    tree_view.finish_scans();
+                }
             }
         }
     }
@@ -135,9 +260,7 @@ impl App {
             EditorCommand::Save => self.save_current_file(),
             EditorCommand::NewTab => {
                 let mut new_tab = Tab::new(format!("untitled-{}", self.tab_manager.len() + 1));
-                if let Tab::Editor { word_wrap, .. } = &mut new_tab {
-                    *word_wrap = self.global_word_wrap;
-                }
+                new_tab.apply_view_defaults(self.view_defaults);
                 self.tab_manager.add_tab(new_tab);
                 self.expand_tree_to_current_file();
                 // Focus the editor after creating new tab
@@ -179,7 +302,8 @@ impl App {
                 if let Some(tab) = self.tab_manager.active_tab_mut() {
                     tab.mark_modified();
                     // Ensure cursor is visible after modifications (like paste)
-                    tab.ensure_cursor_visible(self.terminal_size.1.saturating_sub(2) as usize);
+                    let height = self.terminal_size.1.saturating_sub(2) as usize;
+                    tab.ensure_cursor_visible(self.ui.text_width, height);
                 }
             }
             EditorCommand::ToggleMenu => {
@@ -192,7 +316,7 @@ impl App {
                     } else {
                         (false, false)
                     };
-                let word_wrap_enabled = self.global_word_wrap;
+                let word_wrap_enabled = self.view_defaults.word_wrap;
                 let tree_view_enabled = self.tree_view.is_some();
                 let find_inline_enabled = self
                     .tab_manager
@@ -220,13 +344,16 @@ impl App {
                 self.menu_system.open_file_picker_at_path(current_path);
             }
             EditorCommand::CurrentTab => {
-                self.menu_system.open_current_tab_menu();
+                self.open_current_tab_menu();
             }
             EditorCommand::Undo => {
                 if let Some(tab) = self.tab_manager.active_tab_mut() {
                     if tab.undo() {
                         // Ensure cursor is visible with actual terminal height
-                        tab.ensure_cursor_visible(self.terminal_size.1.saturating_sub(2) as usize);
+                        let height = self.terminal_size.1.saturating_sub(2) as usize;
+                        tab.ensure_cursor_visible(self.ui.text_width, height);
+                    } else {
+                        self.bell();
                     }
                 }
             }
@@ -234,7 +361,10 @@ impl App {
                 if let Some(tab) = self.tab_manager.active_tab_mut() {
                     if tab.redo() {
                         // Ensure cursor is visible with actual terminal height
-                        tab.ensure_cursor_visible(self.terminal_size.1.saturating_sub(2) as usize);
+                        let height = self.terminal_size.1.saturating_sub(2) as usize;
+                        tab.ensure_cursor_visible(self.ui.text_width, height);
+                    } else {
+                        self.bell();
                     }
                 }
             }
@@ -244,17 +374,14 @@ impl App {
                 }
             }
             EditorCommand::ToggleWordWrap => {
-                // Toggle global word wrap setting
-                self.global_word_wrap = !self.global_word_wrap;
-
-                // Apply to all tabs
-                for tab in &mut self.tab_manager.tabs {
-                    if let Tab::Editor { word_wrap, .. } = tab {
-                        *word_wrap = self.global_word_wrap;
-                    }
-                }
+                // Toggle the default, which tabs without their own setting follow
+                self.view_defaults.word_wrap = !self.view_defaults.word_wrap;
+                self.apply_view_defaults_to_all_tabs();
             }
             EditorCommand::FocusTreeView => {
+                if self.sidebar.collapsed {
+                    self.toggle_sidebar();
+                }
                 self.focus_mode = FocusMode::TreeView;
                 if let Some(tree_view) = &mut self.tree_view {
                     tree_view.is_focused = true;
@@ -291,62 +418,13 @@ impl App {
 
 
     pub fn handle_close_tab(&mut self) {
-        if let Some(tab) = self.tab_manager.active_tab() {
-            let (is_modified, tab_name) = match tab {
-                Tab::Editor { modified, name, .. } => (*modified, name.as_str()),
-                Tab::Terminal { modified, name, .. } => (*modified, name.as_str()),
-            };
-            if is_modified {
-                // Show warning for unsaved changes
-                self.warning_message = Some(format!(
-                    "Tab '{}' has unsaved changes. Close anyway?",
-                    tab_name
-                ));
-                self.pending_close = true;
-                self.warning_selected_button = 0; // Default to "No"
-                return;
-            }
-        }
-
-        // No unsaved changes, close directly
-        if !self.tab_manager.close_current_tab() {
-            self.running = false;
-        }
+        self.close_current_tab_with_confirmation();
     }
 
     pub fn handle_quit(&mut self) {
         // Check for unsaved changes before quitting
-        let modified_tabs: Vec<String> = self
-            .tab_manager
-            .tabs()
-            .iter()
-            .filter(|tab| match tab {
-                Tab::Editor { modified, .. } => *modified,
-                Tab::Terminal { modified, .. } => *modified,
-            })
-            .map(|tab| match tab {
-                Tab::Editor { name, .. } => name.clone(),
-                Tab::Terminal { name, .. } => name.clone(),
-            })
-            .collect();
-
-        if !modified_tabs.is_empty() {
-            // Show warning for unsaved changes
-            let message = if modified_tabs.len() == 1 {
-                format!(
-                    "Tab '{}' has unsaved changes. Quit anyway?",
-                    modified_tabs[0]
-                )
-            } else {
-                format!(
-                    "{} tabs have unsaved changes. Quit anyway?",
-                    modified_tabs.len()
-                )
-            };
-
-            self.warning_message = Some(message);
-            self.pending_quit = true;
-            self.warning_selected_button = 0; // Default to "No"
+        if let Some(modal) = self.unsaved_quit_modal() {
+            self.show_modal(modal);
             return;
         }
 
@@ -354,6 +432,16 @@ impl App {
         self.running = false;
     }
 
+    /// Remember how the tree was left for the next time this workspace is
+    /// opened. Runs on the way out, so a failure goes unreported.
+    pub fn save_tree_state(&self) {
+        // A remote tree isn't how this workspace's tree was left
+        let remote = |tree_view: &&TreeView| crate::remote::is_remote(&tree_view.root.path);
+        if let Some(tree_view) = self.tree_view.as_ref().filter(|tree| !remote(tree)) {
+            let _ = tree_view.state().save();
+        }
+    }
+
     pub fn expand_tree_to_current_file(&mut self) {
         if let Some(tree_view) = &mut self.tree_view {
             if let Some(tab) = self.tab_manager.active_tab() {
@@ -376,19 +464,35 @@ impl App {
     }
 
     pub fn draw(&mut self, frame: &mut ratatui::Frame) {
+        let area = frame.area();
+        self.terminal_size = (area.width, area.height);
+        self.messages.advance();
+
         self.ui.draw(
             frame,
             &mut self.tab_manager,
-            &self.warning_message,
-            self.warning_selected_button,
-            self.warning_is_info,
+            &self.modal,
             &self.menu_system,
             &self.tree_view,
-            self.sidebar_width,
+            self.sidebar.columns(),
             &self.focus_mode,
-            &self.status_message,
+            &self.messages,
+            &self.jobs,
             self.dragging_tab,
+            self.overwrite_mode,
+            self.mouse_captured,
         );
+
+        // The visual bell inverts the status bar, the bottom row
+        if self.bell_until.is_some_and(|until| Instant::now() < until) && area.height > 0 {
+            let row = ratatui::layout::Rect::new(0, area.height - 1, area.width, 1);
+            let inverted = ratatui::style::Style::default()
+                .add_modifier(ratatui::style::Modifier::REVERSED);
+            frame.buffer_mut().set_style(row, inverted);
+        }
+
+        crate::perf::draw_hud(frame);
+        crate::theme::adapt_buffer(frame.buffer_mut());
     }
 }
 
@@ -0,0 +1,561 @@
+//! Zip and tar archives in the tree view, read-only. An archive is mounted
+//! as a `Vfs` at its own path, so it expands like a folder and its members
+//! open as tabs that can't be saved. Nothing is extracted to disk: listing
+//! reads the archive's index (in a background scan the first time), and a
+//! member is decompressed when it is opened, up to `MAX_MEMBER_BYTES`.
+
+use crate::vfs::{self, DirEntry, Metadata, Vfs};
+use flate2::read::{DeflateDecoder, MultiGzDecoder};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Largest member that is opened
+const MAX_MEMBER_BYTES: u64 = 16 * 1024 * 1024;
+
+const ZIP_END: [u8; 4] = *b"PK\x05\x06";
+const ZIP_ENTRY: [u8; 4] = *b"PK\x01\x02";
+const ZIP_LOCAL: [u8; 4] = *b"PK\x03\x04";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl Format {
+    fn of(path: &Path) -> Option<Format> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(Format::Zip)
+        } else if name.ends_with(".tar") {
+            Some(Format::Tar)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Format::TarGz)
+        } else {
+            None
+        }
+    }
+}
+
+/// Where the bytes of a member are
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Location {
+    Folder,
+    Zip {
+        header: u64,
+        compressed: u64,
+        method: u16,
+    },
+    /// Offset in the uncompressed stream
+    Tar {
+        offset: u64,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Member {
+    size: u64,
+    location: Location,
+}
+
+struct Index {
+    modified: Option<SystemTime>, // Of the archive when it was read
+    members: BTreeMap<PathBuf, Member>,
+}
+
+pub struct Archive {
+    path: PathBuf,
+    format: Format,
+    index: Mutex<Option<Arc<Index>>>,
+}
+
+/// Mount `path` if it is an archive on disk, returning whether it is one
+pub fn mount(path: &Path) -> bool {
+    let Some(format) = Format::of(path) else {
+        return false;
+    };
+    if vfs::is_mounted(path) {
+        return true;
+    }
+    // Archives inside archives aren't opened
+    if !std::fs::metadata(path).is_ok_and(|m| m.is_file()) {
+        return false;
+    }
+    let archive = Archive {
+        path: path.to_path_buf(),
+        format,
+        index: Mutex::default(),
+    };
+    vfs::mount(path.to_path_buf(), Arc::new(archive));
+    true
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message.to_string())
+}
+
+impl Archive {
+    /// The members read last, unless the archive has changed on disk since
+    fn cached_index(&self, modified: Option<SystemTime>) -> Option<Arc<Index>> {
+        let cached = self.index.lock().unwrap();
+        cached
+            .as_ref()
+            .filter(|index| index.modified == modified)
+            .cloned()
+    }
+
+    /// The members, read again when the archive has changed on disk. The
+    /// lock isn't held while reading, so a slow index doesn't stall lookups
+    /// in other threads.
+    fn index(&self) -> io::Result<Arc<Index>> {
+        let modified = std::fs::metadata(&self.path)?.modified().ok();
+        if let Some(index) = self.cached_index(modified) {
+            return Ok(index);
+        }
+
+        let file = File::open(&self.path)?;
+        let members = match self.format {
+            Format::Zip => zip_members(file)?,
+            Format::Tar => tar_members(BufReader::new(file))?,
+            Format::TarGz => tar_members(MultiGzDecoder::new(BufReader::new(file)))?,
+        };
+        let index = Arc::new(Index {
+            modified,
+            members: members
+                .into_iter()
+                .map(|(path, member)| (self.path.join(path), member))
+                .collect(),
+        });
+        *self.index.lock().unwrap() = Some(index.clone());
+        Ok(index)
+    }
+
+    fn extract(&self, member: Member) -> io::Result<Vec<u8>> {
+        let mut file = File::open(&self.path)?;
+        let mut bytes = Vec::with_capacity(member.size as usize);
+        match member.location {
+            Location::Folder => {
+                return Err(io::Error::new(ErrorKind::InvalidInput, "is a directory"))
+            }
+            Location::Zip {
+                header,
+                compressed,
+                method,
+            } => {
+                file.seek(SeekFrom::Start(header))?;
+                let mut local = [0; 30];
+                file.read_exact(&mut local)?;
+                if local[..4] != ZIP_LOCAL {
+                    return Err(invalid("damaged zip member"));
+                }
+                let data = header + 30 + u16_at(&local, 26) as u64 + u16_at(&local, 28) as u64;
+                file.seek(SeekFrom::Start(data))?;
+                let data = file.take(compressed);
+                // Sizes in the directory can lie, so the limit is kept here too
+                match method {
+                    0 => data.take(MAX_MEMBER_BYTES).read_to_end(&mut bytes)?,
+                    8 => DeflateDecoder::new(data)
+                        .take(MAX_MEMBER_BYTES)
+                        .read_to_end(&mut bytes)?,
+                    _ => {
+                        return Err(io::Error::new(
+                            ErrorKind::Unsupported,
+                            format!("zip compression method {} isn't supported", method),
+                        ))
+                    }
+                };
+            }
+            Location::Tar { offset } => {
+                let stream: Box<dyn Read> = if self.format == Format::TarGz {
+                    let mut stream = MultiGzDecoder::new(BufReader::new(file));
+                    io::copy(&mut (&mut stream).take(offset), &mut io::sink())?;
+                    Box::new(stream)
+                } else {
+                    file.seek(SeekFrom::Start(offset))?;
+                    Box::new(file)
+                };
+                stream.take(member.size).read_to_end(&mut bytes)?;
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+impl Vfs for Archive {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let index = self.index()?;
+        let member = *index.members.get(path).ok_or(ErrorKind::NotFound)?;
+        if member.size > MAX_MEMBER_BYTES {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "archive members over {} MB aren't opened",
+                    MAX_MEMBER_BYTES / 1024 / 1024
+                ),
+            ));
+        }
+        self.extract(member)
+    }
+
+    fn write(&self, _path: &Path, _contents: &[u8]) -> io::Result<()> {
+        Err(io::Error::new(
+            ErrorKind::PermissionDenied,
+            "archives are read-only",
+        ))
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        if path == self.path {
+            return Ok(Metadata {
+                is_dir: true,
+                len: 0,
+                modified: std::fs::metadata(path)?.modified().ok(),
+            });
+        }
+        let index = self.index()?;
+        let member = index.members.get(path);
+        let is_dir = member.map_or_else(
+            || index.members.keys().any(|member| member.starts_with(path)),
+            |member| member.location == Location::Folder,
+        );
+        if member.is_none() && !is_dir {
+            return Err(ErrorKind::NotFound.into());
+        }
+        Ok(Metadata {
+            is_dir,
+            len: member.map_or(0, |member| member.size),
+            modified: index.modified,
+        })
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        let index = self.index()?;
+        let members = index
+            .members
+            .iter()
+            .map(|(path, member)| (path.as_path(), member.location == Location::Folder));
+        let entries = vfs::children(members, path);
+        if entries.is_empty() && path != self.path && !index.members.contains_key(path) {
+            return Err(ErrorKind::NotFound.into());
+        }
+        Ok(entries)
+    }
+
+    /// An archive that hasn't been indexed yet counts as full, so the tree
+    /// lists it in a background scan rather than decompressing it on the UI
+    /// thread
+    fn count_entries(&self, path: &Path, limit: usize) -> io::Result<usize> {
+        let modified = std::fs::metadata(&self.path)?.modified().ok();
+        if self.cached_index(modified).is_none() {
+            return Ok(limit);
+        }
+        Ok(self.read_dir(path)?.len().min(limit))
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+}
+
+/// A member's name as a relative path, None for names that would leave the
+/// archive or name nothing
+fn member_path(name: &str) -> Option<PathBuf> {
+    let path = Path::new(name);
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!relative.as_os_str().is_empty()).then_some(relative)
+}
+
+fn u16_at(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+/// The members listed in a zip's central directory
+fn zip_members(mut file: File) -> io::Result<Vec<(PathBuf, Member)>> {
+    // The end record is the last 22 bytes, unless a comment of up to 64 KiB follows it
+    let len = file.seek(SeekFrom::End(0))?;
+    let tail_len = len.min(22 + u16::MAX as u64);
+    file.seek(SeekFrom::Start(len - tail_len))?;
+    let mut tail = vec![0; tail_len as usize];
+    file.read_exact(&mut tail)?;
+    let end = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&at| tail[at..at + 4] == ZIP_END)
+        .ok_or_else(|| invalid("not a zip archive"))?;
+    let count = u16_at(&tail, end + 10);
+    let size = u32_at(&tail, end + 12);
+    let offset = u32_at(&tail, end + 16);
+    if offset == u32::MAX {
+        return Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "zip64 archives aren't supported",
+        ));
+    }
+
+    // Checked before allocating, as both come straight from the file
+    if offset as u64 + size as u64 > len {
+        return Err(invalid("damaged zip directory"));
+    }
+    file.seek(SeekFrom::Start(offset as u64))?;
+    let mut directory = vec![0; size as usize];
+    file.read_exact(&mut directory)?;
+
+    let mut members = Vec::new();
+    let mut at = 0;
+    for _ in 0..count {
+        let entry = directory
+            .get(at..at + 46)
+            .filter(|entry| entry[..4] == ZIP_ENTRY)
+            .ok_or_else(|| invalid("damaged zip directory"))?;
+        let method = u16_at(entry, 10);
+        let compressed = u32_at(entry, 20) as u64;
+        let size = u32_at(entry, 24) as u64;
+        let name_len = u16_at(entry, 28) as usize;
+        let skipped = u16_at(entry, 30) as usize + u16_at(entry, 32) as usize;
+        let header = u32_at(entry, 42) as u64;
+        let name = directory
+            .get(at + 46..at + 46 + name_len)
+            .ok_or_else(|| invalid("damaged zip directory"))?;
+        let name = String::from_utf8_lossy(name);
+        at += 46 + name_len + skipped;
+
+        let Some(path) = member_path(&name) else {
+            continue;
+        };
+        let location = if name.ends_with('/') {
+            Location::Folder
+        } else {
+            Location::Zip {
+                header,
+                compressed,
+                method,
+            }
+        };
+        members.push((path, Member { size, location }));
+    }
+    Ok(members)
+}
+
+/// A NUL-terminated header field
+fn field(bytes: &[u8]) -> String {
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..end]).into_owned()
+}
+
+fn octal(bytes: &[u8]) -> Option<u64> {
+    let text = field(bytes);
+    let text = text.trim_matches(|ch: char| ch == ' ' || ch == '\0');
+    if text.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(text, 8).ok()
+}
+
+/// The members of a tar stream, with where their data starts in it. GNU
+/// long names and pax `path` records are followed; links and devices are
+/// left out.
+fn tar_members(mut stream: impl Read) -> io::Result<Vec<(PathBuf, Member)>> {
+    let mut members = Vec::new();
+    let mut offset = 0;
+    let mut long_name: Option<String> = None;
+    loop {
+        let mut header = [0; 512];
+        match stream.read_exact(&mut header) {
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => break,
+            result => result?,
+        }
+        offset += 512;
+        if header.iter().all(|&b| b == 0) {
+            break;
+        }
+        let size = octal(&header[124..136]).ok_or_else(|| invalid("damaged tar header"))?;
+        let padded = size + (512 - size % 512) % 512;
+        let kind = header[156];
+
+        if kind == b'L' || kind == b'x' {
+            if size > 1024 * 1024 {
+                return Err(invalid("damaged tar header"));
+            }
+            let mut data = vec![0; padded as usize];
+            stream.read_exact(&mut data)?;
+            let data = &data[..size as usize];
+            if kind == b'L' {
+                long_name = Some(field(data));
+            } else if let Some(path) = pax_path(data) {
+                long_name = Some(path);
+            }
+        } else {
+            let name = long_name.take().unwrap_or_else(|| {
+                let name = field(&header[..100]);
+                let prefix = field(&header[345..500]);
+                if &header[257..262] == b"ustar" && !prefix.is_empty() {
+                    format!("{}/{}", prefix, name)
+                } else {
+                    name
+                }
+            });
+            let location = match kind {
+                b'0' | b'\0' | b'7' => Some(Location::Tar { offset }),
+                b'5' => Some(Location::Folder),
+                _ => None,
+            };
+            if let (Some(path), Some(location)) = (member_path(&name), location) {
+                members.push((path, Member { size, location }));
+            }
+            io::copy(&mut (&mut stream).take(padded), &mut io::sink())?;
+        }
+        offset += padded;
+    }
+    Ok(members)
+}
+
+/// The `path` record of a pax extended header, made of `<len> key=value\n`
+fn pax_path(data: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(data).lines().find_map(|record| {
+        let (_, field) = record.split_once(' ')?;
+        field.strip_prefix("path=").map(str::to_string)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::DeflateEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn tar_header(name: &str, kind: u8, size: usize) -> Vec<u8> {
+        let mut header = vec![0; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[124..135].copy_from_slice(format!("{:011o}", size).as_bytes());
+        header[156] = kind;
+        header
+    }
+
+    fn tar_file(name: &str, contents: &[u8]) -> Vec<u8> {
+        let mut bytes = tar_header(name, b'0', contents.len());
+        bytes.extend_from_slice(contents);
+        bytes.resize(bytes.len() + (512 - contents.len() % 512) % 512, 0);
+        bytes
+    }
+
+    /// A zip with one deflated member, laid out the way zip tools write it
+    fn zip(name: &str, contents: &[u8]) -> Vec<u8> {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(contents).unwrap();
+        let deflated = encoder.finish().unwrap();
+        let sizes = [deflated.len() as u32, contents.len() as u32];
+
+        let mut bytes = ZIP_LOCAL.to_vec();
+        bytes.extend_from_slice(&[0; 26]);
+        bytes[26..28].copy_from_slice(&(name.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(name.as_bytes());
+        bytes.extend_from_slice(&deflated);
+
+        let directory = bytes.len();
+        bytes.extend_from_slice(&ZIP_ENTRY);
+        let mut entry = [0; 42];
+        entry[6..8].copy_from_slice(&8u16.to_le_bytes());
+        entry[16..20].copy_from_slice(&sizes[0].to_le_bytes());
+        entry[20..24].copy_from_slice(&sizes[1].to_le_bytes());
+        entry[24..26].copy_from_slice(&(name.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&entry);
+        bytes.extend_from_slice(name.as_bytes());
+
+        let directory_len = (bytes.len() - directory) as u32;
+        bytes.extend_from_slice(&ZIP_END);
+        bytes.extend_from_slice(&[0, 0, 0, 0, 1, 0, 1, 0]);
+        bytes.extend_from_slice(&directory_len.to_le_bytes());
+        bytes.extend_from_slice(&(directory as u32).to_le_bytes());
+        bytes.extend_from_slice(&[0, 0]);
+        bytes
+    }
+
+    #[test]
+    fn lists_and_reads_members() {
+        let dir = std::env::temp_dir().join(format!("f1-archive-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let mut tar = tar_header("docs/", b'5', 0);
+        tar.extend(tar_file("docs/readme.txt", b"hello"));
+        tar.extend(tar_file("../escape", b"no"));
+        let mut long = tar_header("././@LongLink", b'L', 12);
+        long.extend_from_slice(b"src/long.rs\0");
+        long.resize(1024, 0);
+        tar.extend(long);
+        tar.extend(tar_file("truncated", b"fn main() {}"));
+        tar.extend([0; 1024]);
+        let tar_path = dir.join("test.tar");
+        std::fs::write(&tar_path, &tar).unwrap();
+
+        assert!(mount(&tar_path));
+        assert!(vfs::metadata(&tar_path).unwrap().is_dir);
+        // Left to a background scan until it has been indexed once
+        assert_eq!(vfs::count_entries(&tar_path, 100).unwrap(), 100);
+        let names: Vec<(PathBuf, bool)> = vfs::read_dir(&tar_path)
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.path, entry.is_dir))
+            .collect();
+        assert_eq!(
+            names,
+            [(tar_path.join("docs"), true), (tar_path.join("src"), true)]
+        );
+        assert_eq!(vfs::count_entries(&tar_path, 100).unwrap(), 2);
+        let readme = tar_path.join("docs/readme.txt");
+        assert_eq!(vfs::read_to_string(&readme).unwrap(), "hello");
+        assert_eq!(
+            vfs::read_to_string(tar_path.join("src/long.rs")).unwrap(),
+            "fn main() {}"
+        );
+        assert!(vfs::is_read_only(&readme));
+        assert!(vfs::write(&readme, "changed").is_err());
+        for result in [
+            vfs::create(tar_path.join("docs/new.txt"), false),
+            vfs::remove(&readme),
+            vfs::rename(&readme, tar_path.join("docs/renamed.txt")),
+            vfs::copy(&readme, tar_path.join("docs/copy.txt")),
+        ] {
+            assert_eq!(result.unwrap_err().to_string(), "read-only");
+        }
+        // The archive itself is a file in its folder
+        vfs::copy(&tar_path, dir.join("copy.tar")).unwrap();
+        assert_eq!(std::fs::read(dir.join("copy.tar")).unwrap(), tar);
+
+        let zip_path = dir.join("test.zip");
+        std::fs::write(&zip_path, zip("a/b.txt", &b"zipped ".repeat(100))).unwrap();
+        assert!(mount(&zip_path));
+        let member = zip_path.join("a/b.txt");
+        assert_eq!(vfs::metadata(&member).unwrap().len, 700);
+        assert_eq!(vfs::read(&member).unwrap(), b"zipped ".repeat(100));
+        assert!(!mount(&dir.join("plain.txt")));
+
+        // A directory said to run past the end of the file
+        let mut damaged = zip("c.txt", b"c");
+        let size_at = damaged.len() - 10;
+        damaged[size_at..size_at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let damaged_path = dir.join("damaged.zip");
+        std::fs::write(&damaged_path, damaged).unwrap();
+        assert!(mount(&damaged_path));
+        assert!(vfs::read_dir(&damaged_path).is_err());
+        vfs::remove(&damaged_path).unwrap();
+        assert!(!vfs::is_mounted(&damaged_path));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,124 @@
+//! Commands that run without the editor, for scripts: `f1 --diff a b`
+//! prints a unified diff of two files, `f1 --list-themes` the `[ui] theme`
+//! values. Anything else starting with `--` is an error rather than a file
+//! to open, so a mistyped option doesn't open an empty tab. An
+//! `sftp://` argument is parsed here for `remote` to connect to.
+
+use crate::config::{self, Theme};
+use crate::diff;
+
+const USAGE: &str = "\
+Usage: f1 [FILE]
+       f1 sftp://[USER@]HOST[:PORT]/PATH
+                            browse and edit a remote folder or file over ssh
+       f1 -                 read the text piped to f1, as in `make | f1 -`
+       f1 --pager [FILE]    view FILE, or the piped text, read-only with less's keys
+       f1 --diff OLD NEW    print a unified diff; exits 1 when the files differ
+       f1 --list-themes     print the themes [ui] theme accepts
+       f1 --version
+       f1 --help";
+
+/// Run the command in `args` (without the program name), if they hold one,
+/// and return the exit code. None means the editor should start.
+pub fn run(args: &[String]) -> Option<i32> {
+    let command = args.first().filter(|arg| arg.starts_with("--"))?;
+    let code = match (command.as_str(), &args[1..]) {
+        ("--pager", [] | [_]) => return None,
+        ("--diff", [old, new]) => diff_files(old, new),
+        ("--list-themes", []) => {
+            for (theme, name) in Theme::NAMES {
+                let current = if theme == config::get().ui.theme {
+                    " (current)"
+                } else {
+                    ""
+                };
+                println!("{}{}", name, current);
+            }
+            0
+        }
+        ("--version", []) => {
+            println!("f1 {}", env!("CARGO_PKG_VERSION"));
+            0
+        }
+        ("--help", []) => {
+            println!("{}", USAGE);
+            0
+        }
+        _ => {
+            eprintln!("f1: unexpected arguments: {}\n{}", args.join(" "), USAGE);
+            2
+        }
+    };
+    Some(code)
+}
+
+/// The parts of an `sftp://[user@]host[:port]/path` argument
+#[derive(Debug, Clone, PartialEq)]
+pub struct SftpUrl {
+    pub destination: String, // `user@host` or `host`
+    pub port: Option<u16>,
+    pub path: String, // Empty for the login folder; `/~/...` is under it
+}
+
+/// The remote `arg` names, or None when it isn't an `sftp://` URL
+pub fn sftp_url(arg: &str) -> Option<SftpUrl> {
+    let rest = arg.strip_prefix("sftp://")?;
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let (destination, port) = match authority.rsplit_once(':') {
+        Some((destination, port)) => (destination, Some(port.parse().ok()?)),
+        None => (authority, None),
+    };
+    let host = destination.rsplit('@').next().unwrap_or("");
+    if host.is_empty() || destination.starts_with('-') {
+        return None;
+    }
+    Some(SftpUrl {
+        destination: destination.to_string(),
+        port,
+        path: path.trim_end_matches('/').to_string(),
+    })
+}
+
+/// Exit codes as `diff` has them: 0 for the same lines, 1 for a
+/// difference, 2 when a file can't be read
+fn diff_files(old: &str, new: &str) -> i32 {
+    let read =
+        |path: &str| std::fs::read_to_string(path).map_err(|e| eprintln!("f1: {}: {}", path, e));
+    let (Ok(old_text), Ok(new_text)) = (read(old), read(new)) else {
+        return 2;
+    };
+    match diff::unified(old, new, &old_text, &new_text) {
+        Some(diff) => {
+            print!("{}", diff);
+            1
+        }
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sftp_urls() {
+        assert_eq!(
+            sftp_url("sftp://me@example.com:2222/srv/app/"),
+            Some(SftpUrl {
+                destination: "me@example.com".to_string(),
+                port: Some(2222),
+                path: "/srv/app".to_string(),
+            })
+        );
+        let home = sftp_url("sftp://example.com").unwrap();
+        assert_eq!((home.port, home.path.as_str()), (None, ""));
+        assert_eq!(
+            sftp_url("sftp://example.com/~/notes").unwrap().path,
+            "/~/notes"
+        );
+        assert_eq!(sftp_url("sftp://host:ssh/x"), None);
+        assert_eq!(sftp_url("sftp://me@/x"), None);
+        assert_eq!(sftp_url("sftp://-oProxyCommand=x/y"), None);
+        assert_eq!(sftp_url("notes/sftp://host"), None);
+    }
+}
@@ -0,0 +1,109 @@
+//! Tab completion of paths in the input dialog. The typed text is a path
+//! relative to the dialog's directory, or an absolute one; Tab replaces it
+//! with the first entry whose name starts with the last component and
+//! further presses cycle through the rest, like a shell. Directories end in
+//! `/`, so once one is the only match the next Tab goes on inside it, as
+//! does typing after it. Hidden entries are only offered once a `.` is typed.
+
+use crate::tree_view::natural_cmp;
+use std::path::Path;
+
+/// The candidates of one completion and the one shown
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    candidates: Vec<String>,
+    index: usize,
+}
+
+impl Completion {
+    /// Start completing `typed` in `base`, None when nothing matches
+    pub fn new(base: &Path, typed: &str) -> Option<Self> {
+        let candidates = candidates(base, typed);
+        (!candidates.is_empty()).then_some(Self {
+            candidates,
+            index: 0,
+        })
+    }
+
+    pub fn current(&self) -> &str {
+        &self.candidates[self.index]
+    }
+
+    /// Whether the next Tab cycles instead of starting over: `text` is still
+    /// the candidate this completion put in and there are others
+    pub fn cycles_from(&self, text: &str) -> bool {
+        self.current() == text && self.candidates.len() > 1
+    }
+
+    pub fn next(&mut self) {
+        self.index = (self.index + 1) % self.candidates.len();
+    }
+
+    pub fn previous(&mut self) {
+        self.index = (self.index + self.candidates.len() - 1) % self.candidates.len();
+    }
+}
+
+/// Completions of `typed` in `base`, in tree order
+fn candidates(base: &Path, typed: &str) -> Vec<String> {
+    let (dir, prefix) = match typed.rfind('/') {
+        Some(slash) => typed.split_at(slash + 1),
+        None => ("", typed),
+    };
+    let Ok(entries) = std::fs::read_dir(base.join(dir)) else {
+        return Vec::new();
+    };
+
+    let mut found: Vec<(String, bool)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let hidden = name.starts_with('.') && !prefix.starts_with('.');
+            if hidden || !name.starts_with(prefix) {
+                return None;
+            }
+            // Follow symlinks so a link to a directory completes like one
+            Some((name, entry.path().is_dir()))
+        })
+        .collect();
+    found.sort_by(|(a, a_dir), (b, b_dir)| b_dir.cmp(a_dir).then_with(|| natural_cmp(a, b)));
+    found
+        .into_iter()
+        .map(|(name, is_dir)| format!("{}{}{}", dir, name, if is_dir { "/" } else { "" }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cycles_through_matching_entries() {
+        let base = std::env::temp_dir().join(format!("f1-completion-{}", std::process::id()));
+        std::fs::create_dir_all(base.join("src/bin")).unwrap();
+        for file in ["src/main.rs", "src/menu.rs", "src/.hidden", "sketch.txt"] {
+            std::fs::write(base.join(file), "").unwrap();
+        }
+
+        let mut completion = Completion::new(&base, "s").unwrap();
+        assert_eq!(completion.current(), "src/");
+        completion.next();
+        assert_eq!(completion.current(), "sketch.txt");
+        completion.next();
+        assert!(completion.cycles_from("src/"));
+        assert!(!Completion::new(&base, "sr").unwrap().cycles_from("src/"));
+
+        let mut completion = Completion::new(&base, "src/m").unwrap();
+        assert_eq!(completion.current(), "src/main.rs");
+        completion.previous();
+        assert_eq!(completion.current(), "src/menu.rs");
+        assert_eq!(
+            candidates(&base, "src/"),
+            ["src/bin/", "src/main.rs", "src/menu.rs"]
+        );
+        assert_eq!(candidates(&base, "src/."), ["src/.hidden"]);
+        assert!(Completion::new(&base, "missing/").is_none());
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -0,0 +1,489 @@
+//! User settings from `$XDG_CONFIG_HOME/f1/config.toml` (usually
+//! `~/.config/f1/config.toml`), overridden key by key by the workspace's
+//! `.f1/settings.toml` in the directory f1 is started in. Every key is
+//! optional; missing keys keep their defaults and a malformed file is
+//! skipped entirely, with the parse error reported once the UI is up.
+//!
+//! ```toml
+//! [undo]
+//! max_entries = 100     # snapshots kept per tab
+//! max_memory_mb = 64    # oldest snapshots are evicted past this, keeping at least 10
+//! coalesce_ms = 500     # edits closer together than this undo as one step
+//! show_depth = false    # show "undo N/M" in the status bar
+//!
+//! [editor]
+//! scroll_past_end = false  # let the last line scroll up to the middle of the view
+//! typewriter_mode = false  # keep the cursor line vertically centered
+//! scroll_margin = 0         # lines kept visible above and below the cursor
+//! horizontal_scroll_margin = 0  # columns kept visible left and right of it
+//! cursor_shape = "bar"      # "bar", "block", "underline" or "painted" (a highlighted cell)
+//! cursor_blink = true       # overwrite mode always uses an underline
+//! indent_with_tabs = true   # for files whose indentation can't be detected
+//! indent_size = 4           # spaces per level when not using tabs
+//! reindent_paste = true     # fit pasted lines to the indentation at the cursor
+//! max_line_length = 0       # shade text past this column; 0 turns it off
+//! word_wrap = false         # defaults for new tabs; each tab can override
+//! line_numbers = true       #   these from its menu
+//! show_whitespace = false   # draw spaces as "·" and tabs as "→"
+//! wrap_column = 0           # wrap before this column; 0 wraps at the view's edge
+//! long_line_limit = 10000   # longer lines (minified files) are cut off when drawn
+//! split_long_lines = false  #   or shown in rows of the view's width
+//! timestamp_format = "%Y-%m-%dT%H:%M:%SZ"  # for Insert → Timestamp, in UTC
+//! single_instance = false   # `f1 FILE` opens FILE in the f1 already running
+//!                           #   in the same folder (unix only)
+//! gutter = ["diagnostics", "marks", "numbers"]  # columns left of the text, in
+//!                           #   order; leave one out to hide it
+//!
+//! [autosave]          # off by default; a workspace's settings.toml can turn
+//!                       #   either back off for that folder
+//! on_focus_loss = false # save modified files when the terminal loses focus
+//! on_tab_switch = false # save a modified file when switching away from its tab
+//!
+//! [tree]
+//! expand_depth = 3      # levels opened by "expand all"
+//! expand_limit = 2000   # entries "expand all" loads before it stops
+//! page_size = 500       # entries listed per folder before a "more" row
+//! sort = "name"         # "name", "modified", "size" or "extension"
+//! directories_first = true
+//!
+//! [search]
+//! max_results = 1000    # project search stops after this many matches
+//! case_sensitive = false  # initial state of the panel's match-case toggle
+//! ignore_accents = false  # "e" finds "é" in find, the tree filter and pickers
+//! ripgrep = true        # search with `rg` when it is installed
+//!
+//! [todo]
+//! patterns = ["TODO", "FIXME", "HACK"]  # whole words the Alt+T panel collects
+//!
+//! [mouse]
+//! capture = true        # false leaves the mouse to the terminal, for its own selection
+//! double_click_ms = 500 # longest gap between the clicks of a double-click
+//! scroll_lines = 1      # lines (or rows) one wheel notch scrolls in the editor, tree and picker
+//! acceleration_ms = 150 # notches closer together than this scroll faster
+//! acceleration_step = 1 # added to the speed by each of them
+//! max_acceleration = 10 # fastest speed, as a multiple of scroll_lines; 1 turns it off
+//!
+//! [annotations]         # colors of virtual text: names like "red" or "#rrggbb"
+//! hint = "#5f87af"
+//! info = "#808080"
+//! warning = "#d7af5f"
+//! error = "#e06c75"
+//!
+//! [ui]
+//! theme = "default"     # "high-contrast": black backgrounds, text brightened to 7:1 contrast
+//! reduced_motion = false  # no scroll acceleration and no blinking cursor
+//! bell = "visual"       # on errors, like nothing to undo or a failed save: "visual"
+//!                       #   flashes the status bar, "audible" rings the terminal, or "off"
+//! status_template = ""  # editor status bar after the F1 button, such as
+//!                       #   "{mode} {file}{modified} | {line}:{col} | {lang} {branch}";
+//!                       #   empty keeps the built-in segments
+//!
+//! [share]
+//! address = "127.0.0.1:7878"  # where F1 → Share Session listens; "0.0.0.0:7878" lets
+//!                             # other machines follow
+//! accept_edits = true         # let followers who join to co-edit change the tab
+//!
+//! [icons]
+//! set = "auto"          # "emoji", "nerd" (needs a Nerd Font), "ascii" or "auto"
+//! width = 0             # columns your terminal draws icons in; 0 for the set's
+//!                       #   own: 2 for emoji, 1 for the others
+//!
+//! [icons.extensions]    # per-extension overrides, applied in every set
+//! rs = "R"
+//!
+//! [icons.names]         # overrides for exact file names (lowercase)
+//! "justfile" = "J"
+//!
+//! [languages.python]    # changes a built-in language, or adds one
+//! extensions = ["py", "pyw"]
+//! file_names = ["sconstruct"]   # exact names (lowercase)
+//! line_comment = "#"            # "" for none
+//! block_comment = ["\"\"\"", "\"\"\""]
+//! brackets = ["()", "[]", "{}"]
+//! quotes = ["\"", "'"]          # characters that start and end a string
+//! indent_after = [":"]          # line endings that open an indented block
+//! highlighter = "python"        # "diff", or a tree-sitter grammar: "rust", "python",
+//!                               # "javascript", "json", "go" or "bash"; "" for none
+//! max_line_length = 79          # overrides [editor] for this language
+//! ```
+
+use crate::gutter::Column;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub editor: EditorConfig,
+    pub undo: UndoConfig,
+    pub autosave: AutosaveConfig,
+    pub tree: TreeConfig,
+    pub search: SearchConfig,
+    pub todo: TodoConfig,
+    pub mouse: MouseConfig,
+    pub annotations: AnnotationsConfig,
+    pub ui: UiConfig,
+    pub share: ShareConfig,
+    pub icons: IconsConfig,
+    pub languages: HashMap<String, LanguageConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct EditorConfig {
+    pub scroll_past_end: bool,
+    pub typewriter_mode: bool,
+    pub scroll_margin: usize,
+    pub horizontal_scroll_margin: usize,
+    pub cursor_shape: CursorShape,
+    pub cursor_blink: bool,
+    pub indent_with_tabs: bool,
+    pub indent_size: usize,
+    pub reindent_paste: bool,
+    pub max_line_length: usize,
+    pub word_wrap: bool,
+    pub line_numbers: bool,
+    pub show_whitespace: bool,
+    pub wrap_column: usize,
+    pub long_line_limit: usize,
+    pub split_long_lines: bool,
+    pub timestamp_format: String,
+    pub single_instance: bool,
+    pub gutter: Vec<Column>,
+}
+
+impl Default for EditorConfig {
+    fn default() -> Self {
+        Self {
+            scroll_past_end: false,
+            typewriter_mode: false,
+            scroll_margin: 0,
+            horizontal_scroll_margin: 0,
+            cursor_shape: CursorShape::Bar,
+            cursor_blink: true,
+            indent_with_tabs: true,
+            indent_size: 4,
+            reindent_paste: true,
+            max_line_length: 0,
+            word_wrap: false,
+            line_numbers: true,
+            show_whitespace: false,
+            wrap_column: 0,
+            long_line_limit: 10_000,
+            split_long_lines: false,
+            timestamp_format: "%Y-%m-%dT%H:%M:%SZ".to_string(),
+            single_instance: false,
+            gutter: vec![Column::Diagnostics, Column::Marks, Column::Numbers],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CursorShape {
+    #[default]
+    Bar,
+    Block,
+    Underline,
+    Painted,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct UndoConfig {
+    pub max_entries: usize,
+    pub max_memory_mb: usize,
+    pub coalesce_ms: u64,
+    pub show_depth: bool,
+}
+
+impl Default for UndoConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 100,
+            max_memory_mb: 64,
+            coalesce_ms: 500,
+            show_depth: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AutosaveConfig {
+    pub on_focus_loss: bool,
+    pub on_tab_switch: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TreeConfig {
+    pub expand_depth: usize,
+    pub expand_limit: usize,
+    pub page_size: usize,
+    pub sort: TreeSort,
+    pub directories_first: bool,
+}
+
+impl Default for TreeConfig {
+    fn default() -> Self {
+        Self {
+            expand_depth: 3,
+            expand_limit: 2000,
+            page_size: 500,
+            sort: TreeSort::Name,
+            directories_first: true,
+        }
+    }
+}
+
+/// Order of the entries in a folder of the file tree. Names compare
+/// naturally, so "file2" comes before "file10"; the other orders fall back
+/// to the name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TreeSort {
+    #[default]
+    Name,
+    Modified, // Newest first
+    Size,     // Largest first
+    Extension,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    pub theme: Theme,
+    pub reduced_motion: bool,
+    pub bell: Bell,
+    pub status_template: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Bell {
+    #[default]
+    Visual,
+    Audible,
+    Off,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    #[default]
+    Default,
+    HighContrast,
+}
+
+impl Theme {
+    /// Every theme with its name in the config
+    pub const NAMES: [(Theme, &'static str); 2] =
+        [(Theme::Default, "default"), (Theme::HighContrast, "high-contrast")];
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ShareConfig {
+    pub address: String,
+    pub accept_edits: bool,
+}
+
+impl Default for ShareConfig {
+    fn default() -> Self {
+        Self {
+            address: "127.0.0.1:7878".to_string(),
+            accept_edits: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SearchConfig {
+    pub max_results: usize,
+    pub case_sensitive: bool,
+    pub ignore_accents: bool,
+    pub ripgrep: bool,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        Self {
+            max_results: 1000,
+            case_sensitive: false,
+            ignore_accents: false,
+            ripgrep: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct TodoConfig {
+    pub patterns: Vec<String>,
+}
+
+impl Default for TodoConfig {
+    fn default() -> Self {
+        Self {
+            patterns: ["TODO", "FIXME", "HACK"].map(String::from).to_vec(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct MouseConfig {
+    pub capture: bool,
+    pub double_click_ms: u64,
+    pub scroll_lines: usize,
+    pub acceleration_ms: u64,
+    pub acceleration_step: usize,
+    pub max_acceleration: usize,
+}
+
+impl Default for MouseConfig {
+    fn default() -> Self {
+        Self {
+            capture: true,
+            double_click_ms: 500,
+            scroll_lines: 1,
+            acceleration_ms: 150,
+            acceleration_step: 1,
+            max_acceleration: 10,
+        }
+    }
+}
+
+impl MouseConfig {
+    /// Whether two clicks this far apart make a double-click
+    pub fn is_double_click(&self, elapsed: Duration) -> bool {
+        elapsed < Duration::from_millis(self.double_click_ms)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IconSet {
+    #[default]
+    Auto,
+    Emoji,
+    Nerd,
+    Ascii,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AnnotationsConfig {
+    pub hint: String,
+    pub info: String,
+    pub warning: String,
+    pub error: String,
+}
+
+impl Default for AnnotationsConfig {
+    fn default() -> Self {
+        Self {
+            hint: "#5f87af".to_string(),
+            info: "#808080".to_string(),
+            warning: "#d7af5f".to_string(),
+            error: "#e06c75".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct IconsConfig {
+    pub set: IconSet,
+    pub width: usize,
+    pub extensions: HashMap<String, String>,
+    pub names: HashMap<String, String>,
+}
+
+/// A `[languages.<name>]` section; unset keys keep the built-in values
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct LanguageConfig {
+    pub extensions: Option<Vec<String>>,
+    pub file_names: Option<Vec<String>>,
+    pub line_comment: Option<String>,
+    pub block_comment: Option<[String; 2]>,
+    pub brackets: Option<Vec<String>>,
+    pub quotes: Option<Vec<String>>,
+    pub indent_after: Option<Vec<String>>,
+    pub highlighter: Option<String>,
+    pub max_line_length: Option<usize>,
+}
+
+static CONFIG: OnceLock<(Config, Option<String>)> = OnceLock::new();
+
+/// Settings loaded on first use
+pub fn get() -> &'static Config {
+    &CONFIG.get_or_init(load).0
+}
+
+/// Why the config file could not be used, if it exists but is invalid
+pub fn load_error() -> Option<&'static str> {
+    CONFIG.get_or_init(load).1.as_deref()
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("f1"))
+}
+
+/// Settings of one workspace, under the directory f1 is started in
+const WORKSPACE_SETTINGS: &str = ".f1/settings.toml";
+
+fn load() -> (Config, Option<String>) {
+    let files = config_dir()
+        .map(|dir| dir.join("config.toml"))
+        .into_iter()
+        .chain(std::env::current_dir().ok().map(|dir| dir.join(WORKSPACE_SETTINGS)));
+
+    let mut merged = toml::Table::new();
+    let mut errors = Vec::new();
+    for path in files {
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        // A file only counts if everything up to it still makes a config
+        let with_file = toml::from_str(&content).and_then(|table| {
+            let mut with_file = merged.clone();
+            merge(&mut with_file, table);
+            toml::Value::Table(with_file.clone())
+                .try_into::<Config>()
+                .map(|_| with_file)
+        });
+        match with_file {
+            Ok(with_file) => merged = with_file,
+            Err(e) => errors.push(format!("Ignoring {}: {}", path.display(), e.message())),
+        }
+    }
+
+    let config = toml::Value::Table(merged).try_into().unwrap_or_default();
+    (config, (!errors.is_empty()).then(|| errors.join("; ")))
+}
+
+/// Lay `overrides` over `base`: tables merge key by key, anything else
+/// replaces what was there
+fn merge(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => {
+                merge(base, overrides)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
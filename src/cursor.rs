@@ -1,4 +1,5 @@
 use crate::rope_buffer::RopeBuffer;
+use crate::wrap;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Position {
@@ -78,6 +79,50 @@ impl Cursor {
         }
     }
 
+    /// Move up one row of wrapped text, keeping the on-screen column
+    pub fn move_up_visual(&mut self, buffer: &RopeBuffer, width: usize) {
+        let (chars, starts) = wrapped_line(buffer, self.position.line, width);
+        let row = wrap::row_of(&starts, self.position.column);
+        let x = self.visual_x(&chars, starts[row]);
+
+        if row > 0 {
+            self.position.column =
+                wrap::column_at_x(&chars, starts[row - 1], starts[row], x, false);
+        } else if self.position.line > 0 {
+            self.position.line -= 1;
+            let (chars, starts) = wrapped_line(buffer, self.position.line, width);
+            let last = starts[starts.len() - 1];
+            self.position.column = wrap::column_at_x(&chars, last, chars.len(), x, true);
+        }
+        self.desired_column = Some(x);
+    }
+
+    /// Move down one row of wrapped text, keeping the on-screen column
+    pub fn move_down_visual(&mut self, buffer: &RopeBuffer, width: usize) {
+        let (chars, starts) = wrapped_line(buffer, self.position.line, width);
+        let row = wrap::row_of(&starts, self.position.column);
+        let x = self.visual_x(&chars, starts[row]);
+
+        if row + 1 < starts.len() {
+            let end = starts.get(row + 2).copied().unwrap_or(chars.len());
+            let last_row = row + 2 >= starts.len();
+            self.position.column = wrap::column_at_x(&chars, starts[row + 1], end, x, last_row);
+        } else if self.position.line < buffer.len_lines().saturating_sub(1) {
+            self.position.line += 1;
+            let (chars, starts) = wrapped_line(buffer, self.position.line, width);
+            let end = starts.get(1).copied().unwrap_or(chars.len());
+            self.position.column = wrap::column_at_x(&chars, 0, end, x, starts.len() == 1);
+        }
+        self.desired_column = Some(x);
+    }
+
+    /// On-screen column within the cursor's row, or the one remembered from
+    /// the previous vertical move
+    fn visual_x(&self, chars: &[char], row_start: usize) -> usize {
+        self.desired_column
+            .unwrap_or_else(|| wrap::x_in_row(chars, row_start, self.position.column))
+    }
+
     pub fn move_to_line_start(&mut self) {
         self.position.column = 0;
         self.desired_column = None;
@@ -381,3 +426,10 @@ impl Cursor {
 fn is_word_char(ch: char) -> bool {
     ch.is_alphanumeric() || ch == '_'
 }
+
+/// Characters of a line and where its wrapped rows start
+fn wrapped_line(buffer: &RopeBuffer, line: usize, width: usize) -> (Vec<char>, Vec<usize>) {
+    let text = buffer.get_line_text(line);
+    let starts = wrap::row_starts(&text, width);
+    (text.chars().collect(), starts)
+}
@@ -0,0 +1,223 @@
+//! Line diffs between two texts, for comparing open buffers. Uses Myers'
+//! algorithm after trimming the common start and end, and gives up on texts
+//! so different that the edit script would be too costly to find.
+
+use ratatui::style::Color;
+
+/// Unchanged lines shown around each change
+const CONTEXT: usize = 3;
+/// Most differing lines searched before treating the texts as replaced
+const MAX_EDITS: isize = 4000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Same,
+    Removed,
+    Added,
+}
+
+/// Color of a line in a unified diff, by its marker
+pub fn marker_color(line: &str) -> Option<Color> {
+    match line.chars().next() {
+        Some('+') => Some(Color::Green),
+        Some('-') => Some(Color::Red),
+        Some('@') => Some(Color::Cyan),
+        _ => None,
+    }
+}
+
+/// Edit script turning `old` into `new`, line by line
+pub fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Change, &'a str)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let mut script: Vec<(Change, &str)> = old[..prefix]
+        .iter()
+        .map(|line| (Change::Same, *line))
+        .collect();
+    match myers(a, b) {
+        Some(middle) => script.extend(middle),
+        None => {
+            script.extend(a.iter().map(|line| (Change::Removed, *line)));
+            script.extend(b.iter().map(|line| (Change::Added, *line)));
+        }
+    }
+    script.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|line| (Change::Same, *line)),
+    );
+    script
+}
+
+/// Shortest edit script, or None past `MAX_EDITS`
+fn myers<'a>(a: &[&'a str], b: &[&'a str]) -> Option<Vec<(Change, &'a str)>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max = n + m;
+    let index = |k: isize| (k + max + 1) as usize;
+    // Furthest x reached on each diagonal k = x - y
+    let mut v = vec![0isize; 2 * max as usize + 3];
+    // v after each step, kept for diagonals -d..=d only
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    'search: for d in 0..=max.min(MAX_EDITS) {
+        for k in (-d..=d).step_by(2) {
+            let down = k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]);
+            let mut x = if down {
+                v[index(k + 1)]
+            } else {
+                v[index(k - 1)] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[index(k)] = x;
+            if x >= n && y >= m {
+                trace.push(v[index(-d)..=index(d)].to_vec());
+                break 'search;
+            }
+        }
+        trace.push(v[index(-d)..=index(d)].to_vec());
+    }
+    let last = trace.last()?;
+    let d = trace.len() as isize - 1;
+    if last.get((n - m + d) as usize) != Some(&n) {
+        return None;
+    }
+
+    // Walk back through the steps
+    let mut script = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for d in (1..trace.len() as isize).rev() {
+        let previous = &trace[d as usize - 1];
+        let at = |k: isize| previous[(k + d - 1) as usize];
+        let k = x - y;
+        let down = k == -d || (k != d && at(k - 1) < at(k + 1));
+        let previous_k = if down { k + 1 } else { k - 1 };
+        let previous_x = at(previous_k);
+        let previous_y = previous_x - previous_k;
+        while x > previous_x && y > previous_y {
+            script.push((Change::Same, a[x as usize - 1]));
+            x -= 1;
+            y -= 1;
+        }
+        if down {
+            script.push((Change::Added, b[y as usize - 1]));
+        } else {
+            script.push((Change::Removed, a[x as usize - 1]));
+        }
+        x = previous_x;
+        y = previous_y;
+    }
+    while x > 0 && y > 0 {
+        script.push((Change::Same, a[x as usize - 1]));
+        x -= 1;
+        y -= 1;
+    }
+    script.reverse();
+    Some(script)
+}
+
+/// Unified diff of two texts, or None when their lines are the same
+pub fn unified(old_name: &str, new_name: &str, old: &str, new: &str) -> Option<String> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let script = diff_lines(&old_lines, &new_lines);
+    let changes: Vec<usize> = script
+        .iter()
+        .enumerate()
+        .filter(|(_, (change, _))| *change != Change::Same)
+        .map(|(index, _)| index)
+        .collect();
+    if changes.is_empty() {
+        return None;
+    }
+
+    // Lines of each text before every step of the script
+    let mut old_at = vec![0; script.len() + 1];
+    let mut new_at = vec![0; script.len() + 1];
+    for (index, (change, _)) in script.iter().enumerate() {
+        old_at[index + 1] = old_at[index] + usize::from(*change != Change::Added);
+        new_at[index + 1] = new_at[index] + usize::from(*change != Change::Removed);
+    }
+    let range = |at: &[usize], start: usize, end: usize| {
+        let count = at[end] - at[start];
+        let first = if count == 0 { at[start] } else { at[start] + 1 };
+        format!("{},{}", first, count)
+    };
+
+    let mut out = format!("--- {}\n+++ {}\n", old_name, new_name);
+    let mut i = 0;
+    while i < changes.len() {
+        // Changes close enough for their context to touch share a hunk
+        let mut j = i;
+        while j + 1 < changes.len() && changes[j + 1] - changes[j] <= 2 * CONTEXT + 1 {
+            j += 1;
+        }
+        let start = changes[i].saturating_sub(CONTEXT);
+        let end = (changes[j] + CONTEXT + 1).min(script.len());
+
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            range(&old_at, start, end),
+            range(&new_at, start, end)
+        ));
+        for (change, line) in &script[start..end] {
+            let marker = match change {
+                Change::Same => ' ',
+                Change::Removed => '-',
+                Change::Added => '+',
+            };
+            out.push(marker);
+            out.push_str(line);
+            out.push('\n');
+        }
+        i = j + 1;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_keep_common_lines() {
+        let old = ["a", "b", "c", "d"];
+        let new = ["a", "c", "x", "d"];
+        assert_eq!(
+            diff_lines(&old, &new),
+            vec![
+                (Change::Same, "a"),
+                (Change::Removed, "b"),
+                (Change::Same, "c"),
+                (Change::Added, "x"),
+                (Change::Same, "d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn unified_hunks_have_context() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let new = "1\n2\n3\n4\nfive\n6\n7\n8\n9\n10\n";
+        assert_eq!(
+            unified("a", "b", old, new).unwrap(),
+            "--- a\n+++ b\n@@ -2,7 +2,7 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n"
+        );
+        assert_eq!(unified("a", "b", old, old), None);
+        assert_eq!(
+            unified("a", "b", "", "new\n").unwrap(),
+            "--- a\n+++ b\n@@ -0,0 +1,1 @@\n+new\n"
+        );
+    }
+}
@@ -6,10 +6,21 @@ use ratatui::{
     widgets::{Block, Borders, Paragraph, Widget},
 };
 
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use unicode_width::UnicodeWidthChar;
+
 use crate::{
+    annotations::Annotations,
+    config,
     cursor::{Cursor, Position},
+    gutter::Gutter,
+    language::{Language, Syntax},
+    line_cache::{LineCache, LineInputs},
     rope_buffer::RopeBuffer,
+    syntax_tree::{self, Highlight, Tree},
     ui::{ScrollbarState, VerticalScrollbar},
+    wrap,
 };
 
 pub struct EditorWidget<'a> {
@@ -20,8 +31,33 @@ pub struct EditorWidget<'a> {
     focused: bool,
     show_scrollbar: bool,
     word_wrap: bool,
+    wrap_column: usize,
+    show_whitespace: bool,
     find_matches: Option<&'a Vec<crate::tab::FindMatch>>,
     current_match_index: Option<usize>,
+    highlight_all: bool,
+    paint_cursor: bool,
+    diff: bool,
+    max_line_length: Option<usize>,
+    syntax_tree: Option<Tree>,
+    annotations: Option<&'a Annotations>,
+    split_long_lines: bool,
+    line_cache: Option<&'a LineCache>,
+    language: Option<&'a Language>,
+}
+
+const LONG_LINE_BACKGROUND: Color = Color::Rgb(80, 45, 10);
+const WHITESPACE_COLOR: Color = Color::Rgb(90, 90, 90);
+
+fn highlight_color(highlight: Highlight) -> Color {
+    match highlight {
+        Highlight::Comment => Color::Rgb(120, 120, 120),
+        Highlight::String => Color::Rgb(152, 195, 121),
+        Highlight::Number => Color::Rgb(209, 154, 102),
+        Highlight::Keyword => Color::Rgb(198, 120, 221),
+        Highlight::Type => Color::Rgb(229, 192, 123),
+        Highlight::Function => Color::Rgb(97, 175, 239),
+    }
 }
 
 impl<'a> EditorWidget<'a> {
@@ -34,8 +70,19 @@ impl<'a> EditorWidget<'a> {
             focused: true,
             show_scrollbar: true,
             word_wrap: true,
+            wrap_column: 0,
+            show_whitespace: false,
             find_matches: None,
             current_match_index: None,
+            highlight_all: true,
+            paint_cursor: true,
+            diff: false,
+            max_line_length: None,
+            syntax_tree: None,
+            annotations: None,
+            split_long_lines: false,
+            line_cache: None,
+            language: None,
         }
     }
 
@@ -49,6 +96,12 @@ impl<'a> EditorWidget<'a> {
         self
     }
 
+    /// Highlight every find match rather than only the current one
+    pub fn highlight_all(mut self, highlight_all: bool) -> Self {
+        self.highlight_all = highlight_all;
+        self
+    }
+
     pub fn viewport_offset(mut self, offset: (usize, usize)) -> Self {
         self.viewport_offset = offset;
         self
@@ -75,93 +128,460 @@ impl<'a> EditorWidget<'a> {
         self
     }
 
-    fn calculate_line_number_width(&self) -> u16 {
-        let max_line = self.buffer.len_lines();
-        let width = max_line.to_string().len();
-        (width + 1).max(4) as u16
+    /// Wrap before this column when it is narrower than the view; 0 for none
+    pub fn wrap_column(mut self, column: usize) -> Self {
+        self.wrap_column = column;
+        self
     }
 
-    fn wrap_line(&self, line_text: &str, available_width: usize) -> Vec<String> {
-        // Fast path: no wrapping needed
-        if !self.word_wrap || available_width == 0 {
-            return vec![line_text.to_string()];
+    /// Draw spaces as "·" and the start of each tab as "→"
+    pub fn show_whitespace(mut self, show: bool) -> Self {
+        self.show_whitespace = show;
+        self
+    }
+
+    /// Paint the cursor as a highlighted cell. Turned off when the terminal's
+    /// own cursor is placed with `cursor_screen_position` instead.
+    pub fn paint_cursor(mut self, paint: bool) -> Self {
+        self.paint_cursor = paint;
+        self
+    }
+
+    /// Color lines by their diff marker
+    pub fn diff(mut self, diff: bool) -> Self {
+        self.diff = diff;
+        self
+    }
+
+    /// Shade text past this display column as too long
+    pub fn max_line_length(mut self, limit: Option<usize>) -> Self {
+        self.max_line_length = limit;
+        self
+    }
+
+    /// Color the text by its syntax tree
+    pub fn syntax_tree(mut self, tree: Option<Tree>) -> Self {
+        self.syntax_tree = tree;
+        self
+    }
+
+    /// Virtual text to draw after lines and in the sign column
+    pub fn annotations(mut self, annotations: &'a Annotations) -> Self {
+        self.annotations = Some(annotations);
+        self
+    }
+
+    /// Draw lines too long to lay out in rows of the view's width rather
+    /// than cut off
+    pub fn split_long_lines(mut self, split: bool) -> Self {
+        self.split_long_lines = split;
+        self
+    }
+
+    /// Reuse the rows of lines that haven't changed since the last frame
+    pub fn line_cache(mut self, cache: &'a LineCache) -> Self {
+        self.line_cache = Some(cache);
+        self
+    }
+
+    /// Color comments and strings by the language's rules when there is no
+    /// syntax tree. Needs the line cache, which remembers where block
+    /// comments run.
+    pub fn language(mut self, language: &'a Language) -> Self {
+        self.language = Some(language);
+        self
+    }
+
+    /// Hash of the settings that style every line, in a text area this wide
+    fn view_key(&self, width: usize) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (width, self.word_wrap, self.wrap_column, self.show_whitespace).hash(&mut hasher);
+        (self.shows_painted_cursor(), self.diff, self.max_line_length).hash(&mut hasher);
+        self.language.map(|language| &language.name).hash(&mut hasher);
+        self.syntax_tree
+            .as_ref()
+            .map(syntax_tree::tree_id)
+            .hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Shown find matches on a line as (index, start, end)
+    fn line_matches(&self, line_idx: usize) -> Vec<(usize, usize, usize)> {
+        let Some(matches) = self.find_matches else {
+            return Vec::new();
+        };
+        // Matches are in document order
+        let first = matches.partition_point(|m| m.start.line < line_idx);
+        matches[first..]
+            .iter()
+            .take_while(|m| m.start.line == line_idx)
+            .enumerate()
+            .map(|(offset, m)| (first + offset, m.start.column, m.end.column))
+            .filter(|(idx, _, _)| self.highlight_all || Some(*idx) == self.current_match_index)
+            .collect()
+    }
+
+    /// What the line cache compares to tell whether a line's rows still hold
+    fn line_inputs(&self, line_idx: usize, cursor_col: Option<usize>) -> LineInputs {
+        let selection = self
+            .cursor
+            .get_selection()
+            .filter(|(start, end)| (start.line..=end.line).contains(&line_idx))
+            .map(|(start, end)| {
+                let from = if start.line == line_idx { start.column } else { 0 };
+                let to = if end.line == line_idx { end.column } else { usize::MAX };
+                (from, to)
+            });
+        LineInputs {
+            cursor_column: cursor_col,
+            selection,
+            matches: self
+                .line_matches(line_idx)
+                .into_iter()
+                .map(|(idx, start, end)| (start, end, Some(idx) == self.current_match_index))
+                .collect(),
+            starts_in_comment: self.starts_in_comment(line_idx).unwrap_or(false),
         }
+    }
 
-        // Quick check if line might need wrapping (conservative estimate)
-        if line_text.len() <= available_width && !line_text.contains('\t') {
-            return vec![line_text.to_string()];
+    /// Whether a line starts inside a block comment, when lines are colored
+    /// by the language's rules rather than a syntax tree
+    fn starts_in_comment(&self, line_idx: usize) -> Option<bool> {
+        if self.syntax_tree.is_some() {
+            return None;
         }
+        let (language, cache) = (self.language?, self.line_cache?);
+        Some(cache.starts_in_comment(line_idx, |line, in_comment| {
+            language
+                .classify_line(&self.buffer.get_line_text(line), in_comment)
+                .1
+        }))
+    }
 
-        let mut wrapped_lines = Vec::new();
-        let mut current_line = String::with_capacity(available_width);
-        let mut current_width = 0;
+    /// Style of each character of a line from the syntax tree; empty without
+    /// one or when the line is too long to lay out
+    fn token_styles(&self, line_idx: usize) -> Vec<Style> {
+        if wrap::is_too_long(self.buffer, line_idx) {
+            return Vec::new();
+        }
+        let base = self.line_style(line_idx);
+        let Some(tree) = &self.syntax_tree else {
+            let (Some(language), Some(in_comment)) =
+                (self.language, self.starts_in_comment(line_idx))
+            else {
+                return Vec::new();
+            };
+            let text = self.buffer.get_line_text(line_idx);
+            let (kinds, _) = language.classify_line(&text, in_comment);
+            return kinds
+                .into_iter()
+                .map(|kind| match kind {
+                    Syntax::Comment => base.fg(highlight_color(Highlight::Comment)),
+                    Syntax::String => base.fg(highlight_color(Highlight::String)),
+                    Syntax::Code => base,
+                })
+                .collect();
+        };
+        let mut styles = vec![base; self.buffer.line(line_idx).len_chars()];
+        for (range, highlight) in syntax_tree::line_highlights(tree, self.buffer, line_idx) {
+            let color = highlight_color(highlight);
+            for style in styles.iter_mut().take(range.end).skip(range.start) {
+                *style = base.fg(color);
+            }
+        }
+        styles
+    }
 
-        for ch in line_text.chars() {
-            // Calculate actual display width for tabs
-            let char_width = if ch == '\t' {
-                // Tab width depends on current position
-                4 - (current_width % 4)
+    /// Index of the first character of a line past the length limit, with
+    /// tabs and wide characters counted by the columns they take up
+    fn overflow_column(&self, line_idx: usize) -> Option<usize> {
+        let limit = self.max_line_length?;
+        let mut width = 0;
+        for (col, ch) in self.buffer.line(line_idx).chars().enumerate() {
+            if width >= limit {
+                return Some(col);
+            }
+            width += if ch == '\t' {
+                4 - width % 4
             } else {
+                ch.width().unwrap_or(0)
+            };
+        }
+        None
+    }
+
+    /// Style a line's text starts from, before selection and matches
+    fn line_style(&self, line_idx: usize) -> Style {
+        if !self.diff {
+            return Style::default();
+        }
+        let line = self.buffer.get_line_text(line_idx);
+        match crate::diff::marker_color(&line) {
+            Some(color) => Style::default().fg(color),
+            None => Style::default(),
+        }
+    }
+
+    fn shows_painted_cursor(&self) -> bool {
+        self.focused && self.paint_cursor
+    }
+
+    fn gutter(&self) -> Gutter<'a> {
+        Gutter::new(
+            &config::get().editor.gutter,
+            self.buffer.len_lines(),
+            self.show_line_numbers,
+            self.annotations,
+        )
+    }
+
+    /// Gutter, text and scrollbar columns of the editor
+    fn split_areas(&self, inner: Rect) -> (Rect, Rect, Option<Rect>) {
+        let gutter_width = self.gutter().width();
+
+        let scrollbar_width =
+            if self.show_scrollbar && self.buffer.len_lines() > inner.height as usize {
                 1
+            } else {
+                0
             };
 
-            if current_width + char_width > available_width && !current_line.is_empty() {
-                wrapped_lines.push(current_line);
-                current_line = String::with_capacity(available_width);
-                current_width = 0;
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(gutter_width),
+                Constraint::Min(0),
+                Constraint::Length(scrollbar_width),
+            ])
+            .split(inner);
+
+        let scrollbar_area = if scrollbar_width > 0 {
+            Some(chunks[2])
+        } else {
+            None
+        };
+        (chunks[0], chunks[1], scrollbar_area)
+    }
+
+    /// Columns available for text in `area`, after the gutter and scrollbar,
+    /// or the narrower width lines are wrapped at
+    pub fn text_width(&self, area: Rect) -> usize {
+        self.row_width(self.split_areas(area).1.width as usize)
+    }
+
+    /// Width rows are laid out at in a text area this wide
+    fn row_width(&self, width: usize) -> usize {
+        if self.word_wrap {
+            self.wrap_width(width)
+        } else {
+            width
+        }
+    }
+
+    /// Width lines wrap at in a text area this wide
+    fn wrap_width(&self, width: usize) -> usize {
+        match self.wrap_column {
+            0 => width,
+            column => column.min(width),
+        }
+    }
+
+    /// The part of a line too long to lay out that is drawn, as rows of
+    /// (first character, text): the start of the line up to the limit, or
+    /// when split, rows of `width` that fit in `rows_left` around the cursor
+    fn long_line_rows(
+        &self,
+        line_idx: usize,
+        width: usize,
+        rows_left: usize,
+    ) -> Vec<(usize, String)> {
+        let line = self.buffer.line(line_idx);
+        let len = wrap::line_len(self.buffer, line_idx);
+        if !self.split_long_lines || width == 0 {
+            return vec![(0, line.slice(..wrap::long_line_limit().min(len)).to_string())];
+        }
+        let cursor = (self.cursor.position.line == line_idx).then_some(self.cursor.position.column);
+        wrap::split_rows(len, width, cursor, rows_left)
+            .map(|row| {
+                let start = row * width;
+                (start, line.slice(start..(start + width).min(len)).to_string())
+            })
+            .collect()
+    }
+
+    /// Mark the screen rows of lines that are cut off, right after their
+    /// text or at the right edge of the view when the text goes on past it
+    fn render_cut_off_notes(&self, rows: &[(usize, String)], area: Rect, buf: &mut Buffer) {
+        let note_width = wrap::CUT_OFF_NOTE.chars().count();
+        let pan = if self.word_wrap { 0 } else { self.viewport_offset.1 };
+        for (row, text) in rows {
+            let text_width = text.chars().fold(0, |x, ch| x + wrap::char_width(ch, x));
+            // A cell is left after the text for the cursor
+            let x = (text_width + 1)
+                .saturating_sub(pan)
+                .min((area.width as usize).saturating_sub(note_width));
+            buf.set_string(
+                area.x + x as u16,
+                area.y + *row as u16,
+                wrap::CUT_OFF_NOTE,
+                Style::default().fg(Color::Black).bg(Color::DarkGray),
+            );
+        }
+    }
+
+    /// Text drawn for a character other than a tab
+    fn shown_char(&self, ch: char) -> String {
+        if self.show_whitespace && ch == ' ' {
+            "·".to_string()
+        } else {
+            ch.to_string()
+        }
+    }
+
+    /// Screen cell of the cursor when it is visible in `area`
+    pub fn cursor_screen_position(&self, area: Rect) -> Option<(u16, u16)> {
+        let (_, content_area, _) = self.split_areas(area);
+        let cursor_line = self.cursor.position.line;
+        let start_line = self.viewport_offset.0;
+        if cursor_line < start_line {
+            return None;
+        }
+
+        let width = content_area.width as usize;
+        let mut row = 0;
+        let mut text_before_cursor = String::new();
+        for line_idx in start_line..=cursor_line {
+            if wrap::is_too_long(self.buffer, line_idx) {
+                let rows_left = (content_area.height as usize).saturating_sub(row);
+                let rows = self.long_line_rows(line_idx, self.row_width(width), rows_left);
+                if line_idx < cursor_line {
+                    row += rows.len();
+                    continue;
+                }
+                // Past the end of a cut off line the cursor is hidden
+                let column = self.cursor.position.column;
+                let (index, (start, text)) = rows
+                    .iter()
+                    .enumerate()
+                    .rfind(|(_, (start, _))| *start <= column)?;
+                if column - start > text.chars().count() {
+                    return None;
+                }
+                row += index;
+                text_before_cursor = text.chars().take(column - start).collect();
+                break;
+            }
+
+            let line_text = self.buffer.get_line_text(line_idx);
+            let portions = if self.word_wrap {
+                self.wrap_line(&line_text, self.wrap_width(width))
+            } else {
+                vec![line_text]
+            };
+
+            if line_idx < cursor_line {
+                row += portions.len();
+                continue;
             }
 
-            current_line.push(ch);
-            current_width += char_width;
+            // Find the wrapped portion holding the cursor; the end of the
+            // line belongs to the last one
+            let mut offset = 0;
+            for (wrap_idx, portion) in portions.iter().enumerate() {
+                let len = portion.chars().count();
+                let column = self.cursor.position.column;
+                if column < offset + len || wrap_idx == portions.len() - 1 {
+                    text_before_cursor = portion
+                        .chars()
+                        .take(column.saturating_sub(offset))
+                        .collect();
+                    break;
+                }
+                offset += len;
+                row += 1;
+            }
+        }
+
+        // Tabs expand to the next multiple of four, as when rendering
+        let mut visual_col = 0;
+        let mut x = 0;
+        for ch in text_before_cursor.chars() {
+            if ch == '\t' {
+                let spaces = 4 - (visual_col % 4);
+                visual_col += spaces;
+                x += spaces;
+            } else {
+                visual_col += 1;
+                x += ch.width().unwrap_or(0);
+            }
         }
 
-        if !current_line.is_empty() || wrapped_lines.is_empty() {
-            wrapped_lines.push(current_line);
+        // Without wrap the view may be panned sideways
+        if !self.word_wrap {
+            x = x.checked_sub(self.viewport_offset.1)?;
         }
 
-        wrapped_lines
+        if row >= content_area.height as usize || x >= width {
+            return None;
+        }
+        Some((content_area.x + x as u16, content_area.y + row as u16))
+    }
+
+    /// End-of-line notes, drawn after the last row of a line
+    fn end_of_line_spans(&self, line_idx: usize) -> Vec<Span<'static>> {
+        self.annotations
+            .map(|annotations| annotations.after_line(line_idx))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(text, style)| Span::styled(text, style))
+            .collect()
     }
 
+    fn wrap_line(&self, line_text: &str, available_width: usize) -> Vec<String> {
+        // Fast path: no wrapping needed
+        if !self.word_wrap || available_width == 0 {
+            return vec![line_text.to_string()];
+        }
+
+        // Quick check if line might need wrapping (conservative estimate)
+        if line_text.len() <= available_width && !line_text.contains('\t') {
+            return vec![line_text.to_string()];
+        }
+
+        crate::wrap::wrap_line(line_text, available_width)
+    }
+
+    /// Spans of one row of a line: the part starting at character
+    /// `char_offset`, which `ends_line` when nothing of the line follows
     fn render_line_portion(
         &self,
         line_idx: usize,
         line_portion: &str,
         cursor_col: Option<usize>,
-        wrap_idx: usize,
-        all_wrapped_lines: &[String],
+        char_offset: usize,
+        ends_line: bool,
     ) -> Vec<Span<'static>> {
         let mut spans = Vec::new();
 
-        // Calculate the character offset for this wrapped line portion
-        let mut char_offset = 0;
-        for line in all_wrapped_lines.iter().take(wrap_idx) {
-            char_offset += line.chars().count();
-        }
-
         // Get selection range if any
         let selection = self.cursor.get_selection();
 
         // Check for find matches on this line
-        let line_matches = if let Some(matches) = self.find_matches {
-            matches
-                .iter()
-                .enumerate()
-                .filter_map(|(idx, m)| {
-                    if m.start.line == line_idx {
-                        Some((idx, m.start.column, m.end.column))
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<_>>()
-        } else {
-            Vec::new()
-        };
+        let line_matches = self.line_matches(line_idx);
 
+        let line_style = self.line_style(line_idx);
+        let token_styles = self.token_styles(line_idx);
+        let overflow = self.overflow_column(line_idx);
         let mut visual_col = 0; // Track visual column position
         for (col, ch) in line_portion.chars().enumerate() {
             let actual_col = char_offset + col;
-            let mut style = Style::default();
+            let mut style = token_styles.get(actual_col).copied().unwrap_or(line_style);
+            if overflow.is_some_and(|start| actual_col >= start) {
+                style = style.bg(LONG_LINE_BACKGROUND);
+            }
+            if self.show_whitespace && (ch == ' ' || ch == '\t') {
+                style = style.fg(WHITESPACE_COLOR);
+            }
 
             // Check if this character is within the selection
             let is_selected = if let Some((start, end)) = selection {
@@ -179,7 +599,7 @@ impl<'a> EditorWidget<'a> {
                 .unwrap_or(false);
 
             // Handle cursor positioning
-            let is_cursor_here = self.focused && cursor_col == Some(actual_col);
+            let is_cursor_here = self.shows_painted_cursor() && cursor_col == Some(actual_col);
 
             if is_selected {
                 // Selected text: white text on blue background
@@ -205,19 +625,20 @@ impl<'a> EditorWidget<'a> {
                     if is_cursor_here && i == 0 {
                         tab_style = tab_style.bg(Color::Rgb(100, 100, 100)).fg(Color::White);
                     }
-                    spans.push(Span::styled(" ", tab_style));
+                    let symbol = if self.show_whitespace && i == 0 { "→" } else { " " };
+                    spans.push(Span::styled(symbol, tab_style));
                 }
                 visual_col += spaces_to_add;
             } else {
-                spans.push(Span::styled(ch.to_string(), style));
+                spans.push(Span::styled(self.shown_char(ch), style));
                 visual_col += 1;
             }
         }
 
         // Handle cursor at end of line portion (only for the last wrapped line)
-        if wrap_idx == all_wrapped_lines.len() - 1 {
+        if ends_line {
             let line_end_col = char_offset + line_portion.chars().count();
-            if self.focused && cursor_col == Some(line_end_col) {
+            if self.shows_painted_cursor() && cursor_col == Some(line_end_col) {
                 let is_cursor_selected = if let Some((start, end)) = selection {
                     self.is_position_selected(Position::new(line_idx, line_end_col), start, end)
                 } else {
@@ -234,7 +655,7 @@ impl<'a> EditorWidget<'a> {
         }
 
         // Handle empty line portions with cursor
-        if spans.is_empty() && self.focused && cursor_col == Some(char_offset) {
+        if spans.is_empty() && self.shows_painted_cursor() && cursor_col == Some(char_offset) {
             spans.push(Span::styled(
                 " ",
                 Style::default().bg(Color::Rgb(100, 100, 100)),
@@ -252,25 +673,20 @@ impl<'a> EditorWidget<'a> {
         let selection = self.cursor.get_selection();
 
         // Check for find matches on this line
-        let line_matches = if let Some(matches) = self.find_matches {
-            matches
-                .iter()
-                .enumerate()
-                .filter_map(|(idx, m)| {
-                    if m.start.line == line_idx {
-                        Some((idx, m.start.column, m.end.column))
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<_>>()
-        } else {
-            Vec::new()
-        };
+        let line_matches = self.line_matches(line_idx);
 
+        let line_style = self.line_style(line_idx);
+        let token_styles = self.token_styles(line_idx);
+        let overflow = self.overflow_column(line_idx);
         let mut visual_col = 0; // Track visual column position
         for (col, ch) in line_text.chars().enumerate() {
-            let mut style = Style::default();
+            let mut style = token_styles.get(col).copied().unwrap_or(line_style);
+            if overflow.is_some_and(|start| col >= start) {
+                style = style.bg(LONG_LINE_BACKGROUND);
+            }
+            if self.show_whitespace && (ch == ' ' || ch == '\t') {
+                style = style.fg(WHITESPACE_COLOR);
+            }
 
             // Check if this character is within the selection
             let is_selected = if let Some((start, end)) = selection {
@@ -288,7 +704,7 @@ impl<'a> EditorWidget<'a> {
                 .unwrap_or(false);
 
             // Handle cursor positioning
-            let is_cursor_here = self.focused && cursor_col == Some(col);
+            let is_cursor_here = self.shows_painted_cursor() && cursor_col == Some(col);
 
             if is_selected {
                 // Selected text: white text on blue background
@@ -314,17 +730,18 @@ impl<'a> EditorWidget<'a> {
                     if is_cursor_here && i == 0 {
                         tab_style = tab_style.bg(Color::Rgb(100, 100, 100)).fg(Color::White);
                     }
-                    spans.push(Span::styled(" ", tab_style));
+                    let symbol = if self.show_whitespace && i == 0 { "→" } else { " " };
+                    spans.push(Span::styled(symbol, tab_style));
                 }
                 visual_col += spaces_to_add;
             } else {
-                spans.push(Span::styled(ch.to_string(), style));
+                spans.push(Span::styled(self.shown_char(ch), style));
                 visual_col += 1;
             }
         }
 
         // Handle cursor at end of line
-        if self.focused && cursor_col == Some(line_text.len()) {
+        if self.shows_painted_cursor() && cursor_col == Some(line_text.len()) {
             let is_cursor_selected = if let Some((start, end)) = selection {
                 self.is_position_selected(Position::new(line_idx, line_text.len()), start, end)
             } else {
@@ -340,7 +757,7 @@ impl<'a> EditorWidget<'a> {
         }
 
         // Handle empty lines with cursor
-        if spans.is_empty() && self.focused && cursor_col == Some(0) {
+        if spans.is_empty() && self.shows_painted_cursor() && cursor_col == Some(0) {
             spans.push(Span::styled(
                 " ",
                 Style::default().bg(Color::Rgb(100, 100, 100)),
@@ -350,6 +767,35 @@ impl<'a> EditorWidget<'a> {
         spans
     }
 
+    /// Screen rows of a line that fits the view, wrapped at `width` when
+    /// wrapping, without its end-of-line notes
+    fn line_rows(
+        &self,
+        line_idx: usize,
+        cursor_col: Option<usize>,
+        width: usize,
+    ) -> Vec<Line<'static>> {
+        if !self.word_wrap {
+            return vec![Line::from(self.render_line(line_idx, cursor_col))];
+        }
+        let line_text = self.buffer.get_line_text(line_idx);
+        let wrapped_lines = self.wrap_line(&line_text, self.wrap_width(width));
+        let mut char_offset = 0;
+        let mut rows = Vec::new();
+        for (wrap_idx, wrapped_line) in wrapped_lines.iter().enumerate() {
+            let ends_line = wrap_idx == wrapped_lines.len() - 1;
+            rows.push(Line::from(self.render_line_portion(
+                line_idx,
+                wrapped_line,
+                cursor_col,
+                char_offset,
+                ends_line,
+            )));
+            char_offset += wrapped_line.chars().count();
+        }
+        rows
+    }
+
     fn is_position_selected(&self, pos: Position, start: Position, end: Position) -> bool {
         if pos.line > end.line || pos.line < start.line {
             return false;
@@ -373,107 +819,80 @@ impl<'a> EditorWidget<'a> {
 
 impl<'a> Widget for EditorWidget<'a> {
     fn render(self, area: Rect, buf: &mut Buffer) {
+        let _span = crate::perf::span("render editor");
         let block = Block::default().borders(Borders::NONE);
 
         let inner = block.inner(area);
         block.render(area, buf);
 
-        let line_number_width = if self.show_line_numbers {
-            self.calculate_line_number_width()
-        } else {
-            0
-        };
-
-        let scrollbar_width =
-            if self.show_scrollbar && self.buffer.len_lines() > inner.height as usize {
-                1
-            } else {
-                0
-            };
-
-        let chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Length(line_number_width),
-                Constraint::Min(0),
-                Constraint::Length(scrollbar_width),
-            ])
-            .split(inner);
-
-        let line_numbers_area = chunks[0];
-        let content_area = chunks[1];
-        let scrollbar_area = if scrollbar_width > 0 {
-            Some(chunks[2])
-        } else {
-            None
-        };
+        let (gutter_area, content_area, scrollbar_area) = self.split_areas(inner);
+        let gutter = self.gutter();
 
         let visible_lines = content_area.height as usize;
         let start_line = self.viewport_offset.0;
         let end_line = (start_line + visible_lines).min(self.buffer.len_lines());
+        if let Some(cache) = self.line_cache {
+            cache.start_frame(self.buffer, self.view_key(content_area.width as usize));
+        }
 
         let mut display_lines = Vec::new();
-        let mut line_number_lines = Vec::new();
+        let mut gutter_lines = Vec::new();
+        let mut cut_off_rows = Vec::new();
 
         for line_idx in start_line..end_line {
-            let line_text = self.buffer.get_line_text(line_idx);
             let cursor_col = if line_idx == self.cursor.position.line {
                 Some(self.cursor.position.column)
             } else {
                 None
             };
 
-            if self.word_wrap {
-                let wrapped_lines = self.wrap_line(&line_text, content_area.width as usize);
-                for (wrap_idx, wrapped_line) in wrapped_lines.iter().enumerate() {
-                    // Render the wrapped line portion
-                    let spans = self.render_line_portion(
-                        line_idx,
-                        wrapped_line,
-                        cursor_col,
-                        wrap_idx,
-                        &wrapped_lines,
-                    );
+            if wrap::is_too_long(self.buffer, line_idx) {
+                let width = self.row_width(content_area.width as usize);
+                let rows_left = visible_lines.saturating_sub(display_lines.len());
+                let len = wrap::line_len(self.buffer, line_idx);
+                for (start, text) in self.long_line_rows(line_idx, width, rows_left) {
+                    let ends_line = self.split_long_lines && start + text.chars().count() == len;
+                    let mut spans =
+                        self.render_line_portion(line_idx, &text, cursor_col, start, ends_line);
+                    if !self.split_long_lines {
+                        cut_off_rows.push((display_lines.len(), text));
+                    }
+                    if ends_line {
+                        spans.extend(self.end_of_line_spans(line_idx));
+                    }
                     display_lines.push(Line::from(spans));
+                    gutter_lines.push(gutter.row(line_idx, start == 0));
+                }
+                continue;
+            }
 
-                    // Line number: show actual line number for first wrapped line, "↳" for continuation lines
-                    if self.show_line_numbers && line_number_width > 0 {
-                        let line_num_text = if wrap_idx == 0 {
-                            format!(
-                                "{:>width$} ",
-                                line_idx + 1,
-                                width = (line_number_width - 1) as usize
-                            )
-                        } else {
-                            format!("{:>width$} ", "↳", width = (line_number_width - 1) as usize)
-                        };
-                        line_number_lines.push(Line::from(Span::styled(
-                            line_num_text,
-                            Style::default().fg(Color::DarkGray),
-                        )));
-                    }
+            let width = content_area.width as usize;
+            let rows = match self.line_cache {
+                Some(cache) => {
+                    let inputs = self.line_inputs(line_idx, cursor_col);
+                    cache.get(line_idx, &inputs).unwrap_or_else(|| {
+                        let rows = self.line_rows(line_idx, cursor_col, width);
+                        cache.insert(line_idx, inputs, rows.clone());
+                        rows
+                    })
                 }
-            } else {
-                let spans = self.render_line(line_idx, cursor_col);
-                display_lines.push(Line::from(spans));
-
-                if self.show_line_numbers && line_number_width > 0 {
-                    let line_num = format!(
-                        "{:>width$} ",
-                        line_idx + 1,
-                        width = (line_number_width - 1) as usize
-                    );
-                    line_number_lines.push(Line::from(Span::styled(
-                        line_num,
-                        Style::default().fg(Color::DarkGray),
-                    )));
+                None => self.line_rows(line_idx, cursor_col, width),
+            };
+            let row_count = rows.len();
+            for (row, mut line) in rows.into_iter().enumerate() {
+                if row + 1 == row_count {
+                    line.spans.extend(self.end_of_line_spans(line_idx));
                 }
+                display_lines.push(line);
+                gutter_lines.push(gutter.row(line_idx, row == 0));
             }
         }
+        if let Some(cache) = self.line_cache {
+            cache.keep(start_line..end_line);
+        }
 
-        if self.show_line_numbers && line_number_width > 0 {
-            let line_numbers_widget = Paragraph::new(line_number_lines);
-            line_numbers_widget.render(line_numbers_area, buf);
+        if gutter_area.width > 0 {
+            Paragraph::new(gutter_lines).render(gutter_area, buf);
         }
 
         if display_lines.is_empty() && self.buffer.len_lines() == 0 {
@@ -483,7 +902,7 @@ impl<'a> Widget for EditorWidget<'a> {
                 None
             };
 
-            let spans = if self.focused && cursor_col == Some(0) {
+            let spans = if self.shows_painted_cursor() && cursor_col == Some(0) {
                 vec![Span::styled(
                     " ",
                     Style::default().bg(Color::Rgb(60, 60, 60)),
@@ -494,8 +913,12 @@ impl<'a> Widget for EditorWidget<'a> {
             display_lines.push(Line::from(spans));
         }
 
-        let content = Paragraph::new(display_lines);
+        let mut content = Paragraph::new(display_lines);
+        if !self.word_wrap {
+            content = content.scroll((0, self.viewport_offset.1 as u16));
+        }
         content.render(content_area, buf);
+        self.render_cut_off_notes(&cut_off_rows, content_area, buf);
 
         // Render scrollbar if needed
         if let Some(scrollbar_area) = scrollbar_area {
@@ -0,0 +1,242 @@
+//! Encoding and decoding of selected text for the tab menu's Transform
+//! submenu: base64, URL percent-encoding, JSON string escapes and HTML
+//! entities. Decoding fails on text that isn't encoded that way, leaving
+//! the selection as it was.
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transform {
+    Base64Encode,
+    Base64Decode,
+    UrlEncode,
+    UrlDecode,
+    JsonEscape,
+    JsonUnescape,
+    HtmlEscape,
+    HtmlUnescape,
+}
+
+impl Transform {
+    /// The submenu action of each transform, in menu order
+    pub const ALL: [(Transform, &'static str, &'static str); 8] = [
+        (Transform::Base64Encode, "Base64 Encode", "encode_base64"),
+        (Transform::Base64Decode, "Base64 Decode", "decode_base64"),
+        (Transform::UrlEncode, "URL Encode", "encode_url"),
+        (Transform::UrlDecode, "URL Decode", "decode_url"),
+        (Transform::JsonEscape, "JSON Escape", "encode_json"),
+        (Transform::JsonUnescape, "JSON Unescape", "decode_json"),
+        (Transform::HtmlEscape, "HTML Escape", "encode_html"),
+        (Transform::HtmlUnescape, "HTML Unescape", "decode_html"),
+    ];
+
+    pub fn from_action(action: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|(_, _, name)| *name == action)
+            .map(|(transform, _, _)| *transform)
+    }
+
+    pub fn apply(self, text: &str) -> Result<String, String> {
+        match self {
+            Transform::Base64Encode => Ok(base64_encode(text.as_bytes())),
+            Transform::Base64Decode => base64_decode(text).and_then(utf8),
+            Transform::UrlEncode => Ok(url_encode(text)),
+            Transform::UrlDecode => url_decode(text).and_then(utf8),
+            Transform::JsonEscape => {
+                let quoted = serde_json::to_string(text).map_err(|e| e.to_string())?;
+                Ok(quoted[1..quoted.len() - 1].to_string())
+            }
+            Transform::JsonUnescape => serde_json::from_str(&format!("\"{}\"", text))
+                .map_err(|_| "Not a JSON-escaped string".to_string()),
+            Transform::HtmlEscape => Ok(html_escape(text)),
+            Transform::HtmlUnescape => Ok(html_unescape(text)),
+        }
+    }
+}
+
+fn utf8(bytes: Vec<u8>) -> Result<String, String> {
+    String::from_utf8(bytes).map_err(|_| "The decoded bytes aren't UTF-8 text".to_string())
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0u32, |group, (i, &byte)| {
+            group | ((byte as u32) << (16 - 8 * i))
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(BASE64[((group >> (18 - 6 * i)) & 63) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+/// Decode standard or URL-safe base64, with or without padding. Line
+/// breaks and spaces, as in wrapped output, are skipped.
+fn base64_decode(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(text.len() / 4 * 3);
+    let (mut group, mut bits) = (0u32, 0);
+    for ch in text
+        .trim_end_matches(|ch: char| ch == '=' || ch.is_whitespace())
+        .chars()
+    {
+        let value = match ch {
+            'A'..='Z' => ch as u32 - 'A' as u32,
+            'a'..='z' => ch as u32 - 'a' as u32 + 26,
+            '0'..='9' => ch as u32 - '0' as u32 + 52,
+            '+' | '-' => 62,
+            '/' | '_' => 63,
+            _ if ch.is_whitespace() => continue,
+            _ => return Err(format!("'{}' isn't a base64 character", ch)),
+        };
+        group = (group << 6) | value;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((group >> bits) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+/// Percent-encode everything but the unreserved characters of RFC 3986
+fn url_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Decode `%XX` escapes, and `+` as a space the way query strings write it
+fn url_decode(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, after)) = rest.split_first() {
+        rest = after;
+        match byte {
+            b'%' => {
+                let hex = rest
+                    .get(..2)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| "A '%' isn't followed by two hex digits".to_string())?;
+                bytes.push(hex);
+                rest = &rest[2..];
+            }
+            b'+' => bytes.push(b' '),
+            _ => bytes.push(byte),
+        }
+    }
+    Ok(bytes)
+}
+
+fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Entities that aren't numeric and are understood
+const NAMED_ENTITIES: [(&str, char); 7] = [
+    ("amp", '&'),
+    ("lt", '<'),
+    ("gt", '>'),
+    ("quot", '"'),
+    ("apos", '\''),
+    ("nbsp", '\u{a0}'),
+    ("copy", '©'),
+];
+
+/// Replace named and numeric entities; anything else after a `&` is kept
+fn html_unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        unescaped.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let entity = rest[1..].find(';').map(|semi| &rest[1..semi + 1]);
+        let decoded = entity.and_then(|entity| {
+            if let Some(number) = entity.strip_prefix('#') {
+                let code = match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => number.parse().ok(),
+                };
+                code.and_then(char::from_u32)
+            } else {
+                NAMED_ENTITIES
+                    .iter()
+                    .find(|(name, _)| *name == entity)
+                    .map(|(_, ch)| *ch)
+            }
+        });
+        match (entity, decoded) {
+            (Some(entity), Some(ch)) => {
+                unescaped.push(ch);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                unescaped.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_and_decodes() {
+        for (text, base64) in [("", ""), ("f", "Zg=="), ("fo", "Zm8="), ("foo", "Zm9v")] {
+            assert_eq!(Transform::Base64Encode.apply(text).unwrap(), base64);
+            assert_eq!(Transform::Base64Decode.apply(base64).unwrap(), text);
+        }
+        assert_eq!(Transform::Base64Decode.apply("w6k\n=").unwrap(), "é");
+        assert!(Transform::Base64Decode.apply("Zm9v!").is_err());
+
+        assert_eq!(
+            Transform::UrlEncode.apply("a b/é").unwrap(),
+            "a%20b%2F%C3%A9"
+        );
+        assert_eq!(Transform::UrlDecode.apply("a+b%2F%C3%A9").unwrap(), "a b/é");
+        assert!(Transform::UrlDecode.apply("100%").is_err());
+
+        let json = Transform::JsonEscape.apply("say \"hi\"\n\t").unwrap();
+        assert_eq!(json, r#"say \"hi\"\n\t"#);
+        assert_eq!(
+            Transform::JsonUnescape.apply(&json).unwrap(),
+            "say \"hi\"\n\t"
+        );
+        assert!(Transform::JsonUnescape.apply(r#"a "b"#).is_err());
+
+        let html = Transform::HtmlEscape.apply("<a href=\"x\">&</a>").unwrap();
+        assert_eq!(html, "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;");
+        assert_eq!(
+            Transform::HtmlUnescape
+                .apply("&lt;b&gt; &#233;&#x263A; &bogus; & done")
+                .unwrap(),
+            "<b> é☺ &bogus; & done"
+        );
+    }
+}
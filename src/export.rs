@@ -0,0 +1,130 @@
+//! Export of a buffer for sharing: HTML or ANSI-colored text with line
+//! numbers and the line coloring the editor draws.
+
+use crate::diff;
+use ratatui::style::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Html,
+    Ansi,
+}
+
+impl Format {
+    /// HTML for `.html` and `.htm` targets, ANSI text otherwise
+    pub fn for_target(target: &str) -> Self {
+        let lower = target.to_lowercase();
+        if lower.ends_with(".html") || lower.ends_with(".htm") {
+            Format::Html
+        } else {
+            Format::Ansi
+        }
+    }
+}
+
+/// Render `text` with numbered lines. `diff` colors added and removed lines
+/// the way diff tabs show them.
+pub fn render(text: &str, title: &str, format: Format, diff: bool) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let number_width = lines.len().max(1).to_string().len();
+    let color = |line: &str| if diff { diff::marker_color(line) } else { None };
+
+    match format {
+        Format::Html => {
+            let mut out = format!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+                 <title>{}</title>\n<style>\n\
+                 pre {{ background: #1e1e1e; color: #d4d4d4; padding: 1em; tab-size: 4; }}\n\
+                 .ln {{ color: #6e6e6e; user-select: none; }}\n\
+                 </style>\n</head>\n<body>\n<pre>",
+                escape_html(title)
+            );
+            for (index, line) in lines.iter().enumerate() {
+                out.push_str(&format!(
+                    "<span class=\"ln\">{:>width$} </span>",
+                    index + 1,
+                    width = number_width
+                ));
+                match color(line).and_then(html_color) {
+                    Some(css) => out.push_str(&format!(
+                        "<span style=\"color: {}\">{}</span>",
+                        css,
+                        escape_html(line)
+                    )),
+                    None => out.push_str(&escape_html(line)),
+                }
+                out.push('\n');
+            }
+            out.push_str("</pre>\n</body>\n</html>\n");
+            out
+        }
+        Format::Ansi => {
+            let mut out = String::new();
+            for (index, line) in lines.iter().enumerate() {
+                out.push_str(&format!(
+                    "\x1b[2m{:>width$} │\x1b[0m ",
+                    index + 1,
+                    width = number_width
+                ));
+                match color(line).and_then(ansi_code) {
+                    Some(code) => out.push_str(&format!("\x1b[{}m{}\x1b[0m", code, line)),
+                    None => out.push_str(line),
+                }
+                out.push('\n');
+            }
+            out
+        }
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn html_color(color: Color) -> Option<&'static str> {
+    match color {
+        Color::Green => Some("#6a9955"),
+        Color::Red => Some("#f14c4c"),
+        Color::Cyan => Some("#29b8db"),
+        _ => None,
+    }
+}
+
+fn ansi_code(color: Color) -> Option<u8> {
+    match color {
+        Color::Green => Some(32),
+        Color::Red => Some(31),
+        Color::Cyan => Some(36),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_is_escaped_and_numbered() {
+        let html = render("a < b\n", "x", Format::Html, false);
+        assert!(html.contains("<span class=\"ln\">1 </span>a &lt; b\n"));
+    }
+
+    #[test]
+    fn ansi_colors_diff_lines() {
+        let text: String = (1..=10).map(|n| format!("+{}\n", n)).collect();
+        let ansi = render(&text, "x", Format::Ansi, true);
+        assert!(ansi.starts_with("\x1b[2m 1 │\x1b[0m \x1b[32m+1\x1b[0m\n"));
+        assert_eq!(Format::for_target("out.HTML"), Format::Html);
+        assert_eq!(Format::for_target("-"), Format::Ansi);
+    }
+}
@@ -1,9 +1,40 @@
+use crate::config::{self, IconSet};
+use ratatui::buffer::Buffer;
+use ratatui::style::Style;
 use std::path::Path;
+use std::sync::OnceLock;
+use unicode_width::UnicodeWidthStr;
 
-/// Get the appropriate emoji icon for a file based on its extension or name
+/// The icon set in use, with `auto` resolved for the current terminal
+pub fn icon_set() -> IconSet {
+    static RESOLVED: OnceLock<IconSet> = OnceLock::new();
+    *RESOLVED.get_or_init(|| match config::get().icons.set {
+        IconSet::Auto => detect_icon_set(),
+        set => set,
+    })
+}
+
+/// Emoji unless the terminal clearly can't draw them. Nerd Fonts can't be
+/// detected, so they are only used when chosen in the config.
+fn detect_icon_set() -> IconSet {
+    let term = std::env::var("TERM").unwrap_or_default();
+    let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+        .unwrap_or_default()
+        .to_lowercase();
+
+    if term == "linux" || term == "dumb" || !(locale.contains("utf-8") || locale.contains("utf8")) {
+        IconSet::Ascii
+    } else {
+        IconSet::Emoji
+    }
+}
+
+/// Get the icon for a file based on its name or extension, honoring config overrides
 pub fn get_file_icon(path: &Path) -> &'static str {
     if path.is_dir() {
-        return "📁";
+        return get_directory_icon(false);
     }
 
     // Get file extension
@@ -20,8 +51,25 @@ pub fn get_file_icon(path: &Path) -> &'static str {
         .unwrap_or("")
         .to_lowercase();
 
+    let icons = &config::get().icons;
+    if let Some(icon) = icons
+        .names
+        .get(&file_name)
+        .or_else(|| icons.extensions.get(&extension))
+    {
+        return icon.as_str();
+    }
+
+    match icon_set() {
+        IconSet::Nerd => nerd_icon(&file_name, &extension),
+        IconSet::Ascii => ascii_icon(&extension),
+        IconSet::Emoji | IconSet::Auto => emoji_icon(&file_name, &extension),
+    }
+}
+
+fn emoji_icon(file_name: &str, extension: &str) -> &'static str {
     // Check for special file names first
-    match file_name.as_str() {
+    match file_name {
         "readme.md" | "readme.txt" | "readme" => "📖",
         "license" | "license.txt" | "license.md" => "📄",
         "dockerfile" => "🐳",
@@ -37,7 +85,7 @@ pub fn get_file_icon(path: &Path) -> &'static str {
         ".env" | ".env.local" | ".env.example" => "⚙️",
         _ => {
             // Check by file extension
-            match extension.as_str() {
+            match extension {
                 // Programming languages
                 "rs" => "🦀",
                 "js" | "mjs" => "💛",
@@ -128,41 +176,160 @@ pub fn get_file_icon(path: &Path) -> &'static str {
     }
 }
 
-/// Get directory icon (can be used for expanded/collapsed states)
-pub fn get_directory_icon(is_expanded: bool) -> &'static str {
-    if is_expanded {
-        "📂"
-    } else {
-        "📁"
-    }
-}
+fn nerd_icon(file_name: &str, extension: &str) -> &'static str {
+    match file_name {
+        "readme.md" | "readme.txt" | "readme" => "\u{f405}",
+        "dockerfile" => "\u{f308}",
+        "makefile" => "\u{e779}",
+        "cargo.toml" | "cargo.lock" => "\u{e7a8}",
+        "package.json" | "package-lock.json" => "\u{e71e}",
+        ".gitignore" | ".gitattributes" | ".gitmodules" => "\u{e702}",
+        ".env" | ".env.local" | ".env.example" => "\u{e615}",
+        _ => match extension {
+            // Programming languages
+            "rs" => "\u{e7a8}",
+            "js" | "mjs" => "\u{e74e}",
+            "ts" => "\u{e628}",
+            "jsx" | "tsx" => "\u{e7ba}",
+            "py" => "\u{e73c}",
+            "go" => "\u{e626}",
+            "java" => "\u{e738}",
+            "kt" | "kts" => "\u{e634}",
+            "swift" => "\u{e755}",
+            "cpp" | "cc" | "cxx" | "c++" => "\u{e61d}",
+            "c" => "\u{e61e}",
+            "h" | "hpp" => "\u{f0fd}",
+            "php" => "\u{e73d}",
+            "rb" => "\u{e739}",
+            "lua" => "\u{e620}",
+            "dart" => "\u{e798}",
+            "scala" => "\u{e737}",
+            "clj" | "cljs" => "\u{e768}",
+            "hs" => "\u{e777}",
+            "elm" => "\u{e62c}",
+            "ex" | "exs" => "\u{e62d}",
+            "erl" => "\u{e7b1}",
 
-/// Get a simple file type indicator (non-emoji version for contexts that don't support emoji)
-#[allow(dead_code)]
-pub fn get_file_type_indicator(path: &Path) -> &'static str {
-    if path.is_dir() {
-        return "D";
-    }
+            // Web technologies
+            "html" | "htm" => "\u{e736}",
+            "css" => "\u{e749}",
+            "scss" | "sass" => "\u{e603}",
 
-    let extension = path
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .unwrap_or("")
-        .to_lowercase();
+            // Data formats and config
+            "json" => "\u{e60b}",
+            "yaml" | "yml" | "toml" | "ini" | "cfg" | "conf" | "xml" => "\u{e615}",
+            "sql" => "\u{f1c0}",
 
-    match extension.as_str() {
+            // Documents
+            "md" | "markdown" => "\u{e73e}",
+            "txt" | "rtf" | "log" | "csv" => "\u{f15c}",
+            "pdf" => "\u{f1c1}",
+            "doc" | "docx" => "\u{f1c2}",
+            "xls" | "xlsx" => "\u{f1c3}",
+            "ppt" | "pptx" => "\u{f1c4}",
+
+            // Media and archives
+            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "tiff" | "svg" | "ico" | "webp" => "\u{f1c5}",
+            "mp3" | "wav" | "flac" | "aac" => "\u{f1c7}",
+            "mp4" | "avi" | "mkv" | "mov" | "wmv" => "\u{f1c8}",
+            "zip" | "rar" | "7z" | "tar" | "gz" | "xz" | "bz2" => "\u{f1c6}",
+
+            // Scripts
+            "sh" | "bash" | "zsh" | "fish" | "bat" | "cmd" | "ps1" => "\u{f489}",
+
+            // Other
+            "lock" => "\u{f023}",
+            "key" | "pem" | "crt" | "cert" => "\u{f084}",
+
+            _ => "\u{f15b}",
+        },
+    }
+}
+
+/// Single-letter type indicators for terminals without emoji or Nerd Fonts
+fn ascii_icon(extension: &str) -> &'static str {
+    match extension {
         "rs" => "R",
         "js" | "mjs" => "J",
         "ts" => "T",
         "py" => "P",
         "go" => "G",
         "java" => "J",
+        "c" | "h" | "cpp" | "hpp" => "C",
         "html" | "htm" => "H",
         "css" => "C",
         "md" | "markdown" => "M",
         "json" => "N",
         "xml" => "X",
         "txt" => "T",
+        "sh" | "bash" | "zsh" | "fish" => "$",
         _ => "F",
     }
 }
+
+/// Get directory icon (can be used for expanded/collapsed states)
+pub fn get_directory_icon(is_expanded: bool) -> &'static str {
+    match (icon_set(), is_expanded) {
+        (IconSet::Nerd, true) => "\u{f07c}",
+        (IconSet::Nerd, false) => "\u{f07b}",
+        (IconSet::Ascii, true) => "-",
+        (IconSet::Ascii, false) => "+",
+        (_, true) => "📂",
+        (_, false) => "📁",
+    }
+}
+
+/// Columns the terminal draws an icon in: `[icons] width`, or the set's
+/// own width, or more when the icon's text measures wider
+fn icon_width(icon: &str) -> usize {
+    let declared = match (config::get().icons.width, icon_set()) {
+        (0, IconSet::Emoji | IconSet::Auto) => 2,
+        (0, _) => 1,
+        (width, _) => width,
+    };
+    declared.max(icon.width())
+}
+
+/// Columns the tree and picker keep for icons, so every name starts in the
+/// same one
+pub fn icon_columns() -> u16 {
+    icon_width("").max(2) as u16
+}
+
+/// Draw `icon` at the start of an icon column, padding the rest with
+/// spaces. Cells a glyph covers beyond what its text measures (emoji with a
+/// variation selector, which most terminals draw two wide) are skipped, so
+/// the padding doesn't overwrite half of it. Nothing is drawn when the
+/// column doesn't fit before `limit`.
+pub fn draw_icon(buf: &mut Buffer, x: u16, y: u16, limit: u16, icon: &str, style: Style) {
+    let columns = icon_columns();
+    if x + columns > limit {
+        return;
+    }
+    // Overrides too wide for the column lose their end
+    let mut icon = icon;
+    while icon.width() > columns as usize {
+        let mut chars = icon.chars();
+        chars.next_back();
+        icon = chars.as_str();
+    }
+    let drawn = icon_width(icon).min(columns as usize) as u16;
+    let measured = icon.width() as u16;
+    buf[(x, y)].set_symbol(icon).set_style(style);
+    for offset in 1..columns {
+        let cell = &mut buf[(x + offset, y)];
+        if offset >= drawn {
+            cell.set_symbol(" ").set_style(style);
+        } else if offset >= measured {
+            cell.set_skip(true);
+        }
+    }
+}
+
+/// Marker drawn after the name of a symlink
+pub fn symlink_suffix() -> &'static str {
+    match icon_set() {
+        IconSet::Ascii => "@",
+        _ => " ↗",
+    }
+}
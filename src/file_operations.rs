@@ -1,6 +1,13 @@
 use crate::app::App;
+use crate::config;
+use crate::export;
+use crate::line_ops::align_on;
+use crate::menu::MenuState;
+use crate::messages::Severity;
+use crate::scratch;
 use crate::tab::Tab;
-use std::path::PathBuf;
+use crate::ui::{Modal, ModalAction, ModalButton, ModalKind};
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 impl App {
@@ -10,100 +17,407 @@ impl App {
                 Tab::Editor { path, .. } => {
                     if path.is_none() {
                         // No path set, show save dialog
-                        let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-                        self.menu_system.open_input_dialog(
-                            "Save as:".to_string(),
-                            "save_file".to_string(),
-                            current_dir,
-                        );
+                        self.open_save_as_dialog("save_file");
                         return;
                     }
                 }
                 Tab::Terminal { .. } => {
-                    // Terminal tabs cannot be saved
+                    self.notify(
+                        Severity::Warning,
+                        "Terminal tabs cannot be saved".to_string(),
+                        Duration::from_secs(2),
+                    );
                     return;
                 }
             }
         }
 
-        // Save existing file
-        if let Some(tab) = self.tab_manager.active_tab_mut() {
+        // Don't silently clobber edits made outside the editor
+        if let Some(tab) = self.tab_manager.active_tab() {
+            if tab.changed_on_disk() {
+                let index = self.tab_manager.active_index();
+                let modal = Modal::new(
+                    ModalKind::Warning,
+                    " File Changed ",
+                    &format!(
+                        "'{}' was changed on disk since it was opened.",
+                        tab.display_name().trim_end_matches('*')
+                    ),
+                )
+                .with_button(ModalButton::new("Cancel", ModalAction::Dismiss))
+                .with_button(
+                    ModalButton::new("Reload", ModalAction::ReloadTab(index)).with_hotkey('r'),
+                )
+                .with_button(
+                    ModalButton::new("Overwrite", ModalAction::OverwriteTab(index))
+                        .with_hotkey('o'),
+                );
+                self.show_modal(modal);
+                return;
+            }
+        }
+
+        self.write_tab(self.tab_manager.active_index());
+    }
+
+    /// Write a tab's buffer to its file, reporting the result in the status bar.
+    /// Returns false if the tab has no path or the write failed.
+    pub fn write_tab(&mut self, index: usize) -> bool {
+        if let Some(tab) = self.tab_manager.tabs.get_mut(index) {
             if let Tab::Editor { path, buffer, .. } = tab {
                 if let Some(path) = path.clone() {
-                    if std::fs::write(&path, buffer.to_string()).is_ok() {
+                    if crate::vfs::write(&path, buffer.to_string()).is_ok() {
                         tab.mark_saved();
                         self.set_status_message(
                             format!("Saved: {}", path.display()),
                             Duration::from_secs(2),
                         );
+                        if path.file_name().is_some_and(|name| name == ".gitignore") {
+                            if let Some(tree_view) = &mut self.tree_view {
+                                tree_view.reload_gitignore();
+                            }
+                        }
+                        return true;
                     } else {
-                        self.set_status_message(
-                            format!("Failed to save: {}", path.display()),
-                            Duration::from_secs(3),
-                        );
+                        self.report_error(format!("Failed to save: {}", path.display()));
                     }
                 }
             }
         }
+        false
+    }
+
+    /// Write every modified scratch buffer without announcing it. Returns
+    /// false if any write failed.
+    pub fn save_scratch_tabs(&mut self) -> bool {
+        let mut ok = true;
+        for tab in &mut self.tab_manager.tabs {
+            let Tab::Editor { path: Some(path), buffer, modified: true, .. } = tab else {
+                continue;
+            };
+            if !scratch::is_scratch(path) {
+                continue;
+            }
+            if crate::vfs::write(&*path, buffer.to_string()).is_ok() {
+                tab.mark_saved();
+            } else {
+                ok = false;
+            }
+        }
+        ok
+    }
+
+    /// Save scratch buffers when the autosave interval has passed; called
+    /// from the event loop
+    pub fn autosave_scratch_tabs(&mut self) {
+        if !self.scratch_autosave.due() {
+            return;
+        }
+        let ok = self.save_scratch_tabs();
+        if self.scratch_autosave.record(ok) {
+            self.report_error("Failed to save scratch buffers".to_string());
+        }
+    }
+
+    /// Write modified files of the given tabs without asking, for the
+    /// `[autosave]` options. Tabs without a file, scratch buffers (saved on
+    /// their own), read-only tabs and files changed on disk since they were
+    /// opened are left for an explicit save.
+    fn autosave_tabs(&mut self, indices: &[usize]) {
+        let mut saved = 0;
+        for &index in indices {
+            let Some(tab) = self.tab_manager.tabs.get(index) else {
+                continue;
+            };
+            if tab.is_read_only() || tab.changed_on_disk() {
+                continue;
+            }
+            let Tab::Editor { path: Some(path), modified: true, .. } = tab else {
+                continue;
+            };
+            if !scratch::is_scratch(path) && self.write_tab(index) {
+                saved += 1;
+            }
+        }
+        if saved > 0 {
+            let files = if saved == 1 { "file" } else { "files" };
+            let message = format!("Autosaved {} {}", saved, files);
+            self.set_status_message(message, Duration::from_secs(2));
+        }
+    }
+
+    /// Save every modified file when the terminal loses focus, if enabled
+    pub fn handle_focus_lost(&mut self) {
+        if config::get().autosave.on_focus_loss {
+            let all: Vec<usize> = (0..self.tab_manager.tabs.len()).collect();
+            self.autosave_tabs(&all);
+        }
+    }
+
+    /// Save the file the last tab switch left, if enabled; called from the
+    /// event loop, so every way of changing tabs counts
+    pub fn autosave_switched_tab(&mut self) {
+        let active = self.tab_manager.active_tab().and_then(Tab::path).cloned();
+        if active == self.last_active_file {
+            return;
+        }
+        let left = std::mem::replace(&mut self.last_active_file, active);
+        if !config::get().autosave.on_tab_switch {
+            return;
+        }
+        let Some(left) = left else {
+            return;
+        };
+        let index = self.tab_manager.tabs.iter().position(|tab| tab.path() == Some(&left));
+        if let Some(index) = index {
+            self.autosave_tabs(&[index]);
+        }
+    }
+
+    /// Ask where to export the active tab, suggesting an HTML file next to it
+    pub fn open_export_dialog(&mut self) {
+        let Some(tab @ Tab::Editor { .. }) = self.tab_manager.active_tab() else {
+            return;
+        };
+        let suggestion = format!("{}.html", tab.name());
+        let dir = tab
+            .path()
+            .and_then(|path| path.parent())
+            .map(Path::to_path_buf)
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_else(|| PathBuf::from("."));
+
+        self.menu_system.open_input_dialog(
+            "Export to (.html for HTML, - for stdout):".to_string(),
+            "export".to_string(),
+            dir,
+        );
+        if let MenuState::InputDialog(state) = &mut self.menu_system.state {
+            state.input.set_text(&suggestion);
+        }
+    }
+
+    /// Write the active tab with line numbers as HTML or ANSI text. `-`
+    /// prints it as ANSI text after f1 exits.
+    fn export_active_tab(&mut self, target_path: &Path, input: &str) {
+        let Some(tab @ Tab::Editor { buffer, .. }) = self.tab_manager.active_tab() else {
+            return;
+        };
+        let target = input.trim();
+        let format = export::Format::for_target(target);
+        let output = export::render(&buffer.to_string(), tab.name(), format, tab.is_diff());
+
+        if target == "-" {
+            self.exit_output.push_str(&output);
+            self.set_status_message(
+                "Export will be printed when f1 exits".to_string(),
+                Duration::from_secs(2),
+            );
+            return;
+        }
+        let file_path = target_path.join(target);
+        match crate::vfs::write(&file_path, output) {
+            Ok(()) => {
+                self.set_status_message(
+                    format!("Exported: {}", file_path.display()),
+                    Duration::from_secs(2),
+                );
+                if let Some(tree_view) = &mut self.tree_view {
+                    tree_view.refresh();
+                }
+            }
+            Err(e) => self.report_error(format!("Export failed: {}", e)),
+        }
     }
 
-    pub fn execute_file_operation(&mut self, operation: &str, target_path: &PathBuf, input: &str) {
+    fn open_save_as_dialog(&mut self, operation: &str) {
+        let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+        self.menu_system.open_input_dialog(
+            "Save as:".to_string(),
+            operation.to_string(),
+            current_dir,
+        );
+    }
+
+    /// Save a tab and close it, asking for a file name first if it has none
+    pub fn save_and_close_tab(&mut self, index: usize) {
+        let has_path = self
+            .tab_manager
+            .tabs
+            .get(index)
+            .is_some_and(|tab| tab.path().is_some());
+
+        if has_path {
+            if self.write_tab(index) && !self.tab_manager.close_tab(index) {
+                self.running = false;
+            }
+        } else {
+            self.tab_manager.set_active_index(index);
+            self.open_save_as_dialog("save_and_close");
+        }
+    }
+
+    /// Save every modified tab and quit
+    pub fn save_all_and_quit(&mut self) {
+        let modified: Vec<usize> = self
+            .tab_manager
+            .tabs
+            .iter()
+            .enumerate()
+            .filter(|(_, tab)| matches!(tab, Tab::Editor { modified: true, .. }))
+            .map(|(index, _)| index)
+            .collect();
+        self.save_tabs_and_quit(modified);
+    }
+
+    /// Save the given tabs and quit. Unnamed buffers are activated one at a
+    /// time for Save As; the flow resumes after each one is saved.
+    pub fn save_tabs_and_quit(&mut self, indices: Vec<usize>) {
+        self.quit_save_queue = indices;
+        self.continue_save_and_quit();
+    }
+
+    fn continue_save_and_quit(&mut self) {
+        while let Some(&index) = self.quit_save_queue.first() {
+            match self.tab_manager.tabs.get(index) {
+                Some(Tab::Terminal { .. }) | None => {}
+                Some(tab) if tab.path().is_none() => {
+                    self.tab_manager.set_active_index(index);
+                    self.open_save_as_dialog("save_and_quit");
+                    return;
+                }
+                Some(_) => {
+                    if !self.write_tab(index) {
+                        // Leave the editor open so nothing is lost
+                        self.quit_save_queue.clear();
+                        return;
+                    }
+                }
+            }
+            self.quit_save_queue.remove(0);
+        }
+
+        self.running = false;
+    }
+
+    /// Save the active tab under a new name. Returns true on success.
+    fn save_active_tab_as(&mut self, target_path: &Path, input: &str) -> bool {
+        if let Some(tab) = self.tab_manager.active_tab_mut() {
+            let file_path = if input.trim().starts_with('/') {
+                PathBuf::from(input.trim())
+            } else {
+                target_path.join(input.trim())
+            };
+
+            if let Tab::Editor { buffer, path, name, .. } = tab {
+                if crate::vfs::write(&file_path, buffer.to_string()).is_ok() {
+                    *path = Some(crate::tab::canonical_path(&file_path));
+                    *name = file_path
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or("untitled")
+                        .to_string();
+                    tab.mark_saved();
+                    self.set_status_message(
+                        format!("Saved: {}", file_path.display()),
+                        Duration::from_secs(2),
+                    );
+
+                    // Refresh tree view to show the new file
+                    if let Some(tree_view) = &mut self.tree_view {
+                        tree_view.refresh();
+                    }
+                    return true;
+                } else {
+                    self.report_error(format!("Failed to save: {}", input.trim()));
+                }
+            }
+        }
+        false
+    }
+
+    pub fn execute_file_operation(&mut self, operation: &str, target_path: &Path, input: &str) {
         match operation {
             "save_file" => {
-                // Save current tab to the specified filename
-                if let Some(tab) = self.tab_manager.active_tab_mut() {
-                    let file_path = if input.trim().starts_with('/') {
-                        PathBuf::from(input.trim())
-                    } else {
-                        target_path.join(input.trim())
-                    };
-
-                    if let Tab::Editor { buffer, path, name, .. } = tab {
-                        if std::fs::write(&file_path, buffer.to_string()).is_ok() {
-                            *path = Some(file_path.clone());
-                            *name = file_path
-                                .file_name()
-                                .and_then(|n| n.to_str())
-                                .unwrap_or("untitled")
-                                .to_string();
-                            tab.mark_saved();
-                            self.set_status_message(
-                                format!("Saved: {}", file_path.display()),
-                                Duration::from_secs(2),
-                            );
-
-                            // Refresh tree view to show the new file
-                            if let Some(tree_view) = &mut self.tree_view {
-                                tree_view.refresh();
-                            }
-                        } else {
-                            self.set_status_message(
-                                format!("Failed to save: {}", input.trim()),
-                                Duration::from_secs(3),
-                            );
+                self.save_active_tab_as(target_path, input);
+            }
+            "export" => {
+                self.export_active_tab(target_path, input);
+            }
+            "save_and_close" => {
+                if self.save_active_tab_as(target_path, input) && !self.tab_manager.close_current_tab() {
+                    self.running = false;
+                }
+            }
+            "wrap_column" => {
+                let input = input.trim();
+                let column = match input {
+                    "" => None,
+                    _ => match input.parse::<usize>() {
+                        Ok(column) => Some(column),
+                        Err(_) => {
+                            self.report_error(format!("Not a column number: '{}'", input));
+                            return;
                         }
+                    },
+                };
+                self.set_tab_view(|_, overrides| overrides.wrap_column = column);
+            }
+            "follow_session" => self.follow_session(input.trim(), false),
+            "coedit_session" => self.follow_session(input.trim(), true),
+            "align_on" => {
+                if input.is_empty() {
+                    self.report_error("Nothing to align on".to_string());
+                    return;
+                }
+                let aligned = self
+                    .tab_manager
+                    .active_tab_mut()
+                    .and_then(|tab| tab.transform_lines(|lines| align_on(lines, input)));
+                if let Some((lines, _)) = aligned {
+                    self.set_status_message(
+                        format!("Aligned {} lines on '{}'", lines, input),
+                        Duration::from_secs(2),
+                    );
+                }
+            }
+            "save_and_quit" => {
+                if self.save_active_tab_as(target_path, input) {
+                    if !self.quit_save_queue.is_empty() {
+                        self.quit_save_queue.remove(0);
                     }
+                    self.continue_save_and_quit();
+                } else {
+                    // Stay open; a later save-and-quit starts over
+                    self.quit_save_queue.clear();
                 }
             }
             _ => {
-                if let Some(tree_view) = &mut self.tree_view {
+                let mut new_file = None;
+                let result = if let Some(tree_view) = &mut self.tree_view {
                     let result = match operation {
-                        "new_file" => tree_view
-                            .create_file(target_path, input.trim())
-                            .map(|_| format!("Created file '{}'", input.trim()))
-                            .map_err(|e| format!("Failed to create file: {}", e)),
+                        "new_file" => match tree_view.create_file(target_path, input.trim()) {
+                            Ok(path) => {
+                                new_file = Some(path);
+                                Ok(format!("Created file '{}'", input.trim()))
+                            }
+                            Err(e) => Err(format!("Failed to create file: {}", e)),
+                        },
                         "new_folder" => tree_view
                             .create_directory(target_path, input.trim())
+                            .and_then(|path| tree_view.expand_to_file(&path))
                             .map(|_| format!("Created directory '{}'", input.trim()))
                             .map_err(|e| format!("Failed to create directory: {}", e)),
                         "rename" => {
                             match tree_view.rename_file_or_directory(target_path, input.trim()) {
                                 Ok(new_path) => {
                                     // Update any open tabs with the renamed file
+                                    let old_path = crate::tab::canonical_path(target_path);
+                                    let new_path = crate::tab::canonical_path(&new_path);
                                     for tab in self.tab_manager.tabs.iter_mut() {
                                         if let crate::tab::Tab::Editor { path, name, .. } = tab {
                                             if let Some(tab_path) = path {
-                                                if tab_path == target_path {
+                                                if *tab_path == old_path {
                                                     // Update tab path and name
                                                     *path = Some(new_path.clone());
                                                     if let Some(file_name) = new_path.file_name() {
@@ -121,31 +435,23 @@ impl App {
                         _ => return
                     };
 
-                    // Handle result after borrow is released
-                    let (message, is_error) = match result {
-                        Ok(msg) => (msg, false),
-                        Err(err) => (err, true),
-                    };
-                    
                     tree_view.refresh();
-                }
-                
-                // Set status message after borrowing is complete
-                if let Some(tree_view) = &mut self.tree_view {
+                    result
+                } else {
+                    return;
+                };
+
+                // A new file opens right away; the tree follows the active tab
+                if let Some(path) = new_file {
+                    self.open_file_in_tab(path, "");
+                } else if operation == "rename" {
                     self.expand_tree_to_current_file();
                 }
-                
-                // Handle the result message
-                match operation {
-                    "new_file" | "new_folder" | "rename" => {
-                        // Dummy operation to get the result
-                        if let Some(_tree_view) = &self.tree_view {
-                            // We need to handle this differently to avoid borrow issues
-                            // For now, let's just set a generic message
-                            self.set_status_message("File operation completed".to_string(), Duration::from_secs(2));
-                        }
-                    }
-                    _ => {}
+
+                // Report the result once the tree view borrow is released
+                match result {
+                    Ok(message) => self.set_status_message(message, Duration::from_secs(2)),
+                    Err(message) => self.report_error(message),
                 }
             }
         }
@@ -0,0 +1,80 @@
+//! Character folding for searches that ignore case or accents. Every
+//! character folds to exactly one other, so a column in folded text is the
+//! same column in the original. Accents are stripped from the precomposed
+//! Latin letters; combining marks in decomposed text are left alone.
+
+use crate::config;
+
+/// How characters compare in a search
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Folding {
+    pub ignore_case: bool,
+    pub ignore_accents: bool,
+}
+
+impl Folding {
+    /// Folding for file name filters in the tree and the pickers, which
+    /// always ignore case
+    pub fn names() -> Self {
+        Self {
+            ignore_case: true,
+            ignore_accents: config::get().search.ignore_accents,
+        }
+    }
+
+    pub fn char(self, ch: char) -> char {
+        let ch = if self.ignore_accents {
+            strip_accent(ch)
+        } else {
+            ch
+        };
+        if self.ignore_case {
+            ch.to_lowercase().next().unwrap_or(ch)
+        } else {
+            ch
+        }
+    }
+
+    pub fn str(self, text: &str) -> String {
+        text.chars().map(|ch| self.char(ch)).collect()
+    }
+}
+
+/// Base letters of U+00C0 to U+017F; `-` marks characters without one
+const LATIN_BASES: &str = concat!(
+    "AAAAAA-CEEEEIIIIDNOOOOO-OUUUUY--aaaaaa-ceeeeiiiidnooooo-ouuuuy-y",
+    "AaAaAaCcCcCcCcDdDdEeEeEeEeEeGgGgGgGgHhHhIiIiIiIiIi--JjKk-LlLlLlLlLl",
+    "NnNnNn---OoOoOo--RrRrRrSsSsSsSsTtTtTtUuUuUuUuUuUuWwYyYZzZzZzs",
+);
+
+fn strip_accent(ch: char) -> char {
+    let Some(offset) = (ch as u32).checked_sub(0xC0) else {
+        return ch;
+    };
+    match LATIN_BASES.as_bytes().get(offset as usize) {
+        Some(b'-') | None => ch,
+        Some(&base) => base as char,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_case_and_accents() {
+        assert_eq!(LATIN_BASES.len(), 0x180 - 0xC0);
+        let both = Folding {
+            ignore_case: true,
+            ignore_accents: true,
+        };
+        assert_eq!(both.str("Crème BRÛLÉE, Łódź"), "creme brulee, lodz");
+        assert_eq!(both.str("Æ × ß ŉ"), "æ × ß ŉ");
+        let accents = Folding {
+            ignore_accents: true,
+            ..Folding::default()
+        };
+        assert_eq!(accents.str("Ça"), "Ca");
+        assert_eq!(Folding::default().str("Été"), "Été");
+    }
+}
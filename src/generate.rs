@@ -0,0 +1,145 @@
+//! Text the tab menu's Insert submenu puts at the cursor: random UUIDs,
+//! timestamps in the `[editor] timestamp_format` and lorem ipsum. Times are
+//! in UTC, as there is no time zone database to find the local offset in.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Generator {
+    Uuid,
+    Timestamp,
+    Lorem,
+}
+
+impl Generator {
+    /// The submenu action of each generator, in menu order
+    pub const ALL: [(Generator, &'static str, &'static str); 3] = [
+        (Generator::Uuid, "UUID v4", "insert_uuid"),
+        (Generator::Timestamp, "Timestamp", "insert_timestamp"),
+        (Generator::Lorem, "Lorem Ipsum Paragraph", "insert_lorem"),
+    ];
+
+    pub fn from_action(action: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|(_, _, name)| *name == action)
+            .map(|(generator, _, _)| *generator)
+    }
+
+    pub fn text(self) -> String {
+        match self {
+            Generator::Uuid => uuid_v4(random(), random()),
+            Generator::Timestamp => timestamp(
+                &crate::config::get().editor.timestamp_format,
+                SystemTime::now(),
+            ),
+            Generator::Lorem => LOREM.to_string(),
+        }
+    }
+}
+
+const LOREM: &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod \
+tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud \
+exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat. Duis aute irure dolor in \
+reprehenderit in voluptate velit esse cillum dolore eu fugiat nulla pariatur. Excepteur sint \
+occaecat cupidatat non proident, sunt in culpa qui officia deserunt mollit anim id est laborum.";
+
+/// 64 random bits, from the hasher keys std seeds from the OS
+fn random() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
+
+/// A version 4 UUID from 128 random bits, less the version and variant
+fn uuid_v4(high: u64, low: u64) -> String {
+    let high = (high & !0xf000) | 0x4000;
+    let low = (low & !(0b11 << 62)) | (0b10 << 62);
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xffff,
+        low >> 48,
+        low & 0xffff_ffff_ffff
+    )
+}
+
+/// Format a time in UTC. `%Y`, `%m`, `%d`, `%H`, `%M`, `%S` and `%s`
+/// (seconds since 1970) are replaced, `%%` is a `%` and anything else is
+/// kept as written.
+pub fn timestamp(format: &str, time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let (year, month, day) = civil_from_days((seconds / 86_400) as i64);
+    let of_day = seconds % 86_400;
+
+    let mut formatted = String::new();
+    let mut chars = format.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '%' {
+            formatted.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('Y') => formatted.push_str(&format!("{:04}", year)),
+            Some('m') => formatted.push_str(&format!("{:02}", month)),
+            Some('d') => formatted.push_str(&format!("{:02}", day)),
+            Some('H') => formatted.push_str(&format!("{:02}", of_day / 3600)),
+            Some('M') => formatted.push_str(&format!("{:02}", of_day / 60 % 60)),
+            Some('S') => formatted.push_str(&format!("{:02}", of_day % 60)),
+            Some('s') => formatted.push_str(&seconds.to_string()),
+            Some('%') => formatted.push('%'),
+            Some(other) => {
+                formatted.push('%');
+                formatted.push(other);
+            }
+            None => formatted.push('%'),
+        }
+    }
+    formatted
+}
+
+/// Year, month and day of a day counted from 1970-01-01, in the proleptic
+/// Gregorian calendar (Howard Hinnant's `civil_from_days`)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn formats_uuids_and_timestamps() {
+        assert_eq!(
+            uuid_v4(u64::MAX, u64::MAX),
+            "ffffffff-ffff-4fff-bfff-ffffffffffff"
+        );
+        assert_eq!(uuid_v4(0, 0), "00000000-0000-4000-8000-000000000000");
+
+        let time = UNIX_EPOCH + Duration::from_secs(951_827_696); // 2000-02-29 12:34:56
+        assert_eq!(
+            timestamp("%Y-%m-%dT%H:%M:%SZ", time),
+            "2000-02-29T12:34:56Z"
+        );
+        assert_eq!(timestamp("%s 100%% %q", time), "951827696 100% %q");
+        assert_eq!(timestamp("%d/%m/%Y", UNIX_EPOCH), "01/01/1970");
+    }
+}
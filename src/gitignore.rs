@@ -1,95 +1,96 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
+/// Ignore rules for a repository, following git's semantics: every directory
+/// may carry its own `.gitignore`, deeper files take precedence over higher
+/// ones, and within a file the last matching pattern wins.
 #[derive(Debug, Clone, PartialEq)]
 pub struct GitIgnore {
-    patterns: Vec<GitIgnorePattern>,
+    defaults: Vec<GitIgnorePattern>,
     repo_root: PathBuf,
+    // Parsed `.gitignore` files keyed by directory (relative to the root),
+    // loaded the first time a path inside that directory is checked
+    files: RefCell<HashMap<PathBuf, Vec<GitIgnorePattern>>>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 struct GitIgnorePattern {
-    pattern: String,
+    pattern: Vec<char>,
     is_negation: bool,
     is_directory_only: bool,
-    is_absolute: bool,
+    // Patterns with a slash before the end match relative to their
+    // `.gitignore`; others match a name at any depth
+    is_anchored: bool,
 }
 
 impl GitIgnore {
     pub fn new(repo_root: PathBuf) -> Self {
-        let mut gitignore = Self {
-            patterns: Vec::new(),
+        // Common patterns that should always be ignored, overridable by the repo
+        let defaults = [".git", ".DS_Store", "Thumbs.db", "*.swp", "*.swo", "*~"]
+            .iter()
+            .filter_map(|line| Self::parse_line(line))
+            .collect();
+
+        Self {
+            defaults,
             repo_root,
-        };
-        gitignore.load_gitignore();
-        gitignore
-    }
-
-    fn load_gitignore(&mut self) {
-        let gitignore_path = self.repo_root.join(".gitignore");
-        if let Ok(content) = fs::read_to_string(&gitignore_path) {
-            for line in content.lines() {
-                if let Some(pattern) = self.parse_line(line) {
-                    self.patterns.push(pattern);
-                }
-            }
+            files: RefCell::new(HashMap::new()),
         }
-
-        // Add common default patterns
-        self.add_default_patterns();
     }
 
-    fn parse_line(&self, line: &str) -> Option<GitIgnorePattern> {
-        let line = line.trim();
+    fn parse_line(line: &str) -> Option<GitIgnorePattern> {
+        // Trailing whitespace is dropped unless escaped with a backslash
+        let mut line = line.trim_end_matches(['\r', '\n']);
+        while line.ends_with(' ') && !line.ends_with("\\ ") {
+            line = &line[..line.len() - 1];
+        }
 
         // Skip empty lines and comments
         if line.is_empty() || line.starts_with('#') {
             return None;
         }
 
-        let mut pattern = line.to_string();
+        let mut pattern = line;
         let mut is_negation = false;
-        let mut is_directory_only = false;
-        let mut is_absolute = false;
-
-        // Handle negation
-        if pattern.starts_with('!') {
+        if let Some(rest) = pattern.strip_prefix('!') {
             is_negation = true;
-            pattern = pattern[1..].to_string();
+            pattern = rest;
+        } else if pattern.starts_with("\\!") || pattern.starts_with("\\#") {
+            pattern = &pattern[1..];
         }
 
-        // Handle directory-only patterns
-        if pattern.ends_with('/') {
+        let mut is_directory_only = false;
+        if let Some(rest) = pattern.strip_suffix('/') {
             is_directory_only = true;
-            pattern.pop();
+            pattern = rest;
         }
 
-        // Handle absolute patterns
-        if pattern.starts_with('/') {
-            is_absolute = true;
-            pattern = pattern[1..].to_string();
+        let is_anchored = pattern.contains('/');
+        let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+        if pattern.is_empty() {
+            return None;
         }
 
         Some(GitIgnorePattern {
-            pattern,
+            pattern: pattern.chars().collect(),
             is_negation,
             is_directory_only,
-            is_absolute,
+            is_anchored,
         })
     }
 
-    fn add_default_patterns(&mut self) {
-        // Add some common patterns that should always be ignored
-        let default_patterns = vec![".git", ".DS_Store", "Thumbs.db", "*.swp", "*.swo", "*~"];
+    /// Forget the parsed `.gitignore` files so the next check reads them
+    /// again, after one was edited
+    pub fn clear(&self) {
+        self.files.borrow_mut().clear();
+    }
 
-        for pattern in default_patterns {
-            self.patterns.push(GitIgnorePattern {
-                pattern: pattern.to_string(),
-                is_negation: false,
-                is_directory_only: false,
-                is_absolute: false,
-            });
-        }
+    fn load_file(&self, dir: &Path) -> Vec<GitIgnorePattern> {
+        fs::read_to_string(self.repo_root.join(dir).join(".gitignore"))
+            .map(|content| content.lines().filter_map(Self::parse_line).collect())
+            .unwrap_or_default()
     }
 
     pub fn is_ignored(&self, path: &Path) -> bool {
@@ -101,111 +102,319 @@ impl GitIgnore {
             return false;
         };
 
-        let path_str = relative_path.to_string_lossy();
-        let is_directory = path.is_dir();
+        let components: Vec<String> = relative_path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        if components.is_empty() {
+            return false;
+        }
 
-        let mut ignored = false;
+        // Nothing inside an ignored directory can be re-included
+        for depth in 1..components.len() {
+            if self.matches(&components[..depth], true) {
+                return true;
+            }
+        }
 
-        for pattern in &self.patterns {
-            if self.matches_pattern(pattern, &path_str, is_directory) {
+        self.matches(&components, path.is_dir())
+    }
+
+    /// Whether a path, given as components relative to the root, is ignored
+    /// by the rules that apply to it, ignoring its parent directories
+    fn matches(&self, components: &[String], is_directory: bool) -> bool {
+        let mut ignored = self
+            .defaults
+            .iter()
+            .rev()
+            .find(|pattern| pattern.matches(&components.join("/"), is_directory))
+            .is_some_and(|pattern| !pattern.is_negation);
+
+        let mut files = self.files.borrow_mut();
+        let mut base = PathBuf::new();
+        for depth in 0..components.len() {
+            if depth > 0 {
+                base.push(&components[depth - 1]);
+            }
+            let patterns = files
+                .entry(base.clone())
+                .or_insert_with(|| self.load_file(&base));
+            let relative = components[depth..].join("/");
+            if let Some(pattern) = patterns
+                .iter()
+                .rev()
+                .find(|pattern| pattern.matches(&relative, is_directory))
+            {
                 ignored = !pattern.is_negation;
             }
         }
 
         ignored
     }
+}
 
-    fn matches_pattern(&self, pattern: &GitIgnorePattern, path: &str, is_directory: bool) -> bool {
-        // If pattern is directory-only and path is not a directory, no match
-        if pattern.is_directory_only && !is_directory {
+impl GitIgnorePattern {
+    /// Match against a path relative to the directory holding the pattern
+    fn matches(&self, path: &str, is_directory: bool) -> bool {
+        if self.is_directory_only && !is_directory {
             return false;
         }
 
-        let pattern_str = &pattern.pattern;
+        if self.is_anchored {
+            let text: Vec<char> = path.chars().collect();
+            glob_match(&self.pattern, &text)
+        } else {
+            let name = path.rsplit('/').next().unwrap_or(path);
+            let text: Vec<char> = name.chars().collect();
+            glob_match(&self.pattern, &text)
+        }
+    }
+}
 
-        // Handle absolute patterns
-        if pattern.is_absolute {
-            return self.glob_match(pattern_str, path);
+/// Glob matching with git's wildmatch rules: `*` and `?` stay within one path
+/// segment, `**` spans segments, `[...]` is a character class and `\` escapes.
+fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    let Some(&first) = pattern.first() else {
+        return text.is_empty();
+    };
+
+    match first {
+        '*' if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            match rest.first() {
+                // Trailing `**` matches everything that is left
+                None => true,
+                // `**/` matches zero or more whole directories
+                Some('/') => {
+                    let rest = &rest[1..];
+                    if glob_match(rest, text) {
+                        return true;
+                    }
+                    text.iter()
+                        .enumerate()
+                        .filter(|(_, c)| **c == '/')
+                        .any(|(i, _)| glob_match(rest, &text[i + 1..]))
+                }
+                // Otherwise `**` behaves like a regular `*`
+                Some(_) => glob_match(&pattern[1..], text),
+            }
+        }
+        '*' => {
+            let rest = &pattern[1..];
+            for i in 0..=text.len() {
+                if glob_match(rest, &text[i..]) {
+                    return true;
+                }
+                if i < text.len() && text[i] == '/' {
+                    break;
+                }
+            }
+            false
         }
+        '?' => match text.first() {
+            Some(&c) if c != '/' => glob_match(&pattern[1..], &text[1..]),
+            _ => false,
+        },
+        '[' => match (class_match(pattern, text.first().copied()), text.first()) {
+            (Some((true, len)), Some(_)) => glob_match(&pattern[len..], &text[1..]),
+            (Some(_), _) => false,
+            // No closing bracket: treat `[` literally
+            (None, Some('[')) => glob_match(&pattern[1..], &text[1..]),
+            (None, _) => false,
+        },
+        '\\' if pattern.len() > 1 => match text.first() {
+            Some(&c) if c == pattern[1] => glob_match(&pattern[2..], &text[1..]),
+            _ => false,
+        },
+        c => match text.first() {
+            Some(&t) if t == c => glob_match(&pattern[1..], &text[1..]),
+            _ => false,
+        },
+    }
+}
 
-        // For relative patterns, check if any part of the path matches
-        let path_parts: Vec<&str> = path.split('/').collect();
+/// Match a `[...]` class at the start of `pattern`. Returns whether `c`
+/// matched and how many pattern characters the class used, or None if the
+/// class is not closed.
+fn class_match(pattern: &[char], c: Option<char>) -> Option<(bool, usize)> {
+    let mut i = 1;
+    let negated = matches!(pattern.get(i), Some('!') | Some('^'));
+    if negated {
+        i += 1;
+    }
 
-        // Try matching against the full path
-        if self.glob_match(pattern_str, path) {
-            return true;
+    let mut matched = false;
+    let mut first = true;
+    while i < pattern.len() {
+        let start = pattern[i];
+        if start == ']' && !first {
+            let hit = c.is_some_and(|c| c != '/') && matched != negated;
+            return Some((hit, i + 1));
         }
+        first = false;
 
-        // Try matching against just the filename
-        if let Some(filename) = path_parts.last() {
-            if self.glob_match(pattern_str, filename) {
-                return true;
+        if pattern.get(i + 1) == Some(&'-') && pattern.get(i + 2).is_some_and(|e| *e != ']') {
+            let end = pattern[i + 2];
+            if c.is_some_and(|c| start <= c && c <= end) {
+                matched = true;
+            }
+            i += 3;
+        } else {
+            if c == Some(start) {
+                matched = true;
             }
+            i += 1;
         }
+    }
+    None
+}
 
-        // Try matching against any suffix of the path
-        for i in 0..path_parts.len() {
-            let suffix = path_parts[i..].join("/");
-            if self.glob_match(pattern_str, &suffix) {
-                return true;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pattern(line: &str) -> GitIgnorePattern {
+        GitIgnore::parse_line(line).expect("pattern")
+    }
+
+    fn matches(line: &str, path: &str, is_directory: bool) -> bool {
+        pattern(line).matches(path, is_directory)
+    }
+
+    /// Create a scratch repository with the given files (`dir/` for folders)
+    fn repo(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root =
+            std::env::temp_dir().join(format!("f1-gitignore-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for (path, content) in files {
+            let full = root.join(path);
+            if path.ends_with('/') {
+                fs::create_dir_all(&full).unwrap();
+            } else {
+                fs::create_dir_all(full.parent().unwrap()).unwrap();
+                fs::write(&full, content).unwrap();
             }
         }
+        root
+    }
 
-        false
+    #[test]
+    fn comments_blank_lines_and_escapes() {
+        assert!(GitIgnore::parse_line("# comment").is_none());
+        assert!(GitIgnore::parse_line("   ").is_none());
+        assert!(matches("\\#file", "#file", false));
+        assert!(matches("\\!important", "!important", false));
+        assert!(matches("trailing   ", "trailing", false));
+        assert!(matches("space\\ ", "space ", false));
     }
 
-    fn glob_match(&self, pattern: &str, text: &str) -> bool {
-        // Simple glob matching implementation
-        // This is a basic implementation - could be enhanced with a proper glob library
+    #[test]
+    fn name_patterns_match_at_any_depth() {
+        assert!(matches("*.log", "debug.log", false));
+        assert!(matches("*.log", "logs/debug.log", false));
+        assert!(matches("target", "crates/core/target", true));
+        assert!(!matches("*.log", "debug.log.txt", false));
+    }
 
-        if pattern == text {
-            return true;
-        }
+    #[test]
+    fn slashes_anchor_to_the_gitignore() {
+        assert!(matches("/build", "build", true));
+        assert!(!matches("/build", "src/build", true));
+        assert!(matches("doc/frotz", "doc/frotz", true));
+        assert!(!matches("doc/frotz", "a/doc/frotz", true));
+    }
 
-        if pattern.contains('*') {
-            return self.wildcard_match(pattern, text);
-        }
+    #[test]
+    fn wildcards_stay_within_a_segment() {
+        assert!(matches("foo/*", "foo/test.json", false));
+        assert!(!matches("foo/*", "foo/bar/hello.c", false));
+        assert!(matches("file?.txt", "file1.txt", false));
+        assert!(!matches("a?b", "a/b", false));
+    }
 
-        false
+    #[test]
+    fn directory_only_patterns() {
+        assert!(matches("build/", "build", true));
+        assert!(!matches("build/", "build", false));
+        assert!(matches("build/", "src/build", true));
     }
 
-    fn wildcard_match(&self, pattern: &str, text: &str) -> bool {
-        let pattern_chars: Vec<char> = pattern.chars().collect();
-        let text_chars: Vec<char> = text.chars().collect();
+    #[test]
+    fn double_star() {
+        assert!(matches("**/foo", "foo", false));
+        assert!(matches("**/foo", "a/b/foo", false));
+        assert!(matches("**/foo/bar", "x/foo/bar", false));
+        assert!(matches("abc/**", "abc/x/y", false));
+        assert!(!matches("abc/**", "abc", true));
+        assert!(matches("a/**/b", "a/b", false));
+        assert!(matches("a/**/b", "a/x/y/b", false));
+        assert!(!matches("a/**/b", "x/a/b", false));
+    }
 
-        self.wildcard_match_recursive(&pattern_chars, &text_chars, 0, 0)
+    #[test]
+    fn character_classes() {
+        assert!(matches("*.[oa]", "lib.a", false));
+        assert!(matches("file[0-9]", "file7", false));
+        assert!(!matches("file[!0-9]", "file7", false));
+        assert!(matches("file[!0-9]", "filex", false));
+        assert!(matches("[abc", "[abc", false));
     }
 
-    #[allow(clippy::only_used_in_recursion)]
-    fn wildcard_match_recursive(
-        &self,
-        pattern: &[char],
-        text: &[char],
-        p: usize,
-        t: usize,
-    ) -> bool {
-        if p >= pattern.len() {
-            return t >= text.len();
-        }
+    #[test]
+    fn negation_and_precedence() {
+        let root = repo(
+            "negation",
+            &[
+                (".gitignore", "*.log\n!keep.log\n"),
+                ("debug.log", ""),
+                ("keep.log", ""),
+            ],
+        );
+        let gitignore = GitIgnore::new(root.clone());
+        assert!(gitignore.is_ignored(&root.join("debug.log")));
+        assert!(!gitignore.is_ignored(&root.join("keep.log")));
+
+        // An edited file is read again once the cache is cleared
+        fs::write(root.join(".gitignore"), "*.txt\n").unwrap();
+        assert!(gitignore.is_ignored(&root.join("debug.log")));
+        gitignore.clear();
+        assert!(!gitignore.is_ignored(&root.join("debug.log")));
+        fs::remove_dir_all(&root).unwrap();
+    }
 
-        if pattern[p] == '*' {
-            // Try matching zero characters
-            if self.wildcard_match_recursive(pattern, text, p + 1, t) {
-                return true;
-            }
-            // Try matching one or more characters
-            for i in t..text.len() {
-                if self.wildcard_match_recursive(pattern, text, p + 1, i + 1) {
-                    return true;
-                }
-            }
-            false
-        } else if t >= text.len() {
-            false
-        } else if pattern[p] == '?' || pattern[p] == text[t] {
-            self.wildcard_match_recursive(pattern, text, p + 1, t + 1)
-        } else {
-            false
-        }
+    #[test]
+    fn nested_gitignore_files() {
+        let root = repo(
+            "nested",
+            &[
+                (".gitignore", "*.tmp\n"),
+                ("sub/.gitignore", "!keep.tmp\n/local\n"),
+                ("sub/keep.tmp", ""),
+                ("sub/other.tmp", ""),
+                ("sub/local/", ""),
+                ("local/", ""),
+            ],
+        );
+        let gitignore = GitIgnore::new(root.clone());
+        assert!(!gitignore.is_ignored(&root.join("sub/keep.tmp")));
+        assert!(gitignore.is_ignored(&root.join("sub/other.tmp")));
+        assert!(gitignore.is_ignored(&root.join("sub/local")));
+        assert!(!gitignore.is_ignored(&root.join("local")));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn ignored_directories_cannot_be_reincluded() {
+        let root = repo(
+            "parent",
+            &[
+                (".gitignore", "build/\n!build/keep.txt\n"),
+                ("build/keep.txt", ""),
+            ],
+        );
+        let gitignore = GitIgnore::new(root.clone());
+        assert!(gitignore.is_ignored(&root.join("build")));
+        assert!(gitignore.is_ignored(&root.join("build/keep.txt")));
+        fs::remove_dir_all(&root).unwrap();
     }
 }
@@ -0,0 +1,165 @@
+//! The columns left of an editor's text. Each column shows one kind of
+//! per-line information, and `[editor] gutter` picks which ones show and
+//! in what order. A column of notes only takes room while the tab has a
+//! note for it, and the numbers column follows the tab's line numbers
+//! toggle.
+
+use crate::annotations::Annotations;
+use crate::marks;
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Column {
+    /// The loudest gutter note of a line, as its severity's icon, from
+    /// every source other than the marks
+    Diagnostics,
+    /// The quick slots pointing at a line
+    Marks,
+    /// Line numbers, with "↳" on the wrapped rows of a line
+    Numbers,
+}
+
+impl Column {
+    /// Whether the notes of an annotation source show in this column
+    fn shows(self, source: &str) -> bool {
+        match self {
+            Column::Diagnostics => source != marks::ANNOTATION_SOURCE,
+            Column::Marks => source == marks::ANNOTATION_SOURCE,
+            Column::Numbers => false,
+        }
+    }
+}
+
+/// The gutter of one editor, with the columns that take room and their
+/// widths
+pub struct Gutter<'a> {
+    columns: Vec<(Column, usize)>,
+    annotations: Option<&'a Annotations>,
+}
+
+impl<'a> Gutter<'a> {
+    pub fn new(
+        columns: &[Column],
+        line_count: usize,
+        line_numbers: bool,
+        annotations: Option<&'a Annotations>,
+    ) -> Self {
+        let columns = columns
+            .iter()
+            .filter_map(|&column| {
+                let width = match column {
+                    Column::Numbers if line_numbers => (line_count.to_string().len() + 1).max(4),
+                    Column::Numbers => return None,
+                    _ => {
+                        let has_notes = annotations
+                            .is_some_and(|notes| notes.has_gutter(|source| column.shows(source)));
+                        if !has_notes {
+                            return None;
+                        }
+                        2
+                    }
+                };
+                Some((column, width))
+            })
+            .collect();
+        Self {
+            columns,
+            annotations,
+        }
+    }
+
+    pub fn width(&self) -> u16 {
+        self.columns.iter().map(|(_, width)| *width as u16).sum()
+    }
+
+    /// One screen row of the gutter; notes show only on the first row of a
+    /// wrapped line
+    pub fn row(&self, line: usize, first_row: bool) -> Line<'static> {
+        let spans = self.columns.iter().map(|&(column, width)| {
+            if column == Column::Numbers {
+                let number = if first_row {
+                    (line + 1).to_string()
+                } else {
+                    "↳".to_string()
+                };
+                let text = format!("{:>width$} ", number, width = width - 1);
+                return Span::styled(text, Style::default().fg(Color::DarkGray));
+            }
+            let note = self
+                .annotations
+                .and_then(|notes| notes.gutter_note(line, |source| column.shows(source)))
+                .filter(|_| first_row);
+            let sign = note.and_then(|note| match column {
+                Column::Diagnostics => Some(note.severity.icon()),
+                _ => note.text.chars().next(),
+            });
+            match (note, sign) {
+                (Some(note), Some(sign)) => Span::styled(
+                    format!("{:<width$}", sign, width = width),
+                    note.severity.style(),
+                ),
+                _ => Span::raw(" ".repeat(width)),
+            }
+        });
+        Line::from(spans.collect::<Vec<_>>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::annotations::{Annotation, Placement, Severity};
+
+    fn text(line: &Line) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    #[test]
+    fn columns_take_room_only_for_their_notes() {
+        let all = [Column::Diagnostics, Column::Marks, Column::Numbers];
+        let mut notes = Annotations::default();
+        let gutter = Gutter::new(&all, 120, true, Some(&notes));
+        assert_eq!(gutter.width(), 4);
+        assert_eq!(text(&gutter.row(8, true)), "  9 ");
+        assert_eq!(text(&gutter.row(8, false)), "  ↳ ");
+
+        notes.set(
+            marks::ANNOTATION_SOURCE,
+            vec![Annotation {
+                line: 8,
+                text: "2".to_string(),
+                placement: Placement::Gutter,
+                severity: Severity::Info,
+            }],
+        );
+        let gutter = Gutter::new(&all, 120, false, Some(&notes));
+        assert_eq!(gutter.width(), 2);
+        assert_eq!(text(&gutter.row(8, true)), "2 ");
+        assert_eq!(text(&gutter.row(8, false)), "  ");
+
+        notes.set(
+            "lint",
+            vec![Annotation {
+                line: 3,
+                text: "unused".to_string(),
+                placement: Placement::Gutter,
+                severity: Severity::Error,
+            }],
+        );
+        let gutter = Gutter::new(
+            &[Column::Marks, Column::Diagnostics],
+            120,
+            true,
+            Some(&notes),
+        );
+        let icon = Severity::Error.icon();
+        assert_eq!(text(&gutter.row(3, true)), format!("  {} ", icon));
+        assert_eq!(text(&gutter.row(8, true)), "2   ");
+    }
+}
@@ -0,0 +1,278 @@
+//! The keys handled wherever the focus is, as a table: `handle_key_event`
+//! looks keys up in it and the Help tab lists it, so the two can't drift
+//! apart. Keys that only mean something in the tree, the find bar or the
+//! editor are still matched where they are handled.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Global {
+    Quit,
+    Save,
+    CloseTab,
+    NewTab,
+    NewScratch,
+    Compare,
+    Export,
+    Todos,
+    SearchInFiles,
+    NewTerminal,
+    SwitchBuffer,
+    ToggleSidebar,
+    NarrowSidebar,
+    WidenSidebar,
+    Find,
+    FindAgain,
+    FindReplace,
+    Suspend,
+    CancelJobs,
+    ToggleLog,
+    ToggleMouse,
+    ToggleOverwrite,
+    Menu,
+    #[cfg(feature = "perf")]
+    PerfHud,
+    NextTab,
+    PrevTab,
+}
+
+pub struct Binding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+    pub command: Global,
+    pub description: &'static str,
+}
+
+const fn bind(
+    code: KeyCode,
+    modifiers: KeyModifiers,
+    command: Global,
+    description: &'static str,
+) -> Binding {
+    Binding {
+        code,
+        modifiers,
+        command,
+        description,
+    }
+}
+
+const CTRL: KeyModifiers = KeyModifiers::CONTROL;
+const ALT: KeyModifiers = KeyModifiers::ALT;
+const SHIFT: KeyModifiers = KeyModifiers::SHIFT;
+const NONE: KeyModifiers = KeyModifiers::NONE;
+const CTRL_ALT: KeyModifiers = KeyModifiers::CONTROL.union(KeyModifiers::ALT);
+const CTRL_SHIFT: KeyModifiers = KeyModifiers::CONTROL.union(KeyModifiers::SHIFT);
+
+/// Entries for the same command in a row are listed together in the help
+pub const GLOBAL: &[Binding] = &[
+    bind(KeyCode::Char('q'), CTRL, Global::Quit, "Quit"),
+    bind(KeyCode::Char('s'), CTRL, Global::Save, "Save"),
+    bind(KeyCode::Char('w'), CTRL, Global::CloseTab, "Close Tab"),
+    bind(KeyCode::Char('n'), CTRL, Global::NewTab, "New Tab"),
+    bind(
+        KeyCode::Char('n'),
+        ALT,
+        Global::NewScratch,
+        "New Scratch Buffer",
+    ),
+    bind(
+        KeyCode::Char('d'),
+        ALT,
+        Global::Compare,
+        "Compare Active Tab With…",
+    ),
+    bind(
+        KeyCode::Char('e'),
+        ALT,
+        Global::Export,
+        "Export as HTML / ANSI",
+    ),
+    bind(KeyCode::Char('t'), ALT, Global::Todos, "List TODO Comments"),
+    bind(
+        KeyCode::Char('f'),
+        CTRL_SHIFT,
+        Global::SearchInFiles,
+        "Search in Files",
+    ),
+    bind(
+        KeyCode::Char('F'),
+        CTRL_SHIFT,
+        Global::SearchInFiles,
+        "Search in Files",
+    ),
+    bind(
+        KeyCode::Char('t'),
+        CTRL,
+        Global::NewTerminal,
+        "New Terminal Tab",
+    ),
+    bind(
+        KeyCode::Char('b'),
+        CTRL,
+        Global::SwitchBuffer,
+        "Switch to Tab by Name",
+    ),
+    bind(
+        KeyCode::Char('b'),
+        CTRL_ALT,
+        Global::ToggleSidebar,
+        "Collapse / Restore the Sidebar",
+    ),
+    bind(
+        KeyCode::Left,
+        CTRL_ALT,
+        Global::NarrowSidebar,
+        "Narrow the Sidebar",
+    ),
+    bind(
+        KeyCode::Right,
+        CTRL_ALT,
+        Global::WidenSidebar,
+        "Widen the Sidebar",
+    ),
+    bind(KeyCode::Char('f'), CTRL, Global::Find, "Find"),
+    bind(
+        KeyCode::F(3),
+        NONE,
+        Global::FindAgain,
+        "Find Next / Previous",
+    ),
+    bind(
+        KeyCode::F(3),
+        SHIFT,
+        Global::FindAgain,
+        "Find Next / Previous",
+    ),
+    bind(
+        KeyCode::Char('h'),
+        CTRL,
+        Global::FindReplace,
+        "Find and Replace",
+    ),
+    bind(
+        KeyCode::Char('z'),
+        ALT,
+        Global::Suspend,
+        "Suspend to the Shell",
+    ),
+    bind(
+        KeyCode::Char('k'),
+        ALT,
+        Global::CancelJobs,
+        "Cancel Background Jobs",
+    ),
+    bind(
+        KeyCode::Char('l'),
+        ALT,
+        Global::ToggleLog,
+        "Show / Hide the Message Log",
+    ),
+    bind(
+        KeyCode::Char('m'),
+        ALT,
+        Global::ToggleMouse,
+        "Leave the Mouse to the Terminal / Take It Back",
+    ),
+    bind(
+        KeyCode::Insert,
+        NONE,
+        Global::ToggleOverwrite,
+        "Overwrite / Insert Mode",
+    ),
+    bind(KeyCode::F(1), NONE, Global::Menu, "Menu"),
+    #[cfg(feature = "perf")]
+    bind(KeyCode::F(12), NONE, Global::PerfHud, "Frame Timings"),
+    bind(KeyCode::Tab, CTRL, Global::NextTab, "Next Tab"),
+    bind(KeyCode::PageDown, CTRL, Global::NextTab, "Next Tab"),
+    bind(KeyCode::BackTab, SHIFT, Global::PrevTab, "Previous Tab"),
+    bind(KeyCode::PageUp, CTRL, Global::PrevTab, "Previous Tab"),
+];
+
+/// The global command `key` runs, if any
+pub fn lookup(key: &KeyEvent) -> Option<Global> {
+    GLOBAL
+        .iter()
+        .find(|binding| binding.code == key.code && binding.modifiers == key.modifiers)
+        .map(|binding| binding.command)
+}
+
+/// How a key is written in the help, such as `Ctrl+Shift+F`
+pub fn key_label(code: KeyCode, modifiers: KeyModifiers) -> String {
+    let mut label = String::new();
+    for (modifier, name) in [(CTRL, "Ctrl+"), (ALT, "Alt+"), (SHIFT, "Shift+")] {
+        if modifiers.contains(modifier) {
+            label.push_str(name);
+        }
+    }
+    match code {
+        KeyCode::Char(ch) => label.extend(ch.to_uppercase()),
+        KeyCode::F(n) => label.push_str(&format!("F{}", n)),
+        KeyCode::Left => label.push('←'),
+        KeyCode::Right => label.push('→'),
+        KeyCode::PageUp => label.push_str("PageUp"),
+        KeyCode::PageDown => label.push_str("PageDown"),
+        // Crossterm reports Shift+Tab as its own key, with Shift held
+        KeyCode::BackTab => label.push_str("Tab"),
+        code => label.push_str(&format!("{:?}", code)),
+    }
+    label
+}
+
+/// The table as a markdown section for the Help tab
+pub fn cheat_sheet() -> String {
+    let mut rows: Vec<(Global, &str, Vec<String>)> = Vec::new();
+    for binding in GLOBAL {
+        let label = format!("`{}`", key_label(binding.code, binding.modifiers));
+        match rows.last_mut() {
+            Some((command, _, labels)) if *command == binding.command => {
+                if !labels.contains(&label) {
+                    labels.push(label);
+                }
+            }
+            _ => rows.push((binding.command, binding.description, vec![label])),
+        }
+    }
+    let mut markdown = String::from(
+        "## Keys That Work Everywhere\n\n| Action | Shortcut |\n|--------|----------|\n",
+    );
+    for (_, description, labels) in rows {
+        markdown.push_str(&format!("| {} | {} |\n", description, labels.join(" or ")));
+    }
+    markdown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_key_is_bound_twice() {
+        for (index, binding) in GLOBAL.iter().enumerate() {
+            let clash = GLOBAL[index + 1..]
+                .iter()
+                .find(|other| other.code == binding.code && other.modifiers == binding.modifiers);
+            assert!(
+                clash.is_none(),
+                "{} is bound twice",
+                key_label(binding.code, binding.modifiers)
+            );
+        }
+    }
+
+    #[test]
+    fn lists_each_command_once() {
+        let sheet = cheat_sheet();
+        assert!(sheet.contains("| Search in Files | `Ctrl+Shift+F` |\n"));
+        assert!(sheet.contains("| Find Next / Previous | `F3` or `Shift+F3` |\n"));
+        assert!(sheet.contains("| Previous Tab | `Shift+Tab` or `Ctrl+PageUp` |\n"));
+        assert_eq!(
+            lookup(&KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL)),
+            Some(Global::Save)
+        );
+        assert_eq!(
+            lookup(&KeyEvent::new(KeyCode::Char('s'), KeyModifiers::NONE)),
+            None
+        );
+    }
+}
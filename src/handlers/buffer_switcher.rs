@@ -0,0 +1,142 @@
+use crate::app::App;
+use crate::menu::{BufferEntry, MenuState};
+use crate::tab::Tab;
+use crate::ui::buffer_switcher;
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Rect;
+use std::time::Duration;
+
+impl App {
+    /// Open tabs as the switcher lists them
+    fn buffer_entries(&self) -> Vec<BufferEntry> {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        self.tab_manager
+            .tabs()
+            .iter()
+            .enumerate()
+            .map(|(tab_index, tab)| BufferEntry {
+                tab_index,
+                name: tab.name().to_string(),
+                path: tab
+                    .path()
+                    .map(|path| {
+                        path.strip_prefix(&cwd)
+                            .unwrap_or(path)
+                            .display()
+                            .to_string()
+                    })
+                    .unwrap_or_default(),
+                modified: tab.is_modified(),
+            })
+            .collect()
+    }
+
+    /// Open the Ctrl+B switcher listing every open tab
+    pub fn open_buffer_switcher(&mut self) {
+        let entries = self.buffer_entries();
+        self.menu_system
+            .open_buffer_switcher(entries, self.tab_manager.active_index());
+    }
+
+    /// Pick another editor tab to compare the active one with
+    pub fn open_compare_picker(&mut self) {
+        let active = self.tab_manager.active_index();
+        if !matches!(self.tab_manager.active_tab(), Some(Tab::Editor { .. })) {
+            return;
+        }
+        let entries: Vec<BufferEntry> = self
+            .buffer_entries()
+            .into_iter()
+            .filter(|entry| {
+                let tab = self.tab_manager.tabs.get(entry.tab_index);
+                entry.tab_index != active && matches!(tab, Some(Tab::Editor { .. }))
+            })
+            .collect();
+        if entries.is_empty() {
+            self.set_status_message(
+                "No other tab to compare with".to_string(),
+                Duration::from_secs(2),
+            );
+            return;
+        }
+        self.menu_system.open_compare_picker(entries, active);
+    }
+
+    fn switch_to_selected_buffer(&mut self) {
+        let MenuState::BufferSwitcher(state) = &self.menu_system.state else {
+            return;
+        };
+        let selected = state.selected_tab();
+        let compare_with = state.compare_with;
+        self.menu_system.close();
+        match (selected, compare_with) {
+            (Some(index), Some(active)) => self.compare_tabs(active, index),
+            (Some(index), None) => {
+                self.tab_manager.set_active_index(index);
+                self.expand_tree_to_current_file();
+            }
+            _ => {}
+        }
+    }
+
+    pub fn handle_buffer_switcher_key(&mut self, key: KeyEvent) {
+        use crossterm::event::{KeyCode, KeyModifiers};
+
+        let MenuState::BufferSwitcher(state) = &mut self.menu_system.state else {
+            return;
+        };
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('b'), KeyModifiers::CONTROL) => {
+                self.menu_system.close();
+            }
+            (KeyCode::Enter, _) => self.switch_to_selected_buffer(),
+            (KeyCode::Up, _) | (KeyCode::BackTab, _) => state.move_up(),
+            (KeyCode::Down, _) | (KeyCode::Tab, _) => state.move_down(),
+            _ => {
+                // Everything else edits the search box
+                let previous = state.search_input.text().to_string();
+                state.search_input.handle_key(key);
+                if state.search_input.text() != previous {
+                    state.update_filter();
+                }
+            }
+        }
+    }
+
+    /// Clicking an entry switches to it; clicking outside closes the switcher
+    pub fn handle_mouse_on_buffer_switcher(&mut self, mouse: MouseEvent) {
+        let MenuState::BufferSwitcher(state) = &mut self.menu_system.state else {
+            return;
+        };
+        let screen = Rect::new(0, 0, self.terminal_size.0, self.terminal_size.1);
+        let modal = buffer_switcher::modal_area(screen, state);
+        let list = buffer_switcher::list_area(screen, state);
+        let contains = |area: Rect| {
+            mouse.column >= area.x
+                && mouse.column < area.x + area.width
+                && mouse.row >= area.y
+                && mouse.row < area.y + area.height
+        };
+
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if !contains(modal) {
+                    self.menu_system.close();
+                    return;
+                }
+                if !contains(list) {
+                    return;
+                }
+                let first = buffer_switcher::first_visible(state, list.height as usize);
+                let row = first + (mouse.row - list.y) as usize;
+                if row < state.filtered.len() {
+                    state.selected_index = row;
+                    self.switch_to_selected_buffer();
+                }
+            }
+            MouseEventKind::ScrollUp => state.move_up(),
+            MouseEventKind::ScrollDown => state.move_down(),
+            _ => {}
+        }
+    }
+}
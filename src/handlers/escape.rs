@@ -0,0 +1,91 @@
+//! Escape closes the topmost UI layer that is open, and only that one: a
+//! dialog, then a menu or panel, the find bar, the tree's search or inline
+//! rename, the editor's selection, and last the tree's focus, which goes
+//! back to the editor. The key handlers of those layers leave Escape to
+//! this order instead of each checking for it.
+
+use crate::app::{App, FocusMode};
+use crate::menu::MenuState;
+use crate::tab::Tab;
+
+/// The layers Escape closes, topmost first
+#[derive(Debug, Clone, Copy)]
+enum Layer {
+    Dialog,
+    Menu,
+    FindBar,
+    TreeSearch,
+    Selection,
+    TreeFocus,
+}
+
+impl App {
+    fn escape_layer(&self) -> Option<Layer> {
+        let tree_focused = self.focus_mode == FocusMode::TreeView;
+        let tree_busy = self
+            .tree_view
+            .as_ref()
+            .is_some_and(|tree| tree.is_searching || tree.rename.is_some());
+        let tab = self.tab_manager.active_tab();
+        if self.modal.is_some() || matches!(self.menu_system.state, MenuState::InputDialog(_)) {
+            Some(Layer::Dialog)
+        } else if !matches!(self.menu_system.state, MenuState::Closed) {
+            Some(Layer::Menu)
+        } else if matches!(tab, Some(Tab::Editor { find_replace_state, .. })
+            if find_replace_state.active)
+        {
+            Some(Layer::FindBar)
+        } else if tree_focused && tree_busy {
+            Some(Layer::TreeSearch)
+        } else if !tree_focused
+            && matches!(tab, Some(Tab::Editor { cursor, .. }) if cursor.has_selection())
+        {
+            Some(Layer::Selection)
+        } else if tree_focused {
+            Some(Layer::TreeFocus)
+        } else {
+            None
+        }
+    }
+
+    /// Close the topmost layer. Returns false when nothing was open.
+    pub fn handle_escape(&mut self) -> bool {
+        let Some(layer) = self.escape_layer() else {
+            return false;
+        };
+        match layer {
+            Layer::Dialog => match self.modal.take() {
+                Some(modal) => self.run_modal_action(modal.cancel_action()),
+                None => self.cancel_input_dialog(),
+            },
+            Layer::Menu => match self.menu_system.state {
+                MenuState::TodoList(_) => self.close_todo_list(),
+                _ => self.menu_system.close(),
+            },
+            Layer::FindBar => {
+                if let Some(tab) = self.tab_manager.active_tab_mut() {
+                    tab.stop_find_replace();
+                }
+            }
+            Layer::TreeSearch => {
+                if let Some(tree_view) = &mut self.tree_view {
+                    if tree_view.rename.take().is_none() {
+                        tree_view.stop_search();
+                    }
+                }
+            }
+            Layer::Selection => {
+                if let Some(Tab::Editor { cursor, .. }) = self.tab_manager.active_tab_mut() {
+                    cursor.clear_selection();
+                }
+            }
+            Layer::TreeFocus => {
+                self.focus_mode = FocusMode::Editor;
+                if let Some(tree_view) = &mut self.tree_view {
+                    tree_view.is_focused = false;
+                }
+            }
+        }
+        true
+    }
+}
@@ -1,8 +1,16 @@
 use crate::app::App;
-use crate::tab::Tab;
+use crate::ui::Modal;
 use crossterm::event::{KeyEvent, MouseEvent, MouseButton, MouseEventKind};
 
 impl App {
+    /// Keep the open picker's entries in step with files created, removed
+    /// or renamed while it is open; called from the event loop
+    pub fn poll_file_picker(&mut self) {
+        if let crate::menu::MenuState::FilePicker(picker_state) = &mut self.menu_system.state {
+            picker_state.refresh_if_changed();
+        }
+    }
+
     pub fn handle_file_picker_key(&mut self, key: KeyEvent) {
         use crossterm::event::{KeyCode, KeyModifiers};
         if let crate::menu::MenuState::FilePicker(picker_state) = &mut self.menu_system.state {
@@ -12,10 +20,6 @@ impl App {
                     self.menu_system.close();
                     self.handle_quit();
                 }
-                (KeyCode::Esc, KeyModifiers::NONE) => {
-                    // Close file picker
-                    self.menu_system.close();
-                }
                 (KeyCode::Enter, KeyModifiers::NONE) | (KeyCode::Right, KeyModifiers::NONE) => {
                     // Enter directory or open file
                     if let Some(selected_item) = picker_state.get_selected_item() {
@@ -24,7 +28,7 @@ impl App {
                             picker_state.enter_directory(selected_item.path.clone());
                         } else {
                             // Open file
-                            match std::fs::read(&selected_item.path) {
+                            match crate::vfs::read(&selected_item.path) {
                                 Ok(bytes) => {
                                     // Try to convert to string, if it fails show warning
                                     match String::from_utf8(bytes) {
@@ -34,23 +38,23 @@ impl App {
                                                 selected_item.path.clone(),
                                                 &text,
                                             );
-                                            if let Tab::Editor { word_wrap, .. } = &mut new_tab {
-                                                *word_wrap = self.global_word_wrap;
-                                            }
+                                            new_tab.apply_view_defaults(self.view_defaults);
+                                            let target_line = picker_state.target_line();
                                             self.tab_manager.add_tab(new_tab);
                                             self.menu_system.close();
+                                            if let Some(line) = target_line {
+                                                self.go_to_line(line);
+                                            }
                                         }
                                         Err(_) => {
                                             // Binary file - show warning, don't open
-                                            let size = std::fs::metadata(&selected_item.path)
-                                                .map(|m| m.len())
+                                            let size = crate::vfs::metadata(&selected_item.path)
+                                                .map(|m| m.len)
                                                 .unwrap_or(0);
-                                            self.warning_message = Some(format!(
+                                            self.modal = Some(Modal::info(&format!(
                                                 "Cannot open binary file '{}' ({} bytes)",
                                                 selected_item.name, size
-                                            ));
-                                            self.warning_selected_button = 0;
-                                            self.warning_is_info = true;
+                                            )));
                                             // Close file picker but don't open the file
                                             self.menu_system.close();
                                         }
@@ -58,12 +62,10 @@ impl App {
                                 }
                                 Err(e) => {
                                     // Error reading file
-                                    self.warning_message = Some(format!(
+                                    self.modal = Some(Modal::info(&format!(
                                         "Cannot read file '{}': {}",
                                         selected_item.name, e
-                                    ));
-                                    self.warning_selected_button = 0;
-                                    self.warning_is_info = true;
+                                    )));
                                     // Close file picker
                                     self.menu_system.close();
                                 }
@@ -71,7 +73,9 @@ impl App {
                         }
                     }
                 }
-                (KeyCode::Left, KeyModifiers::NONE) | (KeyCode::Backspace, KeyModifiers::NONE) => {
+                (KeyCode::Left, KeyModifiers::NONE) | (KeyCode::Backspace, KeyModifiers::NONE)
+                    if picker_state.search_input.is_empty() =>
+                {
                     // Go back to parent directory
                     picker_state.go_up();
                 }
@@ -93,7 +97,14 @@ impl App {
                 (KeyCode::End, KeyModifiers::NONE) => {
                     picker_state.move_to_end();
                 }
-                _ => {}
+                _ => {
+                    // Everything else edits the search box
+                    let previous = picker_state.search_input.text().to_string();
+                    picker_state.search_input.handle_key(key);
+                    if picker_state.search_input.text() != previous {
+                        picker_state.update_filter();
+                    }
+                }
             }
         }
     }
@@ -162,13 +173,13 @@ impl App {
                 }
                 MouseEventKind::ScrollUp => {
                     if let crate::menu::MenuState::FilePicker(picker_state) = &mut self.menu_system.state {
-                        picker_state.move_up();
+                        picker_state.scroll_up();
                     }
                     return true;
                 }
                 MouseEventKind::ScrollDown => {
                     if let crate::menu::MenuState::FilePicker(picker_state) = &mut self.menu_system.state {
-                        picker_state.move_down();
+                        picker_state.scroll_down();
                     }
                     return true;
                 }
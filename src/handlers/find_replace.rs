@@ -1,6 +1,8 @@
 use crate::app::App;
 use crate::tab::{Tab, FindFocusedField};
+use crate::ui::{FindBarLayout, FindBarTarget};
 use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Rect;
 use std::time::Duration;
 
 impl App {
@@ -22,12 +24,6 @@ impl App {
         }
 
         match (key.code, key.modifiers) {
-            // ESC to close find/replace
-            (KeyCode::Esc, KeyModifiers::NONE) => {
-                tab.stop_find_replace();
-                return true;
-            }
-
             // Tab to switch between find and replace fields
             (KeyCode::Tab, KeyModifiers::NONE) => {
                 if let Tab::Editor { find_replace_state, .. } = tab {
@@ -44,6 +40,7 @@ impl App {
             // Enter or F3 for next match
             (KeyCode::Enter, KeyModifiers::NONE) | (KeyCode::F(3), KeyModifiers::NONE) => {
                 let has_matches = if let Tab::Editor { find_replace_state, .. } = tab {
+                    find_replace_state.find_input.commit_to_history();
                     !find_replace_state.matches.is_empty()
                 } else {
                     false
@@ -102,6 +99,15 @@ impl App {
                 return true;
             }
 
+            // Alt+E to toggle matching "é" with "e"
+            (KeyCode::Char('e'), KeyModifiers::ALT) | (KeyCode::Char('E'), KeyModifiers::ALT) => {
+                if let Tab::Editor { find_replace_state, .. } = tab {
+                    find_replace_state.ignore_accents = !find_replace_state.ignore_accents;
+                    tab.perform_find();
+                }
+                return true;
+            }
+
             // Alt+W to toggle whole word
             (KeyCode::Char('w'), KeyModifiers::ALT) | (KeyCode::Char('W'), KeyModifiers::ALT) => {
                 if let Tab::Editor { find_replace_state, .. } = tab {
@@ -111,6 +117,20 @@ impl App {
                 return true;
             }
 
+            // Alt+B to toggle searching only the block around the cursor
+            (KeyCode::Char('b'), KeyModifiers::ALT) | (KeyCode::Char('B'), KeyModifiers::ALT) => {
+                self.toggle_find_in_block();
+                return true;
+            }
+
+            // Alt+A to toggle highlighting every match
+            (KeyCode::Char('a'), KeyModifiers::ALT) | (KeyCode::Char('A'), KeyModifiers::ALT) => {
+                if let Tab::Editor { find_replace_state, .. } = tab {
+                    find_replace_state.highlight_all = !find_replace_state.highlight_all;
+                }
+                return true;
+            }
+
             // Ctrl+H to toggle replace mode
             (KeyCode::Char('h'), KeyModifiers::CONTROL) => {
                 if let Tab::Editor { find_replace_state, .. } = tab {
@@ -128,6 +148,7 @@ impl App {
             // Ctrl+R to replace current
             (KeyCode::Char('r'), KeyModifiers::CONTROL) => {
                 let is_replace_mode = if let Tab::Editor { find_replace_state, .. } = tab {
+                    find_replace_state.replace_input.commit_to_history();
                     find_replace_state.is_replace_mode
                 } else {
                     false
@@ -153,58 +174,144 @@ impl App {
                 return true;
             }
 
-            // Character input for find/replace fields
-            (KeyCode::Char(c), KeyModifiers::NONE) | (KeyCode::Char(c), KeyModifiers::SHIFT) => {
-                if let Tab::Editor { find_replace_state, .. } = tab {
-                    match find_replace_state.focused_field {
-                        FindFocusedField::Find => {
-                            find_replace_state
-                                .find_query
-                                .insert(find_replace_state.find_cursor_position, c);
-                            find_replace_state.find_cursor_position += 1;
+            _ => {}
+        }
+
+        // Remaining keys edit the focused input field
+        if let Tab::Editor { find_replace_state, .. } = tab {
+            match find_replace_state.focused_field {
+                FindFocusedField::Find => {
+                    let previous = find_replace_state.find_input.text().to_string();
+                    if find_replace_state.find_input.handle_key(key) {
+                        if find_replace_state.find_input.text() != previous {
                             tab.perform_find();
                         }
-                        FindFocusedField::Replace => {
-                            find_replace_state
-                                .replace_query
-                                .insert(find_replace_state.replace_cursor_position, c);
-                            find_replace_state.replace_cursor_position += 1;
-                        }
+                        return true;
+                    }
+                }
+                FindFocusedField::Replace => {
+                    if find_replace_state.replace_input.handle_key(key) {
+                        return true;
                     }
                 }
-                return true;
             }
-
-            _ => {}
         }
 
         false
     }
 
+    /// Limit the search to the block around the cursor or lift the limit,
+    /// saying so when the cursor is in no block
+    fn toggle_find_in_block(&mut self) {
+        let Some(tab) = self.tab_manager.active_tab_mut() else {
+            return;
+        };
+        if !tab.toggle_find_in_block() {
+            self.set_status_message("Not inside a block".to_string(), Duration::from_secs(2));
+        }
+    }
+
+    /// The find bar control under a screen cell, if the bar is open
+    pub fn find_bar_target_at(&self, column: u16, row: u16) -> Option<FindBarTarget> {
+        let replace_mode = match self.tab_manager.active_tab() {
+            Some(Tab::Editor { find_replace_state, .. }) if find_replace_state.active => {
+                find_replace_state.is_replace_mode
+            }
+            _ => return None,
+        };
+        FindBarLayout::new(self.find_bar_area(replace_mode), replace_mode).target_at(column, row)
+    }
+
+    /// Screen area of the find/replace bar: the top of the editor pane
+    fn find_bar_area(&self, replace_mode: bool) -> Rect {
+        let x = if self.tree_view.is_some() {
+            self.sidebar.columns()
+        } else {
+            0
+        };
+        Rect {
+            x,
+            y: 1,
+            width: self.terminal_size.0.saturating_sub(x),
+            height: FindBarLayout::height(replace_mode),
+        }
+    }
+
     pub fn handle_mouse_on_find_replace(&mut self, mouse: MouseEvent) -> bool {
-        if let MouseEventKind::Down(MouseButton::Left) = mouse.kind {
-            // Check if click is on find/replace bar
-            let find_bar_row = self.terminal_size.1.saturating_sub(2);
-            
-            if mouse.row == find_bar_row {
-                // Handle clicks on find/replace controls
-                if let Some(tab) = self.tab_manager.active_tab_mut() {
-                    if let Tab::Editor { find_replace_state, .. } = tab {
-                        if find_replace_state.active {
-                            // Simple field switching based on click position
-                            let half_width = self.terminal_size.0 / 2;
-                            
-                            if find_replace_state.is_replace_mode && mouse.column > half_width {
-                                find_replace_state.focused_field = FindFocusedField::Replace;
-                            } else {
-                                find_replace_state.focused_field = FindFocusedField::Find;
-                            }
-                            return true;
-                        }
-                    }
+        if mouse.kind != MouseEventKind::Down(MouseButton::Left) {
+            return false;
+        }
+
+        let replace_mode = match self.tab_manager.active_tab() {
+            Some(Tab::Editor { find_replace_state, .. }) if find_replace_state.active => {
+                find_replace_state.is_replace_mode
+            }
+            _ => return false,
+        };
+        let area = self.find_bar_area(replace_mode);
+        if mouse.row < area.y || mouse.row >= area.y + area.height || mouse.column < area.x {
+            return false;
+        }
+        let layout = FindBarLayout::new(area, replace_mode);
+        let Some(target) = layout.target_at(mouse.column, mouse.row) else {
+            // Clicks on the bar's background are swallowed
+            return true;
+        };
+
+        let Some(tab) = self.tab_manager.active_tab_mut() else {
+            return true;
+        };
+        let Tab::Editor { find_replace_state, .. } = tab else {
+            return true;
+        };
+        match target {
+            FindBarTarget::FindInput => {
+                find_replace_state.focused_field = FindFocusedField::Find;
+            }
+            FindBarTarget::ReplaceInput => {
+                find_replace_state.focused_field = FindFocusedField::Replace;
+            }
+            FindBarTarget::CaseSensitive => {
+                find_replace_state.case_sensitive = !find_replace_state.case_sensitive;
+                tab.perform_find();
+            }
+            FindBarTarget::IgnoreAccents => {
+                find_replace_state.ignore_accents = !find_replace_state.ignore_accents;
+                tab.perform_find();
+            }
+            FindBarTarget::WholeWord => {
+                find_replace_state.whole_word = !find_replace_state.whole_word;
+                tab.perform_find();
+            }
+            FindBarTarget::InBlock => {
+                self.toggle_find_in_block();
+                self.ensure_cursor_visible();
+            }
+            FindBarTarget::HighlightAll => {
+                find_replace_state.highlight_all = !find_replace_state.highlight_all;
+            }
+            FindBarTarget::FindNext => {
+                find_replace_state.find_input.commit_to_history();
+                if !find_replace_state.matches.is_empty() {
+                    tab.find_next();
+                    self.ensure_cursor_visible();
                 }
             }
+            FindBarTarget::Replace => {
+                find_replace_state.replace_input.commit_to_history();
+                tab.replace_current();
+                self.ensure_cursor_visible();
+            }
+            FindBarTarget::ReplaceAll => {
+                find_replace_state.replace_input.commit_to_history();
+                let count = find_replace_state.matches.len();
+                tab.replace_all();
+                self.set_status_message(
+                    format!("Replaced {} matches", count),
+                    Duration::from_secs(2),
+                );
+            }
         }
-        false
+        true
     }
-}
\ No newline at end of file
+}
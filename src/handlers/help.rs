@@ -0,0 +1,63 @@
+use crate::app::{App, FocusMode};
+use crate::help;
+use crate::tab::Tab;
+use crossterm::event::{KeyCode, KeyEvent};
+use std::path::PathBuf;
+use std::time::Duration;
+
+impl App {
+    /// Switch to the Help tab, opening it if it isn't
+    pub fn open_help_tab(&mut self) {
+        let open = self.tab_manager.tabs.iter().position(
+            |tab| matches!(tab, Tab::Editor { name, path: None, .. } if name == help::TAB_NAME),
+        );
+        match open {
+            Some(index) => self.tab_manager.set_active_index(index),
+            None => {
+                let mut tab = help::tab();
+                tab.apply_view_defaults(self.view_defaults);
+                self.tab_manager.add_tab(tab);
+            }
+        }
+        self.focus_mode = FocusMode::Editor;
+        if let Some(tree_view) = &mut self.tree_view {
+            tree_view.is_focused = false;
+        }
+    }
+
+    pub fn handle_main_menu_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::F(1) => self.menu_system.close(),
+            KeyCode::Up => self.menu_system.handle_up(),
+            KeyCode::Down => self.menu_system.handle_down(),
+            KeyCode::Enter => {
+                if let Some(action) = self.menu_system.handle_enter() {
+                    self.run_main_menu_action(&action);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn run_main_menu_action(&mut self, action: &str) {
+        match action {
+            "show_help" => self.open_help_tab(),
+            "toggle_share" => self.toggle_sharing(),
+            "follow_session" => self.menu_system.open_input_dialog(
+                "Follow session at (host:port):".to_string(),
+                "follow_session".to_string(),
+                PathBuf::new(),
+            ),
+            "coedit_session" => self.menu_system.open_input_dialog(
+                "Co-edit session at (host:port):".to_string(),
+                "coedit_session".to_string(),
+                PathBuf::new(),
+            ),
+            "show_about" => self.set_status_message(
+                format!("f1 {}", env!("CARGO_PKG_VERSION")),
+                Duration::from_secs(4),
+            ),
+            _ => {}
+        }
+    }
+}
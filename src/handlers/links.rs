@@ -0,0 +1,57 @@
+use crate::app::App;
+use crate::handlers::tree_menu::external_open_command;
+use crate::links::{self, Link};
+use crate::tab::Tab;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+impl App {
+    /// Open the link at the cursor of the active tab
+    pub fn open_link_under_cursor(&mut self) {
+        if let Some(Tab::Editor { cursor, .. }) = self.tab_manager.active_tab() {
+            let position = cursor.position;
+            self.open_link_at(position.line, position.column);
+        }
+    }
+
+    /// Open the link at a buffer position: files as tabs, URLs in the browser
+    pub fn open_link_at(&mut self, line: usize, column: usize) {
+        let Some(Tab::Editor { buffer, path, .. }) = self.tab_manager.active_tab() else {
+            return;
+        };
+        let base = path
+            .as_deref()
+            .and_then(Path::parent)
+            .map(Path::to_path_buf);
+
+        match links::link_at(&buffer.get_line_text(line), column) {
+            Some(Link::Url(url)) => {
+                let message = format!("Opened {}", url);
+                self.run_opener(external_open_command(Path::new(&url), false), &url, message);
+            }
+            Some(Link::File { path, line }) => match links::resolve(&path, base.as_deref()) {
+                Some(file) => self.open_linked_file(file, line),
+                None => self.set_status_message(
+                    format!("No file found for {}", path),
+                    Duration::from_secs(2),
+                ),
+            },
+            None => self.set_status_message(
+                "No link under the cursor".to_string(),
+                Duration::from_secs(2),
+            ),
+        }
+    }
+
+    pub(crate) fn open_linked_file(&mut self, path: PathBuf, line: Option<usize>) {
+        match std::fs::read_to_string(&path) {
+            Ok(content) => {
+                self.open_file_in_tab(path, &content);
+                if let Some(line) = line {
+                    self.go_to_line(line);
+                }
+            }
+            Err(e) => self.report_error(format!("Failed to open {}: {}", path.display(), e)),
+        }
+    }
+}
@@ -1,14 +1,20 @@
-use crate::app::App;
+use super::bindings::{self, Global};
+use crate::app::{App, FocusMode};
+use crate::marks;
 use crate::tab::Tab;
-use crossterm::event::KeyEvent;
+use crossterm::event::{KeyEvent, KeyModifiers};
 
 impl App {
     pub fn handle_key_event(&mut self, key: KeyEvent) -> bool {
         use crossterm::event::{KeyCode, KeyModifiers};
 
-        // Handle warning dialog first
-        if self.warning_message.is_some() {
-            self.handle_warning_key(key);
+        if key.code == KeyCode::Esc && self.handle_escape() {
+            return false;
+        }
+
+        // Handle modal dialog first
+        if self.modal.is_some() {
+            self.handle_modal_key(key);
             return false;
         }
 
@@ -18,12 +24,42 @@ impl App {
             return false;
         }
 
+        if let crate::menu::MenuState::BufferSwitcher(_) = &self.menu_system.state {
+            self.handle_buffer_switcher_key(key);
+            return false;
+        }
+
+        if let crate::menu::MenuState::ProjectSearch(_) = &self.menu_system.state {
+            self.handle_project_search_key(key);
+            return false;
+        }
+
+        if let crate::menu::MenuState::TodoList(_) = &self.menu_system.state {
+            self.handle_todo_list_key(key);
+            return false;
+        }
+
         // Handle input dialog
         if let crate::menu::MenuState::InputDialog(_) = &self.menu_system.state {
             self.handle_input_dialog_key(key);
             return false;
         }
 
+        if let crate::menu::MenuState::TreeContextMenu(_) = &self.menu_system.state {
+            self.handle_tree_menu_key(key);
+            return false;
+        }
+
+        if let crate::menu::MenuState::CurrentTabMenu(_) = &self.menu_system.state {
+            self.handle_tab_menu_key(key);
+            return false;
+        }
+
+        if let crate::menu::MenuState::MainMenu(_) = &self.menu_system.state {
+            self.handle_main_menu_key(key);
+            return false;
+        }
+
         // Check if find/replace is active
         let is_find_active = if let Some(tab) = self.tab_manager.active_tab() {
             match tab {
@@ -36,91 +72,126 @@ impl App {
 
         // Handle find/replace keys if active
         if is_find_active && self.handle_find_replace_key(key) {
+            // Search jumps respect the scroll margin like any cursor move
+            self.ensure_cursor_visible();
             return true;
         }
 
-        // Handle global commands
-        match (key.code, key.modifiers) {
-            (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
-                self.handle_quit();
-                return true;
-            }
-            (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
-                self.save_current_file();
-                return true;
-            }
-            (KeyCode::Char('w'), KeyModifiers::CONTROL) => {
-                self.handle_close_tab();
-                return true;
-            }
-            (KeyCode::Char('n'), KeyModifiers::CONTROL) => {
-                self.create_new_tab();
-                return true;
-            }
-            (KeyCode::Char('t'), KeyModifiers::CONTROL) => {
-                self.create_new_terminal_tab();
-                return true;
-            }
-            (KeyCode::Char('f'), KeyModifiers::CONTROL) => {
-                if let Some(tab) = self.tab_manager.active_tab_mut() {
-                    tab.start_find();
-                }
-                return true;
-            }
-            (KeyCode::Char('h'), KeyModifiers::CONTROL) => {
-                if let Some(tab) = self.tab_manager.active_tab_mut() {
-                    tab.start_find_replace();
-                }
-                return true;
-            }
-            (KeyCode::F(1), KeyModifiers::NONE) => {
-                self.menu_system.toggle_help();
-                return true;
-            }
-            (KeyCode::Tab, KeyModifiers::CONTROL) => {
-                self.switch_next_tab();
-                return true;
-            }
-            (KeyCode::BackTab, KeyModifiers::SHIFT) => {
-                self.switch_prev_tab();
-                return true;
+        if self.pager && self.focus_mode == FocusMode::Editor && self.handle_pager_key(key) {
+            return true;
+        }
+
+        if let Some((slot, assign)) = marks::slot_for_key(key.code, key.modifiers) {
+            if assign {
+                self.set_mark(slot);
+            } else {
+                self.jump_to_mark(slot);
             }
-            _ => {}
+            return true;
+        }
+
+        // Handle global commands
+        if let Some(command) = bindings::lookup(&key) {
+            self.run_global_command(command, key);
+            return true;
         }
 
         // Handle tree view commands when focused
         if self.focus_mode == crate::app::FocusMode::TreeView {
+            if self.tree_view.as_ref().is_some_and(|tree_view| tree_view.rename.is_some()) {
+                self.handle_inline_rename_key(key);
+                return true;
+            }
             if let Some(tree_view) = &mut self.tree_view {
+                // While searching, text keys edit the query; navigation keys fall through
+                if tree_view.is_searching {
+                    match key.code {
+                        KeyCode::Enter | KeyCode::Up | KeyCode::Down => {}
+                        _ => {
+                            tree_view.handle_search_key(key);
+                            return true;
+                        }
+                    }
+                }
+
                 match (key.code, key.modifiers) {
+                    (KeyCode::Char('/'), KeyModifiers::NONE) => {
+                        tree_view.start_search();
+                        return true;
+                    }
                     (KeyCode::Char('e'), KeyModifiers::NONE) | (KeyCode::Enter, KeyModifiers::NONE) => {
                         if let Some(selected_item) = tree_view.get_selected_item() {
-                            if !selected_item.is_dir {
+                            if !selected_item.is_dir && selected_item.placeholder.is_none() {
                                 // Open file in new tab
-                                match std::fs::read_to_string(&selected_item.path) {
+                                match crate::vfs::read_to_string(&selected_item.path) {
                                     Ok(content) => {
                                         let mut new_tab = Tab::from_file(selected_item.path.clone(), &content);
-                                        if let Tab::Editor { word_wrap, .. } = &mut new_tab {
-                                            *word_wrap = self.global_word_wrap;
-                                        }
+                                        new_tab.apply_view_defaults(self.view_defaults);
                                         self.tab_manager.add_tab(new_tab);
                                         self.focus_mode = crate::app::FocusMode::Editor;
                                         tree_view.is_focused = false;
                                     }
                                     Err(e) => {
-                                        self.set_status_message(
-                                            format!("Failed to open file: {}", e),
-                                            std::time::Duration::from_secs(3),
-                                        );
+                                        self.report_error(format!("Failed to open file: {}", e));
                                     }
                                 }
                             } else {
-                                tree_view.toggle_directory();
+                                self.toggle_tree_directory();
                             }
                         }
                         return true;
                     }
                     (KeyCode::Char(' '), KeyModifiers::NONE) => {
-                        tree_view.toggle_directory();
+                        self.toggle_tree_directory();
+                        return true;
+                    }
+                    (KeyCode::Char('-'), KeyModifiers::NONE) => {
+                        tree_view.collapse_all();
+                        return true;
+                    }
+                    (KeyCode::Char('*'), _) => {
+                        self.expand_tree_all();
+                        return true;
+                    }
+                    (KeyCode::Home, KeyModifiers::NONE) => {
+                        tree_view.go_to_root();
+                        return true;
+                    }
+                    (KeyCode::F(2), KeyModifiers::NONE) => {
+                        if let Some(path) = tree_view.get_selected_item().map(|item| item.path.clone()) {
+                            self.open_rename_dialog(path);
+                        }
+                        return true;
+                    }
+                    (KeyCode::Char('c'), KeyModifiers::CONTROL) => {
+                        tree_view.copy_selected();
+                        if let Some(info) = tree_view.get_clipboard_info() {
+                            self.set_status_message(info, std::time::Duration::from_secs(2));
+                        }
+                        return true;
+                    }
+                    (KeyCode::Char('x'), KeyModifiers::CONTROL) => {
+                        tree_view.cut_selected();
+                        if let Some(info) = tree_view.get_clipboard_info() {
+                            self.set_status_message(info, std::time::Duration::from_secs(2));
+                        }
+                        return true;
+                    }
+                    (KeyCode::Char('v'), KeyModifiers::CONTROL) => {
+                        self.start_paste();
+                        return true;
+                    }
+                    (KeyCode::Char('c') | KeyCode::Char('C'), m) if m == KeyModifiers::ALT | KeyModifiers::SHIFT => {
+                        match tree_view.copy_path_to_clipboard() {
+                            Ok(message) => self.set_status_message(message, std::time::Duration::from_secs(2)),
+                            Err(message) => self.report_error(message),
+                        }
+                        return true;
+                    }
+                    (KeyCode::Delete, KeyModifiers::NONE) => {
+                        if let Some(path) = tree_view.get_selected_item().map(|item| item.path.clone()) {
+                            self.confirm_delete(path);
+                        }
                         return true;
                     }
                     (KeyCode::Up, KeyModifiers::NONE) => {
@@ -131,20 +202,100 @@ impl App {
                         tree_view.move_down();
                         return true;
                     }
-                    (KeyCode::Esc, KeyModifiers::NONE) => {
-                        self.focus_mode = crate::app::FocusMode::Editor;
-                        tree_view.is_focused = false;
-                        return true;
-                    }
                     _ => {}
                 }
             }
         }
 
+        // Undo/redo
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('z'), KeyModifiers::CONTROL) => {
+                self.handle_command(crate::keyboard::EditorCommand::Undo);
+                return true;
+            }
+            (KeyCode::Char('y'), KeyModifiers::CONTROL) => {
+                self.handle_command(crate::keyboard::EditorCommand::Redo);
+                return true;
+            }
+            (KeyCode::Char('z'), m) if m == KeyModifiers::CONTROL | KeyModifiers::SHIFT => {
+                self.handle_command(crate::keyboard::EditorCommand::Redo);
+                return true;
+            }
+            _ => {}
+        }
+
+        // Structural selection and comments
+        if let Some(tab) = self.tab_manager.active_tab_mut() {
+            match (key.code, key.modifiers) {
+                (KeyCode::Right, m) if m == KeyModifiers::ALT | KeyModifiers::SHIFT => {
+                    tab.expand_selection();
+                    return true;
+                }
+                (KeyCode::Left, m) if m == KeyModifiers::ALT | KeyModifiers::SHIFT => {
+                    tab.shrink_selection();
+                    return true;
+                }
+                // Terminals send Ctrl+/ as Ctrl+7
+                (KeyCode::Char('/') | KeyCode::Char('7'), KeyModifiers::CONTROL) => {
+                    if !tab.toggle_comment() {
+                        let message = format!("{} has no comment syntax", tab.language().name);
+                        self.set_status_message(message, std::time::Duration::from_secs(2));
+                    }
+                    return true;
+                }
+                (KeyCode::Char('m') | KeyCode::Char('M'), m)
+                    if m == KeyModifiers::CONTROL | KeyModifiers::SHIFT =>
+                {
+                    tab.jump_to_matching_bracket();
+                    let height = (self.terminal_size.1 as usize).saturating_sub(2);
+                    tab.update_viewport(self.ui.text_width, height);
+                    return true;
+                }
+                _ => {}
+            }
+        }
+
+        if let (KeyCode::Enter, KeyModifiers::ALT) = (key.code, key.modifiers) {
+            self.open_link_under_cursor();
+            return true;
+        }
+
+        if let (KeyCode::F(2), KeyModifiers::NONE) = (key.code, key.modifiers) {
+            self.rename_occurrences();
+            return true;
+        }
+
+        let is_edit = matches!(
+            (key.code, key.modifiers),
+            (KeyCode::Char(_), KeyModifiers::NONE)
+                | (KeyCode::Char(_), KeyModifiers::SHIFT)
+                | (KeyCode::Enter, KeyModifiers::NONE)
+                | (KeyCode::Tab, KeyModifiers::NONE)
+                | (KeyCode::Char('v'), KeyModifiers::CONTROL)
+                | (KeyCode::Backspace, KeyModifiers::NONE)
+                | (KeyCode::Delete, KeyModifiers::NONE)
+        );
+        if is_edit && self.tab_manager.active_tab().is_some_and(Tab::is_read_only) {
+            self.set_status_message(
+                "This file is read-only".to_string(),
+                std::time::Duration::from_secs(2),
+            );
+            self.bell();
+            return true;
+        }
+
         // Handle editor commands
         if let Some(tab) = self.tab_manager.active_tab_mut() {
+            // Snapshot for undo before the buffer changes
+            if is_edit && !self.in_typed_text {
+                tab.begin_edit();
+            }
+
+            let language = tab.language();
             match tab {
-                Tab::Editor { cursor, buffer, .. } => {
+                Tab::Editor { cursor, buffer, indent, view, .. } => {
+                    let indent = *indent;
+                    let word_wrap = view.word_wrap;
                     match (key.code, key.modifiers) {
                         // Navigation
                         (KeyCode::Left, KeyModifiers::NONE) => {
@@ -153,6 +304,13 @@ impl App {
                         (KeyCode::Right, KeyModifiers::NONE) => {
                             cursor.move_right(buffer);
                         }
+                        // With wrap on, Up and Down move by screen row
+                        (KeyCode::Up, KeyModifiers::NONE) if word_wrap => {
+                            cursor.move_up_visual(buffer, self.ui.text_width);
+                        }
+                        (KeyCode::Down, KeyModifiers::NONE) if word_wrap => {
+                            cursor.move_down_visual(buffer, self.ui.text_width);
+                        }
                         (KeyCode::Up, KeyModifiers::NONE) => {
                             cursor.move_up(buffer);
                         }
@@ -175,9 +333,17 @@ impl App {
                         }
                         // Text editing
                         (KeyCode::Char(c), KeyModifiers::NONE) | (KeyCode::Char(c), KeyModifiers::SHIFT) => {
+                            let char_idx = buffer.line_to_char(cursor.position.line) + cursor.position.column;
                             if cursor.has_selection() {
                                 Self::delete_selection(buffer, cursor);
+                            } else if self.overwrite_mode {
+                                // Replace the character under the cursor, but never the line break
+                                let line_len = buffer.get_line_text(cursor.position.line).chars().count();
+                                if cursor.position.column < line_len {
+                                    buffer.delete_char(char_idx);
+                                }
                             }
+                            // The selection may have moved the cursor
                             let char_idx = buffer.line_to_char(cursor.position.line) + cursor.position.column;
                             buffer.insert_char(char_idx, c);
                             cursor.move_right(buffer);
@@ -187,17 +353,18 @@ impl App {
                             if cursor.has_selection() {
                                 Self::delete_selection(buffer, cursor);
                             }
-                            let char_idx = buffer.line_to_char(cursor.position.line) + cursor.position.column;
-                            buffer.insert_char(char_idx, '\n');
-                            cursor.move_down(buffer);
-                            cursor.move_to_line_start();
+                            Self::insert_newline(buffer, cursor, indent, language);
+                            tab.mark_modified();
+                        }
+                        (KeyCode::Char('v'), KeyModifiers::CONTROL) => {
+                            crate::keyboard::paste_from_clipboard(buffer, cursor, indent);
                             tab.mark_modified();
                         }
                         (KeyCode::Tab, KeyModifiers::NONE) => {
                             if cursor.has_selection() {
                                 Self::delete_selection(buffer, cursor);
                             }
-                            Self::insert_tab(buffer, cursor);
+                            Self::insert_tab(buffer, cursor, indent);
                             tab.mark_modified();
                         }
                         (KeyCode::Backspace, KeyModifiers::NONE) => {
@@ -229,7 +396,8 @@ impl App {
                         }
                         _ => {}
                     }
-                    tab.update_viewport((self.terminal_size.1 as usize).saturating_sub(2));
+                    let height = (self.terminal_size.1 as usize).saturating_sub(2);
+                    tab.update_viewport(self.ui.text_width, height);
                 }
                 Tab::Terminal { .. } => {
                     // Terminal handles its own key events
@@ -239,4 +407,77 @@ impl App {
 
         true
     }
-}
\ No newline at end of file
+
+    /// Run a key from the global binding table
+    fn run_global_command(&mut self, command: Global, key: KeyEvent) {
+        match command {
+            Global::Quit => self.handle_quit(),
+            Global::Save => self.save_current_file(),
+            Global::CloseTab => self.handle_close_tab(),
+            Global::NewTab => self.create_new_tab(),
+            Global::NewScratch => self.create_scratch_tab(),
+            Global::Compare => self.open_compare_picker(),
+            Global::Export => self.open_export_dialog(),
+            Global::Todos => self.open_todo_list(),
+            Global::SearchInFiles => self.open_project_search(),
+            Global::NewTerminal => self.create_new_terminal_tab(),
+            Global::SwitchBuffer => self.open_buffer_switcher(),
+            Global::ToggleSidebar => self.toggle_sidebar(),
+            Global::NarrowSidebar => self.resize_sidebar_by(-crate::sidebar::STEP),
+            Global::WidenSidebar => self.resize_sidebar_by(crate::sidebar::STEP),
+            Global::Find => {
+                if let Some(tab) = self.tab_manager.active_tab_mut() {
+                    tab.start_find();
+                }
+            }
+            // The find bar handles these itself while it is open
+            Global::FindAgain => {
+                let Some(tab) = self.tab_manager.active_tab_mut() else {
+                    return;
+                };
+                let searched = matches!(tab, Tab::Editor { find_replace_state, .. }
+                    if !find_replace_state.find_input.is_empty());
+                if !searched {
+                    tab.start_find();
+                } else if tab.find_again(key.modifiers == KeyModifiers::SHIFT) {
+                    self.ensure_cursor_visible();
+                } else {
+                    self.set_status_message(
+                        "No matches".to_string(),
+                        std::time::Duration::from_secs(2),
+                    );
+                }
+            }
+            Global::FindReplace => {
+                if let Some(tab) = self.tab_manager.active_tab_mut() {
+                    tab.start_find_replace();
+                }
+            }
+            // Ctrl+Z is undo
+            Global::Suspend => self.suspend_requested = true,
+            Global::CancelJobs => {
+                if !self.jobs.is_idle() {
+                    self.jobs.cancel_all();
+                    self.set_status_message(
+                        "Cancelling background jobs...".to_string(),
+                        std::time::Duration::from_secs(2),
+                    );
+                }
+            }
+            Global::ToggleLog => self.messages.toggle_log(),
+            Global::ToggleMouse => self.toggle_mouse_capture(),
+            Global::ToggleOverwrite => {
+                self.overwrite_mode = !self.overwrite_mode;
+                // Typing in the other mode starts a new undo step
+                if let Some(tab) = self.tab_manager.active_tab_mut() {
+                    tab.end_edit_group();
+                }
+            }
+            Global::Menu => self.menu_system.toggle_help(self.share_host.is_some()),
+            #[cfg(feature = "perf")]
+            Global::PerfHud => crate::perf::toggle_hud(),
+            Global::NextTab => self.switch_next_tab(),
+            Global::PrevTab => self.switch_prev_tab(),
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::app::App;
+use crate::marks::{self, Mark};
+use crate::tab::Tab;
+use std::time::Duration;
+
+impl App {
+    /// Remember the active file and cursor position in a slot
+    pub fn set_mark(&mut self, slot: usize) {
+        let Some(Tab::Editor {
+            path: Some(path),
+            cursor,
+            name,
+            ..
+        }) = self.tab_manager.active_tab()
+        else {
+            self.set_status_message(
+                "Only saved files can go in a slot".to_string(),
+                Duration::from_secs(2),
+            );
+            return;
+        };
+        let mark = Mark {
+            path: path.clone(),
+            line: cursor.position.line,
+            column: cursor.position.column,
+        };
+        let message = format!("Slot {}: {}:{}", slot + 1, name, mark.line + 1);
+        match marks::set(slot, mark) {
+            Ok(()) => self.set_status_message(message, Duration::from_secs(2)),
+            Err(e) => self.report_error(format!("Failed to save slot {}: {}", slot + 1, e)),
+        }
+        self.refresh_mark_annotations();
+    }
+
+    /// Number the lines the slots point at in every open file's gutter
+    fn refresh_mark_annotations(&mut self) {
+        for tab in &mut self.tab_manager.tabs {
+            if let Tab::Editor {
+                path: Some(path),
+                annotations,
+                ..
+            } = tab
+            {
+                annotations.set(marks::ANNOTATION_SOURCE, marks::annotations(path));
+            }
+        }
+    }
+
+    /// Open the file in a slot at the position it was marked at
+    pub fn jump_to_mark(&mut self, slot: usize) {
+        let Some(mark) = marks::get(slot) else {
+            self.set_status_message(
+                format!(
+                    "Slot {} is empty (Alt+Shift+{} sets it)",
+                    slot + 1,
+                    slot + 1
+                ),
+                Duration::from_secs(2),
+            );
+            return;
+        };
+        if !mark.path.is_file() {
+            self.report_error(format!("{} no longer exists", mark.path.display()));
+            return;
+        }
+        self.open_linked_file(mark.path, Some(mark.line + 1));
+        if let Some(Tab::Editor { cursor, buffer, .. }) = self.tab_manager.active_tab_mut() {
+            let line = mark.line.min(buffer.len_lines().saturating_sub(1));
+            let column = mark.column.min(buffer.get_line_text(line).chars().count());
+            cursor.move_to(line, column);
+        }
+        self.ensure_cursor_visible();
+    }
+}
@@ -3,4 +3,18 @@ pub mod find_replace;
 pub mod keyboard;
 pub mod file_picker;
 pub mod ui_utilities;
-pub mod main_keyboard;
\ No newline at end of file
+pub mod main_keyboard;
+pub mod bindings;
+pub mod modal;
+pub mod tree_menu;
+pub mod tab_menu;
+pub mod paste;
+pub mod buffer_switcher;
+pub mod links;
+pub mod project_search;
+pub mod todos;
+pub mod marks;
+pub mod help;
+pub mod pager;
+pub mod share;
+pub mod escape;
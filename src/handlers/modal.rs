@@ -0,0 +1,186 @@
+use crate::app::App;
+use crate::jobs::{JobContext, JobKind};
+use crate::ui::{Modal, ModalAction};
+use crate::vfs;
+use crate::walk;
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Rect;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+impl App {
+    pub fn show_modal(&mut self, modal: Modal) {
+        self.modal = Some(modal);
+    }
+
+    pub fn handle_modal_key(&mut self, key: KeyEvent) {
+        let screen = Rect::new(0, 0, self.terminal_size.0, self.terminal_size.1);
+        let action = match &mut self.modal {
+            Some(modal) => {
+                let action = modal.handle_key(key);
+                modal.reveal_item(screen);
+                action
+            }
+            None => return,
+        };
+
+        if let Some(action) = action {
+            self.modal = None;
+            self.run_modal_action(action);
+        }
+    }
+
+    pub fn handle_mouse_on_modal(&mut self, mouse: MouseEvent) {
+        let screen = Rect::new(0, 0, self.terminal_size.0, self.terminal_size.1);
+        let modal = match &mut self.modal {
+            Some(modal) => modal,
+            None => return,
+        };
+
+        let button = modal.button_at(screen, mouse.column, mouse.row);
+        match mouse.kind {
+            MouseEventKind::Moved => {
+                if let Some(index) = button {
+                    modal.selected = index;
+                }
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(index) = button {
+                    let action = modal.button_action(index);
+                    self.modal = None;
+                    self.run_modal_action(action);
+                } else if let Some(index) = modal.item_at(screen, mouse.column, mouse.row) {
+                    modal.toggle_item(index);
+                }
+            }
+            MouseEventKind::ScrollUp => modal.scroll_items(screen, -1),
+            MouseEventKind::ScrollDown => modal.scroll_items(screen, 1),
+            _ => {}
+        }
+    }
+
+    pub fn run_modal_action(&mut self, action: ModalAction) {
+        match action {
+            ModalAction::Dismiss => {}
+            ModalAction::CloseTab(index) => {
+                if !self.tab_manager.close_tab(index) {
+                    self.running = false;
+                }
+            }
+            ModalAction::DeleteScratchTab(index) => {
+                let path = self.tab_manager.tabs.get(index).and_then(|tab| tab.path()).cloned();
+                if let Some(path) = path {
+                    match vfs::remove(&path) {
+                        Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                            let message = format!("Failed to delete {}: {}", path.display(), e);
+                            self.report_error(message);
+                            return;
+                        }
+                        _ => {}
+                    }
+                }
+                if !self.tab_manager.close_tab(index) {
+                    self.running = false;
+                }
+            }
+            ModalAction::SaveAndCloseTab(index) => {
+                self.save_and_close_tab(index);
+            }
+            ModalAction::ReplaceInFiles { write } => {
+                self.apply_project_replace(write);
+            }
+            ModalAction::Quit => {
+                self.running = false;
+            }
+            ModalAction::SaveAllAndQuit => {
+                self.save_all_and_quit();
+            }
+            ModalAction::SaveTabsAndQuit(indices) => {
+                self.save_tabs_and_quit(indices);
+            }
+            ModalAction::DeletePath(path) => {
+                let name = path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.display().to_string());
+                self.jobs.spawn(
+                    JobKind::FileOperation,
+                    format!("Delete {}", name),
+                    move |job| {
+                        let mut removed = 0;
+                        remove_path(&path, job, &mut removed)
+                            .map(|_| format!("Deleted: {}", path.display()))
+                            .map_err(|e| format!("Delete failed: {}", e))
+                    },
+                );
+            }
+            ModalAction::OverwriteTab(index) => {
+                self.write_tab(index);
+            }
+            ModalAction::ReloadTab(index) => {
+                if let Some(tab) = self.tab_manager.tabs.get_mut(index) {
+                    match tab.reload_from_disk() {
+                        Ok(()) => self.set_status_message(
+                            "Reloaded from disk".to_string(),
+                            Duration::from_secs(2),
+                        ),
+                        Err(e) => self.report_error(format!("Reload failed: {}", e)),
+                    }
+                }
+            }
+            ModalAction::ResolvePasteConflict {
+                choice,
+                apply_to_all,
+            } => {
+                self.resolve_paste_conflict(choice, apply_to_all);
+            }
+            ModalAction::CancelPaste => {
+                self.cancel_paste();
+            }
+        }
+    }
+
+    /// Ask before deleting a file or directory from disk
+    pub fn confirm_delete(&mut self, path: std::path::PathBuf) {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.display().to_string());
+        if let Err(e) = vfs::writable(&path) {
+            self.report_error(format!("Cannot delete '{}': {}", name, e));
+            return;
+        }
+        let message = if vfs::entry_metadata(&path).is_ok_and(|m| m.is_dir) {
+            format!("Delete folder '{}' and all its contents?", name)
+        } else {
+            format!("Delete file '{}'?", name)
+        };
+        self.show_modal(Modal::confirm(
+            &message,
+            "Delete",
+            ModalAction::DeletePath(path),
+        ));
+    }
+}
+
+/// Delete a file or directory tree, checking for cancellation between entries
+fn remove_path(path: &Path, job: &JobContext, removed: &mut usize) -> io::Result<()> {
+    if job.is_cancelled() {
+        return Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"));
+    }
+
+    // A link to a folder is removed, not what it points to
+    if !walk::is_symlink(path) && vfs::entry_metadata(path)?.is_dir {
+        for entry in vfs::read_dir(path)? {
+            remove_path(&entry.path, job, removed)?;
+        }
+    }
+    vfs::remove(path)?;
+
+    *removed += 1;
+    if removed.is_multiple_of(100) {
+        job.report(format!("{} items", removed));
+    }
+    Ok(())
+}
@@ -1,6 +1,21 @@
 use crate::app::App;
+use crate::config;
 use crate::tab::Tab;
-use crossterm::event::MouseEvent;
+use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Rect;
+use std::time::{Duration, Instant};
+
+/// Time between autoscroll steps while a selection is dragged past the editor edge
+pub const AUTOSCROLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Scrolling in progress while a selection drag rests on the editor's top or
+/// bottom edge. It speeds up the longer the mouse stays there, unless motion
+/// is reduced.
+pub struct DragAutoscroll {
+    up: bool,
+    started: Instant,
+    last_step: Instant,
+}
 
 impl App {
     pub fn handle_mouse_on_editor(&mut self, mouse: MouseEvent) {
@@ -57,12 +72,11 @@ impl App {
 
         // Handle editor scrolling
         match mouse.kind {
-            MouseEventKind::ScrollUp => {
-                self.handle_editor_scroll(MouseEventKind::ScrollUp);
-                return;
-            }
-            MouseEventKind::ScrollDown => {
-                self.handle_editor_scroll(MouseEventKind::ScrollDown);
+            MouseEventKind::ScrollUp
+            | MouseEventKind::ScrollDown
+            | MouseEventKind::ScrollLeft
+            | MouseEventKind::ScrollRight => {
+                self.handle_editor_scroll(mouse.kind, mouse.modifiers);
                 return;
             }
             MouseEventKind::Down(MouseButton::Left) => {
@@ -77,6 +91,14 @@ impl App {
                     None
                 };
 
+                // Ctrl+click follows a link instead of moving the cursor
+                if mouse.modifiers.contains(KeyModifiers::CONTROL) {
+                    if let Some((line, col)) = text_position {
+                        self.open_link_at(line, col);
+                    }
+                    return;
+                }
+
                 // Now handle the click with the computed position
                 if let (Some((line, col)), Some(tab)) = (text_position, self.tab_manager.active_tab_mut()) {
                     if let Tab::Editor { cursor, buffer, .. } = tab {
@@ -90,7 +112,7 @@ impl App {
                         
                         let is_double_click = if let (Some(last_time), Some(last_pos)) = 
                             (self.last_click_time, self.last_click_pos) {
-                            now.duration_since(last_time).as_millis() < 500 &&
+                            config::get().mouse.is_double_click(now.duration_since(last_time)) &&
                             last_pos == click_pos
                         } else {
                             false
@@ -109,6 +131,25 @@ impl App {
             }
             MouseEventKind::Drag(MouseButton::Left) => {
                 if self.mouse_selecting {
+                    // Scroll while the mouse sits on the first or last editor row
+                    let last_row = self.terminal_size.1.saturating_sub(2);
+                    let edge = if mouse.row <= 1 {
+                        Some(true)
+                    } else if mouse.row >= last_row {
+                        Some(false)
+                    } else {
+                        None
+                    };
+                    self.drag_autoscroll = match (edge, self.drag_autoscroll.take()) {
+                        (Some(up), Some(autoscroll)) if autoscroll.up == up => Some(autoscroll),
+                        (Some(up), _) => Some(DragAutoscroll {
+                            up,
+                            started: Instant::now(),
+                            last_step: Instant::now(),
+                        }),
+                        (None, _) => None,
+                    };
+
                     // First get the text position without borrowing tab_manager mutably
                     let text_position = if let Some(tab) = self.tab_manager.active_tab() {
                         if let Tab::Editor { buffer, .. } = tab {
@@ -130,11 +171,54 @@ impl App {
             }
             MouseEventKind::Up(MouseButton::Left) => {
                 self.mouse_selecting = false;
+                self.drag_autoscroll = None;
             }
             _ => {}
         }
     }
 
+    /// Advance drag-select autoscroll; called from the event loop between events
+    pub fn tick_drag_autoscroll(&mut self) {
+        let Some(autoscroll) = &mut self.drag_autoscroll else {
+            return;
+        };
+        if !self.mouse_selecting {
+            self.drag_autoscroll = None;
+            return;
+        }
+        if autoscroll.last_step.elapsed() < AUTOSCROLL_INTERVAL {
+            return;
+        }
+        autoscroll.last_step = Instant::now();
+
+        // One line per step at first, up to ten after a couple of seconds
+        let speed = if config::get().ui.reduced_motion {
+            1
+        } else {
+            (1 + autoscroll.started.elapsed().as_millis() as usize / 250).min(10)
+        };
+        let up = autoscroll.up;
+        let height = (self.terminal_size.1 as usize).saturating_sub(2);
+
+        if let Some(Tab::Editor { cursor, buffer, viewport_offset, .. }) =
+            self.tab_manager.active_tab_mut()
+        {
+            let line_count = buffer.len_lines();
+            if up {
+                viewport_offset.0 = viewport_offset.0.saturating_sub(speed);
+                cursor.extend_selection_to(viewport_offset.0, 0);
+            } else {
+                let max_line = Tab::max_viewport_line(line_count, height);
+                viewport_offset.0 =
+                    (viewport_offset.0 + speed).min(max_line.max(viewport_offset.0));
+                let line = (viewport_offset.0 + height.saturating_sub(1))
+                    .min(line_count.saturating_sub(1));
+                let column = buffer.get_line_text(line).chars().count();
+                cursor.extend_selection_to(line, column);
+            }
+        }
+    }
+
     pub fn mouse_to_text_position(
         &self,
         mouse: MouseEvent,
@@ -159,6 +243,11 @@ impl App {
         };
 
         let line_index = editor_row + viewport_offset.0;
+        // Long lines are panned sideways when wrap is off
+        let editor_col = match self.tab_manager.active_tab() {
+            Some(Tab::Editor { view, .. }) if !view.word_wrap => editor_col + viewport_offset.1,
+            _ => editor_col,
+        };
         
         if line_index >= buffer.len_lines() {
             // Click below content - position at end of last line
@@ -180,127 +269,71 @@ impl App {
         Some((line_index, col_index))
     }
 
-    pub fn handle_mouse_on_dialog(&mut self, mouse: MouseEvent) {
-        use crossterm::event::{MouseButton, MouseEventKind};
-        
-        if let MouseEventKind::Down(MouseButton::Left) = mouse.kind {
-            if let Some(button_index) = self.get_dialog_button_at_position(mouse.column, mouse.row) {
-                match button_index {
-                    0 => {
-                        // "No" or "OK" button
-                        if self.warning_is_info {
-                            // Info dialog - just close
-                            self.warning_message = None;
-                        } else {
-                            // Confirmation dialog - cancel action
-                            self.warning_message = None;
-                            self.pending_delete_path = None;
-                        }
-                    }
-                    1 => {
-                        // "Yes" button - proceed with action
-                        if let Some(delete_path) = self.pending_delete_path.take() {
-                            let result = if delete_path.is_dir() {
-                                std::fs::remove_dir_all(&delete_path)
-                                    .map(|_| format!("Deleted directory: {}", delete_path.display()))
-                            } else {
-                                std::fs::remove_file(&delete_path)
-                                    .map(|_| format!("Deleted file: {}", delete_path.display()))
-                            };
-
-                            match result {
-                                Ok(message) => {
-                                    self.set_status_message(message, std::time::Duration::from_secs(3));
-                                    // Refresh tree view
-                                    if let Some(tree_view) = &mut self.tree_view {
-                                        tree_view.refresh();
-                                    }
-                                }
-                                Err(e) => {
-                                    self.set_status_message(
-                                        format!("Delete failed: {}", e),
-                                        std::time::Duration::from_secs(5),
-                                    );
-                                }
-                            }
-                        }
-                        self.warning_message = None;
-                    }
-                    _ => {}
-                }
-            }
+    pub fn handle_mouse_event(&mut self, mouse: MouseEvent) {
+        use crossterm::event::MouseEventKind;
+
+        // Handle modal dialog first (highest priority)
+        if self.modal.is_some() {
+            self.handle_mouse_on_modal(mouse);
+            return;
         }
-    }
 
-    pub fn get_dialog_button_at_position(&self, mouse_x: u16, mouse_y: u16) -> Option<usize> {
-        if self.warning_message.is_none() {
-            return None;
+        if matches!(
+            mouse.kind,
+            MouseEventKind::Moved | MouseEventKind::ScrollUp | MouseEventKind::ScrollDown
+        ) {
+            self.update_hover(mouse);
         }
 
-        let modal_width = 50u16.min(self.terminal_size.0.saturating_sub(4));
-        let modal_height = if self.warning_is_info { 6 } else { 7 };
+        // Handle input dialog
+        if let crate::menu::MenuState::InputDialog(_) = &self.menu_system.state {
+            if self.handle_mouse_on_input_dialog(mouse) {
+                return;
+            }
+        }
 
-        let modal_x = (self.terminal_size.0.saturating_sub(modal_width)) / 2;
-        let modal_y = (self.terminal_size.1.saturating_sub(modal_height)) / 2;
+        if let crate::menu::MenuState::BufferSwitcher(_) = &self.menu_system.state {
+            self.handle_mouse_on_buffer_switcher(mouse);
+            return;
+        }
 
-        // Check if click is within modal bounds
-        if mouse_x < modal_x 
-            || mouse_x >= modal_x + modal_width 
-            || mouse_y < modal_y 
-            || mouse_y >= modal_y + modal_height {
-            return None;
+        if let crate::menu::MenuState::ProjectSearch(_) = &self.menu_system.state {
+            self.handle_mouse_on_project_search(mouse);
+            return;
         }
 
-        let button_row = modal_y + modal_height.saturating_sub(2);
-        
-        if mouse_y != button_row {
-            return None;
+        if let crate::menu::MenuState::TodoList(_) = &self.menu_system.state {
+            self.handle_mouse_on_todo_list(mouse);
+            return;
         }
 
-        if self.warning_is_info {
-            // Info dialog - only "OK" button
-            let button_x = modal_x + (modal_width / 2).saturating_sub(2);
-            if mouse_x >= button_x && mouse_x < button_x + 4 {
-                Some(0) // OK button
-            } else {
-                None
-            }
-        } else {
-            // Confirmation dialog - "No" and "Yes" buttons
-            let no_button_x = modal_x + (modal_width / 3).saturating_sub(2);
-            let yes_button_x = modal_x + (2 * modal_width / 3).saturating_sub(2);
-
-            if mouse_x >= no_button_x && mouse_x < no_button_x + 4 {
-                Some(0) // No button
-            } else if mouse_x >= yes_button_x && mouse_x < yes_button_x + 5 {
-                Some(1) // Yes button
-            } else {
-                None
+        // Handle file picker
+        if let crate::menu::MenuState::FilePicker(_) = &self.menu_system.state {
+            if self.handle_mouse_on_file_picker(mouse) {
+                return;
             }
         }
-    }
-
-    pub fn handle_mouse_event(&mut self, mouse: MouseEvent) {
-        use crossterm::event::MouseEventKind;
 
-        // Handle dialog first (highest priority)
-        if self.warning_message.is_some() {
-            self.handle_mouse_on_dialog(mouse);
+        if self.handle_mouse_on_status_bar(mouse) {
             return;
         }
 
-        // Handle input dialog
-        if let crate::menu::MenuState::InputDialog(_) = &self.menu_system.state {
-            if self.handle_mouse_on_input_dialog(mouse) {
+        // Scroll the Output/Log panel
+        if self.messages.log_visible {
+            let status_row = self.terminal_size.1.saturating_sub(1);
+            let panel_top = status_row.saturating_sub(crate::ui::LOG_PANEL_HEIGHT);
+            if mouse.row >= panel_top && mouse.row < status_row {
+                match mouse.kind {
+                    MouseEventKind::ScrollUp => self.messages.scroll_log(3),
+                    MouseEventKind::ScrollDown => self.messages.scroll_log(-3),
+                    _ => {}
+                }
                 return;
             }
         }
 
-        // Handle file picker
-        if let crate::menu::MenuState::FilePicker(_) = &self.menu_system.state {
-            if self.handle_mouse_on_file_picker(mouse) {
-                return;
-            }
+        if self.handle_mouse_on_tree_menu(mouse) || self.handle_mouse_on_tab_menu(mouse) {
+            return;
         }
 
         // Handle menus
@@ -313,23 +346,36 @@ impl App {
             return;
         }
 
+        // Handle the tab bar row, and tab drags that wander off it
+        if mouse.row == 0 || self.dragging_tab.is_some() {
+            let active_index = self.tab_manager.active_index();
+            if self.handle_tab_bar_mouse(mouse, active_index) {
+                return;
+            }
+        }
+
         // Handle sidebar resize
         if self.handle_sidebar_resize(mouse) {
             return;
         }
 
+        // Clicking outside the tree abandons an inline rename
+        if mouse.column >= self.sidebar.columns() && matches!(mouse.kind, MouseEventKind::Down(_)) {
+            self.cancel_inline_rename();
+        }
+
         // Handle tree view
-        if mouse.column < self.sidebar_width && self.tree_view.is_some() {
+        if mouse.column < self.sidebar.columns() && self.tree_view.is_some() {
             if self.handle_mouse_on_tree_view(mouse) {
                 return;
             }
         }
 
         // Handle editor (remaining area)
-        if mouse.column >= self.sidebar_width {
+        if mouse.column >= self.sidebar.columns() {
             // Adjust mouse coordinates for sidebar
             let adjusted_mouse = MouseEvent {
-                column: mouse.column - self.sidebar_width,
+                column: mouse.column - self.sidebar.columns(),
                 row: mouse.row,
                 kind: mouse.kind,
                 modifiers: mouse.modifiers,
@@ -346,6 +392,64 @@ impl App {
         }
     }
 
+    /// Clicking the indentation segment cycles the active tab's style
+    fn handle_mouse_on_status_bar(&mut self, mouse: MouseEvent) -> bool {
+        let status_row = self.terminal_size.1.saturating_sub(1);
+        if mouse.row != status_row || mouse.kind != MouseEventKind::Down(MouseButton::Left) {
+            return false;
+        }
+        let area = Rect::new(0, status_row, self.terminal_size.0, 1);
+        let Some(segment) = crate::ui::status_bar::indent_segment_area(
+            area,
+            &self.tab_manager,
+            self.jobs.summary(),
+            self.overwrite_mode,
+        ) else {
+            return false;
+        };
+        if mouse.column < segment.x || mouse.column >= segment.x + segment.width {
+            return false;
+        }
+        if let Some(tab) = self.tab_manager.active_tab_mut() {
+            tab.cycle_indent();
+            if let Some(indent) = tab.indent() {
+                let message = format!("Indentation: {}", indent.label());
+                self.set_status_message(message, Duration::from_secs(2));
+            }
+        }
+        true
+    }
+
+    /// Track which tree row, tab and find bar button the mouse is over. Open
+    /// menus cover them, so nothing underneath is hovered then.
+    fn update_hover(&mut self, mouse: MouseEvent) {
+        let covered = !matches!(self.menu_system.state, crate::menu::MenuState::Closed);
+        let over_tab_bar = !covered && mouse.row == 0;
+        self.ui.tab_bar.hovered_tab = if over_tab_bar {
+            self.get_clicked_tab(mouse.column)
+        } else {
+            None
+        };
+
+        let over_tree = !covered && mouse.column < self.sidebar.columns();
+        if let Some(tree_view) = &mut self.tree_view {
+            tree_view.hovered_index = if over_tree {
+                tree_view.index_at_row(mouse.row, 1)
+            } else {
+                None
+            };
+        }
+
+        let target = if covered {
+            None
+        } else {
+            self.find_bar_target_at(mouse.column, mouse.row)
+        };
+        if let Some(Tab::Editor { find_replace_state, .. }) = self.tab_manager.active_tab_mut() {
+            find_replace_state.hovered = target;
+        }
+    }
+
     // Add missing mouse handler methods
     pub fn handle_mouse_on_menus(&mut self, mouse: MouseEvent) -> bool {
         use crossterm::event::{MouseButton, MouseEventKind};
@@ -374,30 +478,104 @@ impl App {
     }
 
     pub fn handle_mouse_on_tree_view(&mut self, mouse: MouseEvent) -> bool {
-        use crossterm::event::{MouseButton, MouseEventKind};
+        use crossterm::event::{KeyModifiers, MouseButton, MouseEventKind};
         
         if let Some(tree_view) = &mut self.tree_view {
             match mouse.kind {
                 MouseEventKind::Down(MouseButton::Left) => {
                     // Set focus to tree view
+                    let was_focused = tree_view.is_focused;
                     self.focus_mode = crate::app::FocusMode::TreeView;
                     tree_view.is_focused = true;
                     
-                    // Select item at mouse position
-                    let visible_items = tree_view.get_visible_items();
-                    let item_index = mouse.row as usize;
-                    
-                    if item_index < visible_items.len() {
+                    // Select item at mouse position (the tree starts below the tab bar)
+                    if let Some(item_index) = tree_view.index_at_row(mouse.row, 1) {
+                        let was_selected = was_focused && item_index == tree_view.selected_index;
                         tree_view.selected_index = item_index;
+
+                        let path = tree_view.get_selected_item().map(|item| item.path.clone());
+                        if tree_view.rename.as_ref().map(|rename| &rename.path) != path.as_ref() {
+                            tree_view.rename = None;
+                        }
+
+                        // A click on a folder's "more" row lists the next page
+                        let selected = tree_view.get_selected_item();
+                        if selected.is_some_and(|item| item.placeholder.is_some()) {
+                            self.toggle_tree_directory();
+                            return true;
+                        }
+
+                        // A second, slower click on the selected row renames it in place
+                        let now = std::time::Instant::now();
+                        let click_pos = (mouse.column, mouse.row);
+                        let since_last_click = match (self.last_click_time, self.last_click_pos) {
+                            (Some(last_time), Some(last_pos)) if last_pos.1 == click_pos.1 => {
+                                Some(now.duration_since(last_time))
+                            }
+                            _ => None,
+                        };
+                        let mouse_config = &config::get().mouse;
+                        let is_double_click = was_selected
+                            && since_last_click
+                                .is_some_and(|gap| mouse_config.is_double_click(gap));
+                        // Up to three times as long as a double-click
+                        let is_slow_double_click = since_last_click.is_some_and(|gap| {
+                            !mouse_config.is_double_click(gap)
+                                && mouse_config.is_double_click(gap / 3)
+                        });
+                        let is_dir = tree_view.get_selected_item().is_some_and(|item| item.is_dir);
+                        if is_double_click {
+                            // Double-click keeps a file's tab, or opens a folder
+                            if is_dir {
+                                self.toggle_tree_directory();
+                            } else if let Some(tab) = self.tab_manager.active_tab_mut() {
+                                tab.keep();
+                            }
+                            self.last_click_time = None;
+                        } else if was_selected
+                            && is_slow_double_click
+                            && tree_view.rename.is_none()
+                        {
+                            if let Some(path) = path {
+                                tree_view.start_rename(&path);
+                            }
+                            self.last_click_time = None;
+                        } else {
+                            self.last_click_time = Some(now);
+                            self.last_click_pos = Some(click_pos);
+                            // A single click on a file shows it in the preview tab
+                            if let Some(path) = path.filter(|_| !is_dir) {
+                                self.open_preview_tab(path);
+                            }
+                        }
                     }
-                    
+
                     true
                 }
                 MouseEventKind::Up(MouseButton::Left) => {
                     true
                 }
                 MouseEventKind::Down(MouseButton::Right) => {
-                    // Handle right-click context menu
+                    self.open_tree_context_menu_at(mouse);
+                    true
+                }
+                MouseEventKind::ScrollUp | MouseEventKind::ScrollDown => {
+                    // The tree sits between the tab bar and the status bar
+                    let mut visible_height = (self.terminal_size.1 as usize).saturating_sub(2);
+                    if tree_view.is_searching {
+                        visible_height = visible_height.saturating_sub(1);
+                    }
+                    // Ctrl+wheel scrolls a page at a time
+                    let amount = if mouse.modifiers.contains(KeyModifiers::CONTROL) {
+                        visible_height.max(1)
+                    } else {
+                        tree_view.wheel.notch()
+                    };
+                    if mouse.kind == MouseEventKind::ScrollUp {
+                        tree_view.scroll_up(amount);
+                    } else {
+                        tree_view.scroll_down(amount, visible_height);
+                    }
                     true
                 }
                 _ => false
@@ -0,0 +1,64 @@
+use crate::app::{App, FocusMode};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::time::Duration;
+
+impl App {
+    /// Show the tabs read-only with less's keys, for `f1 --pager`
+    pub fn start_pager(&mut self) {
+        self.pager = true;
+        self.sidebar.collapsed = true;
+        self.focus_mode = FocusMode::Editor;
+        for tab in &mut self.tab_manager.tabs {
+            tab.set_read_only(true);
+        }
+    }
+
+    /// Keys of pager mode. Returns false for the ones left to the editor,
+    /// like the arrows.
+    pub fn handle_pager_key(&mut self, key: KeyEvent) -> bool {
+        let height = (self.terminal_size.1 as usize).saturating_sub(2).max(1);
+        let page = height as isize;
+        let lines = match (key.code, key.modifiers) {
+            (KeyCode::Char('q' | 'Q'), KeyModifiers::NONE) => {
+                self.running = false;
+                return true;
+            }
+            (KeyCode::Char(' ' | 'f') | KeyCode::PageDown, KeyModifiers::NONE)
+            | (KeyCode::Char('f'), KeyModifiers::CONTROL) => page,
+            (KeyCode::Char('b') | KeyCode::PageUp, KeyModifiers::NONE)
+            | (KeyCode::Char('b'), KeyModifiers::CONTROL) => -page,
+            (KeyCode::Char('d'), KeyModifiers::NONE | KeyModifiers::CONTROL) => page / 2,
+            (KeyCode::Char('u'), KeyModifiers::NONE | KeyModifiers::CONTROL) => -page / 2,
+            (KeyCode::Char('j' | 'e') | KeyCode::Enter | KeyCode::Down, KeyModifiers::NONE) => 1,
+            (KeyCode::Char('k' | 'y') | KeyCode::Up, KeyModifiers::NONE) => -1,
+            (KeyCode::Char('g' | '<') | KeyCode::Home, KeyModifiers::NONE) => isize::MIN,
+            (KeyCode::Char('G' | '>') | KeyCode::End, KeyModifiers::NONE) => isize::MAX,
+            (KeyCode::Char('/'), KeyModifiers::NONE) => {
+                if let Some(tab) = self.tab_manager.active_tab_mut() {
+                    tab.start_find();
+                }
+                return true;
+            }
+            (KeyCode::Char(next @ ('n' | 'N')), KeyModifiers::NONE) => {
+                let found = self
+                    .tab_manager
+                    .active_tab_mut()
+                    .is_some_and(|tab| tab.find_again(next == 'N'));
+                if found {
+                    self.ensure_cursor_visible();
+                } else {
+                    self.set_status_message(
+                        "Pattern not found".to_string(),
+                        Duration::from_secs(2),
+                    );
+                }
+                return true;
+            }
+            _ => return false,
+        };
+        if let Some(tab) = self.tab_manager.active_tab_mut() {
+            tab.scroll_view(lines, height);
+        }
+        true
+    }
+}
@@ -0,0 +1,137 @@
+use crate::app::App;
+use crate::jobs::JobKind;
+use crate::paste::{ConflictChoice, PasteOperation};
+use crate::ui::{Modal, ModalAction, ModalButton, ModalKind};
+
+impl App {
+    /// Paste the tree clipboard (or files copied in the OS) into the selected
+    /// folder. Conflicts are asked about first; the copy runs as a job.
+    pub fn start_paste(&mut self) {
+        let Some(tree_view) = &mut self.tree_view else {
+            return;
+        };
+        let Some((sources, is_cut)) = tree_view.paste_sources() else {
+            self.report_error("Nothing to paste".to_string());
+            return;
+        };
+        let target_dir = tree_view.paste_target_dir();
+        if crate::vfs::is_read_only(&target_dir) {
+            let message = format!("Cannot paste into {}: read-only", target_dir.display());
+            self.report_error(message);
+            return;
+        }
+
+        match PasteOperation::new(sources, &target_dir, is_cut) {
+            Ok(operation) if operation.items.is_empty() => {
+                tree_view.clear_cut();
+                self.set_status_message(
+                    "Already in this folder".to_string(),
+                    std::time::Duration::from_secs(2),
+                );
+            }
+            Ok(operation) => {
+                self.pending_paste = Some(operation);
+                self.advance_paste();
+            }
+            Err(message) => self.report_error(message),
+        }
+    }
+
+    /// Record the answer to the current conflict dialog and move on
+    pub fn resolve_paste_conflict(&mut self, choice: ConflictChoice, apply_to_all: bool) {
+        if let Some(operation) = &mut self.pending_paste {
+            if let Some(index) = operation.next_unresolved() {
+                operation.items[index].resolution = Some(choice);
+            }
+            if apply_to_all {
+                operation.apply_all = Some(choice);
+            }
+        }
+        self.advance_paste();
+    }
+
+    pub fn cancel_paste(&mut self) {
+        if self.pending_paste.take().is_some() {
+            self.set_status_message(
+                "Paste cancelled".to_string(),
+                std::time::Duration::from_secs(2),
+            );
+        }
+    }
+
+    fn advance_paste(&mut self) {
+        let Some(operation) = &mut self.pending_paste else {
+            return;
+        };
+
+        while let Some(index) = operation.next_unresolved() {
+            match operation.apply_all {
+                Some(choice) => operation.items[index].resolution = Some(choice),
+                None => {
+                    let name = operation.items[index]
+                        .target
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let remaining = operation.items[index + 1..]
+                        .iter()
+                        .filter(|item| item.resolution.is_none() && item.has_conflict())
+                        .count();
+                    let modal = conflict_modal(&name, remaining);
+                    self.show_modal(modal);
+                    return;
+                }
+            }
+        }
+
+        let Some(operation) = self.pending_paste.take() else {
+            return;
+        };
+        let name = match operation.items.as_slice() {
+            [item] => item
+                .source
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            items => format!("{} items", items.len()),
+        };
+        let is_cut = operation.is_cut;
+        let verb = if is_cut { "Move" } else { "Paste" };
+        let id = self.jobs.spawn(
+            JobKind::FileOperation,
+            format!("{} {}", verb, name),
+            move |job| operation.run(job),
+        );
+        if is_cut {
+            self.cut_paste_job = Some(id);
+        }
+    }
+}
+
+fn conflict_modal(name: &str, remaining: usize) -> Modal {
+    let resolve = |choice| ModalAction::ResolvePasteConflict {
+        choice,
+        apply_to_all: false,
+    };
+    let mut modal = Modal::new(
+        ModalKind::Warning,
+        " File Exists ",
+        &format!("'{}' already exists in the destination.", name),
+    );
+    if remaining > 0 {
+        modal = modal.with_item(
+            &format!("Apply to all remaining conflicts ({})", remaining),
+            false,
+            0,
+        );
+    }
+    modal
+        .with_button(
+            ModalButton::new("Overwrite", resolve(ConflictChoice::Overwrite)).with_hotkey('o'),
+        )
+        .with_button(ModalButton::new("Rename", resolve(ConflictChoice::Rename)).with_hotkey('r'))
+        .with_button(ModalButton::new("Skip", resolve(ConflictChoice::Skip)).with_hotkey('s'))
+        .with_button(ModalButton::new("Cancel", ModalAction::CancelPaste).with_hotkey('c'))
+        .with_default(1)
+        .with_cancel(3)
+}
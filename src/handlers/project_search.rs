@@ -0,0 +1,385 @@
+use crate::app::App;
+use crate::jobs::JobKind;
+use crate::menu::{MenuState, ResultRow, SearchField};
+use crate::project_search::{self, Matcher, SharedResults};
+use crate::selection;
+use crate::tab::{canonical_path, Tab};
+use crate::ui::project_search as panel;
+use crate::ui::{Modal, ModalAction, ModalButton, ModalKind};
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Rect;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+impl App {
+    /// Open the Ctrl+Shift+F panel, keeping the last query and its matches
+    pub fn open_project_search(&mut self) {
+        self.menu_system.open_project_search();
+        if let MenuState::ProjectSearch(state) = &mut self.menu_system.state {
+            state.focus = SearchField::Query;
+            state.query_input.select_all();
+        }
+    }
+
+    /// Rename the identifier at the cursor across the workspace: search for
+    /// it as a whole word and preview the replacement, with each occurrence
+    /// checked off before anything changes
+    pub fn rename_occurrences(&mut self) {
+        let Some(Tab::Editor { buffer, cursor, .. }) = self.tab_manager.active_tab() else {
+            return;
+        };
+        let word = match cursor.get_selection() {
+            Some((start, end)) if start.line == end.line => {
+                let start = selection::to_index(buffer, start);
+                buffer
+                    .slice(start..selection::to_index(buffer, end))
+                    .to_string()
+            }
+            _ => {
+                let index = selection::to_index(buffer, cursor.position);
+                let (start, end) = selection::word_at(buffer, index);
+                buffer.slice(start..end).to_string()
+            }
+        };
+        if word.trim().is_empty() {
+            self.set_status_message(
+                "No identifier under the cursor".to_string(),
+                Duration::from_secs(2),
+            );
+            return;
+        }
+
+        self.menu_system.open_project_search();
+        if let MenuState::ProjectSearch(state) = &mut self.menu_system.state {
+            state.query_input.set_text(&word);
+            state.replace_input.set_text(&word);
+            state.replace_input.select_all();
+            state.case_sensitive = true;
+            state.whole_word = true;
+            state.focus = SearchField::Replace;
+        }
+        self.start_project_search();
+    }
+
+    /// Search for the panel's query, replacing any search still running
+    fn start_project_search(&mut self) {
+        let MenuState::ProjectSearch(state) = &mut self.menu_system.state else {
+            return;
+        };
+        if let Some(job) = state.job.take() {
+            self.jobs.cancel(job);
+        }
+        let options = state.options();
+        state.searched = Some(options.clone());
+        state.results = SharedResults::default();
+        state.unchecked.clear();
+        state.selected_index = 0;
+        if options.query.is_empty() {
+            return;
+        }
+
+        let results = state.results.clone();
+        let root = std::env::current_dir().unwrap_or_default();
+        let name = format!("Searching for \"{}\"", options.query);
+        state.job = Some(self.jobs.spawn(JobKind::Search, name, move |job| {
+            project_search::run(&root, &options, &results, job)
+        }));
+    }
+
+    /// Open the selected match (or a file's first one) with the cursor on it
+    fn open_selected_match(&mut self) {
+        let MenuState::ProjectSearch(state) = &self.menu_system.state else {
+            return;
+        };
+        let index = match state.rows().get(state.selected_index) {
+            Some(ResultRow::File(index) | ResultRow::Match(index)) => *index,
+            None => return,
+        };
+        let Some(found) = state.results.lock().matches.get(index).cloned() else {
+            return;
+        };
+        self.menu_system.close();
+        let path = std::env::current_dir()
+            .unwrap_or_default()
+            .join(&found.path);
+        self.open_linked_file(path, Some(found.line + 1));
+        if let Some(Tab::Editor { cursor, .. }) = self.tab_manager.active_tab_mut() {
+            cursor.move_to(found.line, found.column);
+        }
+    }
+
+    /// Ask how to apply the checked replacements
+    fn confirm_project_replace(&mut self) {
+        let MenuState::ProjectSearch(state) = &self.menu_system.state else {
+            return;
+        };
+        if state.is_stale() {
+            self.set_status_message(
+                "Search again before replacing".to_string(),
+                Duration::from_secs(2),
+            );
+            return;
+        }
+        let results = state.results.lock();
+        let checked: Vec<_> = (0..results.matches.len())
+            .filter(|index| !state.unchecked.contains(index))
+            .collect();
+        let files: BTreeSet<_> = checked
+            .iter()
+            .map(|&index| &results.matches[index].path)
+            .collect();
+        if checked.is_empty() {
+            drop(results);
+            self.set_status_message("No lines to replace".to_string(), Duration::from_secs(2));
+            return;
+        }
+
+        let message = format!(
+            "Replace {} occurrences of \"{}\" in {} files with \"{}\"?",
+            checked.len(),
+            state.query_input.text(),
+            files.len(),
+            state.replace_input.text()
+        );
+        drop(results);
+        let modal = Modal::new(ModalKind::Warning, " Replace in Files ", &message)
+            .with_button(
+                ModalButton::new("Open as Tabs", ModalAction::ReplaceInFiles { write: false })
+                    .with_hotkey('o'),
+            )
+            .with_button(
+                ModalButton::new("Write Files", ModalAction::ReplaceInFiles { write: true })
+                    .with_hotkey('w'),
+            )
+            .with_button(ModalButton::new("Cancel", ModalAction::Dismiss).with_hotkey('c'))
+            .with_default(0)
+            .with_cancel(2);
+        self.show_modal(modal);
+    }
+
+    /// Apply the checked replacements, file by file. Files either open as
+    /// modified tabs or are written in place; files with unsaved edits are
+    /// always changed in their tab so the edits aren't lost. A file whose
+    /// occurrences moved since the search is left alone.
+    pub fn apply_project_replace(&mut self, write: bool) {
+        let MenuState::ProjectSearch(state) = &self.menu_system.state else {
+            return;
+        };
+        let Some(options) = &state.searched else {
+            return;
+        };
+        let matcher = Matcher::from_options(options);
+        let replacement = state.replace_input.text().to_string();
+        // File, then line, then the columns of the occurrences to replace
+        let mut files: BTreeMap<PathBuf, BTreeMap<usize, BTreeSet<usize>>> = BTreeMap::new();
+        for (index, found) in state.results.lock().matches.iter().enumerate() {
+            if !state.unchecked.contains(&index) {
+                files
+                    .entry(found.path.clone())
+                    .or_default()
+                    .entry(found.line)
+                    .or_default()
+                    .insert(found.column);
+            }
+        }
+        self.menu_system.close();
+
+        let root = std::env::current_dir().unwrap_or_default();
+        let (mut changed, mut replaced) = (0, 0);
+        let mut stale = Vec::new();
+        for (relative, lines) in &files {
+            let path = root.join(relative);
+            let tab_index = self.tab_index_for(&path);
+            let has_edits = tab_index
+                .and_then(|index| self.tab_manager.tabs.get(index))
+                .is_some_and(|tab| tab.is_modified());
+
+            let result = if write && !has_edits {
+                replace_in_file(&path, lines, &matcher, &replacement).map(|()| {
+                    if let Some(tab) = tab_index.and_then(|i| self.tab_manager.tabs.get_mut(i)) {
+                        let _ = tab.reload_from_disk();
+                    }
+                })
+            } else {
+                self.replace_in_tab(&path, lines, &matcher, &replacement)
+            };
+            match result {
+                Ok(()) => {
+                    changed += 1;
+                    replaced += lines.values().map(BTreeSet::len).sum::<usize>();
+                }
+                Err(reason) => stale.push(format!("{} ({})", relative.display(), reason)),
+            }
+        }
+
+        let mut message = format!("Replaced {} occurrences in {} files", replaced, changed);
+        if stale.is_empty() {
+            self.set_status_message(message, Duration::from_secs(3));
+        } else {
+            message.push_str(&format!("; skipped {}", stale.join(", ")));
+            self.report_error(message);
+        }
+    }
+
+    /// The tab showing a file, if it is open
+    pub fn tab_index_for(&self, path: &Path) -> Option<usize> {
+        let path = canonical_path(path);
+        self.tab_manager.tabs.iter().position(|tab| {
+            tab.path()
+                .is_some_and(|tab_path| canonical_path(tab_path) == path)
+        })
+    }
+
+    /// Replace in the file's tab, opening it first, as one undo step
+    fn replace_in_tab(
+        &mut self,
+        path: &Path,
+        lines: &BTreeMap<usize, BTreeSet<usize>>,
+        matcher: &Matcher,
+        replacement: &str,
+    ) -> Result<(), String> {
+        let index = match self.tab_index_for(path) {
+            Some(index) => index,
+            None => {
+                let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+                self.open_file_in_tab(path.to_path_buf(), &text);
+                self.tab_manager.active_index()
+            }
+        };
+
+        let Some(tab) = self.tab_manager.tabs.get_mut(index) else {
+            return Err("no tab".to_string());
+        };
+        let Tab::Editor { buffer, .. } = &*tab else {
+            return Err("no tab".to_string());
+        };
+        let ranges = lines
+            .iter()
+            .map(|(&line, columns)| {
+                let start = buffer.line_to_char(line);
+                let ranges = matcher.occurrences_at(&buffer.get_line_text(line), columns)?;
+                Some(ranges.into_iter().map(move |(from, to)| start + from..start + to))
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or("changed since the search")?;
+
+        tab.save_state();
+        if let Tab::Editor { buffer, .. } = tab {
+            // Last to first, so earlier occurrences keep their positions
+            for range in ranges.into_iter().flatten().rev() {
+                buffer.replace(range, replacement);
+            }
+        }
+        tab.mark_modified();
+        Ok(())
+    }
+
+    pub fn handle_project_search_key(&mut self, key: KeyEvent) {
+        use crossterm::event::{KeyCode, KeyModifiers};
+
+        let MenuState::ProjectSearch(state) = &mut self.menu_system.state else {
+            return;
+        };
+        let page = panel::list_area(Rect::new(0, 0, self.terminal_size.0, self.terminal_size.1))
+            .height
+            .max(1) as isize;
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('f') | KeyCode::Char('F'), m)
+                if m == KeyModifiers::CONTROL | KeyModifiers::SHIFT =>
+            {
+                self.menu_system.close();
+            }
+            (KeyCode::Enter, _) => {
+                if state.is_stale() {
+                    self.start_project_search();
+                } else {
+                    self.open_selected_match();
+                }
+            }
+            (KeyCode::Tab, _) => state.next_field(),
+            (KeyCode::BackTab, _) => state.previous_field(),
+            (KeyCode::Char('c'), KeyModifiers::ALT) => {
+                state.case_sensitive = !state.case_sensitive;
+            }
+            (KeyCode::Char('w'), KeyModifiers::ALT) => state.whole_word = !state.whole_word,
+            (KeyCode::Char('r'), KeyModifiers::ALT) => self.confirm_project_replace(),
+            (KeyCode::Char(' '), KeyModifiers::CONTROL) => {
+                // Step on after each one, to confirm occurrences in turn
+                if let Some(&row) = state.rows().get(state.selected_index) {
+                    state.toggle_row(row);
+                    state.move_by(1);
+                }
+            }
+            (KeyCode::Up, _) => state.move_by(-1),
+            (KeyCode::Down, _) => state.move_by(1),
+            (KeyCode::PageUp, _) => state.move_by(-page),
+            (KeyCode::PageDown, _) => state.move_by(page),
+            _ => {
+                state.focused_input().handle_key(key);
+            }
+        }
+    }
+
+    /// Clicking a match opens it, or checks it when the click is on its
+    /// checkbox; clicking outside closes the panel
+    pub fn handle_mouse_on_project_search(&mut self, mouse: MouseEvent) {
+        let MenuState::ProjectSearch(state) = &mut self.menu_system.state else {
+            return;
+        };
+        let screen = Rect::new(0, 0, self.terminal_size.0, self.terminal_size.1);
+        let modal = panel::modal_area(screen);
+        let list = panel::list_area(screen);
+        let contains = |area: Rect| {
+            mouse.column >= area.x
+                && mouse.column < area.x + area.width
+                && mouse.row >= area.y
+                && mouse.row < area.y + area.height
+        };
+
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if !contains(modal) {
+                    self.menu_system.close();
+                    return;
+                }
+                if mouse.row == modal.y + 1 {
+                    state.focus = SearchField::Query;
+                } else if mouse.row == modal.y + 2 {
+                    state.focus = SearchField::Replace;
+                } else if mouse.row == modal.y + 3 {
+                    state.focus = SearchField::Files;
+                } else if contains(list) {
+                    let first = panel::first_visible(state.selected_index, list.height as usize);
+                    let index = first + (mouse.row - list.y) as usize;
+                    let Some(&row) = state.rows().get(index) else {
+                        return;
+                    };
+                    state.selected_index = index;
+                    let checkbox = panel::checkbox_column(row) + list.x;
+                    if state.is_replacing() && (checkbox..checkbox + 3).contains(&mouse.column) {
+                        state.toggle_row(row);
+                    } else {
+                        self.open_selected_match();
+                    }
+                }
+            }
+            MouseEventKind::ScrollUp => state.move_by(-3),
+            MouseEventKind::ScrollDown => state.move_by(3),
+            _ => {}
+        }
+    }
+}
+
+/// Replace in a file on disk without opening it
+fn replace_in_file(
+    path: &Path,
+    lines: &BTreeMap<usize, BTreeSet<usize>>,
+    matcher: &Matcher,
+    replacement: &str,
+) -> Result<(), String> {
+    let text = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let replaced = project_search::replace_lines(&text, lines, matcher, replacement)
+        .ok_or("changed since the search")?;
+    project_search::write_atomically(path, &replaced).map_err(|e| e.to_string())
+}
@@ -0,0 +1,270 @@
+use crate::app::App;
+use crate::config;
+use crate::cursor::{Cursor, Position};
+use crate::rope_buffer::RopeBuffer;
+use crate::share::{self, Follower, Host, Version};
+use crate::tab::Tab;
+use std::ops::Range;
+use std::time::Duration;
+
+/// The tab showing a followed session: one without a file whose text is a
+/// state of what the follower last showed
+fn is_follow_tab(tab: &Tab, shown: &RopeBuffer) -> bool {
+    matches!(
+        tab,
+        Tab::Editor {
+            path: None,
+            buffer,
+            ..
+        } if buffer.same_document(shown)
+    )
+}
+
+/// Where `position` in `before` is in `after`, which `changes` made from
+/// it: each a replaced range and how many characters went in its place
+fn carry(
+    position: Position,
+    before: &RopeBuffer,
+    after: &RopeBuffer,
+    changes: &[(Range<usize>, usize)],
+) -> Position {
+    let line = position.line.min(before.len_lines().saturating_sub(1));
+    let column = position.column.min(before.line_range(line).len());
+    let mut index = before.line_to_char(line) + column;
+    for (range, inserted) in changes {
+        if index >= range.end {
+            index = index - range.len() + inserted;
+        } else if index > range.start {
+            index = range.start + inserted;
+        }
+    }
+    let index = index.min(after.len_chars());
+    let line = after.char_to_line(index);
+    Position::new(line, index - after.line_to_char(line))
+}
+
+/// Keep the cursor and selection on the characters they were at
+fn carry_cursor(
+    cursor: &mut Cursor,
+    before: &RopeBuffer,
+    after: &RopeBuffer,
+    changes: &[(Range<usize>, usize)],
+) {
+    cursor.position = carry(cursor.position, before, after, changes);
+    cursor.selection_start = cursor
+        .selection_start
+        .map(|start| carry(start, before, after, changes));
+    cursor.desired_column = None;
+}
+
+impl App {
+    /// Start or stop serving the active tab at the `[share] address`
+    pub fn toggle_sharing(&mut self) {
+        if self.share_host.take().is_some() {
+            self.set_status_message("Stopped sharing".to_string(), Duration::from_secs(2));
+            return;
+        }
+        let address = &config::get().share.address;
+        match Host::start(address) {
+            Ok(host) => {
+                let address = host
+                    .address()
+                    .map_or_else(|_| address.clone(), |address| address.to_string());
+                self.share_host = Some(host);
+                self.set_status_message(
+                    format!("Sharing the active tab at {}", address),
+                    Duration::from_secs(4),
+                );
+            }
+            Err(e) => self.report_error(format!("Failed to share at {}: {}", address, e)),
+        }
+    }
+
+    /// Connect to a shared session and show it in a tab, which is read-only
+    /// unless `co_edit`
+    pub fn follow_session(&mut self, address: &str, co_edit: bool) {
+        let mut follower = match Follower::connect(address, co_edit) {
+            Ok(follower) => follower,
+            Err(e) => {
+                self.report_error(format!("Failed to follow {}: {}", address, e));
+                return;
+            }
+        };
+        let open = self.follower.as_ref().and_then(|previous| {
+            let tabs = &self.tab_manager.tabs;
+            tabs.iter()
+                .position(|tab| is_follow_tab(tab, &previous.shown))
+        });
+        match open {
+            Some(index) => self.tab_manager.set_active_index(index),
+            None => {
+                let mut tab = Tab::new(format!("Following {}", address));
+                tab.apply_view_defaults(self.view_defaults);
+                self.tab_manager.add_tab(tab);
+            }
+        }
+        if let Some(tab) = self.tab_manager.active_tab_mut() {
+            tab.set_read_only(!co_edit);
+            if let Tab::Editor { buffer, .. } = tab {
+                follower.shown = buffer.clone();
+            }
+        }
+        self.follower = Some(follower);
+        let doing = if co_edit { "Co-editing" } else { "Following" };
+        self.set_status_message(format!("{} {}", doing, address), Duration::from_secs(2));
+    }
+
+    /// Apply the edits co-editors sent to the shared tab, or drop them when
+    /// it can't take them
+    fn merge_shared_edits(&mut self) {
+        let Some(host) = &mut self.share_host else {
+            return;
+        };
+        let edits = host.edits();
+        if edits.is_empty() {
+            return;
+        }
+        let mut dropped = 0;
+        match self.tab_manager.active_tab_mut() {
+            Some(
+                tab @ Tab::Editor {
+                    read_only: false, ..
+                },
+            ) if config::get().share.accept_edits => {
+                tab.begin_edit();
+                let mut changes = Vec::new();
+                if let Tab::Editor {
+                    name,
+                    buffer,
+                    cursor,
+                    ..
+                } = tab
+                {
+                    let before = buffer.clone();
+                    for edit in edits {
+                        match host.merge(edit, Some((name.as_str(), &mut *buffer))) {
+                            Some(change) => changes.push(change),
+                            None => dropped += 1,
+                        }
+                    }
+                    carry_cursor(cursor, &before, buffer, &changes);
+                }
+                if !changes.is_empty() {
+                    tab.mark_modified();
+                }
+            }
+            _ => {
+                for edit in edits {
+                    host.merge(edit, None);
+                    dropped += 1;
+                }
+            }
+        }
+        if dropped > 0 {
+            self.set_status_message(
+                format!("Dropped {} edit(s) from co-editors", dropped),
+                Duration::from_secs(2),
+            );
+        }
+    }
+
+    /// Merge co-editors' edits and send the active tab to followers, send
+    /// this co-editor's edits and show what the followed host sent
+    pub fn poll_share(&mut self) {
+        self.merge_shared_edits();
+        if let Some(host) = &mut self.share_host {
+            if let Some(Tab::Editor {
+                name,
+                buffer,
+                cursor,
+                ..
+            }) = self.tab_manager.active_tab()
+            {
+                let version = Version {
+                    name: name.clone(),
+                    revision: buffer.revision(),
+                    line: cursor.position.line,
+                    column: cursor.position.column,
+                };
+                let joined = host.update(version, || buffer.to_string());
+                if joined > 0 {
+                    self.set_status_message(
+                        "A follower joined the session".to_string(),
+                        Duration::from_secs(2),
+                    );
+                }
+            }
+        }
+
+        let Some(follower) = &mut self.follower else {
+            return;
+        };
+        let tabs = &mut self.tab_manager.tabs;
+        let Some(index) = tabs
+            .iter()
+            .position(|tab| is_follow_tab(tab, &follower.shown))
+        else {
+            // Closing the tab stops following
+            self.follower = None;
+            return;
+        };
+
+        // What was typed since goes to the host as one replaced range
+        if let Tab::Editor { buffer, .. } = &tabs[index] {
+            if follower.is_co_editing() && buffer.revision() != follower.shown.revision() {
+                if let Some((range, typed)) = share::difference(&follower.shown, buffer) {
+                    let text = buffer.slice(typed).to_string();
+                    if let Err(e) = follower.send_edit(range, text) {
+                        let address = follower.address.clone();
+                        self.follower = None;
+                        self.report_error(format!("Lost the session at {}: {}", address, e));
+                        return;
+                    }
+                }
+                follower.shown = buffer.clone();
+            }
+        }
+
+        match follower.latest() {
+            Some(None) => {}
+            Some(Some(snapshot)) => {
+                let height = (self.terminal_size.1 as usize).saturating_sub(2);
+                let tab = &mut tabs[index];
+                if let Tab::Editor {
+                    name,
+                    buffer,
+                    cursor,
+                    ..
+                } = tab
+                {
+                    *name = snapshot.name;
+                    let text = RopeBuffer::from_str(&snapshot.text);
+                    if follower.is_co_editing() {
+                        // Only what differs is replaced, and the cursor
+                        // stays this side's own
+                        if let Some((range, theirs)) = share::difference(buffer, &text) {
+                            let before = buffer.clone();
+                            buffer.replace(range.clone(), &text.slice(theirs.clone()).to_string());
+                            carry_cursor(cursor, &before, buffer, &[(range, theirs.len())]);
+                        }
+                    } else {
+                        *buffer = text;
+                        cursor.clear_selection();
+                        cursor.move_to(snapshot.line, snapshot.column);
+                        cursor.clamp_position(buffer);
+                    }
+                    follower.shown = buffer.clone();
+                }
+                tab.update_viewport(self.ui.text_width, height);
+            }
+            None => {
+                let address = follower.address.clone();
+                self.follower = None;
+                self.set_status_message(
+                    format!("The session at {} ended", address),
+                    Duration::from_secs(4),
+                );
+            }
+        }
+    }
+}
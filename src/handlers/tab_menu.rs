@@ -0,0 +1,192 @@
+use crate::app::App;
+use crate::encoding::Transform;
+use crate::generate::Generator;
+use crate::line_ops::LineOp;
+use crate::menu::MenuState;
+use crate::tab::Tab;
+use crate::view::{ViewOptions, ViewOverrides};
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Rect;
+use std::path::PathBuf;
+use std::time::Duration;
+
+impl App {
+    /// Open the active tab's menu below its title
+    pub fn open_current_tab_menu(&mut self) {
+        let tab = self.tab_manager.active_tab();
+        let view = tab.and_then(|tab| tab.view());
+        let preview = tab.filter(|tab| tab.is_markdown()).map(|tab| {
+            matches!(
+                tab,
+                Tab::Editor {
+                    preview_mode: true,
+                    ..
+                }
+            )
+        });
+        self.menu_system.open_current_tab_menu(view, preview);
+    }
+
+    pub fn handle_tab_menu_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Up => self.menu_system.handle_up(),
+            KeyCode::Down => self.menu_system.handle_down(),
+            KeyCode::Enter => self.activate_tab_menu_item(None),
+            _ => {}
+        }
+    }
+
+    /// Returns true if the event was consumed by the open tab menu
+    pub fn handle_mouse_on_tab_menu(&mut self, mouse: MouseEvent) -> bool {
+        if !matches!(self.menu_system.state, MenuState::CurrentTabMenu(_)) {
+            return false;
+        }
+        let x = self.get_tab_x_position_for_menu(self.tab_manager.active_index());
+        let MenuState::CurrentTabMenu(menu) = &mut self.menu_system.state else {
+            return false;
+        };
+        let area = Rect {
+            x,
+            y: 1,
+            width: menu.width,
+            height: menu.height,
+        };
+        let item = menu.get_clicked_item(&area, mouse.column, mouse.row);
+
+        match mouse.kind {
+            MouseEventKind::Moved => {
+                menu.hovered_index = item;
+                item.is_some()
+            }
+            // Clicks elsewhere fall through, so the tab bar can toggle it
+            MouseEventKind::Down(MouseButton::Left) if item.is_some() => {
+                self.activate_tab_menu_item(item);
+                true
+            }
+            _ => item.is_some(),
+        }
+    }
+
+    fn activate_tab_menu_item(&mut self, index: Option<usize>) {
+        if let (MenuState::CurrentTabMenu(menu), Some(index)) = (&mut self.menu_system.state, index)
+        {
+            menu.selected_index = index;
+        }
+        if let Some(action) = self.menu_system.handle_enter() {
+            self.run_tab_menu_action(&action);
+        }
+    }
+
+    fn run_tab_menu_action(&mut self, action: &str) {
+        match action {
+            "next_tab" => self.switch_next_tab(),
+            "prev_tab" => self.switch_prev_tab(),
+            "close_tab" => self.handle_close_tab(),
+            "close_other_tab" => self.close_other_tabs(),
+            "toggle_tab_preview" => {
+                if let Some(tab) = self.tab_manager.active_tab_mut() {
+                    tab.toggle_preview_mode();
+                }
+            }
+            "wrap_column" => {
+                let current = self
+                    .tab_manager
+                    .active_tab()
+                    .and_then(|tab| tab.view())
+                    .and_then(|(_, overrides)| overrides.wrap_column);
+                self.menu_system.open_input_dialog(
+                    "Wrap at column (0 for the view's edge, empty for the default):".to_string(),
+                    "wrap_column".to_string(),
+                    PathBuf::new(),
+                );
+                if let (MenuState::InputDialog(dialog), Some(column)) =
+                    (&mut self.menu_system.state, current)
+                {
+                    dialog.input.set_text(&column.to_string());
+                }
+            }
+            "toggle_tab_word_wrap" => {
+                self.set_tab_view(|view, overrides| overrides.word_wrap = Some(!view.word_wrap))
+            }
+            "toggle_tab_line_numbers" => self
+                .set_tab_view(|view, overrides| overrides.line_numbers = Some(!view.line_numbers)),
+            "toggle_tab_whitespace" => self.set_tab_view(|view, overrides| {
+                overrides.show_whitespace = Some(!view.show_whitespace)
+            }),
+            "toggle_tab_split_long_lines" => self.set_tab_view(|view, overrides| {
+                overrides.split_long_lines = Some(!view.split_long_lines)
+            }),
+            "reset_tab_view" => {
+                self.set_tab_view(|_, overrides| *overrides = ViewOverrides::default())
+            }
+            "lines_menu" => self.menu_system.open_lines_menu(),
+            "transform_menu" => self.menu_system.open_transform_menu(),
+            "insert_menu" => self.menu_system.open_insert_menu(),
+            "align_on" => self.menu_system.open_input_dialog(
+                "Align lines on (for example = or :):".to_string(),
+                "align_on".to_string(),
+                PathBuf::new(),
+            ),
+            action => {
+                if let Some(op) = LineOp::from_action(action) {
+                    self.transform_lines(op);
+                } else if let Some(transform) = Transform::from_action(action) {
+                    self.transform_selection(transform);
+                } else if let Some(generator) = Generator::from_action(action) {
+                    if let Some(tab) = self.tab_manager.active_tab_mut() {
+                        tab.insert_text(&generator.text());
+                        self.ensure_cursor_visible();
+                    }
+                }
+            }
+        }
+    }
+
+    fn transform_lines(&mut self, op: LineOp) {
+        let Some((before, after)) = self
+            .tab_manager
+            .active_tab_mut()
+            .and_then(|tab| tab.transform_lines(|lines| op.apply(lines)))
+        else {
+            return;
+        };
+        let message = match op {
+            LineOp::Unique => format!("Removed {} duplicate lines of {}", before - after, before),
+            LineOp::Reverse => format!("Reversed {} lines", before),
+            LineOp::Shuffle => format!("Shuffled {} lines", before),
+            _ => format!("Sorted {} lines", before),
+        };
+        self.set_status_message(message, Duration::from_secs(2));
+        self.ensure_cursor_visible();
+    }
+
+    fn transform_selection(&mut self, transform: Transform) {
+        let result = self
+            .tab_manager
+            .active_tab_mut()
+            .and_then(|tab| tab.transform_selection(|text| transform.apply(text)));
+        match result {
+            Some(Ok(())) => self.ensure_cursor_visible(),
+            Some(Err(e)) => self.report_error(e),
+            None => self.set_status_message(
+                "Select the text to transform first".to_string(),
+                Duration::from_secs(2),
+            ),
+        }
+    }
+
+    /// Change the active tab's own display options, given the ones in effect
+    pub fn set_tab_view(&mut self, change: impl FnOnce(&ViewOptions, &mut ViewOverrides)) {
+        let defaults = self.view_defaults;
+        let Some(tab) = self.tab_manager.active_tab_mut() else {
+            return;
+        };
+        let Some((view, mut overrides)) = tab.view() else {
+            return;
+        };
+        change(&view, &mut overrides);
+        if let Err(e) = tab.set_view_overrides(overrides, defaults) {
+            self.report_error(format!("Failed to remember the view of this tab: {}", e));
+        }
+    }
+}
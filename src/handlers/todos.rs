@@ -0,0 +1,114 @@
+use crate::app::App;
+use crate::jobs::JobKind;
+use crate::menu::{MenuState, ResultRow, TodoListState};
+use crate::tab::Tab;
+use crate::todos::{self, Tags};
+use crate::ui::project_search as panel;
+use crossterm::event::{KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Rect;
+
+impl App {
+    /// Open the Alt+T panel and collect the workspace's TODOs into it
+    pub fn open_todo_list(&mut self) {
+        let tags = Tags::from_config();
+        let mut state = TodoListState::new(tags.clone());
+        let results = state.results.clone();
+        let root = std::env::current_dir().unwrap_or_default();
+        state.job = Some(
+            self.jobs
+                .spawn(JobKind::TodoScan, "Collecting TODOs", move |job| {
+                    todos::run(&root, &tags, &results, job)
+                }),
+        );
+        self.menu_system.state = MenuState::TodoList(state);
+    }
+
+    /// Close the panel, stopping a scan still running
+    pub fn close_todo_list(&mut self) {
+        if let MenuState::TodoList(state) = &mut self.menu_system.state {
+            if let Some(job) = state.job.take() {
+                self.jobs.cancel(job);
+            }
+        }
+        self.menu_system.close();
+    }
+
+    /// Open the selected TODO (or a file's first one) with the cursor on it
+    fn open_selected_todo(&mut self) {
+        let MenuState::TodoList(state) = &self.menu_system.state else {
+            return;
+        };
+        let index = match state.rows().get(state.selected_index) {
+            Some(ResultRow::File(index) | ResultRow::Match(index)) => *index,
+            None => return,
+        };
+        let Some(found) = state.results.lock().matches.get(index).cloned() else {
+            return;
+        };
+        self.close_todo_list();
+        let path = std::env::current_dir()
+            .unwrap_or_default()
+            .join(&found.path);
+        self.open_linked_file(path, Some(found.line + 1));
+        if let Some(Tab::Editor { cursor, .. }) = self.tab_manager.active_tab_mut() {
+            cursor.move_to(found.line, found.column);
+        }
+    }
+
+    pub fn handle_todo_list_key(&mut self, key: KeyEvent) {
+        use crossterm::event::{KeyCode, KeyModifiers};
+
+        let MenuState::TodoList(state) = &mut self.menu_system.state else {
+            return;
+        };
+        let page =
+            panel::todo_list_area(Rect::new(0, 0, self.terminal_size.0, self.terminal_size.1))
+                .height
+                .max(1) as isize;
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('t'), KeyModifiers::ALT) => self.close_todo_list(),
+            (KeyCode::Enter, _) => self.open_selected_todo(),
+            (KeyCode::Up, _) => state.move_by(-1),
+            (KeyCode::Down, _) => state.move_by(1),
+            (KeyCode::PageUp, _) => state.move_by(-page),
+            (KeyCode::PageDown, _) => state.move_by(page),
+            (KeyCode::Home, _) => state.selected_index = 0,
+            (KeyCode::End, _) => state.move_by(isize::MAX / 2),
+            _ => {}
+        }
+    }
+
+    /// Clicking a row opens it; clicking outside closes the panel
+    pub fn handle_mouse_on_todo_list(&mut self, mouse: MouseEvent) {
+        let MenuState::TodoList(state) = &mut self.menu_system.state else {
+            return;
+        };
+        let screen = Rect::new(0, 0, self.terminal_size.0, self.terminal_size.1);
+        let modal = panel::modal_area(screen);
+        let list = panel::todo_list_area(screen);
+        let contains = |area: Rect| {
+            mouse.column >= area.x
+                && mouse.column < area.x + area.width
+                && mouse.row >= area.y
+                && mouse.row < area.y + area.height
+        };
+
+        match mouse.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if !contains(modal) {
+                    self.close_todo_list();
+                } else if contains(list) {
+                    let first = panel::first_visible(state.selected_index, list.height as usize);
+                    let index = first + (mouse.row - list.y) as usize;
+                    if index < state.rows().len() {
+                        state.selected_index = index;
+                        self.open_selected_todo();
+                    }
+                }
+            }
+            MouseEventKind::ScrollUp => state.move_by(-3),
+            MouseEventKind::ScrollDown => state.move_by(3),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,377 @@
+use crate::app::{App, FocusMode};
+use crate::jobs::JobKind;
+use crate::menu::MenuState;
+use crossterm::event::{KeyCode, KeyEvent, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::Rect;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+impl App {
+    /// Open the tree context menu for the item under the mouse, or the
+    /// empty-area menu below the last item
+    pub fn open_tree_context_menu_at(&mut self, mouse: MouseEvent) {
+        let tree_view = match &mut self.tree_view {
+            Some(tree_view) => tree_view,
+            None => return,
+        };
+        self.focus_mode = FocusMode::TreeView;
+        tree_view.is_focused = true;
+
+        let has_clipboard = tree_view.has_clipboard();
+        let position = (mouse.column, mouse.row);
+        match tree_view.index_at_row(mouse.row, 1) {
+            Some(index) => {
+                tree_view.selected_index = index;
+                let item = &tree_view.get_visible_items()[index];
+                let (path, is_dir) = (item.path.clone(), item.is_dir);
+                self.menu_system
+                    .open_tree_context_menu(path, is_dir, position, has_clipboard);
+            }
+            None => {
+                let root = tree_view.root.path.clone();
+                self.menu_system
+                    .open_tree_empty_area_menu(root, position, has_clipboard);
+            }
+        }
+
+        // Keep the whole menu on screen
+        let (width, height) = self.terminal_size;
+        if let MenuState::TreeContextMenu(state) = &mut self.menu_system.state {
+            let x = state.position.0.min(width.saturating_sub(state.menu.width));
+            let y = state
+                .position
+                .1
+                .min(height.saturating_sub(state.menu.height + 1));
+            state.position = (x, y);
+        }
+    }
+
+    pub fn handle_tree_menu_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Up => self.menu_system.handle_up(),
+            KeyCode::Down => self.menu_system.handle_down(),
+            KeyCode::Enter => self.activate_tree_menu_item(None),
+            _ => {}
+        }
+    }
+
+    /// Returns true if the event was consumed by the open context menu
+    pub fn handle_mouse_on_tree_menu(&mut self, mouse: MouseEvent) -> bool {
+        let state = match &mut self.menu_system.state {
+            MenuState::TreeContextMenu(state) => state,
+            _ => return false,
+        };
+        let area = Rect {
+            x: state.position.0,
+            y: state.position.1,
+            width: state.menu.width,
+            height: state.menu.height,
+        };
+        let item = state.menu.get_clicked_item(&area, mouse.column, mouse.row);
+
+        match mouse.kind {
+            MouseEventKind::Moved => {
+                state.menu.hovered_index = item;
+                item.is_some()
+            }
+            MouseEventKind::Down(MouseButton::Left) => {
+                match item {
+                    Some(index) => self.activate_tree_menu_item(Some(index)),
+                    None => self.menu_system.close(),
+                }
+                true
+            }
+            MouseEventKind::Down(_) => {
+                self.menu_system.close();
+                false
+            }
+            _ => item.is_some(),
+        }
+    }
+
+    fn activate_tree_menu_item(&mut self, index: Option<usize>) {
+        let (target_path, is_directory) = match &mut self.menu_system.state {
+            MenuState::TreeContextMenu(state) => {
+                if let Some(index) = index {
+                    state.menu.selected_index = index;
+                }
+                (state.target_path.clone(), state.is_directory)
+            }
+            _ => return,
+        };
+
+        if let Some(action) = self.menu_system.handle_enter() {
+            self.run_tree_menu_action(&action, target_path, is_directory);
+        }
+    }
+
+    fn run_tree_menu_action(&mut self, action: &str, target_path: PathBuf, is_directory: bool) {
+        // New entries go inside a folder, or next to a file
+        let target_dir = if is_directory {
+            target_path.clone()
+        } else {
+            target_path
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| target_path.clone())
+        };
+
+        // Archive members and other read-only entries are turned down before
+        // a name is asked for
+        let read_only = match action {
+            "new_file" | "new_folder" => crate::vfs::is_read_only(&target_dir),
+            "duplicate" => crate::vfs::writable(&target_path).is_err(),
+            _ => false,
+        };
+        if read_only {
+            self.report_error(format!(
+                "Cannot change {}: read-only",
+                target_path.display()
+            ));
+            return;
+        }
+
+        match action {
+            "new_file" => self.menu_system.open_input_dialog(
+                "New file name:".to_string(),
+                "new_file".to_string(),
+                target_dir,
+            ),
+            "new_folder" => self.menu_system.open_input_dialog(
+                "New folder name:".to_string(),
+                "new_folder".to_string(),
+                target_dir,
+            ),
+            "open" => match crate::vfs::read_to_string(&target_path) {
+                Ok(content) => self.open_file_in_tab(target_path, &content),
+                Err(e) => self.report_error(format!("Failed to open file: {}", e)),
+            },
+            "copy" | "cut" => {
+                if let Some(tree_view) = &mut self.tree_view {
+                    if action == "copy" {
+                        tree_view.copy_selected();
+                    } else {
+                        tree_view.cut_selected();
+                    }
+                    if let Some(info) = tree_view.get_clipboard_info() {
+                        self.set_status_message(info, Duration::from_secs(2));
+                    }
+                }
+            }
+            "copy_contents" | "copy_path" => {
+                if let Some(tree_view) = &self.tree_view {
+                    let result = if action == "copy_contents" {
+                        tree_view.copy_contents_to_clipboard()
+                    } else {
+                        tree_view.copy_path_to_clipboard()
+                    };
+                    match result {
+                        Ok(message) => self.set_status_message(message, Duration::from_secs(2)),
+                        Err(message) => self.report_error(message),
+                    }
+                }
+            }
+            "paste" => self.start_paste(),
+            "duplicate" => {
+                let result = match &mut self.tree_view {
+                    Some(tree_view) => tree_view.duplicate(&target_path),
+                    None => return,
+                };
+                match result {
+                    Ok(new_path) => self.open_rename_dialog(new_path),
+                    Err(e) => self.report_error(format!("Failed to duplicate: {}", e)),
+                }
+            }
+            "rename" => self.open_rename_dialog(target_path),
+            "delete" => self.confirm_delete(target_path),
+            "collapse_all" => {
+                if let Some(tree_view) = &mut self.tree_view {
+                    tree_view.collapse_all();
+                }
+            }
+            "expand_all" => self.expand_tree_all(),
+            "open_external" => self.open_externally(&target_path, false),
+            "reveal" => self.open_externally(&target_path, true),
+            _ => {}
+        }
+    }
+
+    /// Hand a path to the system's opener, or show it in the file manager
+    /// with `reveal`. Failures end up in the status bar.
+    fn open_externally(&mut self, path: &Path, reveal: bool) {
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        let message = if reveal {
+            format!("Showing {} in the file manager", name)
+        } else {
+            format!("Opened {}", name)
+        };
+        self.run_opener(external_open_command(path, reveal), &name, message);
+    }
+
+    /// Run an opener command in the background, reporting `message` once it
+    /// succeeds
+    pub(crate) fn run_opener(&mut self, mut command: Command, name: &str, message: String) {
+        let program = command.get_program().to_string_lossy().into_owned();
+
+        // The opener must not draw over the editor or read its keys
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                self.report_error(format!("Failed to run {}: {}", program, e));
+                return;
+            }
+        };
+
+        self.jobs.spawn(
+            JobKind::ExternalCommand,
+            format!("Opening {}", name),
+            // explorer exits with 1 even when it worked, so only trust the
+            // status elsewhere
+            move |_| match child.wait() {
+                Ok(status) if status.success() || cfg!(windows) => Ok(message),
+                Ok(status) => Err(format!("{} failed ({})", program, status)),
+                Err(e) => Err(format!("{} failed: {}", program, e)),
+            },
+        );
+    }
+
+    /// Expand the tree to the configured depth, noting when the entry limit
+    /// stopped it early
+    pub fn expand_tree_all(&mut self) {
+        let Some(tree_view) = &mut self.tree_view else {
+            return;
+        };
+        let settings = &crate::config::get().tree;
+        if !tree_view.expand_all(settings.expand_depth, settings.expand_limit) {
+            self.set_status_message(
+                format!("Expand all stopped after {} entries", settings.expand_limit),
+                Duration::from_secs(3),
+            );
+        }
+    }
+
+    /// Open or close the selected folder of the tree, listing a large one in
+    /// the background
+    pub fn toggle_tree_directory(&mut self) {
+        let Some(tree_view) = &mut self.tree_view else {
+            return;
+        };
+        match tree_view.toggle_directory() {
+            Ok(Some(scan)) => {
+                let name = format!("Listing {}", scan.path.display());
+                self.jobs
+                    .spawn(JobKind::DirectoryScan, name, move |_| scan.run());
+            }
+            Ok(None) => {}
+            Err(e) => self.report_error(format!("Failed to open folder: {}", e)),
+        }
+    }
+
+    /// Rename in place in the tree row, falling back to the input dialog
+    /// when the row can't be shown
+    pub fn open_rename_dialog(&mut self, path: PathBuf) {
+        if let Err(e) = crate::vfs::writable(&path) {
+            self.report_error(format!("Cannot rename {}: {}", path.display(), e));
+            return;
+        }
+        if let Some(tree_view) = &mut self.tree_view {
+            if tree_view.start_rename(&path) {
+                self.focus_mode = FocusMode::TreeView;
+                tree_view.is_focused = true;
+                return;
+            }
+        }
+
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let stem_len = if crate::vfs::entry_metadata(&path).is_ok_and(|m| m.is_dir) {
+            name.chars().count()
+        } else {
+            path.file_stem()
+                .map(|s| s.to_string_lossy().chars().count())
+                .unwrap_or(0)
+        };
+
+        self.menu_system
+            .open_input_dialog("Rename to:".to_string(), "rename".to_string(), path);
+        if let MenuState::InputDialog(state) = &mut self.menu_system.state {
+            state.input.set_text(&name);
+            state.input.select_range(0, stem_len);
+        }
+    }
+
+    /// Keys while a tree row is being renamed: Enter commits
+    pub fn handle_inline_rename_key(&mut self, key: KeyEvent) {
+        let Some(tree_view) = &mut self.tree_view else {
+            return;
+        };
+        match key.code {
+            KeyCode::Enter => {
+                if let Some(rename) = tree_view.rename.take() {
+                    let old_name = rename.path.file_name().map(|n| n.to_string_lossy());
+                    let new_name = rename.input.text().trim();
+                    if !new_name.is_empty() && old_name.as_deref() != Some(new_name) {
+                        self.execute_file_operation("rename", &rename.path, new_name);
+                    }
+                }
+            }
+            _ => {
+                if let Some(rename) = &mut tree_view.rename {
+                    rename.input.handle_key(key);
+                }
+            }
+        }
+    }
+
+    /// Drop an unfinished inline rename, e.g. when focus moves elsewhere
+    pub fn cancel_inline_rename(&mut self) {
+        if let Some(tree_view) = &mut self.tree_view {
+            tree_view.rename = None;
+        }
+    }
+}
+
+/// The platform's way of opening a path or URL, or of showing a path in its folder
+pub(crate) fn external_open_command(path: &Path, reveal: bool) -> Command {
+    #[cfg(target_os = "macos")]
+    {
+        let mut command = Command::new("open");
+        if reveal {
+            command.arg("-R");
+        }
+        command.arg(path);
+        command
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let mut command = Command::new("explorer");
+        if reveal {
+            command.arg(format!("/select,{}", path.display()));
+        } else {
+            command.arg(path);
+        }
+        command
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        // xdg-open can't select an entry, so open the folder holding it
+        let target = if reveal {
+            path.parent().unwrap_or(path)
+        } else {
+            path
+        };
+        let mut command = Command::new("xdg-open");
+        command.arg(target);
+        command
+    }
+}
@@ -1,35 +1,35 @@
 use crate::app::App;
+use crate::config;
 use crate::tab::Tab;
 use crate::cursor::Cursor;
+use crate::indent::Indent;
+use crate::language::Language;
 use crate::rope_buffer::RopeBuffer;
+use crate::selection;
 use crossterm::event::{MouseEvent, MouseButton, MouseEventKind};
 
 impl App {
-    pub fn handle_editor_scroll(&mut self, scroll_kind: crossterm::event::MouseEventKind) {
-        use crossterm::event::MouseEventKind;
+    /// Scroll the editor with the wheel. Ctrl+wheel scrolls a page at a time,
+    /// and horizontal wheel events pan long lines when wrap is off.
+    pub fn handle_editor_scroll(
+        &mut self,
+        scroll_kind: crossterm::event::MouseEventKind,
+        modifiers: crossterm::event::KeyModifiers,
+    ) {
+        use crossterm::event::{KeyModifiers, MouseEventKind};
 
         if let Some(tab) = self.tab_manager.active_tab_mut() {
-            let now = std::time::Instant::now();
-
-            // Check if we're continuing to scroll (within 100ms of last scroll)
-            if let Some(last_time) = self.last_scroll_time {
-                if now.duration_since(last_time).as_millis() < 100 {
-                    self.scroll_acceleration = (self.scroll_acceleration + 1).min(5);
-                } else {
-                    self.scroll_acceleration = 1;
-                }
+            let editor_height = (self.terminal_size.1 as usize).saturating_sub(2);
+            let page = modifiers.contains(KeyModifiers::CONTROL);
+            let scroll_amount = if page {
+                editor_height.max(1)
             } else {
-                self.scroll_acceleration = 1;
-            }
-
-            self.last_scroll_time = Some(now);
-
-            let scroll_amount = self.scroll_acceleration;
+                self.wheel.notch()
+            };
 
             match tab {
-                Tab::Editor { viewport_offset, buffer, .. } => {
-                    let editor_height = (self.terminal_size.1 as usize).saturating_sub(2);
-                    let max_scroll = buffer.len_lines().saturating_sub(editor_height);
+                Tab::Editor { viewport_offset, buffer, view, .. } => {
+                    let max_scroll = Tab::max_viewport_line(buffer.len_lines(), editor_height);
 
                     match scroll_kind {
                         MouseEventKind::ScrollUp => {
@@ -38,6 +38,20 @@ impl App {
                         MouseEventKind::ScrollDown => {
                             viewport_offset.0 = (viewport_offset.0 + scroll_amount).min(max_scroll);
                         }
+                        MouseEventKind::ScrollLeft if !view.word_wrap => {
+                            viewport_offset.1 = viewport_offset.1.saturating_sub(scroll_amount * 4);
+                        }
+                        MouseEventKind::ScrollRight if !view.word_wrap => {
+                            // Stop once the longest visible line has scrolled into view
+                            let end_line =
+                                (viewport_offset.0 + editor_height).min(buffer.len_lines());
+                            let longest = (viewport_offset.0..end_line)
+                                .map(|line| buffer.get_line_text(line).chars().count())
+                                .max()
+                                .unwrap_or(0);
+                            viewport_offset.1 = (viewport_offset.1 + scroll_amount * 4)
+                                .min(longest.saturating_sub(1).max(viewport_offset.1));
+                        }
                         _ => {}
                     }
                 }
@@ -106,8 +120,30 @@ impl App {
                             
                             if mouse.column >= input_x_start && mouse.column < input_x_start + input_width {
                                 let click_offset = (mouse.column - input_x_start) as usize;
-                                input_state.cursor_position = click_offset.min(input_state.input.len());
-                                input_state.selection_start = None;
+                                let position = input_state
+                                    .input
+                                    .position_at_column(click_offset, input_width as usize);
+
+                                // Double-click selects the word under the mouse
+                                let now = std::time::Instant::now();
+                                let click_pos = (mouse.column, mouse.row);
+                                let is_double_click = matches!(
+                                    (self.last_click_time, self.last_click_pos),
+                                    (Some(last_time), Some(last_pos))
+                                        if config::get()
+                                            .mouse
+                                            .is_double_click(now.duration_since(last_time))
+                                            && last_pos == click_pos
+                                );
+
+                                if is_double_click {
+                                    input_state.input.select_word_at(position);
+                                    self.last_click_time = None;
+                                } else {
+                                    input_state.input.set_cursor(position);
+                                    self.last_click_time = Some(now);
+                                    self.last_click_pos = Some(click_pos);
+                                }
                             }
                         }
 
@@ -122,11 +158,7 @@ impl App {
                                 self.menu_system.close();
                             } else if mouse.column >= ok_button_x && mouse.column < ok_button_x + 4 {
                                 // OK button clicked
-                                let input = input_state.input.clone();
-                                let operation = input_state.operation.clone();
-                                let target_path = input_state.target_path.clone();
-                                self.menu_system.close();
-                                self.execute_file_operation(&operation, &target_path, &input);
+                                self.submit_input_dialog();
                             }
                         }
                     }
@@ -139,12 +171,10 @@ impl App {
                             
                             if mouse.column >= input_x_start && mouse.column < input_x_start + input_width {
                                 let drag_offset = (mouse.column - input_x_start) as usize;
-                                let new_cursor_pos = drag_offset.min(input_state.input.len());
-                                
-                                if input_state.selection_start.is_none() {
-                                    input_state.selection_start = Some(input_state.cursor_position);
-                                }
-                                input_state.cursor_position = new_cursor_pos;
+                                let position = input_state
+                                    .input
+                                    .position_at_column(drag_offset, input_width as usize);
+                                input_state.input.extend_selection_to(position);
                             }
                         }
                     }
@@ -156,81 +186,73 @@ impl App {
         false
     }
 
-    pub fn select_word_at_cursor(input_state: &mut crate::menu::InputDialogState) {
-        let chars: Vec<char> = input_state.input.chars().collect();
-        let pos = input_state.cursor_position.min(chars.len());
-        
-        if chars.is_empty() {
-            return;
-        }
-
-        // Find start of word
-        let mut start = pos;
-        while start > 0 
-            && !chars[start - 1].is_whitespace() 
-            && !crate::app::is_word_separator(chars[start - 1])
-        {
-            start -= 1;
-        }
-
-        // Find end of word
-        let mut end = pos;
-        while end < chars.len() && !chars[end].is_whitespace() && !crate::app::is_word_separator(chars[end]) {
-            end += 1;
-        }
-
-        // Set selection
-        input_state.selection_start = Some(start);
-        input_state.cursor_position = end;
-    }
-
-    pub fn delete_input_selection(input_state: &mut crate::menu::InputDialogState) {
-        if let Some(sel_start) = input_state.selection_start {
-            let (start, end) = if sel_start < input_state.cursor_position {
-                (sel_start, input_state.cursor_position)
-            } else {
-                (input_state.cursor_position, sel_start)
-            };
-
-            // Remove selected characters
-            for _ in start..end {
-                if start < input_state.input.len() {
-                    input_state.input.remove(start);
-                }
-            }
-
-            input_state.cursor_position = start;
-            input_state.selection_start = None;
-        }
-    }
-
     pub fn delete_selection(
         buffer: &mut RopeBuffer,
         cursor: &mut Cursor,
     ) {
         if let Some((start, end)) = cursor.get_selection() {
-            let start_idx = buffer.line_to_char(start.line)
-                + start.column.min(buffer.get_line_text(start.line).len());
-            let end_idx = buffer.line_to_char(end.line)
-                + end.column.min(buffer.get_line_text(end.line).len());
+            let start_idx = selection::to_index(buffer, start);
+            let end_idx = selection::to_index(buffer, end);
 
             buffer.delete_range(start_idx..end_idx);
             cursor.move_to(start.line, start.column);
             cursor.clear_selection();
+            cursor.desired_column = None;
         }
     }
 
-    pub fn insert_tab(buffer: &mut RopeBuffer, cursor: &mut Cursor) {
+    /// Insert one indent step in the tab's style
+    pub fn insert_tab(buffer: &mut RopeBuffer, cursor: &mut Cursor, indent: Indent) {
+        let char_idx = buffer.line_to_char(cursor.position.line) + cursor.position.column;
+        let fill = indent.fill(cursor.position.column);
+        buffer.insert(char_idx, &fill);
+        cursor.position.column += fill.chars().count();
+        cursor.desired_column = None;
+    }
+
+    /// Break the line, carrying its indentation over and adding a level
+    /// where the language opens a block. Between a bracket pair the closing
+    /// bracket moves to a line of its own.
+    pub fn insert_newline(
+        buffer: &mut RopeBuffer,
+        cursor: &mut Cursor,
+        indent: Indent,
+        language: &Language,
+    ) {
+        let line_text = buffer.get_line_text(cursor.position.line);
+        let before: String = line_text.chars().take(cursor.position.column).collect();
+        let after: String = line_text.chars().skip(cursor.position.column).collect();
+        let base: String = before
+            .chars()
+            .take_while(|ch| *ch == ' ' || *ch == '\t')
+            .collect();
+        let mut leading = base.clone();
+        let mut closing_line = String::new();
+        if language.opens_block(&before) {
+            leading.push_str(&indent.unit());
+            let last = before.trim_end().chars().last();
+            let next = after.trim_start().chars().next();
+            if language
+                .brackets
+                .iter()
+                .any(|&(open, close)| last == Some(open) && next == Some(close))
+            {
+                closing_line = format!("\n{}", base);
+            }
+        }
+
         let char_idx = buffer.line_to_char(cursor.position.line) + cursor.position.column;
-        buffer.insert_char(char_idx, '\t');
-        cursor.move_right(buffer);
+        buffer.insert(char_idx, &format!("\n{}{}", leading, closing_line));
+        cursor.position.line += 1;
+        cursor.position.column = leading.chars().count();
+        cursor.desired_column = None;
     }
 
     pub fn handle_sidebar_resize(&mut self, mouse: MouseEvent) -> bool {
         match mouse.kind {
             MouseEventKind::Down(MouseButton::Left) => {
                 // Check if we're clicking on the resize border (right edge of sidebar)
-                if mouse.column == self.sidebar_width {
+                if !self.sidebar.collapsed && mouse.column == self.sidebar.width {
                     self.sidebar_resizing = true;
                     return true;
                 }
@@ -238,15 +260,14 @@ impl App {
             MouseEventKind::Drag(MouseButton::Left) => {
                 if self.sidebar_resizing {
                     // Update sidebar width, ensuring minimum and maximum bounds
-                    let min_width = 15;
-                    let max_width = self.terminal_size.0 / 2;
-                    self.sidebar_width = mouse.column.max(min_width).min(max_width);
+                    self.sidebar.resize(mouse.column, self.terminal_size.0);
                     return true;
                 }
             }
             MouseEventKind::Up(MouseButton::Left) => {
                 if self.sidebar_resizing {
                     self.sidebar_resizing = false;
+                    self.save_sidebar();
                     return true;
                 }
             }
@@ -254,4 +275,71 @@ impl App {
         }
         false
     }
-}
\ No newline at end of file
+
+    /// Widen (positive) or narrow the sidebar from the keyboard, showing it
+    /// first if it is collapsed
+    pub fn resize_sidebar_by(&mut self, delta: i16) {
+        if self.tree_view.is_none() {
+            return;
+        }
+        self.sidebar.collapsed = false;
+        let width = self.sidebar.width.saturating_add_signed(delta);
+        self.sidebar.resize(width, self.terminal_size.0);
+        self.save_sidebar();
+    }
+
+    /// Leave the mouse to the terminal so its own selection and copy work,
+    /// or take it back
+    pub fn toggle_mouse_capture(&mut self) {
+        self.mouse_captured = !self.mouse_captured;
+        let message = if self.mouse_captured {
+            "Mouse captured again"
+        } else {
+            "Terminal selection: select and copy with the mouse, Alt+M when done"
+        };
+        self.set_status_message(message.to_string(), std::time::Duration::from_secs(3));
+    }
+
+    /// Collapse the sidebar, or bring it back at its old width
+    pub fn toggle_sidebar(&mut self) {
+        if self.tree_view.is_none() {
+            return;
+        }
+        self.sidebar.collapsed = !self.sidebar.collapsed;
+        if self.sidebar.collapsed && self.focus_mode == crate::app::FocusMode::TreeView {
+            self.focus_mode = crate::app::FocusMode::Editor;
+            if let Some(tree_view) = &mut self.tree_view {
+                tree_view.is_focused = false;
+            }
+        }
+        self.save_sidebar();
+    }
+
+    fn save_sidebar(&mut self) {
+        if let Err(e) = self.sidebar.save() {
+            self.report_error(format!("Failed to remember the sidebar width: {}", e));
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deleting_a_selection_clears_it() {
+        let mut buffer = RopeBuffer::from_str("abcdefghi");
+        let mut cursor = Cursor::new();
+        cursor.move_to(0, 3);
+        cursor.start_selection();
+        cursor.move_to(0, 0);
+
+        App::delete_selection(&mut buffer, &mut cursor);
+        assert!(!cursor.has_selection());
+        assert_eq!(buffer.to_string(), "defghi");
+
+        // The next key types at the cursor instead of deleting a stale range
+        App::delete_selection(&mut buffer, &mut cursor);
+        buffer.insert_char(cursor.to_char_index(&buffer), 'x');
+        assert_eq!(buffer.to_string(), "xdefghi");
+    }
+}
@@ -0,0 +1,91 @@
+//! The Help tab: a markdown cheat sheet shown in preview, opened from the
+//! F1 menu and once on the first run. The keys that work everywhere are
+//! listed from the binding table the key handler uses; the rest of the
+//! shortcut and mouse tables are the README's own sections, compiled in.
+
+use crate::recovery::state_dir;
+use crate::rope_buffer::RopeBuffer;
+use crate::tab::Tab;
+
+/// Name of the tab, which also makes it render as markdown
+pub const TAB_NAME: &str = "Help.md";
+
+const README: &str = include_str!("../README.md");
+
+/// README sections the cheat sheet is made of
+const SECTIONS: [&str; 2] = ["## Keyboard Shortcuts", "## Mouse Controls"];
+
+pub fn document() -> String {
+    let mut document = String::from(
+        "# F1 Help\n\n\
+         Press `F1` for the menu, `Ctrl+G` for the menu of the current tab and \
+         `Ctrl+W` to close this one.\n\n",
+    );
+    document.push_str(&crate::handlers::bindings::cheat_sheet());
+    document.push('\n');
+    for heading in SECTIONS {
+        if let Some(section) = section(README, heading) {
+            document.push_str(section.trim_end());
+            document.push_str("\n\n");
+        }
+    }
+    document
+}
+
+/// A `## ` section of a markdown document, heading included, up to the
+/// next one
+fn section<'a>(markdown: &'a str, heading: &str) -> Option<&'a str> {
+    let start = markdown.find(&format!("\n{}\n", heading))? + 1;
+    let rest = &markdown[start + heading.len()..];
+    let end = rest
+        .find("\n## ")
+        .map_or(markdown.len(), |end| start + heading.len() + end + 1);
+    Some(&markdown[start..end])
+}
+
+/// An unsaved tab with the cheat sheet, shown in preview
+pub fn tab() -> Tab {
+    let mut tab = Tab::new(TAB_NAME.to_string());
+    if let Tab::Editor {
+        buffer,
+        preview_mode,
+        ..
+    } = &mut tab
+    {
+        *buffer = RopeBuffer::from_str(&document());
+        *preview_mode = true;
+    }
+    tab
+}
+
+/// Whether f1 runs for the first time, remembering that it has run. A
+/// state directory that can't be written counts as not the first run, so
+/// the help doesn't come back every time.
+pub fn first_run() -> bool {
+    let marker = state_dir().join("first-run-done");
+    if marker.exists() {
+        return false;
+    }
+    std::fs::create_dir_all(state_dir())
+        .and_then(|_| std::fs::write(&marker, ""))
+        .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_sections_from_the_readme() {
+        let markdown = "# T\n\n## A\none\n\n## B\ntwo\n";
+        assert_eq!(section(markdown, "## A"), Some("## A\none\n\n"));
+        assert_eq!(section(markdown, "## B"), Some("## B\ntwo\n"));
+        assert_eq!(section(markdown, "## C"), None);
+
+        let document = document();
+        assert!(document.contains("| Save | `Ctrl+S` |"));
+        assert!(document.contains("## Mouse Controls"));
+        assert!(document.contains("| Menu | `F1` |"));
+        assert!(!document.contains("## Installation"));
+    }
+}
@@ -0,0 +1,237 @@
+//! Indentation style of an editor tab. It is detected from the file's
+//! contents when the file is opened, falls back to the `[editor]` config,
+//! and decides what the Tab key and auto-indent insert.
+
+use crate::config;
+use std::cmp::Reverse;
+
+/// Lines looked at when guessing a file's indentation
+const DETECT_LINES: usize = 1000;
+/// Indent sizes offered when cycling from the status bar
+const SPACE_SIZES: [usize; 3] = [2, 4, 8];
+/// Columns a tab advances, as the editor draws it
+pub const TAB_STOP: usize = 4;
+
+/// Display width of a line's leading whitespace
+pub fn leading_columns(line: &str) -> usize {
+    line.chars()
+        .take_while(|ch| *ch == ' ' || *ch == '\t')
+        .fold(0, |columns, ch| {
+            if ch == '\t' {
+                columns + TAB_STOP - columns % TAB_STOP
+            } else {
+                columns + 1
+            }
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    Tabs,
+    Spaces(usize),
+}
+
+impl Default for Indent {
+    /// The configured style, for new buffers and files with no indentation
+    fn default() -> Self {
+        let editor = &config::get().editor;
+        if editor.indent_with_tabs {
+            Indent::Tabs
+        } else {
+            Indent::Spaces(editor.indent_size.clamp(1, 16))
+        }
+    }
+}
+
+impl Indent {
+    /// Guess the style from leading whitespace. Tabs win when more lines
+    /// start with a tab; otherwise the size is the most common step between
+    /// consecutive space-indented lines.
+    pub fn detect(text: &str) -> Option<Self> {
+        let mut tab_lines = 0;
+        let mut space_lines = 0;
+        let mut steps = [0usize; 9];
+        let mut previous = 0;
+
+        for line in text.lines().take(DETECT_LINES) {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if line.starts_with('\t') {
+                tab_lines += 1;
+                continue;
+            }
+
+            let width = line.len() - line.trim_start_matches(' ').len();
+            // Block comment continuations (" * ...") sit one column off
+            if line[width..].starts_with('*') && width % 2 == 1 {
+                continue;
+            }
+            if width > 0 {
+                space_lines += 1;
+            }
+            let step = width.abs_diff(previous);
+            if (2..steps.len()).contains(&step) {
+                steps[step] += 1;
+            }
+            previous = width;
+        }
+
+        if tab_lines == 0 && space_lines == 0 {
+            return None;
+        }
+        if tab_lines > space_lines {
+            return Some(Indent::Tabs);
+        }
+        // Ties go to the smaller step, which the larger ones are multiples of
+        let size = (2..steps.len()).max_by_key(|&size| (steps[size], Reverse(size)))?;
+        (steps[size] > 0).then_some(Indent::Spaces(size))
+    }
+
+    /// One level of indentation
+    pub fn unit(&self) -> String {
+        match self {
+            Indent::Tabs => "\t".to_string(),
+            Indent::Spaces(size) => " ".repeat(*size),
+        }
+    }
+
+    /// What the Tab key inserts at `column`: a tab, or spaces up to the next
+    /// indent stop
+    pub fn fill(&self, column: usize) -> String {
+        match self {
+            Indent::Tabs => "\t".to_string(),
+            Indent::Spaces(size) => " ".repeat(size - column % size),
+        }
+    }
+
+    /// Whitespace reaching `columns` in this style
+    pub fn whitespace(&self, columns: usize) -> String {
+        match self {
+            Indent::Tabs => format!(
+                "{}{}",
+                "\t".repeat(columns / TAB_STOP),
+                " ".repeat(columns % TAB_STOP)
+            ),
+            Indent::Spaces(_) => " ".repeat(columns),
+        }
+    }
+
+    /// Re-indent pasted text for a cursor whose line is indented by
+    /// `base_columns`. The block keeps its internal shape, written in this
+    /// style. When the cursor sits in the line's indentation the first line
+    /// loses its own, since the cursor already provides it.
+    pub fn reindent(&self, text: &str, base_columns: usize, in_indentation: bool) -> String {
+        let lines: Vec<&str> = text.split('\n').collect();
+        if lines.len() < 2 {
+            return text.to_string();
+        }
+
+        // The first line only counts when it was copied from its start
+        let first_counts = leading_columns(lines[0]) > 0;
+        let common = lines
+            .iter()
+            .enumerate()
+            .filter(|(index, line)| !line.trim().is_empty() && (*index > 0 || first_counts))
+            .map(|(_, line)| leading_columns(line))
+            .min()
+            .unwrap_or(0);
+
+        let mut result = Vec::with_capacity(lines.len());
+        for (index, line) in lines.iter().enumerate() {
+            let content = line.trim_start_matches([' ', '\t']);
+            if index == 0 {
+                result.push(if in_indentation {
+                    content.to_string()
+                } else {
+                    line.to_string()
+                });
+            } else if content.trim().is_empty() {
+                // Keep the line break, drop stray whitespace
+                result.push(content.to_string());
+            } else {
+                let columns = base_columns + leading_columns(line).saturating_sub(common);
+                result.push(format!("{}{}", self.whitespace(columns), content));
+            }
+        }
+        result.join("\n")
+    }
+
+    /// Status bar text
+    pub fn label(&self) -> String {
+        match self {
+            Indent::Tabs => "Tabs".to_string(),
+            Indent::Spaces(size) => format!("Spaces: {}", size),
+        }
+    }
+
+    /// The next style offered by the status bar: 2, 4 and 8 spaces, then tabs
+    pub fn next(&self) -> Self {
+        match self {
+            Indent::Tabs => Indent::Spaces(SPACE_SIZES[0]),
+            Indent::Spaces(size) => SPACE_SIZES
+                .iter()
+                .find(|&&next| next > *size)
+                .map_or(Indent::Tabs, |&next| Indent::Spaces(next)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_space_sizes() {
+        let two = "fn main() {\n  if x {\n    y();\n  }\n}\n";
+        assert_eq!(Indent::detect(two), Some(Indent::Spaces(2)));
+
+        let four = "fn main() {\n    if x {\n        y();\n    }\n}\n";
+        assert_eq!(Indent::detect(four), Some(Indent::Spaces(4)));
+    }
+
+    #[test]
+    fn detects_tabs() {
+        let text = "fn main() {\n\tif x {\n\t\ty();\n\t}\n}\n";
+        assert_eq!(Indent::detect(text), Some(Indent::Tabs));
+    }
+
+    #[test]
+    fn ignores_block_comment_continuations() {
+        let text = "/**\n * Docs\n */\nfn main() {\n    x();\n}\n";
+        assert_eq!(Indent::detect(text), Some(Indent::Spaces(4)));
+    }
+
+    #[test]
+    fn unindented_text_has_no_style() {
+        assert_eq!(Indent::detect("one\ntwo\n\nthree\n"), None);
+    }
+
+    #[test]
+    fn reindent_shifts_the_block_to_the_cursor() {
+        let pasted = "if x {\n    y();\n}";
+        let spaces = Indent::Spaces(4);
+        assert_eq!(
+            spaces.reindent(pasted, 8, true),
+            "if x {\n            y();\n        }"
+        );
+
+        // Copied from inside the indentation of a tab-indented file
+        let pasted = "\t\tfoo();\n\t\tbar();\n\t\t\tbaz();";
+        assert_eq!(
+            spaces.reindent(pasted, 4, true),
+            "foo();\n    bar();\n        baz();"
+        );
+        assert_eq!(
+            Indent::Tabs.reindent("a\n  b\n\n  c", 4, false),
+            "a\n\tb\n\n\tc"
+        );
+    }
+
+    #[test]
+    fn fill_reaches_the_next_stop() {
+        assert_eq!(Indent::Spaces(4).fill(0), "    ");
+        assert_eq!(Indent::Spaces(4).fill(6), "  ");
+        assert_eq!(Indent::Tabs.fill(3), "\t");
+    }
+}
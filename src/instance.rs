@@ -0,0 +1,97 @@
+//! Single-instance mode, opted into with `[editor] single_instance = true`.
+//! The first f1 started in a folder listens on a unix socket named after
+//! that workspace, and a later `f1 FILE` in the same folder hands FILE to
+//! it and exits instead of starting a second editor.
+
+use crate::recovery::{state_dir, workspace_key};
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[cfg(unix)]
+use std::io::{BufRead, BufReader, Write};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+
+fn socket_path() -> PathBuf {
+    // FNV-1a, so every build names the same workspace the same way
+    let hash = workspace_key()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+    state_dir()
+        .join("instances")
+        .join(format!("{:016x}.sock", hash))
+}
+
+/// Open `file` in the f1 running in this workspace. Returns false when
+/// there is none to take it.
+#[cfg(unix)]
+pub fn send(file: &Path) -> bool {
+    let Ok(mut stream) = UnixStream::connect(socket_path()) else {
+        return false;
+    };
+    let file = crate::tab::canonical_path(file);
+    writeln!(stream, "{}", file.to_string_lossy()).is_ok()
+}
+
+#[cfg(not(unix))]
+pub fn send(_file: &Path) -> bool {
+    false
+}
+
+/// The socket of the running instance, removed when it exits
+pub struct Listener {
+    #[cfg(unix)]
+    listener: UnixListener,
+    path: PathBuf,
+}
+
+impl Listener {
+    #[cfg(unix)]
+    pub fn bind() -> io::Result<Listener> {
+        let path = socket_path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // Nobody answering means an f1 that didn't exit cleanly left it
+        if UnixStream::connect(&path).is_err() {
+            let _ = std::fs::remove_file(&path);
+        }
+        let listener = UnixListener::bind(&path)?;
+        listener.set_nonblocking(true)?;
+        Ok(Listener { listener, path })
+    }
+
+    #[cfg(not(unix))]
+    pub fn bind() -> io::Result<Listener> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Files other instances sent since the last call
+    #[cfg(unix)]
+    pub fn received(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        while let Ok((stream, _)) = self.listener.accept() {
+            let ready = stream.set_nonblocking(false).and_then(|_| {
+                stream.set_read_timeout(Some(std::time::Duration::from_millis(100)))
+            });
+            if ready.is_ok() {
+                let lines = BufReader::new(stream).lines().map_while(Result::ok);
+                files.extend(lines.map(PathBuf::from));
+            }
+        }
+        files
+    }
+
+    #[cfg(not(unix))]
+    pub fn received(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+}
+
+impl Drop for Listener {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
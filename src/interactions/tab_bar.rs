@@ -1,12 +1,7 @@
 use crate::app::{App, FocusMode};
-use crate::keyboard::EditorCommand;
 use crate::tab::Tab;
 use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
 
-#[allow(dead_code)]
-const TAB_WIDTH: usize = 14;
-
-#[allow(dead_code)]
 impl App {
     /// Handle mouse events on the tab bar
     pub fn handle_tab_bar_mouse(&mut self, mouse: MouseEvent, active_index: usize) -> bool {
@@ -19,13 +14,31 @@ impl App {
                         return true;
                     }
 
-                    // Check if click is on a tab
+                    // Check if click is on a tab's close target, then the tab itself
                     if let Some(clicked_tab) = self.get_clicked_tab(mouse.column) {
-                        self.handle_tab_click(clicked_tab, mouse.column, active_index);
+                        if self.tab_strip().is_close_target(clicked_tab, mouse.column) {
+                            self.close_tab_with_confirmation(clicked_tab);
+                        } else {
+                            self.handle_tab_click(clicked_tab, mouse.column, active_index);
+                        }
                         return true;
                     }
                 }
             }
+            MouseEventKind::ScrollUp | MouseEventKind::ScrollLeft if mouse.row == 0 => {
+                self.ui.tab_bar.scroll_by(-1);
+                return true;
+            }
+            MouseEventKind::ScrollDown | MouseEventKind::ScrollRight if mouse.row == 0 => {
+                self.ui.tab_bar.scroll_by(1);
+                return true;
+            }
+            MouseEventKind::Down(MouseButton::Middle) if mouse.row == 0 => {
+                if let Some(clicked_tab) = self.get_clicked_tab(mouse.column) {
+                    self.close_tab_with_confirmation(clicked_tab);
+                    return true;
+                }
+            }
             MouseEventKind::Drag(MouseButton::Left) => {
                 if let Some(dragging_idx) = self.dragging_tab {
                     if mouse.row == 0 {
@@ -49,9 +62,7 @@ impl App {
     /// Create a new tab from the Ctrl+N hint click
     fn create_new_tab_from_hint(&mut self) {
         let mut new_tab = Tab::new(format!("untitled-{}", self.tab_manager.len() + 1));
-        if let Tab::Editor { word_wrap, .. } = &mut new_tab {
-            *word_wrap = self.global_word_wrap;
-        }
+        new_tab.apply_view_defaults(self.view_defaults);
         self.tab_manager.add_tab(new_tab);
         self.expand_tree_to_current_file();
         // Focus the editor after creating new tab
@@ -111,66 +122,22 @@ impl App {
                 ) {
                     self.menu_system.close();
                 } else {
-                    self.menu_system.open_current_tab_menu();
+                    self.open_current_tab_menu();
                 }
             }
         }
     }
 
+    /// Layout of the visible tabs on the tab bar row
+    pub fn tab_strip(&self) -> crate::ui::TabStrip {
+        self.ui
+            .tab_bar
+            .strip(&self.tab_manager, self.terminal_size.0 as usize)
+    }
+
     /// Get which tab was clicked based on mouse X position
     pub fn get_clicked_tab(&self, mouse_x: u16) -> Option<usize> {
-        let available_width = self.terminal_size.0 as usize;
-        let hint_text = "  Ctrl+N";
-        let hint_width = hint_text.len();
-        let tabs_width = available_width.saturating_sub(hint_width);
-
-        let tabs = self.tab_manager.tabs();
-        let tab_count = tabs.len();
-
-        if tab_count == 0 {
-            return None;
-        }
-
-        let max_tabs_that_fit = tabs_width / TAB_WIDTH;
-
-        if tab_count <= max_tabs_that_fit {
-            // All tabs are visible with fixed width
-            let tab_index = (mouse_x as usize) / TAB_WIDTH;
-            if tab_index < tab_count {
-                return Some(tab_index);
-            }
-        } else {
-            // Too many tabs, showing subset with scrolling
-            let active_index = self.tab_manager.active_index();
-            let half_width = max_tabs_that_fit / 2;
-
-            let start_index = if active_index >= half_width {
-                (active_index - half_width).min(tab_count.saturating_sub(max_tabs_that_fit))
-            } else {
-                0
-            };
-            let end_index = (start_index + max_tabs_that_fit).min(tab_count);
-
-            let mut current_x = 0u16;
-
-            // Account for left truncation indicator
-            if start_index > 0 {
-                if mouse_x < 3 {
-                    return None; // Clicked on « indicator
-                }
-                current_x = 3;
-            }
-
-            // Check visible tabs
-            for i in start_index..end_index {
-                if mouse_x >= current_x && mouse_x < current_x + TAB_WIDTH as u16 {
-                    return Some(i);
-                }
-                current_x += TAB_WIDTH as u16;
-            }
-        }
-
-        None
+        self.tab_strip().tab_at(mouse_x)
     }
 
     /// Get the X position of a tab for menu positioning
@@ -183,80 +150,7 @@ impl App {
 
     /// Check if the Ctrl+N hint was clicked
     pub fn is_ctrl_n_hint_clicked(&self, mouse_x: u16) -> bool {
-        let available_width = self.terminal_size.0 as usize;
-        let hint_text = "  Ctrl+N";
-        let hint_width = hint_text.len();
-        let tabs_width = available_width.saturating_sub(hint_width);
-
-        let tab_count = self.tab_manager.tabs().len();
-        if tab_count == 0 {
-            // If no tabs, hint starts at x=0
-            return mouse_x < hint_width as u16;
-        }
-
-        let max_tabs_that_fit = tabs_width / TAB_WIDTH;
-
-        // Calculate where all tabs end
-        let tabs_total_width = if tab_count <= max_tabs_that_fit {
-            // All tabs visible with fixed width
-            tab_count * TAB_WIDTH
-        } else {
-            // Showing subset with indicators
-            let active_index = self.tab_manager.active_index();
-            let half_width = max_tabs_that_fit / 2;
-
-            let start_index = if active_index >= half_width {
-                (active_index - half_width).min(tab_count.saturating_sub(max_tabs_that_fit))
-            } else {
-                0
-            };
-            let end_index = (start_index + max_tabs_that_fit).min(tab_count);
-
-            let mut width = 0;
-            if start_index > 0 {
-                width += 3; // " « "
-            }
-            width += (end_index - start_index) * TAB_WIDTH;
-            if end_index < tab_count {
-                width += 3; // " » "
-            }
-            width
-        };
-
-        // The hint starts right after the tabs
-        let hint_start_x = tabs_total_width as u16;
-        let hint_end_x = hint_start_x + hint_width as u16;
-
+        let (hint_start_x, hint_end_x) = self.tab_strip().hint_range();
         mouse_x >= hint_start_x && mouse_x < hint_end_x
     }
-
-    /// Handle keyboard commands related to tabs
-    pub fn handle_tab_bar_command(&mut self, command: EditorCommand) {
-        match command {
-            EditorCommand::NewTab => {
-                self.create_new_tab_from_hint();
-            }
-            EditorCommand::NewTerminal => {
-                let terminal_tab = Tab::new_terminal();
-                self.tab_manager.add_tab(terminal_tab);
-                // Focus the terminal after creating it
-                self.focus_mode = FocusMode::Editor;
-                if let Some(tree_view) = &mut self.tree_view {
-                    tree_view.is_focused = false;
-                }
-            }
-            EditorCommand::CloseTab => {
-                self.handle_close_tab();
-            }
-            EditorCommand::NextTab => {
-                self.tab_manager.next_tab();
-                self.expand_tree_to_current_file();
-            }
-            EditorCommand::PrevTab => {
-                self.tab_manager.prev_tab();
-                self.expand_tree_to_current_file();
-            }
-            _ => {}
-        }
-    }
-}
\ No newline at end of file
+}
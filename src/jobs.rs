@@ -0,0 +1,222 @@
+//! Background jobs on worker threads.
+//!
+//! Every long-running task (search, indexing, watchers, formatters, bulk file
+//! operations) goes through the [`JobManager`] so it gets an ID, reports its
+//! progress to the UI and can be cancelled. Workers never touch the `App`;
+//! they send [`JobEvent`]s that the main loop drains with [`JobManager::poll`].
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JobId(u64);
+
+/// What a job is for, so the app can react when it finishes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobKind {
+    FileOperation,
+    /// A program started on the user's behalf, like the system file opener
+    ExternalCommand,
+    /// Searching the project's files for the search panel
+    Search,
+    /// Listing a large folder of the file tree
+    DirectoryScan,
+    /// Collecting TODO comments for the TODO panel
+    TodoScan,
+}
+
+/// Shared flag a worker checks to stop early
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Handed to the worker closure for progress reporting and cancellation checks
+pub struct JobContext {
+    id: JobId,
+    cancel: CancelToken,
+    events: Sender<JobEvent>,
+}
+
+impl JobContext {
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    pub fn report(&self, progress: impl Into<String>) {
+        let _ = self.events.send(JobEvent {
+            id: self.id,
+            update: JobUpdate::Progress(progress.into()),
+        });
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JobUpdate {
+    Progress(String),
+    Finished(Result<String, String>),
+    Cancelled,
+}
+
+#[derive(Debug, Clone)]
+pub struct JobEvent {
+    pub id: JobId,
+    pub update: JobUpdate,
+}
+
+/// A finished job together with what it was, returned from [`JobManager::poll`]
+#[derive(Debug, Clone)]
+pub struct CompletedJob {
+    pub id: JobId,
+    pub kind: JobKind,
+    pub result: Result<String, String>,
+    pub cancelled: bool,
+}
+
+#[derive(Debug)]
+pub struct JobInfo {
+    pub kind: JobKind,
+    pub name: String,
+    pub progress: Option<String>,
+    cancel: CancelToken,
+}
+
+pub struct JobManager {
+    next_id: u64,
+    jobs: BTreeMap<JobId, JobInfo>,
+    sender: Sender<JobEvent>,
+    receiver: Receiver<JobEvent>,
+}
+
+impl JobManager {
+    pub fn new() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            next_id: 1,
+            jobs: BTreeMap::new(),
+            sender,
+            receiver,
+        }
+    }
+
+    /// Run `work` on a new thread. Its return value becomes the job's result;
+    /// a job that notices cancellation should return early, and its result is
+    /// then reported as cancelled.
+    pub fn spawn<F>(&mut self, kind: JobKind, name: impl Into<String>, work: F) -> JobId
+    where
+        F: FnOnce(&JobContext) -> Result<String, String> + Send + 'static,
+    {
+        let id = JobId(self.next_id);
+        self.next_id += 1;
+
+        let cancel = CancelToken::default();
+        let context = JobContext {
+            id,
+            cancel: cancel.clone(),
+            events: self.sender.clone(),
+        };
+
+        self.jobs.insert(
+            id,
+            JobInfo {
+                kind,
+                name: name.into(),
+                progress: None,
+                cancel,
+            },
+        );
+
+        std::thread::spawn(move || {
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| work(&context)))
+                .unwrap_or_else(|_| Err("job panicked".to_string()));
+            let update = if context.is_cancelled() {
+                JobUpdate::Cancelled
+            } else {
+                JobUpdate::Finished(result)
+            };
+            let _ = context.events.send(JobEvent { id, update });
+        });
+
+        id
+    }
+
+    pub fn cancel(&self, id: JobId) {
+        if let Some(job) = self.jobs.get(&id) {
+            job.cancel.cancel();
+        }
+    }
+
+    pub fn cancel_all(&self) {
+        for job in self.jobs.values() {
+            job.cancel.cancel();
+        }
+    }
+
+    /// Apply pending worker events and return the jobs that ended
+    pub fn poll(&mut self) -> Vec<CompletedJob> {
+        let mut completed = Vec::new();
+        while let Ok(event) = self.receiver.try_recv() {
+            match event.update {
+                JobUpdate::Progress(progress) => {
+                    if let Some(job) = self.jobs.get_mut(&event.id) {
+                        job.progress = Some(progress);
+                    }
+                }
+                JobUpdate::Finished(result) => {
+                    if let Some(job) = self.jobs.remove(&event.id) {
+                        completed.push(CompletedJob {
+                            id: event.id,
+                            kind: job.kind,
+                            result,
+                            cancelled: false,
+                        });
+                    }
+                }
+                JobUpdate::Cancelled => {
+                    if let Some(job) = self.jobs.remove(&event.id) {
+                        completed.push(CompletedJob {
+                            id: event.id,
+                            kind: job.kind,
+                            result: Err(format!("{} cancelled", job.name)),
+                            cancelled: true,
+                        });
+                    }
+                }
+            }
+        }
+        completed
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// One-line description of the running jobs for the status bar
+    pub fn summary(&self) -> Option<String> {
+        let first = self.jobs.values().next()?;
+        let mut text = match &first.progress {
+            Some(progress) => format!("{}: {}", first.name, progress),
+            None => first.name.clone(),
+        };
+        if self.jobs.len() > 1 {
+            text.push_str(&format!(" (+{})", self.jobs.len() - 1));
+        }
+        Some(text)
+    }
+}
+
+impl Default for JobManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -3,7 +3,7 @@ use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use std::sync::OnceLock;
 use std::sync::{Arc, Mutex};
 
-use crate::{cursor::Cursor, rope_buffer::RopeBuffer};
+use crate::{cursor::Cursor, indent::Indent, rope_buffer::RopeBuffer};
 
 // Simple in-memory clipboard
 static CLIPBOARD: OnceLock<Arc<Mutex<String>>> = OnceLock::new();
@@ -91,10 +91,7 @@ pub fn handle_key_event(
 
         // Paste - Ctrl+V or Cmd+V
         KeyCode::Char('v') if has_primary_modifier => {
-            if cursor.has_selection() {
-                delete_selection(buffer, cursor);
-            }
-            paste_from_clipboard(buffer, cursor);
+            paste_from_clipboard(buffer, cursor, Indent::default());
             Some(EditorCommand::Modified)
         }
 
@@ -356,10 +353,8 @@ fn delete_word_forward(buffer: &mut RopeBuffer, cursor: &mut Cursor) {
 
 fn delete_selection(buffer: &mut RopeBuffer, cursor: &mut Cursor) {
     if let Some((start, end)) = cursor.get_selection() {
-        let start_idx = buffer.line_to_char(start.line)
-            + start.column.min(buffer.get_line_text(start.line).len());
-        let end_idx =
-            buffer.line_to_char(end.line) + end.column.min(buffer.get_line_text(end.line).len());
+        let start_idx = crate::selection::to_index(buffer, start);
+        let end_idx = crate::selection::to_index(buffer, end);
 
         if end_idx > start_idx {
             buffer.remove(start_idx..end_idx);
@@ -375,12 +370,35 @@ fn get_clipboard() -> Arc<Mutex<String>> {
         .clone()
 }
 
+/// Copy text to both the internal and the system clipboard
+pub fn set_clipboard_text(text: &str) {
+    if let Ok(mut clipboard) = get_clipboard().lock() {
+        *clipboard = text.to_string();
+    }
+
+    if let Ok(mut system_clipboard) = Clipboard::new() {
+        let _ = system_clipboard.set_text(text);
+    }
+}
+
+/// Read clipboard text, preferring the system clipboard over the internal one
+pub fn get_clipboard_text() -> Option<String> {
+    if let Ok(mut system_clipboard) = Clipboard::new() {
+        if let Ok(text) = system_clipboard.get_text() {
+            if let Ok(mut clipboard) = get_clipboard().lock() {
+                *clipboard = text.clone();
+            }
+            return Some(text);
+        }
+    }
+
+    get_clipboard().lock().ok().map(|clipboard| clipboard.clone())
+}
+
 fn copy_selection(buffer: &RopeBuffer, cursor: &Cursor) {
     if let Some((start, end)) = cursor.get_selection() {
-        let start_idx = buffer.line_to_char(start.line)
-            + start.column.min(buffer.get_line_text(start.line).len());
-        let end_idx =
-            buffer.line_to_char(end.line) + end.column.min(buffer.get_line_text(end.line).len());
+        let start_idx = crate::selection::to_index(buffer, start);
+        let end_idx = crate::selection::to_index(buffer, end);
 
         if end_idx > start_idx {
             let selected_text = buffer.slice(start_idx..end_idx).to_string();
@@ -441,60 +459,47 @@ fn cut_current_line(buffer: &mut RopeBuffer, cursor: &mut Cursor) {
     }
 }
 
-fn paste_from_clipboard(buffer: &mut RopeBuffer, cursor: &mut Cursor) {
-    // Try system clipboard first
-    let text_to_paste = if let Ok(mut system_clipboard) = Clipboard::new() {
-        if let Ok(text) = system_clipboard.get_text() {
-            // Update internal clipboard with system clipboard content
-            if let Ok(mut clipboard) = get_clipboard().lock() {
-                *clipboard = text.clone();
-            }
-            text
-        } else {
-            // Fall back to internal clipboard
-            if let Ok(clipboard) = get_clipboard().lock() {
-                clipboard.clone()
-            } else {
-                return;
-            }
-        }
-    } else {
-        // Fall back to internal clipboard
-        if let Ok(clipboard) = get_clipboard().lock() {
-            clipboard.clone()
-        } else {
-            return;
-        }
+/// Paste at the cursor, in place of the selection. Multi-line text is
+/// re-indented to the cursor's line unless `[editor] reindent_paste` is off.
+pub fn paste_from_clipboard(buffer: &mut RopeBuffer, cursor: &mut Cursor, indent: Indent) {
+    let Some(mut text_to_paste) = get_clipboard_text() else {
+        return;
     };
+    if text_to_paste.is_empty() {
+        return;
+    }
+    let (start, end) = cursor
+        .get_selection()
+        .unwrap_or((cursor.position, cursor.position));
+    cursor.position = start;
+
+    if crate::config::get().editor.reindent_paste {
+        let line_text = buffer.get_line_text(cursor.position.line);
+        let before: String = line_text.chars().take(cursor.position.column).collect();
+        let in_indentation = before.trim().is_empty();
+        text_to_paste = indent.reindent(
+            &text_to_paste,
+            crate::indent::leading_columns(&line_text),
+            in_indentation,
+        );
+    }
 
-    if !text_to_paste.is_empty() {
-        let char_idx = cursor.to_char_index(buffer);
-        let initial_column = cursor.position.column;
-
-        // Insert the text all at once - this is already efficient in ropey
-        buffer.insert(char_idx, &text_to_paste);
-
-        // Calculate new cursor position efficiently without iterating through characters
-        let lines: Vec<&str> = text_to_paste.lines().collect();
-        let num_new_lines = lines.len().saturating_sub(1);
-
-        if num_new_lines > 0 {
-            // Multi-line paste: cursor goes to the end of the last pasted line
-            cursor.position.line += num_new_lines;
-            // For multi-line paste, we need to account for text after cursor on original line
-            // The last line length is where the cursor should be
-            cursor.position.column = lines.last().unwrap_or(&"").len();
+    let start = crate::selection::to_index(buffer, start);
+    buffer.replace(start..crate::selection::to_index(buffer, end), &text_to_paste);
 
-            // If we pasted in the middle of a line, the remaining text is now after our cursor
-            // on the last line of the pasted content, so we don't need to adjust further
-        } else {
-            // Single line paste: just advance by the pasted text length
-            cursor.position.column = initial_column + text_to_paste.len();
+    // The cursor ends after the pasted text. Columns count characters, and
+    // a trailing line break leaves it at the start of the next line.
+    match text_to_paste.rfind('\n') {
+        Some(last_break) => {
+            cursor.position.line += text_to_paste.matches('\n').count();
+            cursor.position.column = text_to_paste[last_break + 1..].chars().count();
         }
-
-        // Clear selection after paste
-        cursor.clear_selection();
+        None => cursor.position.column += text_to_paste.chars().count(),
     }
+    cursor.desired_column = None;
+
+    // Clear selection after paste
+    cursor.clear_selection();
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
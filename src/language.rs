@@ -0,0 +1,504 @@
+//! Languages the editor knows about, picked by file extension or name. A
+//! language describes its comment tokens, bracket pairs, what opens an
+//! indented block and which highlighter draws it; toggle-comment, auto-indent
+//! and highlighting read them from here. The `[languages.<name>]` config
+//! sections change the built-in languages or add new ones.
+//!
+//! [`SyntaxMap`] tells code from strings and comments, so bracket matching
+//! and selection can leave the latter alone: from the syntax tree when the
+//! language has a tree-sitter grammar, otherwise by scanning each line for
+//! the comment tokens and quotes.
+
+use crate::config::{self, LanguageConfig};
+use crate::rope_buffer::RopeBuffer;
+use crate::syntax_tree::{self, Tree};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+const DEFAULT_BRACKETS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+const DEFAULT_QUOTES: [char; 2] = ['"', '\''];
+
+/// What a character is part of
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    Code,
+    String,
+    Comment,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Language {
+    pub name: String,
+    pub extensions: Vec<String>,
+    pub file_names: Vec<String>, // Lowercase, for files without a telling extension
+    pub line_comment: Option<String>,
+    pub block_comment: Option<(String, String)>,
+    pub brackets: Vec<(char, char)>,
+    pub quotes: Vec<char>, // Open and close a string on the same line
+    pub indent_after: Vec<String>, // Line endings that open an indented block
+    pub highlighter: Option<String>, // Grammar the editor colors the text with
+    pub max_line_length: Option<usize>, // Overrides the editor's limit; 0 turns it off
+}
+
+impl Language {
+    fn new(name: &str, extensions: &[&str], line_comment: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            extensions: extensions.iter().map(|ext| ext.to_string()).collect(),
+            file_names: Vec::new(),
+            line_comment: line_comment.map(str::to_string),
+            block_comment: None,
+            brackets: DEFAULT_BRACKETS.to_vec(),
+            quotes: DEFAULT_QUOTES.to_vec(),
+            indent_after: DEFAULT_BRACKETS
+                .iter()
+                .map(|(open, _)| open.to_string())
+                .collect(),
+            highlighter: None,
+            max_line_length: None,
+        }
+    }
+
+    fn block_comment(mut self, open: &str, close: &str) -> Self {
+        self.block_comment = Some((open.to_string(), close.to_string()));
+        self
+    }
+
+    fn file_names(mut self, names: &[&str]) -> Self {
+        self.file_names = names.iter().map(|name| name.to_string()).collect();
+        self
+    }
+
+    fn indent_after(mut self, endings: &[&str]) -> Self {
+        self.indent_after = endings.iter().map(|ending| ending.to_string()).collect();
+        self
+    }
+
+    fn quotes(mut self, quotes: &[char]) -> Self {
+        self.quotes = quotes.to_vec();
+        self
+    }
+
+    fn highlighter(mut self, grammar: &str) -> Self {
+        self.highlighter = Some(grammar.to_string());
+        self
+    }
+
+    /// Apply a config section's settings over this language
+    fn apply(&mut self, settings: &LanguageConfig) {
+        let non_empty = |text: &String| (!text.is_empty()).then(|| text.clone());
+        if let Some(extensions) = &settings.extensions {
+            self.extensions = extensions.iter().map(|ext| ext.to_lowercase()).collect();
+        }
+        if let Some(names) = &settings.file_names {
+            self.file_names = names.iter().map(|name| name.to_lowercase()).collect();
+        }
+        if let Some(token) = &settings.line_comment {
+            self.line_comment = non_empty(token);
+        }
+        if let Some([open, close]) = &settings.block_comment {
+            self.block_comment = Some((open.clone(), close.clone())).filter(|_| !open.is_empty());
+        }
+        if let Some(pairs) = &settings.brackets {
+            self.brackets = pairs
+                .iter()
+                .filter_map(|pair| {
+                    let mut chars = pair.chars();
+                    Some((chars.next()?, chars.next()?))
+                })
+                .collect();
+        }
+        if let Some(quotes) = &settings.quotes {
+            self.quotes = quotes.iter().filter_map(|quote| quote.chars().next()).collect();
+        }
+        if let Some(endings) = &settings.indent_after {
+            self.indent_after = endings.clone();
+        }
+        if let Some(grammar) = &settings.highlighter {
+            self.highlighter = non_empty(grammar);
+        }
+        if let Some(limit) = settings.max_line_length {
+            self.max_line_length = Some(limit);
+        }
+    }
+
+    /// Column past which text is shaded as too long, if there is a limit
+    pub fn line_length_limit(&self) -> Option<usize> {
+        let limit = self
+            .max_line_length
+            .unwrap_or(config::get().editor.max_line_length);
+        (limit > 0).then_some(limit)
+    }
+
+    /// Comment out `lines`, or uncomment them when every non-blank one is
+    /// already commented. Line comments go at the shallowest indentation;
+    /// languages with only block comments wrap each line. None when the
+    /// language has no comment syntax.
+    pub fn toggle_comment(&self, lines: &[String]) -> Option<Vec<String>> {
+        let is_blank = |line: &str| line.trim().is_empty();
+        // Indentation is ASCII whitespace, so its byte and char lengths agree
+        let indent_of = |line: &str| line.len() - line.trim_start().len();
+        // A lone blank line still gets a comment to type into
+        let skip = |line: &str| is_blank(line) && lines.len() > 1;
+        let content: Vec<&String> = lines.iter().filter(|line| !is_blank(line)).collect();
+
+        if let Some(token) = self.line_comment.as_deref() {
+            let commented = !content.is_empty()
+                && content
+                    .iter()
+                    .all(|line| line.trim_start().starts_with(token));
+            let column = content
+                .iter()
+                .map(|line| indent_of(line))
+                .min()
+                .unwrap_or(0);
+            return Some(
+                lines
+                    .iter()
+                    .map(|line| {
+                        if skip(line) {
+                            line.clone()
+                        } else if commented {
+                            let start = indent_of(line);
+                            let rest = &line[start + token.len()..];
+                            format!(
+                                "{}{}",
+                                &line[..start],
+                                rest.strip_prefix(' ').unwrap_or(rest)
+                            )
+                        } else {
+                            let at = column.min(line.len());
+                            format!("{}{} {}", &line[..at], token, &line[at..])
+                        }
+                    })
+                    .collect(),
+            );
+        }
+
+        let (open, close) = self.block_comment.as_ref()?;
+        let wrapped = |line: &str| {
+            let text = line.trim();
+            text.len() >= open.len() + close.len()
+                && text.starts_with(open.as_str())
+                && text.ends_with(close.as_str())
+        };
+        let commented = !content.is_empty() && content.iter().all(|line| wrapped(line));
+        Some(
+            lines
+                .iter()
+                .map(|line| {
+                    let start = indent_of(line);
+                    let end = line.trim_end().len().max(start);
+                    if skip(line) {
+                        line.clone()
+                    } else if commented {
+                        let inner = &line[start + open.len()..end - close.len()];
+                        let inner = inner.strip_prefix(' ').unwrap_or(inner);
+                        let inner = inner.strip_suffix(' ').unwrap_or(inner);
+                        format!("{}{}", &line[..start], inner)
+                    } else {
+                        format!("{}{} {} {}", &line[..start], open, &line[start..end], close)
+                    }
+                })
+                .collect(),
+        )
+    }
+
+    /// Whether a line ending with `text` opens an indented block. Trailing
+    /// comments and strings don't count.
+    pub fn opens_block(&self, text: &str) -> bool {
+        let (kinds, _) = self.classify_line(text, false);
+        let code: String = text
+            .chars()
+            .zip(kinds)
+            .filter(|&(_, kind)| kind == Syntax::Code)
+            .map(|(ch, _)| ch)
+            .collect();
+        let code = code.trim_end();
+        self.indent_after
+            .iter()
+            .any(|ending| !ending.is_empty() && code.ends_with(ending.as_str()))
+    }
+
+    /// What each character of a line is part of, given whether the line
+    /// starts inside a block comment, and whether it ends inside one.
+    /// Strings end with their line; a backslash escapes the next character.
+    pub fn classify_line(&self, line: &str, in_block_comment: bool) -> (Vec<Syntax>, bool) {
+        let chars: Vec<char> = line.chars().collect();
+        let starts_with = |at: usize, token: &str| {
+            !token.is_empty()
+                && token
+                    .chars()
+                    .enumerate()
+                    .all(|(k, ch)| chars.get(at + k) == Some(&ch))
+        };
+        let mut kinds = Vec::with_capacity(chars.len());
+        let mut in_comment = in_block_comment;
+        let mut quote = None;
+        while kinds.len() < chars.len() {
+            let at = kinds.len();
+            if in_comment {
+                match &self.block_comment {
+                    Some((_, close)) if starts_with(at, close) => {
+                        kinds.extend(close.chars().map(|_| Syntax::Comment));
+                        in_comment = false;
+                    }
+                    _ => kinds.push(Syntax::Comment),
+                }
+            } else if let Some(open) = quote {
+                kinds.push(Syntax::String);
+                if chars[at] == '\\' && at + 1 < chars.len() {
+                    kinds.push(Syntax::String);
+                } else if chars[at] == open {
+                    quote = None;
+                }
+            } else if self.line_comment.as_deref().is_some_and(|token| starts_with(at, token)) {
+                kinds.resize(chars.len(), Syntax::Comment);
+            } else if let Some((open, _)) = self
+                .block_comment
+                .as_ref()
+                .filter(|(open, _)| starts_with(at, open))
+            {
+                kinds.extend(open.chars().map(|_| Syntax::Comment));
+                in_comment = true;
+            } else if self.quotes.contains(&chars[at]) {
+                kinds.push(Syntax::String);
+                quote = Some(chars[at]);
+            } else {
+                kinds.push(Syntax::Code);
+            }
+        }
+        (kinds, in_comment)
+    }
+}
+
+/// What a buffer's characters are part of. Without a syntax tree, lines are
+/// classified on demand, top down, since a block comment carries over from
+/// one line to the next.
+pub struct SyntaxMap<'a> {
+    language: &'a Language,
+    buffer: &'a RopeBuffer,
+    tree: Option<Tree>,
+    lines: Vec<(Vec<Syntax>, bool)>,
+}
+
+impl<'a> SyntaxMap<'a> {
+    pub fn new(language: &'a Language, buffer: &'a RopeBuffer) -> Self {
+        let tree = language
+            .highlighter
+            .as_deref()
+            .and_then(|grammar| buffer.syntax_tree(grammar));
+        Self {
+            language,
+            buffer,
+            tree,
+            lines: Vec::new(),
+        }
+    }
+
+    /// Character ranges of the syntax nodes around `start..end`, innermost
+    /// first; none without a tree
+    pub fn node_ranges(&self, start: usize, end: usize) -> Vec<(usize, usize)> {
+        self.tree
+            .as_ref()
+            .map(|tree| syntax_tree::node_ranges(tree, self.buffer, start, end))
+            .unwrap_or_default()
+    }
+
+    /// What the character at `index` is part of; a line break belongs to a
+    /// block comment running past it
+    pub fn at(&mut self, index: usize) -> Syntax {
+        let index = index.min(self.buffer.len_chars());
+        if let Some(tree) = &self.tree {
+            return syntax_tree::syntax_at(tree, self.buffer, index);
+        }
+        let line = self.buffer.char_to_line(index);
+        while self.lines.len() <= line {
+            let in_comment = self.lines.last().is_some_and(|(_, in_comment)| *in_comment);
+            let text = self.buffer.get_line_text(self.lines.len());
+            self.lines.push(self.language.classify_line(&text, in_comment));
+        }
+        let (kinds, in_comment) = &self.lines[line];
+        let column = index - self.buffer.line_to_char(line);
+        match kinds.get(column) {
+            Some(&kind) => kind,
+            None if *in_comment => Syntax::Comment,
+            None => Syntax::Code,
+        }
+    }
+}
+
+fn builtins() -> Vec<Language> {
+    let c_like = |name: &str, extensions: &[&str]| {
+        Language::new(name, extensions, Some("//")).block_comment("/*", "*/")
+    };
+    vec![
+        // Single quotes also start lifetimes
+        c_like("Rust", &["rs"]).quotes(&['"']).highlighter("rust"),
+        c_like("C", &["c", "h"]),
+        c_like("C++", &["cpp", "cc", "cxx", "hpp", "hh"]),
+        c_like("C#", &["cs"]),
+        c_like("Go", &["go"]).highlighter("go"),
+        c_like("Java", &["java"]),
+        c_like("Kotlin", &["kt", "kts"]),
+        c_like("Swift", &["swift"]),
+        c_like("Zig", &["zig"]),
+        c_like("JavaScript", &["js", "mjs", "cjs", "jsx"]).highlighter("javascript"),
+        c_like("TypeScript", &["ts", "mts", "cts", "tsx"]),
+        c_like("JSON with comments", &["jsonc", "json5"]),
+        c_like("PHP", &["php"]),
+        c_like("SCSS", &["scss", "less"]),
+        Language::new("Python", &["py", "pyi"], Some("#"))
+            .indent_after(&[":", "(", "[", "{"])
+            .highlighter("python"),
+        Language::new("Ruby", &["rb"], Some("#")).file_names(&["gemfile", "rakefile"]),
+        Language::new("Shell", &["sh", "bash", "zsh", "fish"], Some("#"))
+            .file_names(&[".bashrc", ".zshrc", ".profile"])
+            .highlighter("bash"),
+        Language::new("TOML", &["toml"], Some("#")),
+        Language::new("YAML", &["yaml", "yml"], Some("#")).indent_after(&[":", "[", "{"]),
+        Language::new("Makefile", &["mk"], Some("#")).file_names(&["makefile", "gnumakefile"]),
+        Language::new("Dockerfile", &[], Some("#")).file_names(&["dockerfile", "containerfile"]),
+        Language::new("Git config", &[], Some("#")).file_names(&[".gitignore", ".gitattributes"]),
+        Language::new("Lua", &["lua"], Some("--")).block_comment("--[[", "]]"),
+        Language::new("SQL", &["sql"], Some("--")).block_comment("/*", "*/"),
+        Language::new("Haskell", &["hs"], Some("--")).block_comment("{-", "-}"),
+        Language::new("Vim script", &["vim"], Some("\"")).file_names(&[".vimrc"]),
+        Language::new("Lisp", &["el", "lisp", "clj", "scm"], Some(";")),
+        Language::new("INI", &["ini", "cfg", "conf"], Some(";")),
+        Language::new("JSON", &["json"], None).highlighter("json"),
+        Language::new("HTML", &["html", "htm", "xml", "svg", "vue"], None)
+            .block_comment("<!--", "-->"),
+        Language::new("CSS", &["css"], None).block_comment("/*", "*/"),
+        Language::new("Markdown", &["md", "markdown"], None)
+            .block_comment("<!--", "-->")
+            .quotes(&[])
+            .indent_after(&[]),
+        Language::new("Diff", &["diff", "patch"], None)
+            .quotes(&[])
+            .indent_after(&[])
+            .highlighter("diff"),
+    ]
+}
+
+struct Registry {
+    languages: Vec<Language>,
+    by_extension: HashMap<String, usize>,
+    by_file_name: HashMap<String, usize>,
+    plain: Language,
+}
+
+fn registry() -> &'static Registry {
+    static REGISTRY: OnceLock<Registry> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let mut languages = builtins();
+        // Config sections change the language of the same name (ignoring
+        // case) or add one
+        let mut configured: Vec<_> = config::get().languages.iter().collect();
+        configured.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, settings) in configured {
+            let existing = languages
+                .iter()
+                .position(|language| language.name.eq_ignore_ascii_case(name));
+            let index = existing.unwrap_or_else(|| {
+                languages.push(Language::new(name, &[], None));
+                languages.len() - 1
+            });
+            languages[index].apply(settings);
+        }
+
+        // Later languages win an extension they share with earlier ones
+        let mut by_extension = HashMap::new();
+        let mut by_file_name = HashMap::new();
+        for (index, language) in languages.iter().enumerate() {
+            for ext in &language.extensions {
+                by_extension.insert(ext.clone(), index);
+            }
+            for name in &language.file_names {
+                by_file_name.insert(name.clone(), index);
+            }
+        }
+        Registry {
+            languages,
+            by_extension,
+            by_file_name,
+            plain: Language::new("Plain Text", &[], None).quotes(&[]),
+        }
+    })
+}
+
+/// Language of a file with this name; plain text when none matches
+pub fn for_file_name(name: &str) -> &'static Language {
+    let registry = registry();
+    let lower = name.to_lowercase();
+    let index = registry.by_file_name.get(&lower).or_else(|| {
+        Path::new(&lower)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(|ext| registry.by_extension.get(ext))
+    });
+    index.map_or(&registry.plain, |&index| &registry.languages[index])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn languages_match_by_extension_and_name() {
+        assert_eq!(for_file_name("main.RS").name, "Rust");
+        assert_eq!(for_file_name("Makefile").name, "Makefile");
+        assert_eq!(for_file_name("notes").name, "Plain Text");
+        assert_eq!(
+            for_file_name("a.patch").highlighter.as_deref(),
+            Some("diff")
+        );
+    }
+
+    #[test]
+    fn comments_toggle() {
+        let lines = |text: &[&str]| text.iter().map(|line| line.to_string()).collect::<Vec<_>>();
+        let rust = for_file_name("a.rs");
+        let code = lines(&["    a();", "", "        b();"]);
+        let commented = rust.toggle_comment(&code).unwrap();
+        assert_eq!(commented, lines(&["    // a();", "", "    //     b();"]));
+        assert_eq!(rust.toggle_comment(&commented).unwrap(), code);
+
+        let html = for_file_name("a.html");
+        let wrapped = html.toggle_comment(&lines(&["  <p>"])).unwrap();
+        assert_eq!(wrapped, lines(&["  <!-- <p> -->"]));
+        assert_eq!(html.toggle_comment(&wrapped).unwrap(), lines(&["  <p>"]));
+        assert_eq!(for_file_name("a.json").toggle_comment(&code), None);
+    }
+
+    #[test]
+    fn config_overrides_settings() {
+        let mut python = for_file_name("x.py").clone();
+        assert!(python.opens_block("if x:  "));
+        python.apply(&LanguageConfig {
+            line_comment: Some(String::new()),
+            indent_after: Some(vec!["do".to_string()]),
+            ..LanguageConfig::default()
+        });
+        assert_eq!(python.line_comment, None);
+        assert!(python.opens_block("loop do") && !python.opens_block("if x:"));
+    }
+
+    #[test]
+    fn strings_and_comments_are_told_apart() {
+        use Syntax::{Code as C, Comment as M, String as S};
+        let rust = for_file_name("a.rs");
+        let (kinds, open) = rust.classify_line(r#"f("a\"") /* x"#, false);
+        assert_eq!(kinds, vec![C, C, S, S, S, S, S, C, C, M, M, M, M]);
+        assert!(open);
+        let (kinds, open) = rust.classify_line("x */ y // z", true);
+        assert_eq!(kinds[..6], [M, M, M, M, C, C]);
+        assert_eq!(kinds[9..], [M, M]);
+        assert!(!open);
+
+        assert!(rust.opens_block("fn f() { // done }"));
+        assert!(!rust.opens_block("let s = \"{\""));
+        assert!(!rust.opens_block("// if x {"));
+    }
+}
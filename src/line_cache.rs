@@ -0,0 +1,168 @@
+//! The rows the editor drew for each line on the last frame. A line whose
+//! text, view and styling inputs are the same as then is drawn from here
+//! instead of being styled and wrapped again, so on a large view an idle
+//! frame or a keystroke only restyles the lines it changed. Without a
+//! syntax tree, whether each line starts inside a block comment is kept
+//! too, so the lines above the view are classified once rather than on
+//! every frame.
+
+use crate::rope_buffer::RopeBuffer;
+use ratatui::text::Line;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// What styles the rows of one line, besides its text and the view
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineInputs {
+    pub cursor_column: Option<usize>,
+    /// Selected columns, to `usize::MAX` when the selection goes on past
+    /// the line
+    pub selection: Option<(usize, usize)>,
+    /// Shown find matches as (start, end, current)
+    pub matches: Vec<(usize, usize, bool)>,
+    pub starts_in_comment: bool,
+}
+
+#[derive(Default)]
+pub struct LineCache {
+    state: RefCell<State>,
+}
+
+#[derive(Default)]
+struct State {
+    view: u64,
+    revision: u64,
+    lines: HashMap<usize, (LineInputs, Vec<Line<'static>>)>,
+    // Whether each line starts inside a block comment, for the lines from
+    // the top that have been classified
+    comments: Vec<bool>,
+}
+
+impl LineCache {
+    /// Forget the lines that a change of `view` (a hash of the settings that
+    /// style every line) or the buffer's edits since the last frame touched
+    pub fn start_frame(&self, buffer: &RopeBuffer, view: u64) {
+        let mut state = self.state.borrow_mut();
+        if state.view != view {
+            state.view = view;
+            state.lines.clear();
+            state.comments.clear();
+        }
+        if state.revision != buffer.revision() {
+            match buffer.changed_lines_since(state.revision) {
+                Some(changed) => {
+                    state.lines.retain(|line, _| !changed.contains(line));
+                    // A line's start depends only on the lines above it
+                    state.comments.truncate(changed.start + 1);
+                }
+                None => {
+                    state.lines.clear();
+                    state.comments.clear();
+                }
+            }
+            state.revision = buffer.revision();
+        }
+    }
+
+    /// Whether `line` starts inside a block comment. `ends_in_comment` tells
+    /// for a line above it, given whether that one starts inside one; it is
+    /// only asked about lines no frame has needed since they last changed.
+    pub fn starts_in_comment(
+        &self,
+        line: usize,
+        ends_in_comment: impl Fn(usize, bool) -> bool,
+    ) -> bool {
+        let mut state = self.state.borrow_mut();
+        if state.comments.is_empty() {
+            state.comments.push(false);
+        }
+        while state.comments.len() <= line {
+            let above = state.comments.len() - 1;
+            let ends = ends_in_comment(above, state.comments[above]);
+            state.comments.push(ends);
+        }
+        state.comments[line]
+    }
+
+    /// The rows drawn for `line` last time, if they were drawn from the same
+    /// inputs
+    pub fn get(&self, line: usize, inputs: &LineInputs) -> Option<Vec<Line<'static>>> {
+        let state = self.state.borrow();
+        let (cached_inputs, rows) = state.lines.get(&line)?;
+        (cached_inputs == inputs).then(|| rows.clone())
+    }
+
+    pub fn insert(&self, line: usize, inputs: LineInputs, rows: Vec<Line<'static>>) {
+        self.state.borrow_mut().lines.insert(line, (inputs, rows));
+    }
+
+    /// Drop the lines that scrolled out of view
+    pub fn keep(&self, lines: Range<usize>) {
+        self.state
+            .borrow_mut()
+            .lines
+            .retain(|line, _| lines.contains(line));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_forget_only_the_lines_they_touch() {
+        let mut buffer = RopeBuffer::from_str("one\ntwo\nthree\nfour");
+        let cache = LineCache::default();
+        let inputs = LineInputs {
+            cursor_column: None,
+            selection: None,
+            matches: Vec::new(),
+            starts_in_comment: false,
+        };
+        let fill = |cache: &LineCache| {
+            for line in 0..4 {
+                cache.insert(line, inputs.clone(), vec![Line::from("row")]);
+            }
+        };
+        let kept = |cache: &LineCache| -> Vec<bool> {
+            (0..4)
+                .map(|line| cache.get(line, &inputs).is_some())
+                .collect()
+        };
+        cache.start_frame(&buffer, 1);
+        fill(&cache);
+
+        buffer.insert(buffer.line_to_char(1), "2");
+        cache.start_frame(&buffer, 1);
+        assert_eq!(kept(&cache), [true, false, true, true]);
+
+        // A new line moves every line after it
+        buffer.insert(buffer.line_to_char(2), "\n");
+        cache.start_frame(&buffer, 1);
+        assert_eq!(kept(&cache), [true, false, false, false]);
+
+        fill(&cache);
+        cache.start_frame(&buffer, 2);
+        assert!(cache.get(0, &inputs).is_none());
+
+        // Comment starts are kept above an edit and found again below it
+        let asked = std::cell::Cell::new(0);
+        let ends = |line: usize, starts: bool| {
+            asked.set(asked.get() + 1);
+            starts || line == 1
+        };
+        assert!(cache.starts_in_comment(3, ends));
+        assert_eq!(asked.get(), 3);
+        buffer.insert(buffer.line_to_char(2), "x");
+        cache.start_frame(&buffer, 2);
+        assert!(cache.starts_in_comment(3, ends));
+        assert_eq!(asked.get(), 4);
+
+        // A buffer put back by undo shares no history with the cache
+        fill(&cache);
+        let restored = RopeBuffer::from_str("one\ntwo\nthree\nfour");
+        cache.start_frame(&restored, 2);
+        assert!(cache.get(0, &inputs).is_none());
+    }
+}
@@ -0,0 +1,176 @@
+//! Whole-line transformations for the tab menu's Lines submenu: sorting,
+//! dropping repeats, reversing, shuffling and aligning on a delimiter. They
+//! work on the selected lines or the whole file and the tab applies each as
+//! one undo step.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashSet;
+use std::hash::{BuildHasher, Hasher};
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineOp {
+    SortAscending,
+    SortDescending,
+    SortNumeric, // By the first number on the line; lines without one go last
+    Unique,      // The first of each repeated line stays
+    Reverse,
+    Shuffle,
+}
+
+impl LineOp {
+    /// The submenu action of each operation, in menu order
+    pub const ALL: [(LineOp, &'static str, &'static str); 6] = [
+        (LineOp::SortAscending, "Sort Ascending", "lines_sort"),
+        (LineOp::SortDescending, "Sort Descending", "lines_sort_desc"),
+        (LineOp::SortNumeric, "Sort by Number", "lines_sort_numeric"),
+        (LineOp::Unique, "Remove Duplicates", "lines_unique"),
+        (LineOp::Reverse, "Reverse", "lines_reverse"),
+        (LineOp::Shuffle, "Shuffle", "lines_shuffle"),
+    ];
+
+    pub fn from_action(action: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|(_, _, name)| *name == action)
+            .map(|(op, _, _)| *op)
+    }
+
+    pub fn apply(self, lines: &mut Vec<String>) {
+        match self {
+            LineOp::SortAscending => lines.sort(),
+            LineOp::SortDescending => lines.sort_by(|a, b| b.cmp(a)),
+            LineOp::SortNumeric => lines.sort_by(|a, b| match (first_number(a), first_number(b)) {
+                (Some(a), Some(b)) => a.total_cmp(&b),
+                (a, b) => a.is_none().cmp(&b.is_none()),
+            }),
+            LineOp::Unique => {
+                let mut seen = HashSet::new();
+                lines.retain(|line| seen.insert(line.clone()));
+            }
+            LineOp::Reverse => lines.reverse(),
+            LineOp::Shuffle => {
+                let mut random = RandomState::new().build_hasher().finish() | 1;
+                for i in (1..lines.len()).rev() {
+                    // xorshift64
+                    random ^= random << 13;
+                    random ^= random >> 7;
+                    random ^= random << 17;
+                    lines.swap(i, (random % (i as u64 + 1)) as usize);
+                }
+            }
+        }
+    }
+}
+
+/// Pad lines so the first `delimiter` on each starts in the same column.
+/// A delimiter written after a space, like ` = `, is what lines up; one
+/// attached to the word before it, like `key: value`, stays attached and the
+/// text after it lines up instead. Lines without the delimiter are left alone.
+pub fn align_on(lines: &mut [String], delimiter: &str) {
+    let split: Vec<Option<(&str, &str)>> = lines
+        .iter()
+        .map(|line| line.find(delimiter).map(|at| line.split_at(at)))
+        .collect();
+    let Some((first_before, _)) = split.iter().flatten().next() else {
+        return;
+    };
+    let spaced = first_before.ends_with(char::is_whitespace);
+
+    // Each line as the part to pad and the part that goes after the padding
+    let parts: Vec<Option<(String, String)>> = split
+        .iter()
+        .map(|parts| {
+            parts.map(|(before, from)| {
+                let after = &from[delimiter.len()..];
+                if spaced {
+                    (before.trim_end().to_string(), from.to_string())
+                } else {
+                    (format!("{}{}", before, delimiter), after.trim_start().to_string())
+                }
+            })
+        })
+        .collect();
+    let Some(width) = parts.iter().flatten().map(|(padded, _)| padded.width()).max() else {
+        return;
+    };
+
+    for (line, parts) in lines.iter_mut().zip(parts) {
+        if let Some((padded, rest)) = parts {
+            let aligned = if rest.is_empty() {
+                padded
+            } else {
+                format!("{}{} {}", padded, " ".repeat(width - padded.width()), rest)
+            };
+            *line = aligned;
+        }
+    }
+}
+
+/// The first number on a line, with its sign and decimals
+fn first_number(line: &str) -> Option<f64> {
+    let start = line.find(|ch: char| ch.is_ascii_digit())?;
+    let negative = line[..start].ends_with('-');
+    let digits: String = line[start..]
+        .chars()
+        .take_while(|ch| ch.is_ascii_digit() || *ch == '.')
+        .collect();
+    let number: f64 = digits.trim_end_matches('.').parse().ok()?;
+    Some(if negative { -number } else { number })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn applied(op: LineOp, lines: &[&str]) -> Vec<String> {
+        let mut lines = lines.iter().map(|line| line.to_string()).collect();
+        op.apply(&mut lines);
+        lines
+    }
+
+    #[test]
+    fn transforms_lines() {
+        let lines = ["b 10", "a", "c -2.5", "b 9", "a"];
+        assert_eq!(
+            applied(LineOp::SortAscending, &lines),
+            ["a", "a", "b 10", "b 9", "c -2.5"]
+        );
+        assert_eq!(
+            applied(LineOp::SortDescending, &lines),
+            ["c -2.5", "b 9", "b 10", "a", "a"]
+        );
+        assert_eq!(
+            applied(LineOp::SortNumeric, &lines),
+            ["c -2.5", "b 9", "b 10", "a", "a"]
+        );
+        assert_eq!(
+            applied(LineOp::Unique, &lines),
+            ["b 10", "a", "c -2.5", "b 9"]
+        );
+        assert_eq!(
+            applied(LineOp::Reverse, &lines),
+            ["a", "b 9", "c -2.5", "a", "b 10"]
+        );
+
+        let mut shuffled = applied(LineOp::Shuffle, &lines);
+        shuffled.sort();
+        assert_eq!(shuffled, applied(LineOp::SortAscending, &lines));
+        assert_eq!(LineOp::from_action("lines_unique"), Some(LineOp::Unique));
+    }
+
+    #[test]
+    fn aligns_on_a_delimiter() {
+        let mut lines = ["a = 1", "  long_name=2", "# no delimiter", "b  =  x = y"]
+            .map(String::from);
+        align_on(&mut lines, "=");
+        assert_eq!(
+            lines,
+            ["a           = 1", "  long_name =2", "# no delimiter", "b           =  x = y"]
+        );
+
+        let mut lines = ["name: f1", "version:  2", "tags:"].map(String::from);
+        align_on(&mut lines, ":");
+        assert_eq!(lines, ["name:    f1", "version: 2", "tags:"]);
+    }
+}
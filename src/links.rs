@@ -0,0 +1,146 @@
+//! Links in buffer text: URLs, and file paths optionally followed by
+//! `:line` (or `:line:column`, as compilers print them). Ctrl+click and
+//! Alt+Enter open the one under the pointer or cursor.
+
+use std::path::{Path, PathBuf};
+
+const URL_SCHEMES: [&str; 2] = ["https://", "http://"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Link {
+    Url(String),
+    File { path: String, line: Option<usize> },
+}
+
+/// Characters that end a link. Brackets and quotes usually wrap one rather
+/// than belong to it.
+fn is_delimiter(ch: char) -> bool {
+    ch.is_whitespace()
+        || matches!(
+            ch,
+            '"' | '\'' | '`' | '<' | '>' | '(' | ')' | '[' | ']' | '{' | '}' | ',' | ';' | '|'
+        )
+}
+
+/// The link around `column` of a line. A cursor just past the end of a link
+/// still counts as on it.
+pub fn link_at(line: &str, column: usize) -> Option<Link> {
+    let chars: Vec<char> = line.chars().collect();
+    let on_link = |index: usize| index < chars.len() && !is_delimiter(chars[index]);
+    let column = if on_link(column) {
+        column
+    } else if column > 0 && on_link(column - 1) {
+        column - 1
+    } else {
+        return None;
+    };
+
+    let mut start = column;
+    while start > 0 && on_link(start - 1) {
+        start -= 1;
+    }
+    let mut end = column + 1;
+    while on_link(end) {
+        end += 1;
+    }
+    let token: String = chars[start..end].iter().collect();
+    // Sentence punctuation after a link isn't part of it
+    let token = token.trim_end_matches(['.', ':', '!', '?']);
+
+    if let Some(at) = URL_SCHEMES
+        .iter()
+        .filter_map(|scheme| token.find(scheme))
+        .min()
+    {
+        let url = &token[at..];
+        let has_host = URL_SCHEMES
+            .iter()
+            .any(|scheme| url.len() > scheme.len() && url.starts_with(scheme));
+        return has_host.then(|| Link::Url(url.to_string()));
+    }
+
+    let (path, line) = split_location(token);
+    let looks_like_path =
+        path.contains(['/', '\\', '.']) && path.chars().any(char::is_alphanumeric);
+    looks_like_path.then(|| Link::File {
+        path: path.to_string(),
+        line,
+    })
+}
+
+/// Split `path:line` and `path:line:column` into the path and line
+fn split_location(token: &str) -> (&str, Option<usize>) {
+    let mut path = token;
+    let mut numbers = Vec::new();
+    while numbers.len() < 2 {
+        match path.rsplit_once(':') {
+            Some((rest, number))
+                if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) =>
+            {
+                numbers.push(number.parse().ok());
+                path = rest;
+            }
+            _ => break,
+        }
+    }
+    (path, numbers.last().copied().flatten())
+}
+
+/// The file a link's path names: relative paths are looked up next to the
+/// buffer's file first, then in the working directory
+pub fn resolve(path: &str, base: Option<&Path>) -> Option<PathBuf> {
+    let expanded = match path.strip_prefix("~/") {
+        Some(rest) => PathBuf::from(std::env::var_os("HOME")?).join(rest),
+        None => PathBuf::from(path),
+    };
+    if expanded.is_absolute() {
+        return expanded.is_file().then_some(expanded);
+    }
+    let cwd = std::env::current_dir().ok();
+    base.into_iter()
+        .chain(cwd.as_deref())
+        .map(|dir| dir.join(&expanded))
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(path: &str, line: Option<usize>) -> Option<Link> {
+        Some(Link::File {
+            path: path.to_string(),
+            line,
+        })
+    }
+
+    #[test]
+    fn finds_urls() {
+        let line = "See (https://example.com/docs?q=1). Thanks";
+        let url = Some(Link::Url("https://example.com/docs?q=1".to_string()));
+        assert_eq!(link_at(line, 10), url);
+        assert_eq!(
+            link_at("url=http://a.b", 0),
+            Some(Link::Url("http://a.b".to_string()))
+        );
+        assert_eq!(link_at("https://", 3), None);
+    }
+
+    #[test]
+    fn finds_paths_with_lines() {
+        assert_eq!(
+            link_at("  --> src/main.rs:42:7", 8),
+            file("src/main.rs", Some(42))
+        );
+        assert_eq!(link_at("open README.md:3.", 6), file("README.md", Some(3)));
+        // Just past the end of the path
+        assert_eq!(link_at("\"./notes.txt\"", 12), file("./notes.txt", None));
+    }
+
+    #[test]
+    fn plain_words_are_not_links() {
+        assert_eq!(link_at("hello world", 2), None);
+        assert_eq!(link_at("a  b", 2), None);
+        assert_eq!(link_at("...", 1), None);
+    }
+}
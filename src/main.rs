@@ -1,27 +1,77 @@
+mod annotations;
 mod app;
+mod archive;
+mod cli;
+mod completion;
+mod config;
 mod cursor;
+mod diff;
 mod editor_widget;
+mod encoding;
+mod export;
 mod file_icons;
+mod fold;
+mod generate;
+mod gutter;
+mod help;
+mod instance;
 mod gitignore;
+mod indent;
+mod jobs;
 mod keyboard;
+mod language;
+mod line_cache;
+mod line_ops;
+mod links;
+mod marks;
 mod markdown_widget;
 mod menu;
+mod messages;
+mod paste;
+mod perf;
+mod pipe;
+mod project_search;
+mod recovery;
+mod remote;
 mod rope_buffer;
+mod scratch;
+mod scroll;
+mod share;
+mod selection;
+mod sidebar;
+mod suspend;
+mod syntax_tree;
 mod tab;
 mod tab_operations;
 mod file_operations;
 mod terminal_widget;
+mod theme;
+mod todos;
+mod tree_state;
 mod tree_view;
 mod ui;
+mod vfs;
+mod view;
+mod walk;
+mod wrap;
 
 // New modular structure
 mod interactions;
 mod handlers;
 
-use std::io::{self, stdout};
+use std::io::{self, stdout, IsTerminal};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 
 use crossterm::{
-    event::{DisableMouseCapture, EnableMouseCapture},
+    cursor::SetCursorStyle,
+    event::{
+        DisableFocusChange, DisableMouseCapture, EnableFocusChange, EnableMouseCapture, Event,
+        KeyCode, KeyEvent, KeyEventKind, KeyModifiers,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -31,40 +81,97 @@ use crate::app::App;
 use crate::tab::Tab;
 
 fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
+    // As $PAGER, f1 gets the text on standard input
+    let pager = args.first().is_some_and(|arg| arg == "--pager");
+    let file = args
+        .get(usize::from(pager))
+        .cloned()
+        .or_else(|| (pager && !io::stdin().is_terminal()).then(|| "-".to_string()));
+    // Connected before the terminal is taken over, so ssh can prompt
+    let remote = match file.as_deref().and_then(cli::sftp_url) {
+        Some(url) => match remote::connect(&url) {
+            Ok(path) => Some(path),
+            Err(e) => {
+                eprintln!("f1: {}: {}", file.unwrap_or_default(), e);
+                std::process::exit(1);
+            }
+        },
+        None => None,
+    };
+    let single_instance = config::get().editor.single_instance && !pager && remote.is_none();
+    if let Some(file) = file.as_deref().filter(|file| single_instance && *file != "-") {
+        if instance::send(Path::new(file)) {
+            println!("Opened {} in the f1 already running here", file);
+            return Ok(());
+        }
+    }
+
+    recovery::install_panic_hook();
+    let terminate = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(signal_hook::consts::SIGTERM, Arc::clone(&terminate))?;
+    let signals = Signals::register()?;
+
     enable_raw_mode()?;
     let mut stdout = stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen, EnableFocusChange)?;
 
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
     let mut app = App::new();
-
-    if let Some(args) = std::env::args().nth(1) {
-        if let Ok(content) = std::fs::read_to_string(&args) {
-            let tab = Tab::from_file(args.into(), &content);
+    if single_instance {
+        app.instance = instance::Listener::bind().ok();
+    }
+    if let Some(path) = remote {
+        app.open_remote(path);
+    } else if let Some(file) = file {
+        if file == "-" {
+            app.open_stdin_tab();
+        } else if let Ok(content) = vfs::read_to_string(&file) {
+            let tab = Tab::from_file(file.into(), &content);
             app.tab_manager.tabs.clear();
             app.tab_manager.add_tab(tab);
         }
     }
-
-    loop {
-        terminal.draw(|frame| app.draw(frame))?;
-
-        if !app.running {
-            break;
+    if pager {
+        app.start_pager();
+    } else {
+        app.restore_scratch_tabs();
+        if help::first_run() {
+            app.open_help_tab();
         }
+    }
 
-        if crossterm::event::poll(std::time::Duration::from_millis(100))? {
-            match crossterm::event::read()? {
-                crossterm::event::Event::Key(key) => {
-                    app.handle_key_event(key);
-                }
-                crossterm::event::Event::Mouse(mouse) => {
-                    app.handle_mouse_event(mouse);
-                }
-                _ => {}
-            }
+    // Catch panics so unsaved work can still be dumped; the panic hook has
+    // already restored the terminal and printed the message by then
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        run(&mut terminal, &mut app, &terminate, &signals)
+    }));
+    if result.is_ok() {
+        app.save_scratch_tabs();
+        app.save_tree_state();
+    }
+    remote::disconnect();
+    match result {
+        Ok(Ok(())) if terminate.load(Ordering::Relaxed) => {
+            recovery::restore_terminal();
+            recovery::recover(&app);
+            std::process::exit(143);
+        }
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => {
+            recovery::restore_terminal();
+            recovery::recover(&app);
+            return Err(e);
+        }
+        Err(_) => {
+            recovery::recover(&app);
+            std::process::exit(101);
         }
     }
 
@@ -72,9 +179,171 @@ fn main() -> io::Result<()> {
     execute!(
         terminal.backend_mut(),
         LeaveAlternateScreen,
-        DisableMouseCapture
+        DisableMouseCapture,
+        DisableFocusChange,
+        SetCursorStyle::DefaultUserShape
     )?;
     terminal.show_cursor()?;
+    print!("{}", app.exit_output);
 
     Ok(())
 }
+
+/// How often the event loop wakes to redraw and poll background work while
+/// the terminal is in the background; input still wakes it at once
+const BACKGROUND_INTERVAL: Duration = Duration::from_secs(1);
+
+fn run(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+    terminate: &AtomicBool,
+    signals: &Signals,
+) -> io::Result<()> {
+    // Typing mode and terminal focus the cursor shape was last set for, and
+    // whether the mouse was last captured
+    let mut cursor_mode = None;
+    let mut mouse_mode = None;
+    loop {
+        app.poll_jobs();
+        app.poll_share();
+        app.poll_stdin();
+        app.poll_instance();
+        app.poll_file_picker();
+        app.autosave_scratch_tabs();
+        app.autosave_switched_tab();
+        {
+            let _frame = perf::span("frame");
+            terminal.draw(|frame| app.draw(frame))?;
+        }
+        if cursor_mode != Some((app.overwrite_mode, app.terminal_focused)) {
+            cursor_mode = Some((app.overwrite_mode, app.terminal_focused));
+            execute!(terminal.backend_mut(), app.cursor_style())?;
+        }
+        if mouse_mode != Some(app.mouse_captured) {
+            mouse_mode = Some(app.mouse_captured);
+            if app.mouse_captured {
+                execute!(terminal.backend_mut(), EnableMouseCapture)?;
+            } else {
+                execute!(terminal.backend_mut(), DisableMouseCapture)?;
+            }
+        }
+
+        if !app.running || terminate.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+
+        if app.suspend_requested || signals.stop.swap(false, Ordering::Relaxed) {
+            app.suspend_requested = false;
+            suspend::suspend(terminal)?;
+            signals.resumed.store(false, Ordering::Relaxed);
+            cursor_mode = None;
+            mouse_mode = None;
+            continue;
+        }
+
+        // Stopped by something we could not intercept (e.g. SIGSTOP)
+        if signals.resumed.swap(false, Ordering::Relaxed) {
+            suspend::resume(terminal)?;
+            cursor_mode = None;
+            mouse_mode = None;
+            continue;
+        }
+
+        // Wake up often enough to keep a drag-select autoscroll moving
+        app.tick_drag_autoscroll();
+        let timeout = if app.drag_autoscroll.is_some() {
+            handlers::mouse::AUTOSCROLL_INTERVAL
+        } else if !app.terminal_focused {
+            // Nobody is looking: redraw and poll for background work less
+            BACKGROUND_INTERVAL
+        } else {
+            Duration::from_millis(100)
+        };
+        if crossterm::event::poll(timeout)? {
+            let mut event = Some(crossterm::event::read()?);
+            while let Some(current) = event.take() {
+                match current {
+                    Event::Key(key) => match typed_char(&key) {
+                        Some(first) => {
+                            let (text, next) = read_typed_burst(first)?;
+                            app.handle_typed_text(&text);
+                            event = next;
+                        }
+                        None => {
+                            app.handle_key_event(key);
+                        }
+                    },
+                    Event::Mouse(mouse) => {
+                        app.handle_mouse_event(mouse);
+                    }
+                    Event::FocusGained => {
+                        app.terminal_focused = true;
+                        app.refresh_git_branch();
+                    }
+                    Event::FocusLost => {
+                        app.terminal_focused = false;
+                        app.handle_focus_lost();
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// The character a key press types, if it types one
+fn typed_char(key: &KeyEvent) -> Option<char> {
+    match (key.code, key.modifiers, key.kind) {
+        (KeyCode::Char(ch), KeyModifiers::NONE | KeyModifiers::SHIFT, KeyEventKind::Press) => {
+            Some(ch)
+        }
+        _ => None,
+    }
+}
+
+/// `first` and the typed characters already waiting behind it. Input
+/// methods commit a composed string all at once, so it arrives as such a
+/// burst. Returns the event that ended the burst, if any.
+fn read_typed_burst(first: char) -> io::Result<(String, Option<Event>)> {
+    let mut text = first.to_string();
+    while crossterm::event::poll(Duration::ZERO)? {
+        let event = crossterm::event::read()?;
+        match &event {
+            Event::Key(key) => match typed_char(key) {
+                Some(ch) => text.push(ch),
+                None => return Ok((text, Some(event))),
+            },
+            _ => return Ok((text, Some(event))),
+        }
+    }
+    Ok((text, None))
+}
+
+/// Job-control signals, recorded by flag and handled from the main loop
+struct Signals {
+    stop: Arc<AtomicBool>,
+    resumed: Arc<AtomicBool>,
+}
+
+impl Signals {
+    #[cfg(unix)]
+    fn register() -> io::Result<Self> {
+        use signal_hook::consts::{SIGCONT, SIGTSTP};
+
+        let signals = Self {
+            stop: Arc::new(AtomicBool::new(false)),
+            resumed: Arc::new(AtomicBool::new(false)),
+        };
+        signal_hook::flag::register(SIGTSTP, Arc::clone(&signals.stop))?;
+        signal_hook::flag::register(SIGCONT, Arc::clone(&signals.resumed))?;
+        Ok(signals)
+    }
+
+    #[cfg(not(unix))]
+    fn register() -> io::Result<Self> {
+        Ok(Self {
+            stop: Arc::new(AtomicBool::new(false)),
+            resumed: Arc::new(AtomicBool::new(false)),
+        })
+    }
+}
@@ -0,0 +1,123 @@
+//! Quick file slots: Alt+Shift+1..4 remembers the current file and cursor
+//! position in a slot and Alt+1..4 jumps back to it. Slots belong to the
+//! workspace (the directory f1 was started in) and are kept in
+//! `<state dir>/marks.json` across sessions.
+
+use crate::annotations::{Annotation, Placement, Severity};
+use crate::recovery::{state_dir, workspace_key};
+use crossterm::event::{KeyCode, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const SLOTS: usize = 4;
+
+/// Source of the gutter notes showing which lines the slots point at
+pub const ANNOTATION_SOURCE: &str = "marks";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mark {
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Every workspace's slots, keyed by the workspace path
+type MarkFile = BTreeMap<String, Vec<Option<Mark>>>;
+
+fn marks_file() -> PathBuf {
+    state_dir().join("marks.json")
+}
+
+/// A missing or unreadable file holds no marks
+fn read_all() -> MarkFile {
+    fs::read_to_string(marks_file())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// The mark in a slot of the current workspace
+pub fn get(slot: usize) -> Option<Mark> {
+    read_all()
+        .remove(&workspace_key())?
+        .into_iter()
+        .nth(slot)
+        .flatten()
+}
+
+/// Gutter notes numbering the slots that point into a file
+pub fn annotations(path: &Path) -> Vec<Annotation> {
+    let slots = read_all().remove(&workspace_key()).unwrap_or_default();
+    slots
+        .into_iter()
+        .enumerate()
+        .filter_map(|(slot, mark)| {
+            let mark = mark.filter(|mark| mark.path == path)?;
+            Some(Annotation {
+                line: mark.line,
+                text: (slot + 1).to_string(),
+                placement: Placement::Gutter,
+                severity: Severity::Info,
+            })
+        })
+        .collect()
+}
+
+/// Put a mark in a slot. The file is read again first so slots set by
+/// another f1 in a different workspace survive.
+pub fn set(slot: usize, mark: Mark) -> io::Result<()> {
+    let mut all = read_all();
+    let slots = all.entry(workspace_key()).or_default();
+    slots.resize(SLOTS.max(slots.len()), None);
+    slots[slot] = Some(mark);
+
+    let path = marks_file();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let content = serde_json::to_string_pretty(&all).map_err(io::Error::other)?;
+    fs::write(path, content)
+}
+
+/// The slot a key refers to, and whether it assigns (Alt+Shift+digit) rather
+/// than jumps (Alt+digit). Most terminals report Alt+Shift+1 as Alt+`!`, so
+/// the shifted symbols of a US layout count too.
+pub fn slot_for_key(code: KeyCode, modifiers: KeyModifiers) -> Option<(usize, bool)> {
+    let KeyCode::Char(ch) = code else {
+        return None;
+    };
+    if !modifiers.contains(KeyModifiers::ALT)
+        || modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::SUPER)
+    {
+        return None;
+    }
+    let shifted = ['!', '@', '#', '$'];
+    if let Some(slot) = shifted.iter().position(|&symbol| symbol == ch) {
+        return Some((slot, true));
+    }
+    let slot = ch.to_digit(10)?.checked_sub(1)? as usize;
+    (slot < SLOTS).then_some((slot, modifiers.contains(KeyModifiers::SHIFT)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_pick_slots() {
+        let alt = KeyModifiers::ALT;
+        assert_eq!(slot_for_key(KeyCode::Char('1'), alt), Some((0, false)));
+        assert_eq!(slot_for_key(KeyCode::Char('4'), alt), Some((3, false)));
+        assert_eq!(slot_for_key(KeyCode::Char('#'), alt), Some((2, true)));
+        assert_eq!(
+            slot_for_key(KeyCode::Char('2'), alt | KeyModifiers::SHIFT),
+            Some((1, true))
+        );
+        assert_eq!(slot_for_key(KeyCode::Char('5'), alt), None);
+        assert_eq!(slot_for_key(KeyCode::Char('0'), alt), None);
+        assert_eq!(slot_for_key(KeyCode::Char('1'), KeyModifiers::NONE), None);
+    }
+}
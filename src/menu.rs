@@ -1,7 +1,21 @@
+use crate::completion::Completion;
+use crate::encoding::Transform;
+use crate::fold::Folding;
+use crate::generate::Generator;
 use crate::gitignore::GitIgnore;
-use crate::ui::{MenuAction, MenuComponent, MenuItem};
+use crate::jobs::JobId;
+use crate::line_ops::LineOp;
+use crate::project_search::{Filters, SearchMatch, SearchOptions, SharedResults};
+use crate::scroll::ScrollAcceleration;
+use crate::todos::Tags;
+use crate::ui::{MenuAction, MenuComponent, MenuItem, TextInput};
+use crate::vfs;
+use crate::view::{ViewOptions, ViewOverrides};
+use crate::walk::WalkGuard;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum MenuState {
@@ -9,6 +23,9 @@ pub enum MenuState {
     MainMenu(MenuComponent),
     CurrentTabMenu(MenuComponent),
     FilePicker(FilePickerState),
+    BufferSwitcher(BufferSwitcherState),
+    ProjectSearch(ProjectSearchState),
+    TodoList(TodoListState),
     TreeContextMenu(TreeContextMenuState),
     InputDialog(InputDialogState),
 }
@@ -16,12 +33,45 @@ pub enum MenuState {
 #[derive(Debug, Clone, PartialEq)]
 pub struct InputDialogState {
     pub prompt: String,
-    pub input: String,
+    pub input: TextInput,
     pub operation: String, // "new_file", "new_folder", "rename"
     pub target_path: PathBuf,
-    pub cursor_position: usize,
-    pub selection_start: Option<usize>,
     pub hovered_button: Option<usize>, // 0 = OK, 1 = Cancel
+    pub completion: Option<Completion>,
+}
+
+impl InputDialogState {
+    /// The directory Tab completes paths in, None when the input isn't a path
+    pub fn completion_base(&self) -> Option<&Path> {
+        match self.operation.as_str() {
+            "wrap_column" | "align_on" | "follow_session" | "coedit_session" => None,
+            "rename" => self.target_path.parent(),
+            _ => Some(&self.target_path),
+        }
+    }
+
+    /// Complete the typed path, or show the next (or previous) candidate
+    /// when the last Tab's is still in place
+    pub fn complete(&mut self, backwards: bool) {
+        match &mut self.completion {
+            Some(completion) if completion.cycles_from(self.input.text()) => {
+                if backwards {
+                    completion.previous();
+                } else {
+                    completion.next();
+                }
+            }
+            _ => {
+                let Some(base) = self.completion_base() else {
+                    return;
+                };
+                self.completion = Completion::new(base, self.input.text());
+            }
+        }
+        if let Some(completion) = &self.completion {
+            self.input.set_text(completion.current());
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -34,28 +84,32 @@ pub struct TreeContextMenuState {
 
 #[derive(Debug, Clone)]
 pub struct FilePickerState {
-    pub search_query: String,
+    pub search_input: TextInput,
     pub filtered_items: Vec<FileItem>,
     pub selected_index: usize,
     pub hovered_index: Option<usize>,
     pub current_dir: PathBuf,
     pub all_items: Vec<FileItem>,
     gitignore: GitIgnore,
-    last_scroll_time: Option<Instant>,
-    scroll_acceleration: usize,
+    wheel: ScrollAcceleration,
+    /// Previews read so far, filled in as entries are highlighted
+    previews: RefCell<HashMap<PathBuf, FilePreview>>,
+    /// Folders the shown entries were listed from, with their modification
+    /// times then; a change in one lists them again
+    watched: Vec<(PathBuf, Option<SystemTime>)>,
+    watch_checked: Instant,
 }
 
 impl PartialEq for FilePickerState {
     fn eq(&self, other: &Self) -> bool {
-        self.search_query == other.search_query
+        self.search_input == other.search_input
             && self.filtered_items == other.filtered_items
             && self.selected_index == other.selected_index
             && self.hovered_index == other.hovered_index
             && self.current_dir == other.current_dir
             && self.all_items == other.all_items
-            && self.scroll_acceleration == other.scroll_acceleration
-        // Note: Skipping last_scroll_time comparison as Instant doesn't impl PartialEq
-        // and gitignore comparison as it's internal state
+        // Note: Skipping the wheel, gitignore, preview and watch comparison
+        // as they're internal state
     }
 }
 
@@ -67,36 +121,111 @@ pub struct FileItem {
     pub relative_path: String,
 }
 
+/// Lines to read for a picker preview
+const PREVIEW_LINES: usize = 60;
+/// Bytes to read for a picker preview; enough for the lines shown
+const PREVIEW_BYTES: u64 = 16 * 1024;
+/// Previews kept before the cache starts over
+const PREVIEW_CACHE_SIZE: usize = 200;
+/// How often the picker checks whether the folders it lists changed
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+/// Folders watched at most; a deep search stops watching past this many
+const MAX_WATCHED: usize = 2000;
+
+fn dir_modified(dir: &Path) -> Option<SystemTime> {
+    vfs::metadata(dir).ok().and_then(|metadata| metadata.modified)
+}
+
+/// Read-only look at the highlighted picker entry
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilePreview {
+    Text(Vec<String>),
+    Directory(Vec<String>),
+    Binary(u64),
+    Unreadable(String),
+}
+
+impl FilePreview {
+    fn load(path: &Path) -> Self {
+        if vfs::metadata(path).is_ok_and(|m| m.is_dir) {
+            return match vfs::read_dir(path) {
+                Ok(entries) => {
+                    let mut names: Vec<String> = entries
+                        .iter()
+                        .map(|entry| {
+                            let name = entry.path.file_name().map_or_else(
+                                String::new,
+                                |name| name.to_string_lossy().into_owned(),
+                            );
+                            if entry.is_dir {
+                                format!("{}/", name)
+                            } else {
+                                name
+                            }
+                        })
+                        .collect();
+                    names.sort_by_key(|name| (!name.ends_with('/'), name.to_lowercase()));
+                    names.truncate(PREVIEW_LINES);
+                    FilePreview::Directory(names)
+                }
+                Err(e) => FilePreview::Unreadable(e.to_string()),
+            };
+        }
+
+        let bytes = match vfs::for_path(path).read_start(path, PREVIEW_BYTES) {
+            Ok(bytes) => bytes,
+            Err(e) => return FilePreview::Unreadable(e.to_string()),
+        };
+        if bytes.contains(&0) {
+            let size = vfs::metadata(path).map(|m| m.len).unwrap_or(0);
+            return FilePreview::Binary(size);
+        }
+
+        // The read can stop mid-character; the lossy conversion covers it
+        let text = String::from_utf8_lossy(&bytes);
+        FilePreview::Text(
+            text.lines()
+                .take(PREVIEW_LINES)
+                .map(|line| line.replace('\t', "    "))
+                .collect(),
+        )
+    }
+}
+
 impl FilePickerState {
     pub fn new() -> Self {
         let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
 
         // Create a temporary state to use the find_repo_root method
         let temp_state = Self {
-            search_query: String::new(),
+            search_input: TextInput::new(),
             filtered_items: Vec::new(),
             selected_index: 0,
             hovered_index: None,
             current_dir: current_dir.clone(),
             all_items: Vec::new(),
             gitignore: GitIgnore::new(current_dir.clone()), // Temporary
-            last_scroll_time: None,
-            scroll_acceleration: 1,
+            wheel: ScrollAcceleration::default(),
+            previews: RefCell::new(HashMap::new()),
+            watched: Vec::new(),
+            watch_checked: Instant::now(),
         };
 
         let repo_root = temp_state.find_repo_root(&current_dir);
         let gitignore = GitIgnore::new(repo_root);
 
         let mut state = Self {
-            search_query: String::new(),
+            search_input: TextInput::new(),
             filtered_items: Vec::new(),
             selected_index: 0,
             hovered_index: None,
             current_dir: current_dir.clone(),
             all_items: Vec::new(),
             gitignore,
-            last_scroll_time: None,
-            scroll_acceleration: 1,
+            wheel: ScrollAcceleration::default(),
+            previews: RefCell::new(HashMap::new()),
+            watched: Vec::new(),
+            watch_checked: Instant::now(),
         };
         state.load_current_directory();
         state
@@ -104,6 +233,8 @@ impl FilePickerState {
 
     pub fn load_current_directory(&mut self) {
         self.all_items.clear();
+        self.watched.clear();
+        self.watch(&self.current_dir.clone());
 
         // Add parent directory entry if not at root
         if self.current_dir.parent().is_some() {
@@ -116,12 +247,12 @@ impl FilePickerState {
         }
 
         // Load directory contents
-        if let Ok(entries) = std::fs::read_dir(&self.current_dir) {
+        if let Ok(entries) = vfs::read_dir(&self.current_dir) {
             let mut dirs = Vec::new();
             let mut files = Vec::new();
 
-            for entry in entries.filter_map(|e| e.ok()) {
-                let path = entry.path();
+            for entry in entries {
+                let path = entry.path;
                 let name = path
                     .file_name()
                     .and_then(|n| n.to_str())
@@ -138,7 +269,7 @@ impl FilePickerState {
                     continue;
                 }
 
-                let is_dir = path.is_dir();
+                let is_dir = entry.is_dir;
                 let item = FileItem {
                     path: path.clone(),
                     name,
@@ -166,26 +297,35 @@ impl FilePickerState {
         self.selected_index = 0;
     }
 
+    /// Line requested with a `name:123` query, 1-based
+    pub fn target_line(&self) -> Option<usize> {
+        split_line_suffix(self.search_input.text()).1
+    }
+
     pub fn update_filter(&mut self) {
-        if self.search_query.is_empty() {
+        let (name_query, _) = split_line_suffix(self.search_input.text());
+        self.watched.truncate(1);
+        if name_query.is_empty() {
             self.filtered_items = self.all_items.clone();
         } else {
             // Fuzzy search in current directory and subdirectories
-            let query = self.search_query.to_lowercase();
+            let folding = Folding::names();
+            let query = folding.str(name_query);
             self.filtered_items.clear();
 
             // Search in current directory
             for item in &self.all_items {
-                if item.name != ".." && fuzzy_match(&item.name.to_lowercase(), &query) {
+                if item.name != ".." && fuzzy_match(&folding.str(&item.name), &query) {
                     self.filtered_items.push(item.clone());
                 }
             }
 
             // Search in subdirectories (recursive) - start from depth 1 to avoid duplicating current dir
-            if let Ok(entries) = std::fs::read_dir(&self.current_dir) {
-                for entry in entries.filter_map(|e| e.ok()) {
-                    let path = entry.path();
-                    if path.is_dir() {
+            let mut guard = WalkGuard::new(&self.current_dir);
+            if let Ok(entries) = vfs::read_dir(&self.current_dir) {
+                for entry in entries {
+                    let path = entry.path;
+                    if entry.is_dir {
                         let name = path
                             .file_name()
                             .and_then(|n| n.to_str())
@@ -193,8 +333,9 @@ impl FilePickerState {
                             .to_string();
 
                         // Skip hidden directories
-                        if !name.starts_with('.') {
-                            self.search_recursive(&path, &query, 1, 3); // Start at depth 1
+                        // Start at depth 1
+                        if !name.starts_with('.') && guard.enter(&path) {
+                            self.search_recursive(&path, &query, 1, 3, &mut guard);
                         }
                     }
                 }
@@ -204,14 +345,22 @@ impl FilePickerState {
         self.hovered_index = None; // Clear hover when filtering
     }
 
-    fn search_recursive(&mut self, dir: &PathBuf, query: &str, depth: usize, max_depth: usize) {
+    fn search_recursive(
+        &mut self,
+        dir: &PathBuf,
+        query: &str,
+        depth: usize,
+        max_depth: usize,
+        guard: &mut WalkGuard,
+    ) {
         if depth >= max_depth {
             return;
         }
 
-        if let Ok(entries) = std::fs::read_dir(dir) {
-            for entry in entries.filter_map(|e| e.ok()) {
-                let path = entry.path();
+        self.watch(dir);
+        if let Ok(entries) = vfs::read_dir(dir) {
+            for entry in entries {
+                let path = entry.path;
                 let name = path
                     .file_name()
                     .and_then(|n| n.to_str())
@@ -230,28 +379,65 @@ impl FilePickerState {
                     .unwrap_or("")
                     .to_string();
 
-                if fuzzy_match(&name.to_lowercase(), query)
-                    || fuzzy_match(&relative.to_lowercase(), query)
+                let folding = Folding::names();
+                if fuzzy_match(&folding.str(&name), query)
+                    || fuzzy_match(&folding.str(&relative), query)
                 {
                     self.filtered_items.push(FileItem {
                         path: path.clone(),
                         name,
-                        is_dir: path.is_dir(),
+                        is_dir: entry.is_dir,
                         relative_path: relative,
                     });
                 }
 
-                // Recursively search directories
-                if path.is_dir() {
-                    self.search_recursive(&path, query, depth + 1, max_depth);
+                // Recursively search directories, following each link once
+                if entry.is_dir && guard.enter(&path) {
+                    self.search_recursive(&path, query, depth + 1, max_depth, guard);
                 }
             }
         }
     }
 
+    fn watch(&mut self, dir: &Path) {
+        if self.watched.len() < MAX_WATCHED {
+            self.watched.push((dir.to_path_buf(), dir_modified(dir)));
+        }
+    }
+
+    /// List the entries again when a file was created, removed or renamed
+    /// in a folder they came from, keeping the query and the selected entry.
+    /// Checks at most every `WATCH_INTERVAL`; returns whether it relisted.
+    pub fn refresh_if_changed(&mut self) -> bool {
+        if self.watch_checked.elapsed() < WATCH_INTERVAL {
+            return false;
+        }
+        self.watch_checked = Instant::now();
+        let changed = self
+            .watched
+            .iter()
+            .any(|(dir, modified)| dir_modified(dir) != *modified);
+        if !changed {
+            return false;
+        }
+
+        let selected = self.get_selected_item().map(|item| item.path.clone());
+        self.load_current_directory();
+        if !self.search_input.is_empty() {
+            self.update_filter();
+        }
+        self.previews.borrow_mut().clear();
+        if let Some(index) = selected
+            .and_then(|path| self.filtered_items.iter().position(|item| item.path == path))
+        {
+            self.selected_index = index;
+        }
+        true
+    }
+
     pub fn enter_directory(&mut self, dir: PathBuf) {
         self.current_dir = dir.clone();
-        self.search_query.clear();
+        self.search_input.clear();
         self.hovered_index = None; // Clear hover when changing directory
 
         // Update gitignore for the new directory (find repo root)
@@ -299,57 +485,40 @@ impl FilePickerState {
         self.filtered_items.get(self.selected_index)
     }
 
-    pub fn scroll_up(&mut self, base_amount: usize) {
-        // Update scroll acceleration
-        self.update_scroll_acceleration();
+    /// Entry drawn as highlighted: the hovered one, else the selected one
+    pub fn highlighted_item(&self) -> Option<&FileItem> {
+        self.filtered_items
+            .get(self.hovered_index.unwrap_or(self.selected_index))
+    }
 
-        // Calculate actual scroll amount with acceleration
-        let scroll_amount = base_amount.saturating_mul(self.scroll_acceleration);
+    /// Preview of an entry, read on first use and cached for the session
+    pub fn preview(&self, path: &Path) -> FilePreview {
+        let mut previews = self.previews.borrow_mut();
+        if let Some(preview) = previews.get(path) {
+            return preview.clone();
+        }
+        if previews.len() >= PREVIEW_CACHE_SIZE {
+            previews.clear();
+        }
+        let preview = FilePreview::load(path);
+        previews.insert(path.to_path_buf(), preview.clone());
+        preview
+    }
+
+    /// Move the selection by one wheel notch
+    pub fn scroll_up(&mut self) {
+        let scroll_amount = self.wheel.notch();
         self.selected_index = self.selected_index.saturating_sub(scroll_amount);
         self.hovered_index = None; // Clear hover when scrolling
     }
 
-    pub fn scroll_down(&mut self, base_amount: usize) {
-        // Update scroll acceleration
-        self.update_scroll_acceleration();
-
-        // Calculate actual scroll amount with acceleration
-        let scroll_amount = base_amount.saturating_mul(self.scroll_acceleration);
+    pub fn scroll_down(&mut self) {
+        let scroll_amount = self.wheel.notch();
         let max_index = self.filtered_items.len().saturating_sub(1);
         self.selected_index = (self.selected_index + scroll_amount).min(max_index);
         self.hovered_index = None; // Clear hover when scrolling
     }
 
-    fn update_scroll_acceleration(&mut self) {
-        let now = Instant::now();
-
-        if let Some(last_time) = self.last_scroll_time {
-            // If scrolling within 150ms, increase acceleration
-            if now.duration_since(last_time).as_millis() < 150 {
-                // More aggressive acceleration for file picker
-                let increment = if self.scroll_acceleration < 3 {
-                    1 // Start with +1 for initial acceleration
-                } else if self.scroll_acceleration < 8 {
-                    2 // Medium acceleration +2
-                } else if self.scroll_acceleration < 15 {
-                    3 // Fast acceleration +3
-                } else {
-                    4 // Very fast +4
-                };
-
-                self.scroll_acceleration = (self.scroll_acceleration + increment).min(20);
-            } else {
-                // Reset acceleration if too much time has passed
-                self.scroll_acceleration = 1;
-            }
-        } else {
-            // First scroll, start with base acceleration
-            self.scroll_acceleration = 1;
-        }
-
-        self.last_scroll_time = Some(now);
-    }
-
     // Add missing methods needed by file picker handler
     pub fn move_up(&mut self) {
         self.move_selection_up();
@@ -427,6 +596,281 @@ impl FilePickerState {
     }
 }
 
+/// An open tab as listed in the buffer switcher
+#[derive(Debug, Clone, PartialEq)]
+pub struct BufferEntry {
+    pub tab_index: usize,
+    pub name: String,
+    pub path: String, // Empty for unsaved buffers and terminals
+    pub modified: bool,
+}
+
+/// Quick switcher over the open tabs, filtered by name or path
+#[derive(Debug, Clone, PartialEq)]
+pub struct BufferSwitcherState {
+    pub search_input: TextInput,
+    pub entries: Vec<BufferEntry>,
+    pub filtered: Vec<usize>, // Indices into `entries`
+    pub selected_index: usize,
+    pub compare_with: Option<usize>, // Tab to diff the pick against, instead of switching to it
+}
+
+impl BufferSwitcherState {
+    pub fn new(entries: Vec<BufferEntry>, active_index: usize) -> Self {
+        let selected_index = entries
+            .iter()
+            .position(|entry| entry.tab_index == active_index)
+            .unwrap_or(0);
+        Self {
+            search_input: TextInput::new(),
+            filtered: (0..entries.len()).collect(),
+            entries,
+            selected_index,
+            compare_with: None,
+        }
+    }
+
+    pub fn update_filter(&mut self) {
+        let folding = Folding::names();
+        let query = folding.str(self.search_input.text());
+        self.filtered = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| {
+                fuzzy_match(&folding.str(&entry.name), &query)
+                    || fuzzy_match(&folding.str(&entry.path), &query)
+            })
+            .map(|(index, _)| index)
+            .collect();
+        self.selected_index = 0;
+    }
+
+    pub fn move_up(&mut self) {
+        if !self.filtered.is_empty() {
+            self.selected_index = self
+                .selected_index
+                .checked_sub(1)
+                .unwrap_or(self.filtered.len() - 1);
+        }
+    }
+
+    pub fn move_down(&mut self) {
+        if !self.filtered.is_empty() {
+            self.selected_index = (self.selected_index + 1) % self.filtered.len();
+        }
+    }
+
+    /// Tab index of the highlighted entry
+    pub fn selected_tab(&self) -> Option<usize> {
+        self.filtered
+            .get(self.selected_index)
+            .map(|&index| self.entries[index].tab_index)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchField {
+    Query,
+    Replace,
+    Files,
+}
+
+/// A row of the search results: a file, or one of its matching lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultRow {
+    File(usize), // Index of the file's first match
+    Match(usize),
+}
+
+impl ResultRow {
+    /// Each file with a match, followed by its matches
+    pub fn group(matches: &[SearchMatch]) -> Vec<ResultRow> {
+        let mut rows = Vec::new();
+        for (index, found) in matches.iter().enumerate() {
+            if index == 0 || matches[index - 1].path != found.path {
+                rows.push(ResultRow::File(index));
+            }
+            rows.push(ResultRow::Match(index));
+        }
+        rows
+    }
+}
+
+/// Project-wide search: a query, the globs limiting which files are searched,
+/// and the matches streamed in by the search job, grouped by file. With a
+/// replacement, the matches become a preview of the lines it would change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectSearchState {
+    pub query_input: TextInput,
+    pub replace_input: TextInput,
+    pub files_input: TextInput,
+    pub focus: SearchField,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub results: SharedResults,
+    pub job: Option<JobId>,
+    pub searched: Option<SearchOptions>, // What the shown results were found with
+    pub unchecked: HashSet<usize>,                // Matches left out of the replacement
+    pub selected_index: usize,                    // Index into `rows()`
+}
+
+impl ProjectSearchState {
+    pub fn new() -> Self {
+        Self {
+            query_input: TextInput::new(),
+            replace_input: TextInput::new(),
+            files_input: TextInput::new(),
+            focus: SearchField::Query,
+            case_sensitive: crate::config::get().search.case_sensitive,
+            whole_word: false,
+            results: SharedResults::default(),
+            job: None,
+            searched: None,
+            unchecked: HashSet::new(),
+            selected_index: 0,
+        }
+    }
+
+    pub fn focused_input(&mut self) -> &mut TextInput {
+        match self.focus {
+            SearchField::Query => &mut self.query_input,
+            SearchField::Replace => &mut self.replace_input,
+            SearchField::Files => &mut self.files_input,
+        }
+    }
+
+    pub fn next_field(&mut self) {
+        self.focus = match self.focus {
+            SearchField::Query => SearchField::Replace,
+            SearchField::Replace => SearchField::Files,
+            SearchField::Files => SearchField::Query,
+        };
+    }
+
+    pub fn previous_field(&mut self) {
+        self.focus = match self.focus {
+            SearchField::Query => SearchField::Files,
+            SearchField::Replace => SearchField::Query,
+            SearchField::Files => SearchField::Replace,
+        };
+    }
+
+    /// Whether the results are shown as a replacement preview
+    pub fn is_replacing(&self) -> bool {
+        !self.replace_input.is_empty() || self.focus == SearchField::Replace
+    }
+
+    /// The inputs as they would be searched now
+    pub fn options(&self) -> SearchOptions {
+        let config = &crate::config::get().search;
+        SearchOptions {
+            query: self.query_input.text().to_string(),
+            case_sensitive: self.case_sensitive,
+            whole_word: self.whole_word,
+            filters: Filters::parse(self.files_input.text()),
+            max_results: config.max_results,
+            ripgrep: config.ripgrep,
+        }
+    }
+
+    /// Whether the shown results are out of date with the inputs
+    pub fn is_stale(&self) -> bool {
+        self.searched.as_ref() != Some(&self.options())
+    }
+
+    /// Each file with a match, followed by its matches
+    pub fn rows(&self) -> Vec<ResultRow> {
+        ResultRow::group(&self.results.lock().matches)
+    }
+
+    /// Indices of the matches under a row: one line, or all of a file's
+    pub fn row_matches(matches: &[SearchMatch], row: ResultRow) -> Vec<usize> {
+        match row {
+            ResultRow::Match(index) => vec![index],
+            ResultRow::File(first) => (first..matches.len())
+                .take_while(|&index| matches[index].path == matches[first].path)
+                .collect(),
+        }
+    }
+
+    pub fn is_checked(&self, matches: &[SearchMatch], row: ResultRow) -> bool {
+        Self::row_matches(matches, row)
+            .iter()
+            .any(|index| !self.unchecked.contains(index))
+    }
+
+    /// Check or uncheck a row; a file's checkbox covers all its lines
+    pub fn toggle_row(&mut self, row: ResultRow) {
+        let results = self.results.clone();
+        let matches = &results.lock().matches;
+        let checked = self.is_checked(matches, row);
+        for index in Self::row_matches(matches, row) {
+            if checked {
+                self.unchecked.insert(index);
+            } else {
+                self.unchecked.remove(&index);
+            }
+        }
+    }
+
+    /// Move the selection by `delta` rows, stopping at either end
+    pub fn move_by(&mut self, delta: isize) {
+        let count = self.rows().len();
+        if count > 0 {
+            let index = self.selected_index as isize + delta;
+            self.selected_index = index.clamp(0, count as isize - 1) as usize;
+        }
+    }
+}
+
+/// The TODO comments of the workspace, grouped by file like search results
+#[derive(Debug, Clone, PartialEq)]
+pub struct TodoListState {
+    pub tags: Tags,
+    pub results: SharedResults,
+    pub job: Option<JobId>,
+    pub selected_index: usize, // Index into `rows()`
+}
+
+impl TodoListState {
+    pub fn new(tags: Tags) -> Self {
+        Self {
+            tags,
+            results: SharedResults::default(),
+            job: None,
+            selected_index: 0,
+        }
+    }
+
+    pub fn rows(&self) -> Vec<ResultRow> {
+        ResultRow::group(&self.results.lock().matches)
+    }
+
+    /// Move the selection by `delta` rows, stopping at either end
+    pub fn move_by(&mut self, delta: isize) {
+        let count = self.rows().len();
+        if count > 0 {
+            let index = self.selected_index as isize + delta;
+            self.selected_index = index.clamp(0, count as isize - 1) as usize;
+        }
+    }
+}
+
+/// Split a `name:123` query into the name part and the line number
+fn split_line_suffix(query: &str) -> (&str, Option<usize>) {
+    if let Some((name, line)) = query.rsplit_once(':') {
+        if let Ok(line) = line.parse::<usize>() {
+            return (name, Some(line));
+        }
+        // A bare trailing colon while the number is being typed
+        if line.is_empty() {
+            return (name, None);
+        }
+    }
+    (query, None)
+}
+
 fn fuzzy_match(text: &str, pattern: &str) -> bool {
     let mut pattern_chars = pattern.chars();
     let mut current_char = pattern_chars.next();
@@ -447,12 +891,16 @@ fn fuzzy_match(text: &str, pattern: &str) -> bool {
 #[derive(Debug, Clone, PartialEq)]
 pub struct MenuSystem {
     pub state: MenuState,
+    pub input_history: Vec<String>, // Shared by all input dialogs
+    pub project_search: Option<ProjectSearchState>, // Kept while the panel is closed
 }
 
 impl MenuSystem {
     pub fn new() -> Self {
         Self {
             state: MenuState::Closed,
+            input_history: Vec::new(),
+            project_search: None,
         }
     }
 
@@ -545,8 +993,61 @@ impl MenuSystem {
         self.state = MenuState::MainMenu(menu);
     }
 
-    pub fn open_current_tab_menu(&mut self) {
-        let items = vec![
+    /// The active tab's menu. Editor tabs list their display options, with
+    /// preview for markdown and a reset when they don't follow the defaults.
+    pub fn open_current_tab_menu(
+        &mut self,
+        view: Option<(ViewOptions, ViewOverrides)>,
+        preview: Option<bool>,
+    ) {
+        let mut items = Vec::new();
+        if let Some((view, overrides)) = view {
+            let toggle = |label: &str, action: &str, checked: bool| {
+                MenuItem::new(label, MenuAction::Custom(action.to_string())).with_checkbox(checked)
+            };
+            items.push(toggle("Word Wrap", "toggle_tab_word_wrap", view.word_wrap));
+            items.push(toggle("Line Numbers", "toggle_tab_line_numbers", view.line_numbers));
+            items.push(toggle(
+                "Show Whitespace",
+                "toggle_tab_whitespace",
+                view.show_whitespace,
+            ));
+            items.push(toggle(
+                "Split Long Lines",
+                "toggle_tab_split_long_lines",
+                view.split_long_lines,
+            ));
+            let column = match view.wrap_column {
+                0 => "edge".to_string(),
+                column => column.to_string(),
+            };
+            items.push(
+                MenuItem::new("Wrap Column...", MenuAction::Custom("wrap_column".to_string()))
+                    .with_shortcut(&column),
+            );
+            items.push(MenuItem::new(
+                "Sort / Filter Lines...",
+                MenuAction::Custom("lines_menu".to_string()),
+            ));
+            items.push(MenuItem::new(
+                "Transform Selection...",
+                MenuAction::Custom("transform_menu".to_string()),
+            ));
+            items.push(MenuItem::new(
+                "Insert...",
+                MenuAction::Custom("insert_menu".to_string()),
+            ));
+            if let Some(preview) = preview {
+                items.push(toggle("Preview", "toggle_tab_preview", preview));
+            }
+            if !overrides.is_empty() {
+                items.push(MenuItem::new(
+                    "Use Default View",
+                    MenuAction::Custom("reset_tab_view".to_string()),
+                ));
+            }
+        }
+        items.extend([
             MenuItem::new("Next Tab", MenuAction::Custom("next_tab".to_string()))
                 .with_shortcut("Ctrl+]"),
             MenuItem::new("Previous Tab", MenuAction::Custom("prev_tab".to_string()))
@@ -559,7 +1060,51 @@ impl MenuSystem {
             )
             .with_shortcut("Ctrl+Shift+W"),
             MenuItem::new("Cancel", MenuAction::Close),
-        ];
+        ]);
+        let menu = MenuComponent::new(items)
+            .with_width(30)
+            .with_colors(ratatui::style::Color::Cyan, ratatui::style::Color::Black);
+        self.state = MenuState::CurrentTabMenu(menu);
+    }
+
+    /// The line transformations, for the selected lines or the whole file,
+    /// and aligning them
+    pub fn open_lines_menu(&mut self) {
+        let mut items: Vec<MenuItem> = LineOp::ALL
+            .iter()
+            .map(|(_, label, action)| MenuItem::new(label, MenuAction::Custom(action.to_string())))
+            .collect();
+        items.push(MenuItem::new(
+            "Align on...",
+            MenuAction::Custom("align_on".to_string()),
+        ));
+        items.push(MenuItem::new("Cancel", MenuAction::Close));
+        let menu = MenuComponent::new(items)
+            .with_width(30)
+            .with_colors(ratatui::style::Color::Cyan, ratatui::style::Color::Black);
+        self.state = MenuState::CurrentTabMenu(menu);
+    }
+
+    /// Encodings and escapes to apply to the selection
+    pub fn open_transform_menu(&mut self) {
+        let mut items: Vec<MenuItem> = Transform::ALL
+            .iter()
+            .map(|(_, label, action)| MenuItem::new(label, MenuAction::Custom(action.to_string())))
+            .collect();
+        items.push(MenuItem::new("Cancel", MenuAction::Close));
+        let menu = MenuComponent::new(items)
+            .with_width(30)
+            .with_colors(ratatui::style::Color::Cyan, ratatui::style::Color::Black);
+        self.state = MenuState::CurrentTabMenu(menu);
+    }
+
+    /// Generated text to insert at the cursor
+    pub fn open_insert_menu(&mut self) {
+        let mut items: Vec<MenuItem> = Generator::ALL
+            .iter()
+            .map(|(_, label, action)| MenuItem::new(label, MenuAction::Custom(action.to_string())))
+            .collect();
+        items.push(MenuItem::new("Cancel", MenuAction::Close));
         let menu = MenuComponent::new(items)
             .with_width(30)
             .with_colors(ratatui::style::Color::Cyan, ratatui::style::Color::Black);
@@ -593,8 +1138,28 @@ impl MenuSystem {
         self.state = MenuState::FilePicker(picker_state);
     }
 
+    pub fn open_buffer_switcher(&mut self, entries: Vec<BufferEntry>, active_index: usize) {
+        self.state = MenuState::BufferSwitcher(BufferSwitcherState::new(entries, active_index));
+    }
+
+    /// The switcher as a picker of the tab to compare `tab_index` with
+    pub fn open_compare_picker(&mut self, entries: Vec<BufferEntry>, tab_index: usize) {
+        let mut state = BufferSwitcherState::new(entries, tab_index);
+        state.compare_with = Some(tab_index);
+        self.state = MenuState::BufferSwitcher(state);
+    }
+
+    /// Reopen the search panel as it was left
+    pub fn open_project_search(&mut self) {
+        let state = self.project_search.take().unwrap_or_else(ProjectSearchState::new);
+        self.state = MenuState::ProjectSearch(state);
+    }
+
     pub fn close(&mut self) {
-        self.state = MenuState::Closed;
+        let state = std::mem::replace(&mut self.state, MenuState::Closed);
+        if let MenuState::ProjectSearch(state) = state {
+            self.project_search = Some(state);
+        }
     }
 
     pub fn open_tree_context_menu(
@@ -622,6 +1187,15 @@ impl MenuSystem {
                 "Open",
                 MenuAction::Custom("open".to_string()),
             ));
+            // Created in the file's parent directory
+            items.push(MenuItem::new(
+                "New File (sibling)...",
+                MenuAction::Custom("new_file".to_string()),
+            ));
+            items.push(MenuItem::new(
+                "New Folder (sibling)...",
+                MenuAction::Custom("new_folder".to_string()),
+            ));
         }
 
         // File management operations
@@ -630,6 +1204,30 @@ impl MenuSystem {
             MenuAction::Custom("copy".to_string()),
         ));
         items.push(MenuItem::new("Cut", MenuAction::Custom("cut".to_string())));
+        if !is_directory {
+            items.push(MenuItem::new(
+                "Copy Contents",
+                MenuAction::Custom("copy_contents".to_string()),
+            ));
+        }
+        items.push(MenuItem::new(
+            "Copy Path",
+            MenuAction::Custom("copy_path".to_string()),
+        ));
+        if !is_directory {
+            items.push(MenuItem::new(
+                "Open in Default App",
+                MenuAction::Custom("open_external".to_string()),
+            ));
+        }
+        items.push(MenuItem::new(
+            "Open Containing Folder",
+            MenuAction::Custom("reveal".to_string()),
+        ));
+        items.push(MenuItem::new(
+            "Duplicate",
+            MenuAction::Custom("duplicate".to_string()),
+        ));
 
         // Only show Paste if there's something in clipboard
         if has_clipboard {
@@ -666,17 +1264,15 @@ impl MenuSystem {
         position: (u16, u16),
         has_clipboard: bool,
     ) {
-        let mut items = Vec::new();
-
-        // Only show New File and New Folder for empty area
-        items.push(MenuItem::new(
-            "New File",
-            MenuAction::Custom("new_file".to_string()),
-        ));
-        items.push(MenuItem::new(
-            "New Folder",
-            MenuAction::Custom("new_folder".to_string()),
-        ));
+        // New entries and whole-tree commands for the empty area
+        let mut items = vec![
+            MenuItem::new("New File", MenuAction::Custom("new_file".to_string())),
+            MenuItem::new("New Folder", MenuAction::Custom("new_folder".to_string())),
+            MenuItem::new("Collapse All", MenuAction::Custom("collapse_all".to_string()))
+                .with_shortcut("-"),
+            MenuItem::new("Expand All", MenuAction::Custom("expand_all".to_string()))
+                .with_shortcut("*"),
+        ];
 
         // Only show Paste if there's something in clipboard
         if has_clipboard {
@@ -699,14 +1295,15 @@ impl MenuSystem {
     }
 
     pub fn open_input_dialog(&mut self, prompt: String, operation: String, target_path: PathBuf) {
+        let mut input = TextInput::new();
+        input.set_history(self.input_history.clone());
         let input_state = InputDialogState {
             prompt,
-            input: String::new(),
+            input,
             operation,
             target_path,
-            cursor_position: 0,
-            selection_start: None,
             hovered_button: None,
+            completion: None,
         };
 
         self.state = MenuState::InputDialog(input_state);
@@ -794,15 +1391,22 @@ impl MenuSystem {
         }
     }
 
-    // Add missing method needed by keyboard handler
-    pub fn toggle_help(&mut self) {
-        // For now, this just opens/closes the help menu
-        // In a full implementation, this would show a help dialog
+    /// The F1 menu, or close whatever menu is open
+    pub fn toggle_help(&mut self, sharing: bool) {
         match self.state {
             MenuState::Closed => {
-                // Open a help menu
                 let items = vec![
-                    MenuItem::new("Keyboard Shortcuts", MenuAction::Custom("show_shortcuts".to_string())),
+                    MenuItem::new("Help", MenuAction::Custom("show_help".to_string())),
+                    MenuItem::new("Share Session", MenuAction::Custom("toggle_share".to_string()))
+                        .with_checkbox(sharing),
+                    MenuItem::new(
+                        "Follow Session...",
+                        MenuAction::Custom("follow_session".to_string()),
+                    ),
+                    MenuItem::new(
+                        "Co-edit Session...",
+                        MenuAction::Custom("coedit_session".to_string()),
+                    ),
                     MenuItem::new("About", MenuAction::Custom("show_about".to_string())),
                     MenuItem::new("Close", MenuAction::Close),
                 ];
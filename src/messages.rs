@@ -0,0 +1,134 @@
+//! Status messages: a queue of toasts shown one at a time, plus the full
+//! history displayed in the Output/Log panel.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Entries kept in the log before the oldest are dropped
+const MAX_HISTORY: usize = 500;
+
+/// How long a toast stays up at minimum before a queued one replaces it
+const MIN_TOAST_TIME: Duration = Duration::from_millis(1200);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warn",
+            Severity::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub text: String,
+    pub severity: Severity,
+    pub created: Instant,
+    duration: Duration,
+}
+
+#[derive(Debug)]
+pub struct MessageLog {
+    history: VecDeque<Message>,
+    pending: VecDeque<Message>,
+    current: Option<(Message, Instant)>, // Toast on screen and when it appeared
+    pub log_visible: bool,
+    pub log_scroll: usize, // Lines scrolled up from the newest entry
+}
+
+impl MessageLog {
+    pub fn new() -> Self {
+        Self {
+            history: VecDeque::new(),
+            pending: VecDeque::new(),
+            current: None,
+            log_visible: false,
+            log_scroll: 0,
+        }
+    }
+
+    pub fn push(&mut self, severity: Severity, text: String, duration: Duration) {
+        let message = Message {
+            text,
+            severity,
+            created: Instant::now(),
+            duration,
+        };
+
+        self.history.push_back(message.clone());
+        if self.history.len() > MAX_HISTORY {
+            self.history.pop_front();
+        }
+        self.pending.push_back(message);
+        self.advance();
+    }
+
+    /// Move to the next queued toast once the current one has expired, or
+    /// earlier if others are waiting and it has been readable for a moment.
+    pub fn advance(&mut self) {
+        let now = Instant::now();
+        if let Some((message, shown)) = &self.current {
+            let elapsed = now.duration_since(*shown);
+            let expired = elapsed >= message.duration;
+            let superseded = !self.pending.is_empty() && elapsed >= MIN_TOAST_TIME;
+            if !expired && !superseded {
+                return;
+            }
+            self.current = None;
+        }
+
+        if let Some(next) = self.pending.pop_front() {
+            self.current = Some((next, now));
+        }
+    }
+
+    /// The toast to draw right now, if any
+    pub fn current(&self) -> Option<&Message> {
+        self.current.as_ref().map(|(message, _)| message)
+    }
+
+    /// Number of toasts waiting behind the current one
+    pub fn queued(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn history(&self) -> impl DoubleEndedIterator<Item = &Message> + ExactSizeIterator {
+        self.history.iter()
+    }
+
+    pub fn toggle_log(&mut self) {
+        self.log_visible = !self.log_visible;
+        self.log_scroll = 0;
+    }
+
+    pub fn scroll_log(&mut self, delta: isize) {
+        let max = self.history.len().saturating_sub(1);
+        self.log_scroll = self.log_scroll.saturating_add_signed(delta).min(max);
+    }
+}
+
+impl Default for MessageLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Short "how long ago" text for the log panel
+pub fn format_age(created: Instant) -> String {
+    let secs = created.elapsed().as_secs();
+    if secs < 60 {
+        format!("{:>2}s", secs)
+    } else if secs < 3600 {
+        format!("{:>2}m", secs / 60)
+    } else {
+        format!("{:>2}h", secs / 3600)
+    }
+}
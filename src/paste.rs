@@ -0,0 +1,190 @@
+use crate::jobs::JobContext;
+use crate::tree_view::TreeView;
+use crate::vfs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// How to handle a paste whose target already exists
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictChoice {
+    Overwrite,
+    Rename,
+    Skip,
+}
+
+/// One source to bring into the target folder
+#[derive(Debug, Clone)]
+pub struct PasteItem {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    pub resolution: Option<ConflictChoice>,
+}
+
+impl PasteItem {
+    pub fn has_conflict(&self) -> bool {
+        vfs::exists(&self.target)
+    }
+}
+
+/// A paste waiting for its conflicts to be resolved before it is run
+#[derive(Debug, Clone)]
+pub struct PasteOperation {
+    pub items: Vec<PasteItem>,
+    pub is_cut: bool,
+    pub apply_all: Option<ConflictChoice>,
+}
+
+impl PasteOperation {
+    pub fn new(sources: Vec<PathBuf>, target_dir: &Path, is_cut: bool) -> Result<Self, String> {
+        let mut items = Vec::new();
+        for source in sources {
+            let name = source
+                .file_name()
+                .ok_or_else(|| format!("Cannot paste '{}'", source.display()))?;
+            let is_dir = vfs::entry_metadata(&source).is_ok_and(|m| m.is_dir);
+            if is_dir && target_dir.starts_with(&source) {
+                return Err(format!(
+                    "Cannot paste '{}' into itself",
+                    name.to_string_lossy()
+                ));
+            }
+            let target = target_dir.join(name);
+            if target != source && source.starts_with(&target) {
+                // Replacing the target would delete the source with it
+                return Err(format!(
+                    "Cannot paste '{}' over a folder containing it",
+                    name.to_string_lossy()
+                ));
+            }
+            let resolution = if target == source {
+                if is_cut {
+                    // Moving a file to where it already is leaves it alone
+                    continue;
+                }
+                // Pasting next to the original always makes a copy
+                Some(ConflictChoice::Rename)
+            } else {
+                None
+            };
+            items.push(PasteItem {
+                source,
+                target,
+                resolution,
+            });
+        }
+        Ok(Self {
+            items,
+            is_cut,
+            apply_all: None,
+        })
+    }
+
+    /// Index of the next conflicting item still waiting for a choice
+    pub fn next_unresolved(&self) -> Option<usize> {
+        self.items
+            .iter()
+            .position(|item| item.resolution.is_none() && item.has_conflict())
+    }
+
+    /// Copy or move every item, checking for cancellation between entries
+    pub fn run(self, job: &JobContext) -> Result<String, String> {
+        let mut done = 0;
+        let mut skipped = 0;
+        let mut pasted = 0;
+
+        for item in self.items {
+            let target = match item.resolution {
+                Some(ConflictChoice::Skip) => {
+                    skipped += 1;
+                    continue;
+                }
+                Some(ConflictChoice::Rename) => {
+                    let target_dir = item.target.parent().unwrap_or(Path::new("."));
+                    TreeView::unique_target(&item.source, target_dir)?
+                }
+                Some(ConflictChoice::Overwrite) | None => item.target,
+            };
+
+            let result = if self.is_cut {
+                move_path(&item.source, &target, job, &mut done)
+            } else {
+                copy_path(&item.source, &target, job, &mut done)
+            };
+            result.map_err(|e| format!("Paste failed: {}", e))?;
+            pasted += 1;
+        }
+
+        let verb = if self.is_cut { "Moved" } else { "Pasted" };
+        let mut message = format!("{} {} item(s)", verb, pasted);
+        if skipped > 0 {
+            message.push_str(&format!(", skipped {}", skipped));
+        }
+        Ok(message)
+    }
+}
+
+fn check_cancelled(job: &JobContext) -> io::Result<()> {
+    if job.is_cancelled() {
+        Err(io::Error::new(io::ErrorKind::Interrupted, "cancelled"))
+    } else {
+        Ok(())
+    }
+}
+
+fn count_entry(job: &JobContext, done: &mut usize) {
+    *done += 1;
+    if done.is_multiple_of(100) {
+        job.report(format!("{} items", done));
+    }
+}
+
+/// Copy a file or folder. Existing folders are merged into and existing
+/// files are replaced.
+fn copy_path(source: &Path, target: &Path, job: &JobContext, done: &mut usize) -> io::Result<()> {
+    check_cancelled(job)?;
+
+    let target_is_dir = vfs::entry_metadata(target).is_ok_and(|m| m.is_dir);
+    // Links are copied as links so a link back up the tree can't recurse
+    if crate::walk::is_symlink(source) {
+        if vfs::exists(target) {
+            vfs::remove(target)?;
+        }
+        crate::walk::copy_symlink(source, target)?;
+    } else if vfs::entry_metadata(source)?.is_dir {
+        if vfs::exists(target) && !target_is_dir {
+            vfs::remove(target)?;
+        }
+        vfs::create(target, true)?;
+        for entry in vfs::read_dir(source)? {
+            let name = entry.path.file_name().unwrap_or_default();
+            copy_path(&entry.path, &target.join(name), job, done)?;
+        }
+    } else {
+        if target_is_dir {
+            vfs::remove(target)?;
+        }
+        vfs::copy(source, target)?;
+    }
+
+    count_entry(job, done);
+    Ok(())
+}
+
+/// Move a file or folder, falling back to copy and delete across devices
+fn move_path(source: &Path, target: &Path, job: &JobContext, done: &mut usize) -> io::Result<()> {
+    check_cancelled(job)?;
+    // Before anything is replaced, as the source can't be removed afterwards
+    vfs::writable(source)?;
+
+    if vfs::exists(target) {
+        vfs::remove(target)?;
+    }
+
+    if vfs::rename(source, target).is_ok() {
+        count_entry(job, done);
+        return Ok(());
+    }
+
+    copy_path(source, target, job, done)?;
+    vfs::remove(source)
+}
@@ -0,0 +1,118 @@
+//! Timings for catching performance regressions, behind the `perf` feature
+//! (`cargo run --features perf`). Hot paths open a `span` that records how
+//! long it stays open, and F12 shows a HUD with the count, last, average
+//! and slowest time of each; the `frame` span is the frame time, and its
+//! count the number of redraws. Without the feature a span is empty and
+//! the HUD never shows.
+
+use ratatui::Frame;
+
+#[cfg(feature = "perf")]
+use std::collections::BTreeMap;
+#[cfg(feature = "perf")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "perf")]
+use std::sync::Mutex;
+#[cfg(feature = "perf")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "perf")]
+#[derive(Default, Clone, Copy)]
+struct Timing {
+    count: u32,
+    total: Duration,
+    last: Duration,
+    max: Duration,
+}
+
+#[cfg(feature = "perf")]
+static TIMINGS: Mutex<BTreeMap<&'static str, Timing>> = Mutex::new(BTreeMap::new());
+#[cfg(feature = "perf")]
+static HUD_VISIBLE: AtomicBool = AtomicBool::new(false);
+
+/// Records the time until it is dropped under its name
+#[must_use]
+pub struct Span {
+    #[cfg(feature = "perf")]
+    name: &'static str,
+    #[cfg(feature = "perf")]
+    start: Instant,
+}
+
+#[cfg(feature = "perf")]
+pub fn span(name: &'static str) -> Span {
+    Span {
+        name,
+        start: Instant::now(),
+    }
+}
+
+#[cfg(not(feature = "perf"))]
+pub fn span(_name: &'static str) -> Span {
+    Span {}
+}
+
+#[cfg(feature = "perf")]
+impl Drop for Span {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        if let Ok(mut timings) = TIMINGS.lock() {
+            let timing = timings.entry(self.name).or_default();
+            timing.count = timing.count.saturating_add(1);
+            timing.total += elapsed;
+            timing.last = elapsed;
+            timing.max = timing.max.max(elapsed);
+        }
+    }
+}
+
+#[cfg(feature = "perf")]
+pub fn toggle_hud() {
+    HUD_VISIBLE.fetch_xor(true, Ordering::Relaxed);
+}
+
+/// Draw the HUD over the top right corner when it is on
+#[cfg(feature = "perf")]
+pub fn draw_hud(frame: &mut Frame) {
+    use ratatui::layout::Rect;
+    use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+    if !HUD_VISIBLE.load(Ordering::Relaxed) {
+        return;
+    }
+    let Ok(timings) = TIMINGS.lock() else {
+        return;
+    };
+    let ms = |duration: Duration| format!("{:.2}", duration.as_secs_f64() * 1000.0);
+    let mut lines = vec![format!(
+        "{:<15}{:>7}{:>8}{:>8}{:>8}",
+        "span", "count", "last", "avg", "max"
+    )];
+    lines.extend(timings.iter().map(|(name, timing)| {
+        format!(
+            "{:<15}{:>7}{:>8}{:>8}{:>8}",
+            name,
+            timing.count,
+            ms(timing.last),
+            ms(timing.total / timing.count.max(1)),
+            ms(timing.max)
+        )
+    }));
+
+    let area = frame.area();
+    let width = 48.min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let hud = Rect::new(area.width - width, 0, width, height);
+    frame.render_widget(Clear, hud);
+    frame.render_widget(
+        Paragraph::new(lines.join("\n")).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(" perf, in ms "),
+        ),
+        hud,
+    );
+}
+
+#[cfg(not(feature = "perf"))]
+pub fn draw_hud(_frame: &mut Frame) {}
@@ -0,0 +1,82 @@
+//! Text piped into f1: `make 2>&1 | f1 -` opens standard input in a tab and
+//! keeps appending to it for as long as the other end writes, so f1 can
+//! stand in for `less` in a pipeline. Keys still come from the terminal,
+//! which crossterm opens as `/dev/tty` when standard input isn't one.
+
+use std::io::Read;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+
+/// Name of the tab the input goes to
+pub const TAB_NAME: &str = "stdin";
+
+pub struct Pipe {
+    chunks: Receiver<String>,
+}
+
+impl Pipe {
+    /// Read standard input on a thread until it ends
+    pub fn spawn() -> Pipe {
+        let (sender, chunks) = channel();
+        std::thread::spawn(move || {
+            let mut stdin = std::io::stdin().lock();
+            let mut buffer = [0; 64 * 1024];
+            let mut pending = Vec::new();
+            while let Ok(read @ 1..) = stdin.read(&mut buffer) {
+                pending.extend_from_slice(&buffer[..read]);
+                let text = take_text(&mut pending);
+                if !text.is_empty() && sender.send(text).is_err() {
+                    return;
+                }
+            }
+            if !pending.is_empty() {
+                let _ = sender.send(String::from_utf8_lossy(&pending).into_owned());
+            }
+        });
+        Pipe { chunks }
+    }
+
+    /// The text that arrived since the last call, and whether the input has
+    /// ended
+    pub fn read(&self) -> (String, bool) {
+        let mut text = String::new();
+        loop {
+            match self.chunks.try_recv() {
+                Ok(chunk) => text.push_str(&chunk),
+                Err(TryRecvError::Empty) => return (text, false),
+                Err(TryRecvError::Disconnected) => return (text, true),
+            }
+        }
+    }
+}
+
+/// Take the text from the start of `bytes`, leaving a character split
+/// between two reads for the next one. Bytes that aren't UTF-8 become
+/// replacement characters.
+fn take_text(bytes: &mut Vec<u8>) -> String {
+    let complete = match std::str::from_utf8(bytes) {
+        Ok(_) => bytes.len(),
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        Err(_) => bytes.len(),
+    };
+    let text = String::from_utf8_lossy(&bytes[..complete]).into_owned();
+    bytes.drain(..complete);
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_split_characters_for_the_next_read() {
+        let mut bytes = "aé".as_bytes()[..2].to_vec();
+        assert_eq!(take_text(&mut bytes), "a");
+        bytes.push("é".as_bytes()[1]);
+        bytes.extend_from_slice(b"b");
+        assert_eq!(take_text(&mut bytes), "éb");
+        assert!(bytes.is_empty());
+
+        let mut bytes = vec![b'x', 0xff, b'y'];
+        assert_eq!(take_text(&mut bytes), "x\u{fffd}y");
+    }
+}
@@ -0,0 +1,761 @@
+//! Project-wide text search. A background job walks the working directory
+//! the way ripgrep does, skipping hidden, gitignored and binary files, and
+//! streams matching lines into results the search panel draws from.
+//!
+//! Glob filters follow ripgrep's `--glob`: `*.rs` keeps only matching files,
+//! `!target/**` drops them, and an exclude always beats an include. A glob
+//! without a `/` matches the file name at any depth; one with a `/` matches
+//! the path from the root. `*` and `?` stay within a path component, `**`
+//! crosses them and `{a,b}` matches either alternative.
+
+use crate::fold::Folding;
+use crate::gitignore::GitIgnore;
+use crate::jobs::JobContext;
+use crate::walk::WalkGuard;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Files larger than this are skipped rather than read
+const MAX_FILE_SIZE: u64 = 8 * 1024 * 1024;
+/// Bytes checked for a NUL when deciding whether a file is binary
+const BINARY_CHECK_LEN: usize = 8 * 1024;
+/// Characters of a matching line kept for display
+pub const MAX_PREVIEW_LEN: usize = 300;
+
+/// Include and exclude globs, as typed into the panel's files field
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Filters {
+    globs: Vec<String>, // As typed, for ripgrep's `--glob`
+    include: Vec<Vec<char>>,
+    exclude: Vec<Vec<char>>,
+}
+
+impl Filters {
+    /// Parse globs separated by commas or spaces; `!` marks an exclude
+    pub fn parse(text: &str) -> Self {
+        let mut filters = Self::default();
+        for glob in split_globs(text) {
+            filters.globs.push(glob.to_string());
+            let (list, glob) = match glob.strip_prefix('!') {
+                Some(rest) => (&mut filters.exclude, rest),
+                None => (&mut filters.include, glob),
+            };
+            let glob = glob.trim_start_matches("./").trim_start_matches('/');
+            list.extend(expand_braces(glob).iter().map(|g| g.chars().collect()));
+        }
+        filters
+    }
+
+    /// Whether a file, by its path relative to the root, is searched
+    pub fn allows_file(&self, relative: &str) -> bool {
+        !self.exclude.iter().any(|glob| glob_matches(glob, relative))
+            && (self.include.is_empty()
+                || self.include.iter().any(|glob| glob_matches(glob, relative)))
+    }
+
+    /// Whether a directory is walked at all. Only excludes apply here:
+    /// `!target` and `!target/**` both prune the whole folder.
+    pub fn allows_dir(&self, relative: &str) -> bool {
+        let path: Vec<char> = relative.chars().collect();
+        !self.exclude.iter().any(|glob| {
+            glob_matches(glob, relative)
+                || glob.ends_with(&['/', '*', '*'])
+                    && wildcard_match(&glob[..glob.len() - 3], &path)
+        })
+    }
+}
+
+/// Globs separated by commas or spaces, leaving commas within `{}` alone
+fn split_globs(text: &str) -> Vec<&str> {
+    let mut globs = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (at, ch) in text.char_indices() {
+        match ch {
+            '{' => depth += 1,
+            '}' => depth = (depth - 1).max(0),
+            ',' | ' ' if depth == 0 => {
+                globs.push(&text[start..at]);
+                start = at + 1;
+            }
+            _ => {}
+        }
+    }
+    globs.push(&text[start..]);
+    globs.retain(|glob| !glob.is_empty());
+    globs
+}
+
+/// Every alternative of a glob's `{a,b}` groups
+fn expand_braces(glob: &str) -> Vec<String> {
+    let Some(open) = glob.find('{') else {
+        return vec![glob.to_string()];
+    };
+    let Some(close) = glob[open..].find('}').map(|end| open + end) else {
+        return vec![glob.to_string()];
+    };
+    let rest = expand_braces(&glob[close + 1..]);
+    glob[open + 1..close]
+        .split(',')
+        .flat_map(|choice| {
+            rest.iter()
+                .map(move |tail| format!("{}{}{}", &glob[..open], choice, tail))
+        })
+        .collect()
+}
+
+/// Whether a path relative to the root matches a glob
+fn glob_matches(glob: &[char], path: &str) -> bool {
+    let path: Vec<char> = path.chars().collect();
+    if glob.contains(&'/') {
+        return wildcard_match(glob, &path);
+    }
+    let name_start = path
+        .iter()
+        .rposition(|&ch| ch == '/')
+        .map_or(0, |at| at + 1);
+    wildcard_match(glob, &path[name_start..])
+}
+
+fn wildcard_match(glob: &[char], text: &[char]) -> bool {
+    match glob {
+        [] => text.is_empty(),
+        ['*', '*', '/', rest @ ..] => {
+            // Zero or more whole folders
+            wildcard_match(rest, text)
+                || text
+                    .iter()
+                    .enumerate()
+                    .any(|(i, &ch)| ch == '/' && wildcard_match(rest, &text[i + 1..]))
+        }
+        ['*', '*', rest @ ..] => (0..=text.len()).any(|i| wildcard_match(rest, &text[i..])),
+        ['*', rest @ ..] => {
+            let component = text.iter().position(|&ch| ch == '/').unwrap_or(text.len());
+            (0..=component).any(|i| wildcard_match(rest, &text[i..]))
+        }
+        ['?', rest @ ..] => {
+            matches!(text.first(), Some(&ch) if ch != '/') && wildcard_match(rest, &text[1..])
+        }
+        [ch, rest @ ..] => text.first() == Some(ch) && wildcard_match(rest, &text[1..]),
+    }
+}
+
+/// What to look for and where
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchOptions {
+    pub query: String,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub filters: Filters,
+    pub max_results: usize,
+    pub ripgrep: bool, // Use `rg` when it is installed
+}
+
+/// One occurrence of the query. A line with several has a match for each.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchMatch {
+    /// Relative to the search root
+    pub path: PathBuf,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Default)]
+pub struct SearchResults {
+    pub matches: Vec<SearchMatch>,
+    pub files_searched: usize,
+    pub done: bool,
+    /// Stopped at the results cap
+    pub truncated: bool,
+}
+
+/// Results the search job fills in while the panel reads them. Two handles
+/// are equal when they share the same results.
+#[derive(Debug, Clone, Default)]
+pub struct SharedResults(Arc<Mutex<SearchResults>>);
+
+impl SharedResults {
+    pub fn lock(&self) -> MutexGuard<'_, SearchResults> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl PartialEq for SharedResults {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+/// A query ready to match against lines
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matcher {
+    query: Vec<char>, // Folded like the lines it is matched against
+    folding: Folding,
+    whole_word: bool,
+}
+
+impl Matcher {
+    pub fn new(query: &str, case_sensitive: bool, whole_word: bool) -> Self {
+        let folding = Folding {
+            ignore_case: !case_sensitive,
+            ignore_accents: false,
+        };
+        Self::with_folding(query, folding, whole_word)
+    }
+
+    pub fn with_folding(query: &str, folding: Folding, whole_word: bool) -> Self {
+        Self {
+            query: query.chars().map(|ch| folding.char(ch)).collect(),
+            folding,
+            whole_word,
+        }
+    }
+
+    pub fn from_options(options: &SearchOptions) -> Self {
+        Self::new(&options.query, options.case_sensitive, options.whole_word)
+    }
+
+    /// Character ranges of the occurrences in `line`, left to right and not
+    /// overlapping
+    pub fn occurrences(&self, line: &str) -> Vec<(usize, usize)> {
+        let original: Vec<char> = line.chars().collect();
+        let line: Vec<char> = original.iter().map(|&ch| self.folding.char(ch)).collect();
+        let len = self.query.len();
+        let mut found = Vec::new();
+        if len == 0 {
+            return found;
+        }
+        let mut at = 0;
+        while at + len <= line.len() {
+            let starts_word = at == 0 || !is_word_char(original[at - 1]);
+            let ends_word = original.get(at + len).is_none_or(|&ch| !is_word_char(ch));
+            let bounded = !self.whole_word || (starts_word && ends_word);
+            if line[at..at + len] == self.query[..] && bounded {
+                found.push((at, at + len));
+                at += len;
+            } else {
+                at += 1;
+            }
+        }
+        found
+    }
+
+    /// The character ranges of the occurrences in `line` starting at
+    /// `columns`, or None if any of them is no longer there
+    pub fn occurrences_at(
+        &self,
+        line: &str,
+        columns: &BTreeSet<usize>,
+    ) -> Option<Vec<(usize, usize)>> {
+        let ranges: Vec<(usize, usize)> = self
+            .occurrences(line)
+            .into_iter()
+            .filter(|(start, _)| columns.contains(start))
+            .collect();
+        (ranges.len() == columns.len()).then_some(ranges)
+    }
+
+    /// `line` with the occurrences starting at `columns` replaced, or None if
+    /// any of them is no longer there
+    pub fn replace_at(
+        &self,
+        line: &str,
+        columns: &BTreeSet<usize>,
+        replacement: &str,
+    ) -> Option<String> {
+        let ranges = self.occurrences_at(line, columns)?;
+        let chars: Vec<char> = line.chars().collect();
+        let mut replaced = String::new();
+        let mut kept = 0;
+        for (start, end) in ranges {
+            replaced.extend(&chars[kept..start]);
+            replaced.push_str(replacement);
+            kept = end;
+        }
+        replaced.extend(&chars[kept..]);
+        Some(replaced)
+    }
+}
+
+/// Replace occurrences in a file's text, given as the columns they start at
+/// on each line, keeping the line endings. None if any of them is no longer
+/// there.
+pub fn replace_lines(
+    text: &str,
+    lines: &BTreeMap<usize, BTreeSet<usize>>,
+    matcher: &Matcher,
+    replacement: &str,
+) -> Option<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut replaced = 0;
+    for (index, line) in text.split_inclusive('\n').enumerate() {
+        let Some(columns) = lines.get(&index) else {
+            out.push_str(line);
+            continue;
+        };
+        let content = line.trim_end_matches(['\r', '\n']);
+        out.push_str(&matcher.replace_at(content, columns, replacement)?);
+        out.push_str(&line[content.len()..]);
+        replaced += 1;
+    }
+    (replaced == lines.len()).then_some(out)
+}
+
+/// Write a file by renaming a finished copy over it, so a failed write
+/// leaves the original untouched. A symlink is written through to its
+/// target, and a file with other hard links is written in place, since a
+/// rename would split it from them.
+pub fn write_atomically(path: &Path, content: &str) -> io::Result<()> {
+    let path = &fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    if has_other_links(path) {
+        return fs::write(path, content);
+    }
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{}.f1-replace", name));
+    let result = fs::write(&temp, content)
+        .and_then(|_| match fs::metadata(path) {
+            Ok(metadata) => fs::set_permissions(&temp, metadata.permissions()),
+            Err(_) => Ok(()),
+        })
+        .and_then(|_| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+#[cfg(unix)]
+fn has_other_links(path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).is_ok_and(|metadata| metadata.nlink() > 1)
+}
+
+#[cfg(not(unix))]
+fn has_other_links(_path: &Path) -> bool {
+    false
+}
+
+/// Contents of a file worth searching: not too big, and not binary
+fn read_text(path: &Path) -> Option<String> {
+    let mut file = fs::File::open(path).ok()?;
+    if file.metadata().ok()?.len() > MAX_FILE_SIZE {
+        return None;
+    }
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).ok()?;
+    if bytes[..bytes.len().min(BINARY_CHECK_LEN)].contains(&0) {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Search every file under `root`, adding matches to `results` as they are
+/// found. Stops at `max_results` or when the job is cancelled.
+pub fn run(
+    root: &Path,
+    options: &SearchOptions,
+    results: &SharedResults,
+    job: &JobContext,
+) -> Result<String, String> {
+    let _span = crate::perf::span("project search");
+    if options.ripgrep {
+        if let Some(result) = ripgrep::run(root, options, results, job) {
+            return result;
+        }
+    }
+    walk(root, options, results, job)
+}
+
+/// The built-in search, for when ripgrep is off or missing
+fn walk(
+    root: &Path,
+    options: &SearchOptions,
+    results: &SharedResults,
+    job: &JobContext,
+) -> Result<String, String> {
+    let matcher = Matcher::from_options(options);
+    for_each_file(root, &options.filters, job, |relative, content| {
+        let found: Vec<SearchMatch> = content
+            .lines()
+            .enumerate()
+            .flat_map(|(line, text)| {
+                let preview: String = text.chars().take(MAX_PREVIEW_LEN).collect();
+                matcher
+                    .occurrences(text)
+                    .into_iter()
+                    .map(move |(column, _)| SearchMatch {
+                        path: relative.to_path_buf(),
+                        line,
+                        column,
+                        text: preview.clone(),
+                    })
+            })
+            .collect();
+        add_matches(results, found, options.max_results, job)
+    });
+    Ok(finish(results))
+}
+
+/// Call `visit` with the path (relative to `root`) and text of each file a
+/// search looks at, in name order, until it returns false or the job is
+/// cancelled
+pub fn for_each_file(
+    root: &Path,
+    filters: &Filters,
+    job: &JobContext,
+    mut visit: impl FnMut(&Path, &str) -> bool,
+) {
+    let gitignore = GitIgnore::new(root.to_path_buf());
+    let mut guard = WalkGuard::new(root);
+    let mut pending = vec![root.to_path_buf()];
+
+    'walk: while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut entries: Vec<PathBuf> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+        // Files in name order; folders are pushed reversed so they pop in order
+        entries.sort();
+        let mut subdirs = Vec::new();
+
+        for path in entries {
+            if job.is_cancelled() {
+                break 'walk;
+            }
+            let hidden = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            if hidden || gitignore.is_ignored(&path) {
+                continue;
+            }
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            let relative_text = relative.to_string_lossy().replace('\\', "/");
+
+            if path.is_dir() {
+                if filters.allows_dir(&relative_text) && guard.enter(&path) {
+                    subdirs.push(path);
+                }
+                continue;
+            }
+            if !filters.allows_file(&relative_text) {
+                continue;
+            }
+            let Some(content) = read_text(&path) else {
+                continue;
+            };
+            if !visit(relative, &content) {
+                break 'walk;
+            }
+        }
+        pending.extend(subdirs.into_iter().rev());
+    }
+}
+
+/// Add a file's matches to the results, up to `max_results` in all. False
+/// once the cap is reached.
+pub fn add_matches(
+    results: &SharedResults,
+    found: Vec<SearchMatch>,
+    max_results: usize,
+    job: &JobContext,
+) -> bool {
+    let mut results = results.lock();
+    results.files_searched += 1;
+    let room = max_results.saturating_sub(results.matches.len());
+    if found.len() > room {
+        results.matches.extend(found.into_iter().take(room));
+        results.truncated = true;
+        return false;
+    }
+    results.matches.extend(found);
+    if results.files_searched.is_multiple_of(100) {
+        job.report(format!("{} files", results.files_searched));
+    }
+    true
+}
+
+/// Mark the search done and describe what it found
+fn finish(results: &SharedResults) -> String {
+    let mut results = results.lock();
+    results.done = true;
+    format!(
+        "{}{} matches in {} files",
+        if results.truncated { "First " } else { "" },
+        results.matches.len(),
+        results.files_searched
+    )
+}
+
+/// Searching with ripgrep, reading its `--json` output. It skips the same
+/// hidden, ignored and binary files and takes the same globs, only faster.
+mod ripgrep {
+    use super::*;
+    use serde::Deserialize;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Command, Stdio};
+
+    /// One line of `rg --json` output
+    #[derive(Deserialize)]
+    #[serde(tag = "type", content = "data", rename_all = "lowercase")]
+    pub(super) enum Message {
+        Begin {},
+        Match {
+            path: Text,
+            lines: Text,
+            line_number: Option<usize>,
+            submatches: Vec<Submatch>,
+        },
+        End {},
+        Context {},
+        Summary {
+            stats: Stats,
+        },
+    }
+
+    /// Paths and lines that aren't UTF-8 come as base64 `bytes` instead,
+    /// and are skipped
+    #[derive(Deserialize)]
+    pub(super) struct Text {
+        text: Option<String>,
+    }
+
+    #[derive(Deserialize)]
+    pub(super) struct Submatch {
+        start: usize,
+    }
+
+    #[derive(Deserialize)]
+    pub(super) struct Stats {
+        searches: usize,
+    }
+
+    /// The search results in a match message, one per occurrence
+    pub(super) fn to_matches(message: Message) -> Vec<SearchMatch> {
+        let Message::Match {
+            path,
+            lines,
+            line_number,
+            submatches,
+        } = message
+        else {
+            return Vec::new();
+        };
+        let (Some(path), Some(text), Some(line)) = (path.text, lines.text, line_number) else {
+            return Vec::new();
+        };
+        let path = PathBuf::from(path.strip_prefix("./").unwrap_or(&path));
+        let text = text.trim_end_matches(['\r', '\n']);
+        let preview: String = text.chars().take(MAX_PREVIEW_LEN).collect();
+        submatches
+            .iter()
+            .filter_map(|submatch| {
+                Some(SearchMatch {
+                    path: path.clone(),
+                    line: line.checked_sub(1)?,
+                    column: text.get(..submatch.start)?.chars().count(),
+                    text: preview.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Run `rg`, or None if it isn't installed
+    pub fn run(
+        root: &Path,
+        options: &SearchOptions,
+        results: &SharedResults,
+        job: &JobContext,
+    ) -> Option<Result<String, String>> {
+        let mut command = Command::new("rg");
+        command
+            .current_dir(root)
+            .args(["--json", "--fixed-strings", "--no-require-git"])
+            .arg(if options.case_sensitive {
+                "--case-sensitive"
+            } else {
+                "--ignore-case"
+            })
+            .arg(format!("--max-filesize={}", MAX_FILE_SIZE));
+        if options.whole_word {
+            command.arg("--word-regexp");
+        }
+        for glob in &options.filters.globs {
+            command.arg("--glob").arg(glob);
+        }
+        command
+            .arg("--")
+            .arg(&options.query)
+            .arg("./")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        let mut child = match command.spawn() {
+            Ok(child) => child,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+            Err(e) => return Some(Err(format!("Failed to run rg: {}", e))),
+        };
+        let stdout = child.stdout.take()?;
+        // Drained alongside stdout so a chatty rg can't block on a full pipe
+        let stderr = child.stderr.take()?;
+        let errors = std::thread::spawn(move || {
+            let mut text = String::new();
+            let _ = BufReader::new(stderr).read_to_string(&mut text);
+            text
+        });
+
+        let mut files = 0;
+        for line in BufReader::new(stdout).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if job.is_cancelled() {
+                break;
+            }
+            let Ok(message) = serde_json::from_str::<Message>(&line) else {
+                continue;
+            };
+            let mut results = results.lock();
+            match message {
+                Message::Begin {} => {
+                    files += 1;
+                    results.files_searched = files;
+                }
+                Message::Summary { stats } => results.files_searched = stats.searches,
+                message => {
+                    let found = to_matches(message);
+                    let room = options.max_results - results.matches.len();
+                    if found.len() > room {
+                        results.matches.extend(found.into_iter().take(room));
+                        results.truncated = true;
+                        break;
+                    }
+                    results.matches.extend(found);
+                }
+            }
+        }
+        let _ = child.kill();
+        let status = child.wait().ok()?;
+        let errors = errors.join().unwrap_or_default();
+
+        // Exit code 2 with nothing found is a real failure, like a bad glob
+        let failed = status.code() == Some(2) && results.lock().matches.is_empty();
+        if failed && !job.is_cancelled() {
+            let error = errors.lines().next().unwrap_or("rg failed").to_string();
+            results.lock().done = true;
+            return Some(Err(error));
+        }
+        Some(Ok(finish(results)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs_follow_ripgrep() {
+        let filters = Filters::parse("*.rs, !target/**");
+        assert!(filters.allows_file("src/main.rs"));
+        assert!(filters.allows_file("main.rs"));
+        assert!(!filters.allows_file("target/debug/build.rs"));
+        assert!(!filters.allows_file("README.md"));
+        assert!(!filters.allows_dir("target"));
+        assert!(filters.allows_dir("src"));
+        assert!(filters.allows_dir("src/target"));
+
+        let filters = Filters::parse("src/**/*.{rs,toml} !*_test.rs");
+        assert!(filters.allows_file("src/a/b/lib.rs"));
+        assert!(filters.allows_file("src/Cargo.toml"));
+        assert!(!filters.allows_file("src/lib_test.rs"));
+        assert!(!filters.allows_file("benches/lib.rs"));
+
+        // `*` and `?` stop at folder boundaries
+        let filters = Filters::parse("src/*.r?");
+        assert!(filters.allows_file("src/main.rs"));
+        assert!(!filters.allows_file("src/ui/mod.rs"));
+    }
+
+    #[test]
+    fn matching_options() {
+        let line = "a foo and Foo, foobar";
+        let ranges = |case, word| Matcher::new("Foo", case, word).occurrences(line);
+        assert_eq!(ranges(true, false), vec![(10, 13)]);
+        assert_eq!(ranges(false, false), vec![(2, 5), (10, 13), (15, 18)]);
+        assert_eq!(ranges(false, true), vec![(2, 5), (10, 13)]);
+        assert_eq!(Matcher::new("Foo", false, false).occurrences("fo"), vec![]);
+        let folding = Folding {
+            ignore_case: true,
+            ignore_accents: true,
+        };
+        let matcher = Matcher::with_folding("cafe", folding, true);
+        assert_eq!(matcher.occurrences("Café, cafés"), vec![(0, 4)]);
+    }
+
+    #[test]
+    fn reads_ripgrep_matches() {
+        let line = r#"{"type":"match","data":{"path":{"text":"./src/main.rs"},"lines":{"text":"  let é = foo();\n"},"line_number":3,"absolute_offset":10,"submatches":[{"match":{"text":"foo"},"start":11,"end":14}]}}"#;
+        let message: ripgrep::Message = serde_json::from_str(line).unwrap();
+        assert_eq!(
+            ripgrep::to_matches(message),
+            vec![SearchMatch {
+                path: PathBuf::from("src/main.rs"),
+                line: 2,
+                column: 10,
+                text: "  let é = foo();".to_string(),
+            }]
+        );
+        let summary = r#"{"type":"summary","data":{"elapsed_total":{"secs":0,"nanos":1},"stats":{"searches":4,"searches_with_match":1}}}"#;
+        assert!(serde_json::from_str::<ripgrep::Message>(summary).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn atomic_writes_keep_links() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join(format!("f1-replace-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("file.sh");
+        fs::write(&file, "old").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let link = dir.join("link.sh");
+        std::os::unix::fs::symlink(&file, &link).unwrap();
+        write_atomically(&link, "through the link").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&file).unwrap(), "through the link");
+        let mode = fs::metadata(&file).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+
+        let hard = dir.join("hard.sh");
+        fs::hard_link(&file, &hard).unwrap();
+        write_atomically(&hard, "both names").unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "both names");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn replacements_keep_line_endings() {
+        let matcher = Matcher::new("old", true, false);
+        let text = "old\r\nkeep old\r\nold old\n";
+        let lines = BTreeMap::from([(0, BTreeSet::from([0])), (2, BTreeSet::from([4]))]);
+        assert_eq!(
+            replace_lines(text, &lines, &matcher, "new").as_deref(),
+            Some("new\r\nkeep old\r\nold new\n")
+        );
+        // An occurrence that moved since the search spoils the whole file
+        let lines = BTreeMap::from([(0, BTreeSet::from([0])), (1, BTreeSet::from([0]))]);
+        assert_eq!(replace_lines(text, &lines, &matcher, "new"), None);
+    }
+}
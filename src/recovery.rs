@@ -0,0 +1,121 @@
+use crate::app::App;
+use crate::tab::Tab;
+use crossterm::{
+    cursor::{SetCursorStyle, Show},
+    event::{DisableFocusChange, DisableMouseCapture},
+    execute,
+    terminal::{disable_raw_mode, LeaveAlternateScreen},
+};
+use std::io::{self, stdout};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Put the terminal back into its normal state. Errors are ignored since this
+/// runs on the way out, possibly from inside a panic.
+pub fn restore_terminal() {
+    let _ = disable_raw_mode();
+    let _ = execute!(
+        stdout(),
+        LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableFocusChange,
+        SetCursorStyle::DefaultUserShape,
+        Show
+    );
+}
+
+/// Restore the terminal before the default panic message is printed, so the
+/// message and backtrace are readable instead of lost in the alternate screen.
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        default_hook(info);
+    }));
+}
+
+/// f1's state directory: `$XDG_STATE_HOME/f1`, falling back to
+/// `~/.local/state/f1`
+pub fn state_dir() -> PathBuf {
+    let state_home = std::env::var_os("XDG_STATE_HOME")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/state")))
+        .unwrap_or_else(std::env::temp_dir);
+    state_home.join("f1")
+}
+
+/// Key of the current workspace (the directory f1 was started in) in state
+/// files shared by every workspace
+pub fn workspace_key() -> String {
+    let cwd = std::env::current_dir().unwrap_or_default();
+    std::fs::canonicalize(&cwd)
+        .unwrap_or(cwd)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Where unsaved buffers are written when f1 exits abnormally
+pub fn recovery_dir() -> PathBuf {
+    state_dir().join("recovery")
+}
+
+/// Write every modified editor buffer to the recovery directory and return the
+/// files that were created.
+pub fn dump_unsaved_buffers(app: &App) -> io::Result<Vec<PathBuf>> {
+    let modified: Vec<&Tab> = app
+        .tab_manager
+        .tabs
+        .iter()
+        .filter(|tab| matches!(tab, Tab::Editor { modified: true, .. }))
+        .collect();
+    if modified.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let dir = recovery_dir();
+    std::fs::create_dir_all(&dir)?;
+
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let mut written = Vec::new();
+    for (index, tab) in modified.into_iter().enumerate() {
+        if let Tab::Editor { buffer, name, .. } = tab {
+            let file_name = format!("{}-{}-{}", stamp, index, sanitize(name));
+            let path = dir.join(file_name);
+            std::fs::write(&path, buffer.to_string())?;
+            written.push(path);
+        }
+    }
+    Ok(written)
+}
+
+/// Dump unsaved buffers and tell the user where they went. Called after the
+/// terminal has been restored so the report lands on the normal screen.
+pub fn recover(app: &App) {
+    match dump_unsaved_buffers(app) {
+        Ok(paths) if paths.is_empty() => {}
+        Ok(paths) => {
+            eprintln!("f1: unsaved buffers were written to:");
+            for path in paths {
+                eprintln!("  {}", path.display());
+            }
+        }
+        Err(e) => eprintln!("f1: failed to write recovery files: {}", e),
+    }
+}
+
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c == '/' || c == '\\' || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,325 @@
+//! Remote folders over SSH, for `f1 sftp://host/path`. The system `ssh` is
+//! run for every operation, the way project search runs `rg`: one master
+//! connection is opened before the terminal is taken over, so ssh can ask
+//! for a password, and each later command reuses it through a control
+//! socket. The remote side only needs a POSIX shell with `cat`, `ls` and
+//! `stat`.
+//!
+//! The remote is mounted as a `Vfs` at `sftp://host`, so remote paths show
+//! as URLs in tabs and the tree. Folders are always listed in background
+//! scans; the status bar shows whether the connection still answers.
+
+use crate::cli::SftpUrl;
+use crate::vfs::{self, DirEntry, Metadata, Vfs};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime};
+
+pub struct Sftp {
+    destination: String, // `user@host` or `host`, as ssh takes it
+    port: Option<u16>,
+    root: PathBuf,    // Where it is mounted
+    control: PathBuf, // Socket of the master connection
+    connected: AtomicBool,
+}
+
+static CONNECTION: OnceLock<Arc<Sftp>> = OnceLock::new();
+
+/// Open the master connection for `url` and mount the remote, returning the
+/// local path `url` names. Runs before the terminal is taken over, as ssh
+/// may prompt.
+pub fn connect(url: &SftpUrl) -> io::Result<PathBuf> {
+    let authority = match url.port {
+        Some(port) => format!("{}:{}", url.destination, port),
+        None => url.destination.clone(),
+    };
+    let sftp = Sftp {
+        destination: url.destination.clone(),
+        port: url.port,
+        root: PathBuf::from(format!("sftp://{}", authority)),
+        control: std::env::temp_dir().join(format!("f1-ssh-{}", std::process::id())),
+        connected: AtomicBool::new(false),
+    };
+
+    let status = sftp
+        .command()
+        .args(["-o", "ControlMaster=yes", "-o", "ControlPersist=yes"])
+        .args(["-f", "-N"])
+        .status()
+        .map_err(|e| match e.kind() {
+            ErrorKind::NotFound => io::Error::new(e.kind(), "ssh isn't installed"),
+            _ => e,
+        })?;
+    if !status.success() {
+        return Err(io::Error::new(
+            ErrorKind::NotConnected,
+            format!("couldn't connect to {}", sftp.destination),
+        ));
+    }
+    sftp.connected.store(true, Ordering::Relaxed);
+
+    // Paths are absolute from here on; `/~/...` is under the login folder
+    let home = String::from_utf8_lossy(&sftp.run("pwd", None)?)
+        .trim_end()
+        .to_string();
+    let path = match url.path.strip_prefix("/~") {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => format!("{}{}", home, rest),
+        _ if url.path.is_empty() => home,
+        _ => url.path.clone(),
+    };
+
+    let local = sftp.local_path(&path);
+    let sftp = Arc::new(sftp);
+    vfs::mount(sftp.root.clone(), sftp.clone());
+    let _ = CONNECTION.set(sftp);
+    Ok(local)
+}
+
+/// Close the master connection, if there is one
+pub fn disconnect() {
+    if let Some(sftp) = CONNECTION.get() {
+        let _ = sftp
+            .command()
+            .args(["-O", "exit"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+}
+
+/// The host connected to, and whether its last command got through
+pub fn status() -> Option<(String, bool)> {
+    let sftp = CONNECTION.get()?;
+    Some((
+        sftp.destination.clone(),
+        sftp.connected.load(Ordering::Relaxed),
+    ))
+}
+
+/// Whether `path` is on the remote host
+pub fn is_remote(path: &Path) -> bool {
+    CONNECTION
+        .get()
+        .is_some_and(|sftp| path.starts_with(&sftp.root))
+}
+
+/// `text` quoted for a POSIX shell
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+impl Sftp {
+    /// `ssh` with the options every command shares, up to the destination
+    fn command(&self) -> Command {
+        let mut command = Command::new("ssh");
+        command
+            .arg("-o")
+            .arg(format!("ControlPath={}", self.control.display()));
+        if let Some(port) = self.port {
+            command.arg("-p").arg(port.to_string());
+        }
+        command.arg(&self.destination);
+        command
+    }
+
+    /// The absolute remote path of a path under the mount
+    fn remote_path(&self, path: &Path) -> io::Result<String> {
+        let relative = path
+            .strip_prefix(&self.root)
+            .map_err(|_| io::Error::new(ErrorKind::InvalidInput, "not on this host"))?;
+        let mut remote = String::new();
+        for component in relative.components() {
+            match component {
+                Component::Normal(part) => {
+                    remote.push('/');
+                    remote.push_str(&part.to_string_lossy());
+                }
+                Component::CurDir => {}
+                _ => return Err(io::Error::new(ErrorKind::InvalidInput, "bad remote path")),
+            }
+        }
+        if remote.is_empty() {
+            remote.push('/');
+        }
+        Ok(remote)
+    }
+
+    fn local_path(&self, remote: &str) -> PathBuf {
+        self.root.join(remote.trim_start_matches('/'))
+    }
+
+    fn quoted(&self, path: &Path) -> io::Result<String> {
+        Ok(quote(&self.remote_path(path)?))
+    }
+
+    /// Run `script` in the remote shell, returning what it printed. Exit
+    /// status 255 is ssh's own failure, and marks the connection as lost.
+    fn run(&self, script: &str, input: Option<&[u8]>) -> io::Result<Vec<u8>> {
+        let mut child = self
+            .command()
+            .args(["-o", "BatchMode=yes", "--", script])
+            .stdin(if input.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            stdin.write_all(input)?;
+        }
+        let output = child.wait_with_output()?;
+
+        let lost = output.status.code() == Some(255);
+        self.connected.store(!lost, Ordering::Relaxed);
+        if output.status.success() {
+            return Ok(output.stdout);
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.lines().last().unwrap_or("").trim().to_string();
+        let kind = if lost {
+            ErrorKind::NotConnected
+        } else if message.contains("No such file") || message.contains("can't cd") {
+            ErrorKind::NotFound
+        } else if message.contains("Permission denied") {
+            ErrorKind::PermissionDenied
+        } else if message.contains("exist") {
+            ErrorKind::AlreadyExists
+        } else {
+            ErrorKind::Other
+        };
+        Err(io::Error::new(kind, message))
+    }
+}
+
+impl Vfs for Sftp {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.run(&format!("cat {}", self.quoted(path)?), None)
+    }
+
+    fn read_start(&self, path: &Path, limit: u64) -> io::Result<Vec<u8>> {
+        let script = format!("head -c {} {}", limit, self.quoted(path)?);
+        self.run(&script, None)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let script = format!("cat > {}", self.quoted(path)?);
+        self.run(&script, Some(contents)).map(|_| ())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        // GNU and busybox `stat` first, then the BSD one
+        let path = self.quoted(path)?;
+        let script = format!(
+            "stat -L -c '%F/%s/%Y' {0} 2>/dev/null || stat -L -f '%HT/%z/%m' {0}",
+            path
+        );
+        let output = String::from_utf8_lossy(&self.run(&script, None)?).into_owned();
+        let mut fields = output.trim_end().rsplitn(3, '/');
+        let (Some(modified), Some(len), Some(kind)) = (fields.next(), fields.next(), fields.next())
+        else {
+            return Err(io::Error::new(
+                ErrorKind::InvalidData,
+                "unexpected stat output",
+            ));
+        };
+        Ok(Metadata {
+            is_dir: kind.eq_ignore_ascii_case("directory"),
+            len: len.parse().unwrap_or(0),
+            modified: modified
+                .parse()
+                .ok()
+                .map(|seconds| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)),
+        })
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        // `-p` marks folders, following links with `-L`; names are one a line
+        let script = format!(
+            "cd {} && {{ ls -1ApL 2>/dev/null || true; }}",
+            self.quoted(path)?
+        );
+        let output = self.run(&script, None)?;
+        let mut entries = Vec::new();
+        for name in String::from_utf8_lossy(&output).lines() {
+            let (name, is_dir) = match name.strip_suffix('/') {
+                Some(name) => (name, true),
+                None => (name, false),
+            };
+            if name.is_empty() {
+                continue;
+            }
+            entries.push(DirEntry {
+                path: path.join(name),
+                is_dir,
+                is_symlink: false,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// Every remote folder counts as full, so the tree lists it in a
+    /// background scan instead of waiting on the network
+    fn count_entries(&self, _path: &Path, limit: usize) -> io::Result<usize> {
+        Ok(limit)
+    }
+
+    fn create(&self, path: &Path, is_dir: bool) -> io::Result<()> {
+        let script = if is_dir {
+            format!("mkdir -p {}", self.quoted(path)?)
+        } else {
+            let parent = path.parent().unwrap_or(path);
+            // `set -C` makes the redirection fail on an existing file
+            format!(
+                "mkdir -p {} && set -C && : > {}",
+                self.quoted(parent)?,
+                self.quoted(path)?
+            )
+        };
+        self.run(&script, None).map(|_| ())
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        let script = format!("rm -r {}", self.quoted(path)?);
+        self.run(&script, None).map(|_| ())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let script = format!("mv -f {} {}", self.quoted(from)?, self.quoted(to)?);
+        self.run(&script, None).map(|_| ())
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let script = format!("cp {} {}", self.quoted(from)?, self.quoted(to)?);
+        self.run(&script, None).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_paths_and_quotes_them() {
+        let sftp = Sftp {
+            destination: "me@example.com".to_string(),
+            port: Some(2222),
+            root: PathBuf::from("sftp://me@example.com:2222"),
+            control: PathBuf::from("/tmp/f1-ssh-test"),
+            connected: AtomicBool::new(true),
+        };
+        let local = sftp.local_path("/home/me/it's.txt");
+        assert_eq!(
+            local,
+            Path::new("sftp://me@example.com:2222/home/me/it's.txt")
+        );
+        assert_eq!(sftp.remote_path(&local).unwrap(), "/home/me/it's.txt");
+        assert_eq!(sftp.remote_path(&sftp.root).unwrap(), "/");
+        assert!(sftp.remote_path(Path::new("/home/me")).is_err());
+        assert_eq!(quote("it's"), "'it'\\''s'");
+    }
+}
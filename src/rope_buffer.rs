@@ -1,32 +1,183 @@
+use crate::syntax_tree::{self, EditStart, Parsed, Tree};
 use ropey::Rope;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
 use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Edits whose changed lines are remembered, for `changed_lines_since`
+const CHANGE_LOG_LEN: usize = 64;
+
+// Shared by every buffer, so a buffer put back by undo or reload never
+// has the revision of another state
+static REVISIONS: AtomicU64 = AtomicU64::new(0);
+
+fn next_revision() -> u64 {
+    REVISIONS.fetch_add(1, Ordering::Relaxed) + 1
+}
 
 #[derive(Clone)]
 pub struct RopeBuffer {
     rope: Rope,
+    // Counted on first use, then kept current by each edit
+    words: Cell<Option<usize>>,
+    // Parsed on first use; edits are reported to it for the next re-parse
+    syntax: RefCell<Option<Parsed>>,
+    // Changes with every edit
+    revision: u64,
+    // The revision before each recent edit, with the lines it changed
+    changes: VecDeque<(u64, Range<usize>)>,
+    // Set when the text is made from scratch and kept by clones, which share
+    // the rope's nodes with it
+    origin: u64,
+    // Characters removed and inserted since the origin
+    edited: usize,
 }
 
 impl RopeBuffer {
     pub fn new() -> Self {
-        Self { rope: Rope::new() }
+        Self::from_str("")
     }
 
     pub fn from_str(text: &str) -> Self {
         Self {
             rope: Rope::from_str(text),
+            words: Cell::new(None),
+            syntax: RefCell::new(None),
+            revision: next_revision(),
+            changes: VecDeque::new(),
+            origin: next_revision(),
+            edited: 0,
         }
     }
 
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Whether `other` is a state of this same text: a clone of it, edited
+    /// or not, rather than a buffer made from other text
+    pub fn same_document(&self, other: &RopeBuffer) -> bool {
+        self.origin == other.origin
+    }
+
+    /// Rough bytes of this state that `later` doesn't share with it. A clone
+    /// edited into `later` only copies the text around its edits; a buffer
+    /// made from other text shares nothing.
+    pub fn unshared_with(&self, later: &RopeBuffer) -> usize {
+        if self.origin == later.origin && later.edited >= self.edited {
+            later.edited - self.edited
+        } else {
+            self.rope.len_bytes()
+        }
+    }
+
+    /// Lines that may differ from how they were at `revision`, or None when
+    /// that is too long ago to tell or wasn't a state of this buffer. Lines
+    /// that moved up or down count as changed.
+    pub fn changed_lines_since(&self, revision: u64) -> Option<Range<usize>> {
+        if revision == self.revision {
+            return Some(0..0);
+        }
+        let first = self.changes.iter().position(|(before, _)| *before == revision)?;
+        self.changes
+            .range(first..)
+            .map(|(_, lines)| lines.clone())
+            .reduce(|all, lines| all.start.min(lines.start)..all.end.max(lines.end))
+    }
+
     pub fn insert(&mut self, char_idx: usize, text: &str) {
-        self.rope.insert(char_idx, text);
+        let inserted = text.chars().count();
+        self.edit(char_idx..char_idx, inserted, |rope| rope.insert(char_idx, text));
     }
 
     pub fn insert_char(&mut self, char_idx: usize, ch: char) {
-        self.rope.insert_char(char_idx, ch);
+        self.edit(char_idx..char_idx, 1, |rope| rope.insert_char(char_idx, ch));
     }
 
     pub fn remove(&mut self, range: Range<usize>) {
-        self.rope.remove(range);
+        let removed = range.clone();
+        self.edit(removed, 0, |rope| rope.remove(range));
+    }
+
+    /// Put `text` in place of the characters in `range` as one edit, which
+    /// touches only those characters instead of rebuilding their line
+    pub fn replace(&mut self, range: Range<usize>, text: &str) {
+        let inserted = text.chars().count();
+        let start = range.start;
+        self.edit(range.clone(), inserted, |rope| {
+            rope.remove(range);
+            rope.insert(start, text);
+        });
+    }
+
+    /// Words, as runs of non-whitespace. Only the first call reads the whole
+    /// buffer; edits after it recount just the lines they touch.
+    pub fn word_count(&self) -> usize {
+        if let Some(words) = self.words.get() {
+            return words;
+        }
+        let words = count_words(self.rope.chars());
+        self.words.set(Some(words));
+        words
+    }
+
+    /// The syntax tree in a tree-sitter grammar, when it is built in
+    pub fn syntax_tree(&self, grammar: &str) -> Option<Tree> {
+        syntax_tree::update(&mut self.syntax.borrow_mut(), &self.rope, grammar)
+    }
+
+    /// Apply an edit that turns `range` into `inserted` characters, moving the
+    /// word count by the difference over the lines around it (words never
+    /// span a line break, so those lines are all that can change) and telling
+    /// the syntax tree where the text moved
+    fn edit(&mut self, range: Range<usize>, inserted: usize, apply: impl FnOnce(&mut Rope)) {
+        let _span = crate::perf::span("rope edit");
+        let first_line = self.rope.char_to_line(range.start);
+        let words_before = self.words.get().map(|words| {
+            let before = self.words_in_lines(first_line, self.rope.char_to_line(range.end));
+            (words, before)
+        });
+        let edit_start = self
+            .syntax
+            .get_mut()
+            .is_some()
+            .then(|| EditStart::new(&self.rope, range.clone()));
+
+        let lines_before = self.rope.len_lines();
+        apply(&mut self.rope);
+        self.edited += range.len() + inserted;
+
+        let new_end = range.start + inserted;
+        // Lines after a break that came or went all move
+        let changed_end = if self.rope.len_lines() == lines_before {
+            self.rope.char_to_line(new_end) + 1
+        } else {
+            usize::MAX
+        };
+        if self.changes.len() == CHANGE_LOG_LEN {
+            self.changes.pop_front();
+        }
+        self.changes.push_back((self.revision, first_line..changed_end));
+        self.revision = next_revision();
+
+        if let (Some(parsed), Some(edit_start)) = (self.syntax.get_mut(), edit_start) {
+            parsed.edit(edit_start, &self.rope, new_end);
+        }
+        if let Some((words, before)) = words_before {
+            let after = self.words_in_lines(first_line, self.rope.char_to_line(new_end));
+            self.words.set(Some(words + after - before));
+        }
+    }
+
+    fn words_in_lines(&self, first: usize, last: usize) -> usize {
+        let start = self.rope.line_to_char(first);
+        let end = if last + 1 < self.rope.len_lines() {
+            self.rope.line_to_char(last + 1)
+        } else {
+            self.rope.len_chars()
+        };
+        count_words(self.rope.slice(start..end).chars())
     }
 
     pub fn len_chars(&self) -> usize {
@@ -45,6 +196,29 @@ impl RopeBuffer {
         self.rope.line_to_char(line_idx)
     }
 
+    pub fn char_to_line(&self, char_idx: usize) -> usize {
+        self.rope.char_to_line(char_idx)
+    }
+
+    #[cfg(feature = "tree-sitter")]
+    pub fn char_to_byte(&self, char_idx: usize) -> usize {
+        self.rope.char_to_byte(char_idx)
+    }
+
+    #[cfg(feature = "tree-sitter")]
+    pub fn byte_to_char(&self, byte_idx: usize) -> usize {
+        self.rope.byte_to_char(byte_idx)
+    }
+
+    pub fn char(&self, char_idx: usize) -> char {
+        self.rope.char(char_idx)
+    }
+
+    /// Characters from `char_idx` on; `.reversed()` walks backwards from it
+    pub fn chars_at(&self, char_idx: usize) -> ropey::iter::Chars<'_> {
+        self.rope.chars_at(char_idx)
+    }
+
     #[allow(clippy::inherent_to_string)]
     pub fn to_string(&self) -> String {
         self.rope.to_string()
@@ -52,36 +226,31 @@ impl RopeBuffer {
 
     pub fn get_line_text(&self, line_idx: usize) -> String {
         if line_idx < self.len_lines() {
-            let line = self.line(line_idx);
-            line.to_string().trim_end_matches('\n').to_string()
+            self.slice(self.line_range(line_idx)).to_string()
         } else {
             String::new()
         }
     }
 
+    /// The characters of a line, leaving out its line break
+    pub fn line_range(&self, line_idx: usize) -> Range<usize> {
+        let start = self.rope.line_to_char(line_idx);
+        let line = self.rope.line(line_idx);
+        let mut len = line.len_chars();
+        if len > 0 && line.char(len - 1) == '\n' {
+            len -= 1;
+        }
+        start..start + len
+    }
+
     pub fn slice(&self, range: Range<usize>) -> ropey::RopeSlice<'_> {
         self.rope.slice(range)
     }
 
     pub fn replace_line(&mut self, line_idx: usize, new_text: &str) {
-        if line_idx >= self.len_lines() {
-            return;
-        }
-
-        let line_start = self.line_to_char(line_idx);
-        let line_end = if line_idx + 1 < self.len_lines() {
-            self.line_to_char(line_idx + 1) - 1 // Exclude the newline
-        } else {
-            self.len_chars()
-        };
-
-        // Remove the old line content
-        if line_end > line_start {
-            self.rope.remove(line_start..line_end);
+        if line_idx < self.len_lines() {
+            self.replace(self.line_range(line_idx), new_text);
         }
-
-        // Insert the new line content
-        self.rope.insert(line_start, new_text);
     }
 
     pub fn delete_char(&mut self, char_idx: usize) {
@@ -98,3 +267,52 @@ impl RopeBuffer {
         self.get_line_text(line_idx)
     }
 }
+
+fn count_words(chars: impl Iterator<Item = char>) -> usize {
+    let mut words = 0;
+    let mut in_word = false;
+    for ch in chars {
+        if ch.is_whitespace() {
+            in_word = false;
+        } else if !in_word {
+            in_word = true;
+            words += 1;
+        }
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_count_follows_edits() {
+        let mut buffer = RopeBuffer::from_str("one two\nthree");
+        assert_eq!(buffer.word_count(), 3);
+        buffer.insert(3, "\nfour five");
+        buffer.remove(0..1);
+        buffer.insert_char(2, ' ');
+        buffer.replace_line(2, "six  seven eight");
+        let text = buffer.to_string();
+        assert_eq!(buffer.word_count(), text.split_whitespace().count());
+        // Joining two lines can merge their words
+        let end_of_first = buffer.line_to_char(1) - 1;
+        buffer.remove(end_of_first..end_of_first + 1);
+        assert_eq!(buffer.word_count(), buffer.to_string().split_whitespace().count());
+    }
+
+    #[test]
+    fn replace_edits_only_the_range() {
+        let mut buffer = RopeBuffer::from_str("héllo wörld\nnext\n");
+        assert_eq!(buffer.word_count(), 3);
+        buffer.replace(6..11, "there, world");
+        assert_eq!(buffer.to_string(), "héllo there, world\nnext\n");
+        assert_eq!(buffer.word_count(), 4);
+        assert_eq!(buffer.line_range(1), 19..23);
+        assert_eq!(buffer.line_range(2), 24..24);
+        buffer.replace_line(1, "last");
+        buffer.replace(0..0, "> ");
+        assert_eq!(buffer.to_string(), "> héllo there, world\nlast\n");
+    }
+}
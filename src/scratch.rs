@@ -0,0 +1,113 @@
+//! Scratch buffers: notes that need no file name. Each one is backed by a
+//! file in `<state dir>/scratch`, saved automatically while it is edited and
+//! reopened on the next start. When that folder can't be made, an in-memory
+//! `Vfs` is mounted there instead and the buffers last for the session.
+
+use crate::recovery::state_dir;
+use crate::vfs::{self, Memory};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How often modified scratch buffers are written out
+pub const SAVE_INTERVAL: Duration = Duration::from_secs(2);
+
+const PREFIX: &str = "scratch-";
+const EXTENSION: &str = "txt";
+
+pub fn scratch_dir() -> PathBuf {
+    state_dir().join("scratch")
+}
+
+/// Whether a file lives in the scratch directory
+pub fn is_scratch(path: &Path) -> bool {
+    let dir = scratch_dir();
+    let dir = fs::canonicalize(&dir).unwrap_or(dir);
+    path.parent() == Some(dir.as_path())
+}
+
+/// Number in a scratch file's name, `scratch-<n>.txt`
+fn number_of(path: &Path) -> Option<usize> {
+    if path.extension()? != EXTENSION {
+        return None;
+    }
+    path.file_stem()?
+        .to_str()?
+        .strip_prefix(PREFIX)?
+        .parse()
+        .ok()
+}
+
+/// Create an empty file for a new scratch buffer, using the lowest free
+/// number
+pub fn create() -> io::Result<PathBuf> {
+    let dir = scratch_dir();
+    if !vfs::is_mounted(&dir) && vfs::create(&dir, true).is_err() {
+        vfs::mount(dir.clone(), Arc::new(Memory::default()));
+    }
+    let mut number = 1;
+    loop {
+        let path = dir.join(format!("{}{}.{}", PREFIX, number, EXTENSION));
+        match vfs::create(&path, false) {
+            Ok(_) => return Ok(path),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => number += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Scratch files from earlier sessions, in order. Empty ones hold nothing
+/// worth reopening and are removed instead.
+pub fn saved() -> Vec<PathBuf> {
+    let Ok(entries) = vfs::read_dir(scratch_dir()) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(usize, PathBuf)> = entries
+        .into_iter()
+        .map(|entry| entry.path)
+        .filter_map(|path| number_of(&path).map(|number| (number, path)))
+        .filter(|(_, path)| {
+            let empty = vfs::metadata(path).is_ok_and(|metadata| metadata.len == 0);
+            if empty {
+                let _ = vfs::remove(path);
+            }
+            !empty
+        })
+        .collect();
+    files.sort();
+    files.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Autosave bookkeeping for the main loop
+pub struct Autosave {
+    last_run: Instant,
+    failing: bool, // Set after a failed write so the error is reported once
+}
+
+impl Autosave {
+    pub fn new() -> Self {
+        Self {
+            last_run: Instant::now(),
+            failing: false,
+        }
+    }
+
+    /// Whether a save round is due, starting the next interval when it is
+    pub fn due(&mut self) -> bool {
+        if self.last_run.elapsed() < SAVE_INTERVAL {
+            return false;
+        }
+        self.last_run = Instant::now();
+        true
+    }
+
+    /// Record a round's outcome. Returns true when a failure is new and
+    /// should be reported.
+    pub fn record(&mut self, ok: bool) -> bool {
+        let report = !ok && !self.failing;
+        self.failing = !ok;
+        report
+    }
+}
@@ -0,0 +1,73 @@
+//! Mouse wheel acceleration, shared by the editor, the tree and the file
+//! picker. Each notch that follows the last within `[mouse]
+//! acceleration_ms` adds `acceleration_step` to the speed, up to
+//! `max_acceleration`; a pause starts over at one. `[ui] reduced_motion`
+//! keeps the speed at one.
+
+use crate::config::{self, MouseConfig};
+use std::time::Instant;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScrollAcceleration {
+    last_notch: Option<Instant>,
+    speed: usize,
+}
+
+impl Default for ScrollAcceleration {
+    fn default() -> Self {
+        Self {
+            last_notch: None,
+            speed: 1,
+        }
+    }
+}
+
+impl ScrollAcceleration {
+    /// Lines one notch scrolls now, counting it towards the speed
+    pub fn notch(&mut self) -> usize {
+        let config = config::get();
+        let mouse = &config.mouse;
+        if config.ui.reduced_motion {
+            return mouse.scroll_lines.max(1);
+        }
+        self.speed_up(mouse, Instant::now());
+        mouse.scroll_lines.max(1) * self.speed
+    }
+
+    fn speed_up(&mut self, mouse: &MouseConfig, now: Instant) {
+        let quick = self.last_notch.is_some_and(|last| {
+            now.duration_since(last).as_millis() < u128::from(mouse.acceleration_ms)
+        });
+        self.speed = if quick {
+            (self.speed + mouse.acceleration_step).min(mouse.max_acceleration.max(1))
+        } else {
+            1
+        };
+        self.last_notch = Some(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn quick_notches_speed_up() {
+        let mouse = MouseConfig {
+            acceleration_step: 2,
+            max_acceleration: 5,
+            ..MouseConfig::default()
+        };
+        let start = Instant::now();
+        let mut scroll = ScrollAcceleration::default();
+        let speeds: Vec<usize> = [0, 50, 100, 150, 200, 1000]
+            .iter()
+            .map(|&millis| {
+                scroll.speed_up(&mouse, start + Duration::from_millis(millis));
+                scroll.speed
+            })
+            .collect();
+        assert_eq!(speeds, [1, 3, 5, 5, 5, 1]);
+    }
+}
@@ -0,0 +1,389 @@
+//! Structural selection: matching brackets, and growing a selection outward
+//! through the word, string, bracket contents, line and block around it.
+//! Ranges are character indices into the buffer, end exclusive. Brackets in
+//! strings and comments only pair with others in strings and comments.
+//! The find bar's block scope uses the same brackets, falling back on
+//! indentation for languages without braces.
+
+use crate::cursor::Position;
+use crate::language::{Syntax, SyntaxMap};
+use crate::rope_buffer::RopeBuffer;
+
+/// Characters scanned when looking for a bracket's partner
+const SCAN_LIMIT: usize = 200_000;
+
+const PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+const QUOTES: [char; 3] = ['"', '\'', '`'];
+
+pub fn to_index(buffer: &RopeBuffer, position: Position) -> usize {
+    let line = position.line.min(buffer.len_lines().saturating_sub(1));
+    let line_len = buffer.line(line).len_chars();
+    let start = buffer.line_to_char(line);
+    (start + position.column.min(line_len)).min(buffer.len_chars())
+}
+
+pub fn to_position(buffer: &RopeBuffer, index: usize) -> Position {
+    let line = buffer.char_to_line(index);
+    Position::new(line, index - buffer.line_to_char(line))
+}
+
+fn opener_of(ch: char) -> Option<char> {
+    PAIRS
+        .iter()
+        .find(|(_, close)| *close == ch)
+        .map(|(open, _)| *open)
+}
+
+fn closer_of(ch: char) -> Option<char> {
+    PAIRS
+        .iter()
+        .find(|(open, _)| *open == ch)
+        .map(|(_, close)| *close)
+}
+
+/// What the text around a position is part of: a string or comment only
+/// when the characters on both sides of it are
+fn syntax_around(syntax: &mut SyntaxMap, index: usize) -> Syntax {
+    let after = syntax.at(index);
+    if index > 0 && syntax.at(index - 1) == after {
+        after
+    } else {
+        Syntax::Code
+    }
+}
+
+/// Index of the bracket pairing with the one at `index`
+pub fn matching_bracket(
+    buffer: &RopeBuffer,
+    index: usize,
+    syntax: &mut SyntaxMap,
+) -> Option<usize> {
+    if index >= buffer.len_chars() {
+        return None;
+    }
+    let ch = buffer.char(index);
+    let kind = syntax.at(index);
+
+    if let Some(close) = closer_of(ch) {
+        let mut depth = 0;
+        for (offset, next) in buffer.chars_at(index).enumerate().take(SCAN_LIMIT) {
+            if (next != ch && next != close) || syntax.at(index + offset) != kind {
+                continue;
+            }
+            if next == ch {
+                depth += 1;
+            } else if next == close {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index + offset);
+                }
+            }
+        }
+    } else if let Some(open) = opener_of(ch) {
+        let mut depth = 0;
+        for (offset, previous) in buffer
+            .chars_at(index + 1)
+            .reversed()
+            .enumerate()
+            .take(SCAN_LIMIT)
+        {
+            if (previous != ch && previous != open) || syntax.at(index - offset) != kind {
+                continue;
+            }
+            if previous == ch {
+                depth += 1;
+            } else if previous == open {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(index - offset);
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Innermost bracket pair around `start..end`, as the indices of the
+/// opening and closing brackets
+pub fn enclosing_brackets(
+    buffer: &RopeBuffer,
+    start: usize,
+    end: usize,
+    syntax: &mut SyntaxMap,
+) -> Option<(usize, usize)> {
+    let around = syntax_around(syntax, start);
+    let mut depths = [0usize; PAIRS.len()];
+    for (offset, ch) in buffer
+        .chars_at(start)
+        .reversed()
+        .enumerate()
+        .take(SCAN_LIMIT)
+    {
+        let index = start - offset - 1;
+        let closing = PAIRS.iter().position(|(_, close)| *close == ch);
+        let opening = PAIRS.iter().position(|(open, _)| *open == ch);
+        if (closing.is_none() && opening.is_none()) || syntax.at(index) != around {
+            continue;
+        }
+        if let Some(kind) = closing {
+            depths[kind] += 1;
+        } else if let Some(kind) = opening {
+            if depths[kind] > 0 {
+                depths[kind] -= 1;
+            } else if let Some(close) = matching_bracket(buffer, index, syntax) {
+                if close >= end {
+                    return Some((index, close));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Bracket to jump to from the cursor: the partner of a bracket under or
+/// just before the cursor, else the closing bracket around it
+pub fn bracket_jump_target(
+    buffer: &RopeBuffer,
+    index: usize,
+    syntax: &mut SyntaxMap,
+) -> Option<usize> {
+    let is_bracket = |i: usize| {
+        i < buffer.len_chars() && {
+            let ch = buffer.char(i);
+            closer_of(ch).is_some() || opener_of(ch).is_some()
+        }
+    };
+    if is_bracket(index) {
+        return matching_bracket(buffer, index, syntax);
+    }
+    if index > 0 && is_bracket(index - 1) {
+        return matching_bracket(buffer, index - 1, syntax);
+    }
+    enclosing_brackets(buffer, index, index, syntax).map(|(_, close)| close)
+}
+
+/// Where the nearest block around `index` starts: its opening `{`, or
+/// else the less indented line that the lines around `index` hang under
+pub fn enclosing_block(
+    buffer: &RopeBuffer,
+    index: usize,
+    syntax: &mut SyntaxMap,
+) -> Option<usize> {
+    let (mut start, mut end) = (index, index);
+    while let Some((open, close)) = enclosing_brackets(buffer, start, end, syntax) {
+        if buffer.char(open) == '{' {
+            return Some(open);
+        }
+        (start, end) = (open, close + 1);
+    }
+
+    let line = buffer.char_to_line(index);
+    let indent = (0..=line).rev().find_map(|l| indent_of(buffer, l))?;
+    let header = (0..line)
+        .rev()
+        .find(|&l| indent_of(buffer, l).is_some_and(|i| i < indent))?;
+    Some(buffer.line_to_char(header))
+}
+
+/// The range a block covers, given where `enclosing_block` said it starts:
+/// what is between the braces, or the lines indented under the header line
+pub fn block_range(
+    buffer: &RopeBuffer,
+    start: usize,
+    syntax: &mut SyntaxMap,
+) -> Option<(usize, usize)> {
+    if start >= buffer.len_chars() {
+        return None;
+    }
+    if buffer.char(start) == '{' {
+        return matching_bracket(buffer, start, syntax).map(|close| (start + 1, close));
+    }
+
+    let header = buffer.char_to_line(start);
+    let header_indent = indent_of(buffer, header)?;
+    let mut last = header;
+    for line in header + 1..buffer.len_lines() {
+        match indent_of(buffer, line) {
+            Some(indent) if indent <= header_indent => break,
+            Some(_) => last = line,
+            None => {}
+        }
+    }
+    (last > header).then(|| {
+        let end = buffer.line_to_char(last) + buffer.get_line_text(last).chars().count();
+        (buffer.line_to_char(header + 1), end)
+    })
+}
+
+/// Leading whitespace of a line in characters, None for a blank line
+fn indent_of(buffer: &RopeBuffer, line: usize) -> Option<usize> {
+    let text = buffer.get_line_text(line);
+    let trimmed = text.trim_start();
+    (!trimmed.is_empty()).then(|| text.chars().count() - trimmed.chars().count())
+}
+
+/// The identifier touching `index`, empty when there is none
+pub fn word_at(buffer: &RopeBuffer, index: usize) -> (usize, usize) {
+    let line_index = buffer.char_to_line(index);
+    let line_start = buffer.line_to_char(line_index);
+    let line: Vec<char> = buffer.get_line_text(line_index).chars().collect();
+    let column = (index - line_start).min(line.len());
+
+    let is_word = |ch: char| ch.is_alphanumeric() || ch == '_';
+    let mut word_start = column;
+    while word_start > 0 && is_word(line[word_start - 1]) {
+        word_start -= 1;
+    }
+    let mut word_end = column;
+    while word_end < line.len() && is_word(line[word_end]) {
+        word_end += 1;
+    }
+    (line_start + word_start, line_start + word_end)
+}
+
+/// Ranges that could contain `start..end`, from the word outward
+fn candidates(
+    buffer: &RopeBuffer,
+    start: usize,
+    end: usize,
+    syntax: &mut SyntaxMap,
+) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let first_line = buffer.char_to_line(start);
+    let last_line = buffer.char_to_line(end);
+    let line_start = buffer.line_to_char(first_line);
+    let line: Vec<char> = buffer.get_line_text(first_line).chars().collect();
+
+    // Word
+    ranges.push(word_at(buffer, start));
+
+    // Strings on the line: their contents, then with the quotes
+    let mut open_quote: Option<(usize, char)> = None;
+    let mut escaped = false;
+    for (i, &ch) in line.iter().enumerate() {
+        if escaped {
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if let Some((quote_start, quote)) = open_quote {
+            if ch == quote {
+                ranges.push((line_start + quote_start + 1, line_start + i));
+                ranges.push((line_start + quote_start, line_start + i + 1));
+                open_quote = None;
+            }
+        } else if QUOTES.contains(&ch) {
+            open_quote = Some((i, ch));
+        }
+    }
+
+    // Syntax nodes, when the language has a tree
+    ranges.extend(syntax.node_ranges(start, end));
+
+    // Bracket contents, then with the brackets
+    if let Some((open, close)) = enclosing_brackets(buffer, start, end, syntax) {
+        ranges.push((open + 1, close));
+        ranges.push((open, close + 1));
+    }
+
+    // Whole lines, without the final line break
+    let lines_start = buffer.line_to_char(first_line);
+    let lines_end =
+        buffer.line_to_char(last_line) + buffer.get_line_text(last_line).chars().count();
+    ranges.push((lines_start, lines_end));
+
+    // The block of non-blank lines around them
+    let is_blank = |line: usize| buffer.get_line_text(line).trim().is_empty();
+    let mut block_first = first_line;
+    while block_first > 0 && !is_blank(block_first - 1) {
+        block_first -= 1;
+    }
+    let mut block_last = last_line;
+    while block_last + 1 < buffer.len_lines() && !is_blank(block_last + 1) {
+        block_last += 1;
+    }
+    ranges.push((
+        buffer.line_to_char(block_first),
+        buffer.line_to_char(block_last) + buffer.get_line_text(block_last).chars().count(),
+    ));
+
+    ranges.push((0, buffer.len_chars()));
+    ranges
+}
+
+/// The smallest range that strictly contains `start..end`
+pub fn expand(
+    buffer: &RopeBuffer,
+    start: usize,
+    end: usize,
+    syntax: &mut SyntaxMap,
+) -> Option<(usize, usize)> {
+    candidates(buffer, start, end, syntax)
+        .into_iter()
+        .filter(|&(s, e)| s <= start && end <= e && (s, e) != (start, end))
+        .min_by_key(|&(s, e)| e - s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::language::for_file_name;
+
+    #[test]
+    fn brackets_match_both_ways() {
+        let buffer = RopeBuffer::from_str("f(a[1], (b))");
+        let mut syntax = SyntaxMap::new(for_file_name("a.rs"), &buffer);
+        assert_eq!(matching_bracket(&buffer, 1, &mut syntax), Some(11));
+        assert_eq!(matching_bracket(&buffer, 11, &mut syntax), Some(1));
+        assert_eq!(matching_bracket(&buffer, 3, &mut syntax), Some(5));
+        assert_eq!(matching_bracket(&buffer, 0, &mut syntax), None);
+    }
+
+    #[test]
+    fn brackets_in_strings_and_comments_pair_apart() {
+        let buffer = RopeBuffer::from_str("f(\")\", // (\n  x)");
+        let mut syntax = SyntaxMap::new(for_file_name("a.rs"), &buffer);
+        assert_eq!(matching_bracket(&buffer, 1, &mut syntax), Some(15));
+        assert_eq!(matching_bracket(&buffer, 3, &mut syntax), None);
+        assert_eq!(bracket_jump_target(&buffer, 14, &mut syntax), Some(15));
+    }
+
+    #[test]
+    fn blocks_are_braces_or_indentation() {
+        let buffer = RopeBuffer::from_str("fn f() {\n    g(1, [2]);\n}\n");
+        let mut syntax = SyntaxMap::new(for_file_name("a.rs"), &buffer);
+        assert_eq!(enclosing_block(&buffer, 17, &mut syntax), Some(7));
+        assert_eq!(block_range(&buffer, 7, &mut syntax), Some((8, 24)));
+        assert_eq!(enclosing_block(&buffer, 2, &mut syntax), None);
+
+        let buffer = RopeBuffer::from_str("def f():\n    x = 1\n\n    y = 2\nz = 3\n");
+        let mut syntax = SyntaxMap::new(for_file_name("a.py"), &buffer);
+        assert_eq!(enclosing_block(&buffer, 20, &mut syntax), Some(0));
+        assert_eq!(block_range(&buffer, 0, &mut syntax), Some((9, 29)));
+        assert_eq!(enclosing_block(&buffer, 30, &mut syntax), None);
+    }
+
+    #[test]
+    fn expansion_grows_outward() {
+        let buffer = RopeBuffer::from_str("call(\"some text\", x)\nnext");
+        let mut syntax = SyntaxMap::new(for_file_name("a.c"), &buffer);
+        // Inside "some": word, string contents, string, bracket contents, ...
+        let mut range = (8, 8);
+        let mut steps = Vec::new();
+        while let Some(next) = expand(&buffer, range.0, range.1, &mut syntax) {
+            steps.push(next);
+            range = next;
+        }
+        assert_eq!(
+            steps,
+            vec![
+                (6, 10),
+                (6, 15),
+                (5, 16),
+                (5, 19),
+                (4, 20),
+                (0, 20),
+                (0, 25)
+            ]
+        );
+    }
+}
@@ -0,0 +1,556 @@
+//! Experimental session sharing: one f1 serves its active tab over TCP and
+//! others follow it, seeing its text and cursor as they change. Every
+//! update is the whole document as a line of JSON, which keeps the
+//! protocol trivial and is fast enough for the files two people read
+//! together. A snapshot is only taken when the tab, its text or its cursor
+//! changed, and a thread of the host's does the writing so a slow follower
+//! never holds up the editor.
+//!
+//! Followers are read-only unless they join to co-edit. A co-editor sends
+//! each change back as the range it replaced and the text put there, and
+//! the host applies them one at a time in the order they arrive, so every
+//! copy ends up with the host's text. An edit made before the co-editor saw
+//! the host's latest changes is moved past them; one that overlaps them is
+//! dropped, and the next snapshot puts the co-editor's copy right.
+
+use crate::rope_buffer::RopeBuffer;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::ops::Range;
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use std::time::Duration;
+
+/// Longest the host waits on a follower that stopped reading
+const WRITE_TIMEOUT: Duration = Duration::from_millis(200);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Snapshots the host keeps the text of, to place edits made against them
+const SENT_KEPT: usize = 16;
+/// Edits the host keeps of each co-editor's, for the same
+const EDITS_KEPT: usize = 256;
+
+/// What a follower sees of the host's active tab
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub name: String,
+    pub text: String,
+    pub line: usize,
+    pub column: usize,
+    /// The host buffer's revision, which co-editors' edits name as their base
+    pub revision: u64,
+    /// The last edit of each co-editor's that the host has dealt with
+    pub applied: BTreeMap<String, u64>,
+}
+
+/// A co-editor's change: the characters in `start..end` of the text it had,
+/// replaced by `text`. That text was the snapshot at revision `base` plus
+/// the co-editor's own edits after `base_seq`, the last that snapshot had.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Edit {
+    pub author: String,
+    pub seq: u64,
+    pub base: u64,
+    pub base_seq: u64,
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+/// What the host's followers last saw: the tab, its buffer's revision and
+/// the cursor. A snapshot is only built when this changes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Version {
+    pub name: String,
+    pub revision: u64,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Work for the host's writer thread
+enum Outgoing {
+    Follower(TcpStream),
+    Message(String),
+}
+
+/// A snapshot's text, kept by the host
+struct Sent {
+    revision: u64,
+    name: String,
+    text: String,
+}
+
+/// What the host knows of one co-editor
+#[derive(Default)]
+struct CoEditor {
+    last: u64,            // Its last edit dealt with
+    made: VecDeque<Edit>, // Its recent edits, as it made them
+    in_step: Option<u64>, // A revision of the host's equal to its copy
+}
+
+pub struct Host {
+    listener: TcpListener,
+    outgoing: Sender<Outgoing>,
+    last: Option<Version>,
+    incoming: Sender<Edit>, // Cloned into each follower's reader thread
+    edits: Receiver<Edit>,
+    sent: VecDeque<Sent>,
+    co_editors: HashMap<String, CoEditor>,
+    // Edits were dealt with since the last snapshot
+    handled: bool,
+}
+
+impl Host {
+    pub fn start(address: &str) -> io::Result<Host> {
+        let listener = TcpListener::bind(address)?;
+        listener.set_nonblocking(true)?;
+        let (outgoing, work) = channel();
+        std::thread::spawn(move || serve(work));
+        let (incoming, edits) = channel();
+        Ok(Host {
+            listener,
+            outgoing,
+            last: None,
+            incoming,
+            edits,
+            sent: VecDeque::new(),
+            co_editors: HashMap::new(),
+            handled: false,
+        })
+    }
+
+    pub fn address(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Take in waiting followers and, when `version` differs from what was
+    /// last sent or edits were dealt with since, queue the snapshot `text`
+    /// builds for all of them. Joining followers get the last snapshot.
+    /// Returns how many followers joined.
+    pub fn update(&mut self, version: Version, text: impl FnOnce() -> String) -> usize {
+        let mut joined = 0;
+        while let Ok((stream, _)) = self.listener.accept() {
+            if stream.set_nonblocking(false).is_err()
+                || stream.set_write_timeout(Some(WRITE_TIMEOUT)).is_err()
+            {
+                continue;
+            }
+            if let Ok(reader) = stream.try_clone() {
+                let incoming = self.incoming.clone();
+                std::thread::spawn(move || read_edits(reader, incoming));
+            }
+            if self.outgoing.send(Outgoing::Follower(stream)).is_ok() {
+                joined += 1;
+            }
+        }
+        if self.last.as_ref() == Some(&version) && !self.handled {
+            return joined;
+        }
+
+        let snapshot = Snapshot {
+            name: version.name.clone(),
+            text: text(),
+            line: version.line,
+            column: version.column,
+            revision: version.revision,
+            applied: self
+                .co_editors
+                .iter()
+                .map(|(author, co_editor)| (author.clone(), co_editor.last))
+                .collect(),
+        };
+        if let Ok(mut message) = serde_json::to_string(&snapshot) {
+            message.push('\n');
+            let _ = self.outgoing.send(Outgoing::Message(message));
+        }
+        // A move of the cursor alone sends the same text again
+        if self.sent.back().map(|sent| sent.revision) != Some(snapshot.revision) {
+            if self.sent.len() == SENT_KEPT {
+                self.sent.pop_front();
+            }
+            self.sent.push_back(Sent {
+                revision: snapshot.revision,
+                name: snapshot.name,
+                text: snapshot.text,
+            });
+        }
+        self.handled = false;
+        self.last = Some(version);
+        joined
+    }
+
+    /// The edits co-editors sent since the last call, in the order they came
+    pub fn edits(&self) -> Vec<Edit> {
+        self.edits.try_iter().collect()
+    }
+
+    /// Apply `edit` to `target`, the shared tab's name and text, returning
+    /// the range it replaced and how many characters went in its place.
+    /// None when it was dropped: it overlaps a change its author hadn't
+    /// seen, or there is no tab it may edit.
+    pub fn merge(
+        &mut self,
+        edit: Edit,
+        target: Option<(&str, &mut RopeBuffer)>,
+    ) -> Option<(Range<usize>, usize)> {
+        self.handled = true;
+        let placed = target.and_then(|(name, buffer)| {
+            let (range, in_step) = self.place(&edit, name, buffer)?;
+            buffer.replace(range.clone(), &edit.text);
+            Some((range, in_step.then(|| buffer.revision())))
+        });
+
+        let co_editor = self.co_editors.entry(edit.author.clone()).or_default();
+        co_editor.last = edit.seq;
+        co_editor.in_step = placed.as_ref().and_then(|(_, in_step)| *in_step);
+        if co_editor.made.len() == EDITS_KEPT {
+            co_editor.made.pop_front();
+        }
+        let inserted = edit.text.chars().count();
+        co_editor.made.push_back(edit);
+        placed.map(|(range, _)| (range, inserted))
+    }
+
+    /// Where in `buffer` the range `edit` replaced now is, and whether the
+    /// author's copy was the same as `buffer` when it made the edit
+    fn place(&self, edit: &Edit, name: &str, buffer: &RopeBuffer) -> Option<(Range<usize>, bool)> {
+        let co_editor = self.co_editors.get(&edit.author);
+        let range = edit.start..edit.end;
+        let in_step = co_editor.and_then(|co_editor| co_editor.in_step) == Some(buffer.revision())
+            || (edit.base == buffer.revision() && edit.seq == edit.base_seq + 1);
+        if in_step {
+            return (range.start <= range.end && range.end <= buffer.len_chars())
+                .then_some((range, true));
+        }
+
+        // Rebuild the author's copy: the snapshot it had, and its own edits
+        let sent = self
+            .sent
+            .iter()
+            .rev()
+            .find(|sent| sent.revision == edit.base && sent.name == name)?;
+        let mut copy = RopeBuffer::from_str(&sent.text);
+        let mut next = edit.base_seq + 1;
+        let own = co_editor.into_iter().flat_map(|co_editor| &co_editor.made);
+        for earlier in own.filter(|earlier| earlier.seq > edit.base_seq) {
+            if earlier.seq != next || earlier.start > earlier.end || earlier.end > copy.len_chars()
+            {
+                return None;
+            }
+            copy.replace(earlier.start..earlier.end, &earlier.text);
+            next += 1;
+        }
+        if next != edit.seq || range.start > range.end || range.end > copy.len_chars() {
+            return None;
+        }
+
+        // What changed since is everything between the common start and end
+        let Some((theirs, ours)) = difference(&copy, buffer) else {
+            return Some((range, true));
+        };
+        if range.end <= theirs.start {
+            Some((range, false))
+        } else if range.start >= theirs.end {
+            let moved = |index: usize| index - theirs.end + ours.end;
+            Some((moved(range.start)..moved(range.end), false))
+        } else {
+            None
+        }
+    }
+}
+
+/// A follower's reader thread on the host, passing on the edits it sends
+fn read_edits(stream: TcpStream, edits: Sender<Edit>) {
+    for line in BufReader::new(stream).lines() {
+        let Ok(Ok(edit)) = line.map(|line| serde_json::from_str(&line)) else {
+            break;
+        };
+        if edits.send(edit).is_err() {
+            break;
+        }
+    }
+}
+
+/// The one stretch where `old` and `new` differ, as the range it covers in
+/// each: everything between the start and end they have in common. None
+/// when they are the same.
+pub fn difference(old: &RopeBuffer, new: &RopeBuffer) -> Option<(Range<usize>, Range<usize>)> {
+    let (old_len, new_len) = (old.len_chars(), new.len_chars());
+    let start = old
+        .chars_at(0)
+        .zip(new.chars_at(0))
+        .take_while(|(a, b)| a == b)
+        .count();
+    if start == old_len && start == new_len {
+        return None;
+    }
+    let end = old
+        .chars_at(old_len)
+        .reversed()
+        .zip(new.chars_at(new_len).reversed())
+        .take(old_len.min(new_len) - start)
+        .take_while(|(a, b)| a == b)
+        .count();
+    Some((start..old_len - end, start..new_len - end))
+}
+
+/// The host's writer thread. Snapshots queued while a write was under way
+/// are skipped for the newest one. Ends, closing every follower, when the
+/// host is dropped.
+fn serve(work: Receiver<Outgoing>) {
+    let mut followers: Vec<TcpStream> = Vec::new();
+    let mut last: Option<String> = None;
+    while let Ok(first) = work.recv() {
+        let mut message = None;
+        for item in std::iter::once(first).chain(work.try_iter()) {
+            match item {
+                Outgoing::Follower(mut stream) => {
+                    let sent = match &last {
+                        Some(last) => stream.write_all(last.as_bytes()).is_ok(),
+                        None => true,
+                    };
+                    if sent {
+                        followers.push(stream);
+                    }
+                }
+                Outgoing::Message(text) => message = Some(text),
+            }
+        }
+        if let Some(message) = message {
+            followers.retain_mut(|stream| stream.write_all(message.as_bytes()).is_ok());
+            last = Some(message);
+        }
+    }
+    // Shutting down also ends the reader threads, which hold clones
+    for stream in followers {
+        let _ = stream.shutdown(Shutdown::Both);
+    }
+}
+
+/// A follower's side of co-editing
+struct CoEditing {
+    stream: TcpStream,
+    author: String,
+    sent: u64,     // Its last edit sent
+    base: u64,     // Revision of the snapshot last taken
+    base_seq: u64, // Its last edit that snapshot had
+}
+
+pub struct Follower {
+    pub address: String,
+    updates: Receiver<Snapshot>,
+    co_editing: Option<CoEditing>,
+    /// The follow tab's text as of the last snapshot taken or edit sent
+    pub shown: RopeBuffer,
+}
+
+impl Follower {
+    /// Join the session at `address`, sending edits back when `co_edit`
+    pub fn connect(address: &str, co_edit: bool) -> io::Result<Follower> {
+        let socket = address
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no such host"))?;
+        let stream = TcpStream::connect_timeout(&socket, CONNECT_TIMEOUT)?;
+        let co_editing = if co_edit {
+            let writer = stream.try_clone()?;
+            writer.set_write_timeout(Some(WRITE_TIMEOUT))?;
+            Some(CoEditing {
+                author: format!("{}-{}", std::process::id(), writer.local_addr()?),
+                stream: writer,
+                sent: 0,
+                base: 0,
+                base_seq: 0,
+            })
+        } else {
+            None
+        };
+        let (sender, updates) = channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stream).lines() {
+                // Something that isn't a snapshot isn't an f1 session either
+                let Ok(Ok(snapshot)) = line.map(|line| serde_json::from_str(&line)) else {
+                    break;
+                };
+                if sender.send(snapshot).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Follower {
+            address: address.to_string(),
+            updates,
+            co_editing,
+            shown: RopeBuffer::new(),
+        })
+    }
+
+    pub fn is_co_editing(&self) -> bool {
+        self.co_editing.is_some()
+    }
+
+    /// Send the host a change to the text: `range` of it replaced by `text`
+    pub fn send_edit(&mut self, range: Range<usize>, text: String) -> io::Result<()> {
+        let Some(co_editing) = &mut self.co_editing else {
+            return Ok(());
+        };
+        let edit = Edit {
+            author: co_editing.author.clone(),
+            seq: co_editing.sent + 1,
+            base: co_editing.base,
+            base_seq: co_editing.base_seq,
+            start: range.start,
+            end: range.end,
+            text,
+        };
+        let mut line = serde_json::to_string(&edit)?;
+        line.push('\n');
+        co_editing.stream.write_all(line.as_bytes())?;
+        co_editing.sent = edit.seq;
+        Ok(())
+    }
+
+    /// The newest snapshot that arrived since the last call, or None once
+    /// the host has gone and everything it sent was taken. A co-editor
+    /// skips snapshots that don't have all its edits yet, which would take
+    /// back the ones still on their way.
+    pub fn latest(&mut self) -> Option<Option<Snapshot>> {
+        let mut latest = None;
+        let ended = loop {
+            match self.updates.try_recv() {
+                Ok(snapshot) => {
+                    let missing = self.co_editing.as_ref().is_some_and(|co_editing| {
+                        snapshot
+                            .applied
+                            .get(&co_editing.author)
+                            .copied()
+                            .unwrap_or(0)
+                            < co_editing.sent
+                    });
+                    if !missing {
+                        latest = Some(snapshot);
+                    }
+                }
+                Err(TryRecvError::Empty) => break false,
+                Err(TryRecvError::Disconnected) => break true,
+            }
+        };
+        if let (Some(co_editing), Some(snapshot)) = (&mut self.co_editing, &latest) {
+            co_editing.base = snapshot.revision;
+            co_editing.base_seq = co_editing.sent;
+        }
+        match (ended, latest) {
+            (true, None) => None,
+            (_, latest) => Some(latest),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    /// Poll `done` until it holds, failing the test after a few seconds
+    fn wait_for(what: &str, mut done: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done() {
+            assert!(Instant::now() < deadline, "timed out waiting for {}", what);
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn followers_get_the_hosts_snapshots() {
+        let mut host = Host::start("127.0.0.1:0").unwrap();
+        let mut follower = Follower::connect(&host.address().unwrap().to_string(), false).unwrap();
+        let version = |revision| Version {
+            name: "main.rs".to_string(),
+            revision,
+            line: 0,
+            column: 2,
+        };
+
+        wait_for("the follower to join", || {
+            host.update(version(1), || "one".to_string()) > 0
+        });
+        // An unchanged version doesn't build a snapshot
+        host.update(version(1), || unreachable!());
+        host.update(version(2), || "two".to_string());
+
+        let mut seen = None;
+        wait_for("the second snapshot", || {
+            if let Some(Some(latest)) = follower.latest() {
+                seen = Some(latest);
+            }
+            seen.as_ref().map(|latest: &Snapshot| latest.text.as_str()) == Some("two")
+        });
+        assert_eq!(seen.unwrap().column, 2);
+
+        // The follower notices when the host stops
+        drop(host);
+        wait_for("the session to end", || follower.latest().is_none());
+    }
+
+    #[test]
+    fn co_editors_edits_are_merged_in_order() {
+        let mut host = Host::start("127.0.0.1:0").unwrap();
+        let mut co_editor = Follower::connect(&host.address().unwrap().to_string(), true).unwrap();
+        let mut buffer = RopeBuffer::from_str("hello world");
+        let version = |buffer: &RopeBuffer| Version {
+            name: "notes.txt".to_string(),
+            revision: buffer.revision(),
+            line: 0,
+            column: 0,
+        };
+        wait_for("the co-editor to join", || {
+            host.update(version(&buffer), || buffer.to_string()) > 0
+        });
+        wait_for("the first snapshot", || {
+            matches!(co_editor.latest(), Some(Some(_)))
+        });
+
+        // Made one after another on "hello world", while the host changes
+        // its last word
+        co_editor.send_edit(0..0, "oh, ".to_string()).unwrap();
+        co_editor.send_edit(15..15, "!".to_string()).unwrap();
+        co_editor.send_edit(10..15, "earth".to_string()).unwrap();
+        buffer.replace(6..11, "there");
+
+        let mut edits = Vec::new();
+        wait_for("the edits", || {
+            edits.extend(host.edits());
+            edits.len() == 3
+        });
+        let merged: Vec<_> = edits
+            .into_iter()
+            .map(|edit| host.merge(edit, Some(("notes.txt", &mut buffer))))
+            .collect();
+        // The insertions move past the host's change; the overlapping edit
+        // is dropped
+        assert_eq!(merged, [Some((0..0, 4)), Some((15..15, 1)), None]);
+        assert_eq!(buffer.to_string(), "oh, hello there!");
+
+        host.update(version(&buffer), || buffer.to_string());
+        let mut seen = None;
+        wait_for("the merged snapshot", || {
+            if let Some(Some(latest)) = co_editor.latest() {
+                seen = Some(latest.text);
+            }
+            seen.as_deref() == Some("oh, hello there!")
+        });
+
+        // Edits to a tab that isn't shared any more are dropped
+        co_editor.send_edit(0..0, "Well, ".to_string()).unwrap();
+        let mut edits = Vec::new();
+        wait_for("the edit", || {
+            edits.extend(host.edits());
+            !edits.is_empty()
+        });
+        let mut other = RopeBuffer::from_str("oh, hello there!");
+        assert_eq!(
+            host.merge(edits.remove(0), Some(("other.txt", &mut other))),
+            None
+        );
+    }
+}
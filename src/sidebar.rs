@@ -0,0 +1,69 @@
+//! Width of the file tree sidebar and whether it is collapsed, kept in
+//! `<state dir>/sidebar.json` so the layout survives restarts.
+
+use crate::recovery::state_dir;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+pub const MIN_WIDTH: u16 = 15;
+/// Columns added or taken away by one Ctrl+Alt+Left/Right
+pub const STEP: i16 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Sidebar {
+    pub width: u16, // Kept while collapsed, to restore to
+    pub collapsed: bool,
+}
+
+impl Default for Sidebar {
+    fn default() -> Self {
+        Self {
+            width: 30,
+            collapsed: false,
+        }
+    }
+}
+
+fn sidebar_file() -> PathBuf {
+    state_dir().join("sidebar.json")
+}
+
+impl Sidebar {
+    /// The saved layout, or the default when there is none
+    pub fn load() -> Self {
+        let sidebar: Self = fs::read_to_string(sidebar_file())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            width: sidebar.width.max(MIN_WIDTH),
+            ..sidebar
+        }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let path = sidebar_file();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, content)
+    }
+
+    /// Columns taken up on screen
+    pub fn columns(&self) -> u16 {
+        if self.collapsed {
+            0
+        } else {
+            self.width
+        }
+    }
+
+    /// Set the width, keeping it between the minimum and half the screen
+    pub fn resize(&mut self, width: u16, screen_width: u16) {
+        self.width = width.min(screen_width / 2).max(MIN_WIDTH);
+    }
+}
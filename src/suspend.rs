@@ -0,0 +1,32 @@
+use crate::recovery::restore_terminal;
+use crossterm::{
+    event::EnableFocusChange,
+    execute,
+    terminal::{enable_raw_mode, EnterAlternateScreen},
+};
+use ratatui::{backend::CrosstermBackend, Terminal};
+use std::io::{self, Stdout};
+
+/// Hand the terminal back to the shell and stop the process, as Ctrl+Z does
+/// in a cooked terminal. Returns once the job is resumed with `fg`/SIGCONT,
+/// with the editor's terminal state re-established.
+#[cfg(unix)]
+pub fn suspend(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> io::Result<()> {
+    restore_terminal();
+    signal_hook::low_level::emulate_default_handler(signal_hook::consts::SIGTSTP)?;
+    resume(terminal)
+}
+
+#[cfg(not(unix))]
+pub fn suspend(_terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> io::Result<()> {
+    Ok(())
+}
+
+/// Re-enter raw mode and the alternate screen, then force a full redraw since
+/// whatever ran in the meantime has overwritten the screen. The main loop
+/// captures the mouse again if it should be.
+pub fn resume(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> io::Result<()> {
+    enable_raw_mode()?;
+    execute!(terminal.backend_mut(), EnterAlternateScreen, EnableFocusChange)?;
+    terminal.clear()
+}
@@ -0,0 +1,350 @@
+//! Syntax trees from tree-sitter, behind the `tree-sitter` feature. A buffer
+//! parses itself the first time its tree is asked for, reports each edit to
+//! the tree as it happens and re-parses incrementally on the next request, so
+//! only the edited part is parsed again. Languages pick a grammar with their
+//! `highlighter` setting; without the feature there are no grammars and every
+//! lookup comes back empty.
+#![cfg_attr(not(feature = "tree-sitter"), allow(dead_code))]
+
+use crate::language::Syntax;
+use crate::rope_buffer::RopeBuffer;
+use ropey::Rope;
+use std::ops::Range;
+
+/// Larger files are not parsed
+#[cfg(feature = "tree-sitter")]
+const MAX_PARSE_BYTES: usize = 4 * 1024 * 1024;
+
+#[cfg(feature = "tree-sitter")]
+pub use tree_sitter::Tree;
+
+/// Never built without tree-sitter
+#[cfg(not(feature = "tree-sitter"))]
+#[derive(Clone)]
+pub enum Tree {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Highlight {
+    Comment,
+    String,
+    Number,
+    Keyword,
+    Type,
+    Function,
+}
+
+/// A buffer's tree and the grammar it came from. An edited tree still
+/// describes the old text, shifted to match the new one, until re-parsed.
+#[derive(Clone)]
+pub struct Parsed {
+    grammar: String,
+    tree: Tree,
+    stale: bool,
+}
+
+/// Where an edit starts and what it replaces, taken before the rope changes
+pub struct EditStart {
+    start_byte: usize,
+    start: (usize, usize),
+    old_end_byte: usize,
+    old_end: (usize, usize),
+}
+
+/// Byte offset and (row, byte column) of a character
+fn point(rope: &Rope, char_idx: usize) -> (usize, (usize, usize)) {
+    let byte = rope.char_to_byte(char_idx);
+    let row = rope.char_to_line(char_idx);
+    (byte, (row, byte - rope.line_to_byte(row)))
+}
+
+impl EditStart {
+    pub fn new(rope: &Rope, range: Range<usize>) -> Self {
+        let (start_byte, start) = point(rope, range.start);
+        let (old_end_byte, old_end) = point(rope, range.end);
+        Self {
+            start_byte,
+            start,
+            old_end_byte,
+            old_end,
+        }
+    }
+}
+
+impl Parsed {
+    /// Shift the tree over an edit that ended at `new_end` (a character index)
+    pub fn edit(&mut self, start: EditStart, rope: &Rope, new_end: usize) {
+        #[cfg(feature = "tree-sitter")]
+        {
+            use tree_sitter::{InputEdit, Point};
+            let (new_end_byte, new_end) = point(rope, new_end);
+            let to_point = |(row, column)| Point { row, column };
+            self.tree.edit(&InputEdit {
+                start_byte: start.start_byte,
+                old_end_byte: start.old_end_byte,
+                new_end_byte,
+                start_position: to_point(start.start),
+                old_end_position: to_point(start.old_end),
+                new_end_position: to_point(new_end),
+            });
+            self.stale = true;
+        }
+        #[cfg(not(feature = "tree-sitter"))]
+        let _ = (start, rope, new_end);
+    }
+}
+
+/// The tree of `rope` in a grammar, re-using the cached tree when it came
+/// from the same one. None when the grammar is unknown or the file too big.
+pub fn update(cached: &mut Option<Parsed>, rope: &Rope, grammar: &str) -> Option<Tree> {
+    #[cfg(feature = "tree-sitter")]
+    {
+        if rope.len_bytes() > MAX_PARSE_BYTES {
+            *cached = None;
+            return None;
+        }
+        let old = cached.as_ref().filter(|parsed| parsed.grammar == grammar);
+        if let Some(parsed) = old.filter(|parsed| !parsed.stale) {
+            return Some(parsed.tree.clone());
+        }
+
+        let language = grammar_for(grammar)?;
+        let _span = crate::perf::span("syntax parse");
+        let mut parser = tree_sitter::Parser::new();
+        parser.set_language(&language).ok()?;
+        let mut read = |byte: usize, _| {
+            if byte >= rope.len_bytes() {
+                return &[][..];
+            }
+            let (chunk, chunk_start, _, _) = rope.chunk_at_byte(byte);
+            &chunk.as_bytes()[byte - chunk_start..]
+        };
+        let tree = parser.parse_with(&mut read, old.map(|parsed| &parsed.tree))?;
+        *cached = Some(Parsed {
+            grammar: grammar.to_string(),
+            tree: tree.clone(),
+            stale: false,
+        });
+        Some(tree)
+    }
+    #[cfg(not(feature = "tree-sitter"))]
+    {
+        let _ = (cached, rope, grammar);
+        None
+    }
+}
+
+#[cfg(feature = "tree-sitter")]
+fn grammar_for(name: &str) -> Option<tree_sitter::Language> {
+    let language = match name {
+        "rust" => tree_sitter_rust::LANGUAGE,
+        "python" => tree_sitter_python::LANGUAGE,
+        "javascript" => tree_sitter_javascript::LANGUAGE,
+        "json" => tree_sitter_json::LANGUAGE,
+        "go" => tree_sitter_go::LANGUAGE,
+        "bash" => tree_sitter_bash::LANGUAGE,
+        _ => return None,
+    };
+    Some(language.into())
+}
+
+/// How a node is drawn, if it has a color of its own
+#[cfg(feature = "tree-sitter")]
+fn highlight_of(node: tree_sitter::Node) -> Option<Highlight> {
+    let kind = node.kind();
+    if kind.contains("comment") {
+        return Some(Highlight::Comment);
+    }
+    if kind.contains("string") || kind == "char_literal" || kind == "heredoc_body" {
+        return Some(Highlight::String);
+    }
+    if !node.is_named() {
+        let is_word =
+            !kind.is_empty() && kind.chars().all(|ch| ch.is_ascii_lowercase() || ch == '_');
+        return is_word.then_some(Highlight::Keyword);
+    }
+    if kind.contains("integer") || kind.contains("float") || kind.contains("number") {
+        return Some(Highlight::Number);
+    }
+    match kind {
+        "true" | "false" | "boolean" | "boolean_literal" | "none" | "null" => {
+            Some(Highlight::Number)
+        }
+        "type_identifier" | "primitive_type" => Some(Highlight::Type),
+        "identifier" | "field_identifier" | "property_identifier" => {
+            let parent = node.parent()?;
+            let named_by = |field| parent.child_by_field_name(field) == Some(node);
+            let callable = ["function", "method", "call", "macro"]
+                .iter()
+                .any(|word| parent.kind().contains(word));
+            (callable && (named_by("name") || named_by("function") || named_by("macro")))
+                .then_some(Highlight::Function)
+        }
+        _ => None,
+    }
+}
+
+/// Highlighted columns of a line
+pub fn line_highlights(
+    tree: &Tree,
+    buffer: &RopeBuffer,
+    line: usize,
+) -> Vec<(Range<usize>, Highlight)> {
+    #[cfg(feature = "tree-sitter")]
+    {
+        let line_start = buffer.line_to_char(line);
+        let bytes = buffer.char_to_byte(line_start)
+            ..buffer.char_to_byte(line_start + buffer.line(line).len_chars());
+        let mut found = Vec::new();
+        let Some(node) = tree
+            .root_node()
+            .descendant_for_byte_range(bytes.start, bytes.end)
+        else {
+            return found;
+        };
+        // Inside a multi-line string or comment the whole line is one
+        let mut outer = Some(node);
+        while let Some(current) = outer {
+            if let Some(highlight @ (Highlight::Comment | Highlight::String)) =
+                highlight_of(current)
+            {
+                return vec![(0..buffer.line(line).len_chars(), highlight)];
+            }
+            outer = current.parent();
+        }
+        let mut ranges = Vec::new();
+        collect_highlights(node, &bytes, &mut ranges);
+        for (range, highlight) in ranges {
+            let start = buffer.byte_to_char(range.start.max(bytes.start)) - line_start;
+            let end = buffer.byte_to_char(range.end.min(bytes.end)) - line_start;
+            if start < end {
+                found.push((start..end, highlight));
+            }
+        }
+        found
+    }
+    #[cfg(not(feature = "tree-sitter"))]
+    {
+        let _ = (buffer, line);
+        match *tree {}
+    }
+}
+
+/// Byte ranges of the highlighted nodes under `node` that overlap `bytes`
+#[cfg(feature = "tree-sitter")]
+fn collect_highlights(
+    node: tree_sitter::Node,
+    bytes: &Range<usize>,
+    found: &mut Vec<(Range<usize>, Highlight)>,
+) {
+    if let Some(highlight) = highlight_of(node) {
+        found.push((node.byte_range(), highlight));
+        return;
+    }
+    let mut cursor = node.walk();
+    if cursor.goto_first_child_for_byte(bytes.start).is_none() {
+        return;
+    }
+    loop {
+        let child = cursor.node();
+        if child.start_byte() >= bytes.end {
+            break;
+        }
+        collect_highlights(child, bytes, found);
+        if !cursor.goto_next_sibling() {
+            break;
+        }
+    }
+}
+
+/// Tells trees apart: a re-parse makes a new tree while the old one is
+/// still alive, so it never gets the old one's id
+pub fn tree_id(tree: &Tree) -> usize {
+    #[cfg(feature = "tree-sitter")]
+    {
+        tree.root_node().id()
+    }
+    #[cfg(not(feature = "tree-sitter"))]
+    {
+        match *tree {}
+    }
+}
+
+/// Whether the character at `index` is in a string or comment
+pub fn syntax_at(tree: &Tree, buffer: &RopeBuffer, index: usize) -> Syntax {
+    #[cfg(feature = "tree-sitter")]
+    {
+        let byte = buffer.char_to_byte(index);
+        let mut node = tree.root_node().descendant_for_byte_range(byte, byte + 1);
+        while let Some(current) = node {
+            match highlight_of(current) {
+                Some(Highlight::Comment) => return Syntax::Comment,
+                Some(Highlight::String) => return Syntax::String,
+                _ => node = current.parent(),
+            }
+        }
+        Syntax::Code
+    }
+    #[cfg(not(feature = "tree-sitter"))]
+    {
+        let _ = (buffer, index);
+        match *tree {}
+    }
+}
+
+/// Character ranges of the nodes around `start..end`, innermost first
+pub fn node_ranges(
+    tree: &Tree,
+    buffer: &RopeBuffer,
+    start: usize,
+    end: usize,
+) -> Vec<(usize, usize)> {
+    #[cfg(feature = "tree-sitter")]
+    {
+        let mut ranges = Vec::new();
+        let mut node = tree
+            .root_node()
+            .named_descendant_for_byte_range(buffer.char_to_byte(start), buffer.char_to_byte(end));
+        while let Some(current) = node {
+            ranges.push((
+                buffer.byte_to_char(current.start_byte()),
+                buffer.byte_to_char(current.end_byte()),
+            ));
+            node = current.parent();
+        }
+        ranges
+    }
+    #[cfg(not(feature = "tree-sitter"))]
+    {
+        let _ = (buffer, start, end);
+        match *tree {}
+    }
+}
+
+#[cfg(all(test, feature = "tree-sitter"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_reparse_incrementally() {
+        let mut buffer = RopeBuffer::from_str("fn main() {\n    let x = 1;\n}\n");
+        let tree = buffer.syntax_tree("rust").unwrap();
+        assert!(!tree.root_node().has_error());
+        assert_eq!(
+            line_highlights(&tree, &buffer, 1),
+            vec![(4..7, Highlight::Keyword), (12..13, Highlight::Number)]
+        );
+
+        buffer.insert(27, "// \"done\"\n");
+        let tree = buffer.syntax_tree("rust").unwrap();
+        assert_eq!(
+            line_highlights(&tree, &buffer, 2),
+            vec![(0..9, Highlight::Comment)]
+        );
+        assert_eq!(syntax_at(&tree, &buffer, 30), Syntax::Comment);
+        assert_eq!(syntax_at(&tree, &buffer, 20), Syntax::Code);
+        // The word, the declaration, the block, the function, the file
+        let ranges = node_ranges(&tree, &buffer, 20, 20);
+        assert_eq!(ranges[0], (20, 21));
+        assert_eq!(ranges.last(), Some(&(0, buffer.len_chars())));
+    }
+}
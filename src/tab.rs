@@ -1,10 +1,29 @@
 use crate::{
+    annotations::Annotations,
+    config,
     cursor::{Cursor, Position},
+    fold::Folding,
+    indent::Indent,
+    language::{self, Language, SyntaxMap},
+    line_cache::LineCache,
+    marks,
+    project_search::Matcher,
     rope_buffer::RopeBuffer,
-    terminal_widget::TerminalWidget
+    selection,
+    terminal_widget::TerminalWidget,
+    ui::{FindBarTarget, TextInput},
+    view::{ViewOptions, ViewOverrides},
+    wrap,
 };
 use ratatui::layout::Rect;
-use std::path::PathBuf;
+use unicode_segmentation::UnicodeSegmentation;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Undo steps kept however much memory `[undo] max_memory_mb` allows
+const MIN_UNDO_STEPS: usize = 10;
+/// Rough size of the rope nodes an edit copies, counted per undo snapshot
+const SNAPSHOT_OVERHEAD: usize = 16 * 1024;
 
 #[derive(Clone, Debug)]
 pub struct FindMatch {
@@ -18,6 +37,13 @@ pub struct EditorState {
     pub cursor: Cursor,
 }
 
+/// A selection grown by `expand_selection`, with the cursor it replaced
+#[derive(Clone)]
+pub struct SelectionStep {
+    previous: Cursor,
+    expanded: (Position, Position),
+}
+
 #[derive(Clone, PartialEq)]
 pub enum FindFocusedField {
     Find,
@@ -27,32 +53,36 @@ pub enum FindFocusedField {
 #[derive(Clone)]
 pub struct FindReplaceState {
     pub active: bool,
-    pub find_query: String,
-    pub replace_query: String,
+    pub find_input: TextInput,
+    pub replace_input: TextInput,
     pub current_match_index: Option<usize>,
     pub matches: Vec<FindMatch>,
     pub case_sensitive: bool,
+    pub ignore_accents: bool,
     pub whole_word: bool,
+    pub highlight_all: bool, // Highlight every match, not just the current one
+    pub block: Option<Position>, // Start of the block the search is limited to
     pub is_replace_mode: bool,
-    pub find_cursor_position: usize,
-    pub replace_cursor_position: usize,
     pub focused_field: FindFocusedField,
+    pub hovered: Option<FindBarTarget>, // Control under the mouse
 }
 
 impl Default for FindReplaceState {
     fn default() -> Self {
         Self {
             active: false,
-            find_query: String::new(),
-            replace_query: String::new(),
+            find_input: TextInput::new(),
+            replace_input: TextInput::new(),
             current_match_index: None,
             matches: Vec::new(),
             case_sensitive: false,
+            ignore_accents: config::get().search.ignore_accents,
             whole_word: false,
+            highlight_all: true,
+            block: None,
             is_replace_mode: false,
-            find_cursor_position: 0,
-            replace_cursor_position: 0,
             focused_field: FindFocusedField::Find,
+            hovered: None,
         }
     }
 }
@@ -66,11 +96,19 @@ pub enum Tab {
         viewport_offset: (usize, usize),
         modified: bool,
         preview_mode: bool,
-        word_wrap: bool,
+        view: ViewOptions,
+        view_overrides: ViewOverrides, // Kept for the file across sessions
+        indent: Indent,
+        selection_steps: Vec<SelectionStep>, // Undone by shrink_selection
         find_replace_state: FindReplaceState,
         undo_stack: Vec<EditorState>,
         redo_stack: Vec<EditorState>,
-        max_undo_history: usize,
+        last_edit: Option<Instant>, // When the last coalescable edit happened
+        disk_mtime: Option<SystemTime>, // Modification time when last loaded or saved
+        preview_tab: bool, // Opened by a click in the tree; the next such click replaces it
+        annotations: Annotations, // Virtual text other subsystems attach to lines
+        line_cache: LineCache, // Rows drawn on the last frame
+        read_only: bool, // Edit keys are refused: an archive member or a followed session
     },
     Terminal {
         name: String,
@@ -91,15 +129,24 @@ impl Tab {
             viewport_offset: (0, 0),
             modified: false,
             preview_mode: false,
-            word_wrap: false,
+            view: ViewOptions::from_config(),
+            view_overrides: ViewOverrides::default(),
+            indent: Indent::default(),
+            selection_steps: Vec::new(),
             find_replace_state: FindReplaceState::default(),
             undo_stack: Vec::new(),
             redo_stack: Vec::new(),
-            max_undo_history: 100,
+            last_edit: None,
+            disk_mtime: None,
+            preview_tab: false,
+            annotations: Annotations::default(),
+            line_cache: LineCache::default(),
+            read_only: false,
         }
     }
 
     pub fn from_file(path: PathBuf, content: &str) -> Self {
+        let path = canonical_path(&path);
         let name = path
             .file_name()
             .and_then(|n| n.to_str())
@@ -112,6 +159,12 @@ impl Tab {
             name.ends_with(".md") || name.ends_with(".markdown")
         };
 
+        let disk_mtime = file_mtime(&path);
+        let read_only = crate::vfs::is_read_only(&path);
+        let view_overrides = ViewOverrides::load(&path);
+        let mut annotations = Annotations::default();
+        annotations.set(marks::ANNOTATION_SOURCE, marks::annotations(&path));
+
         Tab::Editor {
             name,
             path: Some(path),
@@ -120,11 +173,19 @@ impl Tab {
             viewport_offset: (0, 0),
             modified: false,
             preview_mode: is_markdown,
-            word_wrap: false,
+            view: view_overrides.apply(ViewOptions::from_config()),
+            view_overrides,
+            indent: Indent::detect(content).unwrap_or_default(),
+            selection_steps: Vec::new(),
             find_replace_state: FindReplaceState::default(),
             undo_stack: Vec::new(),
             redo_stack: Vec::new(),
-            max_undo_history: 100,
+            last_edit: None,
+            disk_mtime,
+            preview_tab: false,
+            annotations,
+            line_cache: LineCache::default(),
+            read_only,
         }
     }
 
@@ -144,37 +205,143 @@ impl Tab {
         }
     }
 
+    /// Name without the modified marker
+    pub fn name(&self) -> &str {
+        match self {
+            Tab::Editor { name, .. } | Tab::Terminal { name, .. } => name,
+        }
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        matches!(self, Tab::Editor { read_only: true, .. })
+    }
+
+    pub fn set_read_only(&mut self, value: bool) {
+        if let Tab::Editor { read_only, .. } = self {
+            *read_only = value;
+        }
+    }
+
+    pub fn is_modified(&self) -> bool {
+        match self {
+            Tab::Editor { modified, .. } | Tab::Terminal { modified, .. } => *modified,
+        }
+    }
+
     pub fn mark_modified(&mut self) {
         match self {
-            Tab::Editor { modified, .. } => *modified = true,
+            Tab::Editor { modified, preview_tab, .. } => {
+                // Editing a preview tab keeps it
+                *modified = true;
+                *preview_tab = false;
+            }
             Tab::Terminal { modified, .. } => *modified = true,
         }
+        self.refresh_find_matches();
+    }
+
+    /// Whether the tab is a preview, which the next file clicked in the tree
+    /// replaces
+    pub fn is_preview_tab(&self) -> bool {
+        matches!(self, Tab::Editor { preview_tab: true, .. })
+    }
+
+    /// Turn a preview tab into a permanent one
+    pub fn keep(&mut self) {
+        if let Tab::Editor { preview_tab, .. } = self {
+            *preview_tab = false;
+        }
     }
 
     pub fn mark_saved(&mut self) {
         match self {
-            Tab::Editor { modified, .. } => *modified = false,
+            Tab::Editor { modified, path, disk_mtime, .. } => {
+                *modified = false;
+                *disk_mtime = path.as_deref().and_then(file_mtime);
+            }
             Tab::Terminal { modified, .. } => *modified = false,
         }
     }
 
-    pub fn update_viewport(&mut self, height: usize) {
+    /// True if the file was modified by someone else since it was loaded or saved
+    pub fn changed_on_disk(&self) -> bool {
         match self {
-            Tab::Editor { cursor, viewport_offset, .. } => {
+            Tab::Editor { path: Some(path), disk_mtime: Some(known), .. } => {
+                file_mtime(path).is_some_and(|current| current > *known)
+            }
+            _ => false,
+        }
+    }
+
+    /// Replace the buffer with the file's current contents, discarding edits
+    pub fn reload_from_disk(&mut self) -> std::io::Result<()> {
+        if let Tab::Editor { path: Some(path), buffer, cursor, undo_stack, redo_stack, .. } = self {
+            let content = crate::vfs::read_to_string(&*path)?;
+            *buffer = RopeBuffer::from_str(&content);
+
+            // Keep the cursor where it was if that position still exists
+            let line = cursor.position.line.min(buffer.len_lines().saturating_sub(1));
+            let column = cursor.position.column.min(buffer.get_line_text(line).len());
+            cursor.move_to(line, column);
+            cursor.clear_selection();
+
+            undo_stack.clear();
+            redo_stack.clear();
+            self.mark_saved();
+        }
+        Ok(())
+    }
+
+    /// Furthest the viewport may scroll down. With scroll-past-end (or
+    /// typewriter mode) the last line can reach the middle of the view.
+    pub fn max_viewport_line(line_count: usize, height: usize) -> usize {
+        let editor = &config::get().editor;
+        let fitted = line_count.saturating_sub(height);
+        if editor.scroll_past_end || editor.typewriter_mode {
+            fitted.max(line_count.saturating_sub(1).saturating_sub(height / 2))
+        } else {
+            fitted
+        }
+    }
+
+    pub fn update_viewport(&mut self, width: usize, height: usize) {
+        match self {
+            Tab::Editor { cursor, viewport_offset, buffer, view, .. } => {
+                let editor = &config::get().editor;
                 let cursor_line = cursor.position.line;
                 let (viewport_line, viewport_col) = *viewport_offset;
-
-                if cursor_line < viewport_line {
-                    viewport_offset.0 = cursor_line;
-                } else if cursor_line >= viewport_line + height {
-                    viewport_offset.0 = cursor_line.saturating_sub(height - 1);
+                let max_line = Self::max_viewport_line(buffer.len_lines(), height);
+                // The margin can't exceed half the view or the cursor could never settle
+                let margin = editor.scroll_margin.min(height.saturating_sub(1) / 2);
+
+                if editor.typewriter_mode {
+                    // Keep the cursor line in the middle of the view
+                    viewport_offset.0 = cursor_line.saturating_sub(height / 2).min(max_line);
+                } else if cursor_line < viewport_line + margin {
+                    viewport_offset.0 = cursor_line.saturating_sub(margin);
+                } else if cursor_line + margin >= viewport_line + height {
+                    viewport_offset.0 = (cursor_line + margin + 1)
+                        .saturating_sub(height)
+                        .min(max_line.max(viewport_line));
                 }
 
+                // A split line's rows fit the view, so it isn't panned
+                if view.split_long_lines && wrap::is_too_long(buffer, cursor_line) {
+                    viewport_offset.1 = 0;
+                    return;
+                }
+                let mut view_width = width.max(1);
+                // A cut off line's note covers the right edge of the view
+                if wrap::is_too_long(buffer, cursor_line) {
+                    let note_width = wrap::CUT_OFF_NOTE.chars().count();
+                    view_width = view_width.saturating_sub(note_width).max(1);
+                }
                 let cursor_col = cursor.position.column;
-                if cursor_col < viewport_col {
-                    viewport_offset.1 = cursor_col;
-                } else if cursor_col >= viewport_col + 80 {
-                    viewport_offset.1 = cursor_col.saturating_sub(79);
+                let col_margin = editor.horizontal_scroll_margin.min((view_width - 1) / 2);
+                if cursor_col < viewport_col + col_margin {
+                    viewport_offset.1 = cursor_col.saturating_sub(col_margin);
+                } else if cursor_col + col_margin >= viewport_col + view_width {
+                    viewport_offset.1 = (cursor_col + col_margin + 1).saturating_sub(view_width);
                 }
             }
             Tab::Terminal { .. } => {
@@ -184,8 +351,8 @@ impl Tab {
         }
     }
 
-    pub fn ensure_cursor_visible(&mut self, height: usize) {
-        self.update_viewport(height);
+    pub fn ensure_cursor_visible(&mut self, width: usize, height: usize) {
+        self.update_viewport(width, height);
     }
 
     pub fn toggle_preview_mode(&mut self) {
@@ -197,13 +364,37 @@ impl Tab {
         }
     }
 
-    #[allow(dead_code)]
-    pub fn toggle_word_wrap(&mut self) {
-        if let Tab::Editor { word_wrap, .. } = self {
-            *word_wrap = !*word_wrap;
+    /// Follow the defaults in every display option the tab doesn't override
+    pub fn apply_view_defaults(&mut self, defaults: ViewOptions) {
+        if let Tab::Editor { view, view_overrides, .. } = self {
+            *view = view_overrides.apply(defaults);
+        }
+    }
+
+    /// Display options in effect and the ones the tab sets itself
+    pub fn view(&self) -> Option<(ViewOptions, ViewOverrides)> {
+        match self {
+            Tab::Editor { view, view_overrides, .. } => Some((*view, *view_overrides)),
+            Tab::Terminal { .. } => None,
         }
     }
 
+    /// Change the tab's own display options; a file remembers them
+    pub fn set_view_overrides(
+        &mut self,
+        overrides: ViewOverrides,
+        defaults: ViewOptions,
+    ) -> std::io::Result<()> {
+        if let Tab::Editor { view_overrides, path, .. } = self {
+            *view_overrides = overrides;
+            if let Some(path) = path {
+                overrides.save(path)?;
+            }
+        }
+        self.apply_view_defaults(defaults);
+        Ok(())
+    }
+
     pub fn is_markdown(&self) -> bool {
         match self {
             Tab::Editor { path, name, .. } => {
@@ -218,22 +409,144 @@ impl Tab {
         }
     }
 
+    /// Language of the tab's file, by its name
+    /// Markdown and plain text, which get word counts rather than code tools
+    pub fn is_prose(&self) -> bool {
+        matches!(self, Tab::Editor { .. })
+            && (self.is_markdown() || self.language().name == "Plain Text")
+    }
+
+    pub fn language(&self) -> &'static Language {
+        let name = match self {
+            Tab::Editor { path, name, .. } => path
+                .as_deref()
+                .and_then(|path| path.file_name())
+                .and_then(|name| name.to_str())
+                .unwrap_or(name),
+            Tab::Terminal { .. } => "",
+        };
+        language::for_file_name(name)
+    }
+
+    /// Diffs and patches, drawn with added and removed lines colored
+    pub fn is_diff(&self) -> bool {
+        self.language().highlighter.as_deref() == Some("diff")
+    }
+
     pub fn save_state(&mut self) {
-        if let Tab::Editor { buffer, cursor, undo_stack, max_undo_history, redo_stack, .. } = self {
+        if let Tab::Editor { buffer, cursor, undo_stack, redo_stack, last_edit, .. } = self {
             let state = EditorState {
                 buffer: buffer.clone(),
                 cursor: cursor.clone(),
             };
             undo_stack.push(state);
-            if undo_stack.len() > *max_undo_history {
-                undo_stack.remove(0);
-            }
             redo_stack.clear();
+            *last_edit = None;
+
+            // Evict the oldest snapshots past the configured entry and memory
+            // limits. Snapshots share the rope's unchanged nodes, so each one
+            // costs what changed between it and the next state, plus the nodes
+            // copied on the way. The newest few are kept whatever they cost.
+            let limits = &config::get().undo;
+            let max_bytes = limits.max_memory_mb.saturating_mul(1024 * 1024);
+            let mut evict = undo_stack.len().saturating_sub(limits.max_entries.max(1));
+            let cost = |index: usize| {
+                let next = undo_stack.get(index + 1).map_or(&*buffer, |state| &state.buffer);
+                undo_stack[index].buffer.unshared_with(next) + SNAPSHOT_OVERHEAD
+            };
+            let mut total_bytes: usize = (evict..undo_stack.len()).map(cost).sum();
+            let keep = MIN_UNDO_STEPS.min(limits.max_entries.max(1));
+            while total_bytes > max_bytes && evict + keep < undo_stack.len() {
+                total_bytes -= cost(evict);
+                evict += 1;
+            }
+            undo_stack.drain(..evict);
+        }
+    }
+
+    /// Snapshot before a typing edit. Edits that follow each other within the
+    /// configured window share one snapshot, so they undo as a single step.
+    pub fn begin_edit(&mut self) {
+        let window = Duration::from_millis(config::get().undo.coalesce_ms);
+        let coalesce = match self {
+            Tab::Editor { last_edit, undo_stack, .. } => {
+                !undo_stack.is_empty() && last_edit.is_some_and(|t| t.elapsed() < window)
+            }
+            Tab::Terminal { .. } => return,
+        };
+
+        if !coalesce {
+            self.save_state();
+        }
+        if let Tab::Editor { last_edit, .. } = self {
+            *last_edit = Some(Instant::now());
+        }
+    }
+
+    /// Make the next edit start its own undo step instead of coalescing
+    pub fn end_edit_group(&mut self) {
+        if let Tab::Editor { last_edit, .. } = self {
+            *last_edit = None;
+        }
+    }
+
+    /// "N lines, M words, K chars selected" for the status bar, if anything is selected
+    pub fn selection_summary(&self) -> Option<String> {
+        let (buffer, cursor) = match self {
+            Tab::Editor { buffer, cursor, .. } => (buffer, cursor),
+            Tab::Terminal { .. } => return None,
+        };
+        let (start, end) = cursor.get_selection()?;
+        if start == end {
+            return None;
+        }
+
+        let to_char = |pos: Position| {
+            if pos.line >= buffer.len_lines() {
+                buffer.len_chars()
+            } else {
+                (buffer.line_to_char(pos.line) + pos.column).min(buffer.len_chars())
+            }
+        };
+        let text = buffer.slice(to_char(start)..to_char(end));
+        let chars = text.len_chars();
+
+        // A selection ending at the start of a line doesn't count that line
+        let mut lines = end.line - start.line + 1;
+        if end.column == 0 && end.line > start.line {
+            lines -= 1;
+        }
+
+        // Word counting walks the whole selection, so skip it for huge ones.
+        // Words never span lines, and a line mostly borrows from the rope.
+        let words = if chars <= 1_000_000 {
+            let words: usize = text
+                .lines()
+                .map(|line| std::borrow::Cow::from(line).unicode_words().count())
+                .sum();
+            format!("{} words, ", words)
+        } else {
+            String::new()
+        };
+
+        Some(if lines > 1 {
+            format!("{} lines, {}{} chars selected", lines, words, chars)
+        } else {
+            format!("{}{} chars selected", words, chars)
+        })
+    }
+
+    /// Number of undo and redo steps available
+    pub fn undo_depth(&self) -> (usize, usize) {
+        match self {
+            Tab::Editor { undo_stack, redo_stack, .. } => (undo_stack.len(), redo_stack.len()),
+            Tab::Terminal { .. } => (0, 0),
         }
     }
 
     pub fn undo(&mut self) -> bool {
-        if let Tab::Editor { buffer, cursor, undo_stack, redo_stack, modified, .. } = self {
+        if let Tab::Editor { buffer, cursor, undo_stack, redo_stack, modified, last_edit, .. } = self {
+            *last_edit = None;
             if let Some(previous_state) = undo_stack.pop() {
                 let current_state = EditorState {
                     buffer: buffer.clone(),
@@ -245,6 +558,7 @@ impl Tab {
                 if undo_stack.is_empty() {
                     *modified = false;
                 }
+                self.refresh_find_matches();
                 true
             } else {
                 false
@@ -255,7 +569,8 @@ impl Tab {
     }
 
     pub fn redo(&mut self) -> bool {
-        if let Tab::Editor { buffer, cursor, undo_stack, redo_stack, modified, .. } = self {
+        if let Tab::Editor { buffer, cursor, undo_stack, redo_stack, modified, last_edit, .. } = self {
+            *last_edit = None;
             if let Some(next_state) = redo_stack.pop() {
                 let current_state = EditorState {
                     buffer: buffer.clone(),
@@ -265,6 +580,7 @@ impl Tab {
                 *buffer = next_state.buffer;
                 *cursor = next_state.cursor;
                 *modified = true;
+                self.refresh_find_matches();
                 true
             } else {
                 false
@@ -274,18 +590,53 @@ impl Tab {
         }
     }
 
+    /// Open the find bar. A selection within one line becomes the query and
+    /// is searched for at once, with the selected occurrence current.
     pub fn start_find(&mut self) {
-        if let Tab::Editor { find_replace_state, .. } = self {
-            find_replace_state.active = true;
-            find_replace_state.is_replace_mode = true;
-            find_replace_state.find_query.clear();
-            find_replace_state.replace_query.clear();
-            find_replace_state.matches.clear();
-            find_replace_state.current_match_index = None;
-            find_replace_state.find_cursor_position = 0;
-            find_replace_state.replace_cursor_position = 0;
-            find_replace_state.focused_field = FindFocusedField::Find;
+        let Tab::Editor { find_replace_state, buffer, cursor, .. } = self else {
+            return;
+        };
+        find_replace_state.active = true;
+        find_replace_state.is_replace_mode = true;
+        find_replace_state.find_input.clear();
+        find_replace_state.replace_input.clear();
+        find_replace_state.matches.clear();
+        find_replace_state.current_match_index = None;
+        find_replace_state.block = None;
+        find_replace_state.focused_field = FindFocusedField::Find;
+
+        let Some((start, end)) = cursor.get_selection() else {
+            return;
+        };
+        if start.line != end.line || start == end {
+            return;
+        }
+        let range = selection::to_index(buffer, start)..selection::to_index(buffer, end);
+        find_replace_state.find_input.set_text(&buffer.slice(range).to_string());
+        cursor.clear_selection();
+        cursor.position = start;
+        self.perform_find();
+    }
+
+    /// Limit the search to the block around the cursor, or search the whole
+    /// file again. Returns false when the cursor is in no block.
+    pub fn toggle_find_in_block(&mut self) -> bool {
+        let language = self.language();
+        let Tab::Editor { find_replace_state, buffer, cursor, .. } = self else {
+            return false;
+        };
+        if find_replace_state.block.is_some() {
+            find_replace_state.block = None;
+        } else {
+            let index = selection::to_index(buffer, cursor.position);
+            let mut syntax = SyntaxMap::new(language, buffer);
+            let Some(start) = selection::enclosing_block(buffer, index, &mut syntax) else {
+                return false;
+            };
+            find_replace_state.block = Some(selection::to_position(buffer, start));
         }
+        self.perform_find();
+        true
     }
 
     pub fn start_find_replace(&mut self) {
@@ -301,62 +652,10 @@ impl Tab {
     }
 
     pub fn perform_find(&mut self) {
-        if let Tab::Editor { find_replace_state, buffer, cursor, .. } = self {
-            find_replace_state.matches.clear();
+        self.collect_matches();
+        if let Tab::Editor { find_replace_state, cursor, .. } = self {
             find_replace_state.current_match_index = None;
 
-            if find_replace_state.find_query.is_empty() {
-                return;
-            }
-
-            let query = if find_replace_state.case_sensitive {
-                find_replace_state.find_query.clone()
-            } else {
-                find_replace_state.find_query.to_lowercase()
-            };
-
-            for line_idx in 0..buffer.len_lines() {
-                let line_text = buffer.get_line_text(line_idx);
-                let search_text = if find_replace_state.case_sensitive {
-                    line_text.clone()
-                } else {
-                    line_text.to_lowercase()
-                };
-
-                let mut start = 0;
-                while let Some(match_start) = search_text[start..].find(&query) {
-                    let absolute_start = start + match_start;
-                    let match_end = absolute_start + query.len();
-
-                    if find_replace_state.whole_word {
-                        let is_word_start = absolute_start == 0
-                            || !search_text
-                                .chars()
-                                .nth(absolute_start.saturating_sub(1))
-                                .is_some_and(|c| c.is_alphanumeric() || c == '_');
-                        let is_word_end = match_end >= search_text.len()
-                            || !search_text
-                                .chars()
-                                .nth(match_end)
-                                .is_some_and(|c| c.is_alphanumeric() || c == '_');
-
-                        if is_word_start && is_word_end {
-                            find_replace_state.matches.push(FindMatch {
-                                start: Position::new(line_idx, absolute_start),
-                                end: Position::new(line_idx, match_end),
-                            });
-                        }
-                    } else {
-                        find_replace_state.matches.push(FindMatch {
-                            start: Position::new(line_idx, absolute_start),
-                            end: Position::new(line_idx, match_end),
-                        });
-                    }
-
-                    start = match_end;
-                }
-            }
-
             if !find_replace_state.matches.is_empty() {
                 let cursor_pos = (cursor.position.line, cursor.position.column);
                 for (i, m) in find_replace_state.matches.iter().enumerate() {
@@ -376,6 +675,72 @@ impl Tab {
         }
     }
 
+    /// Recount matches after the buffer changed, without moving the cursor,
+    /// so the find bar's total stays current while it is open
+    pub fn refresh_find_matches(&mut self) {
+        if !matches!(self, Tab::Editor { find_replace_state, .. } if find_replace_state.active) {
+            return;
+        }
+        self.collect_matches();
+        if let Tab::Editor { find_replace_state, .. } = self {
+            let count = find_replace_state.matches.len();
+            find_replace_state.current_match_index = match find_replace_state.current_match_index {
+                _ if count == 0 => None,
+                Some(index) => Some(index.min(count - 1)),
+                None => None,
+            };
+        }
+    }
+
+    fn collect_matches(&mut self) {
+        let _span = crate::perf::span("find");
+        let language = self.language();
+        if let Tab::Editor { find_replace_state, buffer, .. } = self {
+            find_replace_state.matches.clear();
+
+            if find_replace_state.find_input.is_empty() {
+                return;
+            }
+
+            let folding = Folding {
+                ignore_case: !find_replace_state.case_sensitive,
+                ignore_accents: find_replace_state.ignore_accents,
+            };
+            let matcher = Matcher::with_folding(
+                find_replace_state.find_input.text(),
+                folding,
+                find_replace_state.whole_word,
+            );
+
+            // A block that no longer parses as one, after edits, lifts the limit
+            let block = find_replace_state.block.and_then(|start| {
+                let mut syntax = SyntaxMap::new(language, buffer);
+                let start = selection::to_index(buffer, start);
+                selection::block_range(buffer, start, &mut syntax)
+            });
+            let (first, last) = match block {
+                Some((start, end)) => (buffer.char_to_line(start), buffer.char_to_line(end)),
+                None => (0, buffer.len_lines().saturating_sub(1)),
+            };
+
+            for line_idx in first..=last {
+                let line_text = buffer.get_line_text(line_idx);
+                let line_start = buffer.line_to_char(line_idx);
+                for (start, end) in matcher.occurrences(&line_text) {
+                    let outside = block.is_some_and(|(block_start, block_end)| {
+                        line_start + start < block_start || line_start + end > block_end
+                    });
+                    if !outside {
+                        find_replace_state.matches.push(FindMatch {
+                            start: Position::new(line_idx, start),
+                            end: Position::new(line_idx, end),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
     pub fn find_next(&mut self) {
         if let Tab::Editor { find_replace_state, .. } = self {
             if find_replace_state.matches.is_empty() {
@@ -409,13 +774,273 @@ impl Tab {
         }
     }
 
+    /// Move the cursor to the start of a 1-based line and center it in the view
+    pub fn go_to_line(&mut self, line: usize, height: usize) {
+        if let Tab::Editor { cursor, buffer, viewport_offset, .. } = self {
+            let line_count = buffer.len_lines();
+            let line = line.saturating_sub(1).min(line_count.saturating_sub(1));
+            cursor.clear_selection();
+            cursor.move_to(line, 0);
+            viewport_offset.0 = line
+                .saturating_sub(height / 2)
+                .min(Self::max_viewport_line(line_count, height));
+        }
+    }
+
+    /// Grow the selection to the next enclosing word, string, bracket,
+    /// line or block
+    pub fn expand_selection(&mut self) {
+        let language = self.language();
+        if let Tab::Editor { cursor, buffer, selection_steps, .. } = self {
+            let (start, end) = cursor
+                .get_selection()
+                .unwrap_or((cursor.position, cursor.position));
+            // Steps only stack while the selection is the one they made
+            if selection_steps.last().map(|step| step.expanded) != Some((start, end)) {
+                selection_steps.clear();
+            }
+
+            let range = (selection::to_index(buffer, start), selection::to_index(buffer, end));
+            let mut syntax = SyntaxMap::new(language, buffer);
+            if let Some((new_start, new_end)) =
+                selection::expand(buffer, range.0, range.1, &mut syntax)
+            {
+                let previous = cursor.clone();
+                let new_start = selection::to_position(buffer, new_start);
+                let new_end = selection::to_position(buffer, new_end);
+                cursor.selection_start = Some(new_start);
+                cursor.move_to(new_end.line, new_end.column);
+                selection_steps.push(SelectionStep {
+                    previous,
+                    expanded: (new_start, new_end),
+                });
+            }
+        }
+    }
+
+    /// Step back to the selection before the last expand
+    pub fn shrink_selection(&mut self) {
+        if let Tab::Editor { cursor, selection_steps, .. } = self {
+            match selection_steps.pop() {
+                Some(step) if cursor.get_selection() == Some(step.expanded) => {
+                    *cursor = step.previous;
+                }
+                _ => selection_steps.clear(),
+            }
+        }
+    }
+
+    /// Toggle comments on the selected lines, or the cursor line. Returns
+    /// false when the tab's language has no comment syntax.
+    pub fn toggle_comment(&mut self) -> bool {
+        let language = self.language();
+        let Tab::Editor { cursor, buffer, .. } = self else {
+            return false;
+        };
+        let (first, last) = match cursor.get_selection() {
+            // A selection ending at the start of a line leaves that line out
+            Some((start, end)) if end.column == 0 && end.line > start.line => {
+                (start.line, end.line - 1)
+            }
+            Some((start, end)) => (start.line, end.line),
+            None => (cursor.position.line, cursor.position.line),
+        };
+        let lines: Vec<String> = (first..=last).map(|line| buffer.get_line_text(line)).collect();
+        let Some(toggled) = language.toggle_comment(&lines) else {
+            return false;
+        };
+        if toggled == lines {
+            return true;
+        }
+
+        self.save_state();
+        if let Tab::Editor { cursor, buffer, .. } = self {
+            for (offset, text) in toggled.iter().enumerate() {
+                buffer.replace_line(first + offset, text);
+            }
+            // Columns past the indentation move with the text
+            let shift = |position: &mut Position| {
+                if !(first..=last).contains(&position.line) {
+                    return;
+                }
+                let old = &lines[position.line - first];
+                let new = &toggled[position.line - first];
+                let indent = old.chars().take_while(|ch| ch.is_whitespace()).count();
+                if position.column > indent {
+                    let delta = new.chars().count() as isize - old.chars().count() as isize;
+                    let column = (position.column as isize + delta).max(indent as isize) as usize;
+                    position.column = column.min(new.chars().count());
+                }
+            };
+            shift(&mut cursor.position);
+            if let Some(anchor) = &mut cursor.selection_start {
+                shift(anchor);
+            }
+        }
+        self.mark_modified();
+        true
+    }
+
+    /// Sort, align or otherwise rewrite the selected lines, or every line
+    /// when nothing is selected, as one undo step. Returns how many lines
+    /// were transformed and how many are left, None when there is nothing
+    /// to do.
+    pub fn transform_lines(
+        &mut self,
+        transform: impl FnOnce(&mut Vec<String>),
+    ) -> Option<(usize, usize)> {
+        let Tab::Editor { cursor, buffer, .. } = self else {
+            return None;
+        };
+        let (first, last) = match cursor.get_selection() {
+            // A selection ending at the start of a line leaves that line out
+            Some((start, end)) if end.column == 0 && end.line > start.line => {
+                (start.line, end.line - 1)
+            }
+            Some((start, end)) => (start.line, end.line),
+            // The empty line after a final line break isn't one to sort
+            None => match buffer.len_lines() {
+                lines if lines > 1 && buffer.get_line_text(lines - 1).is_empty() => (0, lines - 2),
+                lines => (0, lines - 1),
+            },
+        };
+        let lines: Vec<String> = (first..=last).map(|line| buffer.get_line_text(line)).collect();
+        let mut transformed = lines.clone();
+        transform(&mut transformed);
+        if transformed == lines {
+            return Some((lines.len(), lines.len()));
+        }
+
+        self.save_state();
+        if let Tab::Editor { cursor, buffer, .. } = self {
+            let start = buffer.line_to_char(first);
+            let end = buffer.line_range(last).end;
+            buffer.replace(start..end, &transformed.join("\n"));
+
+            // Keep the transformed lines selected
+            let new_last = first + transformed.len() - 1;
+            if cursor.get_selection().is_some() {
+                cursor.selection_start = Some(Position::new(first, 0));
+                cursor.move_to(new_last, transformed[transformed.len() - 1].chars().count());
+            } else {
+                let line = cursor.position.line.min(new_last);
+                let column = cursor.position.column.min(buffer.get_line_text(line).chars().count());
+                cursor.move_to(line, column);
+            }
+        }
+        self.mark_modified();
+        Some((lines.len(), transformed.len()))
+    }
+
+    /// Type `text` at the cursor in place of the selection, as one undo step
+    pub fn insert_text(&mut self, text: &str) {
+        if !matches!(self, Tab::Editor { .. }) {
+            return;
+        }
+        self.save_state();
+        if let Tab::Editor { cursor, buffer, .. } = self {
+            let (start, end) = cursor
+                .get_selection()
+                .unwrap_or((cursor.position, cursor.position));
+            let start = selection::to_index(buffer, start);
+            buffer.replace(start..selection::to_index(buffer, end), text);
+            let after = selection::to_position(buffer, start + text.chars().count());
+            cursor.clear_selection();
+            cursor.move_to(after.line, after.column);
+        }
+        self.mark_modified();
+    }
+
+    /// Replace the selected text with what `transform` makes of it, as one
+    /// undo step, and select the result. None when nothing is selected.
+    pub fn transform_selection(
+        &mut self,
+        transform: impl FnOnce(&str) -> Result<String, String>,
+    ) -> Option<Result<(), String>> {
+        let Tab::Editor { cursor, buffer, .. } = self else {
+            return None;
+        };
+        let (start, end) = cursor.get_selection().filter(|(start, end)| start != end)?;
+        let (start, end) = (selection::to_index(buffer, start), selection::to_index(buffer, end));
+        let text = buffer.slice(start..end).to_string();
+        let transformed = match transform(&text) {
+            Ok(transformed) => transformed,
+            Err(e) => return Some(Err(e)),
+        };
+        if transformed == text {
+            return Some(Ok(()));
+        }
+
+        self.save_state();
+        if let Tab::Editor { cursor, buffer, .. } = self {
+            buffer.replace(start..end, &transformed);
+            let new_end = selection::to_position(buffer, start + transformed.chars().count());
+            cursor.selection_start = Some(selection::to_position(buffer, start));
+            cursor.move_to(new_end.line, new_end.column);
+        }
+        self.mark_modified();
+        Some(Ok(()))
+    }
+
+    /// Move to the bracket matching the one at the cursor, or to the closing
+    /// bracket around it. An existing selection is extended.
+    pub fn jump_to_matching_bracket(&mut self) {
+        let language = self.language();
+        if let Tab::Editor { cursor, buffer, .. } = self {
+            let index = selection::to_index(buffer, cursor.position);
+            let mut syntax = SyntaxMap::new(language, buffer);
+            if let Some(target) = selection::bracket_jump_target(buffer, index, &mut syntax) {
+                let target = selection::to_position(buffer, target);
+                cursor.move_to(target.line, target.column);
+            }
+        }
+    }
+
+    /// Move to the next (or previous) match of the last search while the
+    /// find bar is closed, as a pager's `n` and `N` do. Returns false when
+    /// nothing matches.
+    pub fn find_again(&mut self, backwards: bool) -> bool {
+        self.collect_matches();
+        let Tab::Editor { find_replace_state, cursor, .. } = self else {
+            return false;
+        };
+        let at = cursor.position;
+        let matches = &find_replace_state.matches;
+        let index = if backwards {
+            matches
+                .iter()
+                .rposition(|m| (m.start.line, m.start.column) < (at.line, at.column))
+                .or(matches.len().checked_sub(1))
+        } else {
+            matches
+                .iter()
+                .position(|m| (m.start.line, m.start.column) > (at.line, at.column))
+                .or((!matches.is_empty()).then_some(0))
+        };
+        find_replace_state.current_match_index = index;
+        cursor.clear_selection();
+        self.jump_to_current_match();
+        index.is_some()
+    }
+
+    /// Scroll the view by `lines`, no further than the last page, taking
+    /// the cursor to its top line the way a pager shows its position
+    pub fn scroll_view(&mut self, lines: isize, height: usize) {
+        if let Tab::Editor { buffer, cursor, viewport_offset, .. } = self {
+            let last_top = buffer.len_lines().saturating_sub(height);
+            let top = viewport_offset.0.saturating_add_signed(lines).min(last_top);
+            viewport_offset.0 = top;
+            cursor.clear_selection();
+            cursor.move_to(top, 0);
+        }
+    }
+
     fn jump_to_current_match(&mut self) {
         if let Tab::Editor { find_replace_state, cursor, .. } = self {
             if let Some(idx) = find_replace_state.current_match_index {
                 if let Some(m) = find_replace_state.matches.get(idx) {
                     cursor.position.line = m.start.line;
                     cursor.position.column = m.start.column;
-                    self.ensure_cursor_visible(40);
                 }
             }
         }
@@ -431,7 +1056,7 @@ impl Tab {
                 
                 if let Some(idx) = find_replace_state.current_match_index {
                     if let Some(m) = find_replace_state.matches.get(idx) {
-                        (true, m.clone(), find_replace_state.replace_query.clone())
+                        (true, m.clone(), find_replace_state.replace_input.text().to_string())
                     } else {
                         return;
                     }
@@ -446,14 +1071,9 @@ impl Tab {
             self.save_state();
             
             if let Tab::Editor { buffer, .. } = self {
-                let line_text = buffer.get_line_text(match_info.start.line);
-
-                let mut new_line = String::new();
-                new_line.push_str(&line_text[..match_info.start.column]);
-                new_line.push_str(&replace_query);
-                new_line.push_str(&line_text[match_info.end.column..]);
-
-                buffer.replace_line(match_info.start.line, &new_line);
+                let start = selection::to_index(buffer, match_info.start);
+                let end = selection::to_index(buffer, match_info.end);
+                buffer.replace(start..end, &replace_query);
             }
             
             self.mark_modified();
@@ -471,7 +1091,7 @@ impl Tab {
                 
                 let mut matches = find_replace_state.matches.clone();
                 matches.reverse();
-                (true, matches, find_replace_state.replace_query.clone())
+                (true, matches, find_replace_state.replace_input.text().to_string())
             }
             Tab::Terminal { .. } => return
         };
@@ -480,15 +1100,11 @@ impl Tab {
             self.save_state();
 
             if let Tab::Editor { buffer, .. } = self {
+                // Last to first, so earlier matches keep their positions
                 for m in matches {
-                    let line_text = buffer.get_line_text(m.start.line);
-
-                    let mut new_line = String::new();
-                    new_line.push_str(&line_text[..m.start.column]);
-                    new_line.push_str(&replace_query);
-                    new_line.push_str(&line_text[m.end.column..]);
-
-                    buffer.replace_line(m.start.line, &new_line);
+                    let start = selection::to_index(buffer, m.start);
+                    let end = selection::to_index(buffer, m.end);
+                    buffer.replace(start..end, &replace_query);
                 }
             }
 
@@ -517,21 +1133,48 @@ impl TabManager {
         manager
     }
 
+    /// Add a tab, or switch to the one already showing its file, which is
+    /// kept if it was a preview
     pub fn add_tab(&mut self, tab: Tab) {
-        if let Some(ref path) = tab.path() {
-            for (index, existing_tab) in self.tabs.iter().enumerate() {
-                if let Some(ref existing_path) = existing_tab.path() {
-                    if existing_path == path {
-                        self.active_index = index;
-                        return;
-                    }
-                }
-            }
+        if let Some(index) = self.position_of_file(&tab) {
+            self.tabs[index].keep();
+            self.active_index = index;
+            return;
         }
         self.tabs.push(tab);
         self.active_index = self.tabs.len() - 1;
     }
 
+    /// Show a file as the preview tab, in place of the current preview if
+    /// there is one. A file already open is only switched to.
+    pub fn open_preview(&mut self, mut tab: Tab) {
+        if let Some(index) = self.position_of_file(&tab) {
+            self.active_index = index;
+            return;
+        }
+        if let Tab::Editor { preview_tab, .. } = &mut tab {
+            *preview_tab = true;
+        }
+        match self.tabs.iter().position(Tab::is_preview_tab) {
+            Some(index) => {
+                self.tabs[index] = tab;
+                self.active_index = index;
+            }
+            None => {
+                self.tabs.push(tab);
+                self.active_index = self.tabs.len() - 1;
+            }
+        }
+    }
+
+    /// Index of the tab already showing the same file as `tab`
+    fn position_of_file(&self, tab: &Tab) -> Option<usize> {
+        let path = canonical_path(tab.path()?);
+        self.tabs.iter().position(|existing| {
+            existing.path().is_some_and(|existing| canonical_path(existing) == path)
+        })
+    }
+
     pub fn close_tab(&mut self, index: usize) -> bool {
         if self.tabs.len() <= 1 {
             return false;
@@ -539,6 +1182,10 @@ impl TabManager {
 
         if index < self.tabs.len() {
             self.tabs.remove(index);
+            // Keep the same tab active when one before it closes
+            if index < self.active_index {
+                self.active_index -= 1;
+            }
             if self.active_index >= self.tabs.len() {
                 self.active_index = self.tabs.len() - 1;
             }
@@ -588,7 +1235,7 @@ impl TabManager {
         if index < self.tabs.len() {
             self.active_index = index;
             if let Some(tab) = self.active_tab_mut() {
-                tab.ensure_cursor_visible(40);
+                tab.ensure_cursor_visible(80, 40);
             }
         }
     }
@@ -642,4 +1289,70 @@ impl Tab {
             Tab::Terminal { .. } => None,
         }
     }
+
+    pub fn indent(&self) -> Option<Indent> {
+        match self {
+            Tab::Editor { indent, .. } => Some(*indent),
+            Tab::Terminal { .. } => None,
+        }
+    }
+
+    /// Switch to the next indentation style offered by the status bar
+    pub fn cycle_indent(&mut self) {
+        if let Tab::Editor { indent, .. } = self {
+            *indent = indent.next();
+        }
+    }
+}
+
+/// The path tabs know a file by. It is canonical so `./src/main.rs` and its
+/// absolute form are the same file; a file not on disk yet resolves through
+/// its folder.
+pub fn canonical_path(path: &Path) -> PathBuf {
+    if let Ok(canonical) = std::fs::canonicalize(path) {
+        return canonical;
+    }
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().unwrap_or_default().join(path)
+    };
+    match (absolute.parent(), absolute.file_name()) {
+        (Some(parent), Some(name)) => std::fs::canonicalize(parent)
+            .map(|parent| parent.join(name))
+            .unwrap_or(absolute),
+        _ => absolute,
+    }
+}
+
+fn file_mtime(path: &Path) -> Option<SystemTime> {
+    crate::vfs::metadata(path).ok()?.modified
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn large_buffers_keep_their_undo_history() {
+        // Bigger than the default 64 MB cap on its own
+        let mut tab = Tab::new("big.txt".to_string());
+        if let Tab::Editor { buffer, .. } = &mut tab {
+            *buffer = RopeBuffer::from_str(&"x".repeat(80 * 1024 * 1024));
+        }
+        for _ in 0..20 {
+            tab.save_state();
+            if let Tab::Editor { buffer, .. } = &mut tab {
+                buffer.insert(0, "edit\n");
+            }
+        }
+        let Tab::Editor { undo_stack, .. } = &tab else {
+            unreachable!();
+        };
+        assert_eq!(undo_stack.len(), 20);
+
+        // A snapshot of other text shares nothing and counts in full
+        let small = RopeBuffer::from_str("abc");
+        assert_eq!(small.unshared_with(&RopeBuffer::from_str("abc")), 3);
+    }
 }
@@ -1,17 +1,22 @@
 /// Tab operations module - consolidates all tab management functionality
 use crate::app::{App, FocusMode};
 use crate::keyboard::EditorCommand;
+use crate::diff;
+use crate::pipe::{self, Pipe};
+use crate::rope_buffer::RopeBuffer;
+use crate::scratch;
 use crate::tab::Tab;
+use crate::tree_view::TreeView;
+use crate::ui::{Modal, ModalAction, ModalButton, ModalKind};
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[allow(dead_code)]
 impl App {
     /// Create a new untitled tab
     pub fn create_new_tab(&mut self) {
         let mut new_tab = Tab::new(format!("untitled-{}", self.tab_manager.len() + 1));
-        if let Tab::Editor { word_wrap, .. } = &mut new_tab {
-            *word_wrap = self.global_word_wrap;
-        }
+        new_tab.apply_view_defaults(self.view_defaults);
         self.tab_manager.add_tab(new_tab);
         self.expand_tree_to_current_file();
         // Focus the editor after creating new tab
@@ -21,6 +26,100 @@ impl App {
         }
     }
 
+    /// Open a scratch buffer: an unnamed tab backed by a file in the scratch
+    /// directory, saved automatically
+    pub fn create_scratch_tab(&mut self) {
+        match scratch::create() {
+            Ok(path) => self.open_file_in_tab(path, ""),
+            Err(e) => self.report_error(format!("Failed to create scratch buffer: {}", e)),
+        }
+    }
+
+    /// Reopen the scratch buffers left from earlier sessions behind the
+    /// active tab. A blank startup tab gives way to them.
+    pub fn restore_scratch_tabs(&mut self) {
+        let paths = scratch::saved();
+        if paths.is_empty() {
+            return;
+        }
+        let blank_start = self.tab_manager.len() == 1
+            && matches!(
+                self.tab_manager.active_tab(),
+                Some(Tab::Editor { path: None, modified: false, .. })
+            );
+        let active = self.tab_manager.active_index();
+
+        for path in paths {
+            match crate::vfs::read_to_string(&path) {
+                Ok(content) => {
+                    let mut tab = Tab::from_file(path, &content);
+                    tab.apply_view_defaults(self.view_defaults);
+                    self.tab_manager.add_tab(tab);
+                }
+                Err(e) => self.report_error(format!(
+                    "Failed to restore scratch buffer {}: {}",
+                    path.display(),
+                    e
+                )),
+            }
+        }
+
+        if blank_start && self.tab_manager.len() > 1 {
+            self.tab_manager.close_tab(0);
+            self.tab_manager.set_active_index(0);
+        } else {
+            self.tab_manager.set_active_index(active);
+        }
+    }
+
+    /// Open a tab with the differences between two editor tabs' current
+    /// contents, saved or not
+    pub fn compare_tabs(&mut self, old: usize, new: usize) {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        let describe = |tab: &Tab| {
+            let label = match tab.path() {
+                Some(path) => path.strip_prefix(&cwd).unwrap_or(path).display().to_string(),
+                None => tab.name().to_string(),
+            };
+            let text = match tab {
+                Tab::Editor { buffer, .. } => Some(buffer.to_string()),
+                Tab::Terminal { .. } => None,
+            };
+            (tab.name().to_string(), label, text)
+        };
+        let (Some(old_tab), Some(new_tab)) =
+            (self.tab_manager.tabs.get(old), self.tab_manager.tabs.get(new))
+        else {
+            return;
+        };
+        let (old_name, old_label, Some(old_text)) = describe(old_tab) else {
+            return;
+        };
+        let (new_name, new_label, Some(new_text)) = describe(new_tab) else {
+            return;
+        };
+
+        match diff::unified(&old_label, &new_label, &old_text, &new_text) {
+            Some(text) => {
+                // The .diff name gets the tab diff colors
+                let mut tab = Tab::new(format!("{} ↔ {}.diff", old_name, new_name));
+                if let Tab::Editor { buffer, .. } = &mut tab {
+                    *buffer = RopeBuffer::from_str(&text);
+                }
+                tab.apply_view_defaults(self.view_defaults);
+                self.tab_manager.add_tab(tab);
+                self.focus_mode = FocusMode::Editor;
+                if let Some(tree_view) = &mut self.tree_view {
+                    tree_view.is_focused = false;
+                }
+            }
+            None => self.set_status_message(
+                format!("{} and {} have the same lines", old_name, new_name),
+                std::time::Duration::from_secs(2),
+            ),
+        }
+    }
+
     /// Create a new terminal tab
     pub fn create_new_terminal(&mut self) {
         let terminal_tab = Tab::new_terminal();
@@ -35,14 +134,117 @@ impl App {
     /// Open a file in a new tab
     pub fn open_file_in_tab(&mut self, path: PathBuf, content: &str) {
         let mut new_tab = Tab::from_file(path, content);
-        if let Tab::Editor { word_wrap, .. } = &mut new_tab {
-            *word_wrap = self.global_word_wrap;
-        }
+        new_tab.apply_view_defaults(self.view_defaults);
         self.tab_manager.add_tab(new_tab);
         self.expand_tree_to_current_file();
         self.handle_command(EditorCommand::FocusEditor);
     }
 
+    /// Show what `f1 sftp://host/path` named: a remote folder becomes the
+    /// tree's root, a remote file opens in a tab
+    pub fn open_remote(&mut self, path: PathBuf) {
+        if crate::vfs::metadata(&path).is_ok_and(|m| m.is_dir) {
+            match TreeView::new(path.clone(), 30) {
+                Ok(tree_view) => self.tree_view = Some(tree_view),
+                Err(e) => self.report_error(format!("Failed to list {}: {}", path.display(), e)),
+            }
+            return;
+        }
+        match crate::vfs::read_to_string(&path) {
+            Ok(content) => {
+                self.tab_manager.tabs.clear();
+                self.open_file_in_tab(path, &content);
+            }
+            Err(e) => self.report_error(format!("Failed to open {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Open a tab that standard input is read into, as `f1 -` does
+    pub fn open_stdin_tab(&mut self) {
+        let mut tab = Tab::new(pipe::TAB_NAME.to_string());
+        tab.apply_view_defaults(self.view_defaults);
+        self.tab_manager.tabs.clear();
+        self.tab_manager.add_tab(tab);
+        self.stdin = Some(Pipe::spawn());
+    }
+
+    /// Append what arrived on standard input. A cursor on the last line
+    /// stays on it, following the new text like `tail -f`.
+    pub fn poll_stdin(&mut self) {
+        let Some(pipe) = &self.stdin else {
+            return;
+        };
+        let (text, ended) = pipe.read();
+        if ended {
+            self.stdin = None;
+        }
+        if text.is_empty() {
+            return;
+        }
+        let height = (self.terminal_size.1 as usize).saturating_sub(2);
+        let tab = self.tab_manager.tabs.iter_mut().find(
+            |tab| matches!(tab, Tab::Editor { name, path: None, .. } if name == pipe::TAB_NAME),
+        );
+        let Some(tab) = tab else {
+            // Closing the tab stops the reading
+            self.stdin = None;
+            return;
+        };
+        if let Tab::Editor { buffer, cursor, .. } = tab {
+            let following = cursor.position.line + 1 >= buffer.len_lines();
+            buffer.insert(buffer.len_chars(), &text);
+            if following {
+                cursor.move_to(buffer.len_lines().saturating_sub(1), 0);
+                cursor.move_to_line_end(buffer);
+            }
+        }
+        tab.update_viewport(self.ui.text_width, height);
+    }
+
+    /// Open the files `f1 FILE` sent from another terminal in this workspace
+    pub fn poll_instance(&mut self) {
+        let Some(listener) = &self.instance else {
+            return;
+        };
+        for path in listener.received() {
+            if let Some(index) = self.tab_index_for(&path) {
+                self.tab_manager.set_active_index(index);
+                self.handle_command(EditorCommand::FocusEditor);
+                continue;
+            }
+            match crate::vfs::read_to_string(&path) {
+                Ok(content) => {
+                    self.open_file_in_tab(path.clone(), &content);
+                    self.set_status_message(
+                        format!("Opened {}", path.display()),
+                        Duration::from_secs(2),
+                    );
+                }
+                Err(e) => self.report_error(format!("Failed to open {}: {}", path.display(), e)),
+            }
+        }
+    }
+
+    /// Show a file in the preview tab, leaving focus where it is
+    pub fn open_preview_tab(&mut self, path: PathBuf) {
+        match crate::vfs::read_to_string(&path) {
+            Ok(content) => {
+                let mut tab = Tab::from_file(path, &content);
+                tab.apply_view_defaults(self.view_defaults);
+                self.tab_manager.open_preview(tab);
+            }
+            Err(e) => self.report_error(format!("Failed to open file: {}", e)),
+        }
+    }
+
+    /// Jump to a 1-based line in the active tab
+    pub fn go_to_line(&mut self, line: usize) {
+        let height = self.terminal_size.1.saturating_sub(2) as usize;
+        if let Some(tab) = self.tab_manager.active_tab_mut() {
+            tab.go_to_line(line, height);
+        }
+    }
+
     /// Switch to the next tab
     pub fn switch_next_tab(&mut self) {
         self.tab_manager.next_tab();
@@ -57,25 +259,51 @@ impl App {
 
     /// Close the current tab with confirmation if modified
     pub fn close_current_tab_with_confirmation(&mut self) {
-        if let Some(tab) = self.tab_manager.active_tab() {
-            let (is_modified, tab_name) = match tab {
-                Tab::Editor { modified, name, .. } => (*modified, name.as_str()),
-                Tab::Terminal { modified, name, .. } => (*modified, name.as_str()),
-            };
-            if is_modified {
+        self.close_tab_with_confirmation(self.tab_manager.active_index());
+    }
+
+    /// Close a tab, asking to save it first if it is modified. Scratch
+    /// buffers ask whether to keep their notes for the next session instead.
+    pub fn close_tab_with_confirmation(&mut self, index: usize) {
+        if let Some(tab) = self.tab_manager.tabs.get(index) {
+            if tab.path().is_some_and(|path| scratch::is_scratch(path)) {
+                let empty = matches!(tab, Tab::Editor { buffer, .. } if buffer.len_chars() == 0);
+                if empty {
+                    self.run_modal_action(ModalAction::DeleteScratchTab(index));
+                    return;
+                }
+                let modal = Modal::new(
+                    ModalKind::Warning,
+                    " Scratch Buffer ",
+                    &format!("Keep '{}' for the next session?", tab.name()),
+                )
+                .with_button(
+                    ModalButton::new("Keep", ModalAction::SaveAndCloseTab(index)).with_hotkey('k'),
+                )
+                .with_button(
+                    ModalButton::new("Delete", ModalAction::DeleteScratchTab(index))
+                        .with_hotkey('d'),
+                )
+                .with_button(ModalButton::new("Cancel", ModalAction::Dismiss).with_hotkey('c'))
+                .with_default(0)
+                .with_cancel(2);
+                self.show_modal(modal);
+                return;
+            }
+            if tab.is_modified() {
                 // Show warning for unsaved changes
-                self.warning_message = Some(format!(
-                    "Tab '{}' has unsaved changes. Close anyway?",
-                    tab_name
-                ));
-                self.pending_close = true;
-                self.warning_selected_button = 0; // Default to "No"
+                let modal = Modal::unsaved_changes(
+                    &format!("Save changes to '{}' before closing?", tab.name()),
+                    ModalAction::SaveAndCloseTab(index),
+                    ModalAction::CloseTab(index),
+                );
+                self.show_modal(modal);
                 return;
             }
         }
 
         // No unsaved changes, close directly
-        if !self.tab_manager.close_current_tab() {
+        if !self.tab_manager.close_tab(index) {
             self.running = false;
         }
     }
@@ -87,41 +315,66 @@ impl App {
 
     /// Check if quitting should show unsaved changes warning
     pub fn check_unsaved_on_quit(&mut self) -> bool {
-        let modified_tabs: Vec<String> = self
+        if let Some(modal) = self.unsaved_quit_modal() {
+            self.show_modal(modal);
+            return true;
+        }
+
+        false
+    }
+
+    /// Prompt shown when quitting with modified tabs. A single tab gets the
+    /// plain Save / Don't Save / Cancel choice; several tabs are listed with
+    /// checkboxes so the user can pick which ones to save.
+    pub fn unsaved_quit_modal(&self) -> Option<Modal> {
+        let modified_tabs: Vec<(usize, String)> = self
             .tab_manager
             .tabs()
             .iter()
-            .filter(|tab| match tab {
-                Tab::Editor { modified, .. } => *modified,
+            .enumerate()
+            .filter(|(_, tab)| match tab {
+                // Scratch buffers are saved on the way out
+                Tab::Editor { modified, path, .. } => {
+                    *modified && !path.as_deref().is_some_and(scratch::is_scratch)
+                }
                 Tab::Terminal { modified, .. } => *modified,
             })
-            .map(|tab| match tab {
-                Tab::Editor { name, .. } => name.clone(),
-                Tab::Terminal { name, .. } => name.clone(),
+            .map(|(index, tab)| {
+                let label = match tab.path() {
+                    Some(path) => path.display().to_string(),
+                    None => tab.display_name().trim_end_matches('*').to_string(),
+                };
+                (index, label)
             })
             .collect();
 
-        if !modified_tabs.is_empty() {
-            // Show warning for unsaved changes
-            let message = if modified_tabs.len() == 1 {
-                format!(
-                    "Tab '{}' has unsaved changes. Quit anyway?",
-                    modified_tabs[0]
-                )
-            } else {
-                format!(
-                    "{} tabs have unsaved changes. Quit anyway?",
-                    modified_tabs.len()
-                )
-            };
-
-            self.warning_message = Some(message);
-            self.pending_quit = true;
-            self.warning_selected_button = 0; // Default to "No"
-            return true;
+        match modified_tabs.len() {
+            0 => None,
+            1 => Some(Modal::unsaved_changes(
+                &format!("Save changes to '{}' before quitting?", modified_tabs[0].1),
+                ModalAction::SaveAllAndQuit,
+                ModalAction::Quit,
+            )),
+            count => {
+                let modal = Modal::new(
+                    ModalKind::Warning,
+                    " Unsaved Changes ",
+                    &format!("{} files have unsaved changes. Save before quitting?", count),
+                );
+                let save = ModalAction::SaveTabsAndQuit(Vec::new());
+                let modal = modified_tabs
+                    .iter()
+                    .fold(modal, |modal, (index, label)| {
+                        modal.with_item(label, true, *index)
+                    })
+                    .with_button(ModalButton::new("Save Selected", save).with_hotkey('s'))
+                    .with_button(ModalButton::new("Don't Save", ModalAction::Quit).with_hotkey('d'))
+                    .with_button(ModalButton::new("Cancel", ModalAction::Dismiss).with_hotkey('c'))
+                    .with_default(0)
+                    .with_cancel(2);
+                Some(modal)
+            }
         }
-
-        false
     }
 
     /// Toggle preview mode for markdown files
@@ -131,12 +384,10 @@ impl App {
         }
     }
 
-    /// Apply word wrap setting to all tabs
-    pub fn apply_word_wrap_to_all_tabs(&mut self) {
+    /// Apply the default display options to all tabs
+    pub fn apply_view_defaults_to_all_tabs(&mut self) {
         for tab in &mut self.tab_manager.tabs {
-            if let Tab::Editor { word_wrap, .. } = tab {
-                *word_wrap = self.global_word_wrap;
-            }
+            tab.apply_view_defaults(self.view_defaults);
         }
     }
 
@@ -144,14 +395,21 @@ impl App {
     pub fn update_current_tab_viewport(&mut self) {
         if let Some(tab) = self.tab_manager.active_tab_mut() {
             let visible_height = self.terminal_size.1.saturating_sub(2) as usize;
-            tab.update_viewport(visible_height);
+            tab.update_viewport(self.ui.text_width, visible_height);
         }
     }
 
-    /// Ensure cursor is visible in current tab
+    /// Ensure cursor is visible in current tab, below the find bar if it is open
     pub fn ensure_cursor_visible(&mut self) {
         if let Some(tab) = self.tab_manager.active_tab_mut() {
-            tab.ensure_cursor_visible(self.terminal_size.1.saturating_sub(2) as usize);
+            let bar_height = match tab {
+                Tab::Editor { find_replace_state, .. } if find_replace_state.active => {
+                    crate::ui::FindBarLayout::height(find_replace_state.is_replace_mode)
+                }
+                _ => 0,
+            };
+            let height = self.terminal_size.1.saturating_sub(2 + bar_height) as usize;
+            tab.ensure_cursor_visible(self.ui.text_width, height);
         }
     }
 
@@ -187,8 +445,8 @@ impl App {
             EditorCommand::PageDown => self.page_down(),
             EditorCommand::TogglePreview => self.toggle_preview_mode(),
             EditorCommand::ToggleWordWrap => {
-                self.global_word_wrap = !self.global_word_wrap;
-                self.apply_word_wrap_to_all_tabs();
+                self.view_defaults.word_wrap = !self.view_defaults.word_wrap;
+                self.apply_view_defaults_to_all_tabs();
             }
             _ => {}
         }
@@ -0,0 +1,270 @@
+//! Color output adapted to what the terminal can display.
+//!
+//! The UI is styled with truecolor `Color::Rgb` values. On terminals that only
+//! understand the 256-color or 16-color palettes, every cell is mapped to the
+//! closest color the terminal supports after the frame has been drawn.
+//!
+//! The `high-contrast` theme is applied the same way, before that mapping:
+//! dark backgrounds turn black, dimmed text is drawn normally, and text is
+//! lightened (or on light backgrounds darkened) until it has the 7:1
+//! contrast WCAG asks for at level AAA, keeping its hue where it can.
+
+use crate::config::{self, Theme};
+use ratatui::{
+    buffer::Buffer,
+    style::{Color, Modifier},
+};
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSupport {
+    TrueColor,
+    Ansi256,
+    Ansi16,
+}
+
+static COLOR_SUPPORT: OnceLock<ColorSupport> = OnceLock::new();
+
+/// Color depth of the terminal, detected once from the environment
+pub fn color_support() -> ColorSupport {
+    *COLOR_SUPPORT.get_or_init(|| {
+        detect(
+            std::env::var("COLORTERM").ok().as_deref(),
+            std::env::var("TERM").ok().as_deref(),
+        )
+    })
+}
+
+/// `COLORTERM=truecolor|24bit` is the de facto truecolor announcement. Without
+/// it, fall back to the terminfo name in `TERM`.
+fn detect(colorterm: Option<&str>, term: Option<&str>) -> ColorSupport {
+    if let Some(colorterm) = colorterm {
+        let colorterm = colorterm.to_ascii_lowercase();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            return ColorSupport::TrueColor;
+        }
+    }
+
+    let term = term.unwrap_or("").to_ascii_lowercase();
+    if term.contains("truecolor") || term.contains("24bit") || term.contains("direct") {
+        ColorSupport::TrueColor
+    } else if term.contains("256") {
+        ColorSupport::Ansi256
+    } else if term.starts_with("xterm")
+        || term.starts_with("tmux")
+        || term.starts_with("screen")
+        || term.starts_with("alacritty")
+        || term.starts_with("foot")
+        || term.starts_with("wezterm")
+        || term.starts_with("kitty")
+    {
+        // Modern emulators that report a generic name still handle 256 colors
+        ColorSupport::Ansi256
+    } else {
+        ColorSupport::Ansi16
+    }
+}
+
+/// Map every cell of a rendered frame to colors the theme and the terminal
+/// call for
+pub fn adapt_buffer(buffer: &mut Buffer) {
+    if config::get().ui.theme == Theme::HighContrast {
+        for cell in buffer.content.iter_mut() {
+            (cell.fg, cell.bg) = high_contrast(cell.fg, cell.bg);
+            cell.modifier.remove(Modifier::DIM);
+        }
+    }
+
+    let support = color_support();
+    if support == ColorSupport::TrueColor {
+        return;
+    }
+
+    for cell in buffer.content.iter_mut() {
+        cell.fg = adapt(cell.fg, support);
+        cell.bg = adapt(cell.bg, support);
+    }
+}
+
+pub fn adapt(color: Color, support: ColorSupport) -> Color {
+    match (support, color) {
+        (ColorSupport::TrueColor, _) => color,
+        (ColorSupport::Ansi256, Color::Rgb(r, g, b)) => Color::Indexed(nearest_256(r, g, b)),
+        (ColorSupport::Ansi16, Color::Rgb(r, g, b)) => nearest_16(r, g, b),
+        (ColorSupport::Ansi16, Color::Indexed(index)) if index >= 16 => {
+            let (r, g, b) = indexed_to_rgb(index);
+            nearest_16(r, g, b)
+        }
+        _ => color,
+    }
+}
+
+/// Levels of the 6x6x6 color cube in the xterm 256-color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn nearest_cube_level(value: u8) -> usize {
+    CUBE_LEVELS
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, level)| (**level as i32 - value as i32).abs())
+        .map(|(index, _)| index)
+        .unwrap_or(0)
+}
+
+fn nearest_256(r: u8, g: u8, b: u8) -> u8 {
+    let (ri, gi, bi) = (
+        nearest_cube_level(r),
+        nearest_cube_level(g),
+        nearest_cube_level(b),
+    );
+    let cube_index = 16 + 36 * ri + 6 * gi + bi;
+    let cube_rgb = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+
+    // Grays are often closer to the 24-step grayscale ramp than to the cube
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let gray_step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray_value = 8 + gray_step * 10;
+    let gray_rgb = (gray_value, gray_value, gray_value);
+
+    if distance((r, g, b), gray_rgb) < distance((r, g, b), cube_rgb) {
+        232 + gray_step
+    } else {
+        cube_index as u8
+    }
+}
+
+/// The 16 ANSI colors with their typical xterm values
+const ANSI_16: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+fn nearest_16(r: u8, g: u8, b: u8) -> Color {
+    ANSI_16
+        .iter()
+        .min_by_key(|(_, rgb)| distance((r, g, b), *rgb))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
+
+fn indexed_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => ANSI_16[index as usize].1,
+        16..=231 => {
+            let i = index - 16;
+            (
+                CUBE_LEVELS[(i / 36) as usize],
+                CUBE_LEVELS[((i / 6) % 6) as usize],
+                CUBE_LEVELS[(i % 6) as usize],
+            )
+        }
+        _ => {
+            let value = 8 + (index - 232) * 10;
+            (value, value, value)
+        }
+    }
+}
+
+/// Contrast ratio text needs in the high-contrast theme
+const MIN_CONTRAST: f64 = 7.0;
+
+/// Backgrounds darker than this luminance become black
+const DARK_BACKGROUND: f64 = 0.05;
+
+/// Colors of a cell with enough contrast. The terminal's default colors are
+/// taken to be white text on black.
+fn high_contrast(fg: Color, bg: Color) -> (Color, Color) {
+    let mut bg_rgb = to_rgb(bg).unwrap_or((0, 0, 0));
+    if luminance(bg_rgb) < DARK_BACKGROUND {
+        bg_rgb = (0, 0, 0);
+    }
+    let fg_rgb = to_rgb(fg).unwrap_or((255, 255, 255));
+
+    // Move the text toward white or black, whichever the background is further from
+    let toward = if luminance(bg_rgb) < 0.18 { 255.0 } else { 0.0 };
+    let mut shown = fg_rgb;
+    for step in 0..=10 {
+        let t = step as f64 / 10.0;
+        let mix = |c: u8| (c as f64 + (toward - c as f64) * t).round() as u8;
+        shown = (mix(fg_rgb.0), mix(fg_rgb.1), mix(fg_rgb.2));
+        if contrast(shown, bg_rgb) >= MIN_CONTRAST {
+            break;
+        }
+    }
+    (Color::Rgb(shown.0, shown.1, shown.2), Color::Rgb(bg_rgb.0, bg_rgb.1, bg_rgb.2))
+}
+
+/// RGB value of a color; None for the terminal's default
+fn to_rgb(color: Color) -> Option<(u8, u8, u8)> {
+    match color {
+        Color::Reset => None,
+        Color::Rgb(r, g, b) => Some((r, g, b)),
+        Color::Indexed(index) => Some(indexed_to_rgb(index)),
+        named => ANSI_16
+            .iter()
+            .find(|(color, _)| *color == named)
+            .map(|(_, rgb)| *rgb),
+    }
+}
+
+/// Relative luminance, as WCAG defines it
+fn luminance((r, g, b): (u8, u8, u8)) -> f64 {
+    let linear = |c: u8| {
+        let c = c as f64 / 255.0;
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * linear(r) + 0.7152 * linear(g) + 0.0722 * linear(b)
+}
+
+fn contrast(a: (u8, u8, u8), b: (u8, u8, u8)) -> f64 {
+    let (la, lb) = (luminance(a), luminance(b));
+    (la.max(lb) + 0.05) / (la.min(lb) + 0.05)
+}
+
+/// Squared distance weighted toward green, which the eye is most sensitive to
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let dr = a.0 as i32 - b.0 as i32;
+    let dg = a.1 as i32 - b.1 as i32;
+    let db = a.2 as i32 - b.2 as i32;
+    (2 * dr * dr + 4 * dg * dg + 3 * db * db) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn high_contrast_reaches_aaa() {
+        let rgb = |color| to_rgb(color).unwrap();
+        // Comments on the status bar gray: black background, lighter text
+        let (fg, bg) = high_contrast(Color::Rgb(120, 120, 120), Color::Rgb(40, 40, 40));
+        assert_eq!(bg, Color::Rgb(0, 0, 0));
+        assert!(contrast(rgb(fg), rgb(bg)) >= MIN_CONTRAST);
+        // Text on the yellow match highlight stays dark
+        let (fg, bg) = high_contrast(Color::DarkGray, Color::Yellow);
+        assert_eq!(bg, Color::Rgb(205, 205, 0));
+        assert!(contrast(rgb(fg), rgb(bg)) >= MIN_CONTRAST);
+        assert_eq!(
+            high_contrast(Color::Reset, Color::Reset),
+            (Color::Rgb(255, 255, 255), Color::Rgb(0, 0, 0))
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! TODO comments across the workspace, for the Alt+T panel. A background
+//! job walks the same files as project search and collects each line with
+//! one of the `[todo] patterns` on it. Tags match case and whole words and
+//! must start a comment, right after its `//`, `#`, `--` or the like, so
+//! `todo_list`, "TODO" in a string or a TODO mentioned mid-sentence are left
+//! out.
+
+use crate::config;
+use crate::jobs::JobContext;
+use crate::project_search::{
+    add_matches, for_each_file, Filters, Matcher, SearchMatch, SharedResults, MAX_PREVIEW_LEN,
+};
+use std::path::Path;
+
+/// The tags to look for
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tags(Vec<Matcher>);
+
+impl Tags {
+    pub fn from_config() -> Self {
+        Self::new(&config::get().todo.patterns)
+    }
+
+    pub fn new(patterns: &[String]) -> Self {
+        Self(
+            patterns
+                .iter()
+                .filter(|pattern| !pattern.is_empty())
+                .map(|pattern| Matcher::new(pattern, true, true))
+                .collect(),
+        )
+    }
+
+    /// Character range of the first tag on a line that starts a comment
+    pub fn find(&self, line: &str) -> Option<(usize, usize)> {
+        let chars: Vec<char> = line.chars().collect();
+        let starts_comment = |&(start, _): &(usize, usize)| {
+            let before = chars[..start].iter().rev().find(|ch| !ch.is_whitespace());
+            before.is_some_and(|ch| COMMENT_MARKS.contains(*ch))
+        };
+        self.0
+            .iter()
+            .filter_map(|matcher| matcher.occurrences(line).into_iter().find(starts_comment))
+            .min()
+    }
+}
+
+/// Characters that end a comment marker in the common languages
+const COMMENT_MARKS: &str = "/#*-;%!";
+
+/// Collect the tagged lines under `root` into `results`, one match per line
+pub fn run(
+    root: &Path,
+    tags: &Tags,
+    results: &SharedResults,
+    job: &JobContext,
+) -> Result<String, String> {
+    let max_results = config::get().search.max_results;
+    for_each_file(root, &Filters::default(), job, |relative, content| {
+        let found: Vec<SearchMatch> = content
+            .lines()
+            .enumerate()
+            .filter_map(|(line, text)| {
+                let (column, _) = tags.find(text)?;
+                Some(SearchMatch {
+                    path: relative.to_path_buf(),
+                    line,
+                    column,
+                    text: text.chars().take(MAX_PREVIEW_LEN).collect(),
+                })
+            })
+            .collect();
+        add_matches(results, found, max_results, job)
+    });
+
+    let mut results = results.lock();
+    results.done = true;
+    let files = results
+        .matches
+        .iter()
+        .enumerate()
+        .filter(|(index, found)| *index == 0 || results.matches[index - 1].path != found.path)
+        .count();
+    Ok(format!(
+        "{}{} TODOs in {} files",
+        if results.truncated { "First " } else { "" },
+        results.matches.len(),
+        files
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_first_tag() {
+        let tags = Tags::new(&["TODO".to_string(), "FIXME".to_string(), String::new()]);
+        assert_eq!(tags.find("// FIXME: and TODO"), Some((3, 8)));
+        assert_eq!(tags.find("    # TODO(ana) split this"), Some((6, 10)));
+        assert_eq!(tags.find("x = 1  # TODO"), Some((9, 13)));
+        assert_eq!(tags.find("/* the TODO list */"), None);
+        assert_eq!(tags.find("let todo = \"TODO\"; // Todo: later"), None);
+    }
+}
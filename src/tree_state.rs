@@ -0,0 +1,49 @@
+//! How the file tree was left: the folders open, the selected entry and the
+//! scroll position. Kept per workspace in `<state dir>/tree.json`, so the
+//! tree reopens the way it was.
+
+use crate::recovery::{state_dir, workspace_key};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TreeState {
+    pub expanded: Vec<PathBuf>, // Parents before their children
+    pub selected: Option<PathBuf>,
+    pub scroll_offset: usize,
+}
+
+fn tree_file() -> PathBuf {
+    state_dir().join("tree.json")
+}
+
+/// Every workspace's tree, keyed by the workspace path
+fn read_all() -> BTreeMap<String, TreeState> {
+    fs::read_to_string(tree_file())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+impl TreeState {
+    /// The tree as the current workspace left it, if it was saved
+    pub fn load() -> Option<Self> {
+        read_all().remove(&workspace_key())
+    }
+
+    /// Remember the tree for the current workspace, keeping the others
+    pub fn save(&self) -> io::Result<()> {
+        let mut all = read_all();
+        all.insert(workspace_key(), self.clone());
+        let path = tree_file();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(&all).map_err(io::Error::other)?;
+        fs::write(path, content)
+    }
+}
@@ -1,15 +1,26 @@
+use crate::archive;
+use crate::config::{self, TreeSort};
 use crate::file_icons;
+use crate::fold::Folding;
 use crate::gitignore::GitIgnore;
+use crate::keyboard;
+use crate::scroll::ScrollAcceleration;
+use crate::tree_state::TreeState;
 use crate::ui::scrollbar::{ScrollbarState, VerticalScrollbar};
+use crate::ui::TextInput;
+use crate::vfs::{self, DirEntry};
+use crate::walk::{self, WalkGuard};
+use crossterm::event::KeyEvent;
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
     style::{Color, Modifier, Style},
     widgets::Widget,
 };
-use std::fs;
+use std::cmp::Ordering;
 use std::path::{Path, PathBuf};
-use std::time::Instant;
+use std::sync::{Arc, Mutex};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 #[derive(Debug, Clone)]
 pub struct TreeNode {
@@ -20,18 +31,162 @@ pub struct TreeNode {
     pub children: Vec<TreeNode>,
     pub depth: usize,
     pub is_gitignored: bool,
+    /// The entry is a symlink; `is_dir` describes what it points at
+    pub is_symlink: bool,
+    /// Children listed before a "more" row; the rest wait until it is opened
+    pub shown: usize,
+    /// The children are being listed by a background job
+    pub loading: bool,
+    /// "Loading…" or "N more…" row after the listed children
+    pub status_row: Option<Box<TreeNode>>,
+    /// Set on status rows, which stand for their folder's unlisted entries
+    pub placeholder: Option<Placeholder>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Placeholder {
+    Loading,
+    More,
+}
+
+/// Folders with more entries than this are listed on a worker thread
+const LARGE_DIRECTORY: usize = 2000;
+
+/// Children of a loaded folder, with ignored ones flagged, shared with the
+/// job listing it
+type ScanSlot = Arc<Mutex<Option<Vec<TreeNode>>>>;
+
+/// Entries of a folder as nodes at `depth`, in the configured order
+pub fn scan_directory(path: &Path, depth: usize) -> Result<Vec<TreeNode>, std::io::Error> {
+    let settings = &config::get().tree;
+    let mut entries = Vec::new();
+    for entry in vfs::read_dir(path)? {
+        let node = TreeNode::from_entry(&entry, depth);
+        // Only the time and size orders need to look past the name
+        let metadata = match settings.sort {
+            TreeSort::Modified | TreeSort::Size => vfs::metadata(&entry.path).ok(),
+            TreeSort::Name | TreeSort::Extension => None,
+        };
+        let modified = metadata.and_then(|m| m.modified);
+        let size = metadata.filter(|_| !node.is_dir).map_or(0, |m| m.len);
+        entries.push((node, modified, size));
+    }
+
+    entries.sort_by(|(a, a_modified, a_size), (b, b_modified, b_size)| {
+        let folders = if settings.directories_first {
+            b.is_dir.cmp(&a.is_dir)
+        } else {
+            Ordering::Equal
+        };
+        let order = match settings.sort {
+            TreeSort::Name => Ordering::Equal,
+            TreeSort::Modified => b_modified.cmp(a_modified),
+            TreeSort::Size => b_size.cmp(a_size),
+            TreeSort::Extension => natural_cmp(extension(&a.name), extension(&b.name)),
+        };
+        folders
+            .then(order)
+            .then_with(|| natural_cmp(&a.name, &b.name))
+    });
+    Ok(entries.into_iter().map(|(node, _, _)| node).collect())
+}
+
+/// Extension of a file name, empty for none and for dotfiles like ".env"
+fn extension(name: &str) -> &str {
+    match name.rfind('.') {
+        Some(dot) if dot > 0 => &name[dot + 1..],
+        _ => "",
+    }
+}
+
+/// Compare names ignoring case, with runs of digits compared by value so
+/// "file2" sorts before "file10". Names that only differ in case or leading
+/// zeros fall back to plain order, so the result is stable.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut left, mut right) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        match (left.peek().copied(), right.peek().copied()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(l), Some(r)) if l.is_ascii_digit() && r.is_ascii_digit() => {
+                let take_number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(ch) = chars.next_if(char::is_ascii_digit) {
+                        digits.push(ch);
+                    }
+                    digits.trim_start_matches('0').to_string()
+                };
+                let (l, r) = (take_number(&mut left), take_number(&mut right));
+                let order = l.len().cmp(&r.len()).then_with(|| l.cmp(&r));
+                if order != Ordering::Equal {
+                    return order;
+                }
+            }
+            (Some(l), Some(r)) => {
+                let order = l.to_lowercase().cmp(r.to_lowercase());
+                if order != Ordering::Equal {
+                    return order;
+                }
+                left.next();
+                right.next();
+            }
+        }
+    }
+}
+
+/// A large folder to list in the background. `run` does the work on the
+/// job's thread; the tree picks the result up in `finish_scans`.
+pub struct DirectoryScan {
+    pub path: PathBuf,
+    depth: usize,
+    gitignore: GitIgnore,
+    slot: ScanSlot,
+}
+
+impl DirectoryScan {
+    pub fn run(self) -> Result<String, String> {
+        let (children, result) = match scan_directory(&self.path, self.depth) {
+            Ok(mut children) => {
+                for child in &mut children {
+                    child.is_gitignored = self.gitignore.is_ignored(&child.path);
+                }
+                let message =
+                    format!("Listed {} entries in {}", children.len(), self.path.display());
+                (children, Ok(message))
+            }
+            Err(e) => (
+                Vec::new(),
+                Err(format!("Failed to list {}: {}", self.path.display(), e)),
+            ),
+        };
+        // Filled even on failure, so the folder stops showing as loading
+        *self.slot.lock().unwrap_or_else(|e| e.into_inner()) = Some(children);
+        result
+    }
 }
 
 impl TreeNode {
     pub fn new(path: PathBuf, depth: usize) -> Self {
+        let is_dir = vfs::metadata(&path).is_ok_and(|m| m.is_dir);
+        let is_symlink = walk::is_symlink(&path);
+        Self::with_kind(path, depth, is_dir, is_symlink)
+    }
+
+    /// A node for a listed entry, without looking at the disk again. An
+    /// archive is mounted and becomes a folder to expand.
+    pub fn from_entry(entry: &DirEntry, depth: usize) -> Self {
+        let is_dir = entry.is_dir || archive::mount(&entry.path);
+        Self::with_kind(entry.path.clone(), depth, is_dir, entry.is_symlink)
+    }
+
+    fn with_kind(path: PathBuf, depth: usize, is_dir: bool, is_symlink: bool) -> Self {
         let name = path
             .file_name()
             .and_then(|n| n.to_str())
             .unwrap_or("")
             .to_string();
 
-        let is_dir = path.is_dir();
-
         Self {
             path,
             name,
@@ -40,32 +195,66 @@ impl TreeNode {
             children: Vec::new(),
             depth,
             is_gitignored: false, // Will be set later when we have gitignore info
+            is_symlink,
+            shown: 0,
+            loading: false,
+            status_row: None,
+            placeholder: None,
         }
     }
 
     pub fn load_children(&mut self) -> Result<(), std::io::Error> {
-        if !self.is_dir || !self.children.is_empty() {
+        if !self.is_dir || !self.children.is_empty() || self.loading {
             return Ok(());
         }
 
-        let mut entries = Vec::new();
-        for entry in fs::read_dir(&self.path)? {
-            let entry = entry?;
-            let path = entry.path();
+        let children = scan_directory(&self.path, self.depth + 1)?;
+        self.set_children(children);
+        Ok(())
+    }
 
-            let node = TreeNode::new(path, self.depth + 1);
-            entries.push(node);
-        }
+    /// Take freshly listed children, showing the first page of them
+    fn set_children(&mut self, children: Vec<TreeNode>) {
+        self.children = children;
+        self.loading = false;
+        self.shown = config::get().tree.page_size.max(1);
+        self.update_status_row();
+    }
 
-        // Sort: directories first, then files, both alphabetically
-        entries.sort_by(|a, b| match (a.is_dir, b.is_dir) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-        });
+    /// List the next page of children
+    pub fn show_more(&mut self) {
+        self.shown = self
+            .shown
+            .saturating_add(config::get().tree.page_size.max(1));
+        self.update_status_row();
+    }
 
-        self.children = entries;
-        Ok(())
+    fn update_status_row(&mut self) {
+        let hidden = self.children.len().saturating_sub(self.shown);
+        let (placeholder, name) = if self.loading {
+            (Placeholder::Loading, "Loading…".to_string())
+        } else if hidden > 0 {
+            (Placeholder::More, format!("{} more…", hidden))
+        } else {
+            self.status_row = None;
+            return;
+        };
+        self.status_row = Some(Box::new(TreeNode {
+            name,
+            is_dir: false,
+            is_symlink: false,
+            depth: self.depth + 1,
+            placeholder: Some(placeholder),
+            ..TreeNode::new(self.path.clone(), 0)
+        }));
+    }
+
+    /// The children drawn in the tree: the listed ones, then the status row
+    fn listed_children(&self) -> impl Iterator<Item = &TreeNode> {
+        self.children
+            .iter()
+            .take(self.shown)
+            .chain(self.status_row.as_deref())
     }
 
     pub fn toggle_expand(&mut self) -> Result<(), std::io::Error> {
@@ -82,6 +271,44 @@ impl TreeNode {
         Ok(())
     }
 
+    fn collapse_all(&mut self) {
+        self.is_expanded = false;
+        for child in &mut self.children {
+            child.collapse_all();
+        }
+    }
+
+    /// Expand the folders at `level`, below already expanded ones. Ignored
+    /// and symlinked folders stay closed. Returns false once `budget` entries
+    /// are used up.
+    fn expand_level(&mut self, level: usize, gitignore: &GitIgnore, budget: &mut usize) -> bool {
+        if !self.is_dir || self.is_gitignored || self.is_symlink || self.depth > level {
+            return true;
+        }
+        if self.depth < level {
+            if self.is_expanded {
+                for child in &mut self.children {
+                    if !child.expand_level(level, gitignore, budget) {
+                        return false;
+                    }
+                }
+            }
+            return true;
+        }
+
+        if *budget == 0 {
+            return false;
+        }
+        if self.load_children().is_ok() {
+            for child in &mut self.children {
+                child.is_gitignored = gitignore.is_ignored(&child.path);
+            }
+            *budget = budget.saturating_sub(self.children.len());
+            self.is_expanded = true;
+        }
+        true
+    }
+
     pub fn expand_path(&mut self, target_path: &Path) -> Result<bool, std::io::Error> {
         // If this node's path is a prefix of the target path, expand it
         if target_path.starts_with(&self.path) && self.is_dir {
@@ -90,9 +317,15 @@ impl TreeNode {
                 self.is_expanded = true;
             }
 
-            // Try to expand children
-            for child in &mut self.children {
-                if child.expand_path(target_path)? {
+            // Try to expand children, listing as many pages as it takes to
+            // reach the one on the way
+            for index in 0..self.children.len() {
+                if target_path.starts_with(&self.children[index].path) {
+                    while self.shown <= index {
+                        self.show_more();
+                    }
+                }
+                if self.children[index].expand_path(target_path)? {
                     return Ok(true);
                 }
             }
@@ -110,8 +343,9 @@ impl TreeNode {
 pub struct TreeView {
     pub root: TreeNode,
     pub selected_index: usize,
+    pub hovered_index: Option<usize>, // Row under the mouse
     pub scroll_offset: usize,
-    pub search_query: String,
+    pub search_input: TextInput,
     pub is_searching: bool,
     pub filtered_items: Vec<(usize, TreeNode)>, // (original_index, node)
     pub width: u16,
@@ -119,8 +353,16 @@ pub struct TreeView {
     gitignore: GitIgnore,
     pub just_refreshed: bool,              // Flag for visual feedback
     pub clipboard: Option<ClipboardEntry>, // For copy/cut/paste operations
-    last_scroll_time: Option<Instant>,     // For scroll acceleration
-    scroll_acceleration: usize,            // Current scroll speed multiplier
+    pub rename: Option<InlineRename>,      // Row being renamed in place
+    scans: Vec<(PathBuf, ScanSlot)>,       // Large folders being listed in the background
+    pub wheel: ScrollAcceleration,
+}
+
+/// A tree row turned into an editable name field
+#[derive(Debug)]
+pub struct InlineRename {
+    pub path: PathBuf,
+    pub input: TextInput,
 }
 
 #[derive(Debug, Clone)]
@@ -139,8 +381,9 @@ impl TreeView {
         let mut tree_view = Self {
             root,
             selected_index: 0,
+            hovered_index: None,
             scroll_offset: 0,
-            search_query: String::new(),
+            search_input: TextInput::new(),
             is_searching: false,
             filtered_items: Vec::new(),
             width,
@@ -148,8 +391,9 @@ impl TreeView {
             gitignore,
             just_refreshed: false,
             clipboard: None,
-            last_scroll_time: None,
-            scroll_acceleration: 1,
+            rename: None,
+            scans: Vec::new(),
+            wheel: ScrollAcceleration::default(),
         };
 
         // Update gitignore status for all nodes
@@ -158,6 +402,12 @@ impl TreeView {
         Ok(tree_view)
     }
 
+    /// Read the `.gitignore` files again and mark the loaded nodes by them
+    pub fn reload_gitignore(&mut self) {
+        self.gitignore.clear();
+        self.update_gitignore_status();
+    }
+
     fn update_gitignore_status(&mut self) {
         Self::update_node_gitignore_status_recursive(&self.gitignore, &mut self.root);
     }
@@ -169,50 +419,95 @@ impl TreeView {
         }
     }
 
-    pub fn toggle_selected(&mut self) -> Result<(), std::io::Error> {
+    /// Open or close the selected folder, or list more of a long one. A
+    /// large folder opens right away and comes back as a scan to run in the
+    /// background.
+    pub fn toggle_selected(&mut self) -> Result<Option<DirectoryScan>, std::io::Error> {
         let visible_items = self.get_visible_items();
-        if let Some(item) = visible_items.get(self.selected_index) {
-            let path = item.path.clone();
-            // Find the actual node in the tree and toggle it
-            self.toggle_node_at_path(&path)?;
-            // Update gitignore status for any newly loaded nodes
-            self.update_gitignore_status();
+        let Some(item) = visible_items.get(self.selected_index) else {
+            return Ok(None);
+        };
+        let path = item.path.clone();
+        let placeholder = item.placeholder;
+        let Some(node) = Self::node_at_path(&mut self.root, &path) else {
+            return Ok(None);
+        };
+
+        match placeholder {
+            Some(Placeholder::More) => node.show_more(),
+            Some(Placeholder::Loading) => {}
+            None if !node.is_dir => {}
+            None if node.is_expanded => node.is_expanded = false,
+            None if node.children.is_empty()
+                && !node.loading
+                && vfs::count_entries(&node.path, LARGE_DIRECTORY + 1)? > LARGE_DIRECTORY =>
+            {
+                node.loading = true;
+                node.is_expanded = true;
+                node.update_status_row();
+                let slot = ScanSlot::default();
+                self.scans.push((path.clone(), slot.clone()));
+                return Ok(Some(DirectoryScan {
+                    path,
+                    depth: node.depth + 1,
+                    gitignore: self.gitignore.clone(),
+                    slot,
+                }));
+            }
+            None => {
+                let loaded = node.children.is_empty();
+                node.toggle_expand()?;
+                // Flag the ignored ones among newly loaded children
+                if loaded {
+                    for child in &mut node.children {
+                        child.is_gitignored = self.gitignore.is_ignored(&child.path);
+                    }
+                }
+            }
         }
-        Ok(())
+        Ok(None)
     }
 
-    fn toggle_node_at_path(&mut self, path: &Path) -> Result<(), std::io::Error> {
-        Self::toggle_node_recursive(&mut self.root, path)
+    /// Put the children listed by finished background scans in place
+    pub fn finish_scans(&mut self) {
+        let mut pending = Vec::new();
+        for (path, slot) in std::mem::take(&mut self.scans) {
+            let children = slot.lock().unwrap_or_else(|e| e.into_inner()).take();
+            match children {
+                Some(children) => {
+                    // The folder may be gone after a refresh
+                    if let Some(node) = Self::node_at_path(&mut self.root, &path) {
+                        if node.loading {
+                            node.set_children(children);
+                        }
+                    }
+                }
+                None => pending.push((path, slot)),
+            }
+        }
+        self.scans = pending;
     }
 
-    fn toggle_node_recursive(
-        node: &mut TreeNode,
-        target_path: &Path,
-    ) -> Result<(), std::io::Error> {
+    fn node_at_path<'a>(node: &'a mut TreeNode, target_path: &Path) -> Option<&'a mut TreeNode> {
         if node.path == target_path {
-            node.toggle_expand()?;
-            return Ok(());
+            return Some(node);
         }
-
-        for child in &mut node.children {
-            if target_path.starts_with(&child.path) {
-                Self::toggle_node_recursive(child, target_path)?;
-                return Ok(());
-            }
-        }
-
-        Ok(())
+        node.children
+            .iter_mut()
+            .find(|child| target_path.starts_with(&child.path))
+            .and_then(|child| Self::node_at_path(child, target_path))
     }
 
+    /// Create a file under `parent_path`. `filename` may be a relative path
+    /// such as `sub/dir/file.rs`; missing directories are created on the way.
     pub fn create_file(
         &mut self,
         parent_path: &Path,
         filename: &str,
     ) -> Result<PathBuf, std::io::Error> {
-        let file_path = parent_path.join(filename);
+        let file_path = parent_path.join(Self::relative_new_path(filename)?);
 
-        // Create the file
-        std::fs::File::create(&file_path)?;
+        vfs::create(&file_path, false)?;
 
         // Refresh the tree
         self.refresh_directory(parent_path)?;
@@ -220,15 +515,22 @@ impl TreeView {
         Ok(file_path)
     }
 
+    /// Create a directory under `parent_path`, including any intermediate
+    /// directories in a nested name like `a/b/c`
     pub fn create_directory(
         &mut self,
         parent_path: &Path,
         dirname: &str,
     ) -> Result<PathBuf, std::io::Error> {
-        let dir_path = parent_path.join(dirname);
+        let dir_path = parent_path.join(Self::relative_new_path(dirname)?);
 
-        // Create the directory
-        std::fs::create_dir(&dir_path)?;
+        if vfs::exists(&dir_path) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                "already exists",
+            ));
+        }
+        vfs::create(&dir_path, true)?;
 
         // Refresh the tree
         self.refresh_directory(parent_path)?;
@@ -236,12 +538,28 @@ impl TreeView {
         Ok(dir_path)
     }
 
-    pub fn delete_file_or_directory(&mut self, path: &Path) -> Result<(), std::io::Error> {
-        if path.is_dir() {
-            std::fs::remove_dir_all(path)?;
-        } else {
-            std::fs::remove_file(path)?;
+    /// Validate a name typed into the New File/Folder prompt. Nested paths are
+    /// allowed, but not absolute ones or `..`, so entries stay under the target.
+    fn relative_new_path(name: &str) -> Result<PathBuf, std::io::Error> {
+        let invalid = |message: &str| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, message.to_string())
+        };
+
+        let path = PathBuf::from(name.trim_end_matches('/'));
+        if name.trim().is_empty() {
+            return Err(invalid("name is empty"));
+        }
+        for component in path.components() {
+            match component {
+                std::path::Component::Normal(_) | std::path::Component::CurDir => {}
+                _ => return Err(invalid("name must be a relative path inside the folder")),
+            }
         }
+        Ok(path)
+    }
+
+    pub fn delete_file_or_directory(&mut self, path: &Path) -> Result<(), std::io::Error> {
+        vfs::remove(path)?;
 
         // Refresh the parent directory
         if let Some(parent) = path.parent() {
@@ -266,7 +584,7 @@ impl TreeView {
         let new_path = parent.join(new_name);
 
         // Rename the file/directory
-        std::fs::rename(old_path, &new_path)?;
+        vfs::rename(old_path, &new_path)?;
 
         // Refresh the parent directory
         self.refresh_directory(parent)?;
@@ -325,7 +643,8 @@ impl TreeView {
             return;
         }
 
-        // Apply gitignore to root children
+        // Apply gitignore to root children, picking up edited .gitignore files
+        self.gitignore.clear();
         for child in &mut self.root.children {
             child.is_gitignored = self.gitignore.is_ignored(&child.path);
         }
@@ -396,8 +715,42 @@ impl TreeView {
         }
     }
 
+    /// Open folders, selection and scroll, to reopen the tree with later
+    pub fn state(&self) -> TreeState {
+        let mut expanded = Vec::new();
+        self.collect_expanded_paths(&self.root, &mut expanded);
+        TreeState {
+            expanded,
+            selected: self.get_selected_item().map(|item| item.path.clone()),
+            scroll_offset: self.scroll_offset,
+        }
+    }
+
+    /// Reopen the saved folders that still exist and select the saved entry
+    pub fn restore_state(&mut self, state: &TreeState) {
+        for path in state.expanded.iter().filter(|path| path.is_dir()) {
+            Self::expand_path_recursive_static(path, &mut self.root, &self.gitignore);
+        }
+        let count = self.get_visible_items().len();
+        self.scroll_offset = state.scroll_offset.min(count.saturating_sub(1));
+        if let Some(path) = &state.selected {
+            self.restore_selection(path);
+        }
+    }
+
+    /// Index of the visible item drawn at screen row `row`, for a tree whose
+    /// area starts at row `top`
+    pub fn index_at_row(&self, row: u16, top: u16) -> Option<usize> {
+        let first_row = top + if self.is_searching { 1 } else { 0 };
+        if row < first_row {
+            return None;
+        }
+        let index = self.scroll_offset + (row - first_row) as usize;
+        (index < self.get_visible_items().len()).then_some(index)
+    }
+
     pub fn get_visible_items(&self) -> Vec<&TreeNode> {
-        if self.is_searching && !self.search_query.is_empty() {
+        if self.is_searching && !self.search_input.is_empty() {
             return self.filtered_items.iter().map(|(_, node)| node).collect();
         }
 
@@ -414,7 +767,7 @@ impl TreeView {
         }
 
         if node.is_expanded {
-            for child in &node.children {
+            for child in node.listed_children() {
                 self.collect_visible_items(child, items);
             }
         }
@@ -466,36 +819,125 @@ impl TreeView {
         Ok(())
     }
 
+    /// Turn the row for `path` into a name field, with the stem selected so
+    /// typing keeps the extension. Returns false if the row can't be shown.
+    pub fn start_rename(&mut self, path: &Path) -> bool {
+        if path == self.root.path || self.expand_to_file(path).is_err() {
+            return false;
+        }
+        let shown = self
+            .get_visible_items()
+            .get(self.selected_index)
+            .is_some_and(|item| item.path == path);
+        if !shown {
+            return false;
+        }
+
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let stem_len = if path.is_dir() {
+            name.chars().count()
+        } else {
+            path.file_stem()
+                .map(|s| s.to_string_lossy().chars().count())
+                .unwrap_or(0)
+        };
+        let mut input = TextInput::new();
+        input.set_text(&name);
+        input.select_range(0, stem_len);
+        self.rename = Some(InlineRename {
+            path: path.to_path_buf(),
+            input,
+        });
+        true
+    }
+
+    /// Collapse every folder, keeping the selection on the top-level entry
+    /// that contained it
+    pub fn collapse_all(&mut self) {
+        let selected = self.get_selected_item().map(|item| item.path.clone());
+        for child in &mut self.root.children {
+            child.collapse_all();
+        }
+        self.reselect(selected);
+    }
+
+    /// Expand folders `max_depth` levels deep, loading at most `limit`
+    /// entries. Returns false if the limit cut the expansion short.
+    pub fn expand_all(&mut self, max_depth: usize, limit: usize) -> bool {
+        let selected = self.get_selected_item().map(|item| item.path.clone());
+        let mut budget = limit;
+        let mut complete = true;
+        for level in 0..max_depth {
+            if !self.root.expand_level(level, &self.gitignore, &mut budget) {
+                complete = false;
+                break;
+            }
+        }
+        self.reselect(selected);
+        complete
+    }
+
+    /// Select the first entry and scroll back to the top
+    pub fn go_to_root(&mut self) {
+        self.selected_index = 0;
+        self.scroll_offset = 0;
+    }
+
+    /// Select `path`, or its closest visible parent folder
+    fn reselect(&mut self, path: Option<PathBuf>) {
+        let Some(path) = path else {
+            return;
+        };
+        let index = self
+            .get_visible_items()
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| item.placeholder.is_none() && path.starts_with(&item.path))
+            .max_by_key(|(_, item)| item.depth)
+            .map(|(index, _)| index);
+        if let Some(index) = index {
+            self.selected_index = index;
+            self.scroll_offset = self.scroll_offset.min(index);
+        }
+    }
+
     pub fn start_search(&mut self) {
         self.is_searching = true;
-        self.search_query.clear();
+        self.search_input.clear();
         self.update_search_filter();
     }
 
     pub fn stop_search(&mut self) {
+        if !self.search_input.is_empty() {
+            self.search_input.commit_to_history();
+        }
         self.is_searching = false;
-        self.search_query.clear();
+        self.search_input.clear();
         self.filtered_items.clear();
         self.selected_index = 0;
+        self.scroll_offset = 0;
     }
 
-    pub fn add_search_char(&mut self, c: char) {
-        if self.is_searching {
-            self.search_query.push(c);
-            self.update_search_filter();
+    /// Route a key to the search box. Returns true if the key was consumed.
+    pub fn handle_search_key(&mut self, key: KeyEvent) -> bool {
+        if !self.is_searching {
+            return false;
         }
-    }
 
-    pub fn remove_search_char(&mut self) {
-        if self.is_searching && !self.search_query.is_empty() {
-            self.search_query.pop();
+        let previous = self.search_input.text().to_string();
+        let handled = self.search_input.handle_key(key);
+        if self.search_input.text() != previous {
             self.update_search_filter();
         }
+        handled
     }
 
     fn update_search_filter(&mut self) {
-        let query = self.search_query.to_lowercase();
-        let matching_items: Vec<(usize, TreeNode)> = if self.search_query.is_empty() {
+        let query = Folding::names().str(self.search_input.text());
+        let matching_items: Vec<(usize, TreeNode)> = if self.search_input.is_empty() {
             Vec::new()
         } else {
             // Get comprehensive search results including unexpanded directories
@@ -509,18 +951,20 @@ impl TreeView {
     fn search_all_files(&self, query: &str) -> Vec<(usize, TreeNode)> {
         let mut results = Vec::new();
         let mut index = 0;
+        let folding = Folding::names();
 
         // First, search in currently visible/expanded items
         let visible_items = self.get_all_items();
         for node in &visible_items {
-            if node.name.to_lowercase().contains(query) {
+            if folding.str(&node.name).contains(query) {
                 results.push((index, (*node).clone()));
             }
             index += 1;
         }
 
         // Then, search in unexpanded directories recursively
-        self.search_in_directory(&self.root, query, &mut results, &mut index, 3); // Limit depth to 3 levels
+        let mut guard = WalkGuard::new(&self.root.path);
+        self.search_in_directory(&self.root, query, &mut results, &mut index, 3, &mut guard);
 
         results
     }
@@ -532,6 +976,7 @@ impl TreeView {
         results: &mut Vec<(usize, TreeNode)>,
         index: &mut usize,
         max_depth: usize,
+        guard: &mut WalkGuard,
     ) {
         if max_depth == 0 || !node.is_dir {
             return;
@@ -540,30 +985,39 @@ impl TreeView {
         // If this directory is already expanded, search in its children but don't re-read from filesystem
         if node.is_expanded && !node.children.is_empty() {
             for child in &node.children {
-                if child.is_dir {
-                    self.search_in_directory(child, query, results, index, max_depth - 1);
+                if child.is_dir && guard.enter(&child.path) {
+                    self.search_in_directory(child, query, results, index, max_depth - 1, guard);
                 }
             }
             return;
         }
 
         // Search in this unexpanded directory
-        if let Ok(entries) = std::fs::read_dir(&node.path) {
-            for entry in entries.flatten() {
-                let path = entry.path();
+        let folding = Folding::names();
+        if let Ok(entries) = vfs::read_dir(&node.path) {
+            for entry in entries {
+                let path = entry.path.clone();
 
                 if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
                     // Check if this item matches the search query
-                    if name.to_lowercase().contains(query) {
-                        let search_node = TreeNode::new(path.clone(), node.depth + 1);
+                    if folding.str(name).contains(query) {
+                        let search_node = TreeNode::from_entry(&entry, node.depth + 1);
                         results.push((*index, search_node));
                         *index += 1;
                     }
 
-                    // If it's a directory, search recursively
-                    if path.is_dir() {
-                        let dir_node = TreeNode::new(path, node.depth + 1);
-                        self.search_in_directory(&dir_node, query, results, index, max_depth - 1);
+                    // If it's a directory, search recursively unless a link
+                    // leads somewhere already searched or out of the workspace
+                    if entry.is_dir && guard.enter(&path) {
+                        let dir_node = TreeNode::from_entry(&entry, node.depth + 1);
+                        self.search_in_directory(
+                            &dir_node,
+                            query,
+                            results,
+                            index,
+                            max_depth - 1,
+                            guard,
+                        );
                     }
                 }
             }
@@ -602,57 +1056,16 @@ impl TreeView {
         }
     }
 
-    pub fn scroll_up(&mut self, base_amount: usize) {
-        // Update scroll acceleration
-        self.update_scroll_acceleration();
-
-        // Calculate actual scroll amount with acceleration
-        let scroll_amount = base_amount.saturating_mul(self.scroll_acceleration);
+    pub fn scroll_up(&mut self, scroll_amount: usize) {
         self.scroll_offset = self.scroll_offset.saturating_sub(scroll_amount);
     }
 
-    pub fn scroll_down(&mut self, base_amount: usize, visible_height: usize) {
-        // Update scroll acceleration
-        self.update_scroll_acceleration();
-
-        // Calculate actual scroll amount with acceleration
-        let scroll_amount = base_amount.saturating_mul(self.scroll_acceleration);
-
+    pub fn scroll_down(&mut self, scroll_amount: usize, visible_height: usize) {
         let visible_items = self.get_visible_items();
         let max_scroll = visible_items.len().saturating_sub(visible_height);
         self.scroll_offset = (self.scroll_offset + scroll_amount).min(max_scroll);
     }
 
-    fn update_scroll_acceleration(&mut self) {
-        let now = Instant::now();
-
-        if let Some(last_time) = self.last_scroll_time {
-            // If scrolling within 150ms, increase acceleration
-            if now.duration_since(last_time).as_millis() < 150 {
-                // More aggressive acceleration for tree view
-                let increment = if self.scroll_acceleration < 3 {
-                    1 // Start with +1 for initial acceleration
-                } else if self.scroll_acceleration < 8 {
-                    2 // Medium acceleration +2
-                } else if self.scroll_acceleration < 15 {
-                    3 // Fast acceleration +3
-                } else {
-                    4 // Very fast +4
-                };
-
-                self.scroll_acceleration = (self.scroll_acceleration + increment).min(20);
-            } else {
-                // Reset acceleration if too much time has passed
-                self.scroll_acceleration = 1;
-            }
-        } else {
-            // First scroll, start with base acceleration
-            self.scroll_acceleration = 1;
-        }
-
-        self.last_scroll_time = Some(now);
-    }
-
     pub fn resize(&mut self, new_width: u16) {
         self.width = new_width;
     }
@@ -702,14 +1115,9 @@ impl TreeView {
         }
     }
 
-    pub fn paste_to_selected(&mut self) -> Result<String, String> {
-        let clipboard_entry = match &self.clipboard {
-            Some(entry) => entry.clone(),
-            None => return Err("Nothing to paste".to_string()),
-        };
-
-        // Get the target directory
-        let target_dir = if let Some(selected_item) = self.get_selected_item() {
+    /// Folder that Paste targets: the selected folder, or the selected file's parent
+    pub fn paste_target_dir(&self) -> PathBuf {
+        if let Some(selected_item) = self.get_selected_item() {
             if selected_item.is_dir {
                 selected_item.path.clone()
             } else {
@@ -721,23 +1129,67 @@ impl TreeView {
             }
         } else {
             self.root.path.clone()
+        }
+    }
+
+    /// What Paste would bring in and whether it is a move. The internal
+    /// clipboard wins; otherwise files copied in an OS file manager (paths or
+    /// `file://` URIs on the system clipboard) are imported as copies.
+    pub fn paste_sources(&self) -> Option<(Vec<PathBuf>, bool)> {
+        if let Some(entry) = &self.clipboard {
+            return Some((vec![entry.path.clone()], entry.is_cut));
+        }
+        let paths = Self::system_clipboard_paths();
+        (!paths.is_empty()).then_some((paths, false))
+    }
+
+    /// Forget a cut once it has been pasted
+    pub fn clear_cut(&mut self) {
+        if self.clipboard.as_ref().is_some_and(|entry| entry.is_cut) {
+            self.clipboard = None;
+        }
+    }
+
+    /// Existing paths listed on the system clipboard, one per line
+    fn system_clipboard_paths() -> Vec<PathBuf> {
+        let text = match keyboard::get_clipboard_text() {
+            Some(text) => text,
+            None => return Vec::new(),
         };
 
-        let source_name = clipboard_entry
-            .path
+        let paths: Vec<PathBuf> = text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let line = line.strip_prefix("file://").unwrap_or(line);
+                PathBuf::from(percent_decode(line))
+            })
+            .collect();
+
+        // Only treat the clipboard as files if every line is one
+        if !paths.is_empty() && paths.iter().all(|p| p.is_absolute() && p.exists()) {
+            paths
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// `target_dir/name`, or `name_copy_N` if that already exists
+    pub fn unique_target(source: &Path, target_dir: &Path) -> Result<PathBuf, String> {
+        let source_name = source
             .file_name()
             .ok_or_else(|| "Invalid source path".to_string())?;
 
         let mut target_path = target_dir.join(source_name);
 
         // If the target already exists, generate a unique name
-        if target_path.exists() {
-            let stem = clipboard_entry
-                .path
+        if vfs::exists(&target_path) {
+            let stem = source
                 .file_stem()
                 .and_then(|s| s.to_str())
                 .unwrap_or("file");
-            let extension = clipboard_entry.path.extension().and_then(|e| e.to_str());
+            let extension = source.extension().and_then(|e| e.to_str());
 
             let mut counter = 1;
             loop {
@@ -747,63 +1199,116 @@ impl TreeView {
                     format!("{}_copy_{}", stem, counter)
                 };
                 target_path = target_dir.join(new_name);
-                if !target_path.exists() {
+                if !vfs::exists(&target_path) {
                     break;
                 }
                 counter += 1;
             }
         }
 
-        // Perform the operation
-        if clipboard_entry.is_cut {
-            // Move operation
-            fs::rename(&clipboard_entry.path, &target_path)
-                .map_err(|e| format!("Failed to move: {}", e))?;
+        Ok(target_path)
+    }
 
-            // Clear clipboard after successful cut
-            self.clipboard = None;
+    /// Put the selected file's text on the system clipboard
+    pub fn copy_contents_to_clipboard(&self) -> Result<String, String> {
+        let item = self
+            .get_selected_item()
+            .ok_or_else(|| "Nothing selected".to_string())?;
+        if item.is_dir {
+            return Err("Cannot copy the contents of a folder".to_string());
+        }
+        let content = vfs::read_to_string(&item.path)
+            .map_err(|e| format!("Failed to read {}: {}", item.name, e))?;
+        keyboard::set_clipboard_text(&content);
+        Ok(format!("Copied contents of {}", item.name))
+    }
+
+    /// Put the selected entry's absolute path on the system clipboard
+    pub fn copy_path_to_clipboard(&self) -> Result<String, String> {
+        let item = self
+            .get_selected_item()
+            .ok_or_else(|| "Nothing selected".to_string())?;
+        let path = item.path.display().to_string();
+        keyboard::set_clipboard_text(&path);
+        Ok(format!("Copied path: {}", path))
+    }
 
-            // Refresh the tree
-            self.refresh();
+    /// Copy a file or folder to `name_copy` (or `name_copy_2`, ...) next to it
+    /// and return the new path
+    pub fn duplicate(&mut self, source: &Path) -> std::io::Result<PathBuf> {
+        let parent = source.parent().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Cannot duplicate root directory",
+            )
+        })?;
 
-            Ok(format!("Moved to {}", target_path.display()))
+        // Directories and dotfiles keep their whole name as the stem
+        let (stem, extension) = if vfs::entry_metadata(source).is_ok_and(|m| m.is_dir) {
+            (
+                source.file_name().and_then(|n| n.to_str()).unwrap_or("folder"),
+                None,
+            )
         } else {
-            // Copy operation
-            if clipboard_entry.path.is_dir() {
-                Self::copy_dir_recursive(&clipboard_entry.path, &target_path)
-                    .map_err(|e| format!("Failed to copy directory: {}", e))?;
+            (
+                source.file_stem().and_then(|s| s.to_str()).unwrap_or("file"),
+                source.extension().and_then(|e| e.to_str()),
+            )
+        };
+
+        let mut counter = 1;
+        let target_path = loop {
+            let suffix = if counter == 1 {
+                "_copy".to_string()
             } else {
-                fs::copy(&clipboard_entry.path, &target_path)
-                    .map_err(|e| format!("Failed to copy file: {}", e))?;
+                format!("_copy_{}", counter)
+            };
+            let name = match extension {
+                Some(ext) => format!("{}{}.{}", stem, suffix, ext),
+                None => format!("{}{}", stem, suffix),
+            };
+            let candidate = parent.join(name);
+            if !vfs::exists(&candidate) {
+                break candidate;
             }
+            counter += 1;
+        };
 
-            // Refresh the tree
-            self.refresh();
-
-            Ok(format!("Copied to {}", target_path.display()))
+        if walk::is_symlink(source) {
+            walk::copy_symlink(source, &target_path)?;
+        } else if vfs::entry_metadata(source)?.is_dir {
+            Self::copy_dir_recursive(source, &target_path)?;
+        } else {
+            vfs::copy(source, &target_path)?;
         }
+
+        self.refresh();
+        self.restore_selection(&target_path);
+        Ok(target_path)
     }
 
-    fn copy_dir_recursive(src: &Path, dst: &Path) -> std::io::Result<()> {
-        fs::create_dir_all(dst)?;
+    pub fn copy_dir_recursive(src: &Path, dst: &Path) -> std::io::Result<()> {
+        vfs::create(dst, true)?;
 
-        for entry in fs::read_dir(src)? {
-            let entry = entry?;
-            let src_path = entry.path();
-            let dst_path = dst.join(entry.file_name());
+        for entry in vfs::read_dir(src)? {
+            let src_path = entry.path;
+            let dst_path = dst.join(src_path.file_name().unwrap_or_default());
 
-            if src_path.is_dir() {
+            if entry.is_symlink {
+                walk::copy_symlink(&src_path, &dst_path)?;
+            } else if entry.is_dir {
                 Self::copy_dir_recursive(&src_path, &dst_path)?;
             } else {
-                fs::copy(&src_path, &dst_path)?;
+                vfs::copy(&src_path, &dst_path)?;
             }
         }
 
         Ok(())
     }
 
+    /// Whether Paste has anything to work with, internally or from the OS
     pub fn has_clipboard(&self) -> bool {
-        self.clipboard.is_some()
+        self.clipboard.is_some() || !Self::system_clipboard_paths().is_empty()
     }
 
     pub fn get_clipboard_info(&self) -> Option<String> {
@@ -826,7 +1331,7 @@ impl TreeView {
     }
 
     // Add missing methods needed by keyboard handlers
-    pub fn toggle_directory(&mut self) -> Result<(), std::io::Error> {
+    pub fn toggle_directory(&mut self) -> Result<Option<DirectoryScan>, std::io::Error> {
         self.toggle_selected()
     }
 
@@ -869,41 +1374,29 @@ impl Widget for &TreeView {
         let mut content_area = inner;
         if self.is_searching {
             // Draw search box at the top
-            let search_text = format!("Search: {}_", self.search_query);
             let search_y = inner.y;
+            let background = Style::default().bg(Color::DarkGray);
 
             // Clear the search line first
             for x in inner.x..inner.x + content_width {
-                if x < inner.x + content_width {
-                    buf[(x, search_y)]
-                        .set_symbol(" ")
-                        .set_style(Style::default().bg(Color::DarkGray));
-                }
+                buf[(x, search_y)].set_symbol(" ").set_style(background);
             }
 
-            // Draw the search text
-            for (i, ch) in search_text.chars().enumerate() {
-                let x = inner.x + i as u16;
-                if x < inner.x + content_width {
-                    let style = if i < 8 {
-                        // "Search: " part
-                        Style::default().fg(Color::Yellow).bg(Color::DarkGray)
-                    } else if i == search_text.len() - 1 {
-                        // Cursor
-                        Style::default()
-                            .fg(Color::Yellow)
-                            .bg(Color::DarkGray)
-                            .add_modifier(Modifier::SLOW_BLINK)
-                    } else {
-                        // Query text
-                        Style::default().fg(Color::White).bg(Color::DarkGray)
-                    };
-
-                    buf[(x, search_y)]
-                        .set_symbol(&ch.to_string())
-                        .set_style(style);
-                }
-            }
+            let label = "Search: ";
+            buf.set_string(
+                inner.x,
+                search_y,
+                label,
+                Style::default().fg(Color::Yellow).bg(Color::DarkGray),
+            );
+            let label_width = label.len() as u16;
+            let input_width = content_width.saturating_sub(label_width);
+            let line = self.search_input.to_line(
+                Style::default().fg(Color::White).bg(Color::DarkGray),
+                self.is_focused,
+                input_width as usize,
+            );
+            buf.set_line(inner.x + label_width, search_y, &line, input_width);
 
             // Adjust content area to start below search box
             content_area.y += 1;
@@ -931,23 +1424,59 @@ impl Widget for &TreeView {
                     }
                 }
 
-                // Draw file/directory icon
-                if x < content_area.x + content_width {
-                    let icon = if item.is_dir {
-                        file_icons::get_directory_icon(item.is_expanded)
+                // Loading and "more" rows are dimmed text in place of an entry
+                if item.placeholder.is_some() {
+                    let style = if is_selected && self.is_focused {
+                        Style::default().bg(Color::Blue).fg(Color::White)
+                    } else if self.hovered_index == Some(item_index) {
+                        crate::ui::hover_style(Style::default().fg(Color::Gray))
                     } else {
-                        file_icons::get_file_icon(&item.path)
+                        Style::default().fg(Color::DarkGray)
                     };
-                    buf[(x, y)].set_symbol(icon);
-                    x += 2; // Emoji takes 2 columns
+                    let width = content_width.saturating_sub(x - content_area.x);
+                    buf.set_stringn(
+                        x,
+                        y,
+                        &item.name,
+                        width as usize,
+                        style.add_modifier(Modifier::ITALIC),
+                    );
+                    continue;
                 }
 
+                // Draw file/directory icon
+                let icon = if item.is_dir {
+                    file_icons::get_directory_icon(item.is_expanded)
+                } else {
+                    file_icons::get_file_icon(&item.path)
+                };
+                let limit = content_area.x + content_width;
+                file_icons::draw_icon(buf, x, y, limit, icon, Style::default());
+                x = (x + file_icons::icon_columns()).min(limit);
+
                 // Add space between icon and text
                 if x < content_area.x + content_width {
                     buf[(x, y)].set_symbol(" ");
                     x += 1;
                 }
 
+                // A row being renamed shows its name field instead
+                if let Some(rename) = self.rename.as_ref().filter(|r| r.path == item.path) {
+                    let field_width = content_width.saturating_sub(x - content_area.x);
+                    let field_style =
+                        Style::default().bg(Color::Rgb(50, 50, 50)).fg(Color::White);
+                    for field_x in x..x + field_width {
+                        buf[(field_x, y)].set_style(field_style);
+                    }
+                    let line = rename.input.to_line(
+                        field_style,
+                        self.is_focused,
+                        field_width as usize,
+                    );
+                    buf.set_line(x, y, &line, field_width);
+                    continue;
+                }
+
                 // Draw file/directory name
                 let name_style = if is_selected {
                     if self.is_focused {
@@ -963,23 +1492,59 @@ impl Widget for &TreeView {
                 } else {
                     Style::default().fg(Color::White)
                 };
+                let name_style = if !is_selected && self.hovered_index == Some(item_index) {
+                    crate::ui::hover_style(name_style)
+                } else {
+                    name_style
+                };
 
-                let max_name_width = content_width.saturating_sub(x - content_area.x);
-                let display_name = if item.name.len() as u16 > max_name_width {
-                    format!(
-                        "{}...",
-                        &item.name[..max_name_width.saturating_sub(3) as usize]
-                    )
+                let max_name_width = content_width.saturating_sub(x - content_area.x) as usize;
+                let display_name = if item.name.width() > max_name_width {
+                    let mut width = 0;
+                    let kept: String = item
+                        .name
+                        .chars()
+                        .take_while(|ch| {
+                            width += ch.width().unwrap_or(0);
+                            width <= max_name_width.saturating_sub(3)
+                        })
+                        .collect();
+                    format!("{}...", kept)
                 } else {
                     item.name.clone()
                 };
 
+                // Wide characters take two cells; the second is left to them
                 for ch in display_name.chars() {
-                    if x < content_area.x + content_width {
-                        buf[(x, y)]
-                            .set_symbol(&ch.to_string())
-                            .set_style(name_style);
-                        x += 1;
+                    let width = ch.width().unwrap_or(0) as u16;
+                    if width == 0 || x + width > content_area.x + content_width {
+                        continue;
+                    }
+                    buf[(x, y)]
+                        .set_symbol(&ch.to_string())
+                        .set_style(name_style);
+                    for covered in x + 1..x + width {
+                        buf[(covered, y)].set_style(name_style);
+                    }
+                    x += width;
+                }
+
+                // Symlinks get a marker, red when the target is missing
+                if item.is_symlink {
+                    let marker_style = if !item.path.exists() {
+                        name_style.fg(Color::Rgb(200, 80, 80))
+                    } else if is_selected {
+                        name_style
+                    } else {
+                        name_style.fg(Color::Rgb(120, 120, 120))
+                    };
+                    for ch in file_icons::symlink_suffix().chars() {
+                        if x < content_area.x + content_width {
+                            buf[(x, y)]
+                                .set_symbol(&ch.to_string())
+                                .set_style(marker_style);
+                            x += 1;
+                        }
                     }
                 }
 
@@ -1017,3 +1582,23 @@ impl Widget for &TreeView {
         }
     }
 }
+
+/// Decode `%XX` escapes in a `file://` URI path
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
@@ -0,0 +1,149 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph},
+    Frame,
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::menu::BufferSwitcherState;
+
+const BACKGROUND: Color = Color::Rgb(25, 25, 30);
+const SEARCH_BACKGROUND: Color = Color::Rgb(35, 35, 40);
+const SELECTED_BACKGROUND: Color = Color::Rgb(60, 60, 70);
+/// Most entries shown before the list scrolls
+const MAX_VISIBLE: usize = 15;
+
+/// Centered box holding the search row and the list
+pub fn modal_area(screen: Rect, state: &BufferSwitcherState) -> Rect {
+    let width = 70u16.min(screen.width.saturating_sub(4));
+    // One row of padding above and below, the search row and a spacer
+    let rows = state.entries.len().clamp(1, MAX_VISIBLE) as u16 + 4;
+    let height = rows.min(screen.height.saturating_sub(4));
+    Rect {
+        x: (screen.width.saturating_sub(width)) / 2,
+        y: (screen.height.saturating_sub(height)) / 2,
+        width,
+        height,
+    }
+}
+
+/// Rows of the tab list; the mouse handler hit-tests against this too
+pub fn list_area(screen: Rect, state: &BufferSwitcherState) -> Rect {
+    let area = modal_area(screen, state);
+    Rect {
+        x: area.x + 1,
+        y: area.y + 3,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(4),
+    }
+}
+
+/// First entry shown, keeping the selection in view
+pub fn first_visible(state: &BufferSwitcherState, visible: usize) -> usize {
+    (state.selected_index + 1).saturating_sub(visible.max(1))
+}
+
+pub fn draw_buffer_switcher(frame: &mut Frame, state: &BufferSwitcherState) {
+    let area = modal_area(frame.area(), state);
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Block::default().style(Style::default().bg(BACKGROUND)),
+        area,
+    );
+
+    // Search row
+    let search_area = Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: 1,
+    };
+    let search_line = if state.search_input.is_empty() {
+        let placeholder = if state.compare_with.is_some() {
+            "Compare with tab..."
+        } else {
+            "Switch to tab..."
+        };
+        Line::from(vec![
+            Span::raw("  "),
+            Span::styled("│", Style::default().fg(Color::Cyan).bg(SEARCH_BACKGROUND)),
+            Span::styled(
+                placeholder,
+                Style::default()
+                    .fg(Color::Rgb(100, 100, 100))
+                    .bg(SEARCH_BACKGROUND),
+            ),
+        ])
+    } else {
+        let mut line = state.search_input.to_line(
+            Style::default().fg(Color::White).bg(SEARCH_BACKGROUND),
+            true,
+            search_area.width.saturating_sub(2) as usize,
+        );
+        line.spans.insert(0, Span::raw("  "));
+        line
+    };
+    frame.render_widget(
+        Paragraph::new(search_line).style(Style::default().bg(SEARCH_BACKGROUND)),
+        search_area,
+    );
+
+    // Tab list: name with its modified marker, then the dimmed path
+    let list = list_area(frame.area(), state);
+    let visible = list.height as usize;
+    let start = first_visible(state, visible);
+    let width = list.width as usize;
+
+    let mut lines = Vec::new();
+    if state.filtered.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  No matching tabs",
+            Style::default().fg(Color::Rgb(100, 100, 100)),
+        )));
+    }
+    for (row, &entry_index) in state.filtered.iter().enumerate().skip(start).take(visible) {
+        let entry = &state.entries[entry_index];
+        let bg = if row == state.selected_index {
+            SELECTED_BACKGROUND
+        } else {
+            BACKGROUND
+        };
+        let marker = if entry.modified { " ●" } else { "" };
+        let name = format!("  {}{}", entry.name, marker);
+        let path_room = width.saturating_sub(name.width() + 3);
+        let path = truncate_start(&entry.path, path_room);
+        let padding = width.saturating_sub(name.width() + path.width() + 1);
+
+        lines.push(Line::from(vec![
+            Span::styled(name, Style::default().fg(Color::White).bg(bg)),
+            Span::styled(" ".repeat(padding), Style::default().bg(bg)),
+            Span::styled(path, Style::default().fg(Color::Rgb(120, 120, 120)).bg(bg)),
+            Span::styled(" ", Style::default().bg(bg)),
+        ]));
+    }
+    frame.render_widget(Paragraph::new(lines), list);
+}
+
+/// Keep the end of a path, which names the file, when it doesn't fit
+fn truncate_start(text: &str, max_width: usize) -> String {
+    if text.width() <= max_width {
+        return text.to_string();
+    }
+    if max_width == 0 {
+        return String::new();
+    }
+    let chars: Vec<char> = text.chars().collect();
+    let mut width = 1;
+    let mut start = chars.len();
+    while start > 0 {
+        let w = chars[start - 1].to_string().width();
+        if width + w > max_width {
+            break;
+        }
+        width += w;
+        start -= 1;
+    }
+    format!("…{}", chars[start..].iter().collect::<String>())
+}
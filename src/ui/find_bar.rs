@@ -0,0 +1,151 @@
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+
+/// Clickable parts of the find/replace bar
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FindBarTarget {
+    FindInput,
+    ReplaceInput,
+    FindNext,
+    CaseSensitive,
+    IgnoreAccents,
+    WholeWord,
+    InBlock,
+    HighlightAll,
+    Replace,
+    ReplaceAll,
+}
+
+/// Where each part of the find/replace bar is drawn. Rendering and mouse
+/// hit-testing both go through this so they can't drift apart.
+pub struct FindBarLayout {
+    pub find_label: Rect,
+    pub find_input: Rect,
+    pub match_counter: Rect,
+    pub find_next: Rect,
+    pub case_button: Rect,
+    pub accent_button: Rect,
+    pub word_button: Rect,
+    pub block_button: Rect,
+    pub highlight_button: Rect,
+    pub replace_row: Option<ReplaceRowLayout>,
+}
+
+pub struct ReplaceRowLayout {
+    pub label: Rect,
+    pub input: Rect,
+    pub replace_button: Rect,
+    pub replace_all_button: Rect,
+}
+
+impl FindBarLayout {
+    /// Height of the bar: one row for find, two with replace
+    pub fn height(replace_mode: bool) -> u16 {
+        if replace_mode {
+            2
+        } else {
+            1
+        }
+    }
+
+    pub fn new(area: Rect, replace_mode: bool) -> Self {
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(if replace_mode {
+                vec![Constraint::Length(1), Constraint::Length(1)]
+            } else {
+                vec![Constraint::Length(1)]
+            })
+            .split(area);
+
+        let find_chunks = Self::columns(rows[0]);
+        let replace_row = (replace_mode && rows.len() > 1).then(|| {
+            // Columns line up with the find row; Replace All spans the
+            // space under the Aa, é, W, {} and All buttons
+            let chunks = Self::columns(rows[1]);
+            ReplaceRowLayout {
+                label: chunks[0],
+                input: chunks[1],
+                replace_button: chunks[3],
+                replace_all_button: Rect {
+                    width: chunks[4..9].iter().map(|chunk| chunk.width).sum(),
+                    ..chunks[4]
+                },
+            }
+        });
+
+        Self {
+            find_label: find_chunks[0],
+            find_input: find_chunks[1],
+            match_counter: find_chunks[2],
+            find_next: find_chunks[3],
+            case_button: find_chunks[4],
+            accent_button: find_chunks[5],
+            word_button: find_chunks[6],
+            block_button: find_chunks[7],
+            highlight_button: find_chunks[8],
+            replace_row,
+        }
+    }
+
+    fn columns(row: Rect) -> std::rc::Rc<[Rect]> {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Length(10), // Label ("Find:" / "Replace:")
+                Constraint::Min(20),    // Input field (flexible)
+                Constraint::Length(12), // Match counter
+                Constraint::Length(12), // Find Next / Replace button
+                Constraint::Length(5),  // Case button
+                Constraint::Length(5),  // Accent button
+                Constraint::Length(5),  // Whole word button
+                Constraint::Length(5),  // In block button
+                Constraint::Length(5),  // Highlight all button
+                Constraint::Length(2),  // Right padding
+            ])
+            .split(row)
+    }
+
+    /// The control under a screen cell, if any. Labels focus their field.
+    pub fn target_at(&self, column: u16, row: u16) -> Option<FindBarTarget> {
+        let hit = |rect: Rect| {
+            column >= rect.x
+                && column < rect.x + rect.width
+                && row >= rect.y
+                && row < rect.y + rect.height
+        };
+
+        if hit(self.find_label) || hit(self.find_input) {
+            return Some(FindBarTarget::FindInput);
+        }
+        if hit(self.find_next) {
+            return Some(FindBarTarget::FindNext);
+        }
+        if hit(self.case_button) {
+            return Some(FindBarTarget::CaseSensitive);
+        }
+        if hit(self.accent_button) {
+            return Some(FindBarTarget::IgnoreAccents);
+        }
+        if hit(self.word_button) {
+            return Some(FindBarTarget::WholeWord);
+        }
+        if hit(self.block_button) {
+            return Some(FindBarTarget::InBlock);
+        }
+        if hit(self.highlight_button) {
+            return Some(FindBarTarget::HighlightAll);
+        }
+        if let Some(replace_row) = &self.replace_row {
+            if hit(replace_row.label) || hit(replace_row.input) {
+                return Some(FindBarTarget::ReplaceInput);
+            }
+            if hit(replace_row.replace_button) {
+                return Some(FindBarTarget::Replace);
+            }
+            if hit(replace_row.replace_all_button) {
+                return Some(FindBarTarget::ReplaceAll);
+            }
+        }
+        None
+    }
+}
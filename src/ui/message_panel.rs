@@ -0,0 +1,108 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::messages::{format_age, MessageLog, Severity};
+
+/// Rows taken by the Output/Log panel, including its border
+pub const LOG_PANEL_HEIGHT: u16 = 10;
+
+fn severity_color(severity: Severity) -> Color {
+    match severity {
+        Severity::Info => Color::Rgb(80, 160, 255),
+        Severity::Warning => Color::Yellow,
+        Severity::Error => Color::Rgb(255, 90, 90),
+    }
+}
+
+/// Latest message as a one-line toast in the bottom-right corner of `area`
+pub fn draw_toast(frame: &mut Frame, area: Rect, messages: &MessageLog) {
+    let message = match messages.current() {
+        Some(message) => message,
+        None => return,
+    };
+
+    let queued = messages.queued();
+    let suffix = if queued > 0 {
+        format!(" (+{})", queued)
+    } else {
+        String::new()
+    };
+    let text = format!(" {}{} ", message.text, suffix);
+
+    let width = (text.width() as u16 + 2).min(area.width);
+    if width < 6 || area.height < 3 {
+        return;
+    }
+    let toast_area = Rect {
+        x: area.x + area.width - width,
+        y: area.y + area.height - 3,
+        width,
+        height: 3,
+    };
+
+    let color = severity_color(message.severity);
+    frame.render_widget(Clear, toast_area);
+    let toast = Paragraph::new(Line::from(Span::styled(
+        text,
+        Style::default().fg(Color::White),
+    )))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(color)),
+    )
+    .style(Style::default().bg(Color::Rgb(30, 30, 30)));
+    frame.render_widget(toast, toast_area);
+}
+
+/// History of every message, newest at the bottom
+pub fn draw_log_panel(frame: &mut Frame, area: Rect, messages: &MessageLog) {
+    let block = Block::default()
+        .borders(Borders::TOP)
+        .title(" Output (Alt+L to close) ")
+        .border_style(Style::default().fg(Color::Rgb(100, 100, 100)))
+        .style(Style::default().bg(Color::Rgb(25, 25, 25)));
+    let inner = block.inner(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+
+    let rows = inner.height as usize;
+    let total = messages.history().len();
+    let end = total.saturating_sub(messages.log_scroll);
+    let start = end.saturating_sub(rows);
+
+    let lines: Vec<Line> = messages
+        .history()
+        .skip(start)
+        .take(end - start)
+        .map(|message| {
+            Line::from(vec![
+                Span::styled(
+                    format!(" {} ", format_age(message.created)),
+                    Style::default().fg(Color::Rgb(120, 120, 120)),
+                ),
+                Span::styled(
+                    format!("{:<6}", message.severity.label()),
+                    Style::default()
+                        .fg(severity_color(message.severity))
+                        .add_modifier(Modifier::BOLD),
+                ),
+                Span::styled(message.text.clone(), Style::default().fg(Color::White)),
+            ])
+        })
+        .collect();
+
+    if lines.is_empty() {
+        let empty = Paragraph::new(" No messages yet")
+            .style(Style::default().fg(Color::Rgb(120, 120, 120)));
+        frame.render_widget(empty, inner);
+    } else {
+        frame.render_widget(Paragraph::new(lines), inner);
+    }
+}
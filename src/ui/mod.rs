@@ -1,7 +1,13 @@
+pub mod buffer_switcher;
+mod find_bar;
 mod menu_component;
+mod message_panel;
+mod modal;
+pub mod project_search;
 pub mod scrollbar;
-mod status_bar;
+pub mod status_bar;
 mod tab_bar;
+mod text_input;
 
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
@@ -12,20 +18,43 @@ use ratatui::{
 };
 
 use crate::app::FocusMode;
+use crate::config::{self, CursorShape};
 use crate::editor_widget::EditorWidget;
 use crate::file_icons;
+use crate::jobs::JobManager;
 use crate::menu::{MenuState, MenuSystem};
+use crate::messages::MessageLog;
 use crate::tab::{Tab, TabManager};
 use crate::tree_view::TreeView;
+use unicode_width::UnicodeWidthChar;
 
+pub use self::find_bar::{FindBarLayout, FindBarTarget};
 pub use self::menu_component::{MenuAction, MenuComponent, MenuItem};
+pub use self::message_panel::LOG_PANEL_HEIGHT;
+pub use self::modal::{Modal, ModalAction, ModalButton, ModalKind};
 pub use self::scrollbar::{ScrollbarState, VerticalScrollbar};
 use self::status_bar::StatusBar;
 use self::tab_bar::TabBar;
+pub use self::tab_bar::TabStrip;
+pub use self::text_input::TextInput;
+
+/// Background of something under the mouse that has none of its own
+const HOVER_BACKGROUND: Color = Color::Rgb(55, 55, 55);
+
+/// A style lightened to show the mouse is over it
+pub fn hover_style(style: Style) -> Style {
+    match style.bg {
+        Some(Color::Rgb(r, g, b)) => {
+            style.bg(Color::Rgb(r.saturating_add(30), g.saturating_add(30), b.saturating_add(30)))
+        }
+        _ => style.bg(HOVER_BACKGROUND),
+    }
+}
 
 pub struct UI {
     pub tab_bar: TabBar,
     status_bar: StatusBar,
+    pub text_width: usize, // Text columns of the editor as last drawn
 }
 
 impl UI {
@@ -33,23 +62,30 @@ impl UI {
         Self {
             tab_bar: TabBar::new(),
             status_bar: StatusBar::new(),
+            text_width: 80,
         }
     }
 
+    /// Read the workspace's git branch again for the status bar
+    pub fn refresh_git_branch(&mut self, root: &std::path::Path) {
+        self.status_bar.refresh_branch(root);
+    }
+
     #[allow(clippy::too_many_arguments)]
     pub fn draw(
         &mut self,
         frame: &mut Frame,
         tab_manager: &mut TabManager,
-        warning_message: &Option<String>,
-        selected_button: usize,
-        is_info: bool,
+        modal: &Option<Modal>,
         menu_system: &MenuSystem,
         tree_view: &Option<TreeView>,
         sidebar_width: u16,
         focus_mode: &FocusMode,
-        status_message: &Option<String>,
+        messages: &MessageLog,
+        jobs: &JobManager,
         dragging_tab: Option<usize>,
+        overwrite_mode: bool,
+        mouse_captured: bool,
     ) {
         let size = frame.area();
 
@@ -62,14 +98,30 @@ impl UI {
             ])
             .split(size);
 
+        // The terminal cursor is only shown when nothing covers the editor;
+        // with the "painted" cursor shape it is never used
+        let overlay_closed = modal.is_none()
+            && matches!(menu_system.state, MenuState::Closed)
+            && config::get().editor.cursor_shape != CursorShape::Painted;
+
         // Render tab bar
         self.tab_bar
             .draw(frame, chunks[0], tab_manager, dragging_tab);
 
-        let main_area = chunks[1];
+        // The Output/Log panel takes the bottom of the main area when open
+        let (main_area, log_area) = if messages.log_visible {
+            let split = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(LOG_PANEL_HEIGHT)])
+                .split(chunks[1]);
+            (split[0], Some(split[1]))
+        } else {
+            (chunks[1], None)
+        };
 
         // Split main content area into sidebar and editor if tree view exists
-        if let Some(tree_view) = tree_view {
+        // and isn't collapsed
+        if let Some(tree_view) = tree_view.as_ref().filter(|_| sidebar_width > 0) {
             // Create horizontal layout with tree view and editor
             let horizontal_chunks = Layout::default()
                 .direction(Direction::Horizontal)
@@ -86,15 +138,25 @@ impl UI {
             let editor_area = horizontal_chunks[1];
             if let Some(tab) = tab_manager.active_tab_mut() {
                 let is_markdown = tab.is_markdown();
+                let is_diff = tab.is_diff();
+                let language = tab.language();
+                let line_limit = language.line_length_limit();
                 match tab {
-                    Tab::Editor { find_replace_state, preview_mode, buffer, cursor, viewport_offset, word_wrap, .. } => {
+                    Tab::Editor {
+                        find_replace_state,
+                        preview_mode,
+                        buffer,
+                        cursor,
+                        viewport_offset,
+                        view,
+                        annotations,
+                        line_cache,
+                        ..
+                    } => {
                         // Check if we need to show find/replace bar in editor area
                         let final_editor_area = if find_replace_state.active {
-                            let bar_height = if find_replace_state.is_replace_mode {
-                                2
-                            } else {
-                                1
-                            };
+                            let bar_height =
+                                FindBarLayout::height(find_replace_state.is_replace_mode);
                             let split = Layout::default()
                                 .direction(Direction::Vertical)
                                 .constraints([Constraint::Length(bar_height), Constraint::Min(0)])
@@ -115,22 +177,49 @@ impl UI {
                                 .viewport_offset(*viewport_offset);
                             frame.render_widget(preview, final_editor_area);
                         } else {
-                            // Render normal editor
+                            // Render normal editor, showing the terminal's own cursor when it has focus
+                            let use_terminal_cursor = is_editor_focused
+                                && !find_replace_state.active
+                                && overlay_closed;
                             let mut editor = EditorWidget::new(buffer, cursor)
                                 .viewport_offset(*viewport_offset)
-                                .show_line_numbers(true)
+                                .show_line_numbers(view.line_numbers)
                                 .focused(is_editor_focused)
-                                .word_wrap(*word_wrap);
+                                .paint_cursor(!use_terminal_cursor)
+                                .word_wrap(view.word_wrap)
+                                .wrap_column(view.wrap_column)
+                                .show_whitespace(view.show_whitespace)
+                                .split_long_lines(view.split_long_lines)
+                                .diff(is_diff)
+                                .max_line_length(line_limit)
+                                .annotations(annotations)
+                                .line_cache(line_cache)
+                                .language(language)
+                                .syntax_tree(
+                                    language
+                                        .highlighter
+                                        .as_deref()
+                                        .and_then(|grammar| buffer.syntax_tree(grammar)),
+                                );
 
                             // Add find matches if search is active
                             if find_replace_state.active && !find_replace_state.matches.is_empty() {
-                                editor = editor.find_matches(
-                                    &find_replace_state.matches,
-                                    find_replace_state.current_match_index,
-                                );
+                                editor = editor
+                                    .find_matches(
+                                        &find_replace_state.matches,
+                                        find_replace_state.current_match_index,
+                                    )
+                                    .highlight_all(find_replace_state.highlight_all);
                             }
 
+                            let cursor_position = editor
+                                .cursor_screen_position(final_editor_area)
+                                .filter(|_| use_terminal_cursor);
+                            self.text_width = editor.text_width(final_editor_area).max(1);
                             frame.render_widget(editor, final_editor_area);
+                            if let Some(position) = cursor_position {
+                                frame.set_cursor_position(position);
+                            }
                         }
                     }
                     Tab::Terminal { terminal, .. } => {
@@ -142,15 +231,25 @@ impl UI {
             // No tree view, render editor in full main area
             if let Some(tab) = tab_manager.active_tab_mut() {
                 let is_markdown = tab.is_markdown();
+                let is_diff = tab.is_diff();
+                let language = tab.language();
+                let line_limit = language.line_length_limit();
                 match tab {
-                    Tab::Editor { find_replace_state, preview_mode, buffer, cursor, viewport_offset, word_wrap, .. } => {
+                    Tab::Editor {
+                        find_replace_state,
+                        preview_mode,
+                        buffer,
+                        cursor,
+                        viewport_offset,
+                        view,
+                        annotations,
+                        line_cache,
+                        ..
+                    } => {
                         // Check if we need to show find/replace bar
                         let final_editor_area = if find_replace_state.active {
-                            let bar_height = if find_replace_state.is_replace_mode {
-                                2
-                            } else {
-                                1
-                            };
+                            let bar_height =
+                                FindBarLayout::height(find_replace_state.is_replace_mode);
                             let split = Layout::default()
                                 .direction(Direction::Vertical)
                                 .constraints([Constraint::Length(bar_height), Constraint::Min(0)])
@@ -170,22 +269,47 @@ impl UI {
                                 .viewport_offset(*viewport_offset);
                             frame.render_widget(preview, final_editor_area);
                         } else {
-                            // Render normal editor
+                            // Render normal editor, showing the terminal's own cursor
+                            let use_terminal_cursor = !find_replace_state.active && overlay_closed;
                             let mut editor = EditorWidget::new(buffer, cursor)
                                 .viewport_offset(*viewport_offset)
-                                .show_line_numbers(true)
+                                .show_line_numbers(view.line_numbers)
                                 .focused(true)
-                                .word_wrap(*word_wrap);
+                                .paint_cursor(!use_terminal_cursor)
+                                .word_wrap(view.word_wrap)
+                                .wrap_column(view.wrap_column)
+                                .show_whitespace(view.show_whitespace)
+                                .split_long_lines(view.split_long_lines)
+                                .diff(is_diff)
+                                .max_line_length(line_limit)
+                                .annotations(annotations)
+                                .line_cache(line_cache)
+                                .language(language)
+                                .syntax_tree(
+                                    language
+                                        .highlighter
+                                        .as_deref()
+                                        .and_then(|grammar| buffer.syntax_tree(grammar)),
+                                );
 
                             // Add find matches if search is active
                             if find_replace_state.active && !find_replace_state.matches.is_empty() {
-                                editor = editor.find_matches(
-                                    &find_replace_state.matches,
-                                    find_replace_state.current_match_index,
-                                );
+                                editor = editor
+                                    .find_matches(
+                                        &find_replace_state.matches,
+                                        find_replace_state.current_match_index,
+                                    )
+                                    .highlight_all(find_replace_state.highlight_all);
                             }
 
+                            let cursor_position = editor
+                                .cursor_screen_position(final_editor_area)
+                                .filter(|_| use_terminal_cursor);
+                            self.text_width = editor.text_width(final_editor_area).max(1);
                             frame.render_widget(editor, final_editor_area);
+                            if let Some(position) = cursor_position {
+                                frame.set_cursor_position(position);
+                            }
                         }
                     }
                     Tab::Terminal { terminal, .. } => {
@@ -195,15 +319,21 @@ impl UI {
             }
         }
 
-        // Render status bar
-        self.status_bar
-            .draw(frame, chunks[2], tab_manager, status_message.as_ref());
-
-        // Render warning dialog if present
-        if let Some(message) = warning_message {
-            self.draw_warning_dialog(frame, message, selected_button, is_info);
+        if let Some(log_area) = log_area {
+            message_panel::draw_log_panel(frame, log_area, messages);
         }
 
+        // Render status bar
+        self.status_bar.draw(
+            frame,
+            chunks[2],
+            tab_manager,
+            jobs.summary(),
+            overwrite_mode,
+            mouse_captured,
+        );
+        message_panel::draw_toast(frame, chunks[1], messages);
+
         // Render menus if present
         match &menu_system.state {
             MenuState::MainMenu(menu) => {
@@ -232,6 +362,15 @@ impl UI {
             MenuState::FilePicker(picker_state) => {
                 self.draw_file_picker(frame, picker_state);
             }
+            MenuState::BufferSwitcher(switcher_state) => {
+                buffer_switcher::draw_buffer_switcher(frame, switcher_state);
+            }
+            MenuState::ProjectSearch(search_state) => {
+                project_search::draw_project_search(frame, search_state);
+            }
+            MenuState::TodoList(todo_state) => {
+                project_search::draw_todo_list(frame, todo_state);
+            }
             MenuState::TreeContextMenu(context_state) => {
                 let menu_area = Rect {
                     x: context_state.position.0,
@@ -246,129 +385,11 @@ impl UI {
             }
             MenuState::Closed => {}
         }
-    }
-
-    fn draw_warning_dialog(
-        &self,
-        frame: &mut Frame,
-        message: &str,
-        selected_button: usize,
-        is_info: bool,
-    ) {
-        let size = frame.area();
-
-        // Calculate popup size and position
-        let popup_width = (message.len() + 4).clamp(30, 80) as u16;
-        let popup_height = 7; // Increased height for buttons
-        let popup_x = (size.width.saturating_sub(popup_width)) / 2;
-        let popup_y = (size.height.saturating_sub(popup_height)) / 2;
-
-        let popup_area = Rect {
-            x: popup_x,
-            y: popup_y,
-            width: popup_width,
-            height: popup_height,
-        };
-
-        // Clear the area behind the popup
-        frame.render_widget(Clear, popup_area);
-
-        // Create layout for dialog content
-        let dialog_chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .margin(1)
-            .constraints([
-                Constraint::Length(1), // Title spacer
-                Constraint::Length(1), // Message
-                Constraint::Length(1), // Spacer
-                Constraint::Length(1), // Buttons
-            ])
-            .split(popup_area);
-
-        // Render the border and title
-        let warning_block = Block::default()
-            .borders(Borders::ALL)
-            .title(" Warning ")
-            .style(Style::default().bg(Color::Red).fg(Color::White));
-        frame.render_widget(warning_block, popup_area);
-
-        // Render the message
-        let warning_text = Paragraph::new(Line::from(message))
-            .alignment(Alignment::Center)
-            .style(Style::default().fg(Color::White));
-        frame.render_widget(warning_text, dialog_chunks[1]);
-
-        // Create buttons based on dialog type
-        let buttons = if is_info {
-            // Info dialog - only OK button
-            let ok_style = Style::default()
-                .bg(Color::Blue)
-                .fg(Color::White)
-                .add_modifier(Modifier::BOLD);
-
-            Line::from(vec![Span::styled("       [ OK ]       ", ok_style)])
-        } else {
-            // Confirmation dialog - Yes/No buttons
-            let border_style = Style::default().fg(Color::White);
-            let space_style = Style::default();
-
-            let (no_style, no_left_border, no_right_border) = if selected_button == 0 {
-                // Selected No: bright red background with white border
-                (
-                    Style::default()
-                        .bg(Color::Rgb(200, 50, 50)) // Bright red
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD),
-                    Span::styled("[", border_style.bg(Color::Rgb(200, 50, 50))),
-                    Span::styled("]", border_style.bg(Color::Rgb(200, 50, 50))),
-                )
-            } else {
-                // Not selected: dark gray background
-                (
-                    Style::default()
-                        .bg(Color::Rgb(60, 60, 60)) // Dark gray
-                        .fg(Color::Rgb(200, 200, 200)),
-                    Span::styled(" ", Style::default().bg(Color::Rgb(60, 60, 60))),
-                    Span::styled(" ", Style::default().bg(Color::Rgb(60, 60, 60))),
-                )
-            };
 
-            let (yes_style, yes_left_border, yes_right_border) = if selected_button == 1 {
-                // Selected Yes: bright green background with white border
-                (
-                    Style::default()
-                        .bg(Color::Rgb(50, 200, 50)) // Bright green
-                        .fg(Color::White)
-                        .add_modifier(Modifier::BOLD),
-                    Span::styled("[", border_style.bg(Color::Rgb(50, 200, 50))),
-                    Span::styled("]", border_style.bg(Color::Rgb(50, 200, 50))),
-                )
-            } else {
-                // Not selected: dark gray background
-                (
-                    Style::default()
-                        .bg(Color::Rgb(60, 60, 60)) // Dark gray
-                        .fg(Color::Rgb(200, 200, 200)),
-                    Span::styled(" ", Style::default().bg(Color::Rgb(60, 60, 60))),
-                    Span::styled(" ", Style::default().bg(Color::Rgb(60, 60, 60))),
-                )
-            };
-
-            Line::from(vec![
-                Span::styled("  ", space_style),  // Left padding
-                no_left_border,                   // Left border or space
-                Span::styled(" No ", no_style),   // No button with padding
-                no_right_border,                  // Right border or space
-                Span::styled("  ", space_style),  // Space between buttons
-                yes_left_border,                  // Left border or space
-                Span::styled(" Yes ", yes_style), // Yes button with padding
-                yes_right_border,                 // Right border or space
-                Span::styled("  ", space_style),  // Right padding
-            ])
-        };
-
-        let buttons_paragraph = Paragraph::new(buttons).alignment(Alignment::Center);
-        frame.render_widget(buttons_paragraph, dialog_chunks[3]);
+        // Modal dialogs sit above everything else
+        if let Some(modal) = modal {
+            modal.render(frame);
+        }
     }
 
     fn draw_input_dialog(&self, frame: &mut Frame, input_state: &crate::menu::InputDialogState) {
@@ -430,56 +451,13 @@ impl UI {
         frame.render_widget(prompt_paragraph, dialog_chunks[1]);
 
         // Input field with cursor and selection
-        let mut input_spans = Vec::new();
-        let input_bg = Color::Rgb(50, 50, 50);
-        let selection_bg = Color::Rgb(100, 100, 200);
-
-        for (i, ch) in input_state.input.chars().enumerate() {
-            let is_selected = if let Some(sel_start) = input_state.selection_start {
-                let (start, end) = if sel_start < input_state.cursor_position {
-                    (sel_start, input_state.cursor_position)
-                } else {
-                    (input_state.cursor_position, sel_start)
-                };
-                i >= start && i < end
-            } else {
-                false
-            };
-
-            let style = if is_selected {
-                Style::default().bg(selection_bg).fg(Color::White)
-            } else {
-                Style::default().bg(input_bg).fg(Color::White)
-            };
-
-            input_spans.push(Span::styled(ch.to_string(), style));
-        }
-
-        // Add cursor
-        if input_state.cursor_position == input_state.input.len() {
-            input_spans.push(Span::styled(
-                "_",
-                Style::default()
-                    .bg(input_bg)
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::SLOW_BLINK),
-            ));
-        } else {
-            // Insert cursor indicator at position
-            let cursor_style = Style::default().bg(Color::Yellow).fg(Color::Black);
-            if input_state.cursor_position < input_spans.len() {
-                let ch = input_state
-                    .input
-                    .chars()
-                    .nth(input_state.cursor_position)
-                    .unwrap_or(' ');
-                input_spans[input_state.cursor_position] =
-                    Span::styled(ch.to_string(), cursor_style);
-            }
-        }
-
-        let input = Line::from(input_spans);
-        let input_paragraph = Paragraph::new(input);
+        let input_style = Style::default().bg(Color::Rgb(50, 50, 50)).fg(Color::White);
+        let input = input_state.input.to_line(
+            input_style,
+            true,
+            dialog_chunks[2].width.saturating_sub(1) as usize,
+        );
+        let input_paragraph = Paragraph::new(input).style(input_style);
         frame.render_widget(input_paragraph, dialog_chunks[2]);
 
         // Buttons (now at index 4 after adding spacing) with hover effects
@@ -522,37 +500,18 @@ impl UI {
         let bg_style = Style::default().bg(Color::Rgb(40, 40, 40));
         frame.render_widget(Block::default().style(bg_style), area);
 
-        // Split into rows for find and optionally replace
-        let rows = if find_state.is_replace_mode {
-            Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([Constraint::Length(1), Constraint::Length(1)])
-                .split(area)
-        } else {
-            Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([Constraint::Length(1)])
-                .split(area)
+        let layout = FindBarLayout::new(area, find_state.is_replace_mode);
+        let button_style = |style: Style, target: FindBarTarget| {
+            if find_state.hovered == Some(target) {
+                hover_style(style)
+            } else {
+                style
+            }
         };
 
-        // Draw find row
-        let find_row = rows[0];
-        let find_chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Length(10), // "Find:" label (aligned with Replace)
-                Constraint::Min(20),    // Input field (flexible)
-                Constraint::Length(12), // Match counter
-                Constraint::Length(12), // Find Next button (with padding)
-                Constraint::Length(5),  // Case button
-                Constraint::Length(5),  // Whole word button
-                Constraint::Length(2),  // Right padding
-            ])
-            .split(find_row);
-
         // Find label
         let find_label = Span::styled("  Find:", Style::default().fg(Color::Gray));
-        frame.render_widget(Paragraph::new(find_label), find_chunks[0]);
+        frame.render_widget(Paragraph::new(find_label), layout.find_label);
 
         // Find input field
         let find_input_style = if find_state.focused_field == FindFocusedField::Find {
@@ -561,15 +520,14 @@ impl UI {
             Style::default().bg(Color::Rgb(50, 50, 50)).fg(Color::Gray)
         };
 
-        let mut find_text = find_state.find_query.clone();
-        if find_state.focused_field == FindFocusedField::Find
-            && find_state.find_cursor_position <= find_text.len()
-        {
-            find_text.insert(find_state.find_cursor_position, '│');
-        }
+        let find_text = find_state.find_input.to_line(
+            find_input_style,
+            find_state.focused_field == FindFocusedField::Find,
+            layout.find_input.width as usize,
+        );
 
         let find_input = Paragraph::new(find_text).style(find_input_style);
-        frame.render_widget(find_input, find_chunks[1]);
+        frame.render_widget(find_input, layout.find_input);
 
         // Match counter
         let match_text = if !find_state.matches.is_empty() {
@@ -578,7 +536,7 @@ impl UI {
             } else {
                 format!(" 0/{} ", find_state.matches.len())
             }
-        } else if !find_state.find_query.is_empty() {
+        } else if !find_state.find_input.is_empty() {
             " No match ".to_string()
         } else {
             String::new()
@@ -586,17 +544,18 @@ impl UI {
         let match_counter = Paragraph::new(match_text)
             .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::DIM))
             .alignment(Alignment::Center);
-        frame.render_widget(match_counter, find_chunks[2]);
+        frame.render_widget(match_counter, layout.match_counter);
 
         // Find Next button with padding
         let find_next_btn = Paragraph::new(" Find Next ")
-            .style(
+            .style(button_style(
                 Style::default()
                     .bg(Color::Rgb(60, 90, 120))
                     .fg(Color::White),
-            )
+                FindBarTarget::FindNext,
+            ))
             .alignment(Alignment::Center);
-        frame.render_widget(find_next_btn, find_chunks[3]);
+        frame.render_widget(find_next_btn, layout.find_next);
 
         // Case sensitive button
         let case_btn_style = if find_state.case_sensitive {
@@ -610,9 +569,25 @@ impl UI {
                 .fg(Color::Rgb(150, 150, 150))
         };
         let case_btn = Paragraph::new(" Aa ")
-            .style(case_btn_style)
+            .style(button_style(case_btn_style, FindBarTarget::CaseSensitive))
             .alignment(Alignment::Center);
-        frame.render_widget(case_btn, find_chunks[4]);
+        frame.render_widget(case_btn, layout.case_button);
+
+        // Accent insensitive button
+        let accent_btn_style = if find_state.ignore_accents {
+            Style::default()
+                .bg(Color::Rgb(70, 120, 70))
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+                .bg(Color::Rgb(50, 50, 50))
+                .fg(Color::Rgb(150, 150, 150))
+        };
+        let accent_btn = Paragraph::new(" é ")
+            .style(button_style(accent_btn_style, FindBarTarget::IgnoreAccents))
+            .alignment(Alignment::Center);
+        frame.render_widget(accent_btn, layout.accent_button);
 
         // Whole word button
         let word_btn_style = if find_state.whole_word {
@@ -626,32 +601,50 @@ impl UI {
                 .fg(Color::Rgb(150, 150, 150))
         };
         let word_btn = Paragraph::new(" W ")
-            .style(word_btn_style)
+            .style(button_style(word_btn_style, FindBarTarget::WholeWord))
             .alignment(Alignment::Center);
-        frame.render_widget(word_btn, find_chunks[5]);
+        frame.render_widget(word_btn, layout.word_button);
+
+        // Search in the enclosing block button
+        let block_btn_style = if find_state.block.is_some() {
+            Style::default()
+                .bg(Color::Rgb(70, 120, 70))
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+                .bg(Color::Rgb(50, 50, 50))
+                .fg(Color::Rgb(150, 150, 150))
+        };
+        let block_btn = Paragraph::new(" {} ")
+            .style(button_style(block_btn_style, FindBarTarget::InBlock))
+            .alignment(Alignment::Center);
+        frame.render_widget(block_btn, layout.block_button);
+
+        // Highlight all matches button
+        let all_btn_style = if find_state.highlight_all {
+            Style::default()
+                .bg(Color::Rgb(70, 120, 70))
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+                .bg(Color::Rgb(50, 50, 50))
+                .fg(Color::Rgb(150, 150, 150))
+        };
+        let all_btn = Paragraph::new(" All ")
+            .style(button_style(all_btn_style, FindBarTarget::HighlightAll))
+            .alignment(Alignment::Center);
+        frame.render_widget(all_btn, layout.highlight_button);
 
         // Right padding (no close button)
         // Close functionality is handled by pressing Escape
 
         // Draw replace row if in replace mode
-        if find_state.is_replace_mode && rows.len() > 1 {
-            let replace_row = rows[1];
-            let replace_chunks = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([
-                    Constraint::Length(10), // "Replace:" label (aligned with Find)
-                    Constraint::Min(20),    // Input field (flexible, same as Find)
-                    Constraint::Length(12), // Space matching Find's match counter
-                    Constraint::Length(12), // Replace button (matches Find Next position)
-                    Constraint::Length(5),  // Space matching Case button
-                    Constraint::Length(5),  // Space matching Whole word button
-                    Constraint::Length(2),  // Right padding (same as Find)
-                ])
-                .split(replace_row);
-
+        if let Some(replace_row) = &layout.replace_row {
             // Replace label
             let replace_label = Span::styled("  Replace:", Style::default().fg(Color::Gray));
-            frame.render_widget(Paragraph::new(replace_label), replace_chunks[0]);
+            frame.render_widget(Paragraph::new(replace_label), replace_row.label);
 
             // Replace input field
             let replace_input_style = if find_state.focused_field == FindFocusedField::Replace {
@@ -660,52 +653,53 @@ impl UI {
                 Style::default().bg(Color::Rgb(50, 50, 50)).fg(Color::Gray)
             };
 
-            let mut replace_text = find_state.replace_query.clone();
-            if find_state.focused_field == FindFocusedField::Replace
-                && find_state.replace_cursor_position <= replace_text.len()
-            {
-                replace_text.insert(find_state.replace_cursor_position, '│');
-            }
+            let replace_text = find_state.replace_input.to_line(
+                replace_input_style,
+                find_state.focused_field == FindFocusedField::Replace,
+                replace_row.input.width as usize,
+            );
 
             let replace_input = Paragraph::new(replace_text).style(replace_input_style);
-            frame.render_widget(replace_input, replace_chunks[1]);
+            frame.render_widget(replace_input, replace_row.input);
 
             // Empty space for alignment with Find row
             // (aligns with match counter in Find row)
 
             // Replace button (aligns with Find Next button)
             let replace_btn = Paragraph::new(" Replace ")
-                .style(
+                .style(button_style(
                     Style::default()
                         .bg(Color::Rgb(50, 100, 50))
                         .fg(Color::White),
-                )
+                    FindBarTarget::Replace,
+                ))
                 .alignment(Alignment::Center);
-            frame.render_widget(replace_btn, replace_chunks[3]);
-
-            // Replace All button (spans positions 4 and 5)
-            let replace_all_area = Rect {
-                x: replace_chunks[4].x,
-                y: replace_chunks[4].y,
-                width: replace_chunks[4].width + replace_chunks[5].width,
-                height: replace_chunks[4].height,
-            };
+            frame.render_widget(replace_btn, replace_row.replace_button);
+
+            // Replace All button (under the Aa, é, W, {} and All buttons)
             let replace_all_btn = Paragraph::new(" Replace All ")
-                .style(
+                .style(button_style(
                     Style::default()
                         .bg(Color::Rgb(50, 100, 50))
                         .fg(Color::White),
-                )
+                    FindBarTarget::ReplaceAll,
+                ))
                 .alignment(Alignment::Center);
-            frame.render_widget(replace_all_btn, replace_all_area);
+            frame.render_widget(replace_all_btn, replace_row.replace_all_button);
         }
     }
 
     fn draw_file_picker(&self, frame: &mut Frame, picker_state: &crate::menu::FilePickerState) {
         let size = frame.area();
 
-        // Center the file picker modal - make it slightly larger without border
-        let modal_width = 80u16.min(size.width.saturating_sub(4));
+        // Center the file picker modal - make it slightly larger without border.
+        // Wide terminals get room for a preview of the highlighted entry.
+        let show_preview = size.width >= 124;
+        let modal_width = if show_preview {
+            120u16.min(size.width.saturating_sub(4))
+        } else {
+            80u16.min(size.width.saturating_sub(4))
+        };
         let modal_height = 28u16.min(size.height.saturating_sub(4));
         let modal_x = (size.width.saturating_sub(modal_width)) / 2;
         let modal_y = (size.height.saturating_sub(modal_height)) / 2;
@@ -743,41 +737,57 @@ impl UI {
         };
 
         // Draw search input with proper padding
-        let search_text = if picker_state.search_query.is_empty() {
-            "  Type to search files...".to_string()
+        let search_bg = Color::Rgb(35, 35, 40);
+        let search_line = if picker_state.search_input.is_empty() {
+            Line::from(vec![
+                Span::raw("  "),
+                Span::styled("│", Style::default().fg(Color::Cyan).bg(search_bg)),
+                Span::styled(
+                    "Type to search files...",
+                    Style::default().fg(Color::Rgb(100, 100, 100)).bg(search_bg),
+                ),
+            ])
         } else {
-            format!("  {}", picker_state.search_query)
+            let mut line = picker_state.search_input.to_line(
+                Style::default().fg(Color::White).bg(search_bg),
+                true,
+                search_area.width.saturating_sub(2) as usize,
+            );
+            line.spans.insert(0, Span::raw("  "));
+            line
         };
 
-        let search_style = if picker_state.search_query.is_empty() {
-            Style::default()
-                .fg(Color::Rgb(100, 100, 100))
-                .bg(Color::Rgb(35, 35, 40))
+        let search_input = Paragraph::new(search_line).style(Style::default().bg(search_bg));
+        frame.render_widget(search_input, search_area);
+
+        // List on the left, preview on the right
+        let (list_area, preview_area) = if show_preview {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Length(60),
+                    Constraint::Length(1),
+                    Constraint::Min(0),
+                ])
+                .split(modal_chunks[1]);
+            (columns[0], Some(columns[2]))
         } else {
-            Style::default().fg(Color::White).bg(Color::Rgb(35, 35, 40))
+            (modal_chunks[1], None)
         };
-
-        let mut search_spans = vec![Span::styled(&search_text, search_style)];
-        // Add cursor at the end if there's a query
-        if !picker_state.search_query.is_empty() {
-            search_spans.push(Span::styled(
-                "│",
-                Style::default().fg(Color::Cyan).bg(Color::Rgb(35, 35, 40)),
-            ));
+        if let (Some(area), Some(item)) = (preview_area, picker_state.highlighted_item()) {
+            if item.name != ".." {
+                self.draw_file_preview(frame, area, &picker_state.preview(&item.path));
+            }
         }
 
-        let search_input = Paragraph::new(Line::from(search_spans))
-            .style(Style::default().bg(Color::Rgb(35, 35, 40)));
-        frame.render_widget(search_input, search_area);
-
         // File list with two lines per item when searching
-        let is_searching = !picker_state.search_query.is_empty();
+        let is_searching = !picker_state.search_input.is_empty();
         let items_per_entry = if is_searching { 2 } else { 1 };
 
         let total_items = picker_state.filtered_items.len();
 
         // Calculate scrollbar area
-        let scrollbar_width = if total_items * items_per_entry > modal_chunks[1].height as usize {
+        let scrollbar_width = if total_items * items_per_entry > list_area.height as usize {
             1
         } else {
             0
@@ -786,7 +796,7 @@ impl UI {
         let file_list_chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Min(0), Constraint::Length(scrollbar_width)])
-            .split(modal_chunks[1]);
+            .split(list_area);
 
         let file_content_area = file_list_chunks[0];
         let file_scrollbar_area = if scrollbar_width > 0 {
@@ -807,6 +817,7 @@ impl UI {
         };
 
         let mut file_lines = Vec::new();
+        let mut icons = Vec::new();
 
         for (i, item) in picker_state
             .filtered_items
@@ -850,8 +861,11 @@ impl UI {
                 file_icons::get_file_icon(&item.path)
             };
 
-            // First line: icon and name (padded to content area width)
-            let name_line = format!("  {}  {}", icon, item.name);
+            // First line: icon and name (padded to content area width). The
+            // icon is drawn over its column after the list.
+            icons.push((file_lines.len() as u16, icon, style));
+            let indent = " ".repeat(file_icons::icon_columns() as usize + 4);
+            let name_line = format!("{}{}", indent, item.name);
             let content_width = file_content_area.width as usize;
             let padded_name_line = format!("{:<width$}", name_line, width = content_width);
             file_lines.push(Line::from(Span::styled(padded_name_line, style)));
@@ -864,7 +878,7 @@ impl UI {
                     } else {
                         item.relative_path.clone()
                     };
-                let path_line = format!("      {}", path_to_show);
+                let path_line = format!("{}{}", indent, path_to_show);
                 let padded_path_line = format!("{:<width$}", path_line, width = content_width);
                 file_lines.push(Line::from(Span::styled(padded_path_line, dim_style)));
             }
@@ -872,6 +886,13 @@ impl UI {
 
         let file_list = Paragraph::new(file_lines);
         frame.render_widget(file_list, file_content_area);
+        let limit = file_content_area.x + file_content_area.width;
+        for (row, icon, style) in icons {
+            if row < file_content_area.height {
+                let (x, y) = (file_content_area.x + 2, file_content_area.y + row);
+                file_icons::draw_icon(frame.buffer_mut(), x, y, limit, icon, style);
+            }
+        }
 
         // Render scrollbar if needed
         if let Some(scrollbar_area) = file_scrollbar_area {
@@ -885,4 +906,50 @@ impl UI {
             frame.render_widget(scrollbar, scrollbar_area);
         }
     }
+
+    /// Dim, read-only look at the highlighted picker entry
+    fn draw_file_preview(&self, frame: &mut Frame, area: Rect, preview: &crate::menu::FilePreview) {
+        use crate::menu::FilePreview;
+
+        let background = Color::Rgb(30, 30, 35);
+        let text_style = Style::default().fg(Color::Rgb(140, 140, 140)).bg(background);
+        let note_style = Style::default().fg(Color::Rgb(100, 100, 100)).bg(background);
+        let width = area.width as usize;
+        let clip = |line: &str| -> String {
+            let mut clipped = String::new();
+            let mut used = 0;
+            for ch in line.chars() {
+                let w = UnicodeWidthChar::width(ch).unwrap_or(0);
+                if used + w > width.saturating_sub(1) {
+                    break;
+                }
+                used += w;
+                clipped.push(ch);
+            }
+            format!(" {}", clipped)
+        };
+
+        let lines: Vec<Line> = match preview {
+            FilePreview::Text(lines) if lines.is_empty() => {
+                vec![Line::from(Span::styled(" Empty file", note_style))]
+            }
+            FilePreview::Text(lines) | FilePreview::Directory(lines) => lines
+                .iter()
+                .map(|line| Line::from(Span::styled(clip(line), text_style)))
+                .collect(),
+            FilePreview::Binary(size) => vec![Line::from(Span::styled(
+                format!(" Binary file ({} bytes)", size),
+                note_style,
+            ))],
+            FilePreview::Unreadable(error) => vec![Line::from(Span::styled(
+                clip(&format!("Cannot read: {}", error)),
+                note_style,
+            ))],
+        };
+
+        frame.render_widget(
+            Paragraph::new(lines).style(Style::default().bg(background)),
+            area,
+        );
+    }
 }
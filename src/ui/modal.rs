@@ -0,0 +1,423 @@
+use crate::paste::ConflictChoice;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use std::path::PathBuf;
+use unicode_width::UnicodeWidthStr;
+
+/// What happens when a modal button is chosen. The app runs the action after
+/// the modal has been closed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModalAction {
+    Dismiss,
+    CloseTab(usize),
+    DeleteScratchTab(usize), // Close a scratch buffer and remove its file
+    SaveAndCloseTab(usize),
+    Quit,
+    SaveAllAndQuit,
+    SaveTabsAndQuit(Vec<usize>), // Filled with the checked items' values
+    DeletePath(PathBuf),
+    OverwriteTab(usize),
+    ReloadTab(usize),
+    ResolvePasteConflict {
+        choice: ConflictChoice,
+        apply_to_all: bool, // Set from the checklist when the button is chosen
+    },
+    CancelPaste,
+    ReplaceInFiles {
+        write: bool, // Write the files directly instead of opening them as modified tabs
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModalKind {
+    Info,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModalButton {
+    pub label: String,
+    pub action: ModalAction,
+    pub hotkey: Option<char>,
+}
+
+impl ModalButton {
+    pub fn new(label: &str, action: ModalAction) -> Self {
+        Self {
+            label: label.to_string(),
+            action,
+            hotkey: None,
+        }
+    }
+
+    pub fn with_hotkey(mut self, key: char) -> Self {
+        self.hotkey = Some(key.to_ascii_lowercase());
+        self
+    }
+
+    fn width(&self) -> u16 {
+        // "[ label ]"
+        self.label.width() as u16 + 4
+    }
+}
+
+/// Checkbox row shown between the message and the buttons
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModalCheckItem {
+    pub label: String,
+    pub checked: bool,
+    pub value: usize,
+}
+
+/// Screen geometry of a modal, shared by rendering and mouse hit-testing
+pub struct ModalLayout {
+    pub area: Rect,
+    pub message_area: Rect,
+    pub items_area: Rect,
+    pub buttons: Vec<Rect>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Modal {
+    pub kind: ModalKind,
+    pub title: String,
+    pub message: String,
+    pub buttons: Vec<ModalButton>,
+    pub selected: usize,
+    pub items: Vec<ModalCheckItem>,
+    pub item_index: usize,
+    pub item_scroll: usize, // First checklist row shown when they don't all fit
+    cancel_index: usize,
+}
+
+const BUTTON_GAP: u16 = 2;
+
+impl Modal {
+    pub fn new(kind: ModalKind, title: &str, message: &str) -> Self {
+        Self {
+            kind,
+            title: title.to_string(),
+            message: message.to_string(),
+            buttons: Vec::new(),
+            selected: 0,
+            items: Vec::new(),
+            item_index: 0,
+            item_scroll: 0,
+            cancel_index: 0,
+        }
+    }
+
+    /// Single OK button that just closes the dialog
+    pub fn info(message: &str) -> Self {
+        Self::new(ModalKind::Info, " Info ", message)
+            .with_button(ModalButton::new("OK", ModalAction::Dismiss))
+    }
+
+    /// Warning with a safe "Cancel" default and one confirming button
+    pub fn confirm(message: &str, confirm_label: &str, action: ModalAction) -> Self {
+        Self::new(ModalKind::Warning, " Warning ", message)
+            .with_button(ModalButton::new("Cancel", ModalAction::Dismiss).with_hotkey('n'))
+            .with_button(ModalButton::new(confirm_label, action).with_hotkey('y'))
+            .with_default(0)
+            .with_cancel(0)
+    }
+
+    /// Save / Don't Save / Cancel prompt for discarding modified buffers
+    pub fn unsaved_changes(message: &str, save: ModalAction, discard: ModalAction) -> Self {
+        Self::new(ModalKind::Warning, " Unsaved Changes ", message)
+            .with_button(ModalButton::new("Save", save).with_hotkey('s'))
+            .with_button(ModalButton::new("Don't Save", discard).with_hotkey('d'))
+            .with_button(ModalButton::new("Cancel", ModalAction::Dismiss).with_hotkey('c'))
+            .with_default(0)
+            .with_cancel(2)
+    }
+
+    pub fn with_item(mut self, label: &str, checked: bool, value: usize) -> Self {
+        self.items.push(ModalCheckItem {
+            label: label.to_string(),
+            checked,
+            value,
+        });
+        self
+    }
+
+    pub fn with_button(mut self, button: ModalButton) -> Self {
+        self.buttons.push(button);
+        self
+    }
+
+    /// Button selected when the dialog opens (triggered by Enter)
+    pub fn with_default(mut self, index: usize) -> Self {
+        self.selected = index.min(self.buttons.len().saturating_sub(1));
+        self
+    }
+
+    /// Button triggered by Esc
+    pub fn with_cancel(mut self, index: usize) -> Self {
+        self.cancel_index = index.min(self.buttons.len().saturating_sub(1));
+        self
+    }
+
+    pub fn cancel_action(&self) -> ModalAction {
+        self.button_action(self.cancel_index)
+    }
+
+    /// Action for a button, with checklist selections filled in
+    pub fn button_action(&self, index: usize) -> ModalAction {
+        match self.buttons.get(index).map(|button| &button.action) {
+            Some(ModalAction::SaveTabsAndQuit(_)) => ModalAction::SaveTabsAndQuit(
+                self.items
+                    .iter()
+                    .filter(|item| item.checked)
+                    .map(|item| item.value)
+                    .collect(),
+            ),
+            Some(ModalAction::ResolvePasteConflict { choice, .. }) => {
+                ModalAction::ResolvePasteConflict {
+                    choice: *choice,
+                    apply_to_all: self.items.iter().any(|item| item.checked),
+                }
+            }
+            Some(action) => action.clone(),
+            None => ModalAction::Dismiss,
+        }
+    }
+
+    pub fn toggle_item(&mut self, index: usize) {
+        if let Some(item) = self.items.get_mut(index) {
+            item.checked = !item.checked;
+            self.item_index = index;
+        }
+    }
+
+    fn message_lines(&self, width: usize) -> Vec<String> {
+        let mut lines = Vec::new();
+        for paragraph in self.message.lines() {
+            let mut current = String::new();
+            for word in paragraph.split(' ') {
+                if !current.is_empty() && current.width() + 1 + word.width() > width {
+                    lines.push(std::mem::take(&mut current));
+                }
+                if !current.is_empty() {
+                    current.push(' ');
+                }
+                current.push_str(word);
+            }
+            lines.push(current);
+        }
+        lines
+    }
+
+    pub fn layout(&self, screen: Rect) -> ModalLayout {
+        let buttons_width: u16 = self.buttons.iter().map(|b| b.width()).sum::<u16>()
+            + BUTTON_GAP * self.buttons.len().saturating_sub(1) as u16;
+        let longest_line = self
+            .message
+            .lines()
+            .map(|l| l.width())
+            .chain(self.items.iter().map(|item| item.label.width() + 4))
+            .max()
+            .unwrap_or(0) as u16;
+
+        let max_width = 80u16.min(screen.width.saturating_sub(4)).max(10);
+        let width = (longest_line.max(buttons_width) + 4).clamp(30.min(max_width), max_width);
+        let lines = self.message_lines(width.saturating_sub(4) as usize).len() as u16;
+        let item_rows = self.items.len() as u16;
+        let items_height = if item_rows > 0 { item_rows + 1 } else { 0 };
+        // Border, padding, message, items, gap, buttons, padding, border
+        let height = (lines + items_height + 6).min(screen.height);
+
+        let area = Rect {
+            x: screen.x + (screen.width.saturating_sub(width)) / 2,
+            y: screen.y + (screen.height.saturating_sub(height)) / 2,
+            width,
+            height,
+        };
+
+        let message_area = Rect {
+            x: area.x + 2,
+            y: area.y + 2,
+            width: width.saturating_sub(4),
+            height: lines,
+        };
+
+        let items_area = Rect {
+            x: area.x + 2,
+            y: message_area.y + lines + 1,
+            width: width.saturating_sub(4),
+            height: item_rows.min(height.saturating_sub(lines + 6)),
+        };
+
+        let button_y = area.y + height.saturating_sub(3);
+        let mut x = area.x + (width.saturating_sub(buttons_width)) / 2;
+        let buttons = self
+            .buttons
+            .iter()
+            .map(|button| {
+                let rect = Rect {
+                    x,
+                    y: button_y,
+                    width: button.width(),
+                    height: 1,
+                };
+                x += button.width() + BUTTON_GAP;
+                rect
+            })
+            .collect();
+
+        ModalLayout {
+            area,
+            message_area,
+            items_area,
+            buttons,
+        }
+    }
+
+    pub fn button_at(&self, screen: Rect, x: u16, y: u16) -> Option<usize> {
+        self.layout(screen).buttons.iter().position(|rect| {
+            x >= rect.x && x < rect.x + rect.width && y >= rect.y && y < rect.y + rect.height
+        })
+    }
+
+    pub fn item_at(&self, screen: Rect, x: u16, y: u16) -> Option<usize> {
+        let area = self.layout(screen).items_area;
+        if x >= area.x && x < area.x + area.width && y >= area.y && y < area.y + area.height {
+            Some(self.item_scroll + (y - area.y) as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Scroll the checklist by `delta` rows, keeping it within its rows
+    pub fn scroll_items(&mut self, screen: Rect, delta: isize) {
+        let visible = self.layout(screen).items_area.height as usize;
+        let max = self.items.len().saturating_sub(visible);
+        self.item_scroll = self.item_scroll.saturating_add_signed(delta).min(max);
+    }
+
+    /// Scroll the checklist so the current row is on screen
+    pub fn reveal_item(&mut self, screen: Rect) {
+        let visible = (self.layout(screen).items_area.height as usize).max(1);
+        if self.item_index < self.item_scroll {
+            self.item_scroll = self.item_index;
+        } else if self.item_index >= self.item_scroll + visible {
+            self.item_scroll = self.item_index + 1 - visible;
+        }
+    }
+
+    /// Handle a key press. Returns the chosen action once the dialog should close.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<ModalAction> {
+        let count = self.buttons.len().max(1);
+        match (key.code, key.modifiers) {
+            (KeyCode::Char('q'), KeyModifiers::CONTROL) => {
+                Some(self.cancel_action())
+            }
+            // With a checklist, Up/Down pick a row and Space toggles it
+            (KeyCode::Up, _) if !self.items.is_empty() => {
+                self.item_index = self.item_index.saturating_sub(1);
+                None
+            }
+            (KeyCode::Down, _) if !self.items.is_empty() => {
+                self.item_index = (self.item_index + 1).min(self.items.len() - 1);
+                None
+            }
+            (KeyCode::Char(' '), KeyModifiers::NONE) if !self.items.is_empty() => {
+                self.toggle_item(self.item_index);
+                None
+            }
+            (KeyCode::Enter, _) | (KeyCode::Char(' '), KeyModifiers::NONE) => {
+                Some(self.button_action(self.selected))
+            }
+            (KeyCode::Left, _) | (KeyCode::BackTab, _) => {
+                self.selected = (self.selected + count - 1) % count;
+                None
+            }
+            (KeyCode::Right, _) | (KeyCode::Tab, _) => {
+                self.selected = (self.selected + 1) % count;
+                None
+            }
+            (KeyCode::Char(c), KeyModifiers::NONE) | (KeyCode::Char(c), KeyModifiers::SHIFT) => {
+                let c = c.to_ascii_lowercase();
+                self.buttons
+                    .iter()
+                    .position(|button| button.hotkey == Some(c))
+                    .map(|index| self.button_action(index))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let layout = self.layout(frame.area());
+
+        let (border_bg, button_bg) = match self.kind {
+            ModalKind::Warning => (Color::Red, Color::Rgb(200, 50, 50)),
+            ModalKind::Info => (Color::Blue, Color::Rgb(50, 100, 200)),
+        };
+
+        frame.render_widget(Clear, layout.area);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(self.title.as_str())
+            .style(Style::default().bg(border_bg).fg(Color::White));
+        frame.render_widget(block, layout.area);
+
+        let lines: Vec<Line> = self
+            .message_lines(layout.message_area.width as usize)
+            .into_iter()
+            .map(Line::from)
+            .collect();
+        let message = Paragraph::new(lines)
+            .alignment(Alignment::Center)
+            .style(Style::default().fg(Color::White));
+        frame.render_widget(message, layout.message_area);
+
+        if !self.items.is_empty() {
+            let rows: Vec<Line> = self
+                .items
+                .iter()
+                .enumerate()
+                .skip(self.item_scroll)
+                .map(|(index, item)| {
+                    let mark = if item.checked { "[x] " } else { "[ ] " };
+                    let style = if index == self.item_index {
+                        Style::default()
+                            .bg(Color::White)
+                            .fg(Color::Black)
+                            .add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default().fg(Color::White)
+                    };
+                    Line::from(Span::styled(format!("{}{}", mark, item.label), style))
+                })
+                .collect();
+            frame.render_widget(Paragraph::new(rows), layout.items_area);
+        }
+
+        for (index, (button, rect)) in self.buttons.iter().zip(&layout.buttons).enumerate() {
+            let (text, style) = if index == self.selected {
+                (
+                    format!("[ {} ]", button.label),
+                    Style::default()
+                        .bg(button_bg)
+                        .fg(Color::White)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                (
+                    format!("  {}  ", button.label),
+                    Style::default()
+                        .bg(Color::Rgb(60, 60, 60))
+                        .fg(Color::Rgb(200, 200, 200)),
+                )
+            };
+            frame.render_widget(Paragraph::new(Span::styled(text, style)), *rect);
+        }
+    }
+}
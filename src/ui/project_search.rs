@@ -0,0 +1,389 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph},
+    Frame,
+};
+use std::ops::Range;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::menu::{ProjectSearchState, ResultRow, SearchField, TodoListState};
+use crate::ui::TextInput;
+
+const BACKGROUND: Color = Color::Rgb(25, 25, 30);
+const INPUT_BACKGROUND: Color = Color::Rgb(35, 35, 40);
+const SELECTED_BACKGROUND: Color = Color::Rgb(60, 60, 70);
+const DIM: Color = Color::Rgb(100, 100, 100);
+
+/// Centered box holding the input rows, the summary and the matches
+pub fn modal_area(screen: Rect) -> Rect {
+    let width = 100u16.min(screen.width.saturating_sub(4));
+    let height = 30u16.min(screen.height.saturating_sub(4));
+    Rect {
+        x: (screen.width.saturating_sub(width)) / 2,
+        y: (screen.height.saturating_sub(height)) / 2,
+        width,
+        height,
+    }
+}
+
+/// Rows of the match list; the mouse handler hit-tests against this too
+pub fn list_area(screen: Rect) -> Rect {
+    let area = modal_area(screen);
+    Rect {
+        x: area.x + 1,
+        y: area.y + 6,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(7),
+    }
+}
+
+/// Rows of the TODO list, under its summary line
+pub fn todo_list_area(screen: Rect) -> Rect {
+    let area = modal_area(screen);
+    Rect {
+        x: area.x + 1,
+        y: area.y + 3,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(4),
+    }
+}
+
+/// Column of a row's checkbox within the list
+pub fn checkbox_column(row: ResultRow) -> u16 {
+    match row {
+        ResultRow::File(_) => 2,
+        ResultRow::Match(_) => 4,
+    }
+}
+
+/// First row shown, keeping the selected row in view
+pub fn first_visible(selected_index: usize, visible: usize) -> usize {
+    (selected_index + 1).saturating_sub(visible.max(1))
+}
+
+fn input_line(input: &TextInput, placeholder: &str, focused: bool, width: usize) -> Line<'static> {
+    let mut line = if input.is_empty() {
+        let cursor = if focused { "│" } else { " " };
+        Line::from(vec![
+            Span::styled(cursor, Style::default().fg(Color::Cyan)),
+            Span::styled(placeholder.to_string(), Style::default().fg(DIM)),
+        ])
+    } else {
+        input.to_line(Style::default().fg(Color::White), focused, width)
+    };
+    line.spans.insert(0, Span::raw("  "));
+    line
+}
+
+/// The longest start of `text` that fits in `width` columns
+fn fit(text: &str, width: usize) -> String {
+    let mut used = 0;
+    text.chars()
+        .take_while(|ch| {
+            used += ch.width().unwrap_or(0);
+            used <= width
+        })
+        .collect()
+}
+
+pub fn draw_project_search(frame: &mut Frame, state: &ProjectSearchState) {
+    let area = modal_area(frame.area());
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Block::default().style(Style::default().bg(BACKGROUND)),
+        area,
+    );
+    let row = |offset: u16| Rect {
+        x: area.x + 1,
+        y: area.y + offset,
+        width: area.width.saturating_sub(2),
+        height: 1,
+    };
+
+    // Query row, with the match-case and whole-word toggles at its end
+    let toggles = [(" Aa ", state.case_sensitive), (" ab ", state.whole_word)];
+    let toggles_width = toggles.len() * 4 + 1;
+    let query_area = row(1);
+    let input_width = (query_area.width as usize).saturating_sub(toggles_width + 2);
+    let mut query_line = input_line(
+        &state.query_input,
+        "Search in files...",
+        state.focus == SearchField::Query,
+        input_width,
+    );
+    let used: usize = query_line.spans.iter().map(|span| span.width()).sum();
+    query_line.spans.push(Span::raw(
+        " ".repeat((query_area.width as usize).saturating_sub(used + toggles_width)),
+    ));
+    for (label, on) in toggles {
+        let style = if on {
+            Style::default()
+                .fg(Color::Black)
+                .bg(Color::Cyan)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(DIM)
+        };
+        query_line.spans.push(Span::styled(label, style));
+    }
+    frame.render_widget(
+        Paragraph::new(query_line).style(Style::default().bg(INPUT_BACKGROUND)),
+        query_area,
+    );
+
+    let replace_area = row(2);
+    let replace_line = input_line(
+        &state.replace_input,
+        "Replace with...",
+        state.focus == SearchField::Replace,
+        (replace_area.width as usize).saturating_sub(3),
+    );
+    frame.render_widget(
+        Paragraph::new(replace_line).style(Style::default().bg(INPUT_BACKGROUND)),
+        replace_area,
+    );
+
+    let files_area = row(3);
+    let files_line = input_line(
+        &state.files_input,
+        "Files to include, !to exclude (e.g. *.rs, !target/**)",
+        state.focus == SearchField::Files,
+        (files_area.width as usize).saturating_sub(3),
+    );
+    frame.render_widget(
+        Paragraph::new(files_line).style(Style::default().bg(INPUT_BACKGROUND)),
+        files_area,
+    );
+
+    let rows = state.rows();
+    let results = state.results.lock();
+    let summary = if state.searched.is_none() || state.is_stale() {
+        "Enter searches · Tab next field · Alt+C match case · Alt+W whole word".to_string()
+    } else if !results.done {
+        format!(
+            "Searching... {} matches in {} files",
+            results.matches.len(),
+            results.files_searched
+        )
+    } else if state.is_replacing() {
+        format!(
+            "{} of {} occurrences checked · Ctrl+Space toggles · Alt+R replaces",
+            results.matches.len() - state.unchecked.len(),
+            results.matches.len()
+        )
+    } else if results.truncated {
+        format!(
+            "Stopped at {} matches (search.max_results)",
+            results.matches.len()
+        )
+    } else {
+        format!(
+            "{} matches in {} files",
+            results.matches.len(),
+            results.files_searched
+        )
+    };
+    frame.render_widget(
+        Paragraph::new(Line::from(Span::styled(
+            format!("  {}", summary),
+            Style::default().fg(DIM),
+        ))),
+        row(4),
+    );
+
+    // Files, each followed by its matching lines with the indentation dropped.
+    // With a replacement the lines preview it, with a checkbox each.
+    let list = list_area(frame.area());
+    let visible = list.height as usize;
+    let start = first_visible(state.selected_index, visible);
+    let width = list.width as usize;
+    let replacing = state.is_replacing();
+    let query_len = state.query_input.text().chars().count();
+    let replacement = state.replace_input.text();
+    let mut lines = Vec::new();
+    for (index, &result_row) in rows.iter().enumerate().skip(start).take(visible) {
+        let bg = if index == state.selected_index {
+            SELECTED_BACKGROUND
+        } else {
+            BACKGROUND
+        };
+        let checked = state.is_checked(&results.matches, result_row);
+        let checkbox = match (replacing, checked) {
+            (false, _) => "",
+            (true, true) => "[x] ",
+            (true, false) => "[ ] ",
+        };
+        let mut spans = Vec::new();
+        match result_row {
+            ResultRow::File(first) => {
+                spans.push(Span::raw(format!("  {}", checkbox)));
+                spans.push(Span::styled(
+                    results.matches[first].path.display().to_string(),
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ));
+            }
+            ResultRow::Match(at) => {
+                let found = &results.matches[at];
+                spans.push(Span::raw(format!("    {}", checkbox)));
+                spans.push(Span::styled(
+                    format!("{}: ", found.line + 1),
+                    Style::default().fg(DIM),
+                ));
+                let text = found.text.trim_start();
+                let indent = found.text.chars().count() - text.chars().count();
+                let start = found.column.saturating_sub(indent);
+                let replace_with = (replacing && checked).then_some(replacement);
+                spans.extend(occurrence_spans(
+                    text,
+                    start..start + query_len,
+                    replace_with,
+                    replacing && !checked,
+                ));
+            }
+        }
+        lines.push(fit_spans(spans, width, bg));
+    }
+    frame.render_widget(Paragraph::new(lines), list);
+}
+
+/// The TODO panel: a summary, then each file followed by its tagged lines
+pub fn draw_todo_list(frame: &mut Frame, state: &TodoListState) {
+    let area = modal_area(frame.area());
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Block::default().style(Style::default().bg(BACKGROUND)),
+        area,
+    );
+
+    let rows = state.rows();
+    let results = state.results.lock();
+    let summary = if !results.done {
+        format!(
+            "Collecting... {} TODOs, {} files searched",
+            results.matches.len(),
+            results.files_searched
+        )
+    } else if results.matches.is_empty() {
+        "No TODOs found (todo.patterns sets the tags)".to_string()
+    } else {
+        let stopped = if results.truncated {
+            " (stopped at search.max_results)"
+        } else {
+            ""
+        };
+        format!(
+            "{} TODOs{} · Enter opens · Esc closes",
+            results.matches.len(),
+            stopped
+        )
+    };
+    frame.render_widget(
+        Paragraph::new(Line::from(vec![
+            Span::styled(
+                "  TODO  ",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(summary, Style::default().fg(DIM)),
+        ])),
+        Rect {
+            x: area.x + 1,
+            y: area.y + 1,
+            width: area.width.saturating_sub(2),
+            height: 1,
+        },
+    );
+
+    let list = todo_list_area(frame.area());
+    let visible = list.height as usize;
+    let start = first_visible(state.selected_index, visible);
+    let width = list.width as usize;
+    let mut lines = Vec::new();
+    for (index, &result_row) in rows.iter().enumerate().skip(start).take(visible) {
+        let bg = if index == state.selected_index {
+            SELECTED_BACKGROUND
+        } else {
+            BACKGROUND
+        };
+        let mut spans = Vec::new();
+        match result_row {
+            ResultRow::File(first) => {
+                spans.push(Span::raw("  "));
+                spans.push(Span::styled(
+                    results.matches[first].path.display().to_string(),
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                ));
+            }
+            ResultRow::Match(at) => {
+                let found = &results.matches[at];
+                spans.push(Span::raw("    "));
+                spans.push(Span::styled(
+                    format!("{}: ", found.line + 1),
+                    Style::default().fg(DIM),
+                ));
+                let text = found.text.trim_start();
+                let (start, end) = state.tags.find(text).unwrap_or((0, 0));
+                spans.extend(occurrence_spans(text, start..end, None, false));
+            }
+        }
+        lines.push(fit_spans(spans, width, bg));
+    }
+    frame.render_widget(Paragraph::new(lines), list);
+}
+
+/// A line with an occurrence highlighted, or struck out and followed by its
+/// replacement
+fn occurrence_spans(
+    text: &str,
+    occurrence: Range<usize>,
+    replacement: Option<&str>,
+    dimmed: bool,
+) -> Vec<Span<'static>> {
+    let chars: Vec<char> = text.chars().collect();
+    let start = occurrence.start.min(chars.len());
+    let end = occurrence.end.clamp(start, chars.len());
+    let piece = |range: Range<usize>| chars[range].iter().collect::<String>();
+    let plain = Style::default().fg(if dimmed { DIM } else { Color::White });
+
+    let mut spans = vec![Span::styled(piece(0..start), plain)];
+    match replacement {
+        Some(replacement) => {
+            spans.push(Span::styled(
+                piece(start..end),
+                Style::default()
+                    .fg(Color::Red)
+                    .add_modifier(Modifier::CROSSED_OUT),
+            ));
+            spans.push(Span::styled(
+                replacement.to_string(),
+                Style::default().fg(Color::Green),
+            ));
+        }
+        None => spans.push(Span::styled(
+            piece(start..end),
+            plain.add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+        )),
+    }
+    spans.push(Span::styled(piece(end..chars.len()), plain));
+    spans
+}
+
+/// Cut spans to `width` columns and pad them to it, all on `bg`
+fn fit_spans(spans: Vec<Span<'static>>, width: usize, bg: Color) -> Line<'static> {
+    let mut room = width;
+    let mut fitted = Vec::new();
+    for span in spans {
+        let content = fit(&span.content, room);
+        room -= content.width();
+        fitted.push(Span::styled(content, span.style.bg(bg)));
+    }
+    fitted.push(Span::styled(" ".repeat(room), Style::default().bg(bg)));
+    Line::from(fitted)
+}
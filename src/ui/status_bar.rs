@@ -6,13 +6,26 @@ use ratatui::{
     Frame,
 };
 
-use crate::tab::TabManager;
+use std::path::Path;
+use unicode_width::UnicodeWidthStr;
 
-pub struct StatusBar {}
+use crate::tab::{Tab, TabManager};
+
+/// Average silent reading speed behind the reading time estimate
+const WORDS_PER_MINUTE: usize = 230;
+
+pub struct StatusBar {
+    branch: Option<String>, // For `{branch}`, read again by refresh_branch
+}
 
 impl StatusBar {
     pub fn new() -> Self {
-        Self {}
+        Self { branch: None }
+    }
+
+    /// Read the branch checked out in the repository around `root` again
+    pub fn refresh_branch(&mut self, root: &Path) {
+        self.branch = git_branch(root);
     }
 
     pub fn draw(
@@ -20,32 +33,63 @@ impl StatusBar {
         frame: &mut Frame,
         area: Rect,
         tab_manager: &TabManager,
-        status_message: Option<&String>,
+        job_summary: Option<String>,
+        overwrite_mode: bool,
+        mouse_captured: bool,
     ) {
+        // Without the mouse, a reminder how to get it back takes the far left
+        let area = if mouse_captured {
+            area
+        } else {
+            let text = " TERMINAL SELECTION (Alt+M ends) ";
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Length(text.width() as u16), Constraint::Min(0)])
+                .split(area);
+            let selection_status = Paragraph::new(Line::from(vec![Span::raw(text)]))
+                .style(Style::default().bg(Color::Rgb(215, 95, 0)).fg(Color::Black));
+            frame.render_widget(selection_status, chunks[0]);
+            chunks[1]
+        };
+
+        // Running background jobs get their own segment at the far right
+        let (area, jobs_segment) = split_jobs_segment(area, job_summary);
+        if let Some((jobs_area, text)) = jobs_segment {
+            let jobs_status = Paragraph::new(Line::from(vec![Span::raw(text)]))
+                .style(Style::default().bg(Color::Rgb(0, 90, 140)).fg(Color::White));
+            frame.render_widget(jobs_status, jobs_area);
+        }
+
+        // So does the remote host of `f1 sftp://...`, red once ssh stops answering
+        let (area, connection_segment) = split_connection_segment(area, crate::remote::status());
+        if let Some((connection_area, text, connected)) = connection_segment {
+            let background = if connected {
+                Color::Rgb(30, 110, 60)
+            } else {
+                Color::Rgb(160, 40, 40)
+            };
+            let connection_status = Paragraph::new(Line::from(vec![Span::raw(text)]))
+                .style(Style::default().bg(background).fg(Color::White));
+            frame.render_widget(connection_status, connection_area);
+        }
+
         if let Some(tab) = tab_manager.active_tab() {
             match tab {
-                crate::tab::Tab::Editor { cursor, path, name, modified, preview_mode, .. } => {
-                    let cursor_pos = format!(
-                        " L{}:C{} ",
-                        cursor.position.line + 1,
-                        cursor.position.column
-                    );
-
-                    let status_text = if let Some(message) = status_message {
-                        // Show temporary status message with warning styling
-                        format!(" {} ", message)
-                    } else {
-                        // Show normal file info
-                        let file_info = if let Some(path) = path {
-                            format!(" {} ", path.display())
-                        } else {
-                            format!(" {} ", name)
-                        };
+                crate::tab::Tab::Editor { path, name, modified, preview_mode, .. } => {
+                    let template = &crate::config::get().ui.status_template;
+                    let cursor_pos = cursor_text(tab, overwrite_mode);
+                    let indent_text = indent_text(tab);
+                    let prose_text = prose_text(tab);
 
-                        let modified_text = if *modified { " [Modified] " } else { "" };
-                        format!("{}{}", file_info, modified_text)
+                    let file_info = if let Some(path) = path {
+                        format!(" {} ", path.display())
+                    } else {
+                        format!(" {} ", name)
                     };
 
+                    let modified_text = if *modified { " [Modified] " } else { "" };
+                    let status_text = format!("{}{}", file_info, modified_text);
+
                     let f1_menu = " ☰ F1 ";
 
                     // Add preview/edit toggle indicator for markdown files (shows current state)
@@ -59,35 +103,48 @@ impl StatusBar {
                         ""
                     };
 
+                    // A template takes the place of every segment after the indicator
+                    let expanded = (!template.is_empty()).then(|| {
+                        expand_template(template, |field| match field {
+                            "branch" => Some(self.branch.clone().unwrap_or_default()),
+                            _ => template_field(tab, field, overwrite_mode),
+                        })
+                    });
+                    let (status_text, prose_text, indent_text, cursor_pos) = match expanded {
+                        Some(text) => {
+                            (format!(" {} ", text), String::new(), String::new(), String::new())
+                        }
+                        None => (status_text, prose_text, indent_text, cursor_pos),
+                    };
+
                     let chunks = Layout::default()
                         .direction(Direction::Horizontal)
                         .constraints([
                             Constraint::Length(6), // Exactly 6 characters for F1 button
                             Constraint::Length(preview_indicator.len() as u16), // Preview indicator
                             Constraint::Min(0),
-                            Constraint::Length(cursor_pos.len() as u16),
+                            Constraint::Length(prose_text.width() as u16),
+                            Constraint::Length(indent_text.width() as u16),
+                            Constraint::Length(cursor_pos.width() as u16),
                         ])
                         .split(area);
 
                     let f1_status = Paragraph::new(Line::from(vec![Span::raw(f1_menu)]))
                         .style(Style::default().bg(Color::Yellow).fg(Color::Black));
 
-                    let middle_status = if status_message.is_some() {
-                        // Use warning text color but same background for status messages
-                        Paragraph::new(Line::from(vec![Span::raw(status_text)])).style(
-                            Style::default()
-                                .bg(Color::Rgb(40, 40, 40))
-                                .fg(Color::Yellow),
-                        )
-                    } else {
-                        // Use normal colors for file info
-                        Paragraph::new(Line::from(vec![Span::raw(status_text)]))
-                            .style(Style::default().bg(Color::Rgb(40, 40, 40)).fg(Color::White))
-                    };
+                    let middle_status = Paragraph::new(Line::from(vec![Span::raw(status_text)]))
+                        .style(Style::default().bg(Color::Rgb(40, 40, 40)).fg(Color::White));
 
                     let right_status = Paragraph::new(Line::from(vec![Span::raw(cursor_pos)]))
                         .style(Style::default().bg(Color::Rgb(40, 40, 40)).fg(Color::White));
 
+                    let prose_status = Paragraph::new(Line::from(vec![Span::raw(prose_text)]))
+                        .style(Style::default().bg(Color::Rgb(40, 40, 40)).fg(Color::Gray));
+
+                    // Clicking the indentation segment cycles its style
+                    let indent_status = Paragraph::new(Line::from(vec![Span::raw(indent_text)]))
+                        .style(Style::default().bg(Color::Rgb(55, 55, 60)).fg(Color::White));
+
                     let preview_status = if !preview_indicator.is_empty() {
                         Some(
                             Paragraph::new(Line::from(vec![Span::raw(preview_indicator)])).style(
@@ -105,15 +162,13 @@ impl StatusBar {
                         frame.render_widget(preview_widget, chunks[1]);
                     }
                     frame.render_widget(middle_status, chunks[2]);
-                    frame.render_widget(right_status, chunks[3]);
+                    frame.render_widget(prose_status, chunks[3]);
+                    frame.render_widget(indent_status, chunks[4]);
+                    frame.render_widget(right_status, chunks[5]);
                 }
                 crate::tab::Tab::Terminal { name, modified, .. } => {
-                    let status_text = if let Some(message) = status_message {
-                        format!(" {} ", message)
-                    } else {
-                        let modified_text = if *modified { " [Modified] " } else { "" };
-                        format!(" {}{}", name, modified_text)
-                    };
+                    let modified_text = if *modified { " [Modified] " } else { "" };
+                    let status_text = format!(" {}{}", name, modified_text);
 
                     let f1_menu = " ☰ F1 ";
                     let terminal_indicator = " TERMINAL ";
@@ -133,16 +188,8 @@ impl StatusBar {
                     let terminal_status = Paragraph::new(Line::from(vec![Span::raw(terminal_indicator)]))
                         .style(Style::default().bg(Color::Green).fg(Color::Black));
 
-                    let middle_status = if status_message.is_some() {
-                        Paragraph::new(Line::from(vec![Span::raw(status_text)])).style(
-                            Style::default()
-                                .bg(Color::Rgb(40, 40, 40))
-                                .fg(Color::Yellow),
-                        )
-                    } else {
-                        Paragraph::new(Line::from(vec![Span::raw(status_text)]))
-                            .style(Style::default().bg(Color::Rgb(40, 40, 40)).fg(Color::White))
-                    };
+                    let middle_status = Paragraph::new(Line::from(vec![Span::raw(status_text)]))
+                        .style(Style::default().bg(Color::Rgb(40, 40, 40)).fg(Color::White));
 
                     frame.render_widget(f1_status, chunks[0]);
                     frame.render_widget(terminal_status, chunks[1]);
@@ -152,3 +199,174 @@ impl StatusBar {
         }
     }
 }
+
+/// Split off the background jobs segment at the far right, if any jobs run
+fn split_jobs_segment(area: Rect, job_summary: Option<String>) -> (Rect, Option<(Rect, String)>) {
+    let Some(summary) = job_summary else {
+        return (area, None);
+    };
+    let text = format!(" ⟳ {} (Alt+K cancels) ", summary);
+    let width = (text.width() as u16).min(area.width / 2);
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(width)])
+        .split(area);
+    (chunks[0], Some((chunks[1], text)))
+}
+
+fn split_connection_segment(
+    area: Rect,
+    status: Option<(String, bool)>,
+) -> (Rect, Option<(Rect, String, bool)>) {
+    let Some((host, connected)) = status else {
+        return (area, None);
+    };
+    let text = if connected {
+        format!(" ⇅ {} ", host)
+    } else {
+        format!(" ⇅ {} (disconnected) ", host)
+    };
+    let width = (text.width() as u16).min(area.width / 3);
+    let chunks = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Min(0), Constraint::Length(width)])
+        .split(area);
+    (chunks[0], Some((chunks[1], text, connected)))
+}
+
+/// `template` with each `{field}` replaced by its value; unknown fields are
+/// kept as written
+fn expand_template(template: &str, value: impl Fn(&str) -> Option<String>) -> String {
+    let mut text = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        text.push_str(&rest[..open]);
+        let field = &rest[open + 1..];
+        match field.find('}').and_then(|close| Some((close, value(&field[..close])?))) {
+            Some((close, value)) => {
+                text.push_str(&value);
+                rest = &field[close + 1..];
+            }
+            None => {
+                text.push('{');
+                rest = field;
+            }
+        }
+    }
+    text.push_str(rest);
+    text
+}
+
+/// The value of a status template field for an editor tab
+fn template_field(tab: &Tab, field: &str, overwrite_mode: bool) -> Option<String> {
+    let Tab::Editor { path, name, modified, cursor, buffer, .. } = tab else {
+        return None;
+    };
+    Some(match field {
+        "mode" => (if overwrite_mode { "OVR" } else { "INS" }).to_string(),
+        "file" => path
+            .as_ref()
+            .map_or_else(|| name.clone(), |path| path.display().to_string()),
+        "name" => name.clone(),
+        "modified" => (if *modified { "*" } else { "" }).to_string(),
+        "line" => (cursor.position.line + 1).to_string(),
+        "col" => cursor.position.column.to_string(),
+        "lines" => buffer.len_lines().to_string(),
+        "words" => buffer.word_count().to_string(),
+        "lang" => tab.language().name.clone(),
+        "indent" => tab.indent().map(|indent| indent.label()).unwrap_or_default(),
+        "selection" => tab.selection_summary().unwrap_or_default(),
+        _ => return None,
+    })
+}
+
+/// The branch checked out in the repository containing `root`, or the short
+/// commit when the head is detached
+fn git_branch(root: &Path) -> Option<String> {
+    let dot_git = root.ancestors().map(|dir| dir.join(".git")).find(|git| git.exists())?;
+    // In a worktree or submodule, .git is a file naming the real directory
+    let git_dir = match std::fs::read_to_string(&dot_git) {
+        Ok(link) => {
+            let target = link.strip_prefix("gitdir:")?.trim();
+            dot_git.parent()?.join(target)
+        }
+        Err(_) => dot_git,
+    };
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+    match head.strip_prefix("ref: ") {
+        Some(reference) => Some(reference.trim_start_matches("refs/heads/").to_string()),
+        None => Some(head.chars().take(7).collect()),
+    }
+}
+
+/// Mode, cursor position and the optional selection and undo details
+fn cursor_text(tab: &Tab, overwrite_mode: bool) -> String {
+    let Tab::Editor { cursor, .. } = tab else {
+        return String::new();
+    };
+    let mut cursor_pos = format!(
+        " {} | L{}:C{} ",
+        if overwrite_mode { "OVR" } else { "INS" },
+        cursor.position.line + 1,
+        cursor.position.column
+    );
+    if let Some(selection) = tab.selection_summary() {
+        cursor_pos = format!(" {} |{}", selection, cursor_pos);
+    }
+    if crate::config::get().undo.show_depth {
+        let (undo, redo) = tab.undo_depth();
+        cursor_pos = format!(" undo {}/{} |{}", undo, redo, cursor_pos);
+    }
+    cursor_pos
+}
+
+/// Word and character counts with the reading time, for markdown and text
+fn prose_text(tab: &Tab) -> String {
+    let Tab::Editor { buffer, .. } = tab else {
+        return String::new();
+    };
+    if !tab.is_prose() {
+        return String::new();
+    }
+    let words = buffer.word_count();
+    let minutes = words.div_ceil(WORDS_PER_MINUTE).max(1);
+    format!(
+        " {} words · {} chars · {} min read ",
+        words,
+        buffer.len_chars(),
+        minutes
+    )
+}
+
+fn indent_text(tab: &Tab) -> String {
+    tab.indent()
+        .map(|indent| format!(" {} ", indent.label()))
+        .unwrap_or_default()
+}
+
+/// Where the indentation segment is drawn, for mouse hit-testing
+pub fn indent_segment_area(
+    area: Rect,
+    tab_manager: &TabManager,
+    job_summary: Option<String>,
+    overwrite_mode: bool,
+) -> Option<Rect> {
+    if !crate::config::get().ui.status_template.is_empty() {
+        return None;
+    }
+    let tab = tab_manager.active_tab()?;
+    let indent_width = indent_text(tab).width() as u16;
+    if indent_width == 0 {
+        return None;
+    }
+    let (area, _) = split_jobs_segment(area, job_summary);
+    let right = area.x + area.width;
+    let x = right.checked_sub(cursor_text(tab, overwrite_mode).width() as u16 + indent_width)?;
+    Some(Rect {
+        x,
+        y: area.y,
+        width: indent_width,
+        height: 1,
+    })
+}
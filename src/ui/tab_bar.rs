@@ -8,236 +8,255 @@ use ratatui::{
 
 use crate::tab::TabManager;
 
-pub struct TabBar {}
+/// Fixed width of every tab: " name       ● × "
+const TAB_WIDTH: usize = 16;
+const NAME_WIDTH: usize = 10;
+/// Column of the `×` close target inside a tab
+const CLOSE_OFFSET: u16 = 14;
+/// Width of the " « " and " » " overflow indicators
+const INDICATOR_WIDTH: usize = 3;
+const HINT_TEXT: &str = "  Ctrl+N";
+
+/// Where each visible tab sits on the tab bar row
+pub struct TabStrip {
+    pub start_index: usize,
+    pub end_index: usize,
+    pub has_more_left: bool,
+    pub has_more_right: bool,
+}
 
-impl TabBar {
-    pub fn new() -> Self {
-        Self {}
+impl TabStrip {
+    /// X position of a visible tab
+    pub fn tab_x(&self, index: usize) -> Option<u16> {
+        if index < self.start_index || index >= self.end_index {
+            return None;
+        }
+        let left = if self.has_more_left { INDICATOR_WIDTH } else { 0 };
+        Some((left + (index - self.start_index) * TAB_WIDTH) as u16)
     }
 
-    pub fn get_tab_x_position(
-        &self,
-        tab_manager: &TabManager,
-        target_tab_index: usize,
-        available_width: usize,
-    ) -> u16 {
-        let hint_text = "  Ctrl+N";
-        let hint_width = hint_text.len();
-        let tabs_width = available_width.saturating_sub(hint_width);
+    /// Tab under an X position
+    pub fn tab_at(&self, x: u16) -> Option<usize> {
+        (self.start_index..self.end_index).find(|&index| {
+            self.tab_x(index)
+                .is_some_and(|tab_x| x >= tab_x && x < tab_x + TAB_WIDTH as u16)
+        })
+    }
 
-        let tabs = tab_manager.tabs();
-        let tab_count = tabs.len();
+    /// Whether an X position is on a tab's `×` close target
+    pub fn is_close_target(&self, index: usize, x: u16) -> bool {
+        self.tab_x(index)
+            .is_some_and(|tab_x| x.abs_diff(tab_x + CLOSE_OFFSET) <= 1)
+    }
 
-        if tab_count == 0 {
-            return 0;
+    /// X range of the Ctrl+N hint, which follows the last visible tab
+    pub fn hint_range(&self) -> (u16, u16) {
+        let mut width = (self.end_index - self.start_index) * TAB_WIDTH;
+        if self.has_more_left {
+            width += INDICATOR_WIDTH;
         }
+        if self.has_more_right {
+            width += INDICATOR_WIDTH;
+        }
+        (width as u16, (width + HINT_TEXT.len()) as u16)
+    }
+}
 
-        // Fixed width per tab
-        const TAB_WIDTH: usize = 14;
-        let max_tabs_that_fit = tabs_width / TAB_WIDTH;
-
-        if tab_count <= max_tabs_that_fit {
-            // All tabs are visible with fixed width
-            // Simple calculation: tab_index * TAB_WIDTH
-            (target_tab_index * TAB_WIDTH) as u16
-        } else {
-            // Too many tabs, showing subset with scrolling
-            let active_index = tab_manager.active_index();
-            let half_width = max_tabs_that_fit / 2;
+/// Tab bar with a scrollable strip. The strip follows the active tab when it
+/// changes and can otherwise be scrolled freely.
+pub struct TabBar {
+    scroll_offset: usize,
+    followed_active: Option<usize>,
+    pub hovered_tab: Option<usize>, // Tab under the mouse
+}
 
-            let start_index = if active_index >= half_width {
-                (active_index - half_width).min(tab_count.saturating_sub(max_tabs_that_fit))
-            } else {
-                0
-            };
-            let end_index = (start_index + max_tabs_that_fit).min(tab_count);
+impl TabBar {
+    pub fn new() -> Self {
+        Self {
+            scroll_offset: 0,
+            followed_active: None,
+            hovered_tab: None,
+        }
+    }
 
-            // Check if target tab is visible
-            if target_tab_index < start_index || target_tab_index >= end_index {
-                return 0; // Tab is not visible
-            }
+    /// Scroll the strip by a number of tabs (negative scrolls left)
+    pub fn scroll_by(&mut self, delta: isize) {
+        self.scroll_offset = self.scroll_offset.saturating_add_signed(delta);
+    }
 
-            // Calculate position
-            let mut x_pos = 0u16;
+    /// How many tabs the bar shows at once, or None when they all fit
+    fn overflow_capacity(tab_count: usize, available_width: usize) -> Option<usize> {
+        let tabs_width = available_width.saturating_sub(HINT_TEXT.len());
+        if tab_count <= tabs_width / TAB_WIDTH {
+            return None;
+        }
+        // Leave room for both overflow indicators
+        Some((tabs_width.saturating_sub(2 * INDICATOR_WIDTH) / TAB_WIDTH).max(1))
+    }
 
-            // Account for left truncation indicator
-            if start_index > 0 {
-                x_pos = 3; // Width of " « "
+    /// Keep the scroll position within the tabs, moving it only as far as
+    /// needed to show a newly activated tab
+    fn follow_active(&mut self, tab_manager: &TabManager, available_width: usize) {
+        let tab_count = tab_manager.tabs().len();
+        let Some(max_tabs_that_fit) = Self::overflow_capacity(tab_count, available_width) else {
+            self.scroll_offset = 0;
+            return;
+        };
+        let active_index = tab_manager.active_index();
+        if self.followed_active != Some(active_index) {
+            self.followed_active = Some(active_index);
+            if active_index < self.scroll_offset {
+                self.scroll_offset = active_index;
+            } else if active_index >= self.scroll_offset + max_tabs_that_fit {
+                self.scroll_offset = active_index + 1 - max_tabs_that_fit;
             }
+        }
+        self.scroll_offset = self
+            .scroll_offset
+            .min(tab_count.saturating_sub(max_tabs_that_fit));
+    }
 
-            // Add offset for the target tab
-            let tab_offset = target_tab_index - start_index;
-            x_pos += (tab_offset * TAB_WIDTH) as u16;
-
-            x_pos
+    /// Which tabs fit in the bar at the current scroll position
+    pub fn strip(&self, tab_manager: &TabManager, available_width: usize) -> TabStrip {
+        let tab_count = tab_manager.tabs().len();
+        let Some(max_tabs_that_fit) = Self::overflow_capacity(tab_count, available_width) else {
+            return TabStrip {
+                start_index: 0,
+                end_index: tab_count,
+                has_more_left: false,
+                has_more_right: false,
+            };
+        };
+
+        let start_index = self
+            .scroll_offset
+            .min(tab_count.saturating_sub(max_tabs_that_fit));
+        let end_index = (start_index + max_tabs_that_fit).min(tab_count);
+
+        TabStrip {
+            start_index,
+            end_index,
+            has_more_left: start_index > 0,
+            has_more_right: end_index < tab_count,
         }
     }
 
-    pub fn draw(
+    pub fn get_tab_x_position(
         &self,
+        tab_manager: &TabManager,
+        target_tab_index: usize,
+        available_width: usize,
+    ) -> u16 {
+        self.strip(tab_manager, available_width)
+            .tab_x(target_tab_index)
+            .unwrap_or(0)
+    }
+
+    pub fn draw(
+        &mut self,
         frame: &mut Frame,
         area: Rect,
         tab_manager: &TabManager,
         dragging_tab: Option<usize>,
     ) {
-        let available_width = area.width as usize;
-        let hint_text = "  Ctrl+N";
-        let hint_width = hint_text.len();
-        let tabs_width = available_width.saturating_sub(hint_width);
+        self.follow_active(tab_manager, area.width as usize);
+        let strip = self.strip(tab_manager, area.width as usize);
+        let indicator_style = Style::default().fg(Color::Rgb(120, 120, 120));
 
         // Render tabs and hint together in a single line
         let mut spans = Vec::new();
 
-        // Calculate how to display tabs with truncation
-        let tab_spans = self.calculate_tab_spans(tab_manager, tabs_width, dragging_tab);
-        spans.extend(tab_spans);
-
-        // Add the Ctrl+N hint directly after the tabs
-        spans.push(Span::styled(
-            hint_text,
-            Style::default().fg(Color::Rgb(120, 120, 120)),
-        ));
-
-        // Create a single line with all spans
-        let line = Line::from(spans);
-        let paragraph = Paragraph::new(vec![line]);
-
-        frame.render_widget(paragraph, area);
-    }
-
-    fn calculate_tab_spans(
-        &self,
-        tab_manager: &TabManager,
-        available_width: usize,
-        dragging_tab: Option<usize>,
-    ) -> Vec<Span<'_>> {
-        let mut spans = Vec::new();
-        let tabs = tab_manager.tabs();
-        let tab_count = tabs.len();
-
-        if tab_count == 0 {
-            return spans;
+        // Show truncation indicator if there are tabs before
+        if strip.has_more_left {
+            spans.push(Span::styled(" « ", indicator_style));
         }
 
-        // Fixed width per tab
-        const TAB_WIDTH: usize = 14;
-        const TAB_CONTENT_WIDTH: usize = TAB_WIDTH - 2; // Minus padding
-        let max_tabs_that_fit = available_width / TAB_WIDTH;
-
-        if tab_count <= max_tabs_that_fit {
-            // All tabs can fit with fixed width
-            for (i, tab) in tabs.iter().enumerate() {
-                let full_name = tab.display_name();
-                let truncated_name = self.truncate_name(&full_name, TAB_CONTENT_WIDTH);
-
-                // Pad to fixed width
-                let tab_text = format!(" {:<width$} ", truncated_name, width = TAB_CONTENT_WIDTH);
-
-                let style = if Some(i) == dragging_tab {
-                    // Dragging tab: highlighted differently
-                    Style::default()
-                        .fg(Color::White)
-                        .bg(Color::Rgb(100, 100, 100))
-                        .add_modifier(Modifier::BOLD)
-                } else if i == tab_manager.active_index() {
-                    // Active tab: black text on cyan background
-                    Style::default()
-                        .fg(Color::Black)
-                        .bg(Color::Cyan)
-                        .add_modifier(Modifier::BOLD)
-                } else {
-                    // Inactive tab: dimmed text
-                    Style::default().fg(Color::Rgb(180, 180, 180))
-                };
-
-                spans.push(Span::styled(tab_text, style));
-            }
-        } else {
-            // Too many tabs to show all, show as many as possible centered around active tab
-            let active_index = tab_manager.active_index();
-            let half_width = max_tabs_that_fit / 2;
-
-            let start_index = if active_index >= half_width {
-                (active_index - half_width).min(tab_count.saturating_sub(max_tabs_that_fit))
+        let tabs = &tab_manager.tabs()[strip.start_index..strip.end_index];
+        for (i, tab) in tabs.iter().enumerate() {
+            let i = strip.start_index + i;
+            let truncated_name = self.truncate_name(tab.name(), NAME_WIDTH);
+
+            let style = if Some(i) == dragging_tab {
+                // Dragging tab: highlighted differently
+                Style::default()
+                    .fg(Color::White)
+                    .bg(Color::Rgb(100, 100, 100))
+                    .add_modifier(Modifier::BOLD)
+            } else if i == tab_manager.active_index() {
+                // Active tab: black text on cyan background
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD)
+            } else if Some(i) == self.hovered_tab {
+                // Hovered inactive tab: brighter, on a lighter background
+                super::hover_style(Style::default().fg(Color::White))
             } else {
-                0
+                // Inactive tab: dimmed text
+                Style::default().fg(Color::Rgb(180, 180, 180))
             };
-            let end_index = (start_index + max_tabs_that_fit).min(tab_count);
-
-            // Show truncation indicator if there are tabs before
-            if start_index > 0 {
-                spans.push(Span::styled(
-                    " « ",
-                    Style::default().fg(Color::Rgb(120, 120, 120)),
-                ));
-            }
-
-            for (i, tab) in tabs
-                .iter()
-                .enumerate()
-                .skip(start_index)
-                .take(end_index - start_index)
-            {
-                let full_name = tab.display_name();
-                let truncated_name = self.truncate_name(&full_name, TAB_CONTENT_WIDTH);
-
-                // Pad to fixed width
-                let tab_text = format!(" {:<width$} ", truncated_name, width = TAB_CONTENT_WIDTH);
-
-                let style = if Some(i) == dragging_tab {
-                    // Dragging tab: highlighted differently
-                    Style::default()
-                        .fg(Color::White)
-                        .bg(Color::Rgb(100, 100, 100))
-                        .add_modifier(Modifier::BOLD)
-                } else if i == tab_manager.active_index() {
-                    // Active tab: black text on cyan background
-                    Style::default()
-                        .fg(Color::Black)
-                        .bg(Color::Cyan)
-                        .add_modifier(Modifier::BOLD)
-                } else {
-                    // Inactive tab: dimmed text
-                    Style::default().fg(Color::Rgb(180, 180, 180))
-                };
-
-                spans.push(Span::styled(tab_text, style));
-            }
+            // Preview tabs are replaced by the next file opened from the tree
+            let style = if tab.is_preview_tab() {
+                style.add_modifier(Modifier::ITALIC)
+            } else {
+                style
+            };
+            let close_style = if i == tab_manager.active_index() {
+                style
+            } else {
+                style.fg(Color::Rgb(120, 120, 120))
+            };
+            let dot = if tab.is_modified() { "●" } else { " " };
+
+            // Pad the name to its fixed width
+            spans.push(Span::styled(
+                format!(" {:<width$} {} ", truncated_name, dot, width = NAME_WIDTH),
+                style,
+            ));
+            spans.push(Span::styled("× ", close_style));
+        }
 
-            // Show truncation indicator if there are tabs after
-            if end_index < tab_count {
-                spans.push(Span::styled(
-                    " » ",
-                    Style::default().fg(Color::Rgb(120, 120, 120)),
-                ));
-            }
+        // Show truncation indicator if there are tabs after
+        if strip.has_more_right {
+            spans.push(Span::styled(" » ", indicator_style));
         }
 
-        spans
+        // Add the Ctrl+N hint directly after the tabs
+        spans.push(Span::styled(HINT_TEXT, indicator_style));
+
+        frame.render_widget(Paragraph::new(Line::from(spans)), area);
     }
 
     fn truncate_name(&self, name: &str, max_width: usize) -> String {
-        if name.len() <= max_width {
+        let chars: Vec<char> = name.chars().collect();
+        if chars.len() <= max_width {
             name.to_string()
         } else if max_width <= 3 {
             // Too small to show anything meaningful
             "…".to_string()
         } else {
             // Try to keep the file extension visible
-            if let Some(dot_pos) = name.rfind('.') {
-                let extension = &name[dot_pos..];
+            if let Some(dot_pos) = chars.iter().rposition(|&c| c == '.') {
+                let extension = &chars[dot_pos..];
                 if extension.len() < max_width.saturating_sub(1) {
                     // Can fit extension + some of the name
                     let available_for_name =
                         max_width.saturating_sub(extension.len()).saturating_sub(1);
                     if available_for_name > 0 {
-                        return format!("{}…{}", &name[..available_for_name], extension);
+                        return format!(
+                            "{}…{}",
+                            chars[..available_for_name].iter().collect::<String>(),
+                            extension.iter().collect::<String>()
+                        );
                     }
                 }
             }
 
             // Fallback: just truncate from the end
-            format!("{}…", &name[..max_width.saturating_sub(1)])
+            format!(
+                "{}…",
+                chars[..max_width.saturating_sub(1)].iter().collect::<String>()
+            )
         }
     }
 }
@@ -0,0 +1,520 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    style::{Color, Style},
+    text::{Line, Span},
+};
+
+use crate::keyboard;
+
+/// Single-line editable text field with cursor, selection and clipboard support.
+///
+/// Positions are measured in characters, not bytes, so multi-byte input is safe.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextInput {
+    text: String,
+    cursor: usize,
+    selection_anchor: Option<usize>,
+    history: Vec<String>,
+    history_index: Option<usize>,
+    history_draft: String,
+}
+
+const MAX_HISTORY: usize = 50;
+
+impl TextInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.text.chars().count()
+    }
+
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.cursor = self.len();
+        self.selection_anchor = None;
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+        self.selection_anchor = None;
+        self.history_index = None;
+    }
+
+    pub fn set_cursor(&mut self, position: usize) {
+        self.cursor = position.min(self.len());
+        self.selection_anchor = None;
+    }
+
+    /// Move the cursor while keeping the selection anchor (mouse drag)
+    pub fn extend_selection_to(&mut self, position: usize) {
+        if self.selection_anchor.is_none() {
+            self.selection_anchor = Some(self.cursor);
+        }
+        self.cursor = position.min(self.len());
+    }
+
+    /// Select a character range, leaving the cursor at `end`
+    pub fn select_range(&mut self, start: usize, end: usize) {
+        self.selection_anchor = Some(start.min(self.len()));
+        self.cursor = end.min(self.len());
+    }
+
+    pub fn select_word_at(&mut self, position: usize) {
+        let chars: Vec<char> = self.text.chars().collect();
+        let pos = position.min(chars.len());
+        let mut start = pos;
+        while start > 0 && is_word_char(chars[start - 1]) {
+            start -= 1;
+        }
+        let mut end = pos;
+        while end < chars.len() && is_word_char(chars[end]) {
+            end += 1;
+        }
+        self.select_range(start, end);
+    }
+
+    /// Returns the selected range as (start, end) character positions
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        self.selection_anchor.and_then(|anchor| {
+            if anchor == self.cursor {
+                None
+            } else {
+                Some((anchor.min(self.cursor), anchor.max(self.cursor)))
+            }
+        })
+    }
+
+    pub fn selected_text(&self) -> Option<String> {
+        self.selection()
+            .map(|(start, end)| self.text.chars().skip(start).take(end - start).collect())
+    }
+
+    pub fn select_all(&mut self) {
+        self.selection_anchor = Some(0);
+        self.cursor = self.len();
+    }
+
+    fn byte_index(&self, char_pos: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(char_pos)
+            .map(|(i, _)| i)
+            .unwrap_or(self.text.len())
+    }
+
+    fn delete_selection(&mut self) -> bool {
+        if let Some((start, end)) = self.selection() {
+            let (start_byte, end_byte) = (self.byte_index(start), self.byte_index(end));
+            self.text.replace_range(start_byte..end_byte, "");
+            self.cursor = start;
+            self.selection_anchor = None;
+            true
+        } else {
+            self.selection_anchor = None;
+            false
+        }
+    }
+
+    pub fn insert_char(&mut self, ch: char) {
+        self.delete_selection();
+        let byte = self.byte_index(self.cursor);
+        self.text.insert(byte, ch);
+        self.cursor += 1;
+    }
+
+    pub fn insert_str(&mut self, text: &str) {
+        self.delete_selection();
+        // Single-line field: collapse line breaks into spaces
+        let text: String = text
+            .trim_end_matches(['\n', '\r'])
+            .chars()
+            .map(|c| if c == '\n' || c == '\r' { ' ' } else { c })
+            .collect();
+        let byte = self.byte_index(self.cursor);
+        self.text.insert_str(byte, &text);
+        self.cursor += text.chars().count();
+    }
+
+    pub fn backspace(&mut self) {
+        if !self.delete_selection() && self.cursor > 0 {
+            self.cursor -= 1;
+            let byte = self.byte_index(self.cursor);
+            self.text.remove(byte);
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if !self.delete_selection() && self.cursor < self.len() {
+            let byte = self.byte_index(self.cursor);
+            self.text.remove(byte);
+        }
+    }
+
+    pub fn delete_word_backward(&mut self) {
+        if self.delete_selection() {
+            return;
+        }
+        let start = self.word_start_before(self.cursor);
+        let (start_byte, end_byte) = (self.byte_index(start), self.byte_index(self.cursor));
+        self.text.replace_range(start_byte..end_byte, "");
+        self.cursor = start;
+    }
+
+    pub fn delete_word_forward(&mut self) {
+        if self.delete_selection() {
+            return;
+        }
+        let end = self.word_end_after(self.cursor);
+        let (start_byte, end_byte) = (self.byte_index(self.cursor), self.byte_index(end));
+        self.text.replace_range(start_byte..end_byte, "");
+    }
+
+    fn move_to(&mut self, position: usize, extend_selection: bool) {
+        if extend_selection {
+            if self.selection_anchor.is_none() {
+                self.selection_anchor = Some(self.cursor);
+            }
+        } else {
+            self.selection_anchor = None;
+        }
+        self.cursor = position.min(self.len());
+    }
+
+    pub fn move_left(&mut self, extend_selection: bool) {
+        // Collapse an existing selection to its start when not extending
+        if !extend_selection {
+            if let Some((start, _)) = self.selection() {
+                self.move_to(start, false);
+                return;
+            }
+        }
+        self.move_to(self.cursor.saturating_sub(1), extend_selection);
+    }
+
+    pub fn move_right(&mut self, extend_selection: bool) {
+        if !extend_selection {
+            if let Some((_, end)) = self.selection() {
+                self.move_to(end, false);
+                return;
+            }
+        }
+        self.move_to(self.cursor + 1, extend_selection);
+    }
+
+    pub fn move_word_left(&mut self, extend_selection: bool) {
+        let target = self.word_start_before(self.cursor);
+        self.move_to(target, extend_selection);
+    }
+
+    pub fn move_word_right(&mut self, extend_selection: bool) {
+        let target = self.word_end_after(self.cursor);
+        self.move_to(target, extend_selection);
+    }
+
+    pub fn move_home(&mut self, extend_selection: bool) {
+        self.move_to(0, extend_selection);
+    }
+
+    pub fn move_end(&mut self, extend_selection: bool) {
+        self.move_to(self.len(), extend_selection);
+    }
+
+    fn word_start_before(&self, position: usize) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut pos = position.min(chars.len());
+        // Skip separators, then the word itself
+        while pos > 0 && !is_word_char(chars[pos - 1]) {
+            pos -= 1;
+        }
+        while pos > 0 && is_word_char(chars[pos - 1]) {
+            pos -= 1;
+        }
+        pos
+    }
+
+    fn word_end_after(&self, position: usize) -> usize {
+        let chars: Vec<char> = self.text.chars().collect();
+        let mut pos = position.min(chars.len());
+        while pos < chars.len() && !is_word_char(chars[pos]) {
+            pos += 1;
+        }
+        while pos < chars.len() && is_word_char(chars[pos]) {
+            pos += 1;
+        }
+        pos
+    }
+
+    pub fn copy(&self) {
+        if let Some(text) = self.selected_text() {
+            keyboard::set_clipboard_text(&text);
+        }
+    }
+
+    pub fn cut(&mut self) {
+        if let Some(text) = self.selected_text() {
+            keyboard::set_clipboard_text(&text);
+            self.delete_selection();
+        }
+    }
+
+    pub fn paste(&mut self) {
+        if let Some(text) = keyboard::get_clipboard_text() {
+            self.insert_str(&text);
+        }
+    }
+
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    pub fn set_history(&mut self, history: Vec<String>) {
+        self.history = history;
+        self.history_index = None;
+    }
+
+    /// Record the current text as the most recent history entry
+    pub fn commit_to_history(&mut self) {
+        self.history_index = None;
+        if self.text.is_empty() {
+            return;
+        }
+        self.history.retain(|entry| entry != &self.text);
+        self.history.push(self.text.clone());
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+    }
+
+    pub fn history_prev(&mut self) -> bool {
+        let index = match self.history_index {
+            Some(0) => return false,
+            Some(index) => index - 1,
+            None if self.history.is_empty() => return false,
+            None => {
+                self.history_draft = self.text.clone();
+                self.history.len() - 1
+            }
+        };
+        self.history_index = Some(index);
+        let entry = self.history[index].clone();
+        self.set_text(&entry);
+        true
+    }
+
+    pub fn history_next(&mut self) -> bool {
+        match self.history_index {
+            Some(index) if index + 1 < self.history.len() => {
+                self.history_index = Some(index + 1);
+                let entry = self.history[index + 1].clone();
+                self.set_text(&entry);
+                true
+            }
+            Some(_) => {
+                // Walked past the newest entry: restore what was being typed
+                self.history_index = None;
+                let draft = std::mem::take(&mut self.history_draft);
+                self.set_text(&draft);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// First visible character when the field is `width` columns wide,
+    /// keeping the cursor (and the bar drawn after it) on screen
+    fn scroll_offset(&self, width: usize) -> usize {
+        if width == 0 || self.cursor < width {
+            0
+        } else {
+            self.cursor + 1 - width
+        }
+    }
+
+    /// Map a column inside a field of `width` columns to a character position
+    pub fn position_at_column(&self, column: usize, width: usize) -> usize {
+        (self.scroll_offset(width) + column).min(self.len())
+    }
+
+    /// Build a styled line for a field `width` columns wide, highlighting the
+    /// selection and drawing a bar cursor when focused. Long text scrolls
+    /// horizontally so the cursor stays visible; a width of 0 disables that.
+    pub fn to_line(&self, style: Style, focused: bool, width: usize) -> Line<'static> {
+        let selection_style = Style::default().bg(Color::Blue).fg(Color::White);
+        let selection = if focused { self.selection() } else { None };
+        let offset = self.scroll_offset(width);
+        let mut spans = Vec::new();
+        let mut current = String::new();
+        let mut current_selected = false;
+
+        for (idx, ch) in self
+            .text
+            .chars()
+            .chain(std::iter::once('\0'))
+            .enumerate()
+            .skip(offset)
+        {
+            let selected = selection.is_some_and(|(start, end)| idx >= start && idx < end);
+            let at_cursor = focused && idx == self.cursor;
+
+            if (selected != current_selected || at_cursor) && !current.is_empty() {
+                let span_style = if current_selected {
+                    selection_style
+                } else {
+                    style
+                };
+                spans.push(Span::styled(std::mem::take(&mut current), span_style));
+            }
+            current_selected = selected;
+
+            if at_cursor {
+                spans.push(Span::styled("│", style));
+            }
+            if ch != '\0' {
+                current.push(ch);
+            }
+        }
+
+        if !current.is_empty() {
+            let span_style = if current_selected {
+                selection_style
+            } else {
+                style
+            };
+            spans.push(Span::styled(current, span_style));
+        }
+
+        Line::from(spans)
+    }
+
+    /// Apply a key press to the field. Returns true if the key was consumed.
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let alt = key.modifiers.contains(KeyModifiers::ALT);
+        let shift = key.modifiers.contains(KeyModifiers::SHIFT);
+        let word = ctrl || alt;
+
+        match key.code {
+            KeyCode::Char('a') if ctrl => self.select_all(),
+            KeyCode::Char('c') if ctrl => self.copy(),
+            KeyCode::Char('x') if ctrl => self.cut(),
+            KeyCode::Char('v') if ctrl => self.paste(),
+            KeyCode::Char(c) if !ctrl && !alt => self.insert_char(c),
+            KeyCode::Backspace if word => self.delete_word_backward(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete if word => self.delete_word_forward(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left if word => self.move_word_left(shift),
+            KeyCode::Right if word => self.move_word_right(shift),
+            KeyCode::Left => self.move_left(shift),
+            KeyCode::Right => self.move_right(shift),
+            KeyCode::Home => self.move_home(shift),
+            KeyCode::End => self.move_end(shift),
+            KeyCode::Up if !self.history.is_empty() => {
+                self.history_prev();
+            }
+            KeyCode::Down if !self.history.is_empty() => {
+                self.history_next();
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(text: &str) -> TextInput {
+        let mut input = TextInput::new();
+        input.set_text(text);
+        input
+    }
+
+    #[test]
+    fn word_motion_skips_separators_then_the_word() {
+        let mut field = input("héllo, wörld_1 ");
+        field.move_word_left(false);
+        assert_eq!(field.cursor, 7);
+        field.move_word_left(false);
+        assert_eq!(field.cursor, 0);
+        field.move_word_right(false);
+        assert_eq!(field.cursor, 5);
+        field.move_word_right(true);
+        assert_eq!(field.selected_text().as_deref(), Some(", wörld_1"));
+    }
+
+    #[test]
+    fn deleting_counts_characters_not_bytes() {
+        let mut field = input("añb€c");
+        field.backspace();
+        assert_eq!(field.text(), "añb€");
+        field.move_left(false);
+        field.backspace();
+        assert_eq!(field.text(), "añ€");
+        field.move_home(false);
+        field.delete();
+        assert_eq!(field.text(), "ñ€");
+
+        let mut field = input("día de café");
+        field.delete_word_backward();
+        assert_eq!(field.text(), "día de ");
+        field.move_home(false);
+        field.delete_word_forward();
+        assert_eq!(field.text(), " de ");
+    }
+
+    #[test]
+    fn selections_replace_and_collapse() {
+        let mut field = input("ünïcode");
+        field.select_range(1, 3);
+        assert_eq!(field.selected_text().as_deref(), Some("nï"));
+        field.insert_char('X');
+        assert_eq!(field.text(), "üXcode");
+        assert_eq!(field.cursor, 2);
+        assert_eq!(field.selection(), None);
+
+        field.move_end(false);
+        field.move_left(true);
+        field.move_left(true);
+        assert_eq!(field.selected_text().as_deref(), Some("de"));
+        field.move_left(false);
+        assert_eq!((field.cursor, field.selection()), (4, None));
+
+        field.select_word_at(3);
+        assert_eq!(field.selected_text().as_deref(), Some("üXcode"));
+        field.backspace();
+        assert!(field.is_empty());
+    }
+
+    #[test]
+    fn positions_clamp_to_the_text() {
+        let mut field = input("日本語");
+        field.set_cursor(10);
+        assert_eq!(field.cursor, 3);
+        field.move_right(false);
+        assert_eq!(field.cursor, 3);
+        field.select_range(1, 99);
+        assert_eq!(field.selected_text().as_deref(), Some("本語"));
+        field.extend_selection_to(99);
+        assert_eq!(field.cursor, 3);
+        assert_eq!(field.position_at_column(7, 0), 3);
+        // A narrow field scrolls so the cursor stays in view
+        assert_eq!(field.position_at_column(0, 2), 2);
+    }
+}
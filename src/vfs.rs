@@ -0,0 +1,593 @@
+//! The filesystem tabs, the tree view and the file picker read and write
+//! through. Paths under a mounted root go to that root's `Vfs`; everything
+//! else is the local disk. A remote or archive backend is a `Vfs` mounted at
+//! the path it is shown under, and the views work on it unchanged.
+//!
+//! Creating, renaming, copying and deleting go through it too, so a
+//! read-only backend turns them down with a "read-only" error.
+
+use std::collections::BTreeMap;
+use std::io::{self, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::SystemTime;
+
+/// A folder entry as listings see it
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirEntry {
+    pub path: PathBuf,
+    pub is_dir: bool, // Following symlinks
+    pub is_symlink: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metadata {
+    pub is_dir: bool,
+    pub len: u64,
+    pub modified: Option<SystemTime>,
+}
+
+pub trait Vfs: Send + Sync {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()>;
+    fn metadata(&self, path: &Path) -> io::Result<Metadata>;
+    /// Entries of a folder, in no particular order
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>>;
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        String::from_utf8(self.read(path)?).map_err(|_| {
+            io::Error::new(ErrorKind::InvalidData, "stream did not contain valid UTF-8")
+        })
+    }
+
+    /// Whether writes always fail, so editing the file is pointless
+    fn is_read_only(&self) -> bool {
+        false
+    }
+
+    /// How many entries a folder has, counting no further than `limit`
+    fn count_entries(&self, path: &Path, limit: usize) -> io::Result<usize> {
+        Ok(self.read_dir(path)?.len().min(limit))
+    }
+
+    /// Up to the first `limit` bytes of a file, for previews
+    fn read_start(&self, path: &Path, limit: u64) -> io::Result<Vec<u8>> {
+        let mut bytes = self.read(path)?;
+        bytes.truncate(limit as usize);
+        Ok(bytes)
+    }
+
+    /// Make an empty file, which mustn't exist yet, or a folder, which may.
+    /// Missing folders above it are made on the way.
+    fn create(&self, _path: &Path, _is_dir: bool) -> io::Result<()> {
+        Err(read_only())
+    }
+
+    /// Delete a file, a link or a whole folder
+    fn remove(&self, _path: &Path) -> io::Result<()> {
+        Err(read_only())
+    }
+
+    /// Move within this filesystem, replacing a file at `to`
+    fn rename(&self, _from: &Path, _to: &Path) -> io::Result<()> {
+        Err(read_only())
+    }
+
+    /// Copy a file within this filesystem
+    fn copy(&self, _from: &Path, _to: &Path) -> io::Result<()> {
+        Err(read_only())
+    }
+}
+
+fn read_only() -> io::Error {
+    io::Error::new(ErrorKind::PermissionDenied, "read-only")
+}
+
+/// The local disk
+pub struct Local;
+
+impl Vfs for Local {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        std::fs::write(path, contents)
+    }
+
+    fn read_start(&self, path: &Path, limit: u64) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        std::fs::File::open(path)?
+            .take(limit)
+            .read_to_end(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let metadata = std::fs::metadata(path)?;
+        Ok(Metadata {
+            is_dir: metadata.is_dir(),
+            len: metadata.len(),
+            modified: metadata.modified().ok(),
+        })
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(path)? {
+            let path = entry?.path();
+            entries.push(DirEntry {
+                is_dir: path.is_dir(),
+                is_symlink: crate::walk::is_symlink(&path),
+                path,
+            });
+        }
+        Ok(entries)
+    }
+
+    fn count_entries(&self, path: &Path, limit: usize) -> io::Result<usize> {
+        Ok(std::fs::read_dir(path)?.take(limit).count())
+    }
+
+    fn create(&self, path: &Path, is_dir: bool) -> io::Result<()> {
+        if is_dir {
+            return std::fs::create_dir_all(path);
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Don't truncate an existing file
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .map(|_| ())
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        // A link to a folder is removed, not what it points to
+        if std::fs::symlink_metadata(path)?.is_dir() {
+            std::fs::remove_dir_all(path)
+        } else {
+            std::fs::remove_file(path)
+        }
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        std::fs::rename(from, to)
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()> {
+        std::fs::copy(from, to).map(|_| ())
+    }
+}
+
+/// Files kept in memory, for documents with nothing on disk behind them,
+/// such as scratch buffers when the state directory can't be written.
+/// Folders exist while a file is in them, or once created.
+#[derive(Default)]
+pub struct Memory {
+    nodes: Mutex<BTreeMap<PathBuf, Node>>,
+}
+
+enum Node {
+    File(Vec<u8>, SystemTime),
+    Folder,
+}
+
+impl Memory {
+    fn is_dir(nodes: &BTreeMap<PathBuf, Node>, path: &Path) -> bool {
+        matches!(nodes.get(path), Some(Node::Folder))
+            || nodes
+                .keys()
+                .any(|node| node != path && node.starts_with(path))
+    }
+
+    /// Whether a file stands where a folder above `path` should be
+    fn under_file(nodes: &BTreeMap<PathBuf, Node>, path: &Path) -> bool {
+        path.ancestors()
+            .skip(1)
+            .any(|parent| matches!(nodes.get(parent), Some(Node::File(..))))
+    }
+
+    /// Take `path` and everything under it out, in order
+    fn take(nodes: &mut BTreeMap<PathBuf, Node>, path: &Path) -> Vec<(PathBuf, Node)> {
+        let paths: Vec<PathBuf> = nodes
+            .keys()
+            .filter(|node| node.starts_with(path))
+            .cloned()
+            .collect();
+        paths
+            .into_iter()
+            .filter_map(|node| nodes.remove_entry(&node))
+            .collect()
+    }
+}
+
+impl Vfs for Memory {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let nodes = self.nodes.lock().unwrap();
+        match nodes.get(path) {
+            Some(Node::File(contents, _)) => Ok(contents.clone()),
+            Some(Node::Folder) => Err(io::Error::new(ErrorKind::InvalidInput, "is a directory")),
+            None => Err(ErrorKind::NotFound.into()),
+        }
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> io::Result<()> {
+        let mut nodes = self.nodes.lock().unwrap();
+        if Memory::is_dir(&nodes, path) {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "is a directory"));
+        }
+        if Memory::under_file(&nodes, path) {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "not a directory"));
+        }
+        let file = Node::File(contents.to_vec(), SystemTime::now());
+        nodes.insert(path.to_path_buf(), file);
+        Ok(())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Metadata> {
+        let nodes = self.nodes.lock().unwrap();
+        if let Some(Node::File(contents, modified)) = nodes.get(path) {
+            return Ok(Metadata {
+                is_dir: false,
+                len: contents.len() as u64,
+                modified: Some(*modified),
+            });
+        }
+        if Memory::is_dir(&nodes, path) {
+            return Ok(Metadata {
+                is_dir: true,
+                len: 0,
+                modified: None,
+            });
+        }
+        Err(ErrorKind::NotFound.into())
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<DirEntry>> {
+        let nodes = self.nodes.lock().unwrap();
+        if !Memory::is_dir(&nodes, path) {
+            return Err(ErrorKind::NotFound.into());
+        }
+        let nodes = nodes
+            .iter()
+            .map(|(node, kind)| (node.as_path(), matches!(kind, Node::Folder)));
+        Ok(children(nodes, path))
+    }
+
+    fn create(&self, path: &Path, is_dir: bool) -> io::Result<()> {
+        let mut nodes = self.nodes.lock().unwrap();
+        if is_dir && Memory::is_dir(&nodes, path) {
+            return Ok(());
+        }
+        if nodes.contains_key(path) || Memory::is_dir(&nodes, path) {
+            return Err(ErrorKind::AlreadyExists.into());
+        }
+        if Memory::under_file(&nodes, path) {
+            return Err(io::Error::new(ErrorKind::InvalidInput, "not a directory"));
+        }
+        let node = if is_dir {
+            Node::Folder
+        } else {
+            Node::File(Vec::new(), SystemTime::now())
+        };
+        nodes.insert(path.to_path_buf(), node);
+        Ok(())
+    }
+
+    fn remove(&self, path: &Path) -> io::Result<()> {
+        let mut nodes = self.nodes.lock().unwrap();
+        if Memory::take(&mut nodes, path).is_empty() {
+            return Err(ErrorKind::NotFound.into());
+        }
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut nodes = self.nodes.lock().unwrap();
+        if to.starts_with(from) && to != from {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                "can't move a folder into itself",
+            ));
+        }
+        let moved = Memory::take(&mut nodes, from);
+        if moved.is_empty() {
+            return Err(ErrorKind::NotFound.into());
+        }
+        Memory::take(&mut nodes, to);
+        for (path, node) in moved {
+            let path = match path.strip_prefix(from) {
+                Ok(relative) if relative.as_os_str().is_empty() => to.to_path_buf(),
+                Ok(relative) => to.join(relative),
+                Err(_) => path,
+            };
+            nodes.insert(path, node);
+        }
+        Ok(())
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let contents = self.read(from)?;
+        self.write(to, &contents)
+    }
+}
+
+/// The entries directly in `dir` of a filesystem that only knows the full
+/// paths of what it holds, given in sorted order and whether each is a
+/// folder. Folders between `dir` and a deeper path are listed once.
+pub fn children<'a>(
+    paths: impl IntoIterator<Item = (&'a Path, bool)>,
+    dir: &Path,
+) -> Vec<DirEntry> {
+    let mut entries: Vec<DirEntry> = Vec::new();
+    for (path, is_dir) in paths {
+        let Ok(relative) = path.strip_prefix(dir) else {
+            continue;
+        };
+        let mut components = relative.components();
+        let Some(first) = components.next() else {
+            continue;
+        };
+        let child = dir.join(first);
+        let is_dir = is_dir || components.next().is_some();
+        match entries.last_mut() {
+            Some(last) if last.path == child => last.is_dir |= is_dir,
+            _ => entries.push(DirEntry {
+                path: child,
+                is_dir,
+                is_symlink: false,
+            }),
+        }
+    }
+    entries
+}
+
+type Mounts = RwLock<Vec<(PathBuf, Arc<dyn Vfs>)>>;
+
+fn mounts() -> &'static Mounts {
+    static MOUNTS: OnceLock<Mounts> = OnceLock::new();
+    MOUNTS.get_or_init(|| RwLock::new(Vec::new()))
+}
+
+pub fn is_mounted(root: &Path) -> bool {
+    mounts()
+        .read()
+        .unwrap()
+        .iter()
+        .any(|(mounted, _)| mounted == root)
+}
+
+/// Serve the paths under `root` from `vfs`. The deepest root holding a
+/// path wins.
+pub fn mount(root: PathBuf, vfs: Arc<dyn Vfs>) {
+    let mut mounts = mounts().write().unwrap();
+    mounts.retain(|(mounted, _)| *mounted != root);
+    mounts.push((root, vfs));
+    mounts.sort_by_key(|(root, _)| std::cmp::Reverse(root.components().count()));
+}
+
+/// Forget the mounts at or below `path`, once what they were mounted on is
+/// gone from there
+fn unmount_under(path: &Path) {
+    mounts()
+        .write()
+        .unwrap()
+        .retain(|(root, _)| !root.starts_with(path));
+}
+
+/// The filesystem `path` is on
+pub fn for_path(path: &Path) -> Arc<dyn Vfs> {
+    mount_of(path).1
+}
+
+/// The filesystem `path` is on and the root it is mounted at, None for the
+/// local disk
+fn mount_of(path: &Path) -> (Option<PathBuf>, Arc<dyn Vfs>) {
+    mounts()
+        .read()
+        .unwrap()
+        .iter()
+        .find(|(root, _)| path.starts_with(root))
+        .map_or_else(
+            || (None, Arc::new(Local) as Arc<dyn Vfs>),
+            |(root, vfs)| (Some(root.clone()), vfs.clone()),
+        )
+}
+
+/// Like `mount_of`, but a mount's root goes to the filesystem it is an entry
+/// of, so an archive is copied, renamed or deleted as the file it is
+fn holder_of(path: &Path) -> (Option<PathBuf>, Arc<dyn Vfs>) {
+    match (mount_of(path), path.parent()) {
+        ((Some(root), _), Some(parent)) if root == path => mount_of(parent),
+        (found, _) => found,
+    }
+}
+
+/// The filesystem holding `path`, or a "read-only" error before anything is
+/// tried on one that can't be changed
+pub fn writable(path: &Path) -> io::Result<Arc<dyn Vfs>> {
+    let vfs = holder_of(path).1;
+    if vfs.is_read_only() {
+        return Err(read_only());
+    }
+    Ok(vfs)
+}
+
+// Shorthands for the `std::fs` functions of the same names
+
+pub fn read(path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+    let path = path.as_ref();
+    for_path(path).read(path)
+}
+
+pub fn read_to_string(path: impl AsRef<Path>) -> io::Result<String> {
+    let path = path.as_ref();
+    for_path(path).read_to_string(path)
+}
+
+pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = path.as_ref();
+    writable(path)?.write(path, contents.as_ref())
+}
+
+pub fn metadata(path: impl AsRef<Path>) -> io::Result<Metadata> {
+    let path = path.as_ref();
+    for_path(path).metadata(path)
+}
+
+pub fn read_dir(path: impl AsRef<Path>) -> io::Result<Vec<DirEntry>> {
+    let path = path.as_ref();
+    for_path(path).read_dir(path)
+}
+
+pub fn count_entries(path: impl AsRef<Path>, limit: usize) -> io::Result<usize> {
+    let path = path.as_ref();
+    for_path(path).count_entries(path, limit)
+}
+
+pub fn is_read_only(path: impl AsRef<Path>) -> bool {
+    for_path(path.as_ref()).is_read_only()
+}
+
+/// What the filesystem holding `path` has there. Unlike `metadata`, a
+/// mounted archive is the file it is on disk, not the folder it lists as.
+pub fn entry_metadata(path: impl AsRef<Path>) -> io::Result<Metadata> {
+    let path = path.as_ref();
+    holder_of(path).1.metadata(path)
+}
+
+/// Whether anything is at `path`, counting links that lead nowhere
+pub fn exists(path: impl AsRef<Path>) -> bool {
+    let path = path.as_ref();
+    entry_metadata(path).is_ok() || crate::walk::is_symlink(path)
+}
+
+pub fn create(path: impl AsRef<Path>, is_dir: bool) -> io::Result<()> {
+    let path = path.as_ref();
+    writable(path)?.create(path, is_dir)
+}
+
+pub fn remove(path: impl AsRef<Path>) -> io::Result<()> {
+    let path = path.as_ref();
+    writable(path)?.remove(path)?;
+    unmount_under(path);
+    Ok(())
+}
+
+/// Move `from` to `to`. Moving between filesystems fails with `Unsupported`,
+/// for the caller to copy and remove instead.
+pub fn rename(from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    let vfs = writable(from)?;
+    writable(to)?;
+    if holder_of(to).0 != holder_of(from).0 {
+        return Err(io::Error::new(
+            ErrorKind::Unsupported,
+            "can't rename across filesystems",
+        ));
+    }
+    vfs.rename(from, to)?;
+    unmount_under(from);
+    Ok(())
+}
+
+/// Copy a file, also from one filesystem to another
+pub fn copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> io::Result<()> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    let (root, vfs) = holder_of(from);
+    let target = writable(to)?;
+    if holder_of(to).0 == root {
+        vfs.copy(from, to)
+    } else {
+        target.write(to, &vfs.read(from)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serves_mounted_paths_from_memory() {
+        let root = PathBuf::from("/f1-vfs-test");
+        let memory = Memory::default();
+        memory.write(&root.join("a.txt"), b"one").unwrap();
+        memory
+            .write(&root.join("src/main.rs"), b"fn main() {}")
+            .unwrap();
+        mount(root.clone(), Arc::new(memory));
+
+        assert_eq!(read_to_string(root.join("a.txt")).unwrap(), "one");
+        write(root.join("b.txt"), "two").unwrap();
+        assert_eq!(metadata(root.join("b.txt")).unwrap().len, 3);
+        assert!(metadata(root.join("src")).unwrap().is_dir);
+        assert!(read(root.join("missing")).is_err());
+
+        let names: Vec<(String, bool)> = read_dir(&root)
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.path.to_string_lossy().into_owned(), entry.is_dir))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                ("/f1-vfs-test/a.txt".to_string(), false),
+                ("/f1-vfs-test/b.txt".to_string(), false),
+                ("/f1-vfs-test/src".to_string(), true),
+            ]
+        );
+
+        // Paths outside the mount are still on disk
+        assert!(read_dir(Path::new("/f1-vfs-test-elsewhere")).is_err());
+        assert!(
+            metadata(Path::new(env!("CARGO_MANIFEST_DIR")))
+                .unwrap()
+                .is_dir
+        );
+    }
+
+    #[test]
+    fn changes_files_in_memory_and_copies_out() {
+        let root = PathBuf::from("/f1-vfs-changes-test");
+        mount(root.clone(), Arc::new(Memory::default()));
+
+        create(root.join("empty"), true).unwrap();
+        assert!(read_dir(root.join("empty")).unwrap().is_empty());
+        create(root.join("src/lib.rs"), false).unwrap();
+        assert_eq!(
+            create(root.join("src/lib.rs"), false).unwrap_err().kind(),
+            ErrorKind::AlreadyExists
+        );
+        write(root.join("src/lib.rs"), "pub fn f() {}").unwrap();
+
+        rename(root.join("src"), root.join("lib")).unwrap();
+        assert!(!exists(root.join("src")));
+        assert_eq!(
+            read_to_string(root.join("lib/lib.rs")).unwrap(),
+            "pub fn f() {}"
+        );
+
+        // Across filesystems a copy is a read and a write
+        let dir = std::env::temp_dir().join(format!("f1-vfs-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        copy(root.join("lib/lib.rs"), dir.join("lib.rs")).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("lib.rs")).unwrap(),
+            "pub fn f() {}"
+        );
+        assert_eq!(
+            rename(dir.join("lib.rs"), root.join("moved.rs"))
+                .unwrap_err()
+                .kind(),
+            ErrorKind::Unsupported
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        remove(root.join("lib")).unwrap();
+        assert!(!exists(root.join("lib/lib.rs")));
+        assert!(exists(root.join("empty")));
+    }
+}
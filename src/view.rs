@@ -0,0 +1,134 @@
+//! Display options of editor tabs. The `[editor]` config section and the
+//! F1 menu's word wrap toggle set the defaults; a tab can override any of
+//! them from its menu. A file's overrides are kept in
+//! `<state dir>/views.json`, so it opens the same way next time.
+
+use crate::config;
+use crate::recovery::state_dir;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewOptions {
+    pub word_wrap: bool,
+    pub line_numbers: bool,
+    pub show_whitespace: bool,
+    pub wrap_column: usize, // 0 wraps at the edge of the view
+    pub split_long_lines: bool,
+}
+
+impl ViewOptions {
+    pub fn from_config() -> Self {
+        let editor = &config::get().editor;
+        Self {
+            word_wrap: editor.word_wrap,
+            line_numbers: editor.line_numbers,
+            show_whitespace: editor.show_whitespace,
+            wrap_column: editor.wrap_column,
+            split_long_lines: editor.split_long_lines,
+        }
+    }
+}
+
+/// Options a tab sets for itself instead of following the defaults
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewOverrides {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub word_wrap: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_numbers: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub show_whitespace: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wrap_column: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub split_long_lines: Option<bool>,
+}
+
+impl ViewOverrides {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    pub fn apply(&self, defaults: ViewOptions) -> ViewOptions {
+        ViewOptions {
+            word_wrap: self.word_wrap.unwrap_or(defaults.word_wrap),
+            line_numbers: self.line_numbers.unwrap_or(defaults.line_numbers),
+            show_whitespace: self.show_whitespace.unwrap_or(defaults.show_whitespace),
+            wrap_column: self.wrap_column.unwrap_or(defaults.wrap_column),
+            split_long_lines: self.split_long_lines.unwrap_or(defaults.split_long_lines),
+        }
+    }
+
+    /// Overrides saved for a file, none when there are none or the state
+    /// file can't be read
+    pub fn load(path: &Path) -> Self {
+        read_all()
+            .remove(&path.to_string_lossy().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Remember the overrides for a file, forgetting it when there are none
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut all = read_all();
+        let key = path.to_string_lossy().to_string();
+        if self.is_empty() {
+            if all.remove(&key).is_none() {
+                return Ok(());
+            }
+        } else {
+            all.insert(key, *self);
+        }
+
+        let file = views_file();
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let content = serde_json::to_string_pretty(&all).map_err(io::Error::other)?;
+        fs::write(file, content)
+    }
+}
+
+fn views_file() -> PathBuf {
+    state_dir().join("views.json")
+}
+
+fn read_all() -> BTreeMap<String, ViewOverrides> {
+    fs::read_to_string(views_file())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_win_over_defaults() {
+        let defaults = ViewOptions {
+            word_wrap: false,
+            line_numbers: true,
+            show_whitespace: false,
+            wrap_column: 0,
+            split_long_lines: false,
+        };
+        let overrides = ViewOverrides {
+            word_wrap: Some(true),
+            wrap_column: Some(80),
+            ..ViewOverrides::default()
+        };
+        let view = overrides.apply(defaults);
+        assert!(view.word_wrap && view.line_numbers && !view.show_whitespace);
+        assert_eq!(view.wrap_column, 80);
+        assert_eq!(ViewOverrides::default().apply(defaults), defaults);
+        assert_eq!(
+            serde_json::to_string(&overrides).unwrap(),
+            r#"{"word_wrap":true,"wrap_column":80}"#
+        );
+    }
+}
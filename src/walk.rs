@@ -0,0 +1,135 @@
+//! Helpers for walking folders that may contain symlinks. A symlinked
+//! folder can point back at one of its parents or somewhere far outside the
+//! workspace, so recursive searches go through a `WalkGuard` before they
+//! descend. The guard also stops at `MAX_DEPTH` folders below the root.
+
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Deepest folder below the root a walk enters, however real the nesting
+pub const MAX_DEPTH: usize = 64;
+
+/// Whether a path is itself a symlink, without following it
+pub fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink())
+}
+
+/// Identity of a folder that survives different paths leading to it
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum DirId {
+    #[cfg(unix)]
+    Inode(u64, u64),
+    #[cfg_attr(unix, allow(dead_code))]
+    Path(PathBuf),
+}
+
+fn dir_id(path: &Path) -> Option<DirId> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let metadata = fs::metadata(path).ok()?;
+        Some(DirId::Inode(metadata.dev(), metadata.ino()))
+    }
+    #[cfg(not(unix))]
+    {
+        fs::canonicalize(path).ok().map(DirId::Path)
+    }
+}
+
+/// Tracks the folders a recursive walk has entered. Each folder is entered
+/// once, however many links lead to it, symlinks that leave the workspace
+/// are not followed, and nothing deeper than `max_depth` is entered.
+pub struct WalkGuard {
+    start: PathBuf,
+    root: Option<PathBuf>,
+    visited: HashSet<DirId>,
+    max_depth: usize,
+}
+
+impl WalkGuard {
+    pub fn new(root: &Path) -> Self {
+        Self::with_max_depth(root, MAX_DEPTH)
+    }
+
+    pub fn with_max_depth(root: &Path, max_depth: usize) -> Self {
+        let mut guard = Self {
+            start: root.to_path_buf(),
+            root: fs::canonicalize(root).ok(),
+            visited: HashSet::new(),
+            max_depth,
+        };
+        guard.enter(root);
+        guard
+    }
+
+    /// Whether to descend into `dir`. Marks it as visited when it is.
+    pub fn enter(&mut self, dir: &Path) -> bool {
+        let depth = dir
+            .strip_prefix(&self.start)
+            .map_or(0, |relative| relative.components().count());
+        if depth > self.max_depth {
+            return false;
+        }
+        if is_symlink(dir) {
+            let inside = match (&self.root, fs::canonicalize(dir)) {
+                (Some(root), Ok(target)) => target.starts_with(root),
+                _ => false,
+            };
+            if !inside {
+                return false;
+            }
+        }
+        match dir_id(dir) {
+            Some(id) => self.visited.insert(id),
+            None => false,
+        }
+    }
+}
+
+/// Copy a symlink as a link rather than the file or folder it points at
+pub fn copy_symlink(source: &Path, target: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(fs::read_link(source)?, target)
+    }
+    #[cfg(not(unix))]
+    {
+        fs::copy(source, target).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_loops_are_entered_once() {
+        let root = std::env::temp_dir().join(format!("f1-walk-loop-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("a")).unwrap();
+        std::os::unix::fs::symlink(&root, root.join("a/back")).unwrap();
+
+        let mut guard = WalkGuard::new(&root);
+        assert!(guard.enter(&root.join("a")));
+        assert!(!guard.enter(&root.join("a/back")));
+        assert!(!guard.enter(&root.join("a")));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn walks_stop_at_the_depth_limit() {
+        let root = std::env::temp_dir().join(format!("f1-walk-depth-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let deep = root.join("1/2/3");
+        fs::create_dir_all(&deep).unwrap();
+
+        let mut guard = WalkGuard::with_max_depth(&root, 2);
+        assert!(guard.enter(&root.join("1")));
+        assert!(guard.enter(&root.join("1/2")));
+        assert!(!guard.enter(&deep));
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,173 @@
+//! Soft wrapping of long lines. The editor draws wrapped rows with these and
+//! the cursor moves through them, so both agree on where a row breaks.
+//!
+//! Lines past `[editor] long_line_limit` characters, like minified files,
+//! are too long to lay out on every frame. They are cut off at the limit, or
+//! with `split_long_lines` drawn in rows of the view's width around the
+//! cursor; either way the file is left as it is.
+
+use crate::config;
+use crate::indent::TAB_STOP;
+use crate::rope_buffer::RopeBuffer;
+use std::ops::Range;
+
+/// Columns `ch` takes when drawn at `column` of a row
+pub fn char_width(ch: char, column: usize) -> usize {
+    if ch == '\t' {
+        TAB_STOP - column % TAB_STOP
+    } else {
+        1
+    }
+}
+
+/// Character index where each wrapped row of a line starts; the first row
+/// always starts at 0
+pub fn row_starts(line: &str, width: usize) -> Vec<usize> {
+    let mut starts = vec![0];
+    if width == 0 {
+        return starts;
+    }
+
+    let mut row_width = 0;
+    for (index, ch) in line.chars().enumerate() {
+        let ch_width = char_width(ch, row_width);
+        if row_width + ch_width > width && index > *starts.last().unwrap_or(&0) {
+            starts.push(index);
+            row_width = 0;
+        }
+        row_width += char_width(ch, row_width);
+    }
+    starts
+}
+
+/// The rows of a wrapped line
+pub fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    let chars: Vec<char> = line.chars().collect();
+    let starts = row_starts(line, width);
+    starts
+        .iter()
+        .enumerate()
+        .map(|(row, &start)| {
+            let end = starts.get(row + 1).copied().unwrap_or(chars.len());
+            chars[start..end].iter().collect()
+        })
+        .collect()
+}
+
+/// Drawn over the end of a line that is cut off
+pub const CUT_OFF_NOTE: &str = " ⋯ line too long ";
+
+/// Characters drawn of a line too long to lay out when it is cut off; 0 when
+/// lines are never too long
+pub fn long_line_limit() -> usize {
+    config::get().editor.long_line_limit
+}
+
+/// Characters of a line, without its line break
+pub fn line_len(buffer: &RopeBuffer, line: usize) -> usize {
+    let text = buffer.line(line);
+    let len = text.len_chars();
+    if len > 0 && text.char(len - 1) == '\n' {
+        len - 1
+    } else {
+        len
+    }
+}
+
+pub fn is_too_long(buffer: &RopeBuffer, line: usize) -> bool {
+    let limit = long_line_limit();
+    limit > 0 && line_len(buffer, line) > limit
+}
+
+/// Rows of `width` characters drawn of a split line `len` long: as many as
+/// fit in `rows` from the first, or when the cursor is on the line, down to
+/// the row holding its column
+pub fn split_rows(len: usize, width: usize, cursor: Option<usize>, rows: usize) -> Range<usize> {
+    let count = len.div_ceil(width.max(1)).max(1);
+    let rows = rows.clamp(1, count);
+    let cursor_row = cursor.map_or(0, |column| (column / width.max(1)).min(count - 1));
+    let first = (cursor_row + 1).saturating_sub(rows);
+    first..first + rows
+}
+
+/// Row holding a cursor column. A column on a break belongs to the row that
+/// starts there, and the end of the line to the last row.
+pub fn row_of(starts: &[usize], column: usize) -> usize {
+    starts
+        .iter()
+        .rposition(|&start| start <= column)
+        .unwrap_or(0)
+}
+
+/// Display column of `column` within the row starting at `row_start`
+pub fn x_in_row(chars: &[char], row_start: usize, column: usize) -> usize {
+    chars[row_start..column.min(chars.len())]
+        .iter()
+        .fold(0, |x, &ch| x + char_width(ch, x))
+}
+
+/// Cursor column closest to display column `x` in the row spanning
+/// `row_start..row_end`. Rows that continue on the next one stop before
+/// their last character so the cursor stays on them.
+pub fn column_at_x(
+    chars: &[char],
+    row_start: usize,
+    row_end: usize,
+    x: usize,
+    last_row: bool,
+) -> usize {
+    let limit = if last_row {
+        row_end
+    } else {
+        row_end.saturating_sub(1).max(row_start)
+    };
+    let mut width = 0;
+    let mut column = row_start;
+    while column < limit {
+        let ch_width = char_width(chars[column], width);
+        if width + ch_width > x {
+            break;
+        }
+        width += ch_width;
+        column += 1;
+    }
+    column
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_break_at_the_width() {
+        assert_eq!(row_starts("abcdefgh", 3), vec![0, 3, 6]);
+        assert_eq!(wrap_line("abcdefgh", 3), vec!["abc", "def", "gh"]);
+        assert_eq!(row_starts("", 3), vec![0]);
+        // A tab at column 2 reaches column 4, past the width
+        assert_eq!(row_starts("ab\tc", 3), vec![0, 2, 3]);
+    }
+
+    #[test]
+    fn columns_map_to_rows() {
+        let starts = row_starts("abcdefgh", 3);
+        assert_eq!(row_of(&starts, 2), 0);
+        assert_eq!(row_of(&starts, 3), 1);
+        assert_eq!(row_of(&starts, 8), 2);
+
+        let chars: Vec<char> = "abcdefgh".chars().collect();
+        assert_eq!(x_in_row(&chars, 3, 5), 2);
+        // Rows that continue stop before their last character
+        assert_eq!(column_at_x(&chars, 0, 3, 5, false), 2);
+        assert_eq!(column_at_x(&chars, 6, 8, 5, true), 8);
+    }
+
+    #[test]
+    fn split_rows_follow_the_cursor() {
+        assert_eq!(split_rows(100, 10, None, 4), 0..4);
+        assert_eq!(split_rows(100, 10, Some(35), 4), 0..4);
+        assert_eq!(split_rows(100, 10, Some(75), 4), 4..8);
+        // The end of the line is on the last row
+        assert_eq!(split_rows(100, 10, Some(100), 4), 6..10);
+        assert_eq!(split_rows(25, 10, None, 40), 0..3);
+    }
+}